@@ -0,0 +1,62 @@
+//! Fills per second through `SimpleBroker`.
+//!
+//! Run with `cargo bench -p broker_sim`.
+
+use broker_sim::SimpleBroker;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use schema::{Bar, BrokerSim, CostModel, MarketContext, Order, OrderType, Side};
+
+/// Per-share commission and fixed slippage, so the cost path is exercised
+struct FlatCost;
+
+impl CostModel for FlatCost {
+    fn calculate_commission(&self, quantity: f64, _price: f64, _market: &MarketContext) -> f64 {
+        quantity * 0.005
+    }
+
+    fn calculate_slippage(
+        &self,
+        _quantity: f64,
+        _price: f64,
+        _side: Side,
+        _market: &MarketContext,
+    ) -> f64 {
+        0.01
+    }
+}
+
+fn fill_throughput(c: &mut Criterion) {
+    const ORDERS: usize = 1_000;
+    let bar = Bar {
+        timestamp: 0,
+        symbol: "AAPL".to_string(),
+        open: 100.0,
+        high: 101.0,
+        low: 99.0,
+        close: 100.5,
+        volume: 1_000_000.0,
+    };
+    let orders: Vec<Order> = (0..ORDERS)
+        .map(|i| Order {
+            symbol: "AAPL".to_string(),
+            side: if i % 2 == 0 { Side::Buy } else { Side::Sell },
+            quantity: 10.0 + i as f64,
+            order_type: OrderType::Market,
+            limit_price: None,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("broker");
+    group.throughput(Throughput::Elements(ORDERS as u64));
+    group.bench_function("market_fills", |b| {
+        let mut broker = SimpleBroker::new(FlatCost, 42);
+        b.iter(|| black_box(broker.process_orders(orders.clone(), &bar).unwrap().len()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, fill_throughput);
+criterion_main!(benches);
@@ -0,0 +1,110 @@
+//! TWAP and VWAP slicing of parent market orders.
+
+use schema::{Fill, Side};
+
+/// How a parent order's quantity is spread over its bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlgoKind {
+    /// Equal slices per bar
+    Twap,
+    /// Slices in proportion to each bar's volume against the symbol's average
+    /// so far, so no slice depends on volume yet to trade
+    Vwap,
+}
+
+/// Work every market order over `bars` bars of its symbol instead of at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionAlgo {
+    pub kind: AlgoKind,
+    pub bars: u32,
+}
+
+/// Execution of one parent order so far
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlgoReport {
+    pub order_id: u64,
+    pub symbol: String,
+    pub side: Side,
+    /// Parent quantity
+    pub quantity: f64,
+    pub filled: f64,
+    /// Volume-weighted price of the child fills
+    pub average_price: f64,
+    pub commission: f64,
+    pub slices: usize,
+    /// Bars still scheduled
+    pub(crate) bars_left: u32,
+}
+
+impl AlgoReport {
+    pub(crate) fn new(order_id: u64, symbol: &str, side: Side, quantity: f64, bars: u32) -> Self {
+        Self {
+            order_id,
+            symbol: symbol.to_string(),
+            side,
+            quantity,
+            filled: 0.0,
+            average_price: 0.0,
+            commission: 0.0,
+            slices: 0,
+            bars_left: bars.max(1),
+        }
+    }
+
+    /// Quantity to work on this bar out of `remaining`. `volume_weight` is
+    /// the bar's volume over the symbol's average volume.
+    pub(crate) fn next_slice(&mut self, kind: AlgoKind, remaining: f64, volume_weight: f64) -> f64 {
+        let bars_left = f64::from(self.bars_left);
+        self.bars_left = self.bars_left.saturating_sub(1).max(1);
+        if bars_left <= 1.0 {
+            return remaining;
+        }
+        match kind {
+            AlgoKind::Twap => remaining / bars_left,
+            AlgoKind::Vwap => {
+                let weight = if volume_weight.is_finite() {
+                    volume_weight.max(0.0)
+                } else {
+                    1.0
+                };
+                remaining * weight / (weight + bars_left - 1.0)
+            }
+        }
+    }
+
+    pub(crate) fn record(&mut self, fill: &Fill) {
+        let filled = self.filled + fill.quantity;
+        if filled > 0.0 {
+            self.average_price =
+                (self.average_price * self.filled + fill.price * fill.quantity) / filled;
+        }
+        self.filled = filled;
+        self.commission += fill.costs.commission;
+        self.slices += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slices_spread_the_parent_over_its_bars() {
+        let mut twap = AlgoReport::new(1, "AAPL", Side::Buy, 300.0, 3);
+        let mut remaining = 300.0;
+        let mut slices = Vec::new();
+        for _ in 0..3 {
+            let slice = twap.next_slice(AlgoKind::Twap, remaining, 1.0);
+            remaining -= slice;
+            slices.push(slice);
+        }
+        assert_eq!(slices, [100.0, 100.0, 100.0]);
+
+        // A bar at twice the average volume takes twice its even share
+        let mut vwap = AlgoReport::new(2, "AAPL", Side::Buy, 400.0, 4);
+        assert_eq!(vwap.next_slice(AlgoKind::Vwap, 400.0, 2.0), 160.0);
+        assert_eq!(vwap.next_slice(AlgoKind::Vwap, 240.0, 0.0), 0.0);
+        assert_eq!(vwap.next_slice(AlgoKind::Vwap, 240.0, 1.0), 120.0);
+        assert_eq!(vwap.next_slice(AlgoKind::Vwap, 120.0, 0.5), 120.0);
+    }
+}
@@ -0,0 +1,447 @@
+//! Alpaca paper trading behind the `LiveBroker` interface.
+
+use anyhow::{Context, Result};
+use schema::{
+    Bar, BrokerResult, CostBreakdown, Fill, LiveBroker, Order, OrderAmendment, OrderRejection,
+    OrderType, Portfolio, RejectionReason, Side,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+const PAPER_ENDPOINT: &str = "https://paper-api.alpaca.markets";
+
+/// Live broker that routes orders to an Alpaca paper-trading account.
+///
+/// Orders go out as day orders and fills are read back from the account's
+/// fill activities, so a call to `process_orders_for` reports whatever
+/// filled since the previous one. Requests are blocking, so the engine
+/// driving this broker should run on a thread of its own.
+pub struct AlpacaPaperBroker {
+    endpoint: String,
+    key_id: String,
+    secret_key: String,
+    /// Alpaca's id for each of our order ids
+    order_ids: BTreeMap<u64, String>,
+    next_order_id: u64,
+    /// Last fill activity reported, to page from
+    last_activity: Option<String>,
+}
+
+impl AlpacaPaperBroker {
+    /// Paper account credentials from `APCA_API_KEY_ID` and
+    /// `APCA_API_SECRET_KEY`
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).with_context(|| format!("{} is not set", name));
+        Ok(Self::new(
+            PAPER_ENDPOINT,
+            &var("APCA_API_KEY_ID")?,
+            &var("APCA_API_SECRET_KEY")?,
+        ))
+    }
+
+    pub fn new(endpoint: &str, key_id: &str, secret_key: &str) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            key_id: key_id.to_string(),
+            secret_key: secret_key.to_string(),
+            order_ids: BTreeMap::new(),
+            next_order_id: 1,
+            last_activity: None,
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        ureq::request(method, &format!("{}{}", self.endpoint, path))
+            .set("APCA-API-KEY-ID", &self.key_id)
+            .set("APCA-API-SECRET-KEY", &self.secret_key)
+    }
+
+    fn our_id(&self, alpaca_id: &str) -> Option<u64> {
+        self.order_ids
+            .iter()
+            .find(|(_, id)| id.as_str() == alpaca_id)
+            .map(|(&id, _)| id)
+    }
+
+    fn alpaca_id(&self, order_id: u64) -> Result<&str> {
+        self.order_ids
+            .get(&order_id)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow::anyhow!("No order {} was placed with Alpaca", order_id))
+    }
+
+    /// Place one order; `Ok(Err(message))` when Alpaca refuses it
+    fn submit(&mut self, order: &mut Order) -> Result<std::result::Result<(), String>> {
+        let order_id = *order.order_id.get_or_insert(self.next_order_id);
+        self.next_order_id = self.next_order_id.max(order_id + 1);
+
+        let response = match self
+            .request("POST", "/v2/orders")
+            .send_json(order_body(order))
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(403 | 422, response)) => {
+                let body: Value = response.into_json().unwrap_or_default();
+                let message = body["message"].as_str().unwrap_or("refused").to_string();
+                return Ok(Err(message));
+            }
+            Err(e) => return Err(e).context("Alpaca order request failed"),
+        };
+        let placed: Value = response
+            .into_json()
+            .context("Alpaca returned invalid JSON")?;
+        self.order_ids
+            .insert(order_id, string_field(&placed, "id")?);
+        // Bracket exits are orders of their own at Alpaca
+        for leg in placed["legs"].as_array().into_iter().flatten() {
+            self.order_ids
+                .insert(self.next_order_id, string_field(leg, "id")?);
+            self.next_order_id += 1;
+        }
+        Ok(Ok(()))
+    }
+
+    /// Fills of our orders reported since the last call
+    fn new_fills(&mut self) -> Result<Vec<Fill>> {
+        let mut fills = Vec::new();
+        loop {
+            let mut call = self
+                .request("GET", "/v2/account/activities/FILL")
+                .query("direction", "asc");
+            if let Some(token) = &self.last_activity {
+                call = call.query("page_token", token);
+            }
+            let activities: Vec<Value> = call
+                .call()
+                .context("Alpaca activities request failed")?
+                .into_json()
+                .context("Alpaca returned invalid JSON")?;
+            let Some(last) = activities.last() else {
+                return Ok(fills);
+            };
+            self.last_activity = Some(string_field(last, "id")?);
+
+            for activity in &activities {
+                if self.our_id(&string_field(activity, "order_id")?).is_none() {
+                    continue;
+                }
+                let time = string_field(activity, "transaction_time")?;
+                fills.push(Fill {
+                    timestamp: chrono::DateTime::parse_from_rfc3339(&time)
+                        .with_context(|| format!("Invalid fill time '{}'", time))?
+                        .timestamp(),
+                    symbol: string_field(activity, "symbol")?,
+                    side: if string_field(activity, "side")? == "buy" {
+                        Side::Buy
+                    } else {
+                        Side::Sell
+                    },
+                    quantity: number_field(activity, "qty")?,
+                    price: number_field(activity, "price")?,
+                    // Alpaca charges no commission on equities
+                    costs: CostBreakdown::default(),
+                });
+            }
+        }
+    }
+}
+
+impl LiveBroker for AlpacaPaperBroker {
+    async fn process_orders_for(
+        &mut self,
+        orders: Vec<Order>,
+        bar: &Bar,
+        _portfolio: &Portfolio,
+    ) -> Result<BrokerResult> {
+        let mut rejections = Vec::new();
+        for mut order in orders {
+            if let Err(message) = self.submit(&mut order)? {
+                rejections.push(OrderRejection {
+                    timestamp: bar.timestamp,
+                    order,
+                    reason: RejectionReason::Refused { message },
+                });
+            }
+        }
+        Ok(BrokerResult {
+            fills: self.new_fills()?,
+            rejections,
+            ..BrokerResult::default()
+        })
+    }
+
+    async fn open_orders(&mut self) -> Result<Vec<Order>> {
+        let open: Vec<Value> = self
+            .request("GET", "/v2/orders")
+            .query("status", "open")
+            .call()
+            .context("Alpaca orders request failed")?
+            .into_json()
+            .context("Alpaca returned invalid JSON")?;
+
+        let mut orders = Vec::new();
+        for placed in &open {
+            let Some(order_id) = self.our_id(&string_field(placed, "id")?) else {
+                continue;
+            };
+            let optional = |name| match placed[name] {
+                Value::Null => Ok(None),
+                _ => number_field(placed, name).map(Some),
+            };
+            orders.push(Order {
+                symbol: string_field(placed, "symbol")?,
+                side: if string_field(placed, "side")? == "buy" {
+                    Side::Buy
+                } else {
+                    Side::Sell
+                },
+                quantity: number_field(placed, "qty")? - optional("filled_qty")?.unwrap_or(0.0),
+                order_type: match string_field(placed, "type")?.as_str() {
+                    "limit" => OrderType::Limit,
+                    "stop" => OrderType::Stop,
+                    "stop_limit" => OrderType::StopLimit,
+                    _ => OrderType::Market,
+                },
+                limit_price: optional("limit_price")?,
+                stop_price: optional("stop_price")?,
+                order_id: Some(order_id),
+                bracket: None,
+            });
+        }
+        orders.sort_by_key(|order| order.order_id);
+        Ok(orders)
+    }
+
+    async fn cancel(&mut self, order_id: u64) -> Result<()> {
+        let path = format!("/v2/orders/{}", self.alpaca_id(order_id)?);
+        self.request("DELETE", &path)
+            .call()
+            .with_context(|| format!("Alpaca could not cancel order {}", order_id))?;
+        Ok(())
+    }
+
+    async fn amend(&mut self, order_id: u64, amendment: &OrderAmendment) -> Result<()> {
+        let path = format!("/v2/orders/{}", self.alpaca_id(order_id)?);
+        let mut body = json!({});
+        for (name, value) in [
+            ("qty", amendment.quantity),
+            ("limit_price", amendment.limit_price),
+            ("stop_price", amendment.stop_price),
+        ] {
+            if let Some(value) = value {
+                body[name] = json!(value.to_string());
+            }
+        }
+        let replaced: Value = self
+            .request("PATCH", &path)
+            .send_json(body)
+            .with_context(|| format!("Alpaca could not amend order {}", order_id))?
+            .into_json()
+            .context("Alpaca returned invalid JSON")?;
+        // A replacement is a new order at Alpaca; keep our id for it
+        self.order_ids
+            .insert(order_id, string_field(&replaced, "id")?);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "AlpacaPaperBroker"
+    }
+}
+
+/// Request body placing `order` as a day order
+fn order_body(order: &Order) -> Value {
+    let mut body = json!({
+        "symbol": order.symbol,
+        "qty": order.quantity.to_string(),
+        "side": match order.side {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        },
+        "type": match order.order_type {
+            OrderType::Market => "market",
+            OrderType::Limit => "limit",
+            OrderType::Stop => "stop",
+            OrderType::StopLimit => "stop_limit",
+        },
+        "time_in_force": "day",
+    });
+    if let Some(price) = order.limit_price {
+        body["limit_price"] = json!(price.to_string());
+    }
+    if let Some(price) = order.stop_price {
+        body["stop_price"] = json!(price.to_string());
+    }
+    if let Some(bracket) = &order.bracket {
+        body["order_class"] = json!("bracket");
+        body["take_profit"] = json!({ "limit_price": bracket.take_profit.to_string() });
+        body["stop_loss"] = json!({ "stop_price": bracket.stop_loss.to_string() });
+    }
+    body
+}
+
+fn string_field(value: &Value, name: &str) -> Result<String> {
+    value[name]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("Alpaca response has no '{}'", name))
+}
+
+/// Alpaca sends decimals as strings
+fn number_field(value: &Value, name: &str) -> Result<f64> {
+    match &value[name] {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    }
+    .ok_or_else(|| anyhow::anyhow!("Alpaca response has no numeric '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Answer one request per connection with a canned status and JSON body,
+    /// returning the request line and body of each
+    fn serve(
+        responses: Vec<(u16, &'static str)>,
+    ) -> (String, std::thread::JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:")
+                    {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut request_body = vec![0u8; length];
+                reader.read_exact(&mut request_body).unwrap();
+                requests.push((
+                    request_line.trim().to_string(),
+                    String::from_utf8(request_body).unwrap(),
+                ));
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+        (endpoint, handle)
+    }
+
+    fn ready<T>(future: impl std::future::Future<Output = T>) -> T {
+        let mut future = std::pin::pin!(future);
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        match future.as_mut().poll(&mut context) {
+            std::task::Poll::Ready(value) => value,
+            std::task::Poll::Pending => panic!("future was not ready"),
+        }
+    }
+
+    fn order(side: Side, limit_price: Option<f64>) -> Order {
+        Order {
+            symbol: "AAPL".to_string(),
+            side,
+            quantity: 10.0,
+            order_type: if limit_price.is_some() {
+                OrderType::Limit
+            } else {
+                OrderType::Market
+            },
+            limit_price,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        }
+    }
+
+    #[test]
+    fn places_orders_and_reports_their_fills() {
+        let (endpoint, server) = serve(vec![
+            (200, r#"{"id":"a-1","status":"accepted"}"#),
+            (
+                403,
+                r#"{"code":40310000,"message":"insufficient buying power"}"#,
+            ),
+            (
+                200,
+                r#"[{"id":"f-1","order_id":"other","symbol":"MSFT","side":"buy","qty":"5","price":"400","transaction_time":"2024-01-02T14:30:00Z"},
+                    {"id":"f-2","order_id":"a-1","symbol":"AAPL","side":"buy","qty":"10","price":"185.5","transaction_time":"2024-01-02T14:30:01.5Z"}]"#,
+            ),
+            (200, "[]"),
+            (
+                200,
+                r#"[{"id":"a-1","symbol":"AAPL","side":"buy","qty":"10","filled_qty":"4","type":"limit","limit_price":"185","stop_price":null}]"#,
+            ),
+            (200, r#"{"id":"a-2"}"#),
+            (204, ""),
+        ]);
+        let mut broker = AlpacaPaperBroker::new(&endpoint, "key", "secret");
+        let bar = Bar {
+            timestamp: 1_704_205_800,
+            symbol: "AAPL".to_string(),
+            open: 185.0,
+            high: 186.0,
+            low: 184.0,
+            close: 185.5,
+            volume: 1000.0,
+        };
+
+        let orders = vec![order(Side::Buy, None), order(Side::Sell, Some(190.0))];
+        let result =
+            ready(broker.process_orders_for(orders, &bar, &Portfolio::new(10_000.0))).unwrap();
+        assert_eq!(result.fills.len(), 1);
+        assert_eq!(result.fills[0].timestamp, 1_704_205_801);
+        assert_eq!(
+            (result.fills[0].quantity, result.fills[0].price),
+            (10.0, 185.5)
+        );
+        assert_eq!(result.rejections[0].order.order_id, Some(2));
+        assert_eq!(
+            result.rejections[0].reason,
+            RejectionReason::Refused {
+                message: "insufficient buying power".to_string()
+            }
+        );
+
+        let open = ready(broker.open_orders()).unwrap();
+        assert_eq!((open[0].order_id, open[0].quantity), (Some(1), 6.0));
+        assert_eq!(open[0].limit_price, Some(185.0));
+        let amendment = OrderAmendment {
+            limit_price: Some(184.5),
+            ..OrderAmendment::default()
+        };
+        ready(broker.amend(1, &amendment)).unwrap();
+        ready(broker.cancel(1)).unwrap();
+        assert!(ready(broker.cancel(7)).is_err());
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].0, "POST /v2/orders HTTP/1.1");
+        let placed: Value = serde_json::from_str(&requests[0].1).unwrap();
+        assert_eq!(placed["qty"], "10");
+        assert_eq!(placed["type"], "market");
+        assert!(requests[3].0.contains("page_token=f-2"));
+        assert!(requests[5].0.starts_with("PATCH /v2/orders/a-1"));
+        assert_eq!(requests[5].1, r#"{"limit_price":"184.5"}"#);
+        assert!(requests[6].0.starts_with("DELETE /v2/orders/a-2"));
+    }
+}
@@ -0,0 +1,66 @@
+//! Short-sale availability: locates and borrow fee rates.
+
+use rand::Rng;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Terms of a hard-to-borrow symbol
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HardToBorrow {
+    /// Annual borrow fee as a fraction of the short's market value
+    pub fee_rate: f64,
+    /// Chance a short order finds shares to borrow
+    pub locate_probability: f64,
+}
+
+/// Which symbols can be sold short and what borrowing them costs.
+///
+/// Easy-to-borrow symbols always locate, hard-to-borrow symbols locate with
+/// their own probability, and any other symbol cannot be shorted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShortAvailability {
+    /// Annual borrow fee of easy-to-borrow symbols
+    easy_fee_rate: f64,
+    easy_to_borrow: BTreeSet<String>,
+    hard_to_borrow: BTreeMap<String, HardToBorrow>,
+}
+
+impl ShortAvailability {
+    pub fn new(easy_fee_rate: f64) -> Self {
+        Self {
+            easy_fee_rate,
+            ..Self::default()
+        }
+    }
+
+    pub fn easy_to_borrow<S: Into<String>>(mut self, symbols: impl IntoIterator<Item = S>) -> Self {
+        self.easy_to_borrow
+            .extend(symbols.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn hard_to_borrow(mut self, symbol: impl Into<String>, terms: HardToBorrow) -> Self {
+        self.hard_to_borrow.insert(symbol.into(), terms);
+        self
+    }
+
+    /// Annual fee of borrowing `symbol`; shorts held in unlisted symbols pay
+    /// the easy-to-borrow rate
+    pub(crate) fn fee_rate(&self, symbol: &str) -> f64 {
+        self.hard_to_borrow
+            .get(symbol)
+            .map_or(self.easy_fee_rate, |terms| terms.fee_rate)
+    }
+
+    /// Try to locate shares of `symbol`, returning whether the symbol is hard
+    /// to borrow when none are found
+    pub(crate) fn locate(&self, symbol: &str, rng: &mut impl Rng) -> Result<(), bool> {
+        if self.easy_to_borrow.contains(symbol) {
+            return Ok(());
+        }
+        match self.hard_to_borrow.get(symbol) {
+            Some(terms) if rng.gen::<f64>() < terms.locate_probability => Ok(()),
+            Some(_) => Err(true),
+            None => Err(false),
+        }
+    }
+}
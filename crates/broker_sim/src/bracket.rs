@@ -0,0 +1,69 @@
+//! Bracket exits and their one-cancels-other resolution.
+
+use anyhow::Result;
+use schema::{Bar, Bracket, Order, OrderType, Side};
+
+/// Check that the take-profit and stop-loss sit on the right sides of each other
+pub(crate) fn validate(order: &Order, bracket: &Bracket) -> Result<()> {
+    let positive = |p: f64| p.is_finite() && p > 0.0;
+    let ordered = match order.side {
+        Side::Buy => bracket.take_profit > bracket.stop_loss,
+        Side::Sell => bracket.take_profit < bracket.stop_loss,
+    };
+    if !(positive(bracket.take_profit) && positive(bracket.stop_loss) && ordered) {
+        anyhow::bail!(
+            "Bracket on {:?} order for {} needs positive prices with the take-profit {} the stop-loss, got {:?}",
+            order.side,
+            order.symbol,
+            match order.side {
+                Side::Buy => "above",
+                Side::Sell => "below",
+            },
+            bracket
+        );
+    }
+    Ok(())
+}
+
+/// Take-profit and stop-loss legs closing `quantity` of a filled entry
+pub(crate) fn exit_legs(entry: &Order, bracket: &Bracket, quantity: f64) -> [Order; 2] {
+    let side = match entry.side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+    };
+    let leg = |order_type, limit_price, stop_price| Order {
+        symbol: entry.symbol.clone(),
+        side,
+        quantity,
+        order_type,
+        limit_price,
+        stop_price,
+        order_id: None,
+        bracket: None,
+    };
+    [
+        leg(OrderType::Limit, Some(bracket.take_profit), None),
+        leg(OrderType::Stop, None, Some(bracket.stop_loss)),
+    ]
+}
+
+/// Which of two OCO legs that could both trade on `bar` gives way.
+///
+/// The bar's path is unknown, so the stop is assumed to trade first unless
+/// the bar opened through the limit leg, which then filled at the open.
+pub(crate) fn same_bar_loser<'a>(a: &'a Order, b: &'a Order, bar: &Bar) -> &'a Order {
+    let (limit, other) = if a.order_type == OrderType::Limit {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    let opened_through = limit.limit_price.is_some_and(|price| match limit.side {
+        Side::Buy => bar.open <= price,
+        Side::Sell => bar.open >= price,
+    });
+    if opened_through {
+        other
+    } else {
+        limit
+    }
+}
@@ -0,0 +1,140 @@
+//! Buying-power checks against the account an order trades for.
+
+use schema::{Fill, Portfolio, RejectionReason, Side};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::margin::MarginSchedule;
+
+/// Cash and positions of the account, kept current through one bar's fills.
+///
+/// A fill is allowed when it does not raise the initial margin required, or
+/// when the initial margin required afterwards stays within equity. An
+/// initial margin of 1.0 is a cash account, 0.5 Reg T margin.
+pub(crate) struct Account {
+    cash: f64,
+    /// Quantity and average price by symbol, sorted so sums do not depend
+    /// on hash order
+    positions: BTreeMap<String, (f64, f64)>,
+}
+
+impl Account {
+    pub(crate) fn new(portfolio: &Portfolio) -> Self {
+        Self {
+            cash: portfolio.cash,
+            positions: portfolio
+                .positions
+                .iter()
+                .map(|(symbol, p)| (symbol.clone(), (p.quantity, p.avg_price)))
+                .collect(),
+        }
+    }
+
+    /// Apply `fill` if the account can carry it under `margin`. Positions
+    /// are valued at `marks` (or their average price when unmarked), the
+    /// traded symbol at the fill price.
+    pub(crate) fn try_apply(
+        &mut self,
+        fill: &Fill,
+        margin: &MarginSchedule,
+        marks: &HashMap<String, f64>,
+    ) -> Result<(), RejectionReason> {
+        let delta = match fill.side {
+            Side::Buy => fill.quantity,
+            Side::Sell => -fill.quantity,
+        };
+        let held = self.positions.get(&fill.symbol).map_or(0.0, |p| p.0);
+        let cash = self.cash - delta * fill.price - fill.costs.charged();
+
+        let mut required_before = 0.0;
+        let mut required_after = 0.0;
+        let mut positions_value = 0.0;
+        for (symbol, &(quantity, avg_price)) in &self.positions {
+            if *symbol == fill.symbol {
+                continue;
+            }
+            let mark = marks.get(symbol).copied().unwrap_or(avg_price);
+            let required = quantity.abs() * mark * margin.requirement(symbol).initial;
+            required_before += required;
+            required_after += required;
+            positions_value += quantity * mark;
+        }
+        let initial = margin.requirement(&fill.symbol).initial;
+        required_before += held.abs() * fill.price * initial;
+        required_after += (held + delta).abs() * fill.price * initial;
+        positions_value += (held + delta) * fill.price;
+
+        let available = cash + positions_value;
+        // Relative tolerance so rounding in sizing does not reject exact fits
+        let tolerance = 1e-9 * required_after.max(1.0);
+        if required_after > required_before + tolerance && required_after > available + tolerance {
+            return Err(RejectionReason::InsufficientBuyingPower {
+                required: required_after,
+                available: available.max(0.0),
+            });
+        }
+
+        self.cash = cash;
+        let position = self
+            .positions
+            .entry(fill.symbol.clone())
+            .or_insert((0.0, fill.price));
+        position.0 = held + delta;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::margin::MarginRequirement;
+    use schema::CostBreakdown;
+
+    fn uniform(initial: f64) -> MarginSchedule {
+        MarginSchedule::new(MarginRequirement {
+            initial,
+            maintenance: initial,
+        })
+    }
+
+    fn fill(symbol: &str, side: Side, quantity: f64, price: f64) -> Fill {
+        Fill {
+            timestamp: 1,
+            symbol: symbol.to_string(),
+            side,
+            quantity,
+            price,
+            costs: CostBreakdown::from_commission(1.0),
+        }
+    }
+
+    #[test]
+    fn exposure_is_capped_at_a_multiple_of_equity() {
+        let marks = HashMap::from([("MSFT".to_string(), 50.0)]);
+        let cash_margin = uniform(1.0);
+        let mut cash = Account::new(&Portfolio::new(10_000.0));
+        // $1 commission leaves $9,999 of equity for $10,000 of stock
+        assert_eq!(
+            cash.try_apply(&fill("AAPL", Side::Buy, 100.0, 100.0), &cash_margin, &marks),
+            Err(RejectionReason::InsufficientBuyingPower {
+                required: 10_000.0,
+                available: 9_999.0
+            })
+        );
+        assert!(cash
+            .try_apply(&fill("AAPL", Side::Buy, 99.0, 100.0), &cash_margin, &marks)
+            .is_ok());
+        // Reducing exposure is always allowed
+        assert!(cash
+            .try_apply(&fill("AAPL", Side::Sell, 99.0, 100.0), &cash_margin, &marks)
+            .is_ok());
+
+        let reg_t = uniform(0.5);
+        let mut margin = Account::new(&Portfolio::new(10_000.0));
+        assert!(margin
+            .try_apply(&fill("AAPL", Side::Sell, 150.0, 100.0), &reg_t, &marks)
+            .is_ok());
+        assert!(margin
+            .try_apply(&fill("MSFT", Side::Buy, 150.0, 50.0), &reg_t, &marks)
+            .is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! Funding payments on perpetual futures.
+
+use schema::FundingPayment;
+use std::collections::BTreeMap;
+
+/// Where a perpetual's funding rate comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum FundingRate {
+    /// The same rate every interval
+    Fixed(f64),
+    /// Rates by the timestamp (seconds) they take effect from; no
+    /// funding is exchanged before the first
+    Series(BTreeMap<i64, f64>),
+}
+
+impl FundingRate {
+    fn at(&self, timestamp: i64) -> Option<f64> {
+        match self {
+            Self::Fixed(rate) => Some(*rate),
+            Self::Series(rates) => rates.range(..=timestamp).next_back().map(|(_, &r)| r),
+        }
+    }
+}
+
+/// Which symbols are perpetuals and when and at what rate they fund.
+///
+/// Funding is exchanged at every multiple of the interval since the epoch,
+/// in UTC. A rate is a fraction of the position's notional per interval:
+/// when positive, longs pay and shorts receive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingSchedule {
+    interval_secs: i64,
+    perpetuals: BTreeMap<String, FundingRate>,
+}
+
+impl FundingSchedule {
+    pub fn new(interval_secs: i64) -> Self {
+        Self {
+            interval_secs: interval_secs.max(1),
+            perpetuals: BTreeMap::new(),
+        }
+    }
+
+    pub fn perpetual(mut self, symbol: impl Into<String>, rate: FundingRate) -> Self {
+        self.perpetuals.insert(symbol.into(), rate);
+        self
+    }
+
+    /// Funding on a signed `quantity` of `symbol` held from `from` (exclusive)
+    /// to `to` (inclusive), valued at `price`
+    pub(crate) fn payments(
+        &self,
+        symbol: &str,
+        from: i64,
+        to: i64,
+        quantity: f64,
+        price: f64,
+    ) -> Vec<FundingPayment> {
+        let Some(rate) = self.perpetuals.get(symbol) else {
+            return Vec::new();
+        };
+        if quantity == 0.0 {
+            return Vec::new();
+        }
+        let first = from.div_euclid(self.interval_secs) + 1;
+        let last = to.div_euclid(self.interval_secs);
+        (first..=last)
+            .map(|k| k * self.interval_secs)
+            .filter_map(|timestamp| {
+                let rate = rate.at(timestamp)?;
+                Some(FundingPayment {
+                    timestamp,
+                    symbol: symbol.to_string(),
+                    quantity,
+                    price,
+                    rate,
+                    amount: quantity * price * rate,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3_600;
+
+    #[test]
+    fn every_funding_time_crossed_pays_at_its_rate() {
+        let series = FundingRate::Series(BTreeMap::from([(8 * HOUR, 0.001), (16 * HOUR, -0.002)]));
+        let schedule = FundingSchedule::new(8 * HOUR)
+            .perpetual("BTC-PERP", series)
+            .perpetual("ETH-PERP", FundingRate::Fixed(0.0001));
+
+        // Midnight predates the series, so only 08:00 and 16:00 fund
+        let payments = schedule.payments("BTC-PERP", -HOUR, 17 * HOUR, 2.0, 50_000.0);
+        let paid: Vec<(i64, f64)> = payments.iter().map(|p| (p.timestamp, p.amount)).collect();
+        assert_eq!(paid, [(8 * HOUR, 100.0), (16 * HOUR, -200.0)]);
+
+        // Shorts receive a positive rate; nothing funds inside an interval
+        let short = schedule.payments("ETH-PERP", 7 * HOUR, 9 * HOUR, -10.0, 3_000.0);
+        assert!((short[0].amount + 3.0).abs() < 1e-12);
+        assert!(schedule
+            .payments("ETH-PERP", 9 * HOUR, 15 * HOUR, -10.0, 3_000.0)
+            .is_empty());
+        assert!(schedule
+            .payments("AAPL", 0, 24 * HOUR, 10.0, 100.0)
+            .is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+//! Currency conversion costs on fills in foreign-currency instruments.
+
+use std::collections::BTreeMap;
+
+/// Which currency each symbol trades in and the spread paid converting
+/// each currency into the account's base currency.
+///
+/// Prices are taken as already in the base currency, so converting at a
+/// rate `spread` worse than mid costs that fraction of a fill's notional.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FxConversion {
+    base: String,
+    spreads: BTreeMap<String, f64>,
+    currencies: BTreeMap<String, String>,
+}
+
+impl FxConversion {
+    pub fn new(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into(),
+            spreads: BTreeMap::new(),
+            currencies: BTreeMap::new(),
+        }
+    }
+
+    /// Convert `currency` at `spread`, a fraction of the converted amount
+    pub fn with_spread(mut self, currency: impl Into<String>, spread: f64) -> Self {
+        self.spreads.insert(currency.into(), spread);
+        self
+    }
+
+    /// Trade `symbol` in `currency`; unlisted symbols trade in the base
+    pub fn with_symbol(mut self, symbol: impl Into<String>, currency: impl Into<String>) -> Self {
+        self.currencies.insert(symbol.into(), currency.into());
+        self
+    }
+
+    pub fn currency(&self, symbol: &str) -> &str {
+        self.currencies.get(symbol).unwrap_or(&self.base)
+    }
+
+    /// Cost of settling `notional` of `symbol` into the base currency
+    pub(crate) fn cost(&self, symbol: &str, notional: f64) -> f64 {
+        let currency = self.currency(symbol);
+        if currency == self.base {
+            return 0.0;
+        }
+        notional.abs() * self.spreads.get(currency).copied().unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_foreign_currency_fills_pay_the_spread() {
+        let fx = FxConversion::new("USD")
+            .with_spread("EUR", 0.0005)
+            .with_spread("USD", 0.01)
+            .with_symbol("SAP", "EUR")
+            .with_symbol("7203.T", "JPY");
+        assert!((fx.cost("SAP", -20_000.0) - 10.0).abs() < 1e-12);
+        assert_eq!(fx.currency("AAPL"), "USD");
+        // The base never converts, whatever its spread; JPY has none set
+        assert_eq!(fx.cost("AAPL", 20_000.0), 0.0);
+        assert_eq!(fx.cost("7203.T", 20_000.0), 0.0);
+    }
+}
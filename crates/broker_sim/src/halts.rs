@@ -0,0 +1,118 @@
+//! Trading halts and limit-up/limit-down states from the data stream.
+
+use schema::{EventEnvelope, MarketEventPayload, Order, Side, TradingState};
+use std::collections::BTreeMap;
+
+/// What happens to an order that cannot trade because of its symbol's state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HaltPolicy {
+    /// Keep the order working until trading resumes
+    #[default]
+    Queue,
+    /// Cancel the order
+    Cancel,
+}
+
+/// Each symbol's trading state over time; symbols trade until a status
+/// event says otherwise
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradingHalts {
+    states: BTreeMap<String, BTreeMap<i64, TradingState>>,
+}
+
+impl TradingHalts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put `symbol` in `state` from `timestamp` (seconds) on
+    pub fn with_state(
+        mut self,
+        symbol: impl Into<String>,
+        timestamp: i64,
+        state: TradingState,
+    ) -> Self {
+        self.states
+            .entry(symbol.into())
+            .or_default()
+            .insert(timestamp, state);
+        self
+    }
+
+    /// The trading status events of a stream, in any order
+    pub fn from_events(events: &[EventEnvelope]) -> Self {
+        events
+            .iter()
+            .fold(Self::new(), |halts, event| match &event.payload {
+                MarketEventPayload::TradingStatus(status) => {
+                    halts.with_state(event.symbol.clone(), event.event_time, status.state)
+                }
+                _ => halts,
+            })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn state(&self, symbol: &str, timestamp: i64) -> TradingState {
+        self.states
+            .get(symbol)
+            .and_then(|states| states.range(..=timestamp).next_back())
+            .map_or(TradingState::Trading, |(_, &state)| state)
+    }
+
+    /// Whether `order` is kept from trading at `timestamp`
+    pub(crate) fn blocks(&self, order: &Order, timestamp: i64) -> bool {
+        match self.state(&order.symbol, timestamp) {
+            TradingState::Trading => false,
+            TradingState::Halted => true,
+            TradingState::LimitUp => order.side == Side::Buy,
+            TradingState::LimitDown => order.side == Side::Sell,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::{MarketEventType, TradePayload, TradingStatusPayload};
+
+    fn event(event_time: i64, payload: MarketEventPayload) -> EventEnvelope {
+        EventEnvelope {
+            event_type: payload.event_type(),
+            symbol: "GME".to_string(),
+            event_time,
+            ingest_time: event_time,
+            source_id: "test".to_string(),
+            quality_flags: vec![],
+            payload,
+        }
+    }
+
+    #[test]
+    fn status_events_set_the_state_until_the_next_one() {
+        let status = |state| {
+            MarketEventPayload::TradingStatus(TradingStatusPayload {
+                state,
+                reason: None,
+            })
+        };
+        let trade = MarketEventPayload::Trade(TradePayload {
+            price: 10.0,
+            quantity: 1.0,
+            venue: None,
+        });
+        let events = [
+            event(200, status(TradingState::Trading)),
+            event(50, trade),
+            event(100, status(TradingState::LimitUp)),
+        ];
+        let halts = TradingHalts::from_events(&events);
+        assert_eq!(events[0].event_type, MarketEventType::TradingStatus);
+        assert_eq!(halts.state("GME", 99), TradingState::Trading);
+        assert_eq!(halts.state("GME", 100), TradingState::LimitUp);
+        assert_eq!(halts.state("GME", 200), TradingState::Trading);
+        assert_eq!(halts.state("AMC", 150), TradingState::Trading);
+    }
+}
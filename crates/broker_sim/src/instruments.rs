@@ -0,0 +1,184 @@
+//! Lot and tick size rules of traded instruments.
+
+use schema::{Order, RejectionReason, Side};
+use std::collections::BTreeMap;
+
+/// Slack for quantities and prices that are multiples up to float error
+const TOLERANCE: f64 = 1e-9;
+
+/// Trading increments of one instrument; `None` leaves that side free
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct InstrumentRules {
+    /// Quantities trade in multiples of this (1.0 for whole shares)
+    pub lot_size: Option<f64>,
+    /// Prices are multiples of this
+    pub tick_size: Option<f64>,
+}
+
+/// What happens to an order off its instrument's lot or tick
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round the quantity down to whole lots and prices to the tick on the
+    /// side that is never more aggressive than requested
+    #[default]
+    Snap,
+    /// Reject the order
+    Reject,
+}
+
+/// Instrument rules by symbol, with a default for unlisted symbols
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstrumentTable {
+    default: InstrumentRules,
+    symbols: BTreeMap<String, InstrumentRules>,
+    rounding: Rounding,
+}
+
+impl InstrumentTable {
+    pub fn new(default: InstrumentRules, rounding: Rounding) -> Self {
+        Self {
+            default,
+            symbols: BTreeMap::new(),
+            rounding,
+        }
+    }
+
+    /// Rules for `symbol` in place of the default
+    pub fn with_symbol(mut self, symbol: impl Into<String>, rules: InstrumentRules) -> Self {
+        self.symbols.insert(symbol.into(), rules);
+        self
+    }
+
+    pub fn rules(&self, symbol: &str) -> InstrumentRules {
+        self.symbols.get(symbol).copied().unwrap_or(self.default)
+    }
+
+    /// Whole lots of `symbol` in `quantity`
+    pub(crate) fn round_lots(&self, symbol: &str, quantity: f64) -> f64 {
+        match self.rules(symbol).lot_size {
+            Some(lot) => ((quantity / lot) + TOLERANCE).floor() * lot,
+            None => quantity,
+        }
+    }
+
+    /// Bring `order` onto its instrument's lot and tick, or say why it
+    /// cannot be
+    pub(crate) fn conform(&self, order: &mut Order) -> Result<(), RejectionReason> {
+        let rules = self.rules(&order.symbol);
+        if let Some(lot_size) = rules.lot_size {
+            let lots = self.round_lots(&order.symbol, order.quantity);
+            let off_lot = (order.quantity - lots).abs() > TOLERANCE * lot_size.max(1.0);
+            if lots <= 0.0 || (off_lot && self.rounding == Rounding::Reject) {
+                return Err(RejectionReason::OffLot { lot_size });
+            }
+            order.quantity = lots;
+        }
+        let Some(tick_size) = rules.tick_size else {
+            return Ok(());
+        };
+        // Limits round away from the market and stops further from it
+        let buy = order.side == Side::Buy;
+        if let Some(price) = &mut order.limit_price {
+            self.snap(price, tick_size, !buy)?;
+        }
+        if let Some(price) = &mut order.stop_price {
+            self.snap(price, tick_size, buy)?;
+        }
+        if let Some(bracket) = &mut order.bracket {
+            self.snap(&mut bracket.take_profit, tick_size, buy)?;
+            self.snap(&mut bracket.stop_loss, tick_size, !buy)?;
+        }
+        Ok(())
+    }
+
+    /// Put `price` on the tick, rounding up or down when it is off it
+    pub(crate) fn snap(
+        &self,
+        price: &mut f64,
+        tick_size: f64,
+        up: bool,
+    ) -> Result<(), RejectionReason> {
+        let ticks = *price / tick_size;
+        if (ticks - ticks.round()).abs() <= TOLERANCE {
+            *price = ticks.round() * tick_size;
+        } else if self.rounding == Rounding::Reject {
+            return Err(RejectionReason::OffTick { tick_size });
+        } else {
+            *price = if up { ticks.ceil() } else { ticks.floor() } * tick_size;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::{Bracket, OrderType};
+
+    fn order(side: Side, quantity: f64, limit_price: f64) -> Order {
+        Order {
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            order_type: OrderType::Limit,
+            limit_price: Some(limit_price),
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        }
+    }
+
+    #[test]
+    fn orders_snap_to_the_less_aggressive_lot_and_tick() {
+        let shares = InstrumentRules {
+            lot_size: Some(1.0),
+            tick_size: Some(0.01),
+        };
+        let table = InstrumentTable::new(shares, Rounding::Snap).with_symbol(
+            "BTC",
+            InstrumentRules {
+                lot_size: Some(0.001),
+                tick_size: None,
+            },
+        );
+        assert_eq!(table.rules("MSFT"), shares);
+
+        // Buys bid lower; the bracket exits sit further out
+        let mut buy = Order {
+            bracket: Some(Bracket {
+                take_profit: 110.004,
+                stop_loss: 95.006,
+            }),
+            ..order(Side::Buy, 3.71, 100.017)
+        };
+        table.conform(&mut buy).unwrap();
+        assert_eq!(buy.quantity, 3.0);
+        assert!((buy.limit_price.unwrap() - 100.01).abs() < 1e-9);
+        let bracket = buy.bracket.unwrap();
+        assert!((bracket.take_profit - 110.01).abs() < 1e-9);
+        assert!((bracket.stop_loss - 95.0).abs() < 1e-9);
+
+        let mut sell = order(Side::Sell, 10.0, 100.011);
+        table.conform(&mut sell).unwrap();
+        assert!((sell.limit_price.unwrap() - 100.02).abs() < 1e-9);
+        let mut btc = Order {
+            symbol: "BTC".to_string(),
+            ..order(Side::Buy, 0.12345, 50_000.123)
+        };
+        table.conform(&mut btc).unwrap();
+        assert!((btc.quantity - 0.123).abs() < 1e-12);
+        assert_eq!(btc.limit_price, Some(50_000.123));
+
+        // Less than a lot, or anything off the grid when rejecting, is refused
+        assert_eq!(
+            table.conform(&mut order(Side::Buy, 0.5, 100.0)),
+            Err(RejectionReason::OffLot { lot_size: 1.0 })
+        );
+        let strict = InstrumentTable::new(shares, Rounding::Reject);
+        assert!(strict.conform(&mut order(Side::Buy, 3.0, 100.25)).is_ok());
+        assert_eq!(
+            strict.conform(&mut order(Side::Buy, 3.0, 100.255)),
+            Err(RejectionReason::OffTick { tick_size: 0.01 })
+        );
+    }
+}
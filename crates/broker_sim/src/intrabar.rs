@@ -0,0 +1,109 @@
+//! Seeded intrabar price paths.
+
+use cost::standard_normal;
+use rand::Rng;
+use schema::{Bar, Side};
+
+/// Simulated prices within one bar, from its open to its close.
+///
+/// The path visits the bar's high and low at random steps, in random order,
+/// and is a Brownian bridge clamped to the bar's range between those points.
+pub(crate) struct IntrabarPath {
+    prices: Vec<f64>,
+}
+
+impl IntrabarPath {
+    /// Path of `steps` steps (at least 3) through `bar`
+    pub(crate) fn generate(bar: &Bar, steps: usize, rng: &mut impl Rng) -> Self {
+        let steps = steps.max(3);
+        let low = bar.low.min(bar.open).min(bar.close);
+        let high = bar.high.max(bar.open).max(bar.close);
+
+        // Two distinct interior steps for the extremes
+        let a = rng.gen_range(1..steps);
+        let mut b = rng.gen_range(1..steps - 1);
+        if b >= a {
+            b += 1;
+        }
+        let (first, second) = (a.min(b), a.max(b));
+        let (first_price, second_price) = if rng.gen_bool(0.5) {
+            (high, low)
+        } else {
+            (low, high)
+        };
+        let anchors = [
+            (0, bar.open),
+            (first, first_price),
+            (second, second_price),
+            (steps, bar.close),
+        ];
+
+        let mut normal = move || standard_normal(rng);
+        let sigma = (high - low) / (steps as f64).sqrt();
+        let mut prices = vec![bar.open; steps + 1];
+        for pair in anchors.windows(2) {
+            let ((start, _), (end, target)) = (pair[0], pair[1]);
+            for i in start + 1..end {
+                let previous = prices[i - 1];
+                let remaining = (end - i + 1) as f64;
+                let drift = (target - previous) / remaining;
+                let spread = sigma * ((remaining - 1.0) / remaining).sqrt();
+                prices[i] = (previous + drift + spread * normal()).clamp(low, high);
+            }
+            prices[end] = target;
+        }
+        Self { prices }
+    }
+
+    /// First step from `from` on at or above `level` when `up`, else at or
+    /// below it, with the price there
+    fn first_touch(&self, level: f64, up: bool, from: usize) -> Option<(usize, f64)> {
+        self.prices
+            .iter()
+            .enumerate()
+            .skip(from)
+            .find(|(_, &price)| if up { price >= level } else { price <= level })
+            .map(|(i, &price)| (i, price))
+    }
+
+    /// Step and price a limit order fills at: the open when the path starts
+    /// through the limit, else the limit itself
+    pub(crate) fn limit_fill(&self, side: Side, limit: f64, from: usize) -> Option<(usize, f64)> {
+        let (step, price) = self.first_touch(limit, side == Side::Sell, from)?;
+        Some((step, if step == from { price } else { limit }))
+    }
+
+    /// Step and price a stop triggers at: the first step through the stop,
+    /// at that step's price
+    pub(crate) fn stop_trigger(&self, side: Side, stop: f64) -> Option<(usize, f64)> {
+        self.first_touch(stop, side == Side::Buy, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn paths_span_the_bar_and_repeat_for_a_seed() {
+        let bar = Bar {
+            timestamp: 1,
+            symbol: "AAPL".to_string(),
+            open: 100.0,
+            high: 104.0,
+            low: 97.0,
+            close: 102.0,
+            volume: 1000.0,
+        };
+        let path = |seed| IntrabarPath::generate(&bar, 32, &mut ChaCha8Rng::seed_from_u64(seed));
+        let prices = path(7).prices;
+        assert_eq!(prices.len(), 33);
+        assert_eq!((prices[0], prices[32]), (100.0, 102.0));
+        assert!(prices.contains(&104.0) && prices.contains(&97.0));
+        assert!(prices.iter().all(|p| (97.0..=104.0).contains(p)));
+        assert_eq!(prices, path(7).prices);
+        assert_ne!(prices, path(8).prices);
+    }
+}
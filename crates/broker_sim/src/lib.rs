@@ -1,15 +1,182 @@
 #![forbid(unsafe_code)]
 
+pub mod algo;
+#[cfg(feature = "alpaca")]
+pub mod alpaca;
+mod borrow;
+mod bracket;
+mod buying_power;
+mod funding;
+mod fx;
+mod halts;
+mod instruments;
+mod intrabar;
+pub mod margin;
+pub mod options;
+pub mod order_book;
+pub mod queue;
+
+pub use algo::{AlgoKind, AlgoReport, ExecutionAlgo};
+#[cfg(feature = "alpaca")]
+pub use alpaca::AlpacaPaperBroker;
+pub use borrow::{HardToBorrow, ShortAvailability};
+pub use funding::{FundingRate, FundingSchedule};
+pub use fx::FxConversion;
+pub use halts::{HaltPolicy, TradingHalts};
+pub use instruments::{InstrumentRules, InstrumentTable, Rounding};
+pub use margin::{MarginRequirement, MarginSchedule, MarginStatus};
+pub use options::{OptionContract, OptionContracts, OptionKind};
+pub use order_book::OrderBookBroker;
+pub use queue::{QueueBroker, QueuedOrder};
+
 use anyhow::Result;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
-use schema::{Bar, BrokerSim, CostModel, Fill, Order, OrderType, Side};
+use schema::{
+    Bar, BorrowCharge, BrokerResult, BrokerSim, CostBreakdown, CostModel, Fill, FundingPayment,
+    MarginCall, MarketContext, OptionExpiration, Order, OrderAmendment, OrderEvent, OrderEventKind,
+    OrderRejection, OrderType, Portfolio, RejectionReason, Side,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::buying_power::Account;
+use crate::intrabar::IntrabarPath;
+
+/// When market orders fill
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FillPolicy {
+    /// At the close of the bar the order is submitted on, the close the
+    /// strategy has already seen
+    #[default]
+    Close,
+    /// At the open of the symbol's next bar, so no order trades at a price
+    /// known when it was placed
+    NextOpen,
+}
+
+/// Delay between submitting an order and it reaching the market
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Latency {
+    /// The order arrives on the symbol's `n`th bar after the one it was
+    /// submitted on
+    Bars(u32),
+    /// The order arrives on the first bar of its symbol at least `base` plus
+    /// a seeded uniform draw from `0..=jitter` milliseconds after the bar it
+    /// was submitted on
+    Millis { base: u64, jitter: u64 },
+}
 
-/// Simple broker simulator that fills all market orders immediately
+/// Simple broker simulator.
+///
+/// Market orders fill at the bar's close, or at the next bar's open under
+/// `FillPolicy::NextOpen`. Limit, stop and stop-limit orders rest from the bar
+/// they are submitted on and are checked against each later bar of their
+/// symbol until filled or cancelled. With a maximum participation rate, each
+/// bar fills at most that fraction of its volume and the residual of a larger
+/// order rests for the following bars. With latency, an order is handled as if
+/// submitted on the bar it arrives on. With a margin schedule, fills the
+/// portfolio cannot carry are rejected when processed through
+/// `process_orders_for`, and a portfolio below maintenance margin has its
+/// position in each bar's symbol liquidated until the call is met. With
+/// intrabar paths, resting orders trigger and fill along a seeded path through
+/// each bar instead of on its extremes, and the first OCO leg the path reaches
+/// wins. Bracket exits rest from the bar their entry fills on.
+/// With an execution algo, market orders are worked in slices over several
+/// bars. With short availability, short sales that find no locate are
+/// rejected and shorts accrue daily borrow fees, again through
+/// `process_orders_for`. With a funding schedule, positions in perpetual
+/// futures, such as crypto perpetual swaps, exchange funding at each funding
+/// time a bar of their symbol passes. With option contracts, option positions
+/// settle on the first bar of their underlying at or after expiry, at its
+/// close: in-the-money longs are exercised and shorts assigned at the strike.
 pub struct SimpleBroker<C: CostModel> {
     cost_model: C,
-    #[allow(dead_code)]
-    rng: ChaCha8Rng, // For future stochastic features, currently unused but seeded for determinism
+    /// Draws latency jitter and locates, so both are deterministic given the
+    /// seed
+    rng: ChaCha8Rng,
+    /// Resting orders and residuals in submission order
+    open_orders: Vec<Order>,
+    /// Id given to the next order submitted without one
+    next_order_id: u64,
+    /// Each resting bracket leg's one-cancels-other sibling
+    oco: HashMap<u64, u64>,
+    /// Orders still on their way to the market, in submission order
+    in_flight: Vec<InFlight>,
+    max_participation: Option<f64>,
+    fill_policy: FillPolicy,
+    latency: Option<Latency>,
+    margin: Option<MarginSchedule>,
+    /// Forced liquidations, so a residual still resting counts towards the
+    /// next margin call instead of being sold again
+    liquidations: HashSet<u64>,
+    /// Latest close of each symbol, to value positions in buying-power checks
+    marks: HashMap<String, f64>,
+    algo: Option<ExecutionAlgo>,
+    /// Parent orders worked by the algo, by order id
+    algo_reports: BTreeMap<u64, AlgoReport>,
+    /// Total volume and bar count of each symbol, for VWAP weights
+    volumes: HashMap<String, (f64, u32)>,
+    short_availability: Option<ShortAvailability>,
+    /// Orders that have located shares to sell short
+    located: HashSet<u64>,
+    /// Day of each symbol's latest bar, from which borrow fees accrue
+    borrow_days: HashMap<String, i64>,
+    funding: Option<FundingSchedule>,
+    /// Timestamp of each perpetual's latest bar, from which funding accrues
+    funding_times: HashMap<String, i64>,
+    options: Option<OptionContracts>,
+    instruments: Option<InstrumentTable>,
+    halts: Option<(TradingHalts, HaltPolicy)>,
+    fx: Option<FxConversion>,
+    /// Steps of each bar's simulated path, when orders trade along one
+    intrabar_steps: Option<usize>,
+    /// Draws intrabar paths on its own stream of the seed, so enabling them
+    /// leaves latency and locate draws unchanged
+    path_rng: ChaCha8Rng,
+    /// Order events not yet returned, including cancels between bars
+    events: Vec<OrderEvent>,
+    /// Timestamp of the latest bar, at which cancels between bars happen
+    clock: i64,
+}
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// An order delayed by latency
+struct InFlight {
+    order: Order,
+    arrival: Arrival,
+}
+
+enum Arrival {
+    /// Bars of the symbol still to pass
+    AfterBars(u32),
+    /// Earliest bar timestamp, in milliseconds
+    AtMillis(i64),
+}
+
+/// State shared by the orders executed on one bar
+struct BarState<'a> {
+    bar: &'a Bar,
+    /// Volume the bar can still fill
+    capacity: f64,
+    /// The account fills are checked against, when buying power is checked
+    account: Option<Account>,
+    /// Simulated path through the bar, when orders trade along one
+    path: Option<IntrabarPath>,
+    /// Signed holdings by symbol, when short sales are checked
+    holdings: Option<HashMap<String, f64>>,
+    rejections: Vec<OrderRejection>,
+    /// Orders that traded, after the fill, with the quantity filled
+    executed: Vec<(Order, f64)>,
+}
+
+/// How an executable order is priced
+#[derive(Clone, Copy)]
+enum Execution {
+    /// Slippage applies on top of the price
+    Market(f64),
+    /// The limit caps the price, so no slippage is applied
+    Limit(f64),
 }
 
 impl<C: CostModel> SimpleBroker<C> {
@@ -17,52 +184,944 @@ impl<C: CostModel> SimpleBroker<C> {
         Self {
             cost_model,
             rng: ChaCha8Rng::seed_from_u64(seed),
+            path_rng: {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                rng.set_stream(1);
+                rng
+            },
+            open_orders: Vec::new(),
+            next_order_id: 1,
+            oco: HashMap::new(),
+            in_flight: Vec::new(),
+            max_participation: None,
+            fill_policy: FillPolicy::Close,
+            latency: None,
+            margin: None,
+            liquidations: HashSet::new(),
+            marks: HashMap::new(),
+            algo: None,
+            algo_reports: BTreeMap::new(),
+            volumes: HashMap::new(),
+            short_availability: None,
+            located: HashSet::new(),
+            borrow_days: HashMap::new(),
+            funding: None,
+            funding_times: HashMap::new(),
+            options: None,
+            instruments: None,
+            halts: None,
+            fx: None,
+            intrabar_steps: None,
+            events: Vec::new(),
+            clock: 0,
         }
     }
-}
 
-impl<C: CostModel> BrokerSim for SimpleBroker<C> {
-    fn process_orders(&mut self, orders: Vec<Order>, bar: &Bar) -> Result<Vec<Fill>> {
+    /// Trigger and fill resting orders along a seeded Brownian-bridge path of
+    /// `steps` steps through each bar
+    pub fn with_intrabar_path(mut self, steps: usize) -> Self {
+        self.intrabar_steps = Some(steps);
+        self
+    }
+
+    /// Require a locate for short sales and charge borrow fees on shorts
+    pub fn with_short_availability(mut self, shorts: ShortAvailability) -> Self {
+        self.short_availability = Some(shorts);
+        self
+    }
+
+    /// Exchange funding on positions in the schedule's perpetuals
+    pub fn with_funding(mut self, funding: FundingSchedule) -> Self {
+        self.funding = Some(funding);
+        self
+    }
+
+    /// Exercise, assign or expire option positions in these contracts
+    pub fn with_options(mut self, options: OptionContracts) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    /// Snap or reject orders off their instrument's lot and tick sizes
+    pub fn with_instruments(mut self, instruments: InstrumentTable) -> Self {
+        self.instruments = Some(instruments);
+        self
+    }
+
+    /// Keep orders from trading while their symbol is halted or at a price
+    /// limit, queuing or cancelling them by `policy`
+    pub fn with_halts(mut self, halts: TradingHalts, policy: HaltPolicy) -> Self {
+        self.halts = Some((halts, policy));
+        self
+    }
+
+    /// Charge the conversion spread of foreign-currency fills as commission
+    pub fn with_fx_conversion(mut self, fx: FxConversion) -> Self {
+        self.fx = Some(fx);
+        self
+    }
+
+    /// Work market orders in slices over several bars
+    pub fn with_execution_algo(mut self, algo: ExecutionAlgo) -> Self {
+        self.algo = Some(algo);
+        self
+    }
+
+    /// Progress of every order worked by the execution algo, by order id
+    pub fn algo_reports(&self) -> impl Iterator<Item = &AlgoReport> {
+        self.algo_reports.values()
+    }
+
+    /// Reject fills that would take gross exposure above `multiplier` times
+    /// equity; 1.0 models a cash account, 2.0 Reg T margin. Positions are
+    /// never liquidated.
+    pub fn with_margin_multiplier(self, multiplier: f64) -> Self {
+        self.with_margin(MarginSchedule::new(MarginRequirement {
+            initial: 1.0 / multiplier,
+            maintenance: 0.0,
+        }))
+    }
+
+    /// Check fills against initial margin and liquidate positions when
+    /// equity falls below maintenance margin
+    pub fn with_margin(mut self, margin: MarginSchedule) -> Self {
+        self.margin = Some(margin);
+        self
+    }
+
+    /// Delay every order by `latency` before it can trade
+    pub fn with_latency(mut self, latency: Latency) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Fill market orders per `policy` instead of at the current close
+    pub fn with_fill_policy(mut self, policy: FillPolicy) -> Self {
+        self.fill_policy = policy;
+        self
+    }
+
+    /// Fill at most `fraction` of each bar's volume across all orders for
+    /// its symbol
+    pub fn with_max_participation(mut self, fraction: f64) -> Self {
+        self.max_participation = Some(fraction);
+        self
+    }
+
+    /// Orders submitted but not yet arrived at the market
+    pub fn in_flight_orders(&self) -> impl Iterator<Item = &Order> {
+        self.in_flight.iter().map(|f| &f.order)
+    }
+
+    /// Cancel the resting and in-flight orders for `symbol` and return them
+    pub fn cancel_orders(&mut self, symbol: &str) -> Vec<Order> {
+        self.cancel_matching(|order| order.symbol == symbol)
+    }
+
+    /// Cancel the resting and in-flight orders `matches` picks out
+    fn cancel_matching(&mut self, matches: impl Fn(&Order) -> bool) -> Vec<Order> {
+        let (mut cancelled, open): (Vec<Order>, _) = std::mem::take(&mut self.open_orders)
+            .into_iter()
+            .partition(|order| matches(order));
+        self.open_orders = open;
+        let (in_flight, still_in_flight): (Vec<InFlight>, _) = std::mem::take(&mut self.in_flight)
+            .into_iter()
+            .partition(|f| matches(&f.order));
+        self.in_flight = still_in_flight;
+        cancelled.extend(in_flight.into_iter().map(|f| f.order));
+        self.prune_oco();
+        for order in &cancelled {
+            let remaining = order.quantity;
+            self.log(order, OrderEventKind::Cancelled { remaining });
+        }
+        cancelled
+    }
+
+    /// Record a step in `order`'s life at the latest bar's timestamp
+    fn log(&mut self, order: &Order, kind: OrderEventKind) {
+        self.events.push(OrderEvent {
+            timestamp: self.clock,
+            order_id: order.order_id.unwrap_or_default(),
+            symbol: order.symbol.clone(),
+            kind,
+        });
+    }
+
+    /// Forget OCO links to orders no longer resting, so a cancelled leg's
+    /// sibling stands alone
+    fn prune_oco(&mut self) {
+        let open: HashSet<u64> = self.open_orders.iter().filter_map(|o| o.order_id).collect();
+        self.oco
+            .retain(|id, sibling| open.contains(id) && open.contains(sibling));
+    }
+
+    fn market_price(&self, bar: &Bar) -> f64 {
+        match self.fill_policy {
+            FillPolicy::Close => bar.close,
+            FillPolicy::NextOpen => bar.open,
+        }
+    }
+
+    /// Ids of resting OCO legs that give way to their sibling on `bar`
+    /// because both could trade on it
+    fn oco_losers(&self, bar: &Bar, path: Option<&IntrabarPath>) -> HashSet<u64> {
+        let market_price = self.market_price(bar);
+        let tradable =
+            |order: &Order| executable_price(&mut order.clone(), bar, market_price, path).is_some();
+        let resting = |id: u64| self.open_orders.iter().find(|o| o.order_id == Some(id));
+        let mut losers = HashSet::new();
+        for (&id, &sibling_id) in &self.oco {
+            let (Some(leg), Some(sibling)) = (resting(id), resting(sibling_id)) else {
+                continue;
+            };
+            if leg.symbol != bar.symbol || !tradable(leg) || !tradable(sibling) {
+                continue;
+            }
+            let step = |order: &Order| {
+                path.and_then(|path| path_execution(&mut order.clone(), path, market_price))
+                    .map(|(step, _)| step)
+            };
+            let loser = match (step(leg), step(sibling)) {
+                (Some(a), Some(b)) if a < b => sibling,
+                (Some(a), Some(b)) if b < a => leg,
+                _ => bracket::same_bar_loser(leg, sibling, bar),
+            };
+            losers.extend(loser.order_id);
+        }
+        losers
+    }
+
+    /// Reduce the OCO siblings of legs that traded and place the exits of
+    /// bracket entries that filled
+    fn settle(&mut self, executed: Vec<(Order, f64)>) {
+        for (order, quantity) in executed {
+            let Some(id) = order.order_id else { continue };
+            if let Some(sibling_id) = self.oco.get(&id).copied() {
+                if let Some(i) = self
+                    .open_orders
+                    .iter()
+                    .position(|o| o.order_id == Some(sibling_id))
+                {
+                    self.open_orders[i].quantity -= quantity;
+                    if self.open_orders[i].quantity <= 0.0 {
+                        let sibling = self.open_orders.remove(i);
+                        let remaining = sibling.quantity + quantity;
+                        self.log(&sibling, OrderEventKind::Cancelled { remaining });
+                    }
+                }
+            }
+            if let Some(bracket) = &order.bracket {
+                let [take_profit, stop_loss] = bracket::exit_legs(&order, bracket, quantity);
+                let ids = [take_profit, stop_loss].map(|mut leg| {
+                    let leg_id = self.next_order_id;
+                    self.next_order_id += 1;
+                    leg.order_id = Some(leg_id);
+                    self.log(&leg, OrderEventKind::Submitted { order: leg.clone() });
+                    self.log(&leg, OrderEventKind::Accepted);
+                    self.open_orders.push(leg);
+                    leg_id
+                });
+                self.oco.insert(ids[0], ids[1]);
+                self.oco.insert(ids[1], ids[0]);
+            }
+        }
+        self.prune_oco();
+    }
+
+    /// Give `order` the next id unless it brings its own unused one
+    fn assign_id(&mut self, order: &mut Order) -> Result<()> {
+        match order.order_id {
+            Some(id) => {
+                if self.find_mut(id).is_some() {
+                    anyhow::bail!("Order id {} is already in use", id);
+                }
+                self.next_order_id = self.next_order_id.max(id + 1);
+            }
+            None => {
+                order.order_id = Some(self.next_order_id);
+                self.next_order_id += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// The resting or in-flight order with `order_id`
+    fn find_mut(&mut self, order_id: u64) -> Option<&mut Order> {
+        self.open_orders
+            .iter_mut()
+            .chain(self.in_flight.iter_mut().map(|f| &mut f.order))
+            .find(|order| order.order_id == Some(order_id))
+    }
+
+    /// When an order submitted on `bar` reaches the market, or `None` if it
+    /// arrives straight away
+    fn arrival(&mut self, bar: &Bar) -> Option<Arrival> {
+        match self.latency? {
+            Latency::Bars(0) => None,
+            Latency::Bars(bars) => Some(Arrival::AfterBars(bars)),
+            Latency::Millis { base, jitter } => {
+                let delay = base + self.rng.gen_range(0..=jitter);
+                (delay > 0).then(|| {
+                    Arrival::AtMillis(
+                        bar.timestamp
+                            .saturating_mul(1000)
+                            .saturating_add(delay as i64),
+                    )
+                })
+            }
+        }
+    }
+
+    /// Take the in-flight orders that arrive on `bar`, in submission order
+    fn arrivals(&mut self, bar: &Bar) -> Vec<Order> {
+        let mut arrived = Vec::new();
+        let mut still_in_flight = Vec::with_capacity(self.in_flight.len());
+        for mut flight in std::mem::take(&mut self.in_flight) {
+            if flight.order.symbol == bar.symbol {
+                let here = match &mut flight.arrival {
+                    Arrival::AfterBars(bars) => {
+                        *bars -= 1;
+                        *bars == 0
+                    }
+                    Arrival::AtMillis(at) => bar.timestamp.saturating_mul(1000) >= *at,
+                };
+                if here {
+                    arrived.push(flight.order);
+                    continue;
+                }
+            }
+            still_in_flight.push(flight);
+        }
+        self.in_flight = still_in_flight;
+        arrived
+    }
+
+    /// Fill as much of `order` as the bar has capacity for, leaving the
+    /// residual in `order.quantity`. A fill the account cannot carry rejects
+    /// the whole residual.
+    fn execute(&mut self, order: &mut Order, state: &mut BarState) -> Option<Fill> {
+        let bar = state.bar;
+        if let Some((halts, policy)) = &self.halts {
+            if halts.blocks(order, bar.timestamp) {
+                if *policy == HaltPolicy::Cancel {
+                    let remaining = std::mem::take(&mut order.quantity);
+                    self.log(order, OrderEventKind::Cancelled { remaining });
+                }
+                return None;
+            }
+        }
+        let execution = executable_price(order, bar, self.market_price(bar), state.path.as_ref())?;
+        let mut quantity = order.quantity.min(state.capacity);
+        let parent = match (order.order_type, self.algo, order.order_id) {
+            (OrderType::Market, Some(algo), Some(id)) => self
+                .algo_reports
+                .get_mut(&id)
+                .map(|report| (algo.kind, report)),
+            _ => None,
+        };
+        if let Some((kind, report)) = parent {
+            let (total, bars) = self.volumes.get(&bar.symbol).copied().unwrap_or((0.0, 0));
+            let weight = bar.volume / (total / f64::from(bars.max(1)));
+            quantity = quantity.min(report.next_slice(kind, order.quantity, weight));
+        }
+        // Partial fills trade whole lots; the last fill takes any remainder
+        if let (Some(instruments), true) = (&self.instruments, quantity < order.quantity) {
+            quantity = instruments.round_lots(&order.symbol, quantity);
+        }
+        if quantity <= 0.0 {
+            return None;
+        }
+
+        // Commission and fees are charged on the price before slippage
+        let market = MarketContext {
+            symbol: order.symbol.clone(),
+            volume: bar.volume,
+        };
+        let (price, slippage) = match execution {
+            Execution::Market(price) => (
+                price,
+                self.cost_model
+                    .calculate_slippage(quantity, price, order.side, &market),
+            ),
+            Execution::Limit(price) => (price, 0.0),
+        };
+        let fill_price = match order.side {
+            Side::Buy => price + slippage,
+            Side::Sell => price - slippage,
+        };
+        let costs = CostBreakdown {
+            commission: self
+                .cost_model
+                .calculate_commission(quantity, price, &market),
+            exchange_fees: self
+                .cost_model
+                .calculate_exchange_fees(quantity, price, &market),
+            slippage: slippage * quantity,
+            borrow: 0.0,
+            // Settling a foreign-currency fill pays the conversion spread
+            other: self
+                .fx
+                .as_ref()
+                .map_or(0.0, |fx| fx.cost(&order.symbol, quantity * fill_price)),
+        };
+        let fill = Fill {
+            timestamp: bar.timestamp,
+            symbol: order.symbol.clone(),
+            side: order.side,
+            quantity,
+            price: fill_price,
+            costs,
+        };
+
+        if let Err(reason) = self.locate(order, quantity, state) {
+            return self.reject(order, reason, state);
+        }
+        if let (Some(account), Some(margin)) = (&mut state.account, &self.margin) {
+            if let Err(reason) = account.try_apply(&fill, margin, &self.marks) {
+                return self.reject(order, reason, state);
+            }
+        }
+        if let Some(holdings) = &mut state.holdings {
+            *holdings.entry(order.symbol.clone()).or_default() += match order.side {
+                Side::Buy => quantity,
+                Side::Sell => -quantity,
+            };
+        }
+        state.capacity -= quantity;
+        order.quantity -= quantity;
+        state.executed.push((order.clone(), quantity));
+        if let Some(report) = order.order_id.and_then(|id| self.algo_reports.get_mut(&id)) {
+            report.record(&fill);
+        }
+        let kind = if order.quantity > 0.0 {
+            OrderEventKind::PartiallyFilled {
+                quantity,
+                price: fill_price,
+                remaining: order.quantity,
+            }
+        } else {
+            OrderEventKind::Filled {
+                quantity,
+                price: fill_price,
+            }
+        };
+        self.log(order, kind);
+        Some(fill)
+    }
+
+    /// Refuse the rest of `order`
+    fn reject(
+        &mut self,
+        order: &mut Order,
+        reason: RejectionReason,
+        state: &mut BarState,
+    ) -> Option<Fill> {
+        self.log(order, OrderEventKind::Rejected(reason.clone()));
+        state.rejections.push(OrderRejection {
+            timestamp: state.bar.timestamp,
+            order: order.clone(),
+            reason,
+        });
+        order.quantity = 0.0;
+        None
+    }
+
+    /// Find shares to borrow when selling `quantity` would open or extend a
+    /// short. An order that locates keeps its locate for later fills.
+    fn locate(
+        &mut self,
+        order: &Order,
+        quantity: f64,
+        state: &BarState,
+    ) -> Result<(), RejectionReason> {
+        let (Some(shorts), Some(holdings)) = (&self.short_availability, &state.holdings) else {
+            return Ok(());
+        };
+        let held = holdings.get(&order.symbol).copied().unwrap_or(0.0);
+        let located = order.order_id.is_some_and(|id| self.located.contains(&id));
+        if order.side == Side::Buy || held - quantity >= 0.0 || located {
+            return Ok(());
+        }
+        shorts
+            .locate(&order.symbol, &mut self.rng)
+            .map_err(|hard_to_borrow| RejectionReason::LocateFailed { hard_to_borrow })?;
+        self.located.extend(order.order_id);
+        Ok(())
+    }
+
+    /// Borrow fee on the portfolio's short in the bar's symbol for the days
+    /// since the symbol's previous bar, valued at its previous close
+    fn accrue_borrow(&mut self, bar: &Bar, portfolio: Option<&Portfolio>) -> Option<BorrowCharge> {
+        let shorts = self.short_availability.as_ref()?;
+        let day = bar.timestamp.div_euclid(SECONDS_PER_DAY);
+        let days = day - replace(&mut self.borrow_days, &bar.symbol, day)?;
+        let quantity = -portfolio?.get_position(&bar.symbol)?.quantity;
+        let price = *self.marks.get(&bar.symbol)?;
+        if days <= 0 || quantity <= 0.0 {
+            return None;
+        }
+        let fee = self.cost_model.calculate_borrow_fee(
+            quantity * price,
+            shorts.fee_rate(&bar.symbol),
+            days as f64,
+        );
+        Some(BorrowCharge {
+            timestamp: bar.timestamp,
+            symbol: bar.symbol.clone(),
+            quantity,
+            price,
+            days,
+            fee,
+        })
+    }
+
+    /// Funding on the portfolio's position in the bar's symbol at each
+    /// funding time since the symbol's previous bar, valued at its previous
+    /// close
+    fn accrue_funding(&mut self, bar: &Bar, portfolio: Option<&Portfolio>) -> Vec<FundingPayment> {
+        let Some(funding) = &self.funding else {
+            return Vec::new();
+        };
+        let from = replace(&mut self.funding_times, &bar.symbol, bar.timestamp);
+        let quantity = portfolio
+            .and_then(|p| p.get_position(&bar.symbol))
+            .map_or(0.0, |position| position.quantity);
+        match (from, self.marks.get(&bar.symbol)) {
+            (Some(from), Some(&price)) => {
+                funding.payments(&bar.symbol, from, bar.timestamp, quantity, price)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Settle the portfolio's positions in options on the bar's symbol that
+    /// have expired, returning them with their fills
+    fn expire_options(
+        &self,
+        bar: &Bar,
+        portfolio: Option<&Portfolio>,
+    ) -> (Vec<OptionExpiration>, Vec<Fill>) {
+        let mut expirations = Vec::new();
         let mut fills = Vec::new();
+        let (Some(options), Some(portfolio)) = (&self.options, portfolio) else {
+            return (expirations, fills);
+        };
+        for (symbol, contract) in options.expired(&bar.symbol, bar.timestamp) {
+            let quantity = portfolio.get_position(symbol).map_or(0.0, |p| p.quantity);
+            if quantity != 0.0 {
+                let (expiration, settlement) =
+                    options::settle(symbol, contract, quantity, bar.close, bar.timestamp);
+                expirations.push(expiration);
+                fills.extend(settlement);
+            }
+        }
+        (expirations, fills)
+    }
 
-        for order in orders {
-            // For now, only support market orders
-            match order.order_type {
-                OrderType::Market => {
-                    // Fill at the close price of the bar
-                    let fill_price = bar.close;
-
-                    // Calculate commission
-                    let commission = self
-                        .cost_model
-                        .calculate_commission(order.quantity, fill_price);
-
-                    // Apply slippage (if any)
-                    let slippage =
-                        self.cost_model
-                            .calculate_slippage(order.quantity, fill_price, order.side);
-                    let adjusted_price = match order.side {
-                        Side::Buy => fill_price + slippage,
-                        Side::Sell => fill_price - slippage,
-                    };
-
-                    fills.push(Fill {
+    /// Execute one bar's resting, arriving and new orders
+    fn process(
+        &mut self,
+        mut orders: Vec<Order>,
+        bar: &Bar,
+        portfolio: Option<&Portfolio>,
+    ) -> Result<BrokerResult> {
+        self.clock = bar.timestamp;
+        for order in &mut orders {
+            validate_prices(order)?;
+            self.assign_id(order)?;
+        }
+        for order in &orders {
+            let submitted = order.clone();
+            self.log(order, OrderEventKind::Submitted { order: submitted });
+        }
+        let mut rejections = Vec::new();
+        if let Some(instruments) = &self.instruments {
+            let mut conforming = Vec::with_capacity(orders.len());
+            for mut order in orders {
+                match instruments.conform(&mut order) {
+                    Ok(()) => conforming.push(order),
+                    Err(reason) => rejections.push(OrderRejection {
                         timestamp: bar.timestamp,
-                        symbol: order.symbol.clone(),
-                        side: order.side,
-                        quantity: order.quantity,
-                        price: adjusted_price,
-                        commission,
-                    });
-                }
-                OrderType::Limit => {
-                    // Limit orders not implemented yet - would need more sophisticated logic
-                    // For simplicity, skip them in this implementation
+                        order,
+                        reason,
+                    }),
                 }
             }
+            orders = conforming;
+            for rejection in &rejections {
+                self.log(
+                    &rejection.order,
+                    OrderEventKind::Rejected(rejection.reason.clone()),
+                );
+            }
+        }
+        let borrow_charges = self.accrue_borrow(bar, portfolio).into_iter().collect();
+        let funding_payments = self.accrue_funding(bar, portfolio);
+        // Expiring options settle ahead of the bar's trading
+        let (option_expirations, mut fills) = self.expire_options(bar, portfolio);
+        replace(&mut self.marks, &bar.symbol, bar.close);
+        let volume = slot(&mut self.volumes, &bar.symbol);
+        volume.0 += bar.volume.max(0.0);
+        volume.1 += 1;
+        let mut margin_calls = Vec::new();
+        let mut liquidation = None;
+        if let (Some(margin), Some(portfolio)) = (&self.margin, portfolio) {
+            let status = margin.status(portfolio, &self.marks);
+            if status.margin_call() {
+                margin_calls.push(MarginCall {
+                    timestamp: bar.timestamp,
+                    equity: status.equity,
+                    maintenance_required: status.maintenance_required,
+                });
+                let pending: f64 = self
+                    .open_orders
+                    .iter()
+                    .filter(|o| o.symbol == bar.symbol)
+                    .filter(|o| o.order_id.is_some_and(|id| self.liquidations.contains(&id)))
+                    .map(|o| o.quantity)
+                    .sum();
+                liquidation = margin
+                    .liquidation(&status, portfolio, &bar.symbol, bar.close)
+                    .map(|mut order| {
+                        order.quantity -= pending;
+                        order
+                    })
+                    .filter(|order| order.quantity > 0.0);
+            }
+        }
+        let path = self
+            .intrabar_steps
+            .map(|steps| IntrabarPath::generate(bar, steps, &mut self.path_rng));
+        let mut state = BarState {
+            bar,
+            path,
+            capacity: self
+                .max_participation
+                .map_or(f64::INFINITY, |fraction| fraction * bar.volume.max(0.0)),
+            account: portfolio
+                .filter(|_| self.margin.is_some())
+                .map(Account::new),
+            holdings: portfolio
+                .filter(|_| self.short_availability.is_some())
+                .map(|portfolio| {
+                    portfolio
+                        .positions
+                        .iter()
+                        .map(|(symbol, position)| (symbol.clone(), position.quantity))
+                        .collect()
+                }),
+            rejections,
+            executed: Vec::new(),
+        };
+
+        // Resting orders trade during the bar, before anything submitted on it
+        let losers = self.oco_losers(bar, state.path.as_ref());
+        let mut still_open = Vec::with_capacity(self.open_orders.len());
+        for mut order in std::mem::take(&mut self.open_orders) {
+            let lost = order.order_id.is_some_and(|id| losers.contains(&id));
+            if order.symbol == bar.symbol && !lost {
+                fills.extend(self.execute(&mut order, &mut state));
+            }
+            if order.quantity > 0.0 {
+                still_open.push(order);
+            }
+        }
+        self.open_orders = still_open;
+
+        // Forced liquidations skip latency and algos and trade ahead of the
+        // bar's own orders
+        if let Some(mut order) = liquidation {
+            self.assign_id(&mut order)?;
+            self.liquidations.extend(order.order_id);
+            self.log(
+                &order,
+                OrderEventKind::Submitted {
+                    order: order.clone(),
+                },
+            );
+            self.log(&order, OrderEventKind::Accepted);
+            if self.fill_policy == FillPolicy::Close {
+                fills.extend(self.execute(&mut order, &mut state));
+            }
+            if order.quantity > 0.0 {
+                self.open_orders.push(order);
+            }
+        }
+
+        let mut arrived = self.arrivals(bar);
+        for order in orders {
+            match self.arrival(bar) {
+                Some(arrival) => self.in_flight.push(InFlight { order, arrival }),
+                None => arrived.push(order),
+            }
         }
 
-        Ok(fills)
+        for mut order in arrived {
+            self.log(&order, OrderEventKind::Accepted);
+            if let (OrderType::Market, Some(algo), Some(id)) =
+                (order.order_type, self.algo, order.order_id)
+            {
+                let report =
+                    AlgoReport::new(id, &order.symbol, order.side, order.quantity, algo.bars);
+                self.algo_reports.insert(id, report);
+            }
+            if order.order_type == OrderType::Market && self.fill_policy == FillPolicy::Close {
+                fills.extend(self.execute(&mut order, &mut state));
+            }
+            if order.quantity > 0.0 {
+                self.open_orders.push(order);
+            }
+        }
+
+        self.settle(state.executed);
+        let open: HashSet<u64> = self.open_orders.iter().filter_map(|o| o.order_id).collect();
+        self.liquidations.retain(|id| open.contains(id));
+        Ok(BrokerResult {
+            fills,
+            rejections: state.rejections,
+            borrow_charges,
+            funding_payments,
+            option_expirations,
+            margin_calls,
+            order_events: std::mem::take(&mut self.events),
+        })
+    }
+}
+
+/// Set `symbol`'s entry in `map` to `value`, returning the one it replaces.
+/// The symbol is only cloned the first time it is seen.
+fn replace<V>(map: &mut HashMap<String, V>, symbol: &str, value: V) -> Option<V> {
+    match map.get_mut(symbol) {
+        Some(entry) => Some(std::mem::replace(entry, value)),
+        None => {
+            map.insert(symbol.to_string(), value);
+            None
+        }
+    }
+}
+
+/// `symbol`'s entry in `map`, inserting a default the first time it is seen
+fn slot<'a, V: Default>(map: &'a mut HashMap<String, V>, symbol: &str) -> &'a mut V {
+    if !map.contains_key(symbol) {
+        map.insert(symbol.to_string(), V::default());
+    }
+    map.get_mut(symbol).expect("inserted above")
+}
+
+/// Price `order` can trade at on a bar of its symbol, where market orders
+/// trade at `market_price`. A triggered stop is converted in place to the
+/// order type it becomes.
+fn executable_price(
+    order: &mut Order,
+    bar: &Bar,
+    market_price: f64,
+    path: Option<&IntrabarPath>,
+) -> Option<Execution> {
+    if let Some(path) = path {
+        return path_execution(order, path, market_price).map(|(_, execution)| execution);
+    }
+    let limit = order.limit_price.unwrap_or_default();
+    let stop = order.stop_price.unwrap_or_default();
+    match order.order_type {
+        OrderType::Market => Some(Execution::Market(market_price)),
+        OrderType::Limit => limit_fill_price(order.side, limit, bar).map(Execution::Limit),
+        OrderType::Stop => {
+            let triggered = stop_trigger_price(order.side, stop, bar)?;
+            // A residual left by limited participation trades like a market order
+            order.order_type = OrderType::Market;
+            Some(Execution::Market(triggered))
+        }
+        OrderType::StopLimit => {
+            let triggered = stop_trigger_price(order.side, stop, bar)?;
+            order.order_type = OrderType::Limit;
+            let within_limit = match order.side {
+                Side::Buy => triggered <= limit,
+                Side::Sell => triggered >= limit,
+            };
+            // Otherwise, which came first within the bar is unknown, so the
+            // limit only starts working from the next bar
+            within_limit.then_some(Execution::Limit(triggered))
+        }
+    }
+}
+
+/// Step of `path` at which `order` trades, and its price there. A stop-limit
+/// order's limit works from the step its stop triggers on.
+fn path_execution(
+    order: &mut Order,
+    path: &IntrabarPath,
+    market_price: f64,
+) -> Option<(usize, Execution)> {
+    let limit = order.limit_price.unwrap_or_default();
+    let stop = order.stop_price.unwrap_or_default();
+    let limit_fill = |from| {
+        path.limit_fill(order.side, limit, from)
+            .map(|(step, price)| (step, Execution::Limit(price)))
+    };
+    match order.order_type {
+        OrderType::Market => Some((0, Execution::Market(market_price))),
+        OrderType::Limit => limit_fill(0),
+        OrderType::Stop => {
+            let (step, price) = path.stop_trigger(order.side, stop)?;
+            order.order_type = OrderType::Market;
+            Some((step, Execution::Market(price)))
+        }
+        OrderType::StopLimit => {
+            let (trigger, _) = path.stop_trigger(order.side, stop)?;
+            let fill = limit_fill(trigger);
+            order.order_type = OrderType::Limit;
+            fill
+        }
+    }
+}
+
+/// Price a resting limit order fills at on `bar`, if the bar reaches it.
+///
+/// A bar that gaps through the limit fills at its open, the better price.
+fn limit_fill_price(side: Side, limit: f64, bar: &Bar) -> Option<f64> {
+    match side {
+        Side::Buy => (bar.low <= limit).then(|| limit.min(bar.open)),
+        Side::Sell => (bar.high >= limit).then(|| limit.max(bar.open)),
+    }
+}
+
+/// Price a stop triggers at on `bar`, if the bar reaches it.
+///
+/// A bar that gaps through the stop triggers at its open, the worse price.
+fn stop_trigger_price(side: Side, stop: f64, bar: &Bar) -> Option<f64> {
+    match side {
+        Side::Buy => (bar.high >= stop).then(|| stop.max(bar.open)),
+        Side::Sell => (bar.low <= stop).then(|| stop.min(bar.open)),
+    }
+}
+
+/// Commission and fees on a fill at a quoted price, which has no slippage
+pub(crate) fn resting_costs<C: CostModel>(
+    cost_model: &C,
+    quantity: f64,
+    price: f64,
+    market: &MarketContext,
+) -> CostBreakdown {
+    CostBreakdown {
+        commission: cost_model.calculate_commission(quantity, price, market),
+        exchange_fees: cost_model.calculate_exchange_fees(quantity, price, market),
+        ..CostBreakdown::default()
+    }
+}
+
+/// Check that a resting order carries the prices its type needs
+pub(crate) fn validate_prices(order: &Order) -> Result<()> {
+    let check = |name: &str, price: Option<f64>| match price {
+        Some(p) if p.is_finite() && p > 0.0 => Ok(()),
+        _ => Err(anyhow::anyhow!(
+            "{:?} order for {} needs a positive {} price, got {:?}",
+            order.order_type,
+            order.symbol,
+            name,
+            price
+        )),
+    };
+    match order.order_type {
+        OrderType::Market => {}
+        OrderType::Limit => check("limit", order.limit_price)?,
+        OrderType::Stop => check("stop", order.stop_price)?,
+        OrderType::StopLimit => {
+            check("stop", order.stop_price)?;
+            check("limit", order.limit_price)?;
+        }
+    }
+    match &order.bracket {
+        Some(exits) => bracket::validate(order, exits),
+        None => Ok(()),
+    }
+}
+
+impl<C: CostModel> BrokerSim for SimpleBroker<C> {
+    fn process_orders(&mut self, orders: Vec<Order>, bar: &Bar) -> Result<Vec<Fill>> {
+        Ok(self.process(orders, bar, None)?.fills)
+    }
+
+    /// Orders waiting to be triggered or filled; a partially filled order
+    /// appears with its residual quantity
+    fn open_orders(&self) -> Vec<Order> {
+        self.open_orders.clone()
+    }
+
+    /// Cancel a resting or in-flight order
+    fn cancel(&mut self, order_id: u64) -> Result<Order> {
+        if let Some(i) = self
+            .open_orders
+            .iter()
+            .position(|o| o.order_id == Some(order_id))
+        {
+            let order = self.open_orders.remove(i);
+            self.prune_oco();
+            self.log(
+                &order,
+                OrderEventKind::Cancelled {
+                    remaining: order.quantity,
+                },
+            );
+            return Ok(order);
+        }
+        match self
+            .in_flight
+            .iter()
+            .position(|f| f.order.order_id == Some(order_id))
+        {
+            Some(i) => {
+                let order = self.in_flight.remove(i).order;
+                self.log(
+                    &order,
+                    OrderEventKind::Cancelled {
+                        remaining: order.quantity,
+                    },
+                );
+                Ok(order)
+            }
+            None => anyhow::bail!("No open order with id {}", order_id),
+        }
+    }
+
+    fn cancel_all(&mut self) -> Result<Vec<Order>> {
+        Ok(self.cancel_matching(|_| true))
+    }
+
+    fn amend(&mut self, order_id: u64, amendment: &OrderAmendment) -> Result<()> {
+        let instruments = self.instruments.clone();
+        let order = self
+            .find_mut(order_id)
+            .ok_or_else(|| anyhow::anyhow!("No open order with id {}", order_id))?;
+        let mut amended = order.clone();
+        if let Some(quantity) = amendment.quantity {
+            if !(quantity.is_finite() && quantity > 0.0) {
+                anyhow::bail!(
+                    "Order {} needs a positive quantity, got {}",
+                    order_id,
+                    quantity
+                );
+            }
+            amended.quantity = quantity;
+        }
+        amended.limit_price = amendment.limit_price.or(amended.limit_price);
+        amended.stop_price = amendment.stop_price.or(amended.stop_price);
+        validate_prices(&amended)?;
+        if let Some(instruments) = instruments {
+            if let Err(reason) = instruments.conform(&mut amended) {
+                anyhow::bail!("Order {} amendment is refused: {:?}", order_id, reason);
+            }
+        }
+        *order = amended;
+        Ok(())
+    }
+
+    fn process_orders_for(
+        &mut self,
+        orders: Vec<Order>,
+        bar: &Bar,
+        portfolio: &Portfolio,
+    ) -> Result<BrokerResult> {
+        self.process(orders, bar, Some(portfolio))
     }
 
     fn name(&self) -> &str {
@@ -73,13 +1132,25 @@ impl<C: CostModel> BrokerSim for SimpleBroker<C> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use schema::{Bracket, TradingState};
 
     struct ZeroCost;
     impl CostModel for ZeroCost {
-        fn calculate_commission(&self, _quantity: f64, _price: f64) -> f64 {
+        fn calculate_commission(
+            &self,
+            _quantity: f64,
+            _price: f64,
+            _market: &MarketContext,
+        ) -> f64 {
             0.0
         }
-        fn calculate_slippage(&self, _quantity: f64, _price: f64, _side: Side) -> f64 {
+        fn calculate_slippage(
+            &self,
+            _quantity: f64,
+            _price: f64,
+            _side: Side,
+            _market: &MarketContext,
+        ) -> f64 {
             0.0
         }
     }
@@ -104,6 +1175,9 @@ mod tests {
             quantity: 10.0,
             order_type: OrderType::Market,
             limit_price: None,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
         }];
 
         let fills = broker.process_orders(orders, &bar).unwrap();
@@ -112,7 +1186,86 @@ mod tests {
         assert_eq!(fills[0].symbol, "AAPL");
         assert_eq!(fills[0].quantity, 10.0);
         assert_eq!(fills[0].price, 101.0);
-        assert_eq!(fills[0].commission, 0.0);
+        assert_eq!(fills[0].costs, CostBreakdown::default());
+    }
+
+    #[test]
+    fn test_fill_costs_are_broken_down_by_kind() {
+        struct Costly;
+        impl CostModel for Costly {
+            fn calculate_commission(&self, quantity: f64, _price: f64, _: &MarketContext) -> f64 {
+                quantity * 0.01
+            }
+            fn calculate_slippage(&self, _: f64, _: f64, _: Side, _: &MarketContext) -> f64 {
+                0.05
+            }
+            fn calculate_exchange_fees(&self, _: f64, price: f64, _: &MarketContext) -> f64 {
+                price * 0.001
+            }
+        }
+        let mut broker = SimpleBroker::new(Costly, 42);
+        let market = Order {
+            quantity: 100.0,
+            order_type: OrderType::Market,
+            limit_price: None,
+            ..limit(Side::Sell, 0.0)
+        };
+        let fills = broker
+            .process_orders(vec![market], &bar(1, 100.0, 101.0, 99.0, 100.0))
+            .unwrap();
+        // Slippage is in the price; the rest is charged on the quoted close
+        assert!((fills[0].price - 99.95).abs() < 1e-12);
+        let costs = fills[0].costs;
+        assert!((costs.commission - 1.0).abs() < 1e-12);
+        assert!((costs.exchange_fees - 0.1).abs() < 1e-12);
+        assert!((costs.slippage - 5.0).abs() < 1e-12);
+        assert!((costs.charged() - 1.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fill_events_carry_the_slipped_price() {
+        struct Slippy;
+        impl CostModel for Slippy {
+            fn calculate_commission(&self, _: f64, _: f64, _: &MarketContext) -> f64 {
+                0.0
+            }
+            fn calculate_slippage(&self, _: f64, _: f64, _: Side, _: &MarketContext) -> f64 {
+                0.05
+            }
+        }
+        let mut broker = SimpleBroker::new(Slippy, 42).with_max_participation(0.5);
+        let market = Order {
+            quantity: 8000.0,
+            order_type: OrderType::Market,
+            limit_price: None,
+            ..limit(Side::Buy, 0.0)
+        };
+        let mut events = Vec::new();
+        let mut fills = Vec::new();
+        for (i, orders) in [vec![market], vec![]].into_iter().enumerate() {
+            let result = broker
+                .process_orders_for(
+                    orders,
+                    &bar(i as i64 + 1, 100.0, 101.0, 99.0, 100.0),
+                    &Portfolio::new(100_000.0),
+                )
+                .unwrap();
+            fills.extend(result.fills.into_iter().map(|f| f.price));
+            events.extend(
+                result
+                    .order_events
+                    .into_iter()
+                    .filter_map(|e| match e.kind {
+                        OrderEventKind::PartiallyFilled { price, .. } => Some(price),
+                        OrderEventKind::Filled { price, .. } => Some(price),
+                        _ => None,
+                    }),
+            );
+        }
+        // A partial fill on the first bar and the rest on the second
+        assert_eq!(fills.len(), 2);
+        assert!(fills.iter().all(|price| (price - 100.05).abs() < 1e-12));
+        assert_eq!(events, fills);
     }
 
     #[test]
@@ -133,6 +1286,9 @@ mod tests {
             quantity: 10.0,
             order_type: OrderType::Market,
             limit_price: None,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
         }];
 
         // Run the same simulation twice with the same seed
@@ -147,7 +1303,790 @@ mod tests {
         for (f1, f2) in fills1.iter().zip(fills2.iter()) {
             assert_eq!(f1.price, f2.price);
             assert_eq!(f1.quantity, f2.quantity);
-            assert_eq!(f1.commission, f2.commission);
+            assert_eq!(f1.costs, f2.costs);
         }
     }
+
+    fn bar(timestamp: i64, open: f64, high: f64, low: f64, close: f64) -> Bar {
+        Bar {
+            timestamp,
+            symbol: "AAPL".to_string(),
+            open,
+            high,
+            low,
+            close,
+            volume: 10000.0,
+        }
+    }
+
+    fn limit(side: Side, price: f64) -> Order {
+        Order {
+            symbol: "AAPL".to_string(),
+            side,
+            quantity: 10.0,
+            order_type: OrderType::Limit,
+            limit_price: Some(price),
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        }
+    }
+
+    #[test]
+    fn test_limit_orders_rest_until_touched() {
+        let mut broker = SimpleBroker::new(ZeroCost, 42);
+        let orders = vec![limit(Side::Buy, 98.0), limit(Side::Sell, 105.0)];
+
+        // Submitted on this bar, so not filled against it
+        let fills = broker
+            .process_orders(orders, &bar(1, 100.0, 106.0, 97.0, 101.0))
+            .unwrap();
+        assert!(fills.is_empty());
+        assert_eq!(broker.open_orders().len(), 2);
+
+        // Buy limit touched within the range fills at the limit
+        let fills = broker
+            .process_orders(vec![], &bar(2, 100.0, 101.0, 97.5, 99.0))
+            .unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].side, fills[0].price), (Side::Buy, 98.0));
+
+        // Sell limit gapped through fills at the better open
+        let other = Bar {
+            symbol: "MSFT".to_string(),
+            ..bar(3, 200.0, 210.0, 190.0, 200.0)
+        };
+        assert!(broker.process_orders(vec![], &other).unwrap().is_empty());
+        let fills = broker
+            .process_orders(vec![], &bar(4, 107.0, 108.0, 104.0, 106.0))
+            .unwrap();
+        assert_eq!((fills[0].side, fills[0].price), (Side::Sell, 107.0));
+        assert!(broker.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_limit_orders_can_be_cancelled() {
+        let mut broker = SimpleBroker::new(ZeroCost, 42);
+        let flat = bar(1, 100.0, 101.0, 99.0, 100.0);
+        broker
+            .process_orders(vec![limit(Side::Buy, 90.0)], &flat)
+            .unwrap();
+        let cancelled = broker.cancel_orders("AAPL");
+        assert_eq!(cancelled[0].limit_price, Some(90.0));
+        let fills = broker
+            .process_orders(vec![], &bar(2, 90.0, 91.0, 80.0, 85.0))
+            .unwrap();
+        assert!(fills.is_empty());
+
+        let mut missing = limit(Side::Buy, 90.0);
+        missing.order_type = OrderType::StopLimit;
+        missing.stop_price = Some(95.0);
+        assert!(broker.process_orders(vec![missing.clone()], &flat).is_ok());
+        missing.limit_price = None;
+        assert!(broker.process_orders(vec![missing], &flat).is_err());
+    }
+
+    #[test]
+    fn test_stop_orders_trigger_on_the_bar_range() {
+        let mut broker = SimpleBroker::new(ZeroCost, 42);
+        let stop_loss = Order {
+            order_type: OrderType::Stop,
+            limit_price: None,
+            stop_price: Some(95.0),
+            order_id: None,
+            bracket: None,
+            ..limit(Side::Sell, 1.0)
+        };
+        let stop_limit = Order {
+            order_type: OrderType::StopLimit,
+            stop_price: Some(105.0),
+            order_id: None,
+            bracket: None,
+            ..limit(Side::Buy, 106.0)
+        };
+        broker
+            .process_orders(
+                vec![stop_loss, stop_limit],
+                &bar(1, 100.0, 101.0, 99.0, 100.0),
+            )
+            .unwrap();
+
+        // The sell stop fills at its stop; the buy stop-limit gaps through
+        // its limit at the open and becomes a limit order
+        let fills = broker
+            .process_orders(vec![], &bar(2, 107.0, 108.0, 94.0, 100.0))
+            .unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].side, fills[0].price), (Side::Sell, 95.0));
+        assert_eq!(broker.open_orders()[0].order_type, OrderType::Limit);
+
+        let fills = broker
+            .process_orders(vec![], &bar(3, 109.0, 110.0, 105.5, 107.0))
+            .unwrap();
+        assert_eq!((fills[0].side, fills[0].price), (Side::Buy, 106.0));
+        assert!(broker.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_max_participation_splits_large_orders() {
+        let mut broker = SimpleBroker::new(ZeroCost, 42).with_max_participation(0.1);
+        let order = |quantity| Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            quantity,
+            ..limit(Side::Buy, 1.0)
+        };
+
+        // 10% of 10,000 shares caps the bar at 1,000 across both orders
+        let fills = broker
+            .process_orders(
+                vec![order(2_500.0), order(100.0)],
+                &bar(1, 100.0, 102.0, 99.0, 101.0),
+            )
+            .unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].quantity, 1_000.0);
+        let residuals: Vec<f64> = broker.open_orders().iter().map(|o| o.quantity).collect();
+        assert_eq!(residuals, [1_500.0, 100.0]);
+
+        // Residuals fill at later closes in submission order
+        let fills = broker
+            .process_orders(vec![], &bar(2, 101.0, 103.0, 100.0, 102.0))
+            .unwrap();
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].quantity, fills[0].price), (1_000.0, 102.0));
+        let fills = broker
+            .process_orders(vec![], &bar(3, 102.0, 104.0, 101.0, 103.0))
+            .unwrap();
+        let filled: Vec<f64> = fills.iter().map(|f| f.quantity).collect();
+        assert_eq!(filled, [500.0, 100.0]);
+        assert!(broker.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_next_open_policy_fills_at_the_following_open() {
+        let mut broker = SimpleBroker::new(ZeroCost, 42).with_fill_policy(FillPolicy::NextOpen);
+        let order = Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            ..limit(Side::Buy, 1.0)
+        };
+        let fills = broker
+            .process_orders(vec![order], &bar(1, 100.0, 102.0, 99.0, 101.0))
+            .unwrap();
+        assert!(fills.is_empty());
+
+        let fills = broker
+            .process_orders(vec![], &bar(2, 101.5, 103.0, 100.0, 102.0))
+            .unwrap();
+        assert_eq!((fills[0].timestamp, fills[0].price), (2, 101.5));
+        assert!(broker.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_latency_delays_orders_deterministically() {
+        let market = Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            ..limit(Side::Buy, 1.0)
+        };
+        let mut broker = SimpleBroker::new(ZeroCost, 42).with_latency(Latency::Bars(2));
+        let bars: Vec<Bar> = (1..=3)
+            .map(|t| bar(t, 100.0, 101.0, 99.0, 100.0 + t as f64))
+            .collect();
+        assert!(broker
+            .process_orders(vec![market.clone()], &bars[0])
+            .unwrap()
+            .is_empty());
+        assert!(broker.process_orders(vec![], &bars[1]).unwrap().is_empty());
+        assert_eq!(broker.in_flight_orders().count(), 1);
+        let fills = broker.process_orders(vec![], &bars[2]).unwrap();
+        assert_eq!((fills[0].timestamp, fills[0].price), (3, 103.0));
+
+        // Jittered millisecond delays repeat for the same seed
+        let arrival_bars = |seed| {
+            let mut broker = SimpleBroker::new(ZeroCost, seed).with_latency(Latency::Millis {
+                base: 500,
+                jitter: 3_000,
+            });
+            let mut arrived = Vec::new();
+            for t in 0..20 {
+                let orders = if t < 5 { vec![market.clone()] } else { vec![] };
+                let fills = broker
+                    .process_orders(orders, &bar(t, 1.0, 1.0, 1.0, 1.0))
+                    .unwrap();
+                arrived.extend(fills.iter().map(|f| f.timestamp));
+            }
+            arrived
+        };
+        let arrived = arrival_bars(7);
+        assert_eq!(arrived.len(), 5);
+        assert!(arrived
+            .iter()
+            .zip(0..)
+            .all(|(&t, sent)| t > sent && t <= sent + 4));
+        assert_eq!(arrived, arrival_bars(7));
+    }
+
+    #[test]
+    fn test_orders_are_cancelled_and_amended_by_id() {
+        let mut broker = SimpleBroker::new(ZeroCost, 42);
+        let flat = bar(1, 100.0, 101.0, 99.0, 100.0);
+        let mut own_id = limit(Side::Sell, 110.0);
+        own_id.order_id = Some(7);
+        broker
+            .process_orders(
+                vec![
+                    limit(Side::Buy, 90.0),
+                    own_id.clone(),
+                    limit(Side::Buy, 95.0),
+                ],
+                &flat,
+            )
+            .unwrap();
+        let ids: Vec<Option<u64>> = broker.open_orders().iter().map(|o| o.order_id).collect();
+        assert_eq!(ids, [Some(1), Some(7), Some(8)]);
+        assert!(broker.process_orders(vec![own_id], &flat).is_err());
+
+        assert_eq!(broker.cancel(7).unwrap().limit_price, Some(110.0));
+        assert!(broker.cancel(7).is_err());
+        let raise = OrderAmendment {
+            quantity: Some(4.0),
+            limit_price: Some(99.5),
+            ..Default::default()
+        };
+        broker.amend(1, &raise).unwrap();
+        let bad = OrderAmendment {
+            quantity: Some(0.0),
+            ..Default::default()
+        };
+        assert!(broker.amend(8, &bad).is_err());
+
+        let fills = broker
+            .process_orders(vec![], &bar(2, 100.0, 100.5, 99.0, 100.0))
+            .unwrap();
+        assert_eq!((fills[0].quantity, fills[0].price), (4.0, 99.5));
+        assert_eq!(broker.open_orders()[0].order_id, Some(8));
+    }
+
+    #[test]
+    fn test_bracket_legs_cancel_each_other() {
+        let entry = |bracket| Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            bracket: Some(bracket),
+            ..limit(Side::Buy, 1.0)
+        };
+        let exits = Bracket {
+            take_profit: 110.0,
+            stop_loss: 95.0,
+        };
+        let run = |next: Bar| {
+            let mut broker = SimpleBroker::new(ZeroCost, 42);
+            broker
+                .process_orders(vec![entry(exits)], &bar(1, 100.0, 101.0, 99.0, 100.0))
+                .unwrap();
+            let legs: Vec<OrderType> = broker.open_orders().iter().map(|o| o.order_type).collect();
+            assert_eq!(legs, [OrderType::Limit, OrderType::Stop]);
+            let fills = broker.process_orders(vec![], &next).unwrap();
+            assert!(broker.open_orders().is_empty());
+            fills.iter().map(|f| (f.side, f.price)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            run(bar(2, 100.0, 111.0, 99.0, 105.0)),
+            [(Side::Sell, 110.0)]
+        );
+        // Both legs in range: the stop is assumed to trade first...
+        assert_eq!(run(bar(2, 100.0, 111.0, 94.0, 105.0)), [(Side::Sell, 95.0)]);
+        // ...unless the bar opened through the take-profit
+        assert_eq!(
+            run(bar(2, 112.0, 113.0, 94.0, 100.0)),
+            [(Side::Sell, 112.0)]
+        );
+
+        let inverted = Bracket {
+            take_profit: 90.0,
+            stop_loss: 95.0,
+        };
+        let mut broker = SimpleBroker::new(ZeroCost, 42);
+        let flat = bar(1, 100.0, 101.0, 99.0, 100.0);
+        assert!(broker.process_orders(vec![entry(inverted)], &flat).is_err());
+    }
+
+    #[test]
+    fn test_twap_works_market_orders_over_several_bars() {
+        let mut broker = SimpleBroker::new(ZeroCost, 42).with_execution_algo(ExecutionAlgo {
+            kind: AlgoKind::Twap,
+            bars: 3,
+        });
+        let parent = Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            quantity: 30.0,
+            ..limit(Side::Buy, 1.0)
+        };
+        let mut fills = Vec::new();
+        for t in 1..=4 {
+            let orders = if t == 1 { vec![parent.clone()] } else { vec![] };
+            let close = 100.0 + t as f64;
+            fills.extend(
+                broker
+                    .process_orders(orders, &bar(t, close, close, close, close))
+                    .unwrap(),
+            );
+        }
+        let slices: Vec<(i64, f64)> = fills.iter().map(|f| (f.timestamp, f.quantity)).collect();
+        assert_eq!(slices, [(1, 10.0), (2, 10.0), (3, 10.0)]);
+
+        let report = broker.algo_reports().next().unwrap();
+        assert_eq!((report.filled, report.slices), (30.0, 3));
+        assert!((report.average_price - 102.0).abs() < 1e-9);
+        assert!(broker.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_vwap_slices_follow_bar_volume() {
+        let slices = |volumes: [f64; 3]| {
+            let mut broker = SimpleBroker::new(ZeroCost, 42).with_execution_algo(ExecutionAlgo {
+                kind: AlgoKind::Vwap,
+                bars: 3,
+            });
+            let parent = Order {
+                order_type: OrderType::Market,
+                limit_price: None,
+                quantity: 300.0,
+                ..limit(Side::Buy, 1.0)
+            };
+            let mut fills = Vec::new();
+            for (t, volume) in (1..).zip(volumes) {
+                let orders = if t == 1 { vec![parent.clone()] } else { vec![] };
+                let bar = Bar {
+                    volume,
+                    ..bar(t, 100.0, 100.0, 100.0, 100.0)
+                };
+                fills.extend(broker.process_orders(orders, &bar).unwrap());
+            }
+            fills.iter().map(|f| f.quantity).collect::<Vec<f64>>()
+        };
+        let assert_slices = |actual: Vec<f64>, expected: [f64; 3]| {
+            assert_eq!(actual.len(), 3);
+            for (a, e) in actual.iter().zip(expected) {
+                assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+            }
+        };
+
+        // The second bar trades 1.5x the average volume so far, so takes
+        // 1.5 shares of the two bars left against the last bar's one
+        assert_slices(slices([1000.0, 3000.0, 1000.0]), [100.0, 120.0, 80.0]);
+        // At two thirds of the average it takes less than an even share
+        assert_slices(slices([1000.0, 500.0, 1000.0]), [100.0, 80.0, 120.0]);
+    }
+
+    #[test]
+    fn test_shorts_need_a_locate_and_pay_borrow_fees() {
+        let shorts = ShortAvailability::new(0.36)
+            .easy_to_borrow(["AAPL"])
+            .hard_to_borrow(
+                "GME",
+                HardToBorrow {
+                    fee_rate: 0.72,
+                    locate_probability: 0.0,
+                },
+            );
+        let mut broker = SimpleBroker::new(ZeroCost, 42).with_short_availability(shorts);
+        let mut portfolio = Portfolio::new(10_000.0);
+        portfolio.get_position_mut("MSFT").quantity = 10.0;
+        let sell = |symbol: &str| Order {
+            symbol: symbol.to_string(),
+            order_type: OrderType::Market,
+            limit_price: None,
+            ..limit(Side::Sell, 1.0)
+        };
+        let on = |symbol: &str, timestamp| Bar {
+            symbol: symbol.to_string(),
+            ..bar(timestamp, 100.0, 100.0, 100.0, 100.0)
+        };
+
+        let mut reasons = Vec::new();
+        for symbol in ["AAPL", "MSFT", "GME", "TSLA"] {
+            let result = broker
+                .process_orders_for(vec![sell(symbol)], &on(symbol, 0), &portfolio)
+                .unwrap();
+            reasons.extend(result.rejections.into_iter().map(|r| r.reason));
+        }
+        // Selling a long needs no locate
+        assert_eq!(
+            reasons,
+            [
+                RejectionReason::LocateFailed {
+                    hard_to_borrow: true
+                },
+                RejectionReason::LocateFailed {
+                    hard_to_borrow: false
+                },
+            ]
+        );
+
+        // 100 shares short at $100 for two days at 36% a year, actual/360
+        portfolio.get_position_mut("AAPL").quantity = -100.0;
+        let result = broker
+            .process_orders_for(vec![], &on("AAPL", 2 * SECONDS_PER_DAY), &portfolio)
+            .unwrap();
+        let charge = &result.borrow_charges[0];
+        assert_eq!((charge.days, charge.quantity), (2, 100.0));
+        assert!((charge.fee - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_orders_trade_in_whole_lots_on_the_tick() {
+        let rules = InstrumentRules {
+            lot_size: Some(1.0),
+            tick_size: Some(0.05),
+        };
+        let mut broker = SimpleBroker::new(ZeroCost, 42)
+            .with_instruments(InstrumentTable::new(rules, Rounding::Snap))
+            .with_max_participation(0.00025);
+        let market = |quantity| Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            quantity,
+            ..limit(Side::Buy, 1.0)
+        };
+        let resting = Order {
+            quantity: 1.0,
+            ..limit(Side::Buy, 99.07)
+        };
+
+        // 3.71 shares trade as 3, two a bar under a 2.5-share cap
+        let result = broker
+            .process_orders_for(
+                vec![market(3.71), market(0.4), resting],
+                &bar(1, 100.0, 100.0, 100.0, 100.0),
+                &Portfolio::new(10_000.0),
+            )
+            .unwrap();
+        assert_eq!(result.fills[0].quantity, 2.0);
+        assert_eq!(
+            result.rejections[0].reason,
+            RejectionReason::OffLot { lot_size: 1.0 }
+        );
+        let open: Vec<(f64, Option<f64>)> = broker
+            .open_orders()
+            .iter()
+            .map(|o| (o.quantity, o.limit_price))
+            .collect();
+        assert_eq!(open.len(), 2);
+        assert_eq!(open[0].0, 1.0);
+        assert!((open[1].1.unwrap() - 99.05).abs() < 1e-9);
+        let fills = broker
+            .process_orders(vec![], &bar(2, 101.0, 101.0, 101.0, 101.0))
+            .unwrap();
+        assert_eq!(fills[0].quantity, 1.0);
+
+        let id = broker.open_orders()[0].order_id.unwrap();
+        let amendment = OrderAmendment {
+            quantity: Some(2.6),
+            ..OrderAmendment::default()
+        };
+        broker.amend(id, &amendment).unwrap();
+        assert_eq!(broker.open_orders()[0].quantity, 2.0);
+    }
+
+    #[test]
+    fn test_halts_queue_or_cancel_orders_until_trading_resumes() {
+        let halts = TradingHalts::new()
+            .with_state("AAPL", 1, TradingState::Halted)
+            .with_state("AAPL", 2, TradingState::LimitDown)
+            .with_state("AAPL", 3, TradingState::Trading);
+        let market = |side| Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            ..limit(side, 1.0)
+        };
+        let bars: Vec<Bar> = (1..=3)
+            .map(|t| bar(t, 100.0, 100.0, 100.0, 100.0 + t as f64))
+            .collect();
+
+        // Queued orders wait out the halt; sells also wait out limit-down
+        let mut broker =
+            SimpleBroker::new(ZeroCost, 42).with_halts(halts.clone(), HaltPolicy::Queue);
+        let orders = vec![market(Side::Buy), market(Side::Sell)];
+        assert!(broker.process_orders(orders, &bars[0]).unwrap().is_empty());
+        let fills = broker.process_orders(vec![], &bars[1]).unwrap();
+        assert_eq!((fills.len(), fills[0].side), (1, Side::Buy));
+        let fills = broker.process_orders(vec![], &bars[2]).unwrap();
+        assert_eq!((fills[0].side, fills[0].price), (Side::Sell, 103.0));
+
+        let mut broker = SimpleBroker::new(ZeroCost, 42).with_halts(halts, HaltPolicy::Cancel);
+        let result = broker
+            .process_orders_for(vec![market(Side::Buy)], &bars[0], &Portfolio::new(10_000.0))
+            .unwrap();
+        assert!(result.fills.is_empty() && broker.open_orders().is_empty());
+        assert_eq!(
+            result.order_events.last().unwrap().kind,
+            OrderEventKind::Cancelled { remaining: 10.0 }
+        );
+    }
+
+    #[test]
+    fn test_foreign_currency_fills_pay_the_conversion_spread() {
+        let fx = FxConversion::new("USD")
+            .with_spread("EUR", 0.001)
+            .with_symbol("SAP", "EUR");
+        let mut broker = SimpleBroker::new(ZeroCost, 42).with_fx_conversion(fx);
+        let buy = |symbol: &str| Order {
+            symbol: symbol.to_string(),
+            ..limit(Side::Buy, 100.0)
+        };
+        let on = |symbol: &str| Bar {
+            symbol: symbol.to_string(),
+            ..bar(1, 100.0, 100.0, 99.0, 100.0)
+        };
+        broker
+            .process_orders(vec![buy("SAP"), buy("AAPL")], &on("SAP"))
+            .unwrap();
+        let sap = broker.process_orders(vec![], &on("SAP")).unwrap();
+        let aapl = broker.process_orders(vec![], &on("AAPL")).unwrap();
+        // $1,000 converted at 0.1% worse than mid
+        assert!((sap[0].costs.other - 1.0).abs() < 1e-12);
+        assert_eq!(aapl[0].costs.other, 0.0);
+    }
+
+    #[test]
+    fn test_perpetuals_exchange_funding_at_the_previous_close() {
+        let hours = |h: i64| h * 3_600;
+        let funding =
+            FundingSchedule::new(hours(8)).perpetual("BTC-PERP", FundingRate::Fixed(0.0001));
+        let mut broker = SimpleBroker::new(ZeroCost, 42).with_funding(funding);
+        let mut portfolio = Portfolio::new(10_000.0);
+        portfolio.get_position_mut("BTC-PERP").quantity = -2.0;
+        let on = |timestamp, close| Bar {
+            symbol: "BTC-PERP".to_string(),
+            ..bar(timestamp, close, close, close, close)
+        };
+
+        let mut paid = Vec::new();
+        for (timestamp, close) in [(hours(7), 50_000.0), (hours(9), 60_000.0), (hours(25), 0.0)] {
+            let result = broker
+                .process_orders_for(vec![], &on(timestamp, close), &portfolio)
+                .unwrap();
+            paid.extend(
+                result
+                    .funding_payments
+                    .iter()
+                    .map(|p| (p.timestamp, p.amount)),
+            );
+        }
+        // The short receives at 08:00 on $50,000, then at 16:00 and 24:00 on $60,000
+        assert_eq!(
+            paid,
+            [(hours(8), -10.0), (hours(16), -12.0), (hours(24), -12.0)]
+        );
+    }
+
+    #[test]
+    fn test_margin_calls_liquidate_until_maintenance_is_met() {
+        let margin = MarginSchedule::new(MarginRequirement {
+            initial: 0.5,
+            maintenance: 0.25,
+        });
+        let mut broker = SimpleBroker::new(ZeroCost, 42).with_margin(margin.clone());
+        // 200 shares bought at $100 on $10,000 of borrowed cash
+        let mut portfolio = Portfolio::new(-10_000.0);
+        let position = portfolio.get_position_mut("AAPL");
+        position.quantity = 200.0;
+        position.avg_price = 100.0;
+
+        // At $60: $2,000 of equity against $3,000 of maintenance
+        let crash = bar(1, 60.0, 60.0, 60.0, 60.0);
+        let add = Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            ..limit(Side::Buy, 1.0)
+        };
+        let result = broker
+            .process_orders_for(vec![add], &crash, &portfolio)
+            .unwrap();
+        assert_eq!(result.margin_calls[0].maintenance_required, 3_000.0);
+        let liquidated: Vec<(Side, f64)> =
+            result.fills.iter().map(|f| (f.side, f.quantity)).collect();
+        assert_eq!(liquidated, [(Side::Sell, 67.0)]);
+        assert!(matches!(
+            result.rejections[0].reason,
+            RejectionReason::InsufficientBuyingPower { .. }
+        ));
+
+        portfolio.cash += 67.0 * 60.0;
+        portfolio.get_position_mut("AAPL").quantity -= 67.0;
+        let marks = HashMap::from([("AAPL".to_string(), 60.0)]);
+        assert!(!margin.status(&portfolio, &marks).margin_call());
+        let result = broker
+            .process_orders_for(vec![], &bar(2, 60.0, 60.0, 60.0, 60.0), &portfolio)
+            .unwrap();
+        assert!(result.margin_calls.is_empty() && result.fills.is_empty());
+    }
+
+    #[test]
+    fn test_resting_liquidations_are_not_issued_twice() {
+        let margin = MarginSchedule::new(MarginRequirement {
+            initial: 0.5,
+            maintenance: 0.25,
+        });
+        let mut broker = SimpleBroker::new(ZeroCost, 42)
+            .with_margin(margin)
+            .with_fill_policy(FillPolicy::NextOpen);
+        let mut portfolio = Portfolio::new(-10_000.0);
+        let position = portfolio.get_position_mut("AAPL");
+        position.quantity = 200.0;
+        position.avg_price = 100.0;
+
+        // The liquidation rests for the next open, so the call is still
+        // open on the next bar but the pending sale already covers it
+        let crash = bar(1, 60.0, 60.0, 60.0, 60.0);
+        let result = broker
+            .process_orders_for(vec![], &crash, &portfolio)
+            .unwrap();
+        assert_eq!(result.margin_calls.len(), 1);
+        assert!(result.fills.is_empty());
+        assert_eq!(broker.open_orders()[0].quantity, 67.0);
+
+        let result = broker
+            .process_orders_for(vec![], &bar(2, 60.0, 60.0, 60.0, 60.0), &portfolio)
+            .unwrap();
+        assert_eq!(result.margin_calls.len(), 1);
+        let liquidated: Vec<(Side, f64)> =
+            result.fills.iter().map(|f| (f.side, f.quantity)).collect();
+        assert_eq!(liquidated, [(Side::Sell, 67.0)]);
+        assert!(broker.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_intrabar_paths_decide_which_leg_trades_first() {
+        let entry = Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            bracket: Some(Bracket {
+                take_profit: 110.0,
+                stop_loss: 95.0,
+            }),
+            ..limit(Side::Buy, 1.0)
+        };
+        let exit_price = |seed| {
+            let mut broker = SimpleBroker::new(ZeroCost, seed).with_intrabar_path(64);
+            broker
+                .process_orders(vec![entry.clone()], &bar(1, 100.0, 101.0, 99.0, 100.0))
+                .unwrap();
+            let fills = broker
+                .process_orders(vec![], &bar(2, 100.0, 111.0, 94.0, 105.0))
+                .unwrap();
+            assert_eq!(fills.len(), 1);
+            assert!(broker.open_orders().is_empty());
+            fills[0].price
+        };
+        let exits: Vec<f64> = (0..16).map(exit_price).collect();
+        // Either leg can win, and a stop fills where the path crossed it
+        assert!(exits.contains(&110.0));
+        assert!(exits.iter().any(|&p| (94.0..=95.0).contains(&p)));
+        assert_eq!(exits, (0..16).map(exit_price).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_every_order_step_is_logged() {
+        let mut broker = SimpleBroker::new(ZeroCost, 42)
+            .with_max_participation(0.1)
+            .with_margin_multiplier(1.0);
+        let market = |quantity| Order {
+            order_type: OrderType::Market,
+            limit_price: None,
+            quantity,
+            ..limit(Side::Buy, 1.0)
+        };
+        let step = |broker: &mut SimpleBroker<ZeroCost>, orders, bar: &Bar, cash| {
+            let result = broker
+                .process_orders_for(orders, bar, &Portfolio::new(cash))
+                .unwrap();
+            let events: Vec<(i64, u64, OrderEventKind)> = result
+                .order_events
+                .into_iter()
+                .map(|e| (e.timestamp, e.order_id, e.kind))
+                .collect();
+            (events, broker.open_orders())
+        };
+
+        let resting = limit(Side::Sell, 110.0);
+        let (events, _) = step(
+            &mut broker,
+            vec![market(1_500.0), resting.clone()],
+            &bar(1, 100.0, 102.0, 99.0, 101.0),
+            200_000.0,
+        );
+        let submitted = |id| OrderEventKind::Submitted {
+            order: Order {
+                order_id: Some(id),
+                ..if id == 1 {
+                    market(1_500.0)
+                } else {
+                    resting.clone()
+                }
+            },
+        };
+        assert_eq!(
+            events,
+            [
+                (1, 1, submitted(1)),
+                (1, 2, submitted(2)),
+                (1, 1, OrderEventKind::Accepted),
+                (
+                    1,
+                    1,
+                    OrderEventKind::PartiallyFilled {
+                        quantity: 1_000.0,
+                        price: 101.0,
+                        remaining: 500.0
+                    }
+                ),
+                (1, 2, OrderEventKind::Accepted),
+            ]
+        );
+
+        // A cancel between bars is stamped with the bar before it
+        broker.cancel(2).unwrap();
+        let (events, open) = step(
+            &mut broker,
+            vec![],
+            &bar(2, 101.0, 103.0, 100.0, 102.0),
+            200_000.0,
+        );
+        assert!(open.is_empty());
+        assert_eq!(
+            events,
+            [
+                (1, 2, OrderEventKind::Cancelled { remaining: 10.0 }),
+                (
+                    2,
+                    1,
+                    OrderEventKind::Filled {
+                        quantity: 500.0,
+                        price: 102.0
+                    }
+                ),
+            ]
+        );
+
+        let (events, _) = step(
+            &mut broker,
+            vec![market(100.0)],
+            &bar(3, 102.0, 104.0, 101.0, 103.0),
+            50.0,
+        );
+        assert!(matches!(
+            events[2],
+            (
+                3,
+                3,
+                OrderEventKind::Rejected(RejectionReason::InsufficientBuyingPower { .. })
+            )
+        ));
+    }
 }
@@ -0,0 +1,109 @@
+//! Initial and maintenance margin, and the orders that meet a margin call.
+
+use schema::{Order, OrderType, Portfolio, Side};
+use std::collections::{BTreeMap, HashMap};
+
+/// Margin posted per dollar of position value, long or short
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginRequirement {
+    /// Required to open or add to a position
+    pub initial: f64,
+    /// Below this the account is in a margin call
+    pub maintenance: f64,
+}
+
+/// Margin requirements by symbol, with a default for unlisted symbols
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginSchedule {
+    default: MarginRequirement,
+    symbols: BTreeMap<String, MarginRequirement>,
+}
+
+/// Equity of an account against what its positions require
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginStatus {
+    pub equity: f64,
+    pub initial_required: f64,
+    pub maintenance_required: f64,
+}
+
+impl MarginStatus {
+    pub fn margin_call(&self) -> bool {
+        self.equity < self.maintenance_required
+    }
+}
+
+impl MarginSchedule {
+    pub fn new(default: MarginRequirement) -> Self {
+        Self {
+            default,
+            symbols: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_symbol(
+        mut self,
+        symbol: impl Into<String>,
+        requirement: MarginRequirement,
+    ) -> Self {
+        self.symbols.insert(symbol.into(), requirement);
+        self
+    }
+
+    pub fn requirement(&self, symbol: &str) -> MarginRequirement {
+        self.symbols.get(symbol).copied().unwrap_or(self.default)
+    }
+
+    /// Margin status of `portfolio`, valuing positions at `marks` or, when
+    /// unmarked, their average price. Positions are summed in symbol order.
+    pub fn status(&self, portfolio: &Portfolio, marks: &HashMap<String, f64>) -> MarginStatus {
+        let mut positions: Vec<_> = portfolio.positions.values().collect();
+        positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        let mut status = MarginStatus {
+            equity: portfolio.cash,
+            initial_required: 0.0,
+            maintenance_required: 0.0,
+        };
+        for position in positions {
+            let price = marks
+                .get(&position.symbol)
+                .copied()
+                .unwrap_or(position.avg_price);
+            let requirement = self.requirement(&position.symbol);
+            status.equity += position.quantity * price;
+            status.initial_required += position.quantity.abs() * price * requirement.initial;
+            status.maintenance_required +=
+                position.quantity.abs() * price * requirement.maintenance;
+        }
+        status
+    }
+
+    /// Market order closing as much of the `symbol` position as a margin
+    /// call needs, in whole shares, at `price`. Equity is assumed unchanged
+    /// by the sale, so the order releases the shortfall in maintenance.
+    pub(crate) fn liquidation(
+        &self,
+        status: &MarginStatus,
+        portfolio: &Portfolio,
+        symbol: &str,
+        price: f64,
+    ) -> Option<Order> {
+        let held = portfolio.get_position(symbol)?.quantity;
+        let released_per_share = price * self.requirement(symbol).maintenance;
+        if held == 0.0 || released_per_share <= 0.0 {
+            return None;
+        }
+        let shortfall = status.maintenance_required - status.equity;
+        let quantity = (shortfall / released_per_share).ceil().min(held.abs());
+        Some(Order {
+            symbol: symbol.to_string(),
+            side: if held > 0.0 { Side::Sell } else { Side::Buy },
+            quantity,
+            order_type: OrderType::Market,
+            limit_price: None,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        })
+    }
+}
@@ -0,0 +1,196 @@
+//! Option contracts and their exercise and assignment at expiry.
+
+use anyhow::Result;
+use schema::{CostBreakdown, ExpiryOutcome, Fill, OptionExpiration, OptionsChainPayload, Side};
+use std::collections::BTreeMap;
+
+/// Shares per contract of standard equity options
+pub const STANDARD_MULTIPLIER: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+/// Terms of one option contract, physically settled in its underlying
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionContract {
+    pub underlying: String,
+    pub kind: OptionKind,
+    pub strike: f64,
+    /// Unix timestamp in seconds
+    pub expiry: i64,
+    /// Shares of the underlying per contract
+    pub multiplier: f64,
+}
+
+impl OptionContract {
+    /// Value per share at expiry with the underlying at `price`
+    pub fn intrinsic_value(&self, price: f64) -> f64 {
+        match self.kind {
+            OptionKind::Call => (price - self.strike).max(0.0),
+            OptionKind::Put => (self.strike - price).max(0.0),
+        }
+    }
+}
+
+/// Option contracts by symbol
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OptionContracts {
+    contracts: BTreeMap<String, OptionContract>,
+}
+
+impl OptionContracts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_contract(mut self, symbol: impl Into<String>, contract: OptionContract) -> Self {
+        self.contracts.insert(symbol.into(), contract);
+        self
+    }
+
+    /// Add every contract of a chain snapshot at the standard multiplier;
+    /// `option_type` is `call` or `put` (or `c` or `p`), in any case
+    pub fn with_chain(mut self, chain: &OptionsChainPayload) -> Result<Self> {
+        for snapshot in &chain.contracts {
+            let kind = match snapshot.option_type.to_ascii_lowercase().as_str() {
+                "call" | "c" => OptionKind::Call,
+                "put" | "p" => OptionKind::Put,
+                other => anyhow::bail!(
+                    "Option {} has unknown type '{}' (expected call or put)",
+                    snapshot.symbol,
+                    other
+                ),
+            };
+            self.contracts.insert(
+                snapshot.symbol.clone(),
+                OptionContract {
+                    underlying: chain.underlying.clone(),
+                    kind,
+                    strike: snapshot.strike,
+                    expiry: snapshot.expiry,
+                    multiplier: STANDARD_MULTIPLIER,
+                },
+            );
+        }
+        Ok(self)
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&OptionContract> {
+        self.contracts.get(symbol)
+    }
+
+    /// Contracts on `underlying` expired by `timestamp`, in symbol order
+    pub(crate) fn expired<'a>(
+        &'a self,
+        underlying: &'a str,
+        timestamp: i64,
+    ) -> impl Iterator<Item = (&'a String, &'a OptionContract)> {
+        self.contracts
+            .iter()
+            .filter(move |(_, c)| c.underlying == underlying && c.expiry <= timestamp)
+    }
+}
+
+/// Settle a signed `quantity` of contracts at expiry with the underlying at
+/// `price`. The option position closes at zero; an in-the-money one is
+/// exercised if long and assigned if short, trading the underlying at the
+/// strike.
+pub(crate) fn settle(
+    symbol: &str,
+    contract: &OptionContract,
+    quantity: f64,
+    price: f64,
+    timestamp: i64,
+) -> (OptionExpiration, Vec<Fill>) {
+    let fill = |symbol: &str, side, quantity, price| Fill {
+        timestamp,
+        symbol: symbol.to_string(),
+        side,
+        quantity,
+        price,
+        costs: CostBreakdown::default(),
+    };
+    let close_side = if quantity > 0.0 {
+        Side::Sell
+    } else {
+        Side::Buy
+    };
+    let mut fills = vec![fill(symbol, close_side, quantity.abs(), 0.0)];
+
+    let outcome = if contract.intrinsic_value(price) <= 0.0 {
+        ExpiryOutcome::Expired
+    } else {
+        // Long calls and short puts end up buying the underlying
+        let buys = (contract.kind == OptionKind::Call) == (quantity > 0.0);
+        fills.push(fill(
+            &contract.underlying,
+            if buys { Side::Buy } else { Side::Sell },
+            quantity.abs() * contract.multiplier,
+            contract.strike,
+        ));
+        if quantity > 0.0 {
+            ExpiryOutcome::Exercised
+        } else {
+            ExpiryOutcome::Assigned
+        }
+    };
+    let expiration = OptionExpiration {
+        timestamp,
+        symbol: symbol.to_string(),
+        underlying: contract.underlying.clone(),
+        quantity,
+        strike: contract.strike,
+        underlying_price: price,
+        outcome,
+    };
+    (expiration, fills)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::OptionContractSnapshot;
+
+    #[test]
+    fn chains_load_and_positions_settle_by_moneyness() {
+        let snapshot = |symbol: &str, option_type: &str| OptionContractSnapshot {
+            symbol: symbol.to_string(),
+            strike: 100.0,
+            expiry: 1_000,
+            option_type: option_type.to_string(),
+            bid: None,
+            ask: None,
+            last: None,
+        };
+        let chain = OptionsChainPayload {
+            underlying: "AAPL".to_string(),
+            contracts: vec![snapshot("AAPL_C100", "Call"), snapshot("AAPL_P100", "p")],
+        };
+        let contracts = OptionContracts::new().with_chain(&chain).unwrap();
+        let call = contracts.get("AAPL_C100").unwrap();
+        let put = contracts.get("AAPL_P100").unwrap();
+        assert_eq!((call.kind, put.kind), (OptionKind::Call, OptionKind::Put));
+        assert_eq!(contracts.expired("AAPL", 999).count(), 0);
+        assert_eq!(contracts.expired("AAPL", 1_000).count(), 2);
+
+        // Two long calls in the money buy 200 shares at the strike
+        let (expiration, fills) = settle("AAPL_C100", call, 2.0, 110.0, 1_000);
+        assert_eq!(expiration.outcome, ExpiryOutcome::Exercised);
+        assert_eq!((fills[0].side, fills[0].price), (Side::Sell, 0.0));
+        assert_eq!(
+            (fills[1].symbol.as_str(), fills[1].side, fills[1].quantity),
+            ("AAPL", Side::Buy, 200.0)
+        );
+
+        // A short put in the money is assigned, one out of the money expires
+        let (expiration, fills) = settle("AAPL_P100", put, -1.0, 90.0, 1_000);
+        assert_eq!(expiration.outcome, ExpiryOutcome::Assigned);
+        assert_eq!((fills[1].side, fills[1].price), (Side::Buy, 100.0));
+        let (expiration, fills) = settle("AAPL_P100", put, -1.0, 100.0, 1_000);
+        assert_eq!(expiration.outcome, ExpiryOutcome::Expired);
+        assert_eq!((fills.len(), fills[0].side), (1, Side::Buy));
+    }
+}
@@ -0,0 +1,274 @@
+//! Matching against tier-3 order book snapshots.
+
+use anyhow::Result;
+use schema::{
+    CostModel, EventEnvelope, Fill, MarketContext, MarketEventPayload, Order, OrderBookLevel,
+    OrderBookPayload, OrderType, Side,
+};
+use std::collections::HashMap;
+
+use crate::{resting_costs, validate_prices};
+
+/// Broker simulator that matches orders against displayed book liquidity.
+///
+/// Each `OrderBookUpdate` replaces the symbol's book. An order walks the
+/// opposite side level by level, producing one fill per level and depleting
+/// the sizes it takes, so later orders against the same snapshot see less
+/// liquidity. Whatever the book cannot fill (or, for a limit order, fill
+/// within its limit) rests and is matched against later snapshots. Walking
+/// the book is the price impact, so the cost model only adds commission.
+pub struct OrderBookBroker<C: CostModel> {
+    cost_model: C,
+    books: HashMap<String, OrderBookPayload>,
+    /// Resting orders and residuals in submission order
+    open_orders: Vec<Order>,
+}
+
+impl<C: CostModel> OrderBookBroker<C> {
+    pub fn new(cost_model: C) -> Self {
+        Self {
+            cost_model,
+            books: HashMap::new(),
+            open_orders: Vec::new(),
+        }
+    }
+
+    /// The symbol's book as depleted by fills since its latest snapshot
+    pub fn book(&self, symbol: &str) -> Option<&OrderBookPayload> {
+        self.books.get(symbol)
+    }
+
+    pub fn open_orders(&self) -> &[Order] {
+        &self.open_orders
+    }
+
+    /// Cancel the resting orders for `symbol` and return them
+    pub fn cancel_orders(&mut self, symbol: &str) -> Vec<Order> {
+        let (cancelled, open) = std::mem::take(&mut self.open_orders)
+            .into_iter()
+            .partition(|order| order.symbol == symbol);
+        self.open_orders = open;
+        cancelled
+    }
+
+    /// Apply a market event; book updates replace the symbol's book and
+    /// return the fills of resting orders that now cross it
+    pub fn on_event(&mut self, event: &EventEnvelope) -> Result<Vec<Fill>> {
+        match &event.payload {
+            MarketEventPayload::OrderBookUpdate(book) => {
+                Ok(self.on_book(&event.symbol, event.event_time, book))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Replace the symbol's book and match resting orders against it
+    pub fn on_book(&mut self, symbol: &str, timestamp: i64, book: &OrderBookPayload) -> Vec<Fill> {
+        self.books.insert(symbol.to_string(), sorted_book(book));
+
+        let mut fills = Vec::new();
+        let mut still_open = Vec::with_capacity(self.open_orders.len());
+        for mut order in std::mem::take(&mut self.open_orders) {
+            if order.symbol == symbol {
+                fills.extend(self.match_order(&mut order, timestamp));
+            }
+            if order.quantity > 0.0 {
+                still_open.push(order);
+            }
+        }
+        self.open_orders = still_open;
+        fills
+    }
+
+    /// Match a new order against the current book of its symbol at
+    /// `timestamp`; the residual rests
+    pub fn submit(&mut self, mut order: Order, timestamp: i64) -> Result<Vec<Fill>> {
+        validate_prices(&order)?;
+        if !matches!(order.order_type, OrderType::Market | OrderType::Limit) {
+            anyhow::bail!(
+                "OrderBookBroker supports market and limit orders, got {:?} for {}",
+                order.order_type,
+                order.symbol
+            );
+        }
+        let fills = self.match_order(&mut order, timestamp);
+        if order.quantity > 0.0 {
+            self.open_orders.push(order);
+        }
+        Ok(fills)
+    }
+
+    /// Walk the opposite side of the book, leaving the residual in
+    /// `order.quantity`
+    fn match_order(&mut self, order: &mut Order, timestamp: i64) -> Vec<Fill> {
+        let Some(book) = self.books.get_mut(&order.symbol) else {
+            return Vec::new();
+        };
+        let levels = match order.side {
+            Side::Buy => &mut book.asks,
+            Side::Sell => &mut book.bids,
+        };
+        let limit = match order.order_type {
+            OrderType::Limit => order.limit_price,
+            _ => None,
+        };
+        let market = MarketContext {
+            symbol: order.symbol.clone(),
+            ..MarketContext::default()
+        };
+
+        let mut fills = Vec::new();
+        for level in levels.iter_mut() {
+            if order.quantity <= 0.0 {
+                break;
+            }
+            let within_limit = limit.is_none_or(|limit| match order.side {
+                Side::Buy => level.price <= limit,
+                Side::Sell => level.price >= limit,
+            });
+            if !within_limit {
+                break;
+            }
+            let quantity = order.quantity.min(level.size);
+            if quantity <= 0.0 {
+                continue;
+            }
+            level.size -= quantity;
+            order.quantity -= quantity;
+            fills.push(Fill {
+                timestamp,
+                symbol: order.symbol.clone(),
+                side: order.side,
+                quantity,
+                price: level.price,
+                costs: resting_costs(&self.cost_model, quantity, level.price, &market),
+            });
+        }
+        levels.retain(|level| level.size > 0.0);
+        fills
+    }
+}
+
+/// Copy of `book` with the best prices first and empty or invalid levels dropped
+fn sorted_book(book: &OrderBookPayload) -> OrderBookPayload {
+    let valid = |levels: &[OrderBookLevel]| -> Vec<OrderBookLevel> {
+        levels
+            .iter()
+            .filter(|l| l.price.is_finite() && l.price > 0.0 && l.size > 0.0)
+            .cloned()
+            .collect()
+    };
+    let mut bids = valid(&book.bids);
+    let mut asks = valid(&book.asks);
+    bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+    asks.sort_by(|a, b| a.price.total_cmp(&b.price));
+    OrderBookPayload { bids, asks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct PerShare;
+    impl CostModel for PerShare {
+        fn calculate_commission(&self, quantity: f64, _price: f64, _market: &MarketContext) -> f64 {
+            quantity * 0.01
+        }
+        fn calculate_slippage(
+            &self,
+            _quantity: f64,
+            _price: f64,
+            _side: Side,
+            _market: &MarketContext,
+        ) -> f64 {
+            1.0
+        }
+    }
+
+    fn level(price: f64, size: f64) -> OrderBookLevel {
+        OrderBookLevel { price, size }
+    }
+
+    fn order(side: Side, quantity: f64, limit_price: Option<f64>) -> Order {
+        Order {
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            order_type: if limit_price.is_some() {
+                OrderType::Limit
+            } else {
+                OrderType::Market
+            },
+            limit_price,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        }
+    }
+
+    #[test]
+    fn orders_walk_and_deplete_displayed_levels() {
+        let mut broker = OrderBookBroker::new(PerShare);
+        broker.on_book(
+            "AAPL",
+            1,
+            &OrderBookPayload {
+                bids: vec![level(99.9, 100.0), level(100.0, 50.0)],
+                asks: vec![level(100.2, 30.0), level(100.1, 50.0), level(100.3, 0.0)],
+            },
+        );
+
+        let fills = broker.submit(order(Side::Buy, 70.0, None), 2).unwrap();
+        let walked: Vec<(f64, f64)> = fills.iter().map(|f| (f.price, f.quantity)).collect();
+        assert_eq!(walked, [(100.1, 50.0), (100.2, 20.0)]);
+        assert!((fills[0].costs.commission - 0.5).abs() < 1e-12);
+
+        // The next order sees what the first left behind, and the residual
+        // beyond its limit rests
+        let fills = broker
+            .submit(order(Side::Buy, 40.0, Some(100.25)), 3)
+            .unwrap();
+        assert_eq!((fills[0].price, fills[0].quantity), (100.2, 10.0));
+        assert_eq!(broker.open_orders()[0].quantity, 30.0);
+        assert!(broker.book("AAPL").unwrap().asks.is_empty());
+
+        let fills = broker.submit(order(Side::Sell, 120.0, None), 4).unwrap();
+        let walked: Vec<(f64, f64)> = fills.iter().map(|f| (f.price, f.quantity)).collect();
+        assert_eq!(walked, [(100.0, 50.0), (99.9, 70.0)]);
+    }
+
+    #[test]
+    fn resting_orders_match_later_snapshots() {
+        let mut broker = OrderBookBroker::new(PerShare);
+        assert!(broker
+            .submit(order(Side::Buy, 25.0, Some(100.0)), 1)
+            .unwrap()
+            .is_empty());
+
+        let update = |asks| EventEnvelope {
+            event_type: schema::MarketEventType::OrderBookUpdate,
+            symbol: "AAPL".to_string(),
+            event_time: 10,
+            ingest_time: 10,
+            source_id: "test".to_string(),
+            quality_flags: vec![],
+            payload: MarketEventPayload::OrderBookUpdate(OrderBookPayload { bids: vec![], asks }),
+        };
+        assert!(broker
+            .on_event(&update(vec![level(100.5, 100.0)]))
+            .unwrap()
+            .is_empty());
+        let fills = broker
+            .on_event(&update(vec![level(99.8, 10.0), level(100.0, 40.0)]))
+            .unwrap();
+        let walked: Vec<(f64, f64)> = fills.iter().map(|f| (f.price, f.quantity)).collect();
+        assert_eq!(walked, [(99.8, 10.0), (100.0, 15.0)]);
+        assert_eq!(fills[0].timestamp, 10);
+        assert!(broker.open_orders().is_empty());
+
+        let mut stop = order(Side::Sell, 1.0, None);
+        stop.order_type = OrderType::Stop;
+        stop.stop_price = Some(90.0);
+        assert!(broker.submit(stop, 11).is_err());
+    }
+}
@@ -0,0 +1,357 @@
+//! Limit-order queue position against tier-2 quotes and trades.
+
+use anyhow::Result;
+use schema::{
+    CostModel, EventEnvelope, Fill, MarketContext, MarketEventPayload, Order, OrderType,
+    QuotePayload, Side, TradePayload,
+};
+use std::collections::HashMap;
+
+use crate::{resting_costs, validate_prices};
+
+/// A resting limit order and the displayed size queued ahead of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedOrder {
+    pub order: Order,
+    /// Size that must trade at the order's price before it fills; `None`
+    /// while the price is behind the best quote and its queue is not displayed
+    pub ahead: Option<f64>,
+}
+
+/// Broker simulator that queues limit orders behind displayed quote size.
+///
+/// An order joining the best bid or ask queues behind `queue_position` of
+/// the displayed size (1.0, the default, is the back of the queue), and one
+/// that improves the quote is first in line. Trades at the order's price
+/// work through the size ahead before filling it, a shrinking quote shortens
+/// the queue, and a trade or quote through the price fills the order at its
+/// limit. Marketable orders take the opposite quote up to its displayed size.
+pub struct QueueBroker<C: CostModel> {
+    cost_model: C,
+    quotes: HashMap<String, QuotePayload>,
+    /// Resting orders in submission order
+    open_orders: Vec<QueuedOrder>,
+    queue_position: f64,
+}
+
+impl<C: CostModel> QueueBroker<C> {
+    pub fn new(cost_model: C) -> Self {
+        Self {
+            cost_model,
+            quotes: HashMap::new(),
+            open_orders: Vec::new(),
+            queue_position: 1.0,
+        }
+    }
+
+    /// Fraction of the displayed size a joining order queues behind, from
+    /// 0.0 (front) to 1.0 (back)
+    pub fn with_queue_position(mut self, fraction: f64) -> Self {
+        self.queue_position = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The symbol's latest quote, less the size taken by marketable orders
+    pub fn quote(&self, symbol: &str) -> Option<&QuotePayload> {
+        self.quotes.get(symbol)
+    }
+
+    pub fn open_orders(&self) -> &[QueuedOrder] {
+        &self.open_orders
+    }
+
+    /// Cancel the resting orders for `symbol` and return them
+    pub fn cancel_orders(&mut self, symbol: &str) -> Vec<Order> {
+        let (cancelled, open): (Vec<_>, Vec<_>) = std::mem::take(&mut self.open_orders)
+            .into_iter()
+            .partition(|queued| queued.order.symbol == symbol);
+        self.open_orders = open;
+        cancelled.into_iter().map(|queued| queued.order).collect()
+    }
+
+    /// Apply a market event; quotes and trades return the fills of resting
+    /// orders they reach
+    pub fn on_event(&mut self, event: &EventEnvelope) -> Result<Vec<Fill>> {
+        match &event.payload {
+            MarketEventPayload::Quote(quote) => {
+                Ok(self.on_quote(&event.symbol, event.event_time, quote))
+            }
+            MarketEventPayload::Trade(trade) => {
+                Ok(self.on_trade(&event.symbol, event.event_time, trade))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Replace the symbol's quote, update queues and fill crossed orders
+    pub fn on_quote(&mut self, symbol: &str, timestamp: i64, quote: &QuotePayload) -> Vec<Fill> {
+        let mut quote = quote.clone();
+        let mut fills = Vec::new();
+        for i in 0..self.open_orders.len() {
+            if self.open_orders[i].order.symbol != symbol {
+                continue;
+            }
+            let (side, limit) = limit_of(&self.open_orders[i].order);
+            let opposite = match side {
+                Side::Buy => (quote.ask_price <= limit).then_some(&mut quote.ask_size),
+                Side::Sell => (quote.bid_price >= limit).then_some(&mut quote.bid_size),
+            };
+            match opposite {
+                Some(size) => {
+                    let quantity = self.open_orders[i].order.quantity.min(*size);
+                    *size -= quantity;
+                    fills.extend(self.fill(i, quantity, limit, timestamp));
+                }
+                None => {
+                    let queued = &mut self.open_orders[i];
+                    queued.ahead = requeue(queued.ahead, side, limit, &quote, self.queue_position);
+                }
+            }
+        }
+        self.open_orders
+            .retain(|queued| queued.order.quantity > 0.0);
+        self.quotes.insert(symbol.to_string(), quote);
+        fills
+    }
+
+    /// Work a trade through the queues it reaches. A trade at an order's
+    /// price fills it once the size ahead has traded; one through its price
+    /// fills it outright.
+    pub fn on_trade(&mut self, symbol: &str, timestamp: i64, trade: &TradePayload) -> Vec<Fill> {
+        let mut left = trade.quantity;
+        let mut fills = Vec::new();
+        for i in 0..self.open_orders.len() {
+            if left <= 0.0 {
+                break;
+            }
+            let queued = &mut self.open_orders[i];
+            if queued.order.symbol != symbol {
+                continue;
+            }
+            let (side, limit) = limit_of(&queued.order);
+            let through = match side {
+                Side::Buy => trade.price < limit,
+                Side::Sell => trade.price > limit,
+            };
+            let available = if through {
+                left
+            } else if trade.price == limit {
+                let Some(ahead) = &mut queued.ahead else {
+                    continue;
+                };
+                let traded_ahead = ahead.min(left);
+                *ahead -= traded_ahead;
+                left - traded_ahead
+            } else {
+                continue;
+            };
+            let quantity = queued.order.quantity.min(available);
+            left -= quantity;
+            fills.extend(self.fill(i, quantity, limit, timestamp));
+        }
+        self.open_orders
+            .retain(|queued| queued.order.quantity > 0.0);
+        fills
+    }
+
+    /// Submit a limit order at `timestamp`. The marketable part takes the
+    /// opposite quote; the rest joins the queue at its limit.
+    pub fn submit(&mut self, order: Order, timestamp: i64) -> Result<Vec<Fill>> {
+        validate_prices(&order)?;
+        if order.order_type != OrderType::Limit {
+            anyhow::bail!(
+                "QueueBroker supports limit orders, got {:?} for {}",
+                order.order_type,
+                order.symbol
+            );
+        }
+        let (side, limit) = limit_of(&order);
+        self.open_orders.push(QueuedOrder { order, ahead: None });
+        let i = self.open_orders.len() - 1;
+
+        let mut fills = Vec::new();
+        if let Some(quote) = self.quotes.get_mut(&self.open_orders[i].order.symbol) {
+            let (price, size) = match side {
+                Side::Buy => (quote.ask_price, &mut quote.ask_size),
+                Side::Sell => (quote.bid_price, &mut quote.bid_size),
+            };
+            let marketable = match side {
+                Side::Buy => price <= limit,
+                Side::Sell => price >= limit,
+            };
+            if marketable && *size > 0.0 {
+                let quantity = self.open_orders[i].order.quantity.min(*size);
+                *size -= quantity;
+                fills.extend(self.fill(i, quantity, price, timestamp));
+            }
+        }
+        let quote = self.quotes.get(&self.open_orders[i].order.symbol);
+        let ahead = quote.and_then(|quote| requeue(None, side, limit, quote, self.queue_position));
+        self.open_orders[i].ahead = ahead;
+        self.open_orders
+            .retain(|queued| queued.order.quantity > 0.0);
+        Ok(fills)
+    }
+
+    fn fill(&mut self, i: usize, quantity: f64, price: f64, timestamp: i64) -> Option<Fill> {
+        if quantity <= 0.0 {
+            return None;
+        }
+        let order = &mut self.open_orders[i].order;
+        order.quantity -= quantity;
+        let market = MarketContext {
+            symbol: order.symbol.clone(),
+            ..MarketContext::default()
+        };
+        Some(Fill {
+            timestamp,
+            symbol: order.symbol.clone(),
+            side: order.side,
+            quantity,
+            price,
+            costs: resting_costs(&self.cost_model, quantity, price, &market),
+        })
+    }
+}
+
+fn limit_of(order: &Order) -> (Side, f64) {
+    (order.side, order.limit_price.unwrap_or_default())
+}
+
+/// Size ahead of an order at `limit` after `quote`. Improving the quote puts
+/// it first; reaching the best price from behind puts it behind
+/// `queue_position` of the displayed size; at the best price the queue
+/// cannot be longer than what is displayed.
+fn requeue(
+    ahead: Option<f64>,
+    side: Side,
+    limit: f64,
+    quote: &QuotePayload,
+    queue_position: f64,
+) -> Option<f64> {
+    let (best, size) = match side {
+        Side::Buy => (quote.bid_price, quote.bid_size),
+        Side::Sell => (quote.ask_price, quote.ask_size),
+    };
+    let improves = match side {
+        Side::Buy => limit > best,
+        Side::Sell => limit < best,
+    };
+    if improves {
+        Some(0.0)
+    } else if limit == best {
+        Some(ahead.map_or(size * queue_position, |ahead| ahead.min(size)))
+    } else {
+        ahead
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct PerShare;
+    impl CostModel for PerShare {
+        fn calculate_commission(&self, quantity: f64, _price: f64, _market: &MarketContext) -> f64 {
+            quantity * 0.01
+        }
+        fn calculate_slippage(
+            &self,
+            _quantity: f64,
+            _price: f64,
+            _side: Side,
+            _market: &MarketContext,
+        ) -> f64 {
+            1.0
+        }
+    }
+
+    fn quote(bid_price: f64, bid_size: f64, ask_price: f64, ask_size: f64) -> QuotePayload {
+        QuotePayload {
+            bid_price,
+            bid_size,
+            ask_price,
+            ask_size,
+        }
+    }
+
+    fn trade(price: f64, quantity: f64) -> TradePayload {
+        TradePayload {
+            price,
+            quantity,
+            venue: None,
+        }
+    }
+
+    fn bid(price: f64, quantity: f64) -> Order {
+        Order {
+            symbol: "AAPL".to_string(),
+            side: Side::Buy,
+            quantity,
+            order_type: OrderType::Limit,
+            limit_price: Some(price),
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        }
+    }
+
+    fn filled(fills: &[Fill]) -> Vec<(f64, f64)> {
+        fills.iter().map(|f| (f.price, f.quantity)).collect()
+    }
+
+    #[test]
+    fn orders_fill_once_the_size_ahead_has_traded() {
+        let mut broker = QueueBroker::new(PerShare);
+        broker.on_quote("AAPL", 1, &quote(100.0, 500.0, 100.1, 300.0));
+        assert!(broker.submit(bid(100.0, 100.0), 2).unwrap().is_empty());
+        assert_eq!(broker.open_orders()[0].ahead, Some(500.0));
+
+        assert!(broker.on_trade("AAPL", 3, &trade(100.0, 300.0)).is_empty());
+        // Cancellations shrink the displayed size, and with it the queue
+        broker.on_quote("AAPL", 4, &quote(100.0, 150.0, 100.1, 300.0));
+        assert_eq!(broker.open_orders()[0].ahead, Some(150.0));
+        let fills = broker.on_trade("AAPL", 5, &trade(100.0, 200.0));
+        assert_eq!(filled(&fills), [(100.0, 50.0)]);
+        assert!((fills[0].costs.commission - 0.5).abs() < 1e-12);
+        // A trade through the price fills the rest at the limit
+        let fills = broker.on_trade("AAPL", 6, &trade(99.9, 100.0));
+        assert_eq!(filled(&fills), [(100.0, 50.0)]);
+        assert!(broker.open_orders().is_empty());
+
+        // At the front of the queue the first trade fills it
+        let mut front = QueueBroker::new(PerShare).with_queue_position(0.0);
+        front.on_quote("AAPL", 1, &quote(100.0, 500.0, 100.1, 300.0));
+        front.submit(bid(100.0, 100.0), 2).unwrap();
+        let fills = front.on_trade("AAPL", 3, &trade(100.0, 300.0));
+        assert_eq!(filled(&fills), [(100.0, 100.0)]);
+    }
+
+    #[test]
+    fn quotes_reaching_the_limit_fill_or_requeue() {
+        let mut broker = QueueBroker::new(PerShare);
+        broker.on_quote("AAPL", 1, &quote(100.0, 500.0, 100.1, 300.0));
+        // Marketable part takes the ask, the rest improves the bid
+        let fills = broker.submit(bid(100.1, 400.0), 2).unwrap();
+        assert_eq!(filled(&fills), [(100.1, 300.0)]);
+        assert_eq!(broker.open_orders()[0].ahead, Some(0.0));
+        assert_eq!(broker.quote("AAPL").unwrap().ask_size, 0.0);
+
+        // Behind the best bid the queue is unknown until the bid falls to it
+        broker.submit(bid(99.9, 10.0), 3).unwrap();
+        assert_eq!(broker.open_orders()[1].ahead, None);
+        let fills = broker.on_quote("AAPL", 4, &quote(99.9, 40.0, 100.05, 60.0));
+        assert_eq!(filled(&fills), [(100.1, 60.0)]);
+        assert_eq!(broker.open_orders()[1].ahead, Some(40.0));
+        assert!(broker
+            .submit(
+                Order {
+                    order_type: OrderType::Market,
+                    limit_price: None,
+                    ..bid(1.0, 1.0)
+                },
+                5
+            )
+            .is_err());
+    }
+}
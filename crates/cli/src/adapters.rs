@@ -0,0 +1,144 @@
+use anyhow::Result;
+use schema::{
+    Bar, EventEnvelope, FidelityTier, MarketAssetClass, MarketDataAdapter, MarketEventPayload,
+    MarketEventType, ProviderCapabilityDeclaration, ProviderRecord, QualityFlag,
+};
+
+/// Adapter for provider-native OHLCV records.
+///
+/// Accepts `raw_payload` objects keyed either by full names (`open`, `high`,
+/// `low`, `close`, `volume`) or the common short forms (`o`, `h`, `l`, `c`, `v`).
+/// A missing volume is filled with zero and flagged `missing_source_field`.
+pub struct OhlcvRecordAdapter {
+    provider_id: String,
+}
+
+impl OhlcvRecordAdapter {
+    pub fn new(provider_id: impl Into<String>) -> Self {
+        Self {
+            provider_id: provider_id.into(),
+        }
+    }
+}
+
+impl MarketDataAdapter for OhlcvRecordAdapter {
+    fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    fn capabilities(&self) -> ProviderCapabilityDeclaration {
+        ProviderCapabilityDeclaration {
+            provider_id: self.provider_id.clone(),
+            supported_asset_classes: vec![
+                MarketAssetClass::Equity,
+                MarketAssetClass::Future,
+                MarketAssetClass::Crypto,
+                MarketAssetClass::Fx,
+                MarketAssetClass::Commodity,
+            ],
+            supported_event_types: vec![MarketEventType::Bar],
+            supported_fidelity_tiers: vec![FidelityTier::Tier1Bar],
+        }
+    }
+
+    fn normalize_record(&self, record: ProviderRecord) -> Result<EventEnvelope> {
+        let price = |long: &str, short: &str| -> Result<Option<f64>> {
+            match record
+                .raw_payload
+                .get(long)
+                .or_else(|| record.raw_payload.get(short))
+            {
+                None | Some(serde_json::Value::Null) => Ok(None),
+                Some(value) => value.as_f64().map(Some).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{}@{}: field '{}' is not numeric",
+                        record.symbol,
+                        record.event_time,
+                        long
+                    )
+                }),
+            }
+        };
+        let required = |long: &str, short: &str| -> Result<f64> {
+            price(long, short)?.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{}@{}: missing required field '{}'",
+                    record.symbol,
+                    record.event_time,
+                    long
+                )
+            })
+        };
+
+        let mut quality_flags = record.quality_flags.clone();
+        let volume = match price("volume", "v")? {
+            Some(volume) => volume,
+            None => {
+                quality_flags.push(QualityFlag::MissingSourceField);
+                0.0
+            }
+        };
+
+        Ok(EventEnvelope {
+            event_type: MarketEventType::Bar,
+            symbol: record.symbol.clone(),
+            event_time: record.event_time,
+            ingest_time: record.ingest_time,
+            source_id: self.provider_id.clone(),
+            quality_flags,
+            payload: MarketEventPayload::Bar(Bar {
+                timestamp: record.event_time,
+                symbol: record.symbol.clone(),
+                open: required("open", "o")?,
+                high: required("high", "h")?,
+                low: required("low", "l")?,
+                close: required("close", "c")?,
+                volume,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(raw_payload: serde_json::Value) -> ProviderRecord {
+        ProviderRecord {
+            symbol: "BTC-USD".to_string(),
+            event_time: 1_700_000_000,
+            ingest_time: 1_700_000_005,
+            raw_payload,
+            quality_flags: vec![],
+        }
+    }
+
+    #[test]
+    fn normalizes_short_keys_and_flags_missing_volume() {
+        let adapter = OhlcvRecordAdapter::new("vendor");
+        let event = adapter
+            .normalize_record(record(
+                serde_json::json!({"o": 1.0, "h": 2.0, "l": 0.5, "c": 1.5}),
+            ))
+            .unwrap();
+
+        assert_eq!(event.source_id, "vendor");
+        assert_eq!(event.quality_flags, vec![QualityFlag::MissingSourceField]);
+        match event.payload {
+            MarketEventPayload::Bar(bar) => {
+                assert_eq!(bar.close, 1.5);
+                assert_eq!(bar.volume, 0.0);
+            }
+            other => panic!("unexpected payload {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_missing_prices() {
+        let adapter = OhlcvRecordAdapter::new("vendor");
+        let err = adapter
+            .normalize_record(record(serde_json::json!({"open": 1.0, "high": 2.0})))
+            .unwrap_err();
+        assert!(err.to_string().contains("'low'"));
+    }
+}
@@ -1,126 +1,961 @@
 use anyhow::{Context, Result};
-use broker_sim::SimpleBroker;
-use cost::{FixedPerShareCost, PercentageCost, ZeroCost};
-use crv_verifier::{CRVVerifier, PolicyConstraints};
-use engine::{BacktestEngine, VecDataFeed};
-use polars::prelude::*;
+use broker_sim::{
+    AlgoKind, FundingRate, FundingSchedule, FxConversion, HaltPolicy, HardToBorrow,
+    InstrumentRules, InstrumentTable, Latency, MarginRequirement, MarginSchedule,
+    ShortAvailability, SimpleBroker, TradingHalts,
+};
+use cost::{
+    CompositeCostModel, FixedPerShareCost, PercentageCost, ScaledCost, SlippageDistribution,
+    StochasticSlippageCost, VolumeParticipationCost, ZeroCost,
+};
+use crv_verifier::{CRVReport, CRVVerifier};
+use engine::bootstrap::BootstrapConfig;
+use engine::currency::Currencies;
+use engine::golden::RunHashes;
+use engine::lots::LotRealization;
+use engine::monte_carlo::{resample_trades, MonteCarloConfig};
+use engine::multi_strategy::{SleeveBooks, SleeveStats};
+use engine::risk::RiskLimits;
+use engine::round_trips::RoundTrip;
+use engine::{BacktestEngine, FinancingRates, Progress, RunTrace, VecDataFeed};
+use hipcortex::Repository;
 use schema::{
-    sort_events_deterministically, validate_events_for_tier, Bar, CostModel, EventEnvelope,
-    FidelityTier, MarketEventPayload, MarketEventType, QualityFlag,
+    BacktestStats, Bar, BootstrapStats, BrokerSim, CostModel, DataFeed, DataQualityReport,
+    EquityPoint, ExposurePoint, Fill, KillSwitchTrip, OrderEvent, OrderRejection, RejectionReason,
 };
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
-use crate::spec::{BacktestSpec, CostModelSpec, DataPipelineSpec, StrategySpec};
-use crate::strategies::TsMomentumStrategy;
+use crate::benchmark::{
+    load_benchmark, print_comparison, run_benchmark, write_benchmark_outputs, BenchmarkComparison,
+    BenchmarkSource,
+};
+use crate::canonical::is_canonical_parquet;
+use crate::checkpoint::{load_completed, write_checkpoint};
+use crate::columnar::{is_columnar, ColumnarDataFeed};
+use crate::data::{load_dataset, LoadedDataset};
+use crate::lineage::commit_run;
+use crate::parquet_feed::{BarChunks, ChunkedDataFeed, ParquetDataFeed};
+use crate::spec::{
+    apply_overrides, parse_spec, BacktestSpec, CashMode, CostModelSpec, DataPipelineSpec,
+    ExecutionAlgo, FillPolicy, FundingSpec, FxPrices, FxSpec, InstrumentRulesSpec, InstrumentsSpec,
+    LotMethod, MarginRequirementSpec, Rounding, SlippageDistributionSpec, StrategySpec,
+};
+use crate::strategies::{build_multi_strategy, build_strategy};
 
-pub fn run_backtest(spec_path: &Path, data_path: &Path, out_dir: &Path) -> Result<()> {
-    // Read spec
-    let spec_str = fs::read_to_string(spec_path).context("Failed to read spec file")?;
-    let spec: BacktestSpec =
-        serde_json::from_str(&spec_str).context("Failed to parse spec JSON")?;
+/// Everything a single backtest run produces, before anything is written to disk
+pub struct BacktestOutcome {
+    pub stats: BacktestStats,
+    pub fills: Vec<Fill>,
+    pub equity_history: Vec<(i64, f64)>,
+    /// `equity_history` split into cash and positions, for `equity_curve.csv`
+    pub equity_points: Vec<EquityPoint>,
+    /// Exposure, leverage and turnover at each equity mark
+    pub exposure_history: Vec<ExposurePoint>,
+    /// Positions from open to flat, for `trades-roundtrip.csv`
+    pub round_trips: Vec<RoundTrip>,
+    /// Tax lots closed, for `tax_lots.csv`
+    pub lot_realizations: Vec<LotRealization>,
+    pub crv_report: CRVReport,
+    /// What the broker did with each order, for `order_events.jsonl`
+    pub order_events: Vec<OrderEvent>,
+    /// Orders the spec's policy refused before they reached the broker,
+    /// logged in `crv_report`
+    pub risk_rejections: Vec<OrderRejection>,
+    /// Events and stage timings, committed as the run's Trace; none for a
+    /// run read back from its checkpoint
+    pub trace: Option<RunTrace>,
+    /// Stats of each sleeve of a `multi_strategy`, for `strategies.json`
+    pub sleeves: Vec<SleeveStats>,
+    /// Trips of the spec's kill switch, for `kill_switch.json`; one per
+    /// symbol at most in a universe run
+    pub kill_switch_trips: Vec<KillSwitchTrip>,
+}
+
+/// An engine over the broker a spec describes
+type SpecEngine<D, S> = BacktestEngine<D, S, SimpleBroker<Box<dyn CostModel>>>;
+
+/// Bars between redraws of the progress bar
+const PROGRESS_EVERY: usize = 1_000;
+
+/// How `run_backtest` runs, beyond what the spec says
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BacktestOptions<'a> {
+    /// Replaces the spec's benchmark
+    pub benchmark: Option<&'a str>,
+    /// Reuse outputs their checkpoint vouches for instead of re-running
+    pub resume: bool,
+    /// HipCortex repository to commit the run to
+    pub repo: Option<&'a Path>,
+    /// Draw a progress bar on stderr while the engine runs
+    pub progress: bool,
+    /// Run the backtest twice and fail unless both runs match
+    pub check_determinism: bool,
+}
+
+/// Run, write and verify a backtest, returning its CRV report for gating
+pub fn run_backtest(
+    spec_path: &Path,
+    overrides: &[String],
+    data_path: &Path,
+    out_dir: &Path,
+    options: &BacktestOptions,
+) -> Result<CRVReport> {
+    let mut spec = load_spec(spec_path, overrides)?;
+    if let Some(benchmark) = options.benchmark {
+        spec.benchmark = Some(benchmark.to_string());
+    }
 
     // Create output directory
     fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+    let spec_hash = write_effective_spec(&spec, out_dir)?;
 
-    // Load data from parquet (legacy bar path or canonical Tier 1 bridge path)
-    let bars = match spec.data_pipeline {
-        DataPipelineSpec::Legacy => load_bars_from_parquet_legacy(data_path)?,
-        DataPipelineSpec::CanonicalTier1 => load_bars_from_parquet_canonical_tier1(data_path)?,
-    };
+    // Load data from a parquet file, directory or glob (legacy bar path or canonical Tier 1 bridge path)
+    let dataset = load_dataset(data_path, &spec.data_pipeline)?;
+
+    // The canonical pipeline enforces bar consistency; legacy data only warns
+    if !dataset.bar_report.is_valid() {
+        println!(
+            "Warning: {} of {} bars have inconsistent OHLCV values",
+            dataset.bar_report.invalid_bars, dataset.bar_report.bars_checked
+        );
+    }
+    let quality_path = out_dir.join("data_quality.json");
+    let quality_file = fs::File::create(&quality_path)?;
+    serde_json::to_writer_pretty(quality_file, &dataset.quality)?;
 
-    println!("Loaded {} bars", bars.len());
+    println!("Loaded {} bars", dataset.bars.len());
     println!("Running backtest with {} strategy", spec.strategy_name());
     println!("Initial cash: ${:.2}", spec.initial_cash);
     println!("Seed: {}", spec.seed);
+    println!("Cost model: {}", serde_json::to_string(&spec.cost_model)?);
+    println!("Spec hash: {}", spec_hash);
     println!(
         "Data pipeline: {}",
         match spec.data_pipeline {
             DataPipelineSpec::Legacy => "legacy",
             DataPipelineSpec::CanonicalTier1 => "canonical_tier1",
+            DataPipelineSpec::PointInTime => "point_in_time",
         }
     );
+    println!("Data quality score: {:.1}", dataset.quality.score);
 
-    // Create data feed
-    let data_feed = VecDataFeed::new(bars);
-
-    // Run backtest based on strategy type
-    match &spec.strategy {
-        StrategySpec::TsMomentum {
-            symbol,
-            lookback,
-            vol_target,
-            vol_lookback,
-        } => {
-            let strategy =
-                TsMomentumStrategy::new(symbol.clone(), *lookback, *vol_target, *vol_lookback);
+    let data_hash = engine::canonical_json_hash(&dataset.bars)?;
+    let completed = if options.resume {
+        load_completed(out_dir, &spec_hash, &data_hash)?
+    } else {
+        None
+    };
+    let (outcome, comparison) = match completed {
+        Some(outcome) => {
+            println!(
+                "Outputs in {:?} match their checkpoint; not re-running",
+                out_dir
+            );
+            let comparison = load_benchmark(out_dir)?.map(|(comparison, _)| comparison);
+            (outcome, comparison)
+        }
+        None => {
+            if options.resume {
+                println!(
+                    "No valid checkpoint in {:?}; running from the start",
+                    out_dir
+                );
+            }
+            let run = execute_and_write(&spec, &dataset, out_dir, options)?;
+            write_checkpoint(out_dir, &spec_hash, &data_hash)?;
+            run
+        }
+    };
 
-            run_backtest_with_strategy(data_feed, strategy, &spec, out_dir)?;
+    println!("Wrote trades to {:?}", out_dir.join("trades.csv"));
+    println!(
+        "Wrote round trips to {:?}",
+        out_dir.join("trades-roundtrip.csv")
+    );
+    println!(
+        "Wrote closed tax lots to {:?}",
+        out_dir.join("tax_lots.csv")
+    );
+    println!(
+        "Wrote equity curve to {:?}",
+        out_dir.join("equity_curve.csv")
+    );
+    println!("Wrote statistics to {:?}", out_dir.join("stats.json"));
+    println!(
+        "Wrote order events to {:?}",
+        out_dir.join("order_events.jsonl")
+    );
+    write_analyses(&spec, &outcome, out_dir)?;
+
+    println!("\n=== Running CRV Verification ===");
+    println!("Wrote CRV report to {:?}", out_dir.join("crv_report.json"));
+    print_crv_report(&outcome.crv_report);
+    print_summary(&outcome.stats);
+    print_sleeves(&outcome.sleeves);
+
+    if let Some(comparison) = &comparison {
+        print_comparison(comparison);
+        println!(
+            "Wrote benchmark comparison to {:?}",
+            out_dir.join("benchmark.json")
+        );
+    }
+
+    if let Some(repo_path) = options.repo {
+        let mut repo = Repository::open(repo_path).context("Failed to open repository")?;
+        let hashes = commit_run(&mut repo, &spec, &data_hash, &outcome, &[], "Backtest")?;
+        println!("\n=== Committed to HipCortex {:?} ===", repo_path);
+        println!("Strategy: {}", hashes.strategy);
+        println!("Config: {}", hashes.config);
+        println!("Result: {}", hashes.result);
+        println!("CRV report: {}", hashes.crv_report);
+        if let Some(trace) = &hashes.trace {
+            println!("Trace: {}", trace);
         }
     }
 
     println!("Backtest completed. Results written to {:?}", out_dir);
+    Ok(outcome.crv_report)
+}
+
+/// Run `spec` over a loaded dataset and write its outputs into `out_dir`,
+/// along with the benchmark comparison the spec asks for
+pub fn execute_and_write(
+    spec: &BacktestSpec,
+    dataset: &LoadedDataset,
+    out_dir: &Path,
+    options: &BacktestOptions,
+) -> Result<(BacktestOutcome, Option<BenchmarkComparison>)> {
+    let mut outcome = execute_with_progress(
+        spec,
+        &dataset.bars,
+        &dataset.halts,
+        &dataset.quality,
+        options.progress,
+    )?;
+    if options.check_determinism {
+        check_determinism(spec, dataset, &outcome)?;
+    }
+    // Relative stats go into stats.json, so the benchmark runs first
+    let comparison = match spec.benchmark.as_deref().map(BenchmarkSource::parse) {
+        Some(source) => {
+            let (comparison, benchmark_outcome) = run_benchmark(spec, &source, dataset, &outcome)?;
+            outcome.stats.benchmark = Some(comparison.relative.clone());
+            write_benchmark_outputs(&comparison, &benchmark_outcome, &outcome, out_dir)?;
+            Some(comparison)
+        }
+        None => None,
+    };
+    write_outputs(&outcome, out_dir)?;
+    Ok((outcome, comparison))
+}
+
+/// Write the rolling metrics and Monte Carlo resamples the spec asks for
+pub fn write_analyses(
+    spec: &BacktestSpec,
+    outcome: &BacktestOutcome,
+    out_dir: &Path,
+) -> Result<()> {
+    write_rolling_metrics(spec, &outcome.equity_history, out_dir)?;
+    write_monte_carlo(spec, outcome, out_dir)
+}
+
+/// Run a backtest streaming bars from one legacy bar parquet file a row
+/// group at a time, or one columnar file a chunk at a time, for datasets
+/// too large to load.
+///
+/// The file must already be in timestamp order. Nothing is held to score
+/// data quality, sort, or checkpoint, so the CRV report skips the data
+/// quality check and runs cannot be resumed.
+pub fn run_streamed_backtest(
+    spec_path: &Path,
+    overrides: &[String],
+    data_path: &Path,
+    out_dir: &Path,
+    progress: bool,
+) -> Result<CRVReport> {
+    let spec = load_spec(spec_path, overrides)?;
+    if spec.universe.is_some() {
+        anyhow::bail!("Universe specs cannot stream their data; run without --stream");
+    }
+    if spec.benchmark.is_some() {
+        anyhow::bail!("Benchmarks need the whole dataset loaded; run without --stream");
+    }
+    if matches!(spec.strategy, StrategySpec::MultiStrategy { .. }) {
+        anyhow::bail!("Multi-strategy runs report per-sleeve stats only without --stream");
+    }
+    if !matches!(spec.data_pipeline, DataPipelineSpec::Legacy) {
+        anyhow::bail!(
+            "Only the legacy data pipeline can stream; the canonical one sorts the whole stream"
+        );
+    }
+    if !data_path.is_file() || (!is_columnar(data_path) && is_canonical_parquet(data_path)?) {
+        anyhow::bail!(
+            "--stream reads a single legacy bar parquet or columnar file, not {:?}",
+            data_path
+        );
+    }
+
+    fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+    let spec_hash = write_effective_spec(&spec, out_dir)?;
+    println!("Streaming bars from {:?}", data_path);
+    println!("Running backtest with {} strategy", spec.strategy_name());
+    println!("Spec hash: {}", spec_hash);
+
+    let outcome = if is_columnar(data_path) {
+        stream_outcome(
+            ColumnarDataFeed::open(data_path)?,
+            &spec,
+            data_path,
+            progress,
+        )?
+    } else {
+        stream_outcome(
+            ParquetDataFeed::open(data_path)?,
+            &spec,
+            data_path,
+            progress,
+        )?
+    };
+    write_outputs(&outcome, out_dir)?;
+    write_analyses(&spec, &outcome, out_dir)?;
+
+    println!("\n=== Running CRV Verification ===");
+    print_crv_report(&outcome.crv_report);
+    print_summary(&outcome.stats);
+    println!("Backtest completed. Results written to {:?}", out_dir);
+    Ok(outcome.crv_report)
+}
+
+/// Run `spec` over a streamed feed, failing if the feed stopped early
+fn stream_outcome<C: BarChunks>(
+    data_feed: ChunkedDataFeed<C>,
+    spec: &BacktestSpec,
+    data_path: &Path,
+    progress: bool,
+) -> Result<BacktestOutcome> {
+    let strategy = build_strategy(&spec.strategy)?;
+    let engine = run_engine(
+        data_feed,
+        strategy,
+        spec,
+        &TradingHalts::new(),
+        progress,
+        None,
+    )?;
+    if let Some(err) = engine.data_feed().error() {
+        anyhow::bail!("Streaming {:?} stopped early: {:#}", data_path, err);
+    }
+    outcome_from_engine(&engine, spec, None)
+}
+
+/// Run the backtest again and fail unless it repeats `outcome`'s fills,
+/// equity history and stats exactly
+fn check_determinism(
+    spec: &BacktestSpec,
+    dataset: &LoadedDataset,
+    outcome: &BacktestOutcome,
+) -> Result<()> {
+    let first = RunHashes::of(&outcome.fills, &outcome.equity_history, &outcome.stats)?;
+    let again = execute_backtest(spec, &dataset.bars, &dataset.halts, &dataset.quality)?;
+    let second = RunHashes::of(&again.fills, &again.equity_history, &again.stats)?;
+    let mismatches = first.mismatches(&second);
+    if !mismatches.is_empty() {
+        anyhow::bail!(
+            "Determinism check failed: two runs with seed {} differ in {}",
+            spec.seed,
+            mismatches.join(", ")
+        );
+    }
+    println!(
+        "Determinism check passed: fills {}, equity {}, stats {}",
+        first.fills, first.equity, first.stats
+    );
     Ok(())
 }
 
-fn run_backtest_with_strategy<S: schema::Strategy>(
-    data_feed: VecDataFeed,
-    strategy: S,
+/// Redraw the progress bar in place on stderr
+fn draw_progress(progress: &Progress) {
+    let elapsed = format_duration(progress.elapsed);
+    let line = match (progress.total_bars, progress.fraction(), progress.eta()) {
+        (Some(total), Some(fraction), Some(eta)) => {
+            let filled = (fraction * 30.0).round() as usize;
+            format!(
+                "[{}{}] {:5.1}% {}/{} bars, {} elapsed, ETA {}",
+                "=".repeat(filled),
+                " ".repeat(30 - filled),
+                fraction * 100.0,
+                progress.bars_processed,
+                total,
+                elapsed,
+                format_duration(eta)
+            )
+        }
+        _ => format!("{} bars, {} elapsed", progress.bars_processed, elapsed),
+    };
+    eprint!("\r{}", line);
+}
+
+/// `1h02m03s`, `2m05s` or `12s`
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+/// Write `rolling_metrics.csv` when the spec sets a rolling window
+fn write_rolling_metrics(
     spec: &BacktestSpec,
+    equity_history: &[(i64, f64)],
     out_dir: &Path,
 ) -> Result<()> {
-    // Create cost model
-    let cost_model: Box<dyn CostModel> = match &spec.cost_model {
-        CostModelSpec::FixedPerShare {
-            cost_per_share,
-            minimum_commission,
-        } => Box::new(FixedPerShareCost::new(*cost_per_share, *minimum_commission)),
-        CostModelSpec::Percentage {
-            percentage,
-            minimum_commission,
-        } => Box::new(PercentageCost::new(*percentage, *minimum_commission)),
-        CostModelSpec::Zero => Box::new(ZeroCost),
+    let Some(window) = spec.metrics.rolling_window else {
+        return Ok(());
     };
+    let path = out_dir.join("rolling_metrics.csv");
+    let metrics =
+        engine::output::rolling_metrics(equity_history, window, spec.metrics.annualization());
+    engine::output::write_rolling_metrics_csv(&metrics, &path)?;
+    println!("Wrote {}-bar rolling metrics to {:?}", window, path);
+    Ok(())
+}
 
-    // Create broker with deterministic seed
-    let broker = SimpleBroker::new(cost_model, spec.seed);
+/// Write `monte_carlo.json` when the spec asks to resample the run's trades
+fn write_monte_carlo(spec: &BacktestSpec, outcome: &BacktestOutcome, out_dir: &Path) -> Result<()> {
+    let Some(monte_carlo) = &spec.monte_carlo else {
+        return Ok(());
+    };
+    let pnls: Vec<f64> = outcome.round_trips.iter().map(|trip| trip.pnl).collect();
+    let config = MonteCarloConfig {
+        simulations: monte_carlo.simulations,
+        block_size: monte_carlo.block_size,
+        ruin_loss: monte_carlo.ruin_loss,
+        seed: spec.seed,
+    };
+    let report = resample_trades(&pnls, spec.initial_cash, &config);
+    let path = out_dir.join("monte_carlo.json");
+    engine::output::write_monte_carlo_json(&report, &path)?;
+    println!(
+        "Wrote {} Monte Carlo resamples of {} trades to {:?} (ruin probability {:.2}%)",
+        report.simulations,
+        report.trades,
+        path,
+        report.ruin_probability * 100.0
+    );
+    Ok(())
+}
+
+/// Read a backtest spec file, apply `--set` overrides and validate the result
+pub fn load_spec(spec_path: &Path, overrides: &[String]) -> Result<BacktestSpec> {
+    let spec_str = fs::read_to_string(spec_path).context("Failed to read spec file")?;
+    let mut value = serde_json::from_str(&spec_str).context("Failed to parse spec JSON")?;
+    apply_overrides(&mut value, overrides)?;
+    parse_spec(value)
+}
+
+/// Write the spec a run actually used to `effective_spec.json`, returning its hash.
+///
+/// The hash is also written to `effective_spec.sha256` so runs can be matched
+/// by configuration without re-deriving overrides.
+pub fn write_effective_spec(spec: &BacktestSpec, out_dir: &Path) -> Result<String> {
+    let spec_file = fs::File::create(out_dir.join("effective_spec.json"))?;
+    serde_json::to_writer_pretty(spec_file, spec)?;
+    let hash = engine::canonical_json_hash(spec)?;
+    fs::write(out_dir.join("effective_spec.sha256"), format!("{}\n", hash))?;
+    Ok(hash)
+}
+
+/// Run one deterministic backtest over already-prepared bars, including CRV verification
+pub fn execute_backtest(
+    spec: &BacktestSpec,
+    bars: &[Bar],
+    halts: &TradingHalts,
+    quality: &DataQualityReport,
+) -> Result<BacktestOutcome> {
+    execute_with_progress(spec, bars, halts, quality, false)
+}
+
+/// `execute_backtest`, drawing a progress bar while the engine runs when
+/// `progress` is set; universe runs draw none
+fn execute_with_progress(
+    spec: &BacktestSpec,
+    bars: &[Bar],
+    halts: &TradingHalts,
+    quality: &DataQualityReport,
+    progress: bool,
+) -> Result<BacktestOutcome> {
+    if let Some(universe) = &spec.universe {
+        return crate::universe::execute_universe(spec, universe, bars, halts, quality);
+    }
+
+    // Create data feed
+    let data_feed = VecDataFeed::new(bars.to_vec());
+
+    if let StrategySpec::MultiStrategy { sleeves } = &spec.strategy {
+        let strategy = build_multi_strategy(sleeves)?;
+        let books = strategy.books();
+        let engine = run_engine(data_feed, strategy, spec, halts, progress, Some(&books))?;
+        let mut outcome = outcome_from_engine(&engine, spec, Some(quality))?;
+        outcome.sleeves = books.stats(spec.metrics.annualization());
+        return Ok(outcome);
+    }
+    let strategy = build_strategy(&spec.strategy)?;
+    run_backtest_with_strategy(data_feed, strategy, spec, halts, quality, progress)
+}
+
+fn run_backtest_with_strategy<S: schema::Strategy>(
+    data_feed: VecDataFeed,
+    strategy: S,
+    spec: &BacktestSpec,
+    halts: &TradingHalts,
+    quality: &DataQualityReport,
+    progress: bool,
+) -> Result<BacktestOutcome> {
+    let engine = run_engine(data_feed, strategy, spec, halts, progress, None)?;
+    outcome_from_engine(&engine, spec, Some(quality))
+}
+
+/// Run the spec's broker and financing over `data_feed` to the end, booking
+/// fills into `sleeves` for a multi-strategy run
+fn run_engine<D: DataFeed, S: schema::Strategy>(
+    data_feed: D,
+    strategy: S,
+    spec: &BacktestSpec,
+    halts: &TradingHalts,
+    progress: bool,
+    sleeves: Option<&SleeveBooks>,
+) -> Result<SpecEngine<D, S>> {
+    let broker = build_broker(spec, halts);
 
     // Create and run engine
-    let mut engine = BacktestEngine::new(data_feed, strategy, broker, spec.initial_cash);
+    let lot_method = match spec.execution.lot_method {
+        LotMethod::Fifo => engine::lots::LotMethod::Fifo,
+        LotMethod::Lifo => engine::lots::LotMethod::Lifo,
+        LotMethod::AverageCost => engine::lots::LotMethod::AverageCost,
+    };
+    let engine = BacktestEngine::new(data_feed, strategy, broker, spec.initial_cash)
+        .with_lot_method(lot_method)
+        .with_trace();
+    let engine = match &spec.execution.fx {
+        Some(fx) if fx.prices == FxPrices::Local => engine.with_currencies(currencies(fx)),
+        _ => engine,
+    };
+    let reserve = spec.execution.cash_reserve.unwrap_or(0.0);
+    let engine = match spec.execution.cash_mode {
+        CashMode::Unchecked => engine,
+        CashMode::Strict => engine.with_strict_cash(true, reserve),
+        CashMode::StrictNoShorts => engine.with_strict_cash(false, reserve),
+    };
+    let engine = match &spec.policy {
+        Some(_) => {
+            let constraints = spec.policy_constraints();
+            engine.with_risk_check(RiskLimits {
+                max_leverage: constraints.max_leverage,
+                max_position_size: constraints.max_position_size,
+                max_turnover: constraints.max_turnover,
+            })
+        }
+        None => engine,
+    };
+    let engine = match &spec.kill_switch {
+        Some(kill_switch) => engine.with_kill_switch(kill_switch.max_drawdown),
+        None => engine,
+    };
+    let engine = match spec.warmup_bars {
+        Some(bars) => engine.with_warmup(bars),
+        None => engine,
+    };
+    let engine = if progress {
+        engine.with_progress(PROGRESS_EVERY, draw_progress)
+    } else {
+        engine
+    };
+    let engine = match sleeves {
+        Some(books) => engine.with_observer(books.clone()),
+        None => engine,
+    };
+    let mut engine = match &spec.execution.financing {
+        Some(financing) => engine.with_financing(FinancingRates {
+            debit_rate: financing.debit_rate,
+            credit_rate: financing.credit_rate,
+            short_borrow_rate: financing.short_borrow_rate,
+        }),
+        None => engine,
+    };
 
     engine.run()?;
+    if progress {
+        eprintln!();
+    }
+    let refused = engine.constrained_orders().map_or(0, |c| c.rejected);
+    if refused > 0 {
+        eprintln!("Warning: pre-trade checks refused {} order(s)", refused);
+    }
+    let stopped = engine
+        .rejections()
+        .iter()
+        .filter(|r| r.reason == RejectionReason::KillSwitch)
+        .count();
+    if let Some(trip) = engine.kill_switch() {
+        eprintln!(
+            "Warning: kill switch tripped at {} with a {:.2}% drawdown; positions closed and {} order(s) refused",
+            trip.timestamp,
+            trip.drawdown * 100.0,
+            stopped
+        );
+    }
+    if engine.rejections().len() > refused + stopped {
+        eprintln!(
+            "Warning: broker rejected {} order(s)",
+            engine.rejections().len() - refused - stopped
+        );
+    }
+    if !engine.margin_calls().is_empty() {
+        eprintln!(
+            "Warning: {} margin call(s) forced liquidations",
+            engine.margin_calls().len()
+        );
+    }
+    Ok(engine)
+}
+
+/// Stats, outputs and CRV report of a finished engine; the data quality
+/// check runs only when the data was scored
+fn outcome_from_engine<D: DataFeed, S: schema::Strategy, B: BrokerSim>(
+    engine: &BacktestEngine<D, S, B>,
+    spec: &BacktestSpec,
+    quality: Option<&DataQualityReport>,
+) -> Result<BacktestOutcome> {
+    let mut stats = engine::output::calculate_stats(
+        engine.equity_history(),
+        engine.fills(),
+        engine.costs(),
+        spec.metrics.annualization(),
+    );
+    stats.financing = engine.financing();
+    stats.constrained_orders = engine.constrained_orders();
+    stats.bootstrap = bootstrap_intervals(spec, engine.equity_history());
 
-    // Write outputs
-    let trades_path = out_dir.join("trades.csv");
-    engine::output::write_trades_csv(engine.fills(), &trades_path)?;
-    println!("Wrote trades to {:?}", trades_path);
+    // Run CRV verification
+    let verifier = CRVVerifier::new(spec.policy_constraints());
 
-    let equity_path = out_dir.join("equity_curve.csv");
-    engine::output::write_equity_curve_csv(engine.equity_history(), &equity_path)?;
-    println!("Wrote equity curve to {:?}", equity_path);
+    let mut crv_report = match quality {
+        Some(quality) => verifier.verify_with_data_quality(
+            &stats,
+            engine.fills(),
+            engine.equity_history(),
+            quality,
+        )?,
+        None => verifier.verify(&stats, engine.fills(), engine.equity_history())?,
+    };
+    verifier.verify_exposure(engine.exposure_history(), &mut crv_report);
+    let risk_rejections: Vec<OrderRejection> =
+        risk_rejections(engine.rejections()).cloned().collect();
+    verifier.verify_risk_rejections(&risk_rejections, &mut crv_report);
+    let kill_switch_trips: Vec<KillSwitchTrip> =
+        engine.kill_switch().copied().into_iter().collect();
+    verifier.verify_kill_switch(&kill_switch_trips, &mut crv_report);
 
-    let stats = engine::output::calculate_stats(
-        engine.equity_history(),
-        engine.num_trades(),
-        engine.total_commission(),
+    Ok(BacktestOutcome {
+        stats,
+        fills: engine.fills().to_vec(),
+        equity_history: engine.equity_history().to_vec(),
+        equity_points: engine.equity_points().to_vec(),
+        exposure_history: engine.exposure_history().to_vec(),
+        round_trips: engine.round_trips().to_vec(),
+        lot_realizations: engine.lot_realizations().to_vec(),
+        crv_report,
+        order_events: engine.order_events().to_vec(),
+        risk_rejections,
+        trace: engine.trace().cloned(),
+        sleeves: Vec::new(),
+        kill_switch_trips,
+    })
+}
+
+/// Confidence intervals of the run's stats, when its spec asks for them
+pub fn bootstrap_intervals(
+    spec: &BacktestSpec,
+    equity_history: &[(i64, f64)],
+) -> Option<BootstrapStats> {
+    let bootstrap = spec.metrics.bootstrap.as_ref()?;
+    let config = BootstrapConfig {
+        samples: bootstrap.samples,
+        confidence: bootstrap.confidence,
+        block_size: bootstrap.block_size,
+        seed: spec.seed,
+    };
+    engine::bootstrap::bootstrap_stats(equity_history, spec.metrics.annualization(), &config)
+}
+
+/// Rejections made by a pre-trade risk check rather than the broker
+fn risk_rejections(rejections: &[OrderRejection]) -> impl Iterator<Item = &OrderRejection> {
+    rejections
+        .iter()
+        .filter(|r| matches!(r.reason, RejectionReason::RiskLimit { .. }))
+}
+
+/// Create the broker described by the spec, seeded for determinism, holding
+/// orders through the data's `halts`
+pub fn build_broker(spec: &BacktestSpec, halts: &TradingHalts) -> SimpleBroker<Box<dyn CostModel>> {
+    let policy = match spec.execution.fill_policy {
+        FillPolicy::Close => broker_sim::FillPolicy::Close,
+        FillPolicy::NextOpen => broker_sim::FillPolicy::NextOpen,
+    };
+    let broker = SimpleBroker::new(build_cost_model(&spec.cost_model, spec.seed), spec.seed)
+        .with_fill_policy(policy);
+    let execution = &spec.execution;
+    let broker = match execution.max_participation {
+        Some(fraction) => broker.with_max_participation(fraction),
+        None => broker,
+    };
+    let latency = match (execution.latency_bars, execution.latency_ms) {
+        (Some(bars), _) => Some(Latency::Bars(bars)),
+        (None, Some(base)) => Some(Latency::Millis {
+            base,
+            jitter: execution.latency_jitter_ms.unwrap_or(0),
+        }),
+        (None, None) => None,
+    };
+    let broker = match latency {
+        Some(latency) => broker.with_latency(latency),
+        None => broker,
+    };
+    let broker = match execution.intrabar_steps {
+        Some(steps) => broker.with_intrabar_path(steps as usize),
+        None => broker,
+    };
+    let broker = match (&execution.margin, execution.margin_multiplier) {
+        (Some(margin), _) => broker.with_margin(margin.symbols.iter().fold(
+            MarginSchedule::new(margin_requirement(&margin.default)),
+            |schedule, (symbol, requirement)| {
+                schedule.with_symbol(symbol.clone(), margin_requirement(requirement))
+            },
+        )),
+        (None, Some(multiplier)) => broker.with_margin_multiplier(multiplier),
+        (None, None) => broker,
+    };
+    let broker = match (execution.algo, execution.algo_bars) {
+        (Some(algo), Some(bars)) => broker.with_execution_algo(broker_sim::ExecutionAlgo {
+            kind: match algo {
+                ExecutionAlgo::Twap => AlgoKind::Twap,
+                ExecutionAlgo::Vwap => AlgoKind::Vwap,
+            },
+            bars,
+        }),
+        _ => broker,
+    };
+    let broker = match &execution.funding {
+        Some(funding) => broker.with_funding(funding_schedule(funding)),
+        None => broker,
+    };
+    let broker = match &execution.instruments {
+        Some(instruments) => broker.with_instruments(instrument_table(instruments)),
+        None => broker,
+    };
+    let broker = match &execution.fx {
+        Some(fx) => broker.with_fx_conversion(fx_conversion(fx)),
+        None => broker,
+    };
+    let broker = if halts.is_empty() {
+        broker
+    } else {
+        let policy = match execution.halt_policy {
+            crate::spec::HaltPolicy::Queue => HaltPolicy::Queue,
+            crate::spec::HaltPolicy::Cancel => HaltPolicy::Cancel,
+        };
+        broker.with_halts(halts.clone(), policy)
+    };
+    if execution.easy_to_borrow.is_none() && execution.hard_to_borrow.is_empty() {
+        return broker;
+    }
+    let shorts = execution.hard_to_borrow.iter().fold(
+        ShortAvailability::new(execution.borrow_fee_rate.unwrap_or(0.0))
+            .easy_to_borrow(execution.easy_to_borrow.iter().flatten().cloned()),
+        |shorts, (symbol, terms)| {
+            shorts.hard_to_borrow(
+                symbol.clone(),
+                HardToBorrow {
+                    fee_rate: terms.fee_rate,
+                    locate_probability: terms.locate_probability,
+                },
+            )
+        },
     );
+    broker.with_short_availability(shorts)
+}
 
-    let stats_path = out_dir.join("stats.json");
-    engine::output::write_stats_json(&stats, &stats_path)?;
-    println!("Wrote statistics to {:?}", stats_path);
+fn funding_schedule(spec: &FundingSpec) -> FundingSchedule {
+    spec.perpetuals.iter().fold(
+        FundingSchedule::new(i64::from(spec.interval_hours) * 3_600),
+        |schedule, (symbol, source)| {
+            let rate = match source.rate {
+                Some(rate) => FundingRate::Fixed(rate),
+                None => {
+                    FundingRate::Series(source.rates.iter().map(|p| (p.from, p.rate)).collect())
+                }
+            };
+            schedule.perpetual(symbol.clone(), rate)
+        },
+    )
+}
 
-    // Run CRV verification
-    println!("\n=== Running CRV Verification ===");
-    let constraints = PolicyConstraints::default();
-    let verifier = CRVVerifier::new(constraints);
+fn fx_conversion(spec: &FxSpec) -> FxConversion {
+    let fx = spec.spreads.iter().fold(
+        FxConversion::new(spec.base.clone()),
+        |fx, (currency, spread)| fx.with_spread(currency.clone(), *spread),
+    );
+    spec.symbols.iter().fold(fx, |fx, (symbol, currency)| {
+        fx.with_symbol(symbol.clone(), currency.clone())
+    })
+}
+
+fn currencies(spec: &FxSpec) -> Currencies {
+    spec.symbols.iter().fold(
+        Currencies::new(spec.base.clone()),
+        |currencies, (symbol, currency)| currencies.with_symbol(symbol.clone(), currency.clone()),
+    )
+}
+
+fn instrument_table(spec: &InstrumentsSpec) -> InstrumentTable {
+    let rounding = match spec.rounding {
+        Rounding::Snap => broker_sim::Rounding::Snap,
+        Rounding::Reject => broker_sim::Rounding::Reject,
+    };
+    spec.symbols.iter().fold(
+        InstrumentTable::new(instrument_rules(&spec.default), rounding),
+        |table, (symbol, rules)| table.with_symbol(symbol.clone(), instrument_rules(rules)),
+    )
+}
+
+fn instrument_rules(spec: &InstrumentRulesSpec) -> InstrumentRules {
+    InstrumentRules {
+        lot_size: spec.lot_size,
+        tick_size: spec.tick_size,
+    }
+}
+
+fn margin_requirement(spec: &MarginRequirementSpec) -> MarginRequirement {
+    MarginRequirement {
+        initial: spec.initial,
+        maintenance: spec.maintenance,
+    }
+}
+
+/// Create the cost model described by the spec
+/// The cost model a spec describes; random ones draw from `seed`
+pub fn build_cost_model(spec: &CostModelSpec, seed: u64) -> Box<dyn CostModel> {
+    match spec {
+        CostModelSpec::FixedPerShare {
+            cost_per_share,
+            minimum_commission,
+        } => Box::new(FixedPerShareCost::new(*cost_per_share, *minimum_commission)),
+        CostModelSpec::Percentage {
+            percentage,
+            minimum_commission,
+        } => Box::new(PercentageCost::new(*percentage, *minimum_commission)),
+        CostModelSpec::VolumeParticipation {
+            percentage,
+            minimum_commission,
+            impact,
+            exponent,
+        } => Box::new(VolumeParticipationCost::new(
+            *percentage,
+            *minimum_commission,
+            *impact,
+            *exponent,
+        )),
+        CostModelSpec::StochasticSlippage {
+            percentage,
+            minimum_commission,
+            distribution,
+        } => Box::new(StochasticSlippageCost::new(
+            *percentage,
+            *minimum_commission,
+            slippage_distribution(*distribution),
+            seed,
+        )),
+        CostModelSpec::Zero => Box::new(ZeroCost),
+        CostModelSpec::Composite {
+            default,
+            asset_classes,
+            symbols,
+            symbol_classes,
+        } => {
+            let mut composite = CompositeCostModel::new(build_cost_model(default, seed));
+            for (asset_class, model) in asset_classes {
+                composite = composite.with_asset_class(*asset_class, build_cost_model(model, seed));
+            }
+            for (symbol, model) in symbols {
+                composite = composite.with_symbol(symbol.clone(), build_cost_model(model, seed));
+            }
+            for (symbol, asset_class) in symbol_classes {
+                composite = composite.with_symbol_class(symbol.clone(), *asset_class);
+            }
+            Box::new(composite)
+        }
+        CostModelSpec::Scaled { multiplier, model } => {
+            Box::new(ScaledCost::new(build_cost_model(model, seed), *multiplier))
+        }
+    }
+}
+
+fn slippage_distribution(spec: SlippageDistributionSpec) -> SlippageDistribution {
+    match spec {
+        SlippageDistributionSpec::Uniform { low, high } => {
+            SlippageDistribution::Uniform { low, high }
+        }
+        SlippageDistributionSpec::Normal { mean, std_dev } => {
+            SlippageDistribution::Normal { mean, std_dev }
+        }
+        SlippageDistributionSpec::Exponential { mean } => {
+            SlippageDistribution::Exponential { mean }
+        }
+    }
+}
 
-    let crv_report = verifier.verify(&stats, engine.fills(), engine.equity_history())?;
+/// Write trades, equity curve, stats, order events and CRV report into `out_dir`
+pub fn write_outputs(outcome: &BacktestOutcome, out_dir: &Path) -> Result<()> {
+    engine::output::write_trades_csv(&outcome.fills, &out_dir.join("trades.csv"))?;
+    engine::output::write_equity_points_csv(
+        &outcome.equity_points,
+        &out_dir.join("equity_curve.csv"),
+    )?;
+    engine::output::write_exposure_csv(&outcome.exposure_history, &out_dir.join("exposure.csv"))?;
+    engine::output::write_round_trips_csv(
+        &outcome.round_trips,
+        &out_dir.join("trades-roundtrip.csv"),
+    )?;
+    engine::output::write_tax_lots_csv(&outcome.lot_realizations, &out_dir.join("tax_lots.csv"))?;
+    engine::output::write_stats_json(&outcome.stats, &out_dir.join("stats.json"))?;
+    engine::output::write_order_events_jsonl(
+        &outcome.order_events,
+        &out_dir.join("order_events.jsonl"),
+    )?;
+    if !outcome.sleeves.is_empty() {
+        engine::output::write_sleeve_stats_json(
+            &outcome.sleeves,
+            &out_dir.join("strategies.json"),
+        )?;
+    }
+    if !outcome.kill_switch_trips.is_empty() {
+        engine::output::write_kill_switch_json(
+            &outcome.kill_switch_trips,
+            &out_dir.join("kill_switch.json"),
+        )?;
+    }
 
-    let crv_path = out_dir.join("crv_report.json");
-    let crv_file = fs::File::create(&crv_path)?;
-    serde_json::to_writer_pretty(crv_file, &crv_report)?;
-    println!("Wrote CRV report to {:?}", crv_path);
+    let crv_file = fs::File::create(out_dir.join("crv_report.json"))?;
+    serde_json::to_writer_pretty(crv_file, &outcome.crv_report)?;
+    Ok(())
+}
 
+pub fn print_crv_report(crv_report: &CRVReport) {
     if crv_report.passed {
         println!("✓ CRV verification passed");
     } else {
@@ -141,122 +976,111 @@ fn run_backtest_with_strategy<S: schema::Strategy>(
             }
         }
     }
+}
+
+/// One line of stats per sleeve of a multi-strategy run
+fn print_sleeves(sleeves: &[SleeveStats]) {
+    if sleeves.is_empty() {
+        return;
+    }
+    println!("\n=== Strategies ===");
+    for sleeve in sleeves {
+        println!(
+            "{} ({:.0}% of capital): return {:.2}%, Sharpe {:.4}, max drawdown {:.2}%, {} trades",
+            sleeve.name,
+            sleeve.allocation * 100.0,
+            sleeve.stats.total_return * 100.0,
+            sleeve.stats.sharpe_ratio,
+            sleeve.stats.max_drawdown * 100.0,
+            sleeve.stats.num_trades
+        );
+    }
+}
 
-    // Print summary
+pub fn print_summary(stats: &BacktestStats) {
     println!("\n=== Backtest Summary ===");
     println!("Initial equity: ${:.2}", stats.initial_equity);
     println!("Final equity: ${:.2}", stats.final_equity);
     println!("Total return: {:.2}%", stats.total_return * 100.0);
     println!("Number of trades: {}", stats.num_trades);
     println!("Total commission: ${:.2}", stats.total_commission);
+    println!("Exchange fees: ${:.2}", stats.costs.exchange_fees);
+    println!("Slippage: ${:.2}", stats.costs.slippage);
+    println!("Borrow fees on fills: ${:.2}", stats.costs.borrow);
+    println!("Other costs: ${:.2}", stats.costs.other);
     println!("Sharpe ratio: {:.4}", stats.sharpe_ratio);
+    let performance = &stats.performance;
+    println!("Sortino ratio: {:.4}", performance.sortino_ratio);
+    println!("Calmar ratio: {:.4}", performance.calmar_ratio);
+    println!(
+        "Annualized volatility: {:.2}%",
+        performance.annualized_volatility * 100.0
+    );
     println!("Max drawdown: {:.2}%", stats.max_drawdown * 100.0);
-
-    Ok(())
-}
-
-fn load_bars_from_parquet_legacy(path: &Path) -> Result<Vec<Bar>> {
-    let df = LazyFrame::scan_parquet(path, Default::default())?.collect()?;
-
-    let timestamps = df
-        .column("timestamp")?
-        .i64()?
-        .into_no_null_iter()
-        .collect::<Vec<_>>();
-    let symbols = df.column("symbol")?.str()?.into_iter().collect::<Vec<_>>();
-    let opens = df
-        .column("open")?
-        .f64()?
-        .into_no_null_iter()
-        .collect::<Vec<_>>();
-    let highs = df
-        .column("high")?
-        .f64()?
-        .into_no_null_iter()
-        .collect::<Vec<_>>();
-    let lows = df
-        .column("low")?
-        .f64()?
-        .into_no_null_iter()
-        .collect::<Vec<_>>();
-    let closes = df
-        .column("close")?
-        .f64()?
-        .into_no_null_iter()
-        .collect::<Vec<_>>();
-    let volumes = df
-        .column("volume")?
-        .f64()?
-        .into_no_null_iter()
-        .collect::<Vec<_>>();
-
-    let bars = timestamps
-        .iter()
-        .zip(symbols.iter())
-        .zip(opens.iter())
-        .zip(highs.iter())
-        .zip(lows.iter())
-        .zip(closes.iter())
-        .zip(volumes.iter())
-        .map(|((((((t, s), o), h), l), c), v)| Bar {
-            timestamp: *t,
-            symbol: s.unwrap_or("UNKNOWN").to_string(),
-            open: *o,
-            high: *h,
-            low: *l,
-            close: *c,
-            volume: *v,
-        })
-        .collect();
-
-    Ok(bars)
-}
-
-fn load_bars_from_parquet_canonical_tier1(path: &Path) -> Result<Vec<Bar>> {
-    let legacy_bars = load_bars_from_parquet_legacy(path)?;
-    let mut events = bars_to_canonical_tier1_events(&legacy_bars, "legacy-parquet");
-
-    sort_events_deterministically(&mut events);
-    validate_events_for_tier(&events, FidelityTier::Tier1Bar)
-        .context("Canonical Tier 1 validation failed")?;
-
-    canonical_tier1_events_to_bars(&events)
-}
-
-fn bars_to_canonical_tier1_events(bars: &[Bar], source_id: &str) -> Vec<EventEnvelope> {
-    bars.iter()
-        .map(|bar| EventEnvelope {
-            event_type: MarketEventType::Bar,
-            symbol: bar.symbol.clone(),
-            event_time: bar.timestamp,
-            ingest_time: bar.timestamp,
-            source_id: source_id.to_string(),
-            quality_flags: vec![QualityFlag::DerivedValue],
-            payload: MarketEventPayload::Bar(bar.clone()),
-        })
-        .collect()
-}
-
-fn canonical_tier1_events_to_bars(events: &[EventEnvelope]) -> Result<Vec<Bar>> {
-    let mut bars = Vec::new();
-
-    for event in events {
-        event
-            .validate_required_fields()
-            .context("Invalid canonical event encountered")?;
-
-        if let MarketEventPayload::Bar(bar) = &event.payload {
-            bars.push(bar.clone());
-        }
+    println!(
+        "Closed trades: {} ({:.2}% won)",
+        performance.closed_trades,
+        performance.win_rate * 100.0
+    );
+    println!("Profit factor: {:.4}", performance.profit_factor);
+    println!("Average trade PnL: ${:.2}", performance.average_trade_pnl);
+    println!("Exposure time: {:.2}%", performance.exposure_time * 100.0);
+    if let Some(financing) = &stats.financing {
+        println!("Debit interest: ${:.2}", financing.debit_interest);
+        println!("Short borrow fees: ${:.2}", financing.short_borrow_fees);
+        println!("Credit interest: ${:.2}", financing.credit_interest);
+    }
+    if let Some(constrained) = &stats.constrained_orders {
+        println!(
+            "Constrained orders: {} downsized, {} refused",
+            constrained.downsized, constrained.rejected
+        );
+    }
+    if let Some(bootstrap) = &stats.bootstrap {
+        let level = bootstrap.confidence * 100.0;
+        let sharpe = bootstrap.sharpe_ratio;
+        println!(
+            "Sharpe ratio {:.0}% CI: [{:.4}, {:.4}]{}",
+            level,
+            sharpe.lower,
+            sharpe.upper,
+            if sharpe.excludes_zero() {
+                ""
+            } else {
+                " (not distinguishable from zero)"
+            }
+        );
+        println!(
+            "Total return {:.0}% CI: [{:.2}%, {:.2}%]",
+            level,
+            bootstrap.total_return.lower * 100.0,
+            bootstrap.total_return.upper * 100.0
+        );
+        println!(
+            "Max drawdown {:.0}% CI: [{:.2}%, {:.2}%]",
+            level,
+            bootstrap.max_drawdown.lower * 100.0,
+            bootstrap.max_drawdown.upper * 100.0
+        );
     }
-
-    Ok(bars)
 }
 
 impl BacktestSpec {
-    fn strategy_name(&self) -> &str {
+    pub fn strategy_name(&self) -> &str {
         match &self.strategy {
             StrategySpec::TsMomentum { .. } => "TsMomentum",
+            StrategySpec::BuyAndHold { .. } => "BuyAndHold",
+            StrategySpec::SmaCrossover { .. } => "SmaCrossover",
+            StrategySpec::MeanReversion { .. } => "MeanReversion",
+            StrategySpec::ChannelBreakout { .. } => "ChannelBreakout",
+            StrategySpec::CrossSectionalMomentum { .. } => "CrossSectionalMomentum",
+            StrategySpec::Pairs { .. } => "Pairs",
+            StrategySpec::FixedWeights { .. } => "FixedWeights",
+            StrategySpec::RiskParity { .. } => "RiskParity",
+            StrategySpec::Ensemble { .. } => "Ensemble",
+            StrategySpec::MultiStrategy { .. } => "MultiStrategy",
+            StrategySpec::Onnx { .. } => "Onnx",
+            StrategySpec::Wasm { .. } => "Wasm",
         }
     }
 }
@@ -264,69 +1088,289 @@ impl BacktestSpec {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::load_dataset;
+    use crate::init_cmd::run_init;
+    use crate::parquet_feed::tests::write_bars;
 
     #[test]
-    fn canonical_tier1_bridge_preserves_legacy_bars() {
-        let legacy = vec![
-            Bar {
-                timestamp: 1000,
-                symbol: "AAPL".to_string(),
-                open: 100.0,
-                high: 102.0,
-                low: 99.0,
-                close: 101.0,
-                volume: 10000.0,
-            },
-            Bar {
-                timestamp: 2000,
-                symbol: "AAPL".to_string(),
-                open: 101.0,
-                high: 103.0,
-                low: 100.0,
-                close: 102.0,
-                volume: 11000.0,
-            },
-        ];
+    fn streamed_backtest_matches_a_loaded_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let spec = project.join("spec.json");
+        let scaffold = project.join("data/bars.parquet");
+        let bars = load_dataset(&scaffold, &DataPipelineSpec::Legacy)
+            .unwrap()
+            .bars;
+        let data = dir.path().join("legacy.parquet");
+        write_bars(&data, &bars, 50);
+        let columnar = dir.path().join("bars.arrow");
+        crate::columnar::write_columnar(&bars, &columnar).unwrap();
+        let (loaded, streamed) = (dir.path().join("loaded"), dir.path().join("streamed"));
+        let mapped = dir.path().join("mapped");
+        let set = [r#"metrics={"rolling_window": 20}"#.to_string()];
+        run_backtest(&spec, &set, &data, &loaded, &BacktestOptions::default()).unwrap();
+        run_streamed_backtest(&spec, &set, &data, &streamed, false).unwrap();
+        run_streamed_backtest(&spec, &set, &columnar, &mapped, false).unwrap();
 
-        let events = bars_to_canonical_tier1_events(&legacy, "legacy-parquet");
-        let recovered = canonical_tier1_events_to_bars(&events).unwrap();
+        for file in [
+            "trades.csv",
+            "equity_curve.csv",
+            "stats.json",
+            "rolling_metrics.csv",
+        ] {
+            let expected = fs::read(loaded.join(file)).unwrap();
+            assert_eq!(expected, fs::read(streamed.join(file)).unwrap(), "{file}");
+            assert_eq!(expected, fs::read(mapped.join(file)).unwrap(), "{file}");
+        }
+        let err = run_streamed_backtest(&spec, &[], &scaffold, &streamed, false);
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("single legacy bar parquet"));
+    }
+
+    #[test]
+    fn spec_benchmark_puts_relative_stats_in_stats_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let (spec, data) = (project.join("spec.json"), project.join("data/bars.parquet"));
+        let out = dir.path().join("out");
+        let set = ["benchmark=SYNTH".to_string()];
+        run_backtest(&spec, &set, &data, &out, &BacktestOptions::default()).unwrap();
+
+        let stats = engine::output::read_stats_json(&out.join("stats.json")).unwrap();
+        let relative = stats.benchmark.unwrap();
+        assert_eq!(relative.symbol, "SYNTH");
+        let (comparison, _) = load_benchmark(&out).unwrap().unwrap();
+        assert_eq!(comparison.relative, relative);
 
-        assert_eq!(legacy, recovered);
+        // Resuming keeps the checkpointed stats, relative ones included
+        let written = fs::read(out.join("stats.json")).unwrap();
+        let resume = BacktestOptions {
+            resume: true,
+            ..BacktestOptions::default()
+        };
+        run_backtest(&spec, &set, &data, &out, &resume).unwrap();
+        assert_eq!(fs::read(out.join("stats.json")).unwrap(), written);
+        let err = run_streamed_backtest(&spec, &set, &data, &dir.path().join("streamed"), false);
+        assert!(err.unwrap_err().to_string().contains("Benchmarks need"));
     }
 
     #[test]
-    fn tier_readiness_requires_trade_or_quote_for_tier2() {
-        let events = bars_to_canonical_tier1_events(
-            &[Bar {
-                timestamp: 1000,
-                symbol: "AAPL".to_string(),
-                open: 100.0,
-                high: 102.0,
-                low: 99.0,
-                close: 101.0,
-                volume: 10000.0,
-            }],
-            "legacy-parquet",
-        );
+    fn spec_monte_carlo_resamples_the_runs_trades() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let (spec, data) = (project.join("spec.json"), project.join("data/bars.parquet"));
+        let (out, again) = (dir.path().join("out"), dir.path().join("again"));
+        let set = [r#"monte_carlo={"simulations": 200, "block_size": 2}"#.to_string()];
+        run_backtest(&spec, &set, &data, &out, &BacktestOptions::default()).unwrap();
+        run_backtest(&spec, &set, &data, &again, &BacktestOptions::default()).unwrap();
+
+        let written = fs::read(out.join("monte_carlo.json")).unwrap();
+        assert_eq!(written, fs::read(again.join("monte_carlo.json")).unwrap());
+        let report: serde_json::Value = serde_json::from_slice(&written).unwrap();
+        let trips =
+            engine::output::read_round_trips_csv(&out.join("trades-roundtrip.csv")).unwrap();
+        assert_eq!(report["simulations"], 200);
+        assert_eq!(report["trades"], trips.len());
+        assert_eq!(report["seed"], 42);
+        assert_eq!(report["ruin_loss"], 0.5);
 
-        assert!(validate_events_for_tier(&events, FidelityTier::Tier2TickQuote).is_err());
+        let bad = [r#"monte_carlo={"ruin_loss": 2}"#.to_string()];
+        let err = run_backtest(&spec, &bad, &data, &out, &BacktestOptions::default());
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("monte_carlo.ruin_loss"));
     }
 
     #[test]
-    fn tier_readiness_requires_book_for_tier3() {
-        let events = bars_to_canonical_tier1_events(
-            &[Bar {
-                timestamp: 1000,
-                symbol: "AAPL".to_string(),
-                open: 100.0,
-                high: 102.0,
-                low: 99.0,
-                close: 101.0,
-                volume: 10000.0,
-            }],
-            "legacy-parquet",
-        );
+    fn spec_bootstrap_puts_confidence_intervals_in_stats_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let (spec, data) = (project.join("spec.json"), project.join("data/bars.parquet"));
+        let out = dir.path().join("out");
+        let set = [r#"metrics={"bootstrap": {"samples": 200}}"#.to_string()];
+        run_backtest(&spec, &set, &data, &out, &BacktestOptions::default()).unwrap();
+
+        let stats = engine::output::read_stats_json(&out.join("stats.json")).unwrap();
+        let bootstrap = stats.bootstrap.unwrap();
+        assert_eq!((bootstrap.samples, bootstrap.confidence), (200, 0.95));
+        let sharpe = bootstrap.sharpe_ratio;
+        assert!(sharpe.lower <= stats.sharpe_ratio && stats.sharpe_ratio <= sharpe.upper);
+
+        run_backtest(&spec, &[], &data, &out, &BacktestOptions::default()).unwrap();
+        let plain = fs::read_to_string(out.join("stats.json")).unwrap();
+        assert!(!plain.contains("bootstrap"));
+    }
+
+    #[test]
+    fn spec_multi_strategy_reports_each_sleeve_and_the_account() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let (spec, data) = (project.join("spec.json"), project.join("data/bars.parquet"));
+        let out = dir.path().join("out");
+        let set = [r#"strategy={"type": "multi_strategy", "sleeves": [
+            {"name": "trend", "allocation": 0.5,
+             "strategy": {"type": "sma_crossover", "symbol": "SYNTH", "fast": 10, "slow": 30}},
+            {"name": "hold", "allocation": 0.3,
+             "strategy": {"type": "buy_and_hold", "symbol": "SYNTH"}}]}"#
+            .to_string()];
+        run_backtest(&spec, &set, &data, &out, &BacktestOptions::default()).unwrap();
+
+        let sleeves = engine::output::read_sleeve_stats_json(&out.join("strategies.json")).unwrap();
+        let names: Vec<_> = sleeves.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["trend", "hold"]);
+        assert_eq!(sleeves[0].stats.initial_equity, 50000.0);
+        assert_eq!(sleeves[1].stats.initial_equity, 30000.0);
+        assert_eq!(sleeves[1].stats.num_trades, 1);
+
+        let stats = engine::output::read_stats_json(&out.join("stats.json")).unwrap();
+        assert_eq!(stats.initial_equity, 100000.0);
+        let trades: usize = sleeves.iter().map(|s| s.stats.num_trades).sum();
+        assert_eq!(stats.num_trades, trades);
+    }
+
+    #[test]
+    fn spec_kill_switch_flattens_the_run_and_logs_the_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let (spec, data) = (project.join("spec.json"), project.join("data/bars.parquet"));
+        let out = dir.path().join("out");
+        let set = [r#"kill_switch={"max_drawdown": 0.05}"#.to_string()];
+        run_backtest(&spec, &set, &data, &out, &BacktestOptions::default()).unwrap();
+
+        let trips = engine::output::read_kill_switch_json(&out.join("kill_switch.json")).unwrap();
+        assert_eq!(trips.len(), 1);
+        assert!(trips[0].drawdown > 0.05);
+        // Only the order closing the position trades after the trip
+        let fills = engine::output::read_trades_csv(&out.join("trades.csv")).unwrap();
+        let after: Vec<_> = fills
+            .iter()
+            .filter(|f| f.timestamp > trips[0].timestamp)
+            .collect();
+        assert_eq!(after.len(), 1);
+        let points = engine::output::read_equity_points_csv(&out.join("equity_curve.csv")).unwrap();
+        assert_eq!(points.last().unwrap().positions_value, 0.0);
+
+        let crv = fs::read_to_string(out.join("crv_report.json")).unwrap();
+        assert!(crv.contains("\"kill_switch\""));
+
+        let bad = [r#"kill_switch={"max_drawdown": 0}"#.to_string()];
+        let err = run_backtest(&spec, &bad, &data, &out, &BacktestOptions::default());
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("kill_switch.max_drawdown"));
+    }
+
+    #[test]
+    fn spec_policy_resizes_and_refuses_orders_before_the_broker() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let (spec, data) = (project.join("spec.json"), project.join("data/bars.parquet"));
+        let out = dir.path().join("out");
+        let set = [r#"policy={"max_position_size": 0.5, "max_turnover": 1.2}"#.to_string()];
+        let report = run_backtest(&spec, &set, &data, &out, &BacktestOptions::default()).unwrap();
+
+        // The strategy's 95% allocation is cut to half of equity
+        let fills = engine::output::read_trades_csv(&out.join("trades.csv")).unwrap();
+        assert!((fills[0].quantity * fills[0].price - 50_000.0).abs() < 1.0);
+        let refused = report
+            .violations
+            .iter()
+            .find(|v| v.severity == crv_verifier::Severity::Info)
+            .unwrap();
+        assert_eq!(refused.rule_id, crv_verifier::RuleId::TurnoverConstraint);
+        assert!(refused.message.contains("max_turnover limit of 1.20x"));
+    }
+
+    #[test]
+    fn strict_cash_mode_keeps_orders_within_cash_and_holdings() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let (spec, data) = (project.join("spec.json"), project.join("data/bars.parquet"));
+        let out = dir.path().join("out");
+        let set = [
+            r#"strategy={"type": "ts_momentum", "symbol": "SYNTH", "lookback": 20, "vol_target": 0.5, "vol_lookback": 20}"#.to_string(),
+            r#"cost_model={"type": "percentage", "percentage": 0.001, "minimum_commission": 0.0}"#.to_string(),
+            r#"execution={"cash_mode": "strict_no_shorts", "cash_reserve": 0.001}"#.to_string(),
+        ];
+        run_backtest(&spec, &set, &data, &out, &BacktestOptions::default()).unwrap();
+
+        let stats = engine::output::read_stats_json(&out.join("stats.json")).unwrap();
+        let constrained = stats.constrained_orders.unwrap();
+        assert!(constrained.downsized > 0 && constrained.rejected > 0);
+        // The reserve covers the commission, so cash never goes negative
+        let (mut cash, mut held) = (stats.initial_equity, 0.0);
+        for fill in engine::output::read_trades_csv(&out.join("trades.csv")).unwrap() {
+            let signed = match fill.side {
+                schema::Side::Buy => fill.quantity,
+                schema::Side::Sell => -fill.quantity,
+            };
+            cash -= signed * fill.price + fill.costs.charged();
+            held += signed;
+            assert!(cash > -1e-9 && held > -1e-9);
+        }
+    }
+
+    #[test]
+    fn determinism_check_fails_loudly_on_a_different_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let (spec, data) = (project.join("spec.json"), project.join("data/bars.parquet"));
+        let options = BacktestOptions {
+            check_determinism: true,
+            ..BacktestOptions::default()
+        };
+        run_backtest(&spec, &[], &data, &dir.path().join("out"), &options).unwrap();
+
+        let spec = load_spec(&spec, &[]).unwrap();
+        let dataset = load_dataset(&data, &spec.data_pipeline).unwrap();
+        let mut outcome =
+            execute_backtest(&spec, &dataset.bars, &dataset.halts, &dataset.quality).unwrap();
+        outcome.equity_history.last_mut().unwrap().1 += 0.01;
+        let err = check_determinism(&spec, &dataset, &outcome).unwrap_err();
+        assert!(err.to_string().contains("differ in equity"));
+    }
+
+    #[test]
+    fn committed_runs_carry_a_trace_of_the_engine() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        let (spec, data) = (project.join("spec.json"), project.join("data/bars.parquet"));
+        let repo_path = dir.path().join("repo");
+        let options = BacktestOptions {
+            repo: Some(&repo_path),
+            ..BacktestOptions::default()
+        };
+        run_backtest(&spec, &[], &data, &dir.path().join("out"), &options).unwrap();
 
-        assert!(validate_events_for_tier(&events, FidelityTier::Tier3OrderBook).is_err());
+        let repo = Repository::open(&repo_path).unwrap();
+        let commits = repo.all_commits().unwrap();
+        let entry = commits.iter().find(|c| c.artifact_type == "trace").unwrap();
+        let result = commits
+            .iter()
+            .find(|c| c.artifact_type == "backtest_result")
+            .unwrap();
+        assert_eq!(entry.parent_hashes, vec![result.artifact_hash.clone()]);
+        let hash = hipcortex::ContentHash::from_hex(entry.artifact_hash.clone());
+        let hipcortex::Artifact::Trace(trace) = repo.get(&hash).unwrap() else {
+            panic!("not a trace");
+        };
+        let bars = load_dataset(&data, &DataPipelineSpec::Legacy).unwrap().bars;
+        assert_eq!(trace.metadata["seed"], 42);
+        assert_eq!(trace.metadata["counts"]["bars"], bars.len());
+        assert_eq!(trace.metadata["strategy"], "SmaCrossover");
     }
 }
@@ -0,0 +1,411 @@
+use anyhow::{Context, Result};
+use crv_verifier::CRVReport;
+use engine::golden::RunHashes;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backtest_cmd::{
+    execute_backtest, load_spec, write_effective_spec, write_outputs, BacktestOutcome,
+};
+use crate::checkpoint::write_checkpoint;
+use crate::data::load_dataset;
+
+/// A list of backtests to run together.
+///
+/// Relative paths are resolved against the manifest's directory:
+/// `{"runs": [{"name": "mom", "spec": "specs/mom.json", "data": "data/aapl.parquet",
+/// "out": "runs/mom", "set": ["seed=7"]}]}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchManifest {
+    pub runs: Vec<BatchEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchEntry {
+    /// Defaults to the output directory's name
+    #[serde(default)]
+    pub name: Option<String>,
+    pub spec: PathBuf,
+    pub data: PathBuf,
+    pub out: PathBuf,
+    /// `--set` style overrides applied to the spec
+    #[serde(default)]
+    pub set: Vec<String>,
+}
+
+/// Outcome of one manifest entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchStatus {
+    Passed,
+    /// Ran, but CRV found violations
+    Failed,
+    /// Could not be run
+    Error,
+}
+
+/// Summary row for one manifest entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRow {
+    pub name: String,
+    pub out: PathBuf,
+    pub status: BatchStatus,
+    pub total_return: Option<f64>,
+    pub sharpe_ratio: Option<f64>,
+    pub max_drawdown: Option<f64>,
+    pub num_trades: Option<usize>,
+    pub crv_violations: Option<usize>,
+    /// Canonical hashes of the run's fills, equity and stats
+    pub hashes: Option<RunHashes>,
+    pub error: Option<String>,
+}
+
+/// Everything a batch produced, in manifest order
+pub struct BatchResult {
+    pub rows: Vec<BatchRow>,
+    /// CRV reports of the entries that ran
+    pub crv_reports: Vec<(String, CRVReport)>,
+}
+
+impl BatchResult {
+    pub fn errors(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|r| r.status == BatchStatus::Error)
+            .count()
+    }
+}
+
+impl BatchManifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open batch manifest {:?}", path))?;
+        let mut manifest: BatchManifest =
+            serde_json::from_reader(file).context("Failed to parse batch manifest JSON")?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        for entry in &mut manifest.runs {
+            entry.spec = base.join(&entry.spec);
+            entry.data = base.join(&entry.data);
+            entry.out = base.join(&entry.out);
+            if entry.name.is_none() {
+                entry.name = entry
+                    .out
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned());
+            }
+        }
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.runs.is_empty() {
+            anyhow::bail!("Batch manifest lists no runs");
+        }
+        let mut names = BTreeSet::new();
+        let mut outs = BTreeSet::new();
+        for entry in &self.runs {
+            let name = entry.name();
+            if !names.insert(name) {
+                anyhow::bail!("Batch manifest has more than one run named '{}'", name);
+            }
+            if !outs.insert(&entry.out) {
+                anyhow::bail!("Batch manifest writes more than one run to {:?}", entry.out);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BatchEntry {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("run")
+    }
+}
+
+/// Run every manifest entry with up to `jobs` at a time and summarize them in `out_dir`.
+///
+/// Each run builds its own broker from its spec's seed, so results do not
+/// depend on `jobs` or on which runs share a thread. A failing entry is
+/// recorded as an error rather than stopping the batch.
+pub fn run_batch(manifest_path: &Path, out_dir: &Path, jobs: usize) -> Result<BatchResult> {
+    let manifest = BatchManifest::load(manifest_path)?;
+    println!(
+        "Running {} backtest(s) from {:?} ({} at a time)",
+        manifest.runs.len(),
+        manifest_path,
+        jobs
+    );
+
+    let results: Vec<Result<(BacktestOutcome, RunHashes)>> = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.max(1))
+        .build()
+        .context("Failed to build batch thread pool")?
+        .install(|| manifest.runs.par_iter().map(run_entry).collect());
+
+    let mut rows = Vec::with_capacity(results.len());
+    let mut crv_reports = Vec::new();
+    for (entry, result) in manifest.runs.iter().zip(results) {
+        let name = entry.name().to_string();
+        let row = match result {
+            Ok((
+                BacktestOutcome {
+                    stats, crv_report, ..
+                },
+                hashes,
+            )) => {
+                let row = BatchRow {
+                    name: name.clone(),
+                    out: entry.out.clone(),
+                    status: if crv_report.passed {
+                        BatchStatus::Passed
+                    } else {
+                        BatchStatus::Failed
+                    },
+                    total_return: Some(stats.total_return),
+                    sharpe_ratio: Some(stats.sharpe_ratio),
+                    max_drawdown: Some(stats.max_drawdown),
+                    num_trades: Some(stats.num_trades),
+                    crv_violations: Some(crv_report.violation_count()),
+                    hashes: Some(hashes),
+                    error: None,
+                };
+                crv_reports.push((name, crv_report));
+                row
+            }
+            Err(err) => BatchRow {
+                name,
+                out: entry.out.clone(),
+                status: BatchStatus::Error,
+                total_return: None,
+                sharpe_ratio: None,
+                max_drawdown: None,
+                num_trades: None,
+                crv_violations: None,
+                hashes: None,
+                error: Some(format!("{:#}", err)),
+            },
+        };
+        rows.push(row);
+    }
+
+    let result = BatchResult { rows, crv_reports };
+    fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+    write_summary(&result.rows, out_dir)?;
+    print_summary(&result);
+    println!(
+        "Wrote batch summary to {:?}",
+        out_dir.join("batch_summary.csv")
+    );
+    Ok(result)
+}
+
+/// Backtest one entry into its output directory
+fn run_entry(entry: &BatchEntry) -> Result<(BacktestOutcome, RunHashes)> {
+    let spec = load_spec(&entry.spec, &entry.set)?;
+    let dataset = load_dataset(&entry.data, &spec.data_pipeline)?;
+    let outcome = execute_backtest(&spec, &dataset.bars, &dataset.halts, &dataset.quality)?;
+
+    fs::create_dir_all(&entry.out).context("Failed to create output directory")?;
+    let spec_hash = write_effective_spec(&spec, &entry.out)?;
+    write_outputs(&outcome, &entry.out)?;
+    let quality_file = fs::File::create(entry.out.join("data_quality.json"))?;
+    serde_json::to_writer_pretty(quality_file, &dataset.quality)?;
+    write_checkpoint(
+        &entry.out,
+        &spec_hash,
+        &engine::canonical_json_hash(&dataset.bars)?,
+    )?;
+    let hashes = RunHashes::of(&outcome.fills, &outcome.equity_history, &outcome.stats)?;
+    Ok((outcome, hashes))
+}
+
+fn write_summary(rows: &[BatchRow], out_dir: &Path) -> Result<()> {
+    let json_file = fs::File::create(out_dir.join("batch_summary.json"))?;
+    serde_json::to_writer_pretty(json_file, rows)?;
+
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    let mut wtr = csv::Writer::from_path(out_dir.join("batch_summary.csv"))?;
+    wtr.write_record([
+        "name",
+        "status",
+        "total_return",
+        "sharpe_ratio",
+        "max_drawdown",
+        "num_trades",
+        "crv_violations",
+        "fills_hash",
+        "equity_hash",
+        "stats_hash",
+        "out",
+        "error",
+    ])?;
+    for row in rows {
+        wtr.write_record([
+            row.name.clone(),
+            serde_json::to_value(&row.status)?
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            optional(row.total_return.map(|v| v.to_string())),
+            optional(row.sharpe_ratio.map(|v| v.to_string())),
+            optional(row.max_drawdown.map(|v| v.to_string())),
+            optional(row.num_trades.map(|v| v.to_string())),
+            optional(row.crv_violations.map(|v| v.to_string())),
+            optional(row.hashes.as_ref().map(|h| h.fills.clone())),
+            optional(row.hashes.as_ref().map(|h| h.equity.clone())),
+            optional(row.hashes.as_ref().map(|h| h.stats.clone())),
+            row.out.display().to_string(),
+            optional(row.error.clone()),
+        ])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+fn print_summary(result: &BatchResult) {
+    println!("\n=== Batch Results ===");
+    for row in &result.rows {
+        match (&row.status, &row.error) {
+            (BatchStatus::Error, Some(error)) => {
+                println!("{:<24} error  {}", row.name, error)
+            }
+            (status, _) => println!(
+                "{:<24} {:<6} return={:.2}%  sharpe={:.4}  max_dd={:.2}%  trades={}  crv_violations={}",
+                row.name,
+                if *status == BatchStatus::Passed { "pass" } else { "fail" },
+                row.total_return.unwrap_or_default() * 100.0,
+                row.sharpe_ratio.unwrap_or_default(),
+                row.max_drawdown.unwrap_or_default() * 100.0,
+                row.num_trades.unwrap_or_default(),
+                row.crv_violations.unwrap_or_default()
+            ),
+        }
+    }
+    let passed = result
+        .rows
+        .iter()
+        .filter(|r| r.status == BatchStatus::Passed)
+        .count();
+    println!(
+        "CRV: {} of {} run(s) passed, {} failed, {} error(s)",
+        passed,
+        result.rows.len(),
+        result.crv_reports.len() - passed,
+        result.errors()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_manifest_and_records_errors_without_stopping() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/data.parquet");
+        fs::write(
+            dir.path().join("spec.json"),
+            serde_json::json!({
+                "initial_cash": 100000.0,
+                "seed": 1,
+                "strategy": {"type": "buy_and_hold", "symbol": "AAPL"},
+                "cost_model": {"type": "zero"}
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("batch.json"),
+            serde_json::json!({"runs": [
+                {"spec": "spec.json", "data": data, "out": "runs/hold"},
+                {"name": "bad", "spec": "missing.json", "data": data, "out": "runs/bad"},
+                {"name": "seeded", "spec": "spec.json", "data": data, "out": "runs/seeded",
+                 "set": ["seed=2"]}
+            ]})
+            .to_string(),
+        )
+        .unwrap();
+
+        let result = run_batch(
+            &dir.path().join("batch.json"),
+            &dir.path().join("summary"),
+            2,
+        )
+        .unwrap();
+        let names: Vec<&str> = result.rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["hold", "bad", "seeded"]);
+        assert_eq!(result.errors(), 1);
+        assert_eq!(result.crv_reports.len(), 2);
+        assert!(dir.path().join("runs/seeded/stats.json").exists());
+        assert!(dir.path().join("summary/batch_summary.csv").exists());
+
+        fs::write(
+            dir.path().join("dupe.json"),
+            r#"{"runs": [{"spec": "a", "data": "d", "out": "x"}, {"spec": "b", "data": "d", "out": "x"}]}"#,
+        )
+        .unwrap();
+        assert!(BatchManifest::load(&dir.path().join("dupe.json")).is_err());
+    }
+
+    #[test]
+    fn parallel_and_serial_batches_hash_the_same() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/data.parquet");
+        fs::write(
+            dir.path().join("spec.json"),
+            serde_json::json!({
+                "initial_cash": 100000.0,
+                "seed": 1,
+                "strategy": {
+                    "type": "ts_momentum",
+                    "symbol": "AAPL",
+                    "lookback": 5,
+                    "vol_target": 0.15,
+                    "vol_lookback": 5
+                },
+                "cost_model": {
+                    "type": "stochastic_slippage",
+                    "percentage": 0.001,
+                    "minimum_commission": 1.0,
+                    "distribution": {"type": "normal", "mean": 0.0005, "std_dev": 0.001}
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let runs: Vec<serde_json::Value> = (1..=6)
+            .map(|seed| {
+                serde_json::json!({"spec": "spec.json", "data": data,
+                    "out": format!("runs/seed_{}", seed), "set": [format!("seed={}", seed)]})
+            })
+            .collect();
+        fs::write(
+            dir.path().join("batch.json"),
+            serde_json::json!({ "runs": runs }).to_string(),
+        )
+        .unwrap();
+
+        let hashes = |jobs: usize| -> Vec<RunHashes> {
+            run_batch(
+                &dir.path().join("batch.json"),
+                &dir.path().join(format!("summary_{}", jobs)),
+                jobs,
+            )
+            .unwrap()
+            .rows
+            .into_iter()
+            .map(|row| row.hashes.unwrap())
+            .collect()
+        };
+        let serial = hashes(1);
+        assert_eq!(serial, hashes(4));
+        // Seeds drive the slippage draws, so the runs are not all one result
+        assert_ne!(serial[0], serial[1]);
+    }
+}
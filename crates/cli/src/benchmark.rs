@@ -0,0 +1,345 @@
+use anyhow::{Context, Result};
+use schema::{BacktestStats, Bar, RelativeStats};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::backtest_cmd::{execute_backtest, BacktestOutcome};
+use crate::data::{load_dataset, LoadedDataset};
+use crate::report_cmd::last_per_timestamp;
+use crate::spec::{BacktestSpec, StrategySpec};
+
+/// Where `--benchmark` takes its bars from
+#[derive(Debug, Clone, PartialEq)]
+pub enum BenchmarkSource {
+    /// A symbol present in the backtest data
+    Symbol(String),
+    /// A separate parquet file, directory or glob holding one symbol
+    Data(String),
+}
+
+impl BenchmarkSource {
+    /// Anything that looks like a path is read as a dataset, otherwise a symbol
+    pub fn parse(value: &str) -> Self {
+        let looks_like_path = Path::new(value).exists()
+            || value.ends_with(".parquet")
+            || value.contains(['/', '\\', '*']);
+        if looks_like_path {
+            BenchmarkSource::Data(value.to_string())
+        } else {
+            BenchmarkSource::Symbol(value.to_string())
+        }
+    }
+}
+
+/// Buy-and-hold benchmark stats and the strategy's performance relative to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkComparison {
+    /// `data` when taken from the backtest data, else the benchmark dataset path
+    pub source: String,
+    pub stats: BacktestStats,
+    #[serde(flatten)]
+    pub relative: RelativeStats,
+}
+
+/// Run buy-and-hold on the benchmark with the spec's cash and costs
+pub fn run_benchmark(
+    spec: &BacktestSpec,
+    source: &BenchmarkSource,
+    dataset: &LoadedDataset,
+    strategy: &BacktestOutcome,
+) -> Result<(BenchmarkComparison, BacktestOutcome)> {
+    let loaded;
+    let (symbol, dataset, source_label) = match source {
+        BenchmarkSource::Symbol(symbol) => (symbol.clone(), dataset, "data".to_string()),
+        BenchmarkSource::Data(path) => {
+            loaded = load_dataset(Path::new(path), &spec.data_pipeline)
+                .with_context(|| format!("Failed to load benchmark data {:?}", path))?;
+            (symbol_of(&loaded.bars)?, &loaded, path.clone())
+        }
+    };
+
+    let bars: Vec<Bar> = dataset
+        .bars
+        .iter()
+        .filter(|b| b.symbol == symbol)
+        .cloned()
+        .collect();
+    if bars.is_empty() {
+        anyhow::bail!("Benchmark symbol '{}' has no bars in the data", symbol);
+    }
+
+    let mut benchmark_spec = spec.clone();
+    benchmark_spec.strategy = StrategySpec::BuyAndHold {
+        symbol: symbol.clone(),
+        allocation: 1.0,
+    };
+    benchmark_spec.universe = None;
+    let outcome = execute_backtest(&benchmark_spec, &bars, &dataset.halts, &dataset.quality)?;
+
+    let comparison = compare(
+        symbol,
+        source_label,
+        &strategy.stats,
+        &strategy.equity_history,
+        &outcome.stats,
+        &outcome.equity_history,
+        spec.metrics.annualization().periods_per_year,
+    );
+    Ok((comparison, outcome))
+}
+
+/// The single symbol of a benchmark dataset
+fn symbol_of(bars: &[Bar]) -> Result<String> {
+    let symbols: BTreeSet<&str> = bars.iter().map(|b| b.symbol.as_str()).collect();
+    match symbols.len() {
+        1 => Ok(symbols.into_iter().next().unwrap().to_string()),
+        0 => anyhow::bail!("Benchmark data has no bars"),
+        _ => anyhow::bail!(
+            "Benchmark data must hold one symbol, found {}",
+            symbols.into_iter().collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+/// Relative metrics over the timestamps both equity curves share,
+/// annualized over `periods_per_year` as in `calculate_stats`
+pub fn compare(
+    symbol: String,
+    source: String,
+    strategy_stats: &BacktestStats,
+    strategy_equity: &[(i64, f64)],
+    benchmark_stats: &BacktestStats,
+    benchmark_equity: &[(i64, f64)],
+    periods_per_year: f64,
+) -> BenchmarkComparison {
+    let aligned = align(strategy_equity, benchmark_equity);
+    let (strategy_returns, benchmark_returns): (Vec<f64>, Vec<f64>) = aligned
+        .windows(2)
+        .filter(|w| w[0].1 > 0.0 && w[0].2 > 0.0)
+        .map(|w| (w[1].1 / w[0].1 - 1.0, w[1].2 / w[0].2 - 1.0))
+        .unzip();
+
+    let active: Vec<f64> = strategy_returns
+        .iter()
+        .zip(&benchmark_returns)
+        .map(|(s, b)| s - b)
+        .collect();
+    let tracking_error = variance(&active).sqrt() * periods_per_year.sqrt();
+    let information_ratio = if tracking_error > 0.0 {
+        mean(&active) * periods_per_year / tracking_error
+    } else {
+        0.0
+    };
+
+    let benchmark_variance = variance(&benchmark_returns);
+    let covariance = covariance(&strategy_returns, &benchmark_returns);
+    let beta = if benchmark_variance > 0.0 {
+        covariance / benchmark_variance
+    } else {
+        0.0
+    };
+    let alpha = (mean(&strategy_returns) - beta * mean(&benchmark_returns)) * periods_per_year;
+    let spread = (variance(&strategy_returns) * benchmark_variance).sqrt();
+    let correlation = if spread > 0.0 {
+        covariance / spread
+    } else {
+        0.0
+    };
+
+    BenchmarkComparison {
+        source,
+        stats: benchmark_stats.clone(),
+        relative: RelativeStats {
+            symbol,
+            excess_return: strategy_stats.total_return - benchmark_stats.total_return,
+            tracking_error,
+            information_ratio,
+            beta,
+            alpha,
+            correlation,
+            up_capture: capture(&strategy_returns, &benchmark_returns, |b| b > 0.0),
+            down_capture: capture(&strategy_returns, &benchmark_returns, |b| b < 0.0),
+        },
+    }
+}
+
+/// Mean strategy return over mean benchmark return, on the bars whose
+/// benchmark return passes `keep`
+fn capture(strategy: &[f64], benchmark: &[f64], keep: impl Fn(f64) -> bool) -> f64 {
+    let (strategy, benchmark): (Vec<f64>, Vec<f64>) = strategy
+        .iter()
+        .zip(benchmark)
+        .filter(|(_, b)| keep(**b))
+        .unzip();
+    let benchmark_mean = mean(&benchmark);
+    if benchmark_mean != 0.0 {
+        mean(&strategy) / benchmark_mean
+    } else {
+        0.0
+    }
+}
+
+/// `(timestamp, strategy, benchmark)` at every timestamp both curves have
+pub fn align(strategy: &[(i64, f64)], benchmark: &[(i64, f64)]) -> Vec<(i64, f64, f64)> {
+    let benchmark: BTreeMap<i64, f64> = last_per_timestamp(benchmark).into_iter().collect();
+    last_per_timestamp(strategy)
+        .into_iter()
+        .filter_map(|(t, s)| benchmark.get(&t).map(|b| (t, s, *b)))
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+fn variance(values: &[f64]) -> f64 {
+    covariance(values, values)
+}
+
+fn covariance(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() {
+        return 0.0;
+    }
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - mean_a) * (y - mean_b))
+        .sum::<f64>()
+        / a.len() as f64
+}
+
+/// Write `benchmark.json`, the benchmark equity curve and the overlaid curves
+pub fn write_benchmark_outputs(
+    comparison: &BenchmarkComparison,
+    benchmark: &BacktestOutcome,
+    strategy: &BacktestOutcome,
+    out_dir: &Path,
+) -> Result<()> {
+    let file = fs::File::create(out_dir.join("benchmark.json"))?;
+    serde_json::to_writer_pretty(file, comparison)?;
+    engine::output::write_equity_curve_csv(
+        &benchmark.equity_history,
+        &out_dir.join("benchmark_equity_curve.csv"),
+    )?;
+
+    let mut wtr = csv::Writer::from_path(out_dir.join("equity_vs_benchmark.csv"))?;
+    wtr.write_record(["timestamp", "strategy_equity", "benchmark_equity"])?;
+    for (t, s, b) in align(&strategy.equity_history, &benchmark.equity_history) {
+        wtr.write_record([t.to_string(), s.to_string(), b.to_string()])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Benchmark comparison and equity curve as written to an output directory
+pub type SavedBenchmark = (BenchmarkComparison, Vec<(i64, f64)>);
+
+/// Load a run's benchmark outputs, if it was run with one
+pub fn load_benchmark(out_dir: &Path) -> Result<Option<SavedBenchmark>> {
+    let path = out_dir.join("benchmark.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let comparison = serde_json::from_reader(fs::File::open(&path)?)
+        .context("Failed to parse benchmark.json")?;
+    let equity =
+        engine::output::read_equity_curve_csv(&out_dir.join("benchmark_equity_curve.csv"))?;
+    Ok(Some((comparison, equity)))
+}
+
+pub fn print_comparison(comparison: &BenchmarkComparison) {
+    let relative = &comparison.relative;
+    println!("\n=== Benchmark: buy-and-hold {} ===", relative.symbol);
+    println!(
+        "Benchmark return: {:.2}%",
+        comparison.stats.total_return * 100.0
+    );
+    println!("Benchmark Sharpe: {:.4}", comparison.stats.sharpe_ratio);
+    println!("Excess return: {:.2}%", relative.excess_return * 100.0);
+    println!("Tracking error: {:.4}", relative.tracking_error);
+    println!("Information ratio: {:.4}", relative.information_ratio);
+    println!(
+        "Beta: {:.4}  Alpha: {:.4}  Correlation: {:.4}",
+        relative.beta, relative.alpha, relative.correlation
+    );
+    println!(
+        "Up capture: {:.2}%  Down capture: {:.2}%",
+        relative.up_capture * 100.0,
+        relative.down_capture * 100.0
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::CostBreakdown;
+
+    fn stats(total_return: f64) -> BacktestStats {
+        BacktestStats {
+            initial_equity: 100.0,
+            final_equity: 100.0 * (1.0 + total_return),
+            total_return,
+            num_trades: 1,
+            total_commission: 0.0,
+            costs: CostBreakdown::default(),
+            sharpe_ratio: 0.0,
+            max_drawdown: 0.0,
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
+        }
+    }
+
+    #[test]
+    fn parses_symbols_and_paths() {
+        assert_eq!(
+            BenchmarkSource::parse("SPY"),
+            BenchmarkSource::Symbol("SPY".to_string())
+        );
+        assert_eq!(
+            BenchmarkSource::parse("data/spy.parquet"),
+            BenchmarkSource::Data("data/spy.parquet".to_string())
+        );
+    }
+
+    #[test]
+    fn leveraged_copy_has_beta_two_and_perfect_correlation() {
+        let benchmark: Vec<(i64, f64)> = [100.0, 102.0, 99.0, 104.0, 103.0]
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (i as i64 + 1, *e))
+            .collect();
+        // Twice the benchmark's per-period return every period
+        let mut strategy = vec![(1, 100.0)];
+        for w in benchmark.windows(2) {
+            let prev = strategy.last().unwrap().1;
+            strategy.push((w[1].0, prev * (1.0 + 2.0 * (w[1].1 / w[0].1 - 1.0))));
+        }
+
+        let comparison = compare(
+            "SPY".to_string(),
+            "data".to_string(),
+            &stats(0.1),
+            &strategy,
+            &stats(0.03),
+            &benchmark,
+            252.0,
+        );
+        let relative = &comparison.relative;
+        assert!((relative.beta - 2.0).abs() < 1e-9);
+        assert!((relative.correlation - 1.0).abs() < 1e-9);
+        assert!(relative.alpha.abs() < 1e-9);
+        assert!((relative.excess_return - 0.07).abs() < 1e-12);
+        assert!(relative.tracking_error > 0.0);
+        assert!((relative.up_capture - 2.0).abs() < 1e-9);
+        assert!((relative.down_capture - 2.0).abs() < 1e-9);
+    }
+}
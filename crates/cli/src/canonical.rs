@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use hipcortex::DatasetMetadata;
+use polars::prelude::*;
+use schema::{EventEnvelope, MarketEventType, QualityFlag};
+use std::fs::File;
+use std::path::Path;
+
+/// Column marking a parquet file as canonical events rather than legacy bars
+const PAYLOAD_COLUMN: &str = "payload";
+
+/// Column carrying the dataset metadata JSON, repeated on every row.
+///
+/// The parquet writer does not expose key-value metadata, so the metadata
+/// rides along as a constant column; dictionary encoding keeps it to one copy.
+const METADATA_COLUMN: &str = "dataset_metadata";
+
+/// Whether a parquet file uses the canonical event layout
+pub fn is_canonical_parquet(path: &Path) -> Result<bool> {
+    let schema = LazyFrame::scan_parquet(path, Default::default())?.collect_schema()?;
+    Ok(schema.contains(PAYLOAD_COLUMN))
+}
+
+/// Write canonical events to parquet with dataset metadata embedded.
+///
+/// Scalar envelope fields get their own columns; flags and payloads are JSON so
+/// every payload type shares one schema.
+pub fn write_canonical_parquet(
+    events: &[EventEnvelope],
+    metadata: &DatasetMetadata,
+    path: &Path,
+) -> Result<()> {
+    let event_types = events
+        .iter()
+        .map(|e| json_string(&e.event_type))
+        .collect::<Result<Vec<_>>>()?;
+    let quality_flags = events
+        .iter()
+        .map(|e| serde_json::to_string(&e.quality_flags).map_err(Into::into))
+        .collect::<Result<Vec<_>>>()?;
+    let payloads = events
+        .iter()
+        .map(|e| serde_json::to_string(&e.payload).map_err(Into::into))
+        .collect::<Result<Vec<_>>>()?;
+    let metadata_json = serde_json::to_string(metadata)?;
+
+    let mut df = df!(
+        "event_type" => event_types,
+        "symbol" => events.iter().map(|e| e.symbol.as_str()).collect::<Vec<_>>(),
+        "event_time" => events.iter().map(|e| e.event_time).collect::<Vec<_>>(),
+        "ingest_time" => events.iter().map(|e| e.ingest_time).collect::<Vec<_>>(),
+        "source_id" => events.iter().map(|e| e.source_id.as_str()).collect::<Vec<_>>(),
+        "quality_flags" => quality_flags,
+        PAYLOAD_COLUMN => payloads,
+        METADATA_COLUMN => vec![metadata_json; events.len()],
+    )?;
+
+    let file = File::create(path).with_context(|| format!("Failed to create {:?}", path))?;
+    ParquetWriter::new(file).finish(&mut df)?;
+    Ok(())
+}
+
+/// Read canonical events back from parquet, in file order
+pub fn read_canonical_parquet(path: &Path) -> Result<Vec<EventEnvelope>> {
+    let df = LazyFrame::scan_parquet(path, Default::default())?.collect()?;
+
+    let event_types = df.column("event_type")?.str()?;
+    let symbols = df.column("symbol")?.str()?;
+    let event_times = df.column("event_time")?.i64()?;
+    let ingest_times = df.column("ingest_time")?.i64()?;
+    let source_ids = df.column("source_id")?.str()?;
+    let flags = df.column("quality_flags")?.str()?;
+    let payloads = df.column(PAYLOAD_COLUMN)?.str()?;
+
+    (0..df.height())
+        .map(|i| {
+            let field = |name: &str| anyhow::anyhow!("row {}: missing {}", i, name);
+            let event_type: MarketEventType = serde_json::from_str(&format!(
+                "\"{}\"",
+                event_types.get(i).ok_or_else(|| field("event_type"))?
+            ))?;
+            let quality_flags: Vec<QualityFlag> =
+                serde_json::from_str(flags.get(i).ok_or_else(|| field("quality_flags"))?)?;
+            Ok(EventEnvelope {
+                event_type,
+                symbol: symbols.get(i).ok_or_else(|| field("symbol"))?.to_string(),
+                event_time: event_times.get(i).ok_or_else(|| field("event_time"))?,
+                ingest_time: ingest_times.get(i).ok_or_else(|| field("ingest_time"))?,
+                source_id: source_ids
+                    .get(i)
+                    .ok_or_else(|| field("source_id"))?
+                    .to_string(),
+                quality_flags,
+                payload: serde_json::from_str(payloads.get(i).ok_or_else(|| field("payload"))?)
+                    .with_context(|| format!("row {}: invalid payload", i))?,
+            })
+        })
+        .collect()
+}
+
+/// Read the dataset metadata embedded in a canonical parquet file
+pub fn read_dataset_metadata(path: &Path) -> Result<Option<DatasetMetadata>> {
+    let df = LazyFrame::scan_parquet(path, Default::default())?
+        .select([col(METADATA_COLUMN)])
+        .limit(1)
+        .collect()?;
+    match df.column(METADATA_COLUMN)?.str()?.get(0) {
+        Some(json) => Ok(Some(serde_json::from_str(json)?)),
+        None => Ok(None),
+    }
+}
+
+fn json_string<T: serde::Serialize>(value: &T) -> Result<String> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(s) => Ok(s),
+        other => anyhow::bail!("expected a string enum, got {}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::{Bar, FidelityTier, LatencyClass, MarketEventPayload, TradePayload};
+
+    #[test]
+    fn canonical_parquet_round_trips_events_and_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.parquet");
+        let events = vec![
+            EventEnvelope {
+                event_type: MarketEventType::Bar,
+                symbol: "AAPL".to_string(),
+                event_time: 60,
+                ingest_time: 61,
+                source_id: "vendor".to_string(),
+                quality_flags: vec![],
+                payload: MarketEventPayload::Bar(Bar {
+                    timestamp: 60,
+                    symbol: "AAPL".to_string(),
+                    open: 100.0,
+                    high: 101.0,
+                    low: 99.5,
+                    close: 100.25,
+                    volume: 1_000.0,
+                }),
+            },
+            EventEnvelope {
+                event_type: MarketEventType::Trade,
+                symbol: "AAPL".to_string(),
+                event_time: 62,
+                ingest_time: 70,
+                source_id: "vendor".to_string(),
+                quality_flags: vec![QualityFlag::LateSourceData],
+                payload: MarketEventPayload::Trade(TradePayload {
+                    price: 100.3,
+                    quantity: 5.0,
+                    venue: Some("XNAS".to_string()),
+                }),
+            },
+        ];
+        let metadata = DatasetMetadata {
+            symbols: vec!["AAPL".to_string()],
+            start_timestamp: 60,
+            end_timestamp: 62,
+            bar_count: 1,
+            provider: "vendor".to_string(),
+            venue_class: "lit".to_string(),
+            timezone_calendar: "America/New_York/XNYS".to_string(),
+            adjustment_policy: "split_adjusted".to_string(),
+            fidelity_tier: FidelityTier::Tier2TickQuote,
+            latency_class: LatencyClass::Delayed,
+            quality_flags: vec![],
+            transform_lineage: vec![],
+        };
+
+        write_canonical_parquet(&events, &metadata, &path).unwrap();
+
+        assert!(is_canonical_parquet(&path).unwrap());
+        assert_eq!(read_canonical_parquet(&path).unwrap(), events);
+        assert_eq!(read_dataset_metadata(&path).unwrap(), Some(metadata));
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::backtest_cmd::BacktestOutcome;
+
+pub const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+/// Files whose contents make up a completed run
+const OUTPUT_FILES: [&str; 8] = [
+    "trades.csv",
+    "trades-roundtrip.csv",
+    "tax_lots.csv",
+    "equity_curve.csv",
+    "exposure.csv",
+    "stats.json",
+    "order_events.jsonl",
+    "crv_report.json",
+];
+
+/// Files only some runs write, hashed along with the rest when they were
+const OPTIONAL_OUTPUT_FILES: [&str; 2] = ["strategies.json", "kill_switch.json"];
+
+/// Completion marker for one run directory.
+///
+/// Written after every output file, so its presence means the run finished;
+/// the hashes tie the outputs to the spec and data that produced them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    pub spec_hash: String,
+    pub data_hash: String,
+    pub outputs_hash: String,
+    /// Optional output files the run wrote, which the hash covers too
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub optional_outputs: Vec<String>,
+}
+
+/// Hash of a run's output files and the `optional` ones it wrote, in a
+/// fixed order
+pub fn outputs_hash(run_dir: &Path, optional: &[String]) -> Result<String> {
+    let mut content = Vec::new();
+    for name in OUTPUT_FILES
+        .into_iter()
+        .chain(optional.iter().map(String::as_str))
+    {
+        let bytes = fs::read(run_dir.join(name))
+            .with_context(|| format!("Missing run output {:?}", run_dir.join(name)))?;
+        content.extend_from_slice(name.as_bytes());
+        content.push(0);
+        content.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        content.extend_from_slice(&bytes);
+    }
+    Ok(engine::stable_hash_bytes(&content))
+}
+
+/// Mark `run_dir` as complete for this spec and data
+pub fn write_checkpoint(run_dir: &Path, spec_hash: &str, data_hash: &str) -> Result<RunCheckpoint> {
+    let optional_outputs: Vec<String> = OPTIONAL_OUTPUT_FILES
+        .into_iter()
+        .filter(|name| run_dir.join(name).exists())
+        .map(String::from)
+        .collect();
+    let checkpoint = RunCheckpoint {
+        spec_hash: spec_hash.to_string(),
+        data_hash: data_hash.to_string(),
+        outputs_hash: outputs_hash(run_dir, &optional_outputs)?,
+        optional_outputs,
+    };
+    let tmp = run_dir.join("checkpoint.json.tmp");
+    serde_json::to_writer_pretty(fs::File::create(&tmp)?, &checkpoint)?;
+    fs::rename(&tmp, run_dir.join(CHECKPOINT_FILE))?;
+    Ok(checkpoint)
+}
+
+/// Outcome of a previously completed run, if `run_dir` holds one for this spec
+/// and data whose outputs still match their recorded hash
+pub fn load_completed(
+    run_dir: &Path,
+    spec_hash: &str,
+    data_hash: &str,
+) -> Result<Option<BacktestOutcome>> {
+    let path = run_dir.join(CHECKPOINT_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let checkpoint: RunCheckpoint = serde_json::from_reader(fs::File::open(&path)?)
+        .with_context(|| format!("Failed to parse {:?}", path))?;
+    if checkpoint.spec_hash != spec_hash || checkpoint.data_hash != data_hash {
+        return Ok(None);
+    }
+    match outputs_hash(run_dir, &checkpoint.optional_outputs) {
+        Ok(hash) if hash == checkpoint.outputs_hash => {}
+        _ => return Ok(None),
+    }
+
+    // Curves written before equity was split into cash and positions re-run
+    let Ok(equity_points) =
+        engine::output::read_equity_points_csv(&run_dir.join("equity_curve.csv"))
+    else {
+        return Ok(None);
+    };
+
+    let crv_file = fs::File::open(run_dir.join("crv_report.json"))?;
+    let wrote = |name: &str| checkpoint.optional_outputs.iter().any(|f| f == name);
+    Ok(Some(BacktestOutcome {
+        stats: engine::output::read_stats_json(&run_dir.join("stats.json"))?,
+        fills: engine::output::read_trades_csv(&run_dir.join("trades.csv"))?,
+        equity_history: equity_points
+            .iter()
+            .map(|p| (p.timestamp, p.equity))
+            .collect(),
+        equity_points,
+        exposure_history: engine::output::read_exposure_csv(&run_dir.join("exposure.csv"))?,
+        round_trips: engine::output::read_round_trips_csv(&run_dir.join("trades-roundtrip.csv"))?,
+        lot_realizations: engine::output::read_tax_lots_csv(&run_dir.join("tax_lots.csv"))?,
+        crv_report: serde_json::from_reader(crv_file).context("Failed to parse crv_report.json")?,
+        order_events: engine::output::read_order_events_jsonl(&run_dir.join("order_events.jsonl"))?,
+        // Already logged in the CRV report
+        risk_rejections: Vec::new(),
+        trace: None,
+        sleeves: if wrote("strategies.json") {
+            engine::output::read_sleeve_stats_json(&run_dir.join("strategies.json"))?
+        } else {
+            Vec::new()
+        },
+        kill_switch_trips: if wrote("kill_switch.json") {
+            engine::output::read_kill_switch_json(&run_dir.join("kill_switch.json"))?
+        } else {
+            Vec::new()
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest_cmd::write_outputs;
+    use crv_verifier::{CRVVerifier, PolicyConstraints};
+    use schema::{BacktestStats, CostBreakdown, EquityPoint, ExposurePoint, Fill, Side};
+
+    fn lot_realizations(fills: &[Fill]) -> Vec<engine::lots::LotRealization> {
+        let mut book = engine::lots::LotBook::new(engine::lots::LotMethod::Fifo);
+        for fill in fills {
+            book.apply_fill(fill);
+        }
+        book.realizations().to_vec()
+    }
+
+    fn outcome() -> BacktestOutcome {
+        let stats = BacktestStats {
+            initial_equity: 1000.0,
+            final_equity: 1010.5,
+            total_return: 0.0105,
+            num_trades: 1,
+            total_commission: 1.0,
+            costs: CostBreakdown::from_commission(1.0),
+            sharpe_ratio: 0.3,
+            max_drawdown: 0.02,
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
+        };
+        let fills = vec![Fill {
+            timestamp: 10,
+            symbol: "AAPL".to_string(),
+            side: Side::Buy,
+            quantity: 1.5,
+            price: 100.1,
+            costs: CostBreakdown::from_commission(1.0),
+        }];
+        let equity_history = vec![(0, 1000.0), (10, 999.0), (20, 1010.5)];
+        let crv_report = CRVVerifier::new(PolicyConstraints::default())
+            .verify(&stats, &fills, &equity_history)
+            .unwrap();
+        let sold = Fill {
+            timestamp: 20,
+            side: Side::Sell,
+            ..fills[0].clone()
+        };
+        BacktestOutcome {
+            stats,
+            round_trips: engine::round_trips::pair_fills(&[fills[0].clone(), sold.clone()]),
+            lot_realizations: lot_realizations(&[fills[0].clone(), sold]),
+            fills,
+            exposure_history: vec![ExposurePoint::new(10, 150.15, 150.15, 150.15, 999.0)],
+            equity_points: equity_history
+                .iter()
+                .map(|&(timestamp, equity)| {
+                    // All cash until the buy at 10
+                    let cash = if timestamp < 10 { equity } else { 848.85 };
+                    EquityPoint {
+                        timestamp,
+                        equity,
+                        cash,
+                        positions_value: equity - cash,
+                    }
+                })
+                .collect(),
+            equity_history,
+            crv_report,
+            order_events: Vec::new(),
+            risk_rejections: Vec::new(),
+            trace: None,
+            sleeves: Vec::new(),
+            kill_switch_trips: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn completed_runs_round_trip_and_tampering_is_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = outcome();
+        write_outputs(&original, dir.path()).unwrap();
+        assert!(load_completed(dir.path(), "spec", "data")
+            .unwrap()
+            .is_none());
+
+        let checkpoint = write_checkpoint(dir.path(), "spec", "data").unwrap();
+        let restored = load_completed(dir.path(), "spec", "data").unwrap().unwrap();
+        assert_eq!(restored.fills, original.fills);
+        assert_eq!(restored.equity_history, original.equity_history);
+        assert_eq!(restored.equity_points, original.equity_points);
+        assert_eq!(restored.exposure_history, original.exposure_history);
+        assert_eq!(restored.round_trips, original.round_trips);
+        assert_eq!(restored.lot_realizations, original.lot_realizations);
+
+        // Rewriting the restored outcome reproduces the same bytes
+        let again = tempfile::tempdir().unwrap();
+        write_outputs(&restored, again.path()).unwrap();
+        assert_eq!(
+            outputs_hash(again.path(), &[]).unwrap(),
+            checkpoint.outputs_hash
+        );
+
+        assert!(load_completed(dir.path(), "other", "data")
+            .unwrap()
+            .is_none());
+        fs::write(dir.path().join("trades.csv"), "tampered").unwrap();
+        assert!(load_completed(dir.path(), "spec", "data")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn optional_outputs_are_hashed_when_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let tripped = BacktestOutcome {
+            kill_switch_trips: vec![schema::KillSwitchTrip {
+                timestamp: 10,
+                equity: 999.0,
+                peak_equity: 1000.0,
+                drawdown: 0.001,
+                max_drawdown: 0.0005,
+            }],
+            ..outcome()
+        };
+        write_outputs(&tripped, dir.path()).unwrap();
+        let checkpoint = write_checkpoint(dir.path(), "spec", "data").unwrap();
+        assert_eq!(checkpoint.optional_outputs, ["kill_switch.json"]);
+        let restored = load_completed(dir.path(), "spec", "data").unwrap().unwrap();
+        assert_eq!(restored.kill_switch_trips, tripped.kill_switch_trips);
+
+        // A file the run didn't write is ignored; dropping one it did re-runs
+        fs::write(dir.path().join("strategies.json"), "[]").unwrap();
+        assert!(load_completed(dir.path(), "spec", "data")
+            .unwrap()
+            .is_some());
+        fs::remove_file(dir.path().join("kill_switch.json")).unwrap();
+        assert!(load_completed(dir.path(), "spec", "data")
+            .unwrap()
+            .is_none());
+    }
+}
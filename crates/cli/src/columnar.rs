@@ -0,0 +1,219 @@
+//! Pre-converted columnar bar files (`.arrow`).
+//!
+//! `data columnar` writes bars, already merged into deterministic order, as
+//! an uncompressed Arrow IPC file in record batches of `CHUNK_ROWS`. Reading
+//! memory-maps the file, so columns are not copied into memory up front, and
+//! builds `Bar`s one chunk at a time straight from the mapped arrays; a
+//! `ColumnarDataFeed` replays it to the engine holding only the current
+//! chunk.
+
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use schema::Bar;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::parquet_feed::{BarChunks, ChunkedDataFeed};
+
+pub const COLUMNAR_EXTENSION: &str = "arrow";
+
+/// Rows per record batch on write and per chunk on read
+pub const CHUNK_ROWS: usize = 65_536;
+
+/// Whether `path` is a columnar bar file, by extension
+pub fn is_columnar(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == COLUMNAR_EXTENSION)
+}
+
+/// Write bars as a columnar file in `CHUNK_ROWS`-row record batches, one
+/// batch built at a time
+pub fn write_columnar(bars: &[Bar], path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = fs::File::create(path)
+        .with_context(|| format!("Failed to create columnar file {:?}", path))?;
+    let mut writer = None;
+    for chunk in bars.chunks(CHUNK_ROWS) {
+        let batch = df!(
+            "timestamp" => chunk.iter().map(|b| b.timestamp).collect::<Vec<_>>(),
+            "symbol" => chunk.iter().map(|b| b.symbol.as_str()).collect::<Vec<_>>(),
+            "open" => chunk.iter().map(|b| b.open).collect::<Vec<_>>(),
+            "high" => chunk.iter().map(|b| b.high).collect::<Vec<_>>(),
+            "low" => chunk.iter().map(|b| b.low).collect::<Vec<_>>(),
+            "close" => chunk.iter().map(|b| b.close).collect::<Vec<_>>(),
+            "volume" => chunk.iter().map(|b| b.volume).collect::<Vec<_>>(),
+        )?;
+        let writer = match &mut writer {
+            Some(writer) => writer,
+            // Memory mapping needs an uncompressed file
+            None => writer.insert(
+                IpcWriter::new(file.try_clone()?)
+                    .with_compression(None)
+                    .batched(batch.schema())?,
+            ),
+        };
+        writer.write_batch(&batch)?;
+    }
+    match &mut writer {
+        Some(writer) => writer.finish()?,
+        None => IpcWriter::new(file)
+            .with_compression(None)
+            .finish(&mut empty_bars()?)?,
+    }
+    Ok(())
+}
+
+/// A frame with the bar columns and no rows
+fn empty_bars() -> Result<DataFrame> {
+    Ok(df!(
+        "timestamp" => Vec::<i64>::new(),
+        "symbol" => Vec::<&str>::new(),
+        "open" => Vec::<f64>::new(),
+        "high" => Vec::<f64>::new(),
+        "low" => Vec::<f64>::new(),
+        "close" => Vec::<f64>::new(),
+        "volume" => Vec::<f64>::new(),
+    )?)
+}
+
+/// A memory-mapped columnar bar file, read chunk by chunk
+pub struct ColumnarBars {
+    frame: DataFrame,
+    offset: usize,
+}
+
+impl ColumnarBars {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open columnar file {:?}", path))?;
+        let frame = IpcReader::new(file)
+            .memory_mapped(Some(PathBuf::from(path)))
+            .set_rechunk(false)
+            .finish()
+            .with_context(|| format!("Failed to map columnar file {:?}", path))?;
+        Ok(Self { frame, offset: 0 })
+    }
+
+    /// The next `CHUNK_ROWS` bars, built from a zero-copy slice of the map
+    pub fn next_chunk(&mut self) -> Result<Option<Vec<Bar>>> {
+        if self.offset >= self.frame.height() {
+            return Ok(None);
+        }
+        let chunk = self.frame.slice(self.offset as i64, CHUNK_ROWS);
+        self.offset += chunk.height();
+        bars_from_frame(&chunk).map(Some)
+    }
+}
+
+/// Bars from the rows of a frame with the bar columns
+pub fn bars_from_frame(chunk: &DataFrame) -> Result<Vec<Bar>> {
+    let f64s = |name: &str| -> Result<Float64Chunked> { Ok(chunk.column(name)?.f64()?.clone()) };
+    let (open, high, low, close, volume) = (
+        f64s("open")?,
+        f64s("high")?,
+        f64s("low")?,
+        f64s("close")?,
+        f64s("volume")?,
+    );
+    let bars = chunk
+        .column("timestamp")?
+        .i64()?
+        .into_no_null_iter()
+        .zip(chunk.column("symbol")?.str()?)
+        .zip(open.into_no_null_iter())
+        .zip(high.into_no_null_iter())
+        .zip(low.into_no_null_iter())
+        .zip(close.into_no_null_iter())
+        .zip(volume.into_no_null_iter())
+        .map(
+            |((((((timestamp, symbol), open), high), low), close), volume)| Bar {
+                timestamp,
+                symbol: symbol.unwrap_or("UNKNOWN").to_string(),
+                open,
+                high,
+                low,
+                close,
+                volume,
+            },
+        )
+        .collect();
+    Ok(bars)
+}
+
+impl BarChunks for ColumnarBars {
+    fn next_chunk(&mut self) -> Result<Option<Vec<Bar>>> {
+        ColumnarBars::next_chunk(self)
+    }
+
+    fn rewind(&mut self) {
+        self.offset = 0;
+    }
+
+    fn total_rows(&self) -> usize {
+        self.frame.height()
+    }
+}
+
+/// A `DataFeed` over a columnar bar file, a chunk at a time
+pub type ColumnarDataFeed = ChunkedDataFeed<ColumnarBars>;
+
+impl ColumnarDataFeed {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(ChunkedDataFeed::new(path, ColumnarBars::open(path)?))
+    }
+}
+
+impl Iterator for ColumnarBars {
+    type Item = Result<Vec<Bar>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_chunk().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::DataFeed;
+
+    #[test]
+    fn columnar_round_trip_reads_in_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bars.arrow");
+        let bars: Vec<Bar> = (0..CHUNK_ROWS as i64 + 10)
+            .map(|t| Bar {
+                timestamp: t,
+                symbol: if t % 2 == 0 { "A" } else { "B" }.to_string(),
+                open: t as f64,
+                high: t as f64 + 1.0,
+                low: t as f64 - 1.0,
+                close: t as f64 + 0.5,
+                volume: 100.0,
+            })
+            .collect();
+        write_columnar(&bars, &path).unwrap();
+        assert!(is_columnar(&path));
+
+        let chunks: Vec<Vec<Bar>> = ColumnarBars::open(&path)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+            [CHUNK_ROWS, 10]
+        );
+        assert_eq!(chunks.concat(), bars);
+
+        let mut feed = ColumnarDataFeed::open(&path).unwrap();
+        assert_eq!(feed.total_bars(), Some(bars.len()));
+        let mut streamed = 0;
+        while let Some(bar) = feed.next_bar() {
+            assert_eq!(bar, &bars[streamed]);
+            streamed += 1;
+        }
+        assert_eq!(streamed, bars.len());
+        assert!(feed.error().is_none());
+    }
+}
@@ -0,0 +1,498 @@
+use anyhow::{Context, Result};
+use hipcortex::Repository;
+use schema::{BacktestStats, Fill};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::run_data::load_run;
+
+/// Two-sided p-value thresholds and their annotations, strictest first
+const SIGNIFICANCE_LEVELS: [(f64, &str); 3] = [(0.01, "***"), (0.05, "**"), (0.10, "*")];
+
+/// Maximum number of differing trades printed to the console
+const MAX_PRINTED_TRADES: usize = 10;
+
+/// Result of a significance test on the difference between two runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Significance {
+    pub test: String,
+    pub statistic: f64,
+    pub p_value: f64,
+    /// `***`, `**`, `*` or `n.s.`
+    pub annotation: String,
+}
+
+/// One metric compared across both runs; `delta` is `b - a`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatDiff {
+    pub metric: String,
+    pub a: f64,
+    pub b: f64,
+    pub delta: f64,
+    pub significance: Option<Significance>,
+}
+
+/// How well the two equity curves line up
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EquityAlignment {
+    pub aligned_points: usize,
+    pub only_in_a: usize,
+    pub only_in_b: usize,
+    pub return_correlation: f64,
+    /// Annualized standard deviation of per-period return differences
+    pub tracking_error: f64,
+}
+
+/// A trade present in both runs with different quantity, price or commission
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeChange {
+    pub a: Fill,
+    pub b: Fill,
+}
+
+/// Trades matched on timestamp, symbol and side
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeDiff {
+    pub identical: usize,
+    pub changed: Vec<TradeChange>,
+    pub only_in_a: Vec<Fill>,
+    pub only_in_b: Vec<Fill>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompareReport {
+    pub a: String,
+    pub b: String,
+    pub alignment: EquityAlignment,
+    pub stats: Vec<StatDiff>,
+    pub trades: TradeDiff,
+}
+
+pub fn run_compare(
+    a: &str,
+    b: &str,
+    repo_path: Option<&Path>,
+    out_dir: Option<&Path>,
+) -> Result<()> {
+    let repo = repo_path
+        .map(|p| Repository::open(p).context("Failed to open repository"))
+        .transpose()?;
+    let run_a = load_run(a, repo.as_ref())?;
+    let run_b = load_run(b, repo.as_ref())?;
+
+    let (aligned, alignment) = align_equity(&run_a.equity_history, &run_b.equity_history);
+    let report = CompareReport {
+        a: run_a.label.clone(),
+        b: run_b.label.clone(),
+        stats: diff_stats(&run_a.stats, &run_b.stats, &aligned),
+        trades: diff_trades(&run_a.fills, &run_b.fills),
+        alignment,
+    };
+
+    if let Some(out_dir) = out_dir {
+        fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+        let json_file = fs::File::create(out_dir.join("compare_report.json"))?;
+        serde_json::to_writer_pretty(json_file, &report)?;
+        write_aligned_equity(&aligned, &out_dir.join("compare_equity.csv"))?;
+    }
+
+    print_report(&report);
+    if let Some(out_dir) = out_dir {
+        println!("\nWrote comparison to {:?}", out_dir);
+    }
+    Ok(())
+}
+
+/// Join two equity curves on timestamp, returning `(timestamp, equity_a, equity_b)`.
+///
+/// The engine records equity after every fill as well as at bar close, so each
+/// curve is first reduced to its last value per timestamp.
+pub fn align_equity(a: &[(i64, f64)], b: &[(i64, f64)]) -> (Vec<(i64, f64, f64)>, EquityAlignment) {
+    let a_by_time: BTreeMap<i64, f64> = a.iter().copied().collect();
+    let b_by_time: BTreeMap<i64, f64> = b.iter().copied().collect();
+    let aligned: Vec<(i64, f64, f64)> = a_by_time
+        .iter()
+        .filter_map(|(t, ea)| b_by_time.get(t).map(|eb| (*t, *ea, *eb)))
+        .collect();
+
+    let (returns_a, returns_b) = aligned_returns(&aligned);
+    let diffs: Vec<f64> = returns_a
+        .iter()
+        .zip(&returns_b)
+        .map(|(ra, rb)| rb - ra)
+        .collect();
+
+    let alignment = EquityAlignment {
+        aligned_points: aligned.len(),
+        only_in_a: a_by_time.len() - aligned.len(),
+        only_in_b: b_by_time.len() - aligned.len(),
+        return_correlation: correlation(&returns_a, &returns_b),
+        tracking_error: std_dev(&diffs) * (252.0_f64).sqrt(),
+    };
+    (aligned, alignment)
+}
+
+fn aligned_returns(aligned: &[(i64, f64, f64)]) -> (Vec<f64>, Vec<f64>) {
+    aligned
+        .windows(2)
+        .filter(|w| w[0].1 > 0.0 && w[0].2 > 0.0)
+        .map(|w| ((w[1].1 - w[0].1) / w[0].1, (w[1].2 - w[0].2) / w[0].2))
+        .unzip()
+}
+
+/// Diff headline stats; return and Sharpe differences are tested on aligned returns
+pub fn diff_stats(
+    a: &BacktestStats,
+    b: &BacktestStats,
+    aligned: &[(i64, f64, f64)],
+) -> Vec<StatDiff> {
+    let (returns_a, returns_b) = aligned_returns(aligned);
+    let row = |metric: &str, va: f64, vb: f64, significance: Option<Significance>| StatDiff {
+        metric: metric.to_string(),
+        a: va,
+        b: vb,
+        delta: vb - va,
+        significance,
+    };
+
+    vec![
+        row(
+            "total_return",
+            a.total_return,
+            b.total_return,
+            paired_mean_test(&returns_a, &returns_b),
+        ),
+        row(
+            "sharpe_ratio",
+            a.sharpe_ratio,
+            b.sharpe_ratio,
+            sharpe_difference_test(&returns_a, &returns_b),
+        ),
+        row("max_drawdown", a.max_drawdown, b.max_drawdown, None),
+        row("final_equity", a.final_equity, b.final_equity, None),
+        row("num_trades", a.num_trades as f64, b.num_trades as f64, None),
+        row(
+            "total_commission",
+            a.total_commission,
+            b.total_commission,
+            None,
+        ),
+    ]
+}
+
+/// Paired test on per-period return differences (normal approximation)
+fn paired_mean_test(a: &[f64], b: &[f64]) -> Option<Significance> {
+    if a.len() < 2 {
+        return None;
+    }
+    let diffs: Vec<f64> = a.iter().zip(b).map(|(ra, rb)| rb - ra).collect();
+    let sd = std_dev(&diffs);
+    let statistic = if sd > 0.0 {
+        mean(&diffs) / (sd / (diffs.len() as f64).sqrt())
+    } else {
+        0.0
+    };
+    Some(significance("paired_t", statistic))
+}
+
+/// Jobson-Korkie test with Memmel's correction on per-period Sharpe ratios
+fn sharpe_difference_test(a: &[f64], b: &[f64]) -> Option<Significance> {
+    if a.len() < 2 {
+        return None;
+    }
+    let (sd_a, sd_b) = (std_dev(a), std_dev(b));
+    if sd_a == 0.0 || sd_b == 0.0 {
+        return None;
+    }
+    let (sr_a, sr_b) = (mean(a) / sd_a, mean(b) / sd_b);
+    let rho = correlation(a, b);
+    let variance = (2.0 - 2.0 * rho
+        + 0.5 * (sr_a.powi(2) + sr_b.powi(2) - 2.0 * sr_a * sr_b * rho.powi(2)))
+        / a.len() as f64;
+    let statistic = if variance > 0.0 {
+        (sr_b - sr_a) / variance.sqrt()
+    } else {
+        0.0
+    };
+    Some(significance("jobson_korkie_memmel", statistic))
+}
+
+fn significance(test: &str, statistic: f64) -> Significance {
+    let p_value = 2.0 * (1.0 - normal_cdf(statistic.abs()));
+    let annotation = SIGNIFICANCE_LEVELS
+        .iter()
+        .find(|(level, _)| p_value < *level)
+        .map(|(_, mark)| *mark)
+        .unwrap_or("n.s.");
+    Significance {
+        test: test.to_string(),
+        statistic,
+        p_value,
+        annotation: annotation.to_string(),
+    }
+}
+
+/// Match trades on (timestamp, symbol, side) and classify the differences
+pub fn diff_trades(a: &[Fill], b: &[Fill]) -> TradeDiff {
+    type TradeKey = (i64, String, bool);
+    let key =
+        |f: &Fill| -> TradeKey { (f.timestamp, f.symbol.clone(), f.side == schema::Side::Buy) };
+
+    let mut b_by_key: BTreeMap<TradeKey, Vec<&Fill>> = BTreeMap::new();
+    for fill in b {
+        b_by_key.entry(key(fill)).or_default().push(fill);
+    }
+
+    let mut diff = TradeDiff::default();
+    for fill in a {
+        match b_by_key.get_mut(&key(fill)).and_then(|fills| {
+            if fills.is_empty() {
+                None
+            } else {
+                Some(fills.remove(0))
+            }
+        }) {
+            Some(other) if same_execution(fill, other) => diff.identical += 1,
+            Some(other) => diff.changed.push(TradeChange {
+                a: fill.clone(),
+                b: other.clone(),
+            }),
+            None => diff.only_in_a.push(fill.clone()),
+        }
+    }
+    diff.only_in_b = b_by_key.into_values().flatten().cloned().collect();
+    diff.only_in_b.sort_by_key(|f| f.timestamp);
+    diff
+}
+
+/// Quantities, prices and costs match up to float round-trip noise (e.g. via JSON)
+fn same_execution(a: &Fill, b: &Fill) -> bool {
+    let close = |x: f64, y: f64| (x - y).abs() <= 1e-9 * x.abs().max(y.abs()).max(1.0);
+    close(a.quantity, b.quantity)
+        && close(a.price, b.price)
+        && close(a.costs.charged(), b.costs.charged())
+        && close(a.costs.slippage, b.costs.slippage)
+}
+
+fn write_aligned_equity(aligned: &[(i64, f64, f64)], path: &Path) -> Result<()> {
+    let mut wtr = csv::Writer::from_path(path)?;
+    wtr.write_record(["timestamp", "equity_a", "equity_b", "difference"])?;
+    for (t, ea, eb) in aligned {
+        wtr.write_record([
+            t.to_string(),
+            ea.to_string(),
+            eb.to_string(),
+            (eb - ea).to_string(),
+        ])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+fn print_report(report: &CompareReport) {
+    println!("=== Comparison ===");
+    println!("A: {}", report.a);
+    println!("B: {}", report.b);
+
+    let alignment = &report.alignment;
+    println!(
+        "\nAligned equity points: {} (only in A: {}, only in B: {})",
+        alignment.aligned_points, alignment.only_in_a, alignment.only_in_b
+    );
+    println!("Return correlation: {:.4}", alignment.return_correlation);
+    println!("Tracking error: {:.4}", alignment.tracking_error);
+
+    println!(
+        "\n{:<18} {:>16} {:>16} {:>16}  sig",
+        "metric", "A", "B", "B - A"
+    );
+    for diff in &report.stats {
+        let sig = diff
+            .significance
+            .as_ref()
+            .map(|s| format!("{} (p={:.3})", s.annotation, s.p_value))
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<18} {:>16.4} {:>16.4} {:>+16.4}  {}",
+            diff.metric, diff.a, diff.b, diff.delta, sig
+        );
+    }
+    println!("Significance: *** p<0.01, ** p<0.05, * p<0.10, n.s. not significant");
+
+    let trades = &report.trades;
+    println!(
+        "\nTrades: {} identical, {} changed, {} only in A, {} only in B",
+        trades.identical,
+        trades.changed.len(),
+        trades.only_in_a.len(),
+        trades.only_in_b.len()
+    );
+    for change in trades.changed.iter().take(MAX_PRINTED_TRADES) {
+        println!(
+            "  ~ {} {} {:?}: {} @ {:.4} -> {} @ {:.4}",
+            change.a.timestamp,
+            change.a.symbol,
+            change.a.side,
+            change.a.quantity,
+            change.a.price,
+            change.b.quantity,
+            change.b.price
+        );
+    }
+    for (mark, fills) in [("-", &trades.only_in_a), ("+", &trades.only_in_b)] {
+        for fill in fills.iter().take(MAX_PRINTED_TRADES) {
+            println!(
+                "  {} {} {} {:?}: {} @ {:.4}",
+                mark, fill.timestamp, fill.symbol, fill.side, fill.quantity, fill.price
+            );
+        }
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(values);
+    (values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (values.len() - 1) as f64).sqrt()
+}
+
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let (sd_a, sd_b) = (std_dev(a), std_dev(b));
+    if sd_a == 0.0 || sd_b == 0.0 {
+        return 0.0;
+    }
+    let (ma, mb) = (mean(a), mean(b));
+    let cov = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| (x - ma) * (y - mb))
+        .sum::<f64>()
+        / (a.len() - 1) as f64;
+    cov / (sd_a * sd_b)
+}
+
+/// Standard normal CDF (Abramowitz & Stegun 7.1.26 approximation of erf)
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::{CostBreakdown, Side};
+
+    fn fill(timestamp: i64, side: Side, quantity: f64) -> Fill {
+        Fill {
+            timestamp,
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            price: 100.0,
+            costs: CostBreakdown::from_commission(1.0),
+        }
+    }
+
+    #[test]
+    fn aligns_equity_on_shared_timestamps() {
+        let a = vec![(1, 100.0), (2, 100.5), (2, 101.0), (3, 102.0), (4, 103.0)];
+        let b = vec![(2, 100.0), (3, 102.0), (4, 101.0), (5, 99.0)];
+        let (aligned, alignment) = align_equity(&a, &b);
+
+        assert_eq!(aligned.len(), 3);
+        assert_eq!(aligned[0], (2, 101.0, 100.0));
+        assert_eq!(alignment.only_in_a, 1);
+        assert_eq!(alignment.only_in_b, 1);
+    }
+
+    #[test]
+    fn identical_runs_are_not_significant() {
+        let equity: Vec<(i64, f64)> = (0..50)
+            .map(|i| (i, 100.0 + (i as f64 * 0.7).sin() + i as f64 * 0.1))
+            .collect();
+        let (aligned, alignment) = align_equity(&equity, &equity);
+        let stats = engine::output::calculate_stats(
+            &equity,
+            &[],
+            CostBreakdown::default(),
+            Default::default(),
+        );
+        let diffs = diff_stats(&stats, &stats, &aligned);
+
+        assert!((alignment.return_correlation - 1.0).abs() < 1e-9);
+        assert_eq!(diffs[0].significance.as_ref().unwrap().annotation, "n.s.");
+        assert_eq!(diffs[1].significance.as_ref().unwrap().annotation, "n.s.");
+        assert!(diffs.iter().all(|d| d.delta == 0.0));
+    }
+
+    #[test]
+    fn consistent_outperformance_is_significant() {
+        let a: Vec<(i64, f64)> = (0..200)
+            .map(|i| (i, 100.0 + (i as f64 * 0.9).sin()))
+            .collect();
+        let b: Vec<(i64, f64)> = a
+            .iter()
+            .map(|(t, e)| (*t, e * 1.002_f64.powi(*t as i32)))
+            .collect();
+        let (aligned, _) = align_equity(&a, &b);
+        let diffs = diff_stats(
+            &engine::output::calculate_stats(&a, &[], CostBreakdown::default(), Default::default()),
+            &engine::output::calculate_stats(&b, &[], CostBreakdown::default(), Default::default()),
+            &aligned,
+        );
+
+        let total_return = &diffs[0];
+        assert!(total_return.delta > 0.0);
+        assert_eq!(
+            total_return.significance.as_ref().unwrap().annotation,
+            "***"
+        );
+    }
+
+    #[test]
+    fn trade_diff_classifies_matches() {
+        let a = vec![
+            fill(1, Side::Buy, 10.0),
+            fill(2, Side::Sell, 5.0),
+            fill(3, Side::Buy, 1.0),
+        ];
+        let b = vec![
+            fill(1, Side::Buy, 10.0),
+            fill(2, Side::Sell, 7.0),
+            fill(4, Side::Sell, 1.0),
+        ];
+        let diff = diff_trades(&a, &b);
+
+        assert_eq!(diff.identical, 1);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].b.quantity, 7.0);
+        assert_eq!(diff.only_in_a[0].timestamp, 3);
+        assert_eq!(diff.only_in_b[0].timestamp, 4);
+    }
+
+    #[test]
+    fn normal_cdf_matches_known_values() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_cdf(-1.645) - 0.05).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,185 @@
+//! Defaults layered under command-line flags.
+//!
+//! Precedence, highest first: flags, `QUANT_ENGINE_*` environment variables,
+//! the config file (`$QUANT_ENGINE_CONFIG`, else
+//! `$XDG_CONFIG_HOME/quant_engine/config.toml`, else
+//! `~/.config/quant_engine/config.toml`). A missing file is not an error.
+//!
+//! ```toml
+//! repo = "~/research/.hipcortex"
+//! out_root = "~/research/runs"
+//! data_root = "/data/bars"
+//! jobs = 8
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_ENV: &str = "QUANT_ENGINE_CONFIG";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// HipCortex repository used when `--repo` is not given
+    pub repo: Option<PathBuf>,
+    /// Directory relative `--out` paths are resolved against
+    pub out_root: Option<PathBuf>,
+    /// Directory relative `--data` paths are resolved against
+    pub data_root: Option<PathBuf>,
+    /// Worker threads for parallel sweeps and walk-forward windows
+    pub jobs: Option<usize>,
+    /// File the defaults were read from, if any
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+impl Config {
+    /// Read the config file and environment of this process
+    pub fn load() -> Result<Self> {
+        Self::from_sources(|name| std::env::var(name).ok())
+    }
+
+    /// Merge the config file and environment as seen through `env`
+    pub fn from_sources(env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let path = match env(CONFIG_ENV) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => env("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| env("HOME").map(|home| Path::new(&home).join(".config")))
+                .map(|dir| dir.join("quant_engine").join("config.toml")),
+        };
+
+        let mut config = match &path {
+            Some(path) if path.exists() => {
+                let text = fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read config file {:?}", path))?;
+                let mut config: Config = toml::from_str(&text)
+                    .with_context(|| format!("Failed to parse config file {:?}", path))?;
+                config.source = Some(path.clone());
+                config
+            }
+            Some(path) if env(CONFIG_ENV).is_some() => {
+                anyhow::bail!("Config file {:?} from {} does not exist", path, CONFIG_ENV)
+            }
+            _ => Config::default(),
+        };
+
+        if let Some(repo) = env("QUANT_ENGINE_REPO") {
+            config.repo = Some(repo.into());
+        }
+        if let Some(out_root) = env("QUANT_ENGINE_OUT_ROOT") {
+            config.out_root = Some(out_root.into());
+        }
+        if let Some(data_root) = env("QUANT_ENGINE_DATA_ROOT") {
+            config.data_root = Some(data_root.into());
+        }
+        if let Some(jobs) = env("QUANT_ENGINE_JOBS") {
+            config.jobs =
+                Some(jobs.parse().with_context(|| {
+                    format!("QUANT_ENGINE_JOBS must be a number, got '{}'", jobs)
+                })?);
+        }
+
+        let home = env("HOME");
+        for path in [
+            &mut config.repo,
+            &mut config.out_root,
+            &mut config.data_root,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = expand_home(path, home.as_deref());
+        }
+        Ok(config)
+    }
+
+    /// `--repo` if given, else the configured repository
+    pub fn repo(&self, flag: Option<PathBuf>) -> Option<PathBuf> {
+        flag.or_else(|| self.repo.clone())
+    }
+
+    /// An output path, placed under `out_root` when relative
+    pub fn out(&self, path: PathBuf) -> PathBuf {
+        under(self.out_root.as_deref(), path)
+    }
+
+    /// A data path or glob, placed under `data_root` when relative
+    pub fn data(&self, path: PathBuf) -> PathBuf {
+        under(self.data_root.as_deref(), path)
+    }
+
+    pub fn print(&self) {
+        match &self.source {
+            Some(path) => println!("Config file: {:?}", path),
+            None => println!("Config file: none"),
+        }
+        let show = |value: &Option<PathBuf>| match value {
+            Some(path) => format!("{:?}", path),
+            None => "-".to_string(),
+        };
+        println!("repo: {}", show(&self.repo));
+        println!("out_root: {}", show(&self.out_root));
+        println!("data_root: {}", show(&self.data_root));
+        match self.jobs {
+            Some(jobs) => println!("jobs: {}", jobs),
+            None => println!("jobs: -"),
+        }
+    }
+}
+
+fn under(root: Option<&Path>, path: PathBuf) -> PathBuf {
+    match root {
+        Some(root) if path.is_relative() => root.join(path),
+        _ => path,
+    }
+}
+
+fn expand_home(path: &Path, home: Option<&str>) -> PathBuf {
+    match (path.strip_prefix("~"), home) {
+        (Ok(rest), Some(home)) => Path::new(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn environment_overrides_file_and_flags_override_both() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_dir = dir.path().join("quant_engine");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(
+            config_dir.join("config.toml"),
+            "repo = \"~/lab/.hipcortex\"\nout_root = \"/runs\"\njobs = 4\n",
+        )
+        .unwrap();
+
+        let mut vars = HashMap::from([
+            ("XDG_CONFIG_HOME", dir.path().display().to_string()),
+            ("HOME", "/home/q".to_string()),
+            ("QUANT_ENGINE_JOBS", "2".to_string()),
+        ]);
+        let config = Config::from_sources(|name| vars.get(name).cloned()).unwrap();
+        assert_eq!(config.repo, Some(PathBuf::from("/home/q/lab/.hipcortex")));
+        assert_eq!(config.jobs, Some(2));
+        assert_eq!(config.out("a".into()), PathBuf::from("/runs/a"));
+        assert_eq!(config.out("/abs".into()), PathBuf::from("/abs"));
+        assert_eq!(config.data("d.parquet".into()), PathBuf::from("d.parquet"));
+        assert_eq!(
+            config.repo(Some("flag".into())),
+            Some(PathBuf::from("flag"))
+        );
+
+        fs::write(config_dir.join("config.toml"), "outroot = \"/runs\"\n").unwrap();
+        assert!(Config::from_sources(|name| vars.get(name).cloned()).is_err());
+
+        vars.insert(CONFIG_ENV, "/missing/config.toml".to_string());
+        assert!(Config::from_sources(|name| vars.get(name).cloned()).is_err());
+    }
+}
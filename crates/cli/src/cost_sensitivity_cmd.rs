@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::backtest_cmd::{execute_backtest, load_spec, write_effective_spec, write_outputs};
+use crate::data::load_dataset;
+use crate::spec::CostModelSpec;
+
+/// Multipliers `--cost-sensitivity` runs when given none
+pub const DEFAULT_MULTIPLIERS: &str = "0,1,2,5";
+
+/// Result of one cost multiplier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostRunRow {
+    pub multiplier: f64,
+    pub total_return: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub num_trades: usize,
+    /// Commission, fees and slippage paid
+    pub total_costs: f64,
+    /// Change in total return from the baseline run
+    pub return_change: f64,
+    /// Change in Sharpe ratio from the baseline run
+    pub sharpe_change: f64,
+    pub crv_passed: bool,
+}
+
+/// How a run's results degrade as its trading costs are multiplied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostSensitivityReport {
+    /// Multiplier the changes are measured from: 1x when it was run, else
+    /// the lowest
+    pub baseline: f64,
+    /// In ascending order of multiplier
+    pub runs: Vec<CostRunRow>,
+    /// Lowest multiplier run at which the Sharpe ratio is no longer positive
+    pub sharpe_breakeven: Option<f64>,
+}
+
+impl CostSensitivityReport {
+    /// Measure `runs`, in ascending order of multiplier, from the baseline
+    pub fn from_runs(mut runs: Vec<CostRunRow>) -> Self {
+        let baseline = runs.iter().position(|r| r.multiplier == 1.0).unwrap_or(0);
+        let (base_return, base_sharpe) = runs
+            .get(baseline)
+            .map_or((0.0, 0.0), |r| (r.total_return, r.sharpe_ratio));
+        for run in &mut runs {
+            run.return_change = run.total_return - base_return;
+            run.sharpe_change = run.sharpe_ratio - base_sharpe;
+        }
+        Self {
+            baseline: runs.get(baseline).map_or(1.0, |r| r.multiplier),
+            sharpe_breakeven: runs
+                .iter()
+                .find(|r| r.sharpe_ratio <= 0.0)
+                .map(|r| r.multiplier),
+            runs,
+        }
+    }
+}
+
+/// Parse `--cost-sensitivity`: a comma list of multipliers, each with an
+/// optional `x` (`0,1,2,5` or `0x,1x,2x,5x`), sorted and deduplicated
+pub fn parse_multipliers(value: &str) -> Result<Vec<f64>> {
+    let mut multipliers = value
+        .split(',')
+        .map(|s| {
+            let s = s.trim();
+            let multiplier: f64 = s
+                .strip_suffix('x')
+                .unwrap_or(s)
+                .parse()
+                .with_context(|| format!("Invalid cost multiplier '{}'", s))?;
+            if !(multiplier >= 0.0 && multiplier.is_finite()) {
+                anyhow::bail!("Cost multiplier '{}' must be a non-negative number", s);
+            }
+            Ok(multiplier)
+        })
+        .collect::<Result<Vec<f64>>>()?;
+    multipliers.sort_by(f64::total_cmp);
+    multipliers.dedup();
+    Ok(multipliers)
+}
+
+/// Run one spec with its trading costs scaled by each multiplier and report
+/// how the results degrade
+pub fn run_cost_sensitivity(
+    spec_path: &Path,
+    overrides: &[String],
+    data_path: &Path,
+    out_dir: &Path,
+    multipliers: &[f64],
+) -> Result<CostSensitivityReport> {
+    let spec = load_spec(spec_path, overrides)?;
+    let dataset = load_dataset(data_path, &spec.data_pipeline)?;
+    fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+    write_effective_spec(&spec, out_dir)?;
+    println!(
+        "Running {} cost multiplier(s) over {} bars",
+        multipliers.len(),
+        dataset.bars.len()
+    );
+
+    let outcomes = multipliers
+        .par_iter()
+        .map(|multiplier| {
+            let mut scaled = spec.clone();
+            scaled.cost_model = CostModelSpec::Scaled {
+                multiplier: *multiplier,
+                model: Box::new(spec.cost_model.clone()),
+            };
+            execute_backtest(&scaled, &dataset.bars, &dataset.halts, &dataset.quality)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut runs = Vec::with_capacity(multipliers.len());
+    for (multiplier, outcome) in multipliers.iter().zip(&outcomes) {
+        let run_dir = out_dir.join("costs").join(format!("cost_{}x", multiplier));
+        fs::create_dir_all(&run_dir).context("Failed to create cost run directory")?;
+        write_outputs(outcome, &run_dir)?;
+        let costs = outcome.stats.costs;
+        runs.push(CostRunRow {
+            multiplier: *multiplier,
+            total_return: outcome.stats.total_return,
+            sharpe_ratio: outcome.stats.sharpe_ratio,
+            max_drawdown: outcome.stats.max_drawdown,
+            num_trades: outcome.stats.num_trades,
+            total_costs: costs.charged() + costs.slippage,
+            return_change: 0.0,
+            sharpe_change: 0.0,
+            crv_passed: outcome.crv_report.passed,
+        });
+    }
+
+    let report = CostSensitivityReport::from_runs(runs);
+    let json_file = fs::File::create(out_dir.join("cost_sensitivity.json"))?;
+    serde_json::to_writer_pretty(json_file, &report)?;
+    let mut wtr = csv::Writer::from_path(out_dir.join("cost_sensitivity.csv"))?;
+    for row in &report.runs {
+        wtr.serialize(row)?;
+    }
+    wtr.flush()?;
+
+    print_report(&report);
+    Ok(report)
+}
+
+fn print_report(report: &CostSensitivityReport) {
+    println!(
+        "\n=== Cost Sensitivity (changes from {}x costs) ===",
+        report.baseline
+    );
+    println!(
+        "{:>8} {:>12} {:>12} {:>10} {:>10} {:>10} {:>8} {:>12}",
+        "costs", "return", "Δ return", "sharpe", "Δ sharpe", "max dd", "trades", "costs paid"
+    );
+    for run in &report.runs {
+        println!(
+            "{:>7}x {:>11.2}% {:>11.2}% {:>10.4} {:>10.4} {:>9.2}% {:>8} {:>12.2}",
+            run.multiplier,
+            run.total_return * 100.0,
+            run.return_change * 100.0,
+            run.sharpe_ratio,
+            run.sharpe_change,
+            run.max_drawdown * 100.0,
+            run.num_trades,
+            run.total_costs
+        );
+    }
+    match report.sharpe_breakeven {
+        Some(multiplier) => println!("\n✗ Sharpe is no longer positive at {}x costs", multiplier),
+        None => println!(
+            "\n✓ Sharpe stays positive up to {}x costs",
+            report.runs.last().map_or(0.0, |r| r.multiplier)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(multiplier: f64, total_return: f64, sharpe_ratio: f64) -> CostRunRow {
+        CostRunRow {
+            multiplier,
+            total_return,
+            sharpe_ratio,
+            max_drawdown: 0.1,
+            num_trades: 10,
+            total_costs: 100.0 * multiplier,
+            return_change: 0.0,
+            sharpe_change: 0.0,
+            crv_passed: true,
+        }
+    }
+
+    #[test]
+    fn parses_multipliers_with_or_without_an_x() {
+        assert_eq!(
+            parse_multipliers("5x, 0,1x,2").unwrap(),
+            [0.0, 1.0, 2.0, 5.0]
+        );
+        assert_eq!(parse_multipliers("1,1").unwrap(), [1.0]);
+        assert!(parse_multipliers("-1").is_err());
+        assert!(parse_multipliers("two").is_err());
+    }
+
+    #[test]
+    fn changes_are_measured_from_one_x() {
+        let report = CostSensitivityReport::from_runs(vec![
+            row(0.0, 0.12, 1.2),
+            row(1.0, 0.10, 1.0),
+            row(2.0, 0.05, 0.4),
+            row(5.0, -0.08, -0.6),
+        ]);
+        assert_eq!(report.baseline, 1.0);
+        assert!((report.runs[0].sharpe_change - 0.2).abs() < 1e-12);
+        assert!((report.runs[3].return_change + 0.18).abs() < 1e-12);
+        assert_eq!(report.sharpe_breakeven, Some(5.0));
+
+        let no_one =
+            CostSensitivityReport::from_runs(vec![row(2.0, 0.05, 0.4), row(3.0, 0.01, 0.1)]);
+        assert_eq!((no_one.baseline, no_one.sharpe_breakeven), (2.0, None));
+    }
+}
@@ -0,0 +1,464 @@
+use anyhow::{Context, Result};
+use broker_sim::TradingHalts;
+use schema::{
+    assess_data_quality, sort_events_deterministically, validate_bar_events, validate_bars,
+    validate_events_for_tier, Bar, BarValidationReport, DataQualityReport, EventEnvelope,
+    FidelityTier, MarketEventPayload, MarketEventType, QualityFlag,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::canonical::{is_canonical_parquet, read_canonical_parquet};
+use crate::columnar::{is_columnar, ColumnarBars};
+use crate::parquet_feed::ParquetBars;
+use crate::spec::DataPipelineSpec;
+
+/// Bars ready for the engine plus the quality checks run while loading them
+pub struct LoadedDataset {
+    pub bars: Vec<Bar>,
+    /// Halts and price-limit states from the data's trading status events
+    pub halts: TradingHalts,
+    pub quality: DataQualityReport,
+    pub bar_report: BarValidationReport,
+}
+
+/// Load, score and prepare data for a backtest
+pub fn load_dataset(data_path: &Path, pipeline: &DataPipelineSpec) -> Result<LoadedDataset> {
+    let raw_events = load_raw_events(data_path)?;
+
+    // Score the input in source order so disorder and duplicates are visible
+    let quality = assess_data_quality(&raw_events, None);
+
+    // Merge into one deterministic stream
+    let bars = match pipeline {
+        // Needs each event's ingest time, which the bars alone don't carry
+        DataPipelineSpec::PointInTime => point_in_time_bars(&raw_events)?,
+        _ => prepare_bars(bars_from_events(&raw_events), pipeline)?,
+    };
+    let bar_report = validate_bars(&bars);
+
+    Ok(LoadedDataset {
+        bars,
+        halts: TradingHalts::from_events(&raw_events),
+        quality,
+        bar_report,
+    })
+}
+
+/// Resolve `--data` into a sorted list of parquet (or columnar `.arrow`) files.
+///
+/// Accepts a single file, a directory (every `*.parquet` and `*.arrow`
+/// directly inside it), or a glob pattern such as `data/*_2023-*.parquet`.
+pub fn resolve_data_files(data_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = if data_path.is_dir() {
+        std::fs::read_dir(data_path)
+            .with_context(|| format!("Failed to read data directory {:?}", data_path))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.is_file() && (p.extension().is_some_and(|ext| ext == "parquet") || is_columnar(p))
+            })
+            .collect::<Vec<_>>()
+    } else if data_path.exists() {
+        vec![data_path.to_path_buf()]
+    } else {
+        let pattern = data_path.to_string_lossy();
+        glob::glob(&pattern)
+            .with_context(|| format!("Invalid data glob pattern {:?}", pattern))?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to expand data glob pattern")?
+            .into_iter()
+            .filter(|p| p.is_file())
+            .collect()
+    };
+
+    if files.is_empty() {
+        anyhow::bail!("No parquet files found for data path {:?}", data_path);
+    }
+
+    // Path order makes the concatenated source order reproducible
+    files.sort();
+    Ok(files)
+}
+
+/// Load canonical events from every resolved file, concatenated in path order.
+///
+/// Canonical event parquet is read as-is; legacy bar parquet and columnar
+/// files are bridged into Tier 1 bar events a chunk or row group at a time.
+pub fn load_raw_events(data_path: &Path) -> Result<Vec<EventEnvelope>> {
+    let mut events = Vec::new();
+    for file in resolve_data_files(data_path)? {
+        if is_columnar(&file) {
+            load_columnar_events(&file, &mut events)
+                .with_context(|| format!("Failed to load columnar file {:?}", file))?;
+        } else if is_canonical_parquet(&file)? {
+            events.extend(
+                read_canonical_parquet(&file)
+                    .with_context(|| format!("Failed to load parquet file {:?}", file))?,
+            );
+        } else {
+            load_legacy_parquet_events(&file, &mut events)
+                .with_context(|| format!("Failed to load parquet file {:?}", file))?;
+        }
+    }
+    Ok(events)
+}
+
+fn load_legacy_parquet_events(path: &Path, events: &mut Vec<EventEnvelope>) -> Result<()> {
+    for chunk in ParquetBars::open(path)? {
+        events.extend(bars_to_canonical_tier1_events(&chunk?, "legacy-parquet"));
+    }
+    Ok(())
+}
+
+fn load_columnar_events(path: &Path, events: &mut Vec<EventEnvelope>) -> Result<()> {
+    for chunk in ColumnarBars::open(path)? {
+        events.extend(bars_to_canonical_tier1_events(&chunk?, "columnar"));
+    }
+    Ok(())
+}
+
+/// Bar payloads of an event stream, in stream order
+pub fn bars_from_events(events: &[EventEnvelope]) -> Vec<Bar> {
+    events
+        .iter()
+        .filter_map(|event| match &event.payload {
+            MarketEventPayload::Bar(bar) => Some(bar.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Merge bars from one or more files into a single deterministic stream
+pub fn merge_bars_deterministically(bars: &mut [Bar]) {
+    bars.sort_by(|a, b| {
+        a.timestamp
+            .cmp(&b.timestamp)
+            .then_with(|| a.symbol.cmp(&b.symbol))
+    });
+}
+
+/// Apply the spec's data pipeline to raw bars
+pub fn prepare_bars(mut bars: Vec<Bar>, pipeline: &DataPipelineSpec) -> Result<Vec<Bar>> {
+    match pipeline {
+        DataPipelineSpec::Legacy => {
+            merge_bars_deterministically(&mut bars);
+            Ok(bars)
+        }
+        DataPipelineSpec::CanonicalTier1 => canonical_tier1_bridge(&bars),
+        DataPipelineSpec::PointInTime => {
+            point_in_time_bars(&bars_to_canonical_tier1_events(&bars, "legacy-parquet"))
+        }
+    }
+}
+
+/// Route bars through the canonical Tier 1 event path (sort + tier and bar validation)
+pub fn canonical_tier1_bridge(bars: &[Bar]) -> Result<Vec<Bar>> {
+    let mut events = bars_to_canonical_tier1_events(bars, "legacy-parquet");
+
+    sort_events_deterministically(&mut events);
+    validate_events_for_tier(&events, FidelityTier::Tier1Bar)
+        .context("Canonical Tier 1 validation failed")?;
+    validate_bar_events(&events)
+        .ensure_valid()
+        .context("Canonical Tier 1 bar validation failed")?;
+
+    canonical_tier1_events_to_bars(&events)
+}
+
+/// Project canonical bar events to bars in the order the strategy could
+/// have seen them.
+///
+/// Each bar becomes visible once its source delivered it, at the later of
+/// its event and ingest times, and is stamped with that time. A bar that
+/// arrived late is replayed where it arrived rather than where it belongs,
+/// so it can't inform decisions made before it existed. A bar that arrives
+/// after a newer bar of its symbol is dropped, so prices never step back
+/// in time.
+pub fn point_in_time_bars(events: &[EventEnvelope]) -> Result<Vec<Bar>> {
+    let mut events: Vec<EventEnvelope> = events
+        .iter()
+        .filter(|event| matches!(event.payload, MarketEventPayload::Bar(_)))
+        .cloned()
+        .collect();
+    sort_events_deterministically(&mut events);
+    validate_events_for_tier(&events, FidelityTier::Tier1Bar)
+        .context("Canonical Tier 1 validation failed")?;
+    validate_bar_events(&events)
+        .ensure_valid()
+        .context("Canonical Tier 1 bar validation failed")?;
+
+    // Stable, so bars available at the same time keep their event order
+    events.sort_by_key(|event| event.event_time.max(event.ingest_time));
+    let mut latest: HashMap<String, i64> = HashMap::new();
+    events.retain(|event| match latest.get_mut(&event.symbol) {
+        Some(newest) if event.event_time < *newest => false,
+        Some(newest) => {
+            *newest = event.event_time;
+            true
+        }
+        None => {
+            latest.insert(event.symbol.clone(), event.event_time);
+            true
+        }
+    });
+    let mut bars = canonical_tier1_events_to_bars(&events)?;
+    for (bar, event) in bars.iter_mut().zip(&events) {
+        bar.timestamp = event.event_time.max(event.ingest_time);
+    }
+    Ok(bars)
+}
+
+pub fn bars_to_canonical_tier1_events(bars: &[Bar], source_id: &str) -> Vec<EventEnvelope> {
+    bars.iter()
+        .map(|bar| EventEnvelope {
+            event_type: MarketEventType::Bar,
+            symbol: bar.symbol.clone(),
+            event_time: bar.timestamp,
+            ingest_time: bar.timestamp,
+            source_id: source_id.to_string(),
+            quality_flags: vec![QualityFlag::DerivedValue],
+            payload: MarketEventPayload::Bar(bar.clone()),
+        })
+        .collect()
+}
+
+pub fn canonical_tier1_events_to_bars(events: &[EventEnvelope]) -> Result<Vec<Bar>> {
+    let mut bars = Vec::new();
+
+    for event in events {
+        event
+            .validate_required_fields()
+            .context("Invalid canonical event encountered")?;
+
+        if let MarketEventPayload::Bar(bar) = &event.payload {
+            bars.push(bar.clone());
+        }
+    }
+
+    Ok(bars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::*;
+
+    #[test]
+    fn canonical_tier1_bridge_preserves_legacy_bars() {
+        let legacy = vec![
+            Bar {
+                timestamp: 1000,
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 102.0,
+                low: 99.0,
+                close: 101.0,
+                volume: 10000.0,
+            },
+            Bar {
+                timestamp: 2000,
+                symbol: "AAPL".to_string(),
+                open: 101.0,
+                high: 103.0,
+                low: 100.0,
+                close: 102.0,
+                volume: 11000.0,
+            },
+        ];
+
+        let events = bars_to_canonical_tier1_events(&legacy, "legacy-parquet");
+        let recovered = canonical_tier1_events_to_bars(&events).unwrap();
+
+        assert_eq!(legacy, recovered);
+    }
+
+    #[test]
+    fn point_in_time_bars_wait_for_their_ingest_time() {
+        let bar = |timestamp: i64, symbol: &str| Bar {
+            timestamp,
+            symbol: symbol.to_string(),
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.5,
+            volume: 1000.0,
+        };
+        let mut events = bars_to_canonical_tier1_events(
+            &[bar(1000, "AAPL"), bar(1000, "MSFT"), bar(2000, "AAPL")],
+            "vendor",
+        );
+        // MSFT's first bar was delivered after AAPL's second
+        events[1].ingest_time = 2500;
+
+        let order: Vec<(i64, String)> = point_in_time_bars(&events)
+            .unwrap()
+            .into_iter()
+            .map(|b| (b.timestamp, b.symbol))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                (1000, "AAPL".to_string()),
+                (2000, "AAPL".to_string()),
+                (2500, "MSFT".to_string())
+            ]
+        );
+
+        // AAPL's first bar arriving after its second is superseded
+        let mut events = bars_to_canonical_tier1_events(
+            &[bar(1000, "AAPL"), bar(1000, "MSFT"), bar(2000, "AAPL")],
+            "vendor",
+        );
+        events[0].ingest_time = 2500;
+        let order: Vec<(i64, String)> = point_in_time_bars(&events)
+            .unwrap()
+            .into_iter()
+            .map(|b| (b.timestamp, b.symbol))
+            .collect();
+        assert_eq!(
+            order,
+            [(1000, "MSFT".to_string()), (2000, "AAPL".to_string())]
+        );
+
+        // With ingest equal to event time it matches the canonical bridge
+        let bars = [bar(2000, "AAPL"), bar(1000, "AAPL")];
+        assert_eq!(
+            prepare_bars(bars.to_vec(), &DataPipelineSpec::PointInTime).unwrap(),
+            canonical_tier1_bridge(&bars).unwrap()
+        );
+    }
+
+    #[test]
+    fn directory_and_glob_inputs_merge_deterministically() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, symbol: &str, timestamps: &[i64]| {
+            let n = timestamps.len();
+            let mut df = df!(
+                "timestamp" => timestamps,
+                "symbol" => vec![symbol; n],
+                "open" => vec![100.0; n],
+                "high" => vec![101.0; n],
+                "low" => vec![99.0; n],
+                "close" => vec![100.5; n],
+                "volume" => vec![1000.0; n],
+            )
+            .unwrap();
+            let file = std::fs::File::create(dir.path().join(name)).unwrap();
+            ParquetWriter::new(file).finish(&mut df).unwrap();
+        };
+        write("msft.parquet", "MSFT", &[1000, 2000]);
+        write("aapl.parquet", "AAPL", &[2000, 1000]);
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let files = resolve_data_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].ends_with("aapl.parquet"));
+
+        let raw = bars_from_events(&load_raw_events(dir.path()).unwrap());
+        let merged = prepare_bars(raw, &DataPipelineSpec::Legacy).unwrap();
+        let order: Vec<(i64, &str)> = merged
+            .iter()
+            .map(|b| (b.timestamp, b.symbol.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (1000, "AAPL"),
+                (1000, "MSFT"),
+                (2000, "AAPL"),
+                (2000, "MSFT")
+            ]
+        );
+
+        let pattern = dir.path().join("m*.parquet");
+        let globbed = bars_from_events(&load_raw_events(&pattern).unwrap());
+        assert_eq!(globbed.len(), 2);
+        assert!(globbed.iter().all(|b| b.symbol == "MSFT"));
+
+        assert!(resolve_data_files(&dir.path().join("none-*.parquet")).is_err());
+    }
+
+    #[test]
+    fn trading_status_events_load_as_halts() {
+        use crate::canonical::write_canonical_parquet;
+        use schema::{LatencyClass, TradingState, TradingStatusPayload};
+
+        let bar = Bar {
+            timestamp: 1000,
+            symbol: "GME".to_string(),
+            open: 100.0,
+            high: 102.0,
+            low: 99.0,
+            close: 101.0,
+            volume: 10000.0,
+        };
+        let mut events = bars_to_canonical_tier1_events(&[bar], "vendor");
+        events.push(EventEnvelope {
+            event_type: MarketEventType::TradingStatus,
+            payload: MarketEventPayload::TradingStatus(TradingStatusPayload {
+                state: TradingState::Halted,
+                reason: Some("LUDP".to_string()),
+            }),
+            event_time: 1500,
+            ..events[0].clone()
+        });
+        let metadata = hipcortex::DatasetMetadata {
+            symbols: vec!["GME".to_string()],
+            start_timestamp: 1000,
+            end_timestamp: 1500,
+            bar_count: 1,
+            provider: "vendor".to_string(),
+            venue_class: "lit".to_string(),
+            timezone_calendar: "America/New_York/XNYS".to_string(),
+            adjustment_policy: "split_adjusted".to_string(),
+            fidelity_tier: FidelityTier::Tier1Bar,
+            latency_class: LatencyClass::Delayed,
+            quality_flags: vec![],
+            transform_lineage: vec![],
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gme.parquet");
+        write_canonical_parquet(&events, &metadata, &path).unwrap();
+
+        let dataset = load_dataset(&path, &DataPipelineSpec::Legacy).unwrap();
+        assert_eq!(dataset.bars.len(), 1);
+        assert_eq!(dataset.halts.state("GME", 1499), TradingState::Trading);
+        assert_eq!(dataset.halts.state("GME", 1500), TradingState::Halted);
+    }
+
+    #[test]
+    fn tier_readiness_requires_trade_or_quote_for_tier2() {
+        let events = bars_to_canonical_tier1_events(
+            &[Bar {
+                timestamp: 1000,
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 102.0,
+                low: 99.0,
+                close: 101.0,
+                volume: 10000.0,
+            }],
+            "legacy-parquet",
+        );
+
+        assert!(validate_events_for_tier(&events, FidelityTier::Tier2TickQuote).is_err());
+    }
+
+    #[test]
+    fn tier_readiness_requires_book_for_tier3() {
+        let events = bars_to_canonical_tier1_events(
+            &[Bar {
+                timestamp: 1000,
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 102.0,
+                low: 99.0,
+                close: 101.0,
+                volume: 10000.0,
+            }],
+            "legacy-parquet",
+        );
+
+        assert!(validate_events_for_tier(&events, FidelityTier::Tier3OrderBook).is_err());
+    }
+}
@@ -0,0 +1,690 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use cost::standard_normal;
+use hipcortex::{Artifact, Dataset, DatasetMetadata, Repository};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use schema::{
+    assess_data_quality, sort_events_deterministically, validate_bar_events,
+    validate_events_for_tier, Bar, BarValidationReport, DataQualityReport, EventEnvelope,
+    FidelityTier, LatencyClass, MarketDataAdapter, MarketEventPayload, ProviderRecord,
+    TransformationStep,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::adapters::OhlcvRecordAdapter;
+use crate::canonical::{is_canonical_parquet, read_dataset_metadata, write_canonical_parquet};
+use crate::columnar::write_columnar;
+use crate::data::{
+    bars_from_events, bars_to_canonical_tier1_events, load_dataset, load_raw_events,
+    resolve_data_files,
+};
+use crate::importers::{import_bars, ImportOptions};
+use crate::spec::DataPipelineSpec;
+
+/// Maximum number of individual issues printed to the console
+const MAX_PRINTED_ISSUES: usize = 10;
+
+/// Fidelity tier accepted on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TierArg {
+    Tier1,
+    Tier2,
+    Tier3,
+}
+
+impl From<TierArg> for FidelityTier {
+    fn from(tier: TierArg) -> Self {
+        match tier {
+            TierArg::Tier1 => FidelityTier::Tier1Bar,
+            TierArg::Tier2 => FidelityTier::Tier2TickQuote,
+            TierArg::Tier3 => FidelityTier::Tier3OrderBook,
+        }
+    }
+}
+
+/// Latency class accepted on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LatencyArg {
+    Realtime,
+    Delayed,
+    EndOfDay,
+    Unknown,
+}
+
+impl From<LatencyArg> for LatencyClass {
+    fn from(latency: LatencyArg) -> Self {
+        match latency {
+            LatencyArg::Realtime => LatencyClass::Realtime,
+            LatencyArg::Delayed => LatencyClass::Delayed,
+            LatencyArg::EndOfDay => LatencyClass::EndOfDay,
+            LatencyArg::Unknown => LatencyClass::Unknown,
+        }
+    }
+}
+
+/// Input formats `data convert` understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// OHLCV bars with columns timestamp,symbol,open,high,low,close,volume
+    Csv,
+    /// One canonical event envelope JSON object per line
+    Jsonl,
+    /// One provider-native record JSON object per line, normalized by the OHLCV adapter
+    Provider,
+}
+
+/// Provenance recorded in the converted dataset's metadata
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    pub provider: String,
+    pub venue_class: String,
+    pub timezone_calendar: String,
+    pub adjustment_policy: String,
+    pub latency_class: LatencyClass,
+}
+
+/// An event missing required fields or carrying an invalid payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventIssue {
+    pub index: usize,
+    pub symbol: String,
+    pub event_time: i64,
+    pub message: String,
+}
+
+/// Everything `data validate` checks, in one structured report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataValidationReport {
+    pub files: Vec<String>,
+    pub tier: FidelityTier,
+    /// `None` when the batch satisfies the tier's readiness requirements
+    pub tier_error: Option<String>,
+    pub event_issues: Vec<EventIssue>,
+    /// Metadata embedded in canonical input files
+    pub metadata: Vec<DatasetMetadata>,
+    pub bars: BarValidationReport,
+    pub quality: DataQualityReport,
+    pub passed: bool,
+}
+
+/// Validate a dataset and return whether it is fit for backtesting
+pub fn run_validate(
+    data_path: &Path,
+    tier: TierArg,
+    expected_interval: Option<i64>,
+    json_path: Option<&Path>,
+) -> Result<bool> {
+    let files = resolve_data_files(data_path)?;
+    let events = load_raw_events(data_path)?;
+
+    let mut report = validate_events(&events, tier.into(), expected_interval);
+    report.files = files.iter().map(|f| f.display().to_string()).collect();
+    for file in &files {
+        if is_canonical_parquet(file)? {
+            report.metadata.extend(read_dataset_metadata(file)?);
+        }
+    }
+
+    print_report(&report);
+
+    if let Some(path) = json_path {
+        let file = fs::File::create(path)
+            .with_context(|| format!("Failed to create report file {:?}", path))?;
+        serde_json::to_writer_pretty(file, &report)?;
+        println!("Wrote data validation report to {:?}", path);
+    }
+
+    Ok(report.passed)
+}
+
+/// Run field, tier, bar consistency and quality checks over canonical events.
+///
+/// Events are checked in the order given so duplicates and disorder in the
+/// source are reported rather than hidden by sorting.
+pub fn validate_events(
+    events: &[EventEnvelope],
+    tier: FidelityTier,
+    expected_interval: Option<i64>,
+) -> DataValidationReport {
+    let event_issues: Vec<EventIssue> = events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, event)| {
+            event
+                .validate_required_fields()
+                .err()
+                .map(|err| EventIssue {
+                    index,
+                    symbol: event.symbol.clone(),
+                    event_time: event.event_time,
+                    message: err.to_string(),
+                })
+        })
+        .collect();
+
+    // Field errors are already itemised above; only report the readiness failure itself
+    let tier_error = if event_issues.is_empty() {
+        validate_events_for_tier(events, tier)
+            .err()
+            .map(|err| err.to_string())
+    } else {
+        None
+    };
+
+    let bars = validate_bar_events(events);
+    let quality = assess_data_quality(events, expected_interval);
+    let passed = tier_error.is_none()
+        && event_issues.is_empty()
+        && bars.is_valid()
+        && quality.duplicate_events == 0;
+
+    DataValidationReport {
+        files: Vec::new(),
+        tier,
+        tier_error,
+        event_issues,
+        metadata: Vec::new(),
+        bars,
+        quality,
+        passed,
+    }
+}
+
+/// Convert a CSV / JSON-lines / provider-native file into canonical event parquet
+pub fn run_convert(
+    input: &Path,
+    format: InputFormat,
+    output: &Path,
+    options: &ConvertOptions,
+) -> Result<()> {
+    let (events, metadata) = convert_file(input, format, options)?;
+    write_canonical_parquet(&events, &metadata, output)?;
+
+    println!(
+        "Converted {} event(s) for {} symbol(s) from {:?}",
+        events.len(),
+        metadata.symbols.len(),
+        input
+    );
+    println!("Fidelity tier: {:?}", metadata.fidelity_tier);
+    println!(
+        "Provider: {}, calendar: {}, adjustment: {}",
+        metadata.provider, metadata.timezone_calendar, metadata.adjustment_policy
+    );
+    println!("Wrote canonical events to {:?}", output);
+    Ok(())
+}
+
+/// Import a Zipline or Lean layout as canonical event parquet, optionally
+/// committing it to HipCortex as a Dataset
+pub fn run_import(
+    input: &Path,
+    options: &ImportOptions,
+    output: &Path,
+    repo_path: Option<&Path>,
+) -> Result<()> {
+    let imported = import_bars(input, options)?;
+    let convert = ConvertOptions {
+        provider: imported.provider,
+        venue_class: "lit".to_string(),
+        timezone_calendar: imported.timezone_calendar,
+        adjustment_policy: "unadjusted".to_string(),
+        latency_class: LatencyClass::EndOfDay,
+    };
+    let (events, metadata) = canonicalize(
+        bars_to_canonical_tier1_events(&imported.bars, &convert.provider),
+        imported.lineage,
+        &convert,
+    )?;
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_canonical_parquet(&events, &metadata, output)?;
+    println!(
+        "Imported {} bar(s) for {} symbol(s) from {:?} ({:?})",
+        events.len(),
+        metadata.symbols.len(),
+        input,
+        options.format
+    );
+    println!("Wrote canonical events to {:?}", output);
+
+    if let Some(repo_path) = repo_path {
+        let mut repo = Repository::open(repo_path).context("Failed to open repository")?;
+        let dataset = Artifact::Dataset(Dataset {
+            name: format!("{}:{}", metadata.provider, metadata.symbols.join(",")),
+            description: format!("{:?} import from {:?}", options.format, input),
+            bars: bars_from_events(&events),
+            metadata,
+        });
+        let hash = repo
+            .commit(
+                &dataset,
+                &format!("Import {:?} data", options.format),
+                vec![],
+            )
+            .context("Failed to commit dataset")?;
+        println!("Committed dataset: {}", hash);
+    }
+    Ok(())
+}
+
+/// Pre-convert bars into a memory-mappable columnar file for fast loading.
+///
+/// Bars are merged into the engine's deterministic order first, so the file
+/// can be read back a chunk at a time without re-sorting.
+pub fn run_columnar(data_path: &Path, output: &Path) -> Result<()> {
+    let dataset = load_dataset(data_path, &DataPipelineSpec::Legacy)?;
+    write_columnar(&dataset.bars, output)?;
+    println!(
+        "Wrote {} bar(s) from {:?} to columnar file {:?}",
+        dataset.bars.len(),
+        data_path,
+        output
+    );
+    Ok(())
+}
+
+/// Shape of a synthetic dataset
+#[derive(Debug, Clone)]
+pub struct SyntheticSpec {
+    pub symbols: Vec<String>,
+    /// Daily bars per symbol
+    pub days: usize,
+    /// First bar, Unix seconds
+    pub start: i64,
+    pub seed: u64,
+}
+
+/// Write a seeded random-walk daily bar dataset as canonical event parquet
+pub fn run_generate(spec: &SyntheticSpec, output: &Path) -> Result<()> {
+    let bars = synthetic_bars(spec);
+    let options = ConvertOptions {
+        provider: "synthetic".to_string(),
+        venue_class: "synthetic".to_string(),
+        timezone_calendar: "UTC/24x7".to_string(),
+        adjustment_policy: "unadjusted".to_string(),
+        latency_class: LatencyClass::EndOfDay,
+    };
+    let lineage = vec![TransformationStep {
+        step: "generate".to_string(),
+        details: format!("random walk, {} day(s), seed {}", spec.days, spec.seed),
+    }];
+    let (events, metadata) = canonicalize(
+        bars_to_canonical_tier1_events(&bars, &options.provider),
+        lineage,
+        &options,
+    )?;
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_canonical_parquet(&events, &metadata, output)?;
+    println!(
+        "Generated {} bar(s) for {} symbol(s) into {:?}",
+        bars.len(),
+        spec.symbols.len(),
+        output
+    );
+    Ok(())
+}
+
+/// Daily OHLCV random walk per symbol; identical for identical specs
+pub fn synthetic_bars(spec: &SyntheticSpec) -> Vec<Bar> {
+    let mut rng = ChaCha8Rng::seed_from_u64(spec.seed);
+    let mut normal = move || standard_normal(&mut rng);
+
+    let mut bars = Vec::with_capacity(spec.symbols.len() * spec.days);
+    for (i, symbol) in spec.symbols.iter().enumerate() {
+        let mut close = 100.0 + 50.0 * i as f64;
+        for day in 0..spec.days {
+            let open = close * (1.0 + 0.005 * normal());
+            close *= 1.0 + 0.0005 + 0.02 * normal();
+            let high = open.max(close) * (1.0 + 0.01 * normal().abs());
+            let low = open.min(close) * (1.0 - 0.01 * normal().abs());
+            bars.push(Bar {
+                timestamp: spec.start + day as i64 * 86_400,
+                symbol: symbol.clone(),
+                open,
+                high,
+                low,
+                close,
+                volume: (1_000_000.0 * (1.0 + 0.3 * normal())).max(1_000.0).round(),
+            });
+        }
+    }
+    bars
+}
+
+/// Parse, normalize, sort and validate an input file into canonical events
+pub fn convert_file(
+    input: &Path,
+    format: InputFormat,
+    options: &ConvertOptions,
+) -> Result<(Vec<EventEnvelope>, DatasetMetadata)> {
+    let source = format!("{:?}", input);
+    let mut lineage = Vec::new();
+
+    let events = match format {
+        InputFormat::Csv => {
+            let mut rdr = csv::Reader::from_path(input)
+                .with_context(|| format!("Failed to open CSV file {}", source))?;
+            let bars = rdr
+                .deserialize::<Bar>()
+                .enumerate()
+                .map(|(i, row)| row.with_context(|| format!("Invalid CSV row {}", i + 1)))
+                .collect::<Result<Vec<_>>>()?;
+            bars_to_canonical_tier1_events(&bars, &options.provider)
+        }
+        InputFormat::Jsonl => read_json_lines::<EventEnvelope>(input)?,
+        InputFormat::Provider => {
+            let records = read_json_lines::<ProviderRecord>(input)?;
+            let batch = OhlcvRecordAdapter::new(options.provider.clone())
+                .normalize_batch(records, Some(&source))?;
+            lineage.extend(batch.lineage);
+            batch.events
+        }
+    };
+    lineage.push(TransformationStep {
+        step: "convert".to_string(),
+        details: format!("{:?} from {}", format, source),
+    });
+
+    canonicalize(events, lineage, options)
+}
+
+/// Sort and validate normalized events and describe them with dataset metadata
+pub fn canonicalize(
+    mut events: Vec<EventEnvelope>,
+    mut lineage: Vec<TransformationStep>,
+    options: &ConvertOptions,
+) -> Result<(Vec<EventEnvelope>, DatasetMetadata)> {
+    sort_events_deterministically(&mut events);
+    lineage.push(TransformationStep {
+        step: "sort_events_deterministically".to_string(),
+        details: "event_time, symbol, event_type".to_string(),
+    });
+
+    let tier = infer_tier(&events);
+    validate_events_for_tier(&events, tier).context("Converted events failed validation")?;
+
+    let symbols: BTreeSet<String> = events.iter().map(|e| e.symbol.clone()).collect();
+    let quality_flags: BTreeSet<_> = events
+        .iter()
+        .flat_map(|e| e.quality_flags.iter().copied())
+        .collect();
+    let metadata = DatasetMetadata {
+        symbols: symbols.into_iter().collect(),
+        start_timestamp: events.first().map(|e| e.event_time).unwrap_or(0),
+        end_timestamp: events.last().map(|e| e.event_time).unwrap_or(0),
+        bar_count: events
+            .iter()
+            .filter(|e| matches!(e.payload, MarketEventPayload::Bar(_)))
+            .count(),
+        provider: options.provider.clone(),
+        venue_class: options.venue_class.clone(),
+        timezone_calendar: options.timezone_calendar.clone(),
+        adjustment_policy: options.adjustment_policy.clone(),
+        fidelity_tier: tier,
+        latency_class: options.latency_class,
+        quality_flags: quality_flags.into_iter().collect(),
+        transform_lineage: lineage,
+    };
+    metadata.validate_provenance()?;
+
+    Ok((events, metadata))
+}
+
+/// Highest fidelity tier the events can serve
+fn infer_tier(events: &[EventEnvelope]) -> FidelityTier {
+    let has = |f: fn(&MarketEventPayload) -> bool| events.iter().any(|e| f(&e.payload));
+    if has(|p| matches!(p, MarketEventPayload::OrderBookUpdate(_))) {
+        FidelityTier::Tier3OrderBook
+    } else if has(|p| {
+        matches!(
+            p,
+            MarketEventPayload::Trade(_) | MarketEventPayload::Quote(_)
+        )
+    }) {
+        FidelityTier::Tier2TickQuote
+    } else {
+        FidelityTier::Tier1Bar
+    }
+}
+
+fn read_json_lines<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let file = fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut items = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        items.push(
+            serde_json::from_str(&line)
+                .with_context(|| format!("Invalid JSON on line {}", i + 1))?,
+        );
+    }
+    Ok(items)
+}
+
+fn print_report(report: &DataValidationReport) {
+    println!("=== Data Validation ===");
+    println!("Files: {}", report.files.len());
+    println!(
+        "Events: {} across {} symbol(s)",
+        report.quality.total_events,
+        report.quality.symbols.len()
+    );
+    println!(
+        "Time range: {} .. {}",
+        report.quality.start_time, report.quality.end_time
+    );
+
+    for metadata in &report.metadata {
+        println!(
+            "Provider: {} ({:?}, {}, {})",
+            metadata.provider,
+            metadata.fidelity_tier,
+            metadata.timezone_calendar,
+            metadata.adjustment_policy
+        );
+    }
+
+    match &report.tier_error {
+        None if report.event_issues.is_empty() => {
+            println!("✓ Tier {:?} readiness", report.tier)
+        }
+        None => println!(
+            "✗ Tier {:?} readiness: {} event(s) with invalid fields",
+            report.tier,
+            report.event_issues.len()
+        ),
+        Some(err) => println!("✗ Tier {:?} readiness: {}", report.tier, err),
+    }
+    for issue in report.event_issues.iter().take(MAX_PRINTED_ISSUES) {
+        println!(
+            "    #{} {}@{}: {}",
+            issue.index, issue.symbol, issue.event_time, issue.message
+        );
+    }
+
+    if report.bars.is_valid() {
+        println!("✓ Bar consistency ({} bars)", report.bars.bars_checked);
+    } else {
+        println!(
+            "✗ Bar consistency: {} of {} bars invalid",
+            report.bars.invalid_bars, report.bars.bars_checked
+        );
+        for (kind, count) in report.bars.counts_by_kind() {
+            println!("    {:?}: {}", kind, count);
+        }
+        for issue in report.bars.issues.iter().take(MAX_PRINTED_ISSUES) {
+            println!(
+                "    #{} {}@{}: {}",
+                issue.index, issue.symbol, issue.timestamp, issue.message
+            );
+        }
+    }
+
+    let quality = &report.quality;
+    println!(
+        "{} Duplicates: {}",
+        if quality.duplicate_events == 0 {
+            "✓"
+        } else {
+            "✗"
+        },
+        quality.duplicate_events
+    );
+    println!(
+        "  Gaps: {} ({} missing session(s), max gap {}s, expected interval {}s)",
+        quality.gaps.gap_count,
+        quality.missing_sessions,
+        quality.gaps.max_gap,
+        quality.gaps.expected_interval
+    );
+    println!("  Out-of-order events: {}", quality.out_of_order_events);
+    println!("  Quality score: {:.1}", quality.score);
+
+    println!(
+        "\nData validation {}",
+        if report.passed { "passed" } else { "failed" }
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::QualityFlag;
+
+    fn options() -> ConvertOptions {
+        ConvertOptions {
+            provider: "vendor".to_string(),
+            venue_class: "lit".to_string(),
+            timezone_calendar: "America/New_York/XNYS".to_string(),
+            adjustment_policy: "split_adjusted".to_string(),
+            latency_class: LatencyClass::EndOfDay,
+        }
+    }
+
+    fn bar(timestamp: i64, high: f64) -> Bar {
+        Bar {
+            timestamp,
+            symbol: "AAPL".to_string(),
+            open: 100.0,
+            high,
+            low: 99.0,
+            close: 100.5,
+            volume: 1_000.0,
+        }
+    }
+
+    #[test]
+    fn clean_bars_pass_tier1() {
+        let bars: Vec<Bar> = (1..=5).map(|i| bar(i * 60, 101.0)).collect();
+        let events = bars_to_canonical_tier1_events(&bars, "test");
+        let report = validate_events(&events, FidelityTier::Tier1Bar, None);
+
+        assert!(report.passed);
+        assert!(report.tier_error.is_none());
+        assert_eq!(report.quality.missing_sessions, 0);
+    }
+
+    #[test]
+    fn reports_tier_bar_and_duplicate_problems_together() {
+        let bars = vec![bar(60, 101.0), bar(60, 101.0), bar(120, 90.0)];
+        let events = bars_to_canonical_tier1_events(&bars, "test");
+
+        let report = validate_events(&events, FidelityTier::Tier1Bar, None);
+        assert!(!report.passed);
+        assert_eq!(report.bars.invalid_bars, 1);
+        assert_eq!(report.quality.duplicate_events, 1);
+
+        let tier2 = validate_events(&events, FidelityTier::Tier2TickQuote, None);
+        assert!(tier2.tier_error.unwrap().contains("tier2"));
+    }
+
+    #[test]
+    fn converts_csv_bars_with_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("bars.csv");
+        fs::write(
+            &input,
+            "timestamp,symbol,open,high,low,close,volume\n\
+             120,MSFT,10,11,9,10.5,100\n\
+             60,AAPL,100,101,99,100.5,1000\n",
+        )
+        .unwrap();
+
+        let (events, metadata) = convert_file(&input, InputFormat::Csv, &options()).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].symbol, "AAPL");
+        assert_eq!(events[0].source_id, "vendor");
+        assert_eq!(metadata.symbols, vec!["AAPL", "MSFT"]);
+        assert_eq!(metadata.fidelity_tier, FidelityTier::Tier1Bar);
+        assert_eq!(metadata.bar_count, 2);
+        assert_eq!(metadata.transform_lineage[0].step, "convert");
+
+        let output = dir.path().join("bars.parquet");
+        run_convert(&input, InputFormat::Csv, &output, &options()).unwrap();
+        let loaded = load_raw_events(&output).unwrap();
+        assert_eq!(loaded, events);
+    }
+
+    #[test]
+    fn converts_provider_records_through_adapter() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("records.jsonl");
+        fs::write(
+            &input,
+            concat!(
+                r#"{"symbol":"BTC-USD","event_time":120,"ingest_time":121,"raw_payload":{"o":2,"h":3,"l":1,"c":2.5,"v":7},"quality_flags":[]}"#,
+                "\n",
+                r#"{"symbol":"BTC-USD","event_time":60,"ingest_time":65,"raw_payload":{"o":1,"h":2,"l":0.5,"c":2},"quality_flags":["late_source_data"]}"#,
+                "\n"
+            ),
+        )
+        .unwrap();
+
+        let (events, metadata) = convert_file(&input, InputFormat::Provider, &options()).unwrap();
+        assert_eq!(events[0].event_time, 60);
+        assert_eq!(
+            metadata.quality_flags,
+            vec![QualityFlag::MissingSourceField, QualityFlag::LateSourceData]
+        );
+        assert_eq!(metadata.transform_lineage[0].step, "normalize_batch");
+    }
+
+    #[test]
+    fn rejects_invalid_canonical_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("events.jsonl");
+        let mut event = bars_to_canonical_tier1_events(&[bar(60, 101.0)], "vendor").remove(0);
+        event.symbol = String::new();
+        fs::write(&input, serde_json::to_string(&event).unwrap()).unwrap();
+
+        assert!(convert_file(&input, InputFormat::Jsonl, &options()).is_err());
+    }
+
+    #[test]
+    fn synthetic_bars_are_seeded_and_consistent() {
+        let spec = SyntheticSpec {
+            symbols: vec!["A".to_string(), "B".to_string()],
+            days: 50,
+            start: 1_672_617_600,
+            seed: 7,
+        };
+        let bars = synthetic_bars(&spec);
+        assert_eq!(bars.len(), 100);
+        assert_eq!(bars, synthetic_bars(&spec));
+        assert_ne!(bars, synthetic_bars(&SyntheticSpec { seed: 8, ..spec }));
+        assert!(
+            validate_bar_events(&bars_to_canonical_tier1_events(&bars, "synthetic")).is_valid()
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Per-bar feature vectors for model-driven strategies, built from the
+//! `indicators` crate.
+
+use indicators::{Atr, Ema, Macd, RollingStd, Rsi, Sma, ZScore};
+use schema::Bar;
+use std::collections::VecDeque;
+
+use crate::spec::FeatureSpec;
+
+enum Feature {
+    Return {
+        lookback: usize,
+        closes: VecDeque<f64>,
+    },
+    Sma(Sma),
+    Ema(Ema),
+    Rsi(Rsi),
+    Atr(Atr),
+    Macd(Macd),
+    Volatility {
+        previous: Option<f64>,
+        returns: RollingStd,
+    },
+    ZScore(ZScore),
+}
+
+impl Feature {
+    fn new(spec: &FeatureSpec) -> Self {
+        match *spec {
+            FeatureSpec::Return { lookback } => Feature::Return {
+                lookback,
+                closes: VecDeque::new(),
+            },
+            FeatureSpec::Sma { window } => Feature::Sma(Sma::new(window)),
+            FeatureSpec::Ema { window } => Feature::Ema(Ema::new(window)),
+            FeatureSpec::Rsi { period } => Feature::Rsi(Rsi::new(period)),
+            FeatureSpec::Atr { period } => Feature::Atr(Atr::new(period)),
+            FeatureSpec::Macd { fast, slow, signal } => {
+                Feature::Macd(Macd::new(fast, slow, signal))
+            }
+            FeatureSpec::Volatility { window } => Feature::Volatility {
+                previous: None,
+                returns: RollingStd::new(window),
+            },
+            FeatureSpec::ZScore { window } => Feature::ZScore(ZScore::new(window)),
+        }
+    }
+
+    fn update(&mut self, bar: &Bar) -> Option<f64> {
+        let close = bar.close;
+        let relative = |value: f64| (value > 0.0).then(|| close / value - 1.0);
+        match self {
+            Feature::Return { lookback, closes } => {
+                closes.push_back(close);
+                if closes.len() > *lookback + 1 {
+                    closes.pop_front();
+                }
+                (closes.len() == *lookback + 1)
+                    .then(|| closes[0])
+                    .and_then(relative)
+            }
+            Feature::Sma(sma) => sma.update(close).and_then(relative),
+            Feature::Ema(ema) => ema.update(close).and_then(relative),
+            Feature::Rsi(rsi) => rsi.update(close),
+            Feature::Atr(atr) => atr.update(bar).filter(|_| close > 0.0).map(|a| a / close),
+            Feature::Macd(macd) => macd
+                .update(close)
+                .filter(|_| close > 0.0)
+                .map(|m| m.histogram / close),
+            Feature::Volatility { previous, returns } => {
+                let previous = previous.replace(close)?;
+                if previous <= 0.0 {
+                    return None;
+                }
+                returns.update(close / previous - 1.0)
+            }
+            Feature::ZScore(z) => z.update(close),
+        }
+    }
+}
+
+/// The configured features of one symbol, updated bar by bar
+pub struct FeatureVector {
+    features: Vec<Feature>,
+}
+
+impl FeatureVector {
+    pub fn new(specs: &[FeatureSpec]) -> Self {
+        Self {
+            features: specs.iter().map(Feature::new).collect(),
+        }
+    }
+
+    /// Number of features per vector
+    pub fn width(&self) -> usize {
+        self.features.len()
+    }
+
+    /// Update every feature with `bar`; `Some` once all of them are warm
+    pub fn update(&mut self, bar: &Bar) -> Option<Vec<f32>> {
+        let values: Vec<Option<f64>> = self.features.iter_mut().map(|f| f.update(bar)).collect();
+        values.into_iter().map(|v| v.map(|v| v as f32)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(close: f64) -> Bar {
+        Bar {
+            timestamp: 0,
+            symbol: "A".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 0.0,
+        }
+    }
+
+    #[test]
+    fn vector_waits_for_every_feature_to_warm_up() {
+        let mut features = FeatureVector::new(&[
+            FeatureSpec::Return { lookback: 1 },
+            FeatureSpec::Sma { window: 3 },
+            FeatureSpec::Volatility { window: 2 },
+        ]);
+        assert_eq!(features.width(), 3);
+        assert_eq!(features.update(&bar(10.0)), None);
+        assert_eq!(features.update(&bar(11.0)), None);
+
+        let vector = features.update(&bar(12.0)).unwrap();
+        // 12/11 - 1, 12/11 - 1 (SMA of 10, 11, 12 is 11), std of (+10%, +1/11)
+        let expected = [1.0 / 11.0, 1.0 / 11.0, (0.1 - 1.0 / 11.0) / 2.0];
+        for (value, expected) in vector.iter().zip(expected) {
+            assert!((f64::from(*value) - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use hipcortex::{Artifact, Dataset, Repository};
+use schema::{AdapterRequest, FidelityTier, MarketAssetClass, MarketEventType, TransformationStep};
+use std::path::Path;
+
+use crate::canonical::write_canonical_parquet;
+use crate::data::bars_from_events;
+use crate::data_cmd::{canonicalize, ConvertOptions};
+use crate::providers::{registered_provider, FetchRequest, ProviderOptions};
+
+/// Fetch bars through a registered provider and write canonical event parquet
+pub fn run_fetch(
+    provider_name: &str,
+    request: &FetchRequest,
+    provider_options: &ProviderOptions,
+    out: &Path,
+    repo_path: Option<&Path>,
+) -> Result<()> {
+    if request.symbols.is_empty() {
+        anyhow::bail!("At least one symbol is required");
+    }
+    if request.start > request.end {
+        anyhow::bail!("Fetch start is after end");
+    }
+
+    let provider = registered_provider(provider_name, provider_options)?;
+    provider.adapter.supports_request(&AdapterRequest {
+        asset_class: MarketAssetClass::Equity,
+        event_type: MarketEventType::Bar,
+        fidelity_tier: FidelityTier::Tier1Bar,
+    })?;
+
+    let records = provider
+        .source
+        .fetch(request)
+        .with_context(|| format!("Failed to fetch from provider '{}'", provider_name))?;
+    if records.is_empty() {
+        anyhow::bail!(
+            "Provider '{}' returned no records for {}",
+            provider_name,
+            request.symbols.join(",")
+        );
+    }
+    println!("Fetched {} record(s) from {}", records.len(), provider_name);
+
+    let description = format!(
+        "{} {} {} {}..{}",
+        provider_name,
+        request.symbols.join(","),
+        request.timeframe,
+        request.start,
+        request.end
+    );
+    let batch = provider
+        .adapter
+        .normalize_batch(records, Some(&format!("fetch {}", description)))?;
+
+    let mut lineage = batch.lineage;
+    lineage.push(TransformationStep {
+        step: "fetch".to_string(),
+        details: description.clone(),
+    });
+    let options = ConvertOptions {
+        provider: provider.adapter.provider_id().to_string(),
+        venue_class: provider.source.venue_class().to_string(),
+        timezone_calendar: provider.source.timezone_calendar().to_string(),
+        adjustment_policy: request.adjustment_policy.clone(),
+        latency_class: provider.source.latency_class(),
+    };
+    let (events, metadata) = canonicalize(batch.events, lineage, &options)?;
+
+    write_canonical_parquet(&events, &metadata, out)?;
+    println!(
+        "Wrote {} canonical event(s) for {} symbol(s) to {:?}",
+        events.len(),
+        metadata.symbols.len(),
+        out
+    );
+
+    if let Some(repo_path) = repo_path {
+        let mut repo = Repository::open(repo_path).context("Failed to open repository")?;
+        let dataset = Artifact::Dataset(Dataset {
+            name: format!("{}:{}", provider_name, request.symbols.join(",")),
+            description,
+            bars: bars_from_events(&events),
+            metadata,
+        });
+        let hash = repo
+            .commit(&dataset, &format!("Fetch from {}", provider_name), vec![])
+            .context("Failed to commit dataset")?;
+        println!("Committed dataset: {}", hash);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical::read_dataset_metadata;
+    use schema::{ProviderRecord, QualityFlag};
+
+    #[test]
+    fn fetch_writes_parquet_and_commits_dataset() {
+        let dir = tempfile::tempdir().unwrap();
+        let records_path = dir.path().join("records.jsonl");
+        let lines: Vec<String> = (1..=3)
+            .map(|i| {
+                serde_json::to_string(&ProviderRecord {
+                    symbol: "AAPL".to_string(),
+                    event_time: i * 86_400,
+                    ingest_time: i * 86_400 + 60,
+                    raw_payload: serde_json::json!({"o": 10.0, "h": 11.0, "l": 9.0, "c": 10.5, "v": 100}),
+                    quality_flags: vec![],
+                })
+                .unwrap()
+            })
+            .collect();
+        std::fs::write(&records_path, lines.join("\n")).unwrap();
+
+        let out = dir.path().join("aapl.parquet");
+        let repo_path = dir.path().join("repo");
+        let request = FetchRequest {
+            symbols: vec!["AAPL".to_string()],
+            start: 0,
+            end: 10 * 86_400,
+            timeframe: "1Day".to_string(),
+            adjustment_policy: "unadjusted".to_string(),
+        };
+        run_fetch(
+            "records",
+            &request,
+            &ProviderOptions {
+                endpoint: Some(records_path.display().to_string()),
+            },
+            &out,
+            Some(&repo_path),
+        )
+        .unwrap();
+
+        let metadata = read_dataset_metadata(&out).unwrap().unwrap();
+        assert_eq!(metadata.provider, "records");
+        assert_eq!(metadata.bar_count, 3);
+        assert!(metadata
+            .transform_lineage
+            .iter()
+            .any(|step| step.step == "fetch"));
+        assert!(!metadata.quality_flags.contains(&QualityFlag::DerivedValue));
+
+        let repo = Repository::open(&repo_path).unwrap();
+        let commits = repo.all_commits().unwrap();
+        assert_eq!(commits.len(), 1);
+    }
+}
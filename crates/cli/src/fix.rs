@@ -0,0 +1,392 @@
+//! FIX 4.4 tag=value messages for simulated orders and drop-copy executions.
+//!
+//! Each simulated fill is rendered as a market NewOrderSingle (`35=D`) and
+//! the ExecutionReport (`35=8`) that filled it. Every message
+//! carries a `ClOrdID` derived from the fill's position in the run, so a
+//! drop copy of the live executions of those orders can be matched back to
+//! them. Messages are written one per line; parsing accepts SOH or `|` as
+//! the field delimiter, as drop-copy logs commonly use either.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime};
+use schema::{CostBreakdown, Fill, Side};
+use std::fmt::Write;
+
+pub const BEGIN_STRING: &str = "FIX.4.4";
+pub const SOH: char = '\x01';
+
+/// Tags used when rendering and reading messages
+pub mod tag {
+    pub const AVG_PX: u32 = 6;
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECK_SUM: u32 = 10;
+    pub const CL_ORD_ID: u32 = 11;
+    pub const COMMISSION: u32 = 12;
+    pub const COMM_TYPE: u32 = 13;
+    pub const CUM_QTY: u32 = 14;
+    pub const EXEC_ID: u32 = 17;
+    pub const LAST_PX: u32 = 31;
+    pub const LAST_QTY: u32 = 32;
+    pub const MSG_SEQ_NUM: u32 = 34;
+    pub const MSG_TYPE: u32 = 35;
+    pub const ORDER_ID: u32 = 37;
+    pub const ORDER_QTY: u32 = 38;
+    pub const ORD_STATUS: u32 = 39;
+    pub const ORD_TYPE: u32 = 40;
+    pub const SENDER_COMP_ID: u32 = 49;
+    pub const SENDING_TIME: u32 = 52;
+    pub const SIDE: u32 = 54;
+    pub const SYMBOL: u32 = 55;
+    pub const TARGET_COMP_ID: u32 = 56;
+    pub const TRANSACT_TIME: u32 = 60;
+    pub const EXEC_TYPE: u32 = 150;
+    pub const LEAVES_QTY: u32 = 151;
+}
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H:%M:%S%.3f";
+
+/// A FIX message as its body fields in order; the standard header's
+/// BeginString and BodyLength and the CheckSum trailer are added on encode
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FixMessage {
+    pub fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> Self {
+        Self {
+            fields: vec![(tag::MSG_TYPE, msg_type.to_string())],
+        }
+    }
+
+    pub fn with(mut self, tag: u32, value: impl ToString) -> Self {
+        self.fields.push((tag, value.to_string()));
+        self
+    }
+
+    /// First value of `tag`
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn msg_type(&self) -> Option<&str> {
+        self.get(tag::MSG_TYPE)
+    }
+
+    /// Wire form with SOH delimiters, BodyLength and CheckSum
+    pub fn encode(&self) -> String {
+        let mut body = String::new();
+        for (tag, value) in &self.fields {
+            let _ = write!(body, "{}={}{}", tag, value, SOH);
+        }
+        let mut message = format!(
+            "{}={}{}{}={}{}{}",
+            tag::BEGIN_STRING,
+            BEGIN_STRING,
+            SOH,
+            tag::BODY_LENGTH,
+            body.len(),
+            SOH,
+            body
+        );
+        let _ = write!(
+            message,
+            "{}={:03}{}",
+            tag::CHECK_SUM,
+            checksum(&message),
+            SOH
+        );
+        message
+    }
+
+    /// Parse one message delimited by SOH or `|`, verifying BodyLength and
+    /// CheckSum when present
+    pub fn parse(raw: &str) -> Result<Self> {
+        let delimiter = if raw.contains(SOH) { SOH } else { '|' };
+        let mut fields = Vec::new();
+        let mut begin_string = None;
+        let mut body_start = None;
+        let mut body_length = None;
+        let mut offset = 0;
+        for field in raw.split_inclusive(delimiter) {
+            let start = offset;
+            offset += field.len();
+            let field = field.trim_end_matches(delimiter).trim();
+            if field.is_empty() {
+                continue;
+            }
+            let (tag, value) = field
+                .split_once('=')
+                .with_context(|| format!("Malformed FIX field '{}'", field))?;
+            let tag: u32 = tag
+                .parse()
+                .with_context(|| format!("Malformed FIX tag '{}'", tag))?;
+            match tag {
+                tag::BEGIN_STRING => begin_string = Some(value.to_string()),
+                tag::BODY_LENGTH => {
+                    body_length = value.parse::<usize>().ok();
+                    body_start = Some(offset);
+                }
+                tag::CHECK_SUM => {
+                    let expected: u32 = value
+                        .parse()
+                        .with_context(|| format!("Malformed FIX CheckSum '{}'", value))?;
+                    // Checksums are only meaningful over the real SOH bytes
+                    if delimiter == SOH && checksum(&raw[..start]) != expected {
+                        anyhow::bail!("FIX CheckSum mismatch: message says {:03}", expected);
+                    }
+                    if let (Some(body_start), Some(length), true) =
+                        (body_start, body_length, delimiter == SOH)
+                    {
+                        if start - body_start != length {
+                            anyhow::bail!(
+                                "FIX BodyLength mismatch: message says {}, body is {}",
+                                length,
+                                start - body_start
+                            );
+                        }
+                    }
+                }
+                _ => fields.push((tag, value.to_string())),
+            }
+        }
+        match begin_string.as_deref() {
+            Some(BEGIN_STRING) => {}
+            Some(other) => anyhow::bail!("Unsupported FIX version '{}'", other),
+            None => anyhow::bail!("FIX message has no BeginString"),
+        }
+        Ok(Self { fields })
+    }
+}
+
+/// Sum of the bytes modulo 256
+fn checksum(bytes: &str) -> u32 {
+    bytes.bytes().map(u32::from).sum::<u32>() % 256
+}
+
+pub fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format(TIMESTAMP_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+/// Seconds since the epoch of a UTCTimestamp, with or without milliseconds
+pub fn parse_timestamp(value: &str) -> Result<i64> {
+    NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y%m%d-%H:%M:%S"))
+        .map(|t| t.and_utc().timestamp())
+        .with_context(|| format!("Malformed FIX UTCTimestamp '{}'", value))
+}
+
+fn side_code(side: Side) -> &'static str {
+    match side {
+        Side::Buy => "1",
+        Side::Sell => "2",
+    }
+}
+
+/// `ClOrdID` of the `index`th (0-based) fill of a run
+pub fn cl_ord_id(prefix: &str, index: usize) -> String {
+    format!("{}-{:06}", prefix, index + 1)
+}
+
+/// Sender and target CompIDs and the outgoing sequence number
+pub struct FixSession {
+    pub sender: String,
+    pub target: String,
+    next_seq: u64,
+}
+
+impl FixSession {
+    pub fn new(sender: &str, target: &str) -> Self {
+        Self {
+            sender: sender.to_string(),
+            target: target.to_string(),
+            next_seq: 1,
+        }
+    }
+
+    /// `body` behind a standard header sent at `timestamp`
+    fn stamp(&mut self, body: FixMessage, timestamp: i64) -> FixMessage {
+        let mut fields = body.fields.into_iter();
+        let mut message = FixMessage {
+            fields: fields.next().into_iter().collect(),
+        }
+        .with(tag::SENDER_COMP_ID, &self.sender)
+        .with(tag::TARGET_COMP_ID, &self.target)
+        .with(tag::MSG_SEQ_NUM, self.next_seq)
+        .with(tag::SENDING_TIME, format_timestamp(timestamp));
+        message.fields.extend(fields);
+        self.next_seq += 1;
+        message
+    }
+
+    /// The market order that produced `fill`
+    pub fn new_order_single(&mut self, id: &str, fill: &Fill) -> FixMessage {
+        let body = FixMessage::new("D")
+            .with(tag::CL_ORD_ID, id)
+            .with(tag::SYMBOL, &fill.symbol)
+            .with(tag::SIDE, side_code(fill.side))
+            .with(tag::TRANSACT_TIME, format_timestamp(fill.timestamp))
+            .with(tag::ORDER_QTY, fill.quantity)
+            .with(tag::ORD_TYPE, "1");
+        self.stamp(body, fill.timestamp)
+    }
+
+    /// The simulated execution filling the order `id` completely
+    pub fn execution_report(&mut self, id: &str, fill: &Fill) -> FixMessage {
+        let body = FixMessage::new("8")
+            .with(tag::ORDER_ID, id)
+            .with(tag::CL_ORD_ID, id)
+            .with(tag::EXEC_ID, format!("{}-1", id))
+            .with(tag::EXEC_TYPE, "F")
+            .with(tag::ORD_STATUS, "2")
+            .with(tag::SYMBOL, &fill.symbol)
+            .with(tag::SIDE, side_code(fill.side))
+            .with(tag::ORDER_QTY, fill.quantity)
+            .with(tag::LAST_QTY, fill.quantity)
+            .with(tag::LAST_PX, fill.price)
+            .with(tag::LEAVES_QTY, 0)
+            .with(tag::CUM_QTY, fill.quantity)
+            .with(tag::AVG_PX, fill.price)
+            .with(tag::COMMISSION, fill.costs.charged())
+            .with(tag::COMM_TYPE, "3")
+            .with(tag::TRANSACT_TIME, format_timestamp(fill.timestamp));
+        self.stamp(body, fill.timestamp)
+    }
+}
+
+/// One trade reported by a drop copy
+#[derive(Debug, Clone, PartialEq)]
+pub struct Execution {
+    pub cl_ord_id: String,
+    pub fill: Fill,
+}
+
+/// Trade executions in a drop-copy log with one message per line.
+///
+/// Only ExecutionReports with ExecType Trade (`F`, or `1`/`2` from pre-4.3
+/// counterparties) are kept; acknowledgements, cancels and other messages
+/// are skipped.
+pub fn parse_drop_copy(text: &str) -> Result<Vec<Execution>> {
+    let mut executions = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message =
+            FixMessage::parse(line).with_context(|| format!("Drop copy line {}", number + 1))?;
+        if message.msg_type() != Some("8")
+            || !matches!(message.get(tag::EXEC_TYPE), Some("F" | "1" | "2"))
+        {
+            continue;
+        }
+        let execution = execution(&message)
+            .with_context(|| format!("Drop copy line {}: invalid execution", number + 1))?;
+        executions.push(execution);
+    }
+    Ok(executions)
+}
+
+fn execution(message: &FixMessage) -> Result<Execution> {
+    let required = |tag: u32| {
+        message
+            .get(tag)
+            .with_context(|| format!("missing tag {}", tag))
+    };
+    let number = |tag: u32| -> Result<f64> {
+        let value = required(tag)?;
+        value
+            .parse()
+            .with_context(|| format!("tag {} is not a number: '{}'", tag, value))
+    };
+    let side = match required(tag::SIDE)? {
+        "1" => Side::Buy,
+        "2" | "5" => Side::Sell,
+        other => anyhow::bail!("unsupported Side '{}'", other),
+    };
+    let timestamp = match message.get(tag::TRANSACT_TIME) {
+        Some(value) => parse_timestamp(value)?,
+        None => parse_timestamp(required(tag::SENDING_TIME)?)?,
+    };
+    Ok(Execution {
+        cl_ord_id: required(tag::CL_ORD_ID)?.to_string(),
+        fill: Fill {
+            timestamp,
+            symbol: required(tag::SYMBOL)?.to_string(),
+            side,
+            quantity: number(tag::LAST_QTY)?,
+            price: number(tag::LAST_PX)?,
+            costs: CostBreakdown::from_commission(
+                message
+                    .get(tag::COMMISSION)
+                    .map(|_| number(tag::COMMISSION))
+                    .transpose()?
+                    .unwrap_or(0.0),
+            ),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill() -> Fill {
+        Fill {
+            timestamp: 1_700_000_000,
+            symbol: "AAPL".to_string(),
+            side: Side::Sell,
+            quantity: 25.0,
+            price: 189.5,
+            costs: CostBreakdown::from_commission(1.25),
+        }
+    }
+
+    #[test]
+    fn rendered_messages_round_trip_with_valid_checksums() {
+        let mut session = FixSession::new("AURELIUS", "BROKER");
+        let order = session.new_order_single("SIM-000001", &fill()).encode();
+        assert!(order.starts_with("8=FIX.4.4\x019="));
+        assert!(order.contains("\x0135=D\x0149=AURELIUS\x0156=BROKER\x0134=1\x01"));
+        assert!(order.contains("\x0152=20231114-22:13:20.000\x01"));
+        assert_eq!(FixMessage::parse(&order).unwrap().get(tag::SIDE), Some("2"));
+
+        let report = session.execution_report("SIM-000001", &fill()).encode();
+        let executions = parse_drop_copy(&report).unwrap();
+        assert_eq!(
+            executions,
+            vec![Execution {
+                cl_ord_id: "SIM-000001".to_string(),
+                fill: fill()
+            }]
+        );
+
+        let corrupted = report.replace("31=189.5", "31=189.6");
+        assert!(FixMessage::parse(&corrupted)
+            .unwrap_err()
+            .to_string()
+            .contains("CheckSum"));
+    }
+
+    #[test]
+    fn drop_copy_keeps_only_trades_and_accepts_pipes() {
+        let log = "\
+8=FIX.4.4|35=8|11=SIM-000001|150=0|39=0|55=AAPL|54=1|\n\
+8=FIX.4.4|35=8|11=SIM-000001|150=F|39=1|55=AAPL|54=1|32=40|31=100.02|60=20231114-22:13:21|\n\
+8=FIX.4.4|35=0|\n\
+8=FIX.4.4|35=8|11=SIM-000001|150=F|39=2|55=AAPL|54=1|32=60|31=100.05|12=0.3|60=20231114-22:13:22.500|\n";
+        let executions = parse_drop_copy(log).unwrap();
+        assert_eq!(executions.len(), 2);
+        assert_eq!(executions[0].fill.quantity, 40.0);
+        assert_eq!(executions[1].fill.costs.commission, 0.3);
+        assert_eq!(executions[1].fill.timestamp, 1_700_000_002);
+
+        let err = parse_drop_copy("8=FIX.4.2|35=8|\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("Unsupported FIX version"));
+    }
+}
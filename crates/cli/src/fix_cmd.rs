@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use hipcortex::Repository;
+use schema::{Fill, Side};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::fix::{cl_ord_id, parse_drop_copy, Execution, FixSession};
+use crate::run_data::load_run;
+
+/// Maximum number of orders printed to the console
+const MAX_PRINTED_ORDERS: usize = 10;
+
+/// Simulated order compared with its real executions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderTca {
+    pub cl_ord_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub simulated_quantity: f64,
+    pub simulated_price: f64,
+    pub real_quantity: f64,
+    /// Volume-weighted price of the real executions
+    pub real_price: f64,
+    pub executions: usize,
+    /// Real price vs simulated, positive when the real fill was worse
+    pub slippage_bps: f64,
+    /// Cost of the real price vs simulated on the real quantity
+    pub shortfall: f64,
+    pub commission_difference: f64,
+}
+
+/// Sim-vs-real transaction cost analysis of one run against a drop copy
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TcaReport {
+    pub run: String,
+    pub matched: Vec<OrderTca>,
+    /// Simulated orders with no execution in the drop copy
+    pub unexecuted: Vec<String>,
+    /// Executions whose `ClOrdID` is not an order of the run
+    pub unmatched_executions: usize,
+    /// Quantity-weighted over matched orders
+    pub average_slippage_bps: f64,
+    pub total_shortfall: f64,
+}
+
+/// Write a run's fills as FIX NewOrderSingle and ExecutionReport messages
+pub fn run_export(
+    run: &str,
+    repo_path: Option<&Path>,
+    out: &Path,
+    sender: &str,
+    target: &str,
+    prefix: &str,
+) -> Result<()> {
+    let fills = load_fills(run, repo_path)?;
+    let mut session = FixSession::new(sender, target);
+    let mut file = fs::File::create(out)
+        .with_context(|| format!("Failed to create FIX message file {:?}", out))?;
+    for (index, fill) in fills.iter().enumerate() {
+        let id = cl_ord_id(prefix, index);
+        writeln!(file, "{}", session.new_order_single(&id, fill).encode())?;
+        writeln!(file, "{}", session.execution_report(&id, fill).encode())?;
+    }
+    println!(
+        "Wrote {} order(s) and execution report(s) to {:?}",
+        fills.len(),
+        out
+    );
+    Ok(())
+}
+
+/// Compare a run's fills with the executions in a drop-copy log
+pub fn run_tca(
+    run: &str,
+    repo_path: Option<&Path>,
+    drop_copy: &Path,
+    prefix: &str,
+    out: Option<&Path>,
+) -> Result<TcaReport> {
+    let fills = load_fills(run, repo_path)?;
+    let text = fs::read_to_string(drop_copy)
+        .with_context(|| format!("Failed to read drop copy {:?}", drop_copy))?;
+    let executions = parse_drop_copy(&text)?;
+
+    let mut report = compare_executions(&fills, &executions, prefix);
+    report.run = run.to_string();
+    if let Some(out) = out {
+        serde_json::to_writer_pretty(fs::File::create(out)?, &report)?;
+    }
+    print_report(&report);
+    if let Some(out) = out {
+        println!("\nWrote TCA report to {:?}", out);
+    }
+    Ok(report)
+}
+
+fn load_fills(run: &str, repo_path: Option<&Path>) -> Result<Vec<Fill>> {
+    let repo = repo_path
+        .map(|p| Repository::open(p).context("Failed to open repository"))
+        .transpose()?;
+    Ok(load_run(run, repo.as_ref())?.fills)
+}
+
+/// Match executions to simulated fills by `ClOrdID` and price the differences
+pub fn compare_executions(fills: &[Fill], executions: &[Execution], prefix: &str) -> TcaReport {
+    let mut by_order: BTreeMap<&str, Vec<&Fill>> = BTreeMap::new();
+    for execution in executions {
+        by_order
+            .entry(execution.cl_ord_id.as_str())
+            .or_default()
+            .push(&execution.fill);
+    }
+
+    let mut report = TcaReport::default();
+    for (index, fill) in fills.iter().enumerate() {
+        let id = cl_ord_id(prefix, index);
+        let Some(real) = by_order.remove(id.as_str()) else {
+            report.unexecuted.push(id);
+            continue;
+        };
+        let real_quantity: f64 = real.iter().map(|f| f.quantity).sum();
+        let real_price = if real_quantity > 0.0 {
+            real.iter().map(|f| f.price * f.quantity).sum::<f64>() / real_quantity
+        } else {
+            0.0
+        };
+        let direction = match fill.side {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        };
+        let slippage_bps = if fill.price > 0.0 && real_quantity > 0.0 {
+            direction * (real_price - fill.price) / fill.price * 10_000.0
+        } else {
+            0.0
+        };
+        report.matched.push(OrderTca {
+            cl_ord_id: id,
+            symbol: fill.symbol.clone(),
+            side: fill.side,
+            simulated_quantity: fill.quantity,
+            simulated_price: fill.price,
+            real_quantity,
+            real_price,
+            executions: real.len(),
+            slippage_bps,
+            shortfall: direction * (real_price - fill.price) * real_quantity,
+            commission_difference: real.iter().map(|f| f.costs.charged()).sum::<f64>()
+                - fill.costs.charged(),
+        });
+    }
+    report.unmatched_executions = by_order.values().map(Vec::len).sum();
+
+    let quantity: f64 = report.matched.iter().map(|o| o.real_quantity).sum();
+    if quantity > 0.0 {
+        report.average_slippage_bps = report
+            .matched
+            .iter()
+            .map(|o| o.slippage_bps * o.real_quantity)
+            .sum::<f64>()
+            / quantity;
+    }
+    report.total_shortfall = report.matched.iter().map(|o| o.shortfall).sum();
+    report
+}
+
+fn print_report(report: &TcaReport) {
+    println!("=== Sim vs Real TCA ===");
+    println!("Run: {}", report.run);
+    println!(
+        "Orders: {} executed, {} unexecuted; {} unmatched execution(s)",
+        report.matched.len(),
+        report.unexecuted.len(),
+        report.unmatched_executions
+    );
+    println!(
+        "Average slippage: {:+.2} bps, total shortfall ${:.2}",
+        report.average_slippage_bps, report.total_shortfall
+    );
+    for order in report.matched.iter().take(MAX_PRINTED_ORDERS) {
+        println!(
+            "  {} {} {:?}: sim {} @ {:.4}, real {} @ {:.4} ({:+.2} bps)",
+            order.cl_ord_id,
+            order.symbol,
+            order.side,
+            order.simulated_quantity,
+            order.simulated_price,
+            order.real_quantity,
+            order.real_price,
+            order.slippage_bps
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::CostBreakdown;
+
+    fn fill(side: Side, quantity: f64, price: f64) -> Fill {
+        Fill {
+            timestamp: 1_700_000_000,
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            price,
+            costs: CostBreakdown::from_commission(1.0),
+        }
+    }
+
+    fn execution(id: &str, fill: Fill) -> Execution {
+        Execution {
+            cl_ord_id: id.to_string(),
+            fill,
+        }
+    }
+
+    #[test]
+    fn partial_executions_are_priced_against_the_simulated_fill() {
+        let fills = vec![
+            fill(Side::Buy, 100.0, 100.0),
+            fill(Side::Sell, 100.0, 110.0),
+            fill(Side::Buy, 10.0, 90.0),
+        ];
+        let executions = vec![
+            execution("SIM-000001", fill(Side::Buy, 40.0, 100.10)),
+            execution("SIM-000001", fill(Side::Buy, 60.0, 100.20)),
+            execution("SIM-000002", fill(Side::Sell, 100.0, 110.011)),
+            execution("MANUAL-1", fill(Side::Buy, 5.0, 50.0)),
+        ];
+        let report = compare_executions(&fills, &executions, "SIM");
+
+        let buy = &report.matched[0];
+        assert_eq!((buy.executions, buy.real_quantity), (2, 100.0));
+        assert!((buy.real_price - 100.16).abs() < 1e-9);
+        assert!((buy.slippage_bps - 16.0).abs() < 1e-9);
+        assert!((buy.shortfall - 16.0).abs() < 1e-9);
+        assert!((buy.commission_difference - 1.0).abs() < 1e-12);
+        // Selling higher than simulated is a gain
+        assert!((report.matched[1].slippage_bps + 1.0).abs() < 1e-9);
+
+        assert_eq!(report.unexecuted, ["SIM-000003"]);
+        assert_eq!(report.unmatched_executions, 1);
+        assert!((report.average_slippage_bps - 7.5).abs() < 1e-9);
+        assert!((report.total_shortfall - 14.9).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use crv_verifier::{CRVReport, RuleId, Severity, VerificationPolicy};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const GATE_FILE: &str = "gate.json";
+
+/// Lowest CRV severity that fails `--gate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GateArg {
+    Critical,
+    High,
+    Medium,
+    Low,
+    Info,
+}
+
+impl From<GateArg> for Severity {
+    fn from(gate: GateArg) -> Self {
+        match gate {
+            GateArg::Critical => Severity::Critical,
+            GateArg::High => Severity::High,
+            GateArg::Medium => Severity::Medium,
+            GateArg::Low => Severity::Low,
+            GateArg::Info => Severity::Info,
+        }
+    }
+}
+
+/// Gate decision across every CRV report a command produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GateSummary {
+    pub passed: bool,
+    pub fail_on: Severity,
+    pub runs: usize,
+    /// Labels of runs with at least one blocking violation
+    pub failed_runs: Vec<String>,
+    pub blocking_violations: usize,
+    pub total_violations: usize,
+    /// Rules behind the blocking violations, in first-seen order
+    pub blocking_rules: Vec<RuleId>,
+}
+
+impl GateSummary {
+    pub fn new<'a>(
+        fail_on: Severity,
+        reports: impl IntoIterator<Item = (String, &'a CRVReport)>,
+    ) -> Self {
+        let policy = VerificationPolicy {
+            fail_on,
+            ..VerificationPolicy::default()
+        };
+        let mut summary = GateSummary {
+            passed: true,
+            fail_on,
+            runs: 0,
+            failed_runs: Vec::new(),
+            blocking_violations: 0,
+            total_violations: 0,
+            blocking_rules: Vec::new(),
+        };
+        for (label, report) in reports {
+            let outcome = policy.gate(report);
+            summary.runs += 1;
+            summary.blocking_violations += outcome.blocking_violations;
+            summary.total_violations += outcome.total_violations;
+            if !outcome.passed {
+                summary.passed = false;
+                summary.failed_runs.push(label);
+            }
+            for violation in &report.violations {
+                if violation.severity.at_least(fail_on)
+                    && !summary.blocking_rules.contains(&violation.rule_id)
+                {
+                    summary.blocking_rules.push(violation.rule_id);
+                }
+            }
+        }
+        summary
+    }
+
+    /// One `key=value` line for logs and CI annotations
+    pub fn line(&self) -> String {
+        format!(
+            "gate={} fail_on={} runs={} failed_runs={} blocking={} total={}",
+            if self.passed { "pass" } else { "fail" },
+            serde_json::to_value(self.fail_on)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            self.runs,
+            self.failed_runs.len(),
+            self.blocking_violations,
+            self.total_violations
+        )
+    }
+}
+
+/// Gate `reports`, write `gate.json` to `out_dir` and print the summary line.
+///
+/// Returns whether the gate passed; callers exit non-zero when it did not.
+pub fn enforce<'a>(
+    fail_on: Severity,
+    reports: impl IntoIterator<Item = (String, &'a CRVReport)>,
+    out_dir: &Path,
+) -> Result<bool> {
+    let summary = GateSummary::new(fail_on, reports);
+    let file = fs::File::create(out_dir.join(GATE_FILE))?;
+    serde_json::to_writer_pretty(file, &summary)?;
+    println!("\n{}", summary.line());
+    Ok(summary.passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crv_verifier::CRVViolation;
+
+    fn report(violations: &[(RuleId, Severity)]) -> CRVReport {
+        let mut report = CRVReport::new(1);
+        for (rule_id, severity) in violations {
+            report.add_violation(CRVViolation {
+                rule_id: *rule_id,
+                severity: *severity,
+                message: String::new(),
+                evidence: vec![],
+            });
+        }
+        report
+    }
+
+    #[test]
+    fn summarizes_blocking_runs_at_threshold() {
+        let clean = report(&[]);
+        let minor = report(&[(RuleId::TurnoverConstraint, Severity::Medium)]);
+        let breach = report(&[
+            (RuleId::MaxDrawdownConstraint, Severity::High),
+            (RuleId::TurnoverConstraint, Severity::Medium),
+        ]);
+        let reports = || {
+            [
+                ("run_0".to_string(), &clean),
+                ("run_1".to_string(), &minor),
+                ("run_2".to_string(), &breach),
+            ]
+        };
+
+        let high = GateSummary::new(Severity::High, reports());
+        assert!(!high.passed);
+        assert_eq!(high.runs, 3);
+        assert_eq!(high.failed_runs, vec!["run_2".to_string()]);
+        assert_eq!(high.blocking_violations, 1);
+        assert_eq!(high.total_violations, 3);
+        assert_eq!(high.blocking_rules, vec![RuleId::MaxDrawdownConstraint]);
+        assert_eq!(
+            high.line(),
+            "gate=fail fail_on=high runs=3 failed_runs=1 blocking=1 total=3"
+        );
+
+        assert!(GateSummary::new(Severity::Critical, reports()).passed);
+        assert_eq!(
+            GateSummary::new(Severity::Medium, reports())
+                .failed_runs
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,442 @@
+//! Importers for Zipline and QuantConnect Lean data layouts.
+//!
+//! Zipline is read in its `csvdir` bundle layout, the per-symbol CSVs a
+//! bundle is ingested from: `<dir>/daily/<SYMBOL>.csv` or
+//! `<dir>/minute/<SYMBOL>.csv` with `date,open,high,low,close,volume` and
+//! optional `dividend,split` columns, timestamps in UTC. Ingested bcolz
+//! bundles are not read; re-export them with `csvdir` first.
+//!
+//! Lean is read from a data folder's equity tree. Daily and hour bars are
+//! one `<symbol>.zip` (or `.csv`) per symbol under
+//! `equity/<market>/<resolution>/`, with `yyyyMMdd HH:mm` times; minute bars
+//! are one `<yyyyMMdd>_trade.zip` per day under
+//! `equity/<market>/minute/<symbol>/`, with milliseconds since midnight.
+//! Times are exchange-local and prices are in units of 1/10000.
+//!
+//! Both are unadjusted; Zipline's dividends and splits are counted in the
+//! import lineage but not applied.
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use schema::{Bar, TransformationStep};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Lean stores equity prices as integers in 1/10000 of a dollar
+const LEAN_PRICE_SCALE: f64 = 10_000.0;
+
+/// Data layouts `data import` understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// Zipline `csvdir` bundle directory
+    Zipline,
+    /// QuantConnect Lean data folder
+    Lean,
+}
+
+/// Bar resolution to import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Resolution {
+    Daily,
+    Hour,
+    Minute,
+}
+
+impl Resolution {
+    fn dir_name(self) -> &'static str {
+        match self {
+            Resolution::Daily => "daily",
+            Resolution::Hour => "hour",
+            Resolution::Minute => "minute",
+        }
+    }
+}
+
+/// Where to read from and which symbols to keep
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    pub format: ImportFormat,
+    pub resolution: Resolution,
+    /// All symbols in the layout when empty
+    pub symbols: Vec<String>,
+    /// Lean market directory, e.g. `usa`
+    pub market: String,
+}
+
+/// Bars read from a foreign layout, with provenance for the dataset metadata
+#[derive(Debug, Clone)]
+pub struct ImportedBars {
+    pub bars: Vec<Bar>,
+    pub provider: String,
+    pub timezone_calendar: String,
+    pub lineage: Vec<TransformationStep>,
+}
+
+pub fn import_bars(input: &Path, options: &ImportOptions) -> Result<ImportedBars> {
+    let imported = match options.format {
+        ImportFormat::Zipline => import_zipline(input, options),
+        ImportFormat::Lean => import_lean(input, options),
+    }?;
+    if imported.bars.is_empty() {
+        anyhow::bail!("No bars found under {:?}", input);
+    }
+    Ok(imported)
+}
+
+/// Whether a file named after `symbol` should be imported
+fn wanted(options: &ImportOptions, symbol: &str) -> bool {
+    options.symbols.is_empty()
+        || options
+            .symbols
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(symbol))
+}
+
+/// Files directly in `dir` with one of `extensions`, sorted by name
+fn files_with_extension(dir: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {:?}", dir))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| extensions.contains(&e))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn import_zipline(input: &Path, options: &ImportOptions) -> Result<ImportedBars> {
+    if options.resolution == Resolution::Hour {
+        anyhow::bail!("Zipline csvdir bundles hold daily or minute bars, not hourly");
+    }
+    let dir = input.join(options.resolution.dir_name());
+    let mut bars = Vec::new();
+    let (mut files, mut dividends, mut splits) = (0, 0, 0);
+    for path in files_with_extension(&dir, &["csv"])? {
+        let symbol = file_stem(&path);
+        if !wanted(options, &symbol) {
+            continue;
+        }
+        files += 1;
+        let mut rdr = csv::Reader::from_path(&path)
+            .with_context(|| format!("Failed to open Zipline CSV {:?}", path))?;
+        let headers = rdr.headers()?.clone();
+        let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+        let required = |name: &str| {
+            column(name).with_context(|| format!("{:?} has no '{}' column", path, name))
+        };
+        let (date, open, high, low, close, volume) = (
+            required("date")?,
+            required("open")?,
+            required("high")?,
+            required("low")?,
+            required("close")?,
+            required("volume")?,
+        );
+        let (dividend, split) = (column("dividend"), column("split"));
+
+        for (row, record) in rdr.records().enumerate() {
+            let record = record?;
+            let context = || format!("{:?} row {}", path, row + 1);
+            let field = |index: usize| record.get(index).unwrap_or_default().trim();
+            let number = |index: usize| -> Result<f64> {
+                field(index)
+                    .parse()
+                    .with_context(|| format!("{}: invalid number '{}'", context(), field(index)))
+            };
+            if dividend.is_some_and(|i| field(i).parse::<f64>().is_ok_and(|d| d != 0.0)) {
+                dividends += 1;
+            }
+            if split.is_some_and(|i| field(i).parse::<f64>().is_ok_and(|s| s != 1.0)) {
+                splits += 1;
+            }
+            bars.push(Bar {
+                timestamp: parse_zipline_time(field(date)).with_context(context)?,
+                symbol: symbol.clone(),
+                open: number(open)?,
+                high: number(high)?,
+                low: number(low)?,
+                close: number(close)?,
+                volume: number(volume)?,
+            });
+        }
+    }
+
+    Ok(ImportedBars {
+        bars,
+        provider: "zipline".to_string(),
+        timezone_calendar: "UTC/XNYS".to_string(),
+        lineage: vec![TransformationStep {
+            step: "import_zipline".to_string(),
+            details: format!(
+                "csvdir {} bars from {} file(s) in {:?}; {} dividend(s) and {} split(s) not applied",
+                options.resolution.dir_name(),
+                files,
+                dir,
+                dividends,
+                splits
+            ),
+        }],
+    })
+}
+
+/// Unix seconds of a csvdir date: `YYYY-MM-DD`, optionally with a time and
+/// a `+00:00` or `Z` suffix
+fn parse_zipline_time(value: &str) -> Result<i64> {
+    let value = value.trim_end_matches('Z').trim_end_matches("+00:00");
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp());
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|t| t.and_utc().timestamp())
+        .with_context(|| format!("Unrecognized date '{}'", value))
+}
+
+fn import_lean(input: &Path, options: &ImportOptions) -> Result<ImportedBars> {
+    let tz = lean_timezone(&options.market)?;
+    let dir = input
+        .join("equity")
+        .join(&options.market)
+        .join(options.resolution.dir_name());
+    let mut bars = Vec::new();
+    let mut files = 0;
+
+    if options.resolution == Resolution::Minute {
+        let mut symbol_dirs: Vec<PathBuf> = fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read directory {:?}", dir))?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|path| path.is_dir())
+            .collect();
+        symbol_dirs.sort();
+        for symbol_dir in symbol_dirs {
+            let symbol = file_stem(&symbol_dir);
+            if !wanted(options, &symbol) {
+                continue;
+            }
+            for path in files_with_extension(&symbol_dir, &["zip", "csv"])? {
+                let day = file_stem(&path);
+                let day = day.split('_').next().unwrap_or_default();
+                let date = NaiveDate::parse_from_str(day, "%Y%m%d")
+                    .with_context(|| format!("{:?} is not named after a yyyyMMdd day", path))?;
+                files += 1;
+                let text = read_lean_file(&path)?;
+                bars.extend(parse_lean_csv(&text, &symbol, &path, |time| {
+                    let millis: i64 = time.parse().ok()?;
+                    let local = date.and_hms_opt(0, 0, 0)? + chrono::Duration::milliseconds(millis);
+                    local_to_utc(&tz, local)
+                })?);
+            }
+        }
+    } else {
+        for path in files_with_extension(&dir, &["zip", "csv"])? {
+            let symbol = file_stem(&path);
+            if !wanted(options, &symbol) {
+                continue;
+            }
+            files += 1;
+            let text = read_lean_file(&path)?;
+            bars.extend(parse_lean_csv(&text, &symbol, &path, |time| {
+                let local = NaiveDateTime::parse_from_str(time, "%Y%m%d %H:%M").ok()?;
+                local_to_utc(&tz, local)
+            })?);
+        }
+    }
+
+    Ok(ImportedBars {
+        bars,
+        provider: "quantconnect_lean".to_string(),
+        timezone_calendar: format!("{}/XNYS", tz.name()),
+        lineage: vec![TransformationStep {
+            step: "import_lean".to_string(),
+            details: format!(
+                "equity/{}/{} bars from {} file(s) in {:?}; {} exchange time to UTC, prices / {}",
+                options.market,
+                options.resolution.dir_name(),
+                files,
+                dir,
+                tz.name(),
+                LEAN_PRICE_SCALE
+            ),
+        }],
+    })
+}
+
+/// Exchange time zone of a Lean equity market
+fn lean_timezone(market: &str) -> Result<Tz> {
+    match market {
+        "usa" => Ok(chrono_tz::America::New_York),
+        other => anyhow::bail!("Unsupported Lean equity market '{}'", other),
+    }
+}
+
+fn local_to_utc(tz: &Tz, local: NaiveDateTime) -> Option<i64> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .map(|t| t.timestamp())
+}
+
+/// The CSV inside a Lean zip, or a plain CSV file
+fn read_lean_file(path: &Path) -> Result<String> {
+    if path.extension().is_some_and(|e| e == "csv") {
+        return fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path));
+    }
+    let file = fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut archive =
+        zip::ZipArchive::new(file).with_context(|| format!("Failed to open zip {:?}", path))?;
+    let mut entry = archive
+        .by_index(0)
+        .with_context(|| format!("{:?} is an empty zip", path))?;
+    let mut text = String::new();
+    entry
+        .read_to_string(&mut text)
+        .with_context(|| format!("Failed to read {} in {:?}", entry.name(), path))?;
+    Ok(text)
+}
+
+/// Rows of `time,open,high,low,close,volume`, times converted by `timestamp`
+fn parse_lean_csv(
+    text: &str,
+    symbol: &str,
+    path: &Path,
+    timestamp: impl Fn(&str) -> Option<i64>,
+) -> Result<Vec<Bar>> {
+    let mut bars = Vec::new();
+    for (row, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let context = || format!("{:?} row {}", path, row + 1);
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() < 6 {
+            anyhow::bail!("{}: expected 6 columns, found {}", context(), fields.len());
+        }
+        let number = |index: usize| -> Result<f64> {
+            fields[index]
+                .parse()
+                .with_context(|| format!("{}: invalid number '{}'", context(), fields[index]))
+        };
+        bars.push(Bar {
+            timestamp: timestamp(fields[0])
+                .with_context(|| format!("{}: invalid time '{}'", context(), fields[0]))?,
+            symbol: symbol.to_uppercase(),
+            open: number(1)? / LEAN_PRICE_SCALE,
+            high: number(2)? / LEAN_PRICE_SCALE,
+            low: number(3)? / LEAN_PRICE_SCALE,
+            close: number(4)? / LEAN_PRICE_SCALE,
+            volume: number(5)?,
+        });
+    }
+    Ok(bars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn options(format: ImportFormat, resolution: Resolution) -> ImportOptions {
+        ImportOptions {
+            format,
+            resolution,
+            symbols: vec![],
+            market: "usa".to_string(),
+        }
+    }
+
+    fn write_zip(path: &Path, name: &str, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn zipline_csvdir_is_read_unadjusted() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("daily")).unwrap();
+        fs::write(
+            dir.path().join("daily/AAPL.csv"),
+            "date,open,high,low,close,volume,dividend,split\n\
+             2020-08-28,500.0,510.0,495.0,499.2,46907500,0.0,1.0\n\
+             2020-08-31,127.6,131.0,126.0,129.0,225702700,0.0,4.0\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("daily/MSFT.csv"),
+            "date,open,high,low,close,volume\n2020-08-28 00:00:00+00:00,1,2,1,2,10\n",
+        )
+        .unwrap();
+
+        let mut only_aapl = options(ImportFormat::Zipline, Resolution::Daily);
+        only_aapl.symbols = vec!["aapl".to_string()];
+        let imported = import_bars(dir.path(), &only_aapl).unwrap();
+        assert_eq!(imported.bars.len(), 2);
+        assert_eq!(imported.bars[1].timestamp, 1_598_832_000);
+        assert_eq!(imported.bars[1].close, 129.0);
+        assert!(imported.lineage[0]
+            .details
+            .contains("0 dividend(s) and 1 split(s) not applied"));
+
+        let all = import_bars(
+            dir.path(),
+            &options(ImportFormat::Zipline, Resolution::Daily),
+        );
+        assert_eq!(all.unwrap().bars[2].timestamp, 1_598_572_800);
+        assert!(import_bars(
+            dir.path(),
+            &options(ImportFormat::Zipline, Resolution::Hour)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn lean_zips_are_scaled_and_converted_from_exchange_time() {
+        let dir = tempfile::tempdir().unwrap();
+        write_zip(
+            &dir.path().join("equity/usa/daily/spy.zip"),
+            "spy.csv",
+            "20200102 00:00,3237800,3248700,3227700,3243500,59151198\n",
+        );
+        let daily =
+            import_bars(dir.path(), &options(ImportFormat::Lean, Resolution::Daily)).unwrap();
+        let bar = &daily.bars[0];
+        assert_eq!(bar.symbol, "SPY");
+        assert_eq!((bar.open, bar.close), (323.78, 324.35));
+        // Midnight in New York is 05:00 UTC in winter
+        assert_eq!(bar.timestamp, 1_577_941_200);
+        assert_eq!(daily.timezone_calendar, "America/New_York/XNYS");
+
+        // 09:31 New York in summer is 13:31 UTC
+        write_zip(
+            &dir.path().join("equity/usa/minute/spy/20200701_trade.zip"),
+            "20200701_spy_minute_trade.csv",
+            "34260000,3100000,3101000,3099000,3100500,1200\n\
+             34320000,3100500,3102000,3100000,3101500,800\n",
+        );
+        let minute =
+            import_bars(dir.path(), &options(ImportFormat::Lean, Resolution::Minute)).unwrap();
+        assert_eq!(minute.bars.len(), 2);
+        assert_eq!(minute.bars[0].timestamp, 1_593_610_260);
+        assert_eq!(minute.bars[1].timestamp - minute.bars[0].timestamp, 60);
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use hipcortex::Repository;
+use std::fs;
+use std::path::Path;
+
+use crate::data_cmd::{run_generate, SyntheticSpec};
+use crate::providers::parse_time;
+
+const SPEC_FILE: &str = "spec.json";
+const DATA_FILE: &str = "data/bars.parquet";
+const REPO_DIR: &str = ".hipcortex";
+const RUN_SCRIPT: &str = "run.sh";
+
+/// Seeded dataset the scaffold ships with; `run.sh` regenerates it identically
+const SYNTHETIC_SYMBOL: &str = "SYNTH";
+const SYNTHETIC_DAYS: usize = 252;
+const SYNTHETIC_START: &str = "2023-01-02";
+const SYNTHETIC_SEED: u64 = 42;
+
+const RUN_SH: &str = r#"#!/bin/sh
+# Generate data, backtest, verify and commit the run to HipCortex.
+# Set QUANT_ENGINE to use a binary that is not on PATH.
+set -e
+cd "$(dirname "$0")"
+QE="${QUANT_ENGINE:-quant_engine}"
+
+"$QE" data generate --out data/bars.parquet --symbols SYNTH --days 252 --start 2023-01-02 --seed 42
+"$QE" backtest --spec spec.json --data data/bars.parquet --out runs/latest --repo .hipcortex
+"$QE" verify --out runs/latest
+"#;
+
+const README: &str = r#"# quant_engine project
+
+Scaffolded by `quant_engine init`.
+
+- `spec.json`: SMA crossover on the synthetic `SYNTH` symbol with IBKR-style costs
+- `data/bars.parquet`: one year of seeded random-walk daily bars
+- `.hipcortex/`: HipCortex repository that backtests commit to
+- `run.sh`: generate data, backtest into `runs/latest`, verify, commit
+
+Run `sh run.sh`, then edit `spec.json` (or pass `--set strategy.fast=5`) and
+run it again. `quant_engine report --out runs/latest` renders the results.
+"#;
+
+/// Scaffold a project in `dir` that runs end to end with `run.sh`
+pub fn run_init(dir: &Path, force: bool) -> Result<()> {
+    if dir.exists() && fs::read_dir(dir)?.next().is_some() && !force {
+        anyhow::bail!(
+            "{:?} is not empty; pass --force to scaffold into it anyway",
+            dir
+        );
+    }
+    fs::create_dir_all(dir).context("Failed to create project directory")?;
+
+    let spec = serde_json::json!({
+        "initial_cash": 100000.0,
+        "seed": 42,
+        "strategy": {
+            "type": "sma_crossover",
+            "symbol": SYNTHETIC_SYMBOL,
+            "fast": 10,
+            "slow": 30,
+            "allocation": 0.95
+        },
+        "cost_model": {
+            "type": "fixed_per_share",
+            "cost_per_share": 0.0035,
+            "minimum_commission": 0.35
+        }
+    });
+    fs::write(
+        dir.join(SPEC_FILE),
+        serde_json::to_string_pretty(&spec)? + "\n",
+    )?;
+
+    run_generate(
+        &SyntheticSpec {
+            symbols: vec![SYNTHETIC_SYMBOL.to_string()],
+            days: SYNTHETIC_DAYS,
+            start: parse_time(SYNTHETIC_START)?,
+            seed: SYNTHETIC_SEED,
+        },
+        &dir.join(DATA_FILE),
+    )?;
+
+    Repository::open(dir.join(REPO_DIR)).context("Failed to create HipCortex repository")?;
+
+    let script = dir.join(RUN_SCRIPT);
+    fs::write(&script, RUN_SH)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755))?;
+    }
+    fs::write(dir.join("README.md"), README)?;
+
+    println!("Initialized project in {:?}", dir);
+    println!("\nNext steps:");
+    println!("  cd {}", dir.display());
+    println!("  sh {}", RUN_SCRIPT);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest_cmd::{execute_backtest, load_spec};
+    use crate::data::load_dataset;
+
+    #[test]
+    fn scaffold_backtests_and_refuses_non_empty_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        run_init(&project, false).unwrap();
+        assert!(project.join(REPO_DIR).is_dir());
+        assert!(project.join(RUN_SCRIPT).is_file());
+
+        let spec = load_spec(&project.join(SPEC_FILE), &[]).unwrap();
+        let dataset = load_dataset(&project.join(DATA_FILE), &spec.data_pipeline).unwrap();
+        assert_eq!(dataset.bars.len(), SYNTHETIC_DAYS);
+        let outcome =
+            execute_backtest(&spec, &dataset.bars, &dataset.halts, &dataset.quality).unwrap();
+        assert!(outcome.stats.num_trades > 0);
+
+        let err = run_init(&project, false).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+        run_init(&project, true).unwrap();
+    }
+}
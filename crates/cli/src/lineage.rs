@@ -0,0 +1,163 @@
+use anyhow::{Context, Result};
+use hipcortex::{
+    Artifact, BacktestConfig, BacktestResult, CRVReportArtifact, CostModelConfig, Repository,
+    StrategySpec as StrategySpecArtifact, Trace,
+};
+
+use crate::backtest_cmd::BacktestOutcome;
+use crate::spec::BacktestSpec;
+
+/// Hashes of the artifacts committed for one backtest run
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RunHashes {
+    pub strategy: String,
+    pub config: String,
+    pub result: String,
+    pub crv_report: String,
+    /// The run's Trace, when it was traced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<String>,
+}
+
+/// Commit strategy, config, result and CRV report artifacts for one run,
+/// and its Trace when it has one.
+///
+/// `parents` links the run back to whatever produced it (a sweep spec, a
+/// walk-forward plan, ...). The result's execution timestamp is the last
+/// equity timestamp so identical runs hash identically; the Trace carries
+/// wall-clock timings, so it hangs off the result and feeds no other hash.
+pub fn commit_run(
+    repo: &mut Repository,
+    spec: &BacktestSpec,
+    dataset_hash: &str,
+    outcome: &BacktestOutcome,
+    parents: &[String],
+    message: &str,
+) -> Result<RunHashes> {
+    let strategy_json = serde_json::to_value(&spec.strategy)?;
+    let strategy_artifact = Artifact::StrategySpec(StrategySpecArtifact {
+        name: spec.strategy_name().to_string(),
+        description: message.to_string(),
+        strategy_type: json_type_tag(&strategy_json),
+        parameters: without_type_tag(strategy_json),
+        goal: "backtest".to_string(),
+        regime_tags: vec![],
+    });
+    let strategy_hash = repo
+        .commit(&strategy_artifact, message, parents.to_vec())
+        .context("Failed to commit strategy spec")?;
+
+    let cost_json = serde_json::to_value(&spec.cost_model)?;
+    let constraints = spec.policy_constraints();
+    let config_artifact = Artifact::BacktestConfig(BacktestConfig {
+        initial_cash: spec.initial_cash,
+        seed: spec.seed,
+        strategy_hash: strategy_hash.as_hex().to_string(),
+        dataset_hash: dataset_hash.to_string(),
+        cost_model: CostModelConfig {
+            model_type: json_type_tag(&cost_json),
+            parameters: without_type_tag(cost_json),
+        },
+        policy: hipcortex::PolicyConstraints {
+            max_drawdown: constraints.max_drawdown,
+            max_leverage: constraints.max_leverage,
+            turnover_limit: constraints.max_turnover,
+        },
+    });
+    let mut config_parents = vec![strategy_hash.as_hex().to_string()];
+    config_parents.extend(parents.iter().cloned());
+    let config_hash = repo
+        .commit(&config_artifact, message, config_parents)
+        .context("Failed to commit backtest config")?;
+
+    let result_artifact = Artifact::BacktestResult(BacktestResult {
+        config_hash: config_hash.as_hex().to_string(),
+        stats: outcome.stats.clone(),
+        trades: outcome.fills.clone(),
+        equity_curve: outcome.equity_points.clone(),
+        execution_timestamp: outcome.equity_history.last().map(|(t, _)| *t).unwrap_or(0),
+    });
+    let result_hash = repo
+        .commit(
+            &result_artifact,
+            message,
+            vec![config_hash.as_hex().to_string()],
+        )
+        .context("Failed to commit backtest result")?;
+
+    let crv_artifact = Artifact::CRVReport(CRVReportArtifact {
+        result_hash: result_hash.as_hex().to_string(),
+        report: outcome.crv_report.clone(),
+    });
+    let crv_hash = repo
+        .commit(
+            &crv_artifact,
+            message,
+            vec![result_hash.as_hex().to_string()],
+        )
+        .context("Failed to commit CRV report")?;
+
+    let trace_hash = match &outcome.trace {
+        Some(trace) => {
+            let counts = trace.counts;
+            let timings = trace.timings;
+            let trace_artifact = Artifact::Trace(Trace {
+                operation: "backtest".to_string(),
+                inputs: vec![config_hash.as_hex().to_string(), dataset_hash.to_string()],
+                output: result_hash.as_hex().to_string(),
+                timestamp: outcome.equity_history.last().map(|(t, _)| *t).unwrap_or(0),
+                metadata: serde_json::json!({
+                    "strategy": trace.strategy,
+                    "seed": spec.seed,
+                    "dataset_hash": dataset_hash,
+                    "counts": {
+                        "bars": counts.bars,
+                        "warmup_bars": counts.warmup_bars,
+                        "orders": counts.orders,
+                        "fills": counts.fills,
+                        "rejections": counts.rejections,
+                    },
+                    "timings_seconds": {
+                        "strategy": timings.strategy.as_secs_f64(),
+                        "risk": timings.risk.as_secs_f64(),
+                        "broker": timings.broker.as_secs_f64(),
+                        "accounting": timings.accounting.as_secs_f64(),
+                        "total": timings.total().as_secs_f64(),
+                    },
+                }),
+            });
+            let hash = repo
+                .commit(
+                    &trace_artifact,
+                    message,
+                    vec![result_hash.as_hex().to_string()],
+                )
+                .context("Failed to commit run trace")?;
+            Some(hash.as_hex().to_string())
+        }
+        None => None,
+    };
+
+    Ok(RunHashes {
+        strategy: strategy_hash.as_hex().to_string(),
+        config: config_hash.as_hex().to_string(),
+        result: result_hash.as_hex().to_string(),
+        crv_report: crv_hash.as_hex().to_string(),
+        trace: trace_hash,
+    })
+}
+
+fn json_type_tag(value: &serde_json::Value) -> String {
+    value
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+fn without_type_tag(mut value: serde_json::Value) -> serde_json::Value {
+    if let Some(map) = value.as_object_mut() {
+        map.remove("type");
+    }
+    value
+}
@@ -4,9 +4,46 @@ use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+mod adapters;
 mod backtest_cmd;
+mod batch_cmd;
+mod benchmark;
+mod canonical;
+mod checkpoint;
+mod columnar;
+mod compare_cmd;
+mod config;
+mod cost_sensitivity_cmd;
+mod data;
+mod data_cmd;
+#[cfg(feature = "onnx")]
+mod features;
+mod fetch_cmd;
+mod fix;
+mod fix_cmd;
+mod gate;
+mod importers;
+mod init_cmd;
+mod lineage;
+mod metrics;
+#[cfg(feature = "onnx")]
+mod onnx_strategy;
+mod out_template;
+mod paper_trade_cmd;
+mod parquet_feed;
+mod providers;
+mod regress_cmd;
+mod report_cmd;
+mod run_data;
+mod seeds_cmd;
+mod serve_cmd;
 mod spec;
 mod strategies;
+mod sweep_cmd;
+mod universe;
+mod verify_cmd;
+mod walkforward_cmd;
+mod wasm_strategy;
 
 #[derive(Parser)]
 #[command(name = "quant_engine")]
@@ -24,24 +61,951 @@ enum Commands {
         #[arg(long)]
         spec: PathBuf,
 
-        /// Path to data parquet file
+        /// Override a spec field, e.g. `--set strategy.lookback=40` (repeatable)
+        #[arg(long = "set", value_name = "PATH=VALUE")]
+        set: Vec<String>,
+
+        /// Use a named broker's cost model; `--set` overrides apply on top
+        #[arg(long, value_enum)]
+        cost_preset: Option<spec::CostPreset>,
+
+        /// Path to a data parquet file, a directory of parquet files, or a glob pattern
+        #[arg(long)]
+        data: PathBuf,
+
+        /// Output directory; may use placeholders, e.g. `runs/{strategy}/{date}/{config_hash_short}`
+        #[arg(long, required_unless_present = "resume")]
+        out: Option<PathBuf>,
+
+        /// Resume an interrupted run in this output directory, reusing verified checkpoints
+        #[arg(long, value_name = "OUT_DIR", conflicts_with_all = ["out", "seeds", "num_seeds"])]
+        resume: Option<PathBuf>,
+
+        /// Run across seeds and report the spread: a range (`1..30`) or list (`1,5,9`)
+        #[arg(long, conflicts_with = "num_seeds")]
+        seeds: Option<String>,
+
+        /// Run across N seeds counting up from the spec's seed
+        #[arg(long)]
+        num_seeds: Option<u64>,
+
+        /// Rerun with trading costs scaled by each multiplier and report how Sharpe and
+        /// return degrade; defaults to `0,1,2,5`
+        #[arg(
+            long,
+            value_name = "MULTIPLIERS",
+            num_args = 0..=1,
+            default_missing_value = cost_sensitivity_cmd::DEFAULT_MULTIPLIERS,
+            conflicts_with_all = [
+                "resume", "seeds", "num_seeds", "benchmark", "repo", "gate", "stream",
+                "progress", "check_determinism"
+            ]
+        )]
+        cost_sensitivity: Option<String>,
+
+        /// Compare against buy-and-hold of a symbol in the data, or of a separate dataset,
+        /// in place of the spec's benchmark
+        #[arg(long, value_name = "SYMBOL|FILE", conflicts_with_all = ["seeds", "num_seeds"])]
+        benchmark: Option<String>,
+
+        /// Commit the run's strategy, config, result and CRV report to this HipCortex repository
+        #[arg(long, conflicts_with_all = ["seeds", "num_seeds"])]
+        repo: Option<PathBuf>,
+
+        /// Exit non-zero when CRV finds a violation at or above this severity; writes <out>/gate.json
+        #[arg(long, value_enum, conflicts_with_all = ["seeds", "num_seeds"], value_name = "SEVERITY")]
+        gate: Option<gate::GateArg>,
+
+        /// Stream a time-ordered legacy parquet or columnar file a chunk at a time instead of loading it
+        #[arg(long, conflicts_with_all = ["resume", "seeds", "num_seeds", "benchmark", "repo"])]
+        stream: bool,
+
+        /// Draw a progress bar with an ETA on stderr while the engine runs
+        #[arg(long, conflicts_with_all = ["seeds", "num_seeds"])]
+        progress: bool,
+
+        /// Run the backtest twice and fail unless fills, equity and stats hash the same
+        #[arg(long, conflicts_with_all = ["resume", "seeds", "num_seeds", "stream"])]
+        check_determinism: bool,
+    },
+
+    /// Run a parameter sweep over a grid of spec values
+    Sweep {
+        /// Path to sweep spec JSON file
+        #[arg(long)]
+        spec: PathBuf,
+
+        /// Override a base spec field, e.g. `--set seed=7` (repeatable)
+        #[arg(long = "set", value_name = "PATH=VALUE")]
+        set: Vec<String>,
+
+        /// Use a named broker's cost model; `--set` overrides apply on top
+        #[arg(long, value_enum)]
+        cost_preset: Option<spec::CostPreset>,
+
+        /// Path to a data parquet file, a directory of parquet files, or a glob pattern
         #[arg(long)]
         data: PathBuf,
 
         /// Output directory
+        #[arg(long, required_unless_present = "resume")]
+        out: Option<PathBuf>,
+
+        /// Resume an interrupted sweep in this output directory, reusing verified checkpoints
+        #[arg(long, value_name = "OUT_DIR", conflicts_with = "out")]
+        resume: Option<PathBuf>,
+
+        /// Number of parallel worker threads (defaults to all cores)
+        #[arg(long)]
+        jobs: Option<usize>,
+
+        /// HipCortex repository to commit each run to
+        #[arg(long)]
+        repo: Option<PathBuf>,
+
+        /// Exit non-zero when CRV finds a violation at or above this severity; writes <out>/gate.json
+        #[arg(long, value_enum, value_name = "SEVERITY")]
+        gate: Option<gate::GateArg>,
+    },
+
+    /// Run every backtest listed in a manifest and summarize them together
+    Batch {
+        /// Manifest JSON listing runs as `{"runs": [{"spec", "data", "out", "set"}]}`
+        #[arg(long)]
+        manifest: PathBuf,
+
+        /// Directory to write the batch summary to
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Number of runs executed at once (defaults to the configured jobs, else 1)
+        #[arg(long)]
+        jobs: Option<usize>,
+
+        /// Exit non-zero when CRV finds a violation at or above this severity; writes <out>/gate.json
+        #[arg(long, value_enum, value_name = "SEVERITY")]
+        gate: Option<gate::GateArg>,
+    },
+
+    /// Render a run's output directory as a self-contained HTML report
+    Report {
+        /// Output directory of a backtest run
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Report file (defaults to <out>/report.html)
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// Compare two runs side by side
+    Compare {
+        /// Baseline run: an output directory or a result/CRV report hash
+        a: String,
+
+        /// Candidate run: an output directory or a result/CRV report hash
+        b: String,
+
+        /// HipCortex repository used to resolve artifact hashes
+        #[arg(long)]
+        repo: Option<PathBuf>,
+
+        /// Directory to write the comparison report to
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Re-run CRV checks on existing outputs; exits non-zero when the gate fails
+    Verify {
+        /// Backtest output directory to verify
+        #[arg(long, required_unless_present = "hash", conflicts_with = "hash")]
+        out: Option<PathBuf>,
+
+        /// Backtest result or CRV report hash to verify
+        #[arg(long)]
+        hash: Option<String>,
+
+        /// HipCortex repository containing `--hash`
+        #[arg(long)]
+        repo: Option<PathBuf>,
+
+        /// Policy/rule-config JSON file (constraints, disabled_rules, fail_on)
+        #[arg(long)]
+        policy: Option<PathBuf>,
+
+        /// Path to write the verification report JSON to
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// Lowest severity that fails verification, overriding the policy's `fail_on`
+        #[arg(long, value_enum, value_name = "SEVERITY")]
+        gate: Option<gate::GateArg>,
+    },
+
+    /// Fetch bars from a data provider into canonical event parquet
+    Fetch {
+        /// Registered provider (e.g. alpaca, records)
+        #[arg(long)]
+        provider: String,
+
+        /// Comma-separated symbols
+        #[arg(long, value_delimiter = ',', required = true)]
+        symbols: Vec<String>,
+
+        /// Start date (YYYY-MM-DD) or RFC 3339 timestamp
+        #[arg(long)]
+        start: String,
+
+        /// End date (YYYY-MM-DD) or RFC 3339 timestamp, inclusive
+        #[arg(long)]
+        end: String,
+
+        /// Provider bar timeframe
+        #[arg(long, default_value = "1Day")]
+        timeframe: String,
+
+        /// Price adjustment policy to request and record
+        #[arg(long, default_value = "unadjusted")]
+        adjustment_policy: String,
+
+        /// Override the provider endpoint (API base URL, or records file for `records`)
+        #[arg(long)]
+        endpoint: Option<String>,
+
+        /// Output parquet file
+        #[arg(long)]
+        out: PathBuf,
+
+        /// HipCortex repository to commit the dataset to
+        #[arg(long)]
+        repo: Option<PathBuf>,
+    },
+
+    /// Paper-trade a spec against a live provider feed with simulated fills
+    PaperTrade {
+        /// Path to spec JSON file
+        #[arg(long)]
+        spec: PathBuf,
+
+        /// Override a spec field, e.g. `--set strategy.lookback=40` (repeatable)
+        #[arg(long = "set", value_name = "PATH=VALUE")]
+        set: Vec<String>,
+
+        /// Use a named broker's cost model; `--set` overrides apply on top
+        #[arg(long, value_enum)]
+        cost_preset: Option<spec::CostPreset>,
+
+        /// Registered provider (e.g. alpaca, records)
+        #[arg(long)]
+        provider: String,
+
+        /// Comma-separated symbols (defaults to the strategy's symbols)
+        #[arg(long, value_delimiter = ',')]
+        symbols: Option<Vec<String>>,
+
+        /// Override the provider endpoint (API base URL, or records file for `records`)
+        #[arg(long)]
+        endpoint: Option<String>,
+
+        /// Provider bar timeframe
+        #[arg(long, default_value = "1Day")]
+        timeframe: String,
+
+        /// Price adjustment policy to request
+        #[arg(long, default_value = "unadjusted")]
+        adjustment_policy: String,
+
+        /// First bar of a new session (YYYY-MM-DD or RFC 3339); ignored when resuming
+        #[arg(long)]
+        start: Option<String>,
+
+        /// Seconds between polls of the feed
+        #[arg(long, default_value_t = 60)]
+        poll_interval: u64,
+
+        /// Stop after this many polls (runs until interrupted by default)
+        #[arg(long)]
+        max_polls: Option<usize>,
+
+        /// Session directory holding persisted state and outputs
+        #[arg(long)]
+        session: PathBuf,
+
+        /// HipCortex repository to log live-session traces to
+        #[arg(long)]
+        repo: Option<PathBuf>,
+
+        /// Serve Prometheus metrics at `http://<addr>/metrics` while running
+        #[arg(long)]
+        metrics_addr: Option<String>,
+    },
+
+    /// Serve an HTTP API for submitting backtest jobs and fetching their results,
+    /// with Prometheus metrics at `/metrics`
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+
+        /// Directory that submitted dataset, benchmark, model and module paths are resolved against
+        #[arg(long)]
+        data_root: Option<PathBuf>,
+
+        /// Directory holding per-job working directories
+        #[arg(long)]
+        work_dir: PathBuf,
+
+        /// Number of jobs run concurrently
+        #[arg(long, default_value_t = 2)]
+        workers: usize,
+
+        /// Maximum number of queued jobs before submissions are rejected
+        #[arg(long, default_value_t = 16)]
+        queue_capacity: usize,
+    },
+
+    /// Rerun the golden reference backtests and compare their output hashes
+    Regress {
+        /// Golden suite manifest (cases and their expected hashes)
+        #[arg(long, default_value = "crates/cli/regress/golden.json")]
+        manifest: PathBuf,
+
+        /// Only run these cases (repeatable; all by default)
+        #[arg(long = "case")]
+        cases: Vec<String>,
+
+        /// Record the hashes the runs produced as the new golden hashes
+        #[arg(long)]
+        bless: bool,
+    },
+
+    /// Inspect and prepare datasets
+    Data {
+        #[command(subcommand)]
+        command: DataCommands,
+    },
+
+    /// Export runs as FIX 4.4 messages and compare them with drop-copy executions
+    Fix {
+        #[command(subcommand)]
+        command: FixCommands,
+    },
+
+    /// Show the defaults merged from the config file and environment
+    Config,
+
+    /// Scaffold a sample project: spec, synthetic data, HipCortex repo and a run script
+    Init {
+        /// Project directory (created if missing)
+        dir: PathBuf,
+
+        /// Write into a non-empty directory, overwriting scaffold files
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Run rolling in-sample/out-of-sample walk-forward analysis
+    Walkforward {
+        /// Path to spec JSON file
+        #[arg(long)]
+        spec: PathBuf,
+
+        /// Override a spec field, e.g. `--set strategy.lookback=40` (repeatable)
+        #[arg(long = "set", value_name = "PATH=VALUE")]
+        set: Vec<String>,
+
+        /// Use a named broker's cost model; `--set` overrides apply on top
+        #[arg(long, value_enum)]
+        cost_preset: Option<spec::CostPreset>,
+
+        /// Path to a data parquet file, a directory of parquet files, or a glob pattern
+        #[arg(long)]
+        data: PathBuf,
+
+        /// Output directory; may use placeholders, e.g. `runs/{strategy}/{date}/{config_hash_short}`
         #[arg(long)]
         out: PathBuf,
+
+        /// In-sample window length, in bars
+        #[arg(long)]
+        train: usize,
+
+        /// Out-of-sample window length, in bars
+        #[arg(long)]
+        test: usize,
+
+        /// Bars to advance between windows (defaults to the test length)
+        #[arg(long)]
+        step: Option<usize>,
+
+        /// Exit non-zero when CRV finds a violation at or above this severity; writes <out>/gate.json
+        #[arg(long, value_enum, value_name = "SEVERITY")]
+        gate: Option<gate::GateArg>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FixCommands {
+    /// Write a run's fills as NewOrderSingle and ExecutionReport messages, one per line
+    Export {
+        /// Run to export: an output directory or a result/CRV report hash
+        run: String,
+
+        /// HipCortex repository used to resolve artifact hashes
+        #[arg(long)]
+        repo: Option<PathBuf>,
+
+        /// File to write the messages to
+        #[arg(long)]
+        out: PathBuf,
+
+        /// SenderCompID of the messages
+        #[arg(long, default_value = "AURELIUS")]
+        sender: String,
+
+        /// TargetCompID of the messages
+        #[arg(long, default_value = "BROKER")]
+        target: String,
+
+        /// ClOrdIDs are `<prefix>-<n>` for the run's n-th fill
+        #[arg(long, default_value = "SIM")]
+        prefix: String,
+    },
+
+    /// Compare a run's simulated fills with real executions from a drop copy (sim-vs-real TCA)
+    Tca {
+        /// Simulated run: an output directory or a result/CRV report hash
+        run: String,
+
+        /// HipCortex repository used to resolve artifact hashes
+        #[arg(long)]
+        repo: Option<PathBuf>,
+
+        /// Drop-copy log of ExecutionReports, one message per line (SOH or `|` delimited)
+        #[arg(long)]
+        drop_copy: PathBuf,
+
+        /// ClOrdID prefix used when the run was exported
+        #[arg(long, default_value = "SIM")]
+        prefix: String,
+
+        /// Path to write the TCA report JSON to
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DataCommands {
+    /// Validate a dataset; exits non-zero when it is not fit for backtesting
+    Validate {
+        /// Path to a data parquet file, a directory of parquet files, or a glob pattern
+        #[arg(long)]
+        data: PathBuf,
+
+        /// Fidelity tier the dataset must be ready for
+        #[arg(long, value_enum, default_value = "tier1")]
+        tier: data_cmd::TierArg,
+
+        /// Expected spacing between events in seconds (inferred when omitted)
+        #[arg(long)]
+        expected_interval: Option<i64>,
+
+        /// Path to write the validation report JSON to
+        #[arg(long)]
+        json: Option<PathBuf>,
+    },
+
+    /// Convert CSV / JSON-lines / provider-native files into canonical event parquet
+    Convert {
+        /// Input file
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Input format
+        #[arg(long, value_enum)]
+        format: data_cmd::InputFormat,
+
+        /// Output parquet file
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Data provider recorded as source and in dataset metadata
+        #[arg(long)]
+        provider: String,
+
+        /// Venue class (e.g. lit, otc, crypto_exchange)
+        #[arg(long, default_value = "unknown")]
+        venue_class: String,
+
+        /// Timezone/calendar the timestamps follow
+        #[arg(long, default_value = "UTC/24x7")]
+        calendar: String,
+
+        /// Price adjustment policy (e.g. unadjusted, split_adjusted)
+        #[arg(long, default_value = "unadjusted")]
+        adjustment_policy: String,
+
+        /// Latency class of the source
+        #[arg(long, value_enum, default_value = "unknown")]
+        latency: data_cmd::LatencyArg,
+    },
+
+    /// Pre-convert bars into a memory-mapped columnar file (`.arrow`) for fast loading
+    /// Import a Zipline csvdir bundle or QuantConnect Lean data folder as canonical event parquet
+    Import {
+        /// Layout of the input directory
+        #[arg(long, value_enum)]
+        format: importers::ImportFormat,
+
+        /// Zipline csvdir bundle directory, or Lean data folder root
+        #[arg(long)]
+        input: PathBuf,
+
+        /// Output parquet file
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Bar resolution to import
+        #[arg(long, value_enum, default_value = "daily")]
+        resolution: importers::Resolution,
+
+        /// Comma-separated symbols to import (all found by default)
+        #[arg(long, value_delimiter = ',')]
+        symbols: Vec<String>,
+
+        /// Lean equity market directory
+        #[arg(long, default_value = "usa")]
+        market: String,
+
+        /// HipCortex repository to commit the dataset to
+        #[arg(long)]
+        repo: Option<PathBuf>,
+    },
+
+    Columnar {
+        /// Path to a data parquet file, a directory of parquet files, or a glob pattern
+        #[arg(long)]
+        data: PathBuf,
+
+        /// Output file; must end in `.arrow` to be recognized as `--data`
+        #[arg(long)]
+        out: PathBuf,
+    },
+
+    /// Generate a seeded random-walk daily bar dataset as canonical event parquet
+    Generate {
+        /// Output parquet file
+        #[arg(long)]
+        out: PathBuf,
+
+        /// Comma-separated symbols
+        #[arg(long, value_delimiter = ',', default_value = "SYNTH")]
+        symbols: Vec<String>,
+
+        /// Daily bars per symbol
+        #[arg(long, default_value_t = 252)]
+        days: usize,
+
+        /// First bar date (YYYY-MM-DD)
+        #[arg(long, default_value = "2023-01-02")]
+        start: String,
+
+        /// Random seed; the same seed always produces the same data
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
     },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let config = config::Config::load()?;
+    if let Some(jobs) = config.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .context("Failed to configure worker threads")?;
+    }
 
     match cli.command {
-        Commands::Backtest { spec, data, out } => {
-            backtest_cmd::run_backtest(&spec, &data, &out).context("Failed to run backtest")?;
+        Commands::Backtest {
+            spec,
+            set,
+            cost_preset,
+            data,
+            out,
+            seeds,
+            num_seeds,
+            cost_sensitivity,
+            benchmark,
+            resume,
+            repo,
+            gate,
+            stream,
+            progress,
+            check_determinism,
+        } => {
+            let set = spec::with_cost_preset(cost_preset, set);
+            let (out, resuming) = out_or_resume(out, resume);
+            let (data, out, repo) = (config.data(data), config.out(out), config.repo(repo));
+            let out = if resuming {
+                out
+            } else {
+                templated_out(out, &spec, &set)?
+            };
+            if stream {
+                let crv_report =
+                    backtest_cmd::run_streamed_backtest(&spec, &set, &data, &out, progress)
+                        .context("Failed to run streamed backtest")?;
+                if let Some(gate) = gate {
+                    let runs = [("backtest".to_string(), &crv_report)];
+                    if !gate::enforce(gate.into(), runs, &out)? {
+                        std::process::exit(1);
+                    }
+                }
+            } else if seeds.is_some() || num_seeds.is_some() {
+                let seeds = seeds.as_deref().map(seeds_cmd::parse_seeds).transpose()?;
+                seeds_cmd::run_seed_robustness(&spec, &set, &data, &out, seeds, num_seeds)
+                    .context("Failed to run seed robustness")?;
+            } else if let Some(multipliers) = cost_sensitivity {
+                let multipliers = cost_sensitivity_cmd::parse_multipliers(&multipliers)?;
+                cost_sensitivity_cmd::run_cost_sensitivity(&spec, &set, &data, &out, &multipliers)
+                    .context("Failed to run cost sensitivity")?;
+            } else {
+                let options = backtest_cmd::BacktestOptions {
+                    benchmark: benchmark.as_deref(),
+                    resume: resuming,
+                    repo: repo.as_deref(),
+                    progress,
+                    check_determinism,
+                };
+                let crv_report = backtest_cmd::run_backtest(&spec, &set, &data, &out, &options)
+                    .context("Failed to run backtest")?;
+                if let Some(gate) = gate {
+                    let runs = [("backtest".to_string(), &crv_report)];
+                    if !gate::enforce(gate.into(), runs, &out)? {
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        Commands::Sweep {
+            spec,
+            set,
+            cost_preset,
+            data,
+            out,
+            jobs,
+            repo,
+            resume,
+            gate,
+        } => {
+            let set = spec::with_cost_preset(cost_preset, set);
+            let (out, resuming) = out_or_resume(out, resume);
+            let (data, out, repo) = (config.data(data), config.out(out), config.repo(repo));
+            let crv_reports =
+                sweep_cmd::run_sweep(&spec, &set, &data, &out, jobs, repo.as_ref(), resuming)
+                    .context("Failed to run sweep")?;
+            if let Some(gate) = gate {
+                let runs = crv_reports.iter().map(|(id, report)| (id.clone(), report));
+                if !gate::enforce(gate.into(), runs, &out)? {
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Batch {
+            manifest,
+            out,
+            jobs,
+            gate,
+        } => {
+            let out = config.out(out);
+            let result = batch_cmd::run_batch(&manifest, &out, jobs.or(config.jobs).unwrap_or(1))
+                .context("Failed to run batch")?;
+            let mut passed = result.errors() == 0;
+            if let Some(gate) = gate {
+                let runs = result
+                    .crv_reports
+                    .iter()
+                    .map(|(name, report)| (name.clone(), report));
+                passed &= gate::enforce(gate.into(), runs, &out)?;
+            }
+            if !passed {
+                std::process::exit(1);
+            }
+        }
+        Commands::Report { out, report } => {
+            let out = config.out(out);
+            report_cmd::run_report(&out, report.as_deref()).context("Failed to render report")?;
+        }
+        Commands::Compare { a, b, repo, out } => {
+            let repo = config.repo(repo);
+            compare_cmd::run_compare(&a, &b, repo.as_deref(), out.as_deref())
+                .context("Failed to compare runs")?;
+        }
+        Commands::Verify {
+            out,
+            hash,
+            repo,
+            policy,
+            report,
+            gate,
+        } => {
+            let (out, repo) = (out.map(|out| config.out(out)), config.repo(repo));
+            let source = match (&out, &hash, &repo) {
+                (Some(out), _, _) => verify_cmd::VerifySource::OutputDir(out),
+                (None, Some(hash), Some(repo)) => verify_cmd::VerifySource::Hash { hash, repo },
+                _ => anyhow::bail!("verify requires --out, or --hash with --repo"),
+            };
+            let passed = verify_cmd::run_verify(
+                source,
+                policy.as_deref(),
+                report.as_deref(),
+                gate.map(Into::into),
+            )
+            .context("Failed to verify run")?;
+            if !passed {
+                std::process::exit(1);
+            }
+        }
+        Commands::Fetch {
+            provider,
+            symbols,
+            start,
+            end,
+            timeframe,
+            adjustment_policy,
+            endpoint,
+            out,
+            repo,
+        } => {
+            let request = providers::FetchRequest {
+                symbols,
+                start: providers::parse_time(&start)?,
+                end: providers::parse_time(&end)?,
+                timeframe,
+                adjustment_policy,
+            };
+            let options = providers::ProviderOptions { endpoint };
+            let repo = config.repo(repo);
+            fetch_cmd::run_fetch(&provider, &request, &options, &out, repo.as_deref())
+                .context("Failed to fetch data")?;
+        }
+        Commands::PaperTrade {
+            spec,
+            set,
+            cost_preset,
+            provider,
+            symbols,
+            endpoint,
+            timeframe,
+            adjustment_policy,
+            start,
+            poll_interval,
+            max_polls,
+            session,
+            repo,
+            metrics_addr,
+        } => {
+            let set = spec::with_cost_preset(cost_preset, set);
+            let options = paper_trade_cmd::PaperTradeOptions {
+                provider,
+                provider_options: providers::ProviderOptions { endpoint },
+                symbols,
+                timeframe,
+                adjustment_policy,
+                start: start.as_deref().map(providers::parse_time).transpose()?,
+                poll_interval: std::time::Duration::from_secs(poll_interval),
+                max_polls,
+                session_dir: session,
+                repo: config.repo(repo),
+                metrics_addr,
+            };
+            paper_trade_cmd::run_paper_trade(&spec, &set, &options)
+                .context("Failed to run paper trading session")?;
+        }
+        Commands::Serve {
+            addr,
+            data_root,
+            work_dir,
+            workers,
+            queue_capacity,
+        } => {
+            let data_root = data_root
+                .or_else(|| config.data_root.clone())
+                .context("serve requires --data-root or a configured data_root")?;
+            serve_cmd::run_serve(serve_cmd::ServeOptions {
+                addr,
+                data_root,
+                work_dir,
+                workers,
+                queue_capacity,
+            })
+            .context("Failed to run server")?;
+        }
+        Commands::Regress {
+            manifest,
+            cases,
+            bless,
+        } => {
+            let passed = regress_cmd::run_regress(&manifest, &cases, bless)
+                .context("Failed to run golden regression suite")?;
+            if !passed {
+                std::process::exit(1);
+            }
+        }
+        Commands::Fix { command } => match command {
+            FixCommands::Export {
+                run,
+                repo,
+                out,
+                sender,
+                target,
+                prefix,
+            } => {
+                let repo = config.repo(repo);
+                fix_cmd::run_export(&run, repo.as_deref(), &out, &sender, &target, &prefix)
+                    .context("Failed to export FIX messages")?;
+            }
+            FixCommands::Tca {
+                run,
+                repo,
+                drop_copy,
+                prefix,
+                out,
+            } => {
+                let repo = config.repo(repo);
+                fix_cmd::run_tca(&run, repo.as_deref(), &drop_copy, &prefix, out.as_deref())
+                    .context("Failed to compare against drop copy")?;
+            }
+        },
+        Commands::Data { command } => match command {
+            DataCommands::Validate {
+                data,
+                tier,
+                expected_interval,
+                json,
+            } => {
+                let data = config.data(data);
+                let passed =
+                    data_cmd::run_validate(&data, tier, expected_interval, json.as_deref())
+                        .context("Failed to validate data")?;
+                if !passed {
+                    std::process::exit(1);
+                }
+            }
+            DataCommands::Convert {
+                input,
+                format,
+                out,
+                provider,
+                venue_class,
+                calendar,
+                adjustment_policy,
+                latency,
+            } => {
+                let options = data_cmd::ConvertOptions {
+                    provider,
+                    venue_class,
+                    timezone_calendar: calendar,
+                    adjustment_policy,
+                    latency_class: latency.into(),
+                };
+                data_cmd::run_convert(&input, format, &out, &options)
+                    .context("Failed to convert data")?;
+            }
+            DataCommands::Import {
+                format,
+                input,
+                out,
+                resolution,
+                symbols,
+                market,
+                repo,
+            } => {
+                let options = importers::ImportOptions {
+                    format,
+                    resolution,
+                    symbols,
+                    market,
+                };
+                let repo = config.repo(repo);
+                data_cmd::run_import(&input, &options, &out, repo.as_deref())
+                    .context("Failed to import data")?;
+            }
+            DataCommands::Columnar { data, out } => {
+                if !columnar::is_columnar(&out) {
+                    anyhow::bail!("Columnar output {:?} must have the .arrow extension", out);
+                }
+                data_cmd::run_columnar(&config.data(data), &out)
+                    .context("Failed to write columnar data")?;
+            }
+            DataCommands::Generate {
+                out,
+                symbols,
+                days,
+                start,
+                seed,
+            } => {
+                let spec = data_cmd::SyntheticSpec {
+                    symbols,
+                    days,
+                    start: providers::parse_time(&start)?,
+                    seed,
+                };
+                data_cmd::run_generate(&spec, &out).context("Failed to generate data")?;
+            }
+        },
+        Commands::Config => config.print(),
+        Commands::Init { dir, force } => {
+            init_cmd::run_init(&dir, force).context("Failed to initialize project")?;
+        }
+        Commands::Walkforward {
+            spec,
+            set,
+            cost_preset,
+            data,
+            out,
+            train,
+            test,
+            step,
+            gate,
+        } => {
+            let set = spec::with_cost_preset(cost_preset, set);
+            let (data, out) = (config.data(data), config.out(out));
+            let out = templated_out(out, &spec, &set)?;
+            let crv_report =
+                walkforward_cmd::run_walkforward(&spec, &set, &data, &out, train, test, step)
+                    .context("Failed to run walk-forward analysis")?;
+            if let Some(gate) = gate {
+                let runs = [("walkforward".to_string(), &crv_report)];
+                if !gate::enforce(gate.into(), runs, &out)? {
+                    std::process::exit(1);
+                }
+            }
         }
     }
 
     Ok(())
 }
+
+/// The output directory of a run, and whether it resumes an earlier one
+/// Expand a templated `--out` for the spec the run will use, recording how it was named
+fn templated_out(out: PathBuf, spec: &std::path::Path, set: &[String]) -> Result<PathBuf> {
+    if !out_template::is_template(&out) {
+        return Ok(out);
+    }
+    let spec = backtest_cmd::load_spec(spec, set)?;
+    let manifest = out_template::resolve(&out, &spec, chrono::Utc::now())?;
+    manifest.write(&manifest.out_dir)?;
+    println!("Output directory: {:?}", manifest.out_dir);
+    Ok(manifest.out_dir)
+}
+
+fn out_or_resume(out: Option<PathBuf>, resume: Option<PathBuf>) -> (PathBuf, bool) {
+    match (resume, out) {
+        (Some(dir), _) => (dir, true),
+        // clap requires one of the two
+        (None, out) => (out.expect("--out or --resume"), false),
+    }
+}
@@ -0,0 +1,238 @@
+//! Prometheus metrics for the long-running modes.
+//!
+//! `serve` exposes `GET /metrics` on its API; `paper-trade` serves it on
+//! `--metrics-addr` when given. Each mode only exports the series it can
+//! observe: serve jobs run whole backtests, so it reports job queue depth
+//! but not individual orders, and only paper sessions commit to a
+//! repository.
+
+use anyhow::Result;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Response, Server};
+
+/// Upper bounds, in seconds, of the commit latency histogram buckets
+const COMMIT_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Prometheus text exposition content type
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Cumulative counts per bucket of `COMMIT_BUCKETS`
+    buckets: [u64; COMMIT_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+/// Counters and gauges shared between a mode's workers and its endpoint
+#[derive(Debug)]
+pub struct Metrics {
+    mode: &'static str,
+    events: AtomicU64,
+    /// `f64` bits of the rate over the most recent batch of events
+    events_per_second: AtomicU64,
+    fills: AtomicU64,
+    orders: Option<AtomicU64>,
+    queue_depth: Option<AtomicU64>,
+    commit_latency: Option<Mutex<Histogram>>,
+}
+
+impl Metrics {
+    fn new(mode: &'static str) -> Self {
+        Self {
+            mode,
+            events: AtomicU64::new(0),
+            events_per_second: AtomicU64::new(0f64.to_bits()),
+            fills: AtomicU64::new(0),
+            orders: None,
+            queue_depth: None,
+            commit_latency: None,
+        }
+    }
+
+    pub fn serve() -> Self {
+        Self {
+            queue_depth: Some(AtomicU64::new(0)),
+            ..Self::new("serve")
+        }
+    }
+
+    pub fn paper_trade() -> Self {
+        Self {
+            orders: Some(AtomicU64::new(0)),
+            commit_latency: Some(Mutex::new(Histogram::default())),
+            ..Self::new("paper_trade")
+        }
+    }
+
+    /// Count a batch of `events` processed in `elapsed`
+    pub fn record_events(&self, events: usize, elapsed: Duration) {
+        self.events.fetch_add(events as u64, Ordering::Relaxed);
+        if events > 0 {
+            let rate = events as f64 / elapsed.as_secs_f64().max(1e-9);
+            self.events_per_second
+                .store(rate.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_fills(&self, fills: usize) {
+        self.fills.fetch_add(fills as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_orders(&self, orders: usize) {
+        if let Some(counter) = &self.orders {
+            counter.fetch_add(orders as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn set_queue_depth(&self, depth: usize) {
+        if let Some(gauge) = &self.queue_depth {
+            gauge.store(depth as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_commit(&self, latency: Duration) {
+        let Some(histogram) = &self.commit_latency else {
+            return;
+        };
+        let seconds = latency.as_secs_f64();
+        let mut histogram = histogram.lock().unwrap();
+        for (bucket, bound) in histogram.buckets.iter_mut().zip(COMMIT_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+    }
+
+    /// The current values in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let label = format!("mode=\"{}\"", self.mode);
+        let mut family = |name: &str, kind: &str, help: &str, value: String| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            let _ = writeln!(out, "{}{{{}}} {}", name, label, value);
+        };
+
+        family(
+            "quant_engine_events_processed_total",
+            "counter",
+            "Market events (bars) run through the engine",
+            self.events.load(Ordering::Relaxed).to_string(),
+        );
+        family(
+            "quant_engine_events_per_second",
+            "gauge",
+            "Events processed per second over the most recent batch",
+            f64::from_bits(self.events_per_second.load(Ordering::Relaxed)).to_string(),
+        );
+        if let Some(orders) = &self.orders {
+            family(
+                "quant_engine_orders_total",
+                "counter",
+                "Orders sent by the strategy to the broker",
+                orders.load(Ordering::Relaxed).to_string(),
+            );
+        }
+        family(
+            "quant_engine_fills_total",
+            "counter",
+            "Fills produced by the simulated broker",
+            self.fills.load(Ordering::Relaxed).to_string(),
+        );
+        if let Some(depth) = &self.queue_depth {
+            family(
+                "quant_engine_job_queue_depth",
+                "gauge",
+                "Submitted jobs waiting for a worker",
+                depth.load(Ordering::Relaxed).to_string(),
+            );
+        }
+
+        if let Some(histogram) = &self.commit_latency {
+            let histogram = histogram.lock().unwrap();
+            let name = "quant_engine_repository_commit_seconds";
+            let _ = writeln!(
+                out,
+                "# HELP {} Latency of HipCortex repository commits",
+                name
+            );
+            let _ = writeln!(out, "# TYPE {} histogram", name);
+            for (count, bound) in histogram.buckets.iter().zip(COMMIT_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{{},le=\"{}\"}} {}",
+                    name, label, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"+Inf\"}} {}",
+                name, label, histogram.count
+            );
+            let _ = writeln!(out, "{}_sum{{{}}} {}", name, label, histogram.sum);
+            let _ = writeln!(out, "{}_count{{{}}} {}", name, label, histogram.count);
+        }
+        out
+    }
+}
+
+/// Serve `GET /metrics` on `addr` from a background thread
+pub fn spawn_metrics_server(addr: &str, metrics: Arc<Metrics>) -> Result<()> {
+    let server =
+        Server::http(addr).map_err(|e| anyhow::anyhow!("Failed to bind {}: {}", addr, e))?;
+    println!("Serving metrics on http://{}/metrics", server.server_addr());
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = if request.method() == &Method::Get && request.url() == "/metrics" {
+                Response::from_string(metrics.render()).with_header(content_type())
+            } else {
+                Response::from_string("Not found").with_status_code(404)
+            };
+            let _ = request.respond(response);
+        }
+    });
+    Ok(())
+}
+
+pub fn content_type() -> Header {
+    Header::from_bytes("Content-Type", CONTENT_TYPE).expect("valid header")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_only_the_series_a_mode_observes() {
+        let metrics = Metrics::paper_trade();
+        metrics.record_events(50, Duration::from_millis(100));
+        metrics.record_orders(3);
+        metrics.record_fills(2);
+        metrics.record_commit(Duration::from_millis(20));
+        metrics.set_queue_depth(4);
+
+        let text = metrics.render();
+        assert!(text.contains("quant_engine_events_processed_total{mode=\"paper_trade\"} 50\n"));
+        assert!(text.contains("quant_engine_events_per_second{mode=\"paper_trade\"} 500\n"));
+        assert!(text.contains("quant_engine_orders_total{mode=\"paper_trade\"} 3\n"));
+        assert!(text.contains("quant_engine_fills_total{mode=\"paper_trade\"} 2\n"));
+        // 20ms falls in every bucket from 0.05s up
+        assert!(text.contains("_bucket{mode=\"paper_trade\",le=\"0.01\"} 0\n"));
+        assert!(text.contains("_bucket{mode=\"paper_trade\",le=\"0.05\"} 1\n"));
+        assert!(text.contains("_bucket{mode=\"paper_trade\",le=\"+Inf\"} 1\n"));
+        assert!(!text.contains("queue_depth"));
+
+        let serve = Metrics::serve();
+        serve.set_queue_depth(4);
+        let text = serve.render();
+        assert!(text.contains("quant_engine_job_queue_depth{mode=\"serve\"} 4\n"));
+        assert!(!text.contains("orders_total") && !text.contains("commit_seconds"));
+    }
+}
@@ -0,0 +1,267 @@
+//! Strategies driven by an ONNX model.
+//!
+//! Each bar of `symbol` updates the spec's feature vector (see `features`).
+//! Once every feature is warm the model runs on it as a `[1, n]` f32 tensor,
+//! and the first element of its first output is the signal. Inference uses
+//! tract, a pure-Rust CPU runtime, so the same model and bars give the same
+//! trades; pin `model_sha256` to make the model part of that guarantee.
+
+use anyhow::{Context, Result};
+use indicators::sizing::equity_fraction_shares;
+use schema::{Bar, Order, Portfolio, Strategy};
+use std::fs;
+use std::path::Path;
+use tract_onnx::prelude::*;
+
+use crate::features::FeatureVector;
+use crate::spec::{Direction, FeatureSpec};
+use crate::strategies::{order_to_target, position_of};
+
+type Plan = Arc<TypedRunnableModel>;
+
+pub struct OnnxStrategy {
+    symbol: String,
+    plan: Plan,
+    features: FeatureVector,
+    threshold: f64,
+    mode: Direction,
+    allocation: f64,
+    /// -1 short, 0 flat, 1 long
+    position: i8,
+}
+
+impl OnnxStrategy {
+    /// Load and optimize a model for the spec's feature count, verifying its
+    /// hash when one is pinned
+    pub fn load(
+        path: &Path,
+        expected_sha256: Option<&str>,
+        symbol: String,
+        features: &[FeatureSpec],
+        threshold: f64,
+        mode: Direction,
+        allocation: f64,
+    ) -> Result<Self> {
+        let bytes =
+            fs::read(path).with_context(|| format!("Failed to read ONNX model {:?}", path))?;
+        if let Some(expected) = expected_sha256 {
+            let actual = engine::stable_hash_bytes(&bytes);
+            if !actual.eq_ignore_ascii_case(expected) {
+                anyhow::bail!(
+                    "ONNX model {:?} has SHA-256 {}, spec pins {}",
+                    path,
+                    actual,
+                    expected
+                );
+            }
+        }
+
+        let features = FeatureVector::new(features);
+        let plan = tract_onnx::onnx()
+            .model_for_read(&mut bytes.as_slice())
+            .and_then(|model| model.with_input_fact(0, f32::fact([1, features.width()]).into()))
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .with_context(|| {
+                format!(
+                    "Failed to load ONNX model {:?} for {} input feature(s)",
+                    path,
+                    features.width()
+                )
+            })?;
+
+        Ok(Self {
+            symbol,
+            plan,
+            features,
+            threshold,
+            mode,
+            allocation,
+            position: 0,
+        })
+    }
+
+    fn signal(&self, vector: Vec<f32>) -> Result<f64> {
+        let input = Tensor::from_shape(&[1, vector.len()], &vector)?;
+        let outputs = self.plan.run(tvec!(input.into()))?;
+        let output = outputs
+            .first()
+            .context("ONNX model produced no outputs")?
+            .to_plain_array_view::<f32>()?;
+        let signal = output
+            .iter()
+            .next()
+            .context("ONNX model produced an empty output")?;
+        Ok(f64::from(*signal))
+    }
+}
+
+impl Strategy for OnnxStrategy {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        if bar.symbol != self.symbol {
+            return vec![];
+        }
+        let Some(vector) = self.features.update(bar) else {
+            return vec![];
+        };
+        // A model that fails at run time behaves as if it gave no signal
+        let Ok(signal) = self.signal(vector) else {
+            return vec![];
+        };
+
+        let position = if signal > self.threshold {
+            1
+        } else if signal < -self.threshold && self.mode == Direction::LongShort {
+            -1
+        } else {
+            0
+        };
+        if position == self.position {
+            return vec![];
+        }
+        self.position = position;
+
+        let target = f64::from(position)
+            * equity_fraction_shares(portfolio.equity, self.allocation, bar.close);
+        order_to_target(&self.symbol, position_of(portfolio, &self.symbol), target)
+    }
+
+    fn name(&self) -> &str {
+        "Onnx"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::StrategySpec;
+    use crate::strategies::build_strategy;
+    use prost::Message;
+    use schema::Side;
+    use tract_onnx::pb;
+
+    /// `signal = features · weights`, serialized as an ONNX model
+    fn linear_model(weights: &[f32]) -> Vec<u8> {
+        let dim = |size: i64| pb::tensor_shape_proto::Dimension {
+            value: Some(pb::tensor_shape_proto::dimension::Value::DimValue(size)),
+            ..Default::default()
+        };
+        let input_type = pb::TypeProto {
+            value: Some(pb::type_proto::Value::TensorType(pb::type_proto::Tensor {
+                elem_type: 1,
+                shape: Some(pb::TensorShapeProto {
+                    dim: vec![dim(1), dim(weights.len() as i64)],
+                }),
+            })),
+            ..Default::default()
+        };
+        pb::ModelProto {
+            ir_version: 7,
+            opset_import: vec![pb::OperatorSetIdProto {
+                domain: String::new(),
+                version: 13,
+            }],
+            graph: Some(pb::GraphProto {
+                name: "linear".into(),
+                node: vec![pb::NodeProto {
+                    input: vec!["features".into(), "weights".into()],
+                    output: vec!["signal".into()],
+                    op_type: "MatMul".into(),
+                    ..Default::default()
+                }],
+                initializer: vec![pb::TensorProto {
+                    name: "weights".into(),
+                    dims: vec![weights.len() as i64, 1],
+                    data_type: 1,
+                    float_data: weights.to_vec(),
+                    ..Default::default()
+                }],
+                input: vec![pb::ValueInfoProto {
+                    name: "features".into(),
+                    r#type: Some(input_type),
+                    ..Default::default()
+                }],
+                output: vec![pb::ValueInfoProto {
+                    name: "signal".into(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    fn bar(timestamp: i64, close: f64) -> Bar {
+        Bar {
+            timestamp,
+            symbol: "AAPL".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000.0,
+        }
+    }
+
+    #[test]
+    fn model_signal_drives_long_short_positions() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("momentum.onnx");
+        fs::write(&model, linear_model(&[1.0])).unwrap();
+
+        // The signal is the one-bar return itself
+        let spec: StrategySpec = serde_json::from_value(serde_json::json!({
+            "type": "onnx",
+            "model": model,
+            "symbol": "AAPL",
+            "features": [{"type": "return", "lookback": 1}],
+            "threshold": 0.01,
+            "mode": "long_short"
+        }))
+        .unwrap();
+        let mut strategy = build_strategy(&spec).unwrap();
+        let portfolio = Portfolio::new(10_000.0);
+        let sides: Vec<Option<Side>> = [10.0, 10.5, 10.5, 10.0]
+            .iter()
+            .enumerate()
+            .map(|(t, close)| {
+                strategy
+                    .on_bar(&bar(t as i64, *close), &portfolio)
+                    .first()
+                    .map(|o| o.side)
+            })
+            .collect();
+        // Warm-up, +5% long, flat again (a no-op order on the unfilled test
+        // portfolio), then -4.8% short
+        assert_eq!(sides, vec![None, Some(Side::Buy), None, Some(Side::Sell)]);
+    }
+
+    #[test]
+    fn load_checks_feature_count_and_pinned_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("two_inputs.onnx");
+        let bytes = linear_model(&[1.0, -1.0]);
+        fs::write(&model, &bytes).unwrap();
+        let features = [
+            FeatureSpec::Return { lookback: 1 },
+            FeatureSpec::Rsi { period: 14 },
+        ];
+        let load = |features: &[FeatureSpec], sha: Option<&str>| {
+            OnnxStrategy::load(
+                &model,
+                sha,
+                "AAPL".to_string(),
+                features,
+                0.0,
+                Direction::LongOnly,
+                1.0,
+            )
+        };
+
+        assert!(load(&features, Some(&engine::stable_hash_bytes(&bytes))).is_ok());
+        assert!(load(&features[..1], None).is_err());
+        let err = load(&features, Some("00")).err().unwrap();
+        assert!(err.to_string().contains("spec pins 00"));
+    }
+}
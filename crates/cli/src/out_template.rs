@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::spec::BacktestSpec;
+
+pub const RUN_MANIFEST_FILE: &str = "run_manifest.json";
+
+/// Placeholders accepted in a templated `--out`
+const PLACEHOLDERS: [&str; 7] = [
+    "strategy",
+    "symbols",
+    "seed",
+    "date",
+    "time",
+    "config_hash",
+    "config_hash_short",
+];
+
+/// Records how a templated output directory was named
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub out_template: String,
+    pub out_dir: PathBuf,
+    /// Hash of the effective spec, as in `effective_spec.sha256`
+    pub config_hash: String,
+    pub strategy: String,
+    pub created_at: String,
+}
+
+impl RunManifest {
+    pub fn write(&self, out_dir: &Path) -> Result<()> {
+        fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+        let file = fs::File::create(out_dir.join(RUN_MANIFEST_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Whether `--out` contains `{placeholder}`s to expand
+pub fn is_template(out: &Path) -> bool {
+    out.to_string_lossy().contains('{')
+}
+
+/// Expand `{strategy}`, `{symbols}`, `{seed}`, `{date}`, `{time}`,
+/// `{config_hash}` and `{config_hash_short}` for the spec a run will use
+pub fn resolve(template: &Path, spec: &BacktestSpec, now: DateTime<Utc>) -> Result<RunManifest> {
+    let template = template.to_string_lossy().into_owned();
+    let config_hash = engine::canonical_json_hash(spec)?;
+    let strategy = serde_json::to_value(&spec.strategy)?
+        .get("type")
+        .and_then(|t| t.as_str())
+        .unwrap_or("strategy")
+        .to_string();
+
+    let value = |name: &str| -> String {
+        match name {
+            "strategy" => strategy.clone(),
+            "symbols" => spec.symbols().join("-"),
+            "seed" => spec.seed.to_string(),
+            "date" => now.format("%Y-%m-%d").to_string(),
+            "time" => now.format("%H%M%S").to_string(),
+            "config_hash" => config_hash.clone(),
+            "config_hash_short" => config_hash.chars().take(12).collect(),
+            _ => unreachable!("placeholders are checked before expansion"),
+        }
+    };
+
+    let mut resolved = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+    while let Some(open) = rest.find('{') {
+        resolved.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .map(|i| open + i)
+            .with_context(|| format!("Unclosed '{{' in --out template '{}'", template))?;
+        let name = &rest[open + 1..close];
+        if !PLACEHOLDERS.contains(&name) {
+            anyhow::bail!(
+                "Unknown placeholder '{{{}}}' in --out template; expected one of {}",
+                name,
+                PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+            );
+        }
+        resolved.push_str(&sanitize(&value(name)));
+        rest = &rest[close + 1..];
+    }
+    resolved.push_str(rest);
+
+    Ok(RunManifest {
+        out_template: template,
+        out_dir: PathBuf::from(resolved),
+        config_hash,
+        strategy,
+        created_at: now.to_rfc3339(),
+    })
+}
+
+/// Keep expanded values to a single, portable path component
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::parse_spec;
+    use chrono::TimeZone;
+
+    #[test]
+    fn expands_placeholders_from_the_effective_spec() {
+        let spec = parse_spec(serde_json::json!({
+            "initial_cash": 1000.0,
+            "seed": 7,
+            "strategy": {"type": "sma_crossover", "symbol": "BRK/B", "fast": 2, "slow": 4},
+            "cost_model": {"type": "zero"}
+        }))
+        .unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 3, 5, 14, 30, 0).unwrap();
+
+        let manifest = resolve(
+            Path::new("runs/{strategy}/{symbols}/{date}/{config_hash_short}-s{seed}"),
+            &spec,
+            now,
+        )
+        .unwrap();
+        let short = &manifest.config_hash[..12];
+        assert_eq!(
+            manifest.out_dir,
+            PathBuf::from(format!("runs/sma_crossover/BRK_B/2024-03-05/{}-s7", short))
+        );
+        assert_eq!(
+            manifest.config_hash,
+            engine::canonical_json_hash(&spec).unwrap()
+        );
+
+        let err = resolve(Path::new("runs/{user}"), &spec, now).unwrap_err();
+        assert!(err.to_string().contains("{config_hash_short}"));
+        assert!(resolve(Path::new("runs/{date"), &spec, now).is_err());
+    }
+}
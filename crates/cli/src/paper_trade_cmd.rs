@@ -0,0 +1,396 @@
+use anyhow::{Context, Result};
+use broker_sim::{SimpleBroker, TradingHalts};
+use engine::LiveEngine;
+use hipcortex::{Artifact, Repository, Trace};
+use schema::{
+    sort_events_deterministically, AdapterRequest, Bar, CostModel, FidelityTier, Fill,
+    MarketAssetClass, MarketEventType, Strategy,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::backtest_cmd::{build_broker, load_spec};
+use crate::data::bars_from_events;
+use crate::metrics::{spawn_metrics_server, Metrics};
+use crate::providers::{registered_provider, FetchRequest, ProviderOptions, RegisteredProvider};
+use crate::spec::BacktestSpec;
+use crate::strategies::build_strategy;
+
+type PaperEngine = LiveEngine<Box<dyn Strategy>, SimpleBroker<Box<dyn CostModel>>>;
+
+/// Persisted state of a paper-trading session.
+///
+/// The engine is deterministic, so replaying `bars` through a fresh engine
+/// restores positions, cash and fills exactly; no strategy internals are stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaperSession {
+    pub spec: BacktestSpec,
+    pub spec_hash: String,
+    pub provider: String,
+    pub symbols: Vec<String>,
+    pub timeframe: String,
+    pub adjustment_policy: String,
+    /// First timestamp requested when the session was created
+    pub start: i64,
+    pub bars: Vec<Bar>,
+    /// Most recent live-session Trace committed to HipCortex
+    pub last_trace: Option<String>,
+}
+
+/// How to connect and poll for a paper session
+pub struct PaperTradeOptions {
+    pub provider: String,
+    pub provider_options: ProviderOptions,
+    /// Defaults to the symbols the strategy trades
+    pub symbols: Option<Vec<String>>,
+    pub timeframe: String,
+    pub adjustment_policy: String,
+    /// Start of a new session; history up to now is simulated before live polling
+    pub start: Option<i64>,
+    pub poll_interval: Duration,
+    /// Stop after this many polls (runs until interrupted when unset)
+    pub max_polls: Option<usize>,
+    pub session_dir: PathBuf,
+    pub repo: Option<PathBuf>,
+    /// Serve Prometheus metrics on this address while the session runs
+    pub metrics_addr: Option<String>,
+}
+
+/// Run a strategy against a live provider feed with simulated execution
+pub fn run_paper_trade(
+    spec_path: &Path,
+    overrides: &[String],
+    options: &PaperTradeOptions,
+) -> Result<PaperSession> {
+    let spec = load_spec(spec_path, overrides)?;
+    if spec.universe.is_some() {
+        anyhow::bail!("Specs with a universe can only be backtested, not paper traded");
+    }
+    let spec_hash = engine::canonical_json_hash(&spec)?;
+    fs::create_dir_all(&options.session_dir).context("Failed to create session directory")?;
+
+    let session_path = options.session_dir.join("session.json");
+    let mut session = if session_path.exists() {
+        let session: PaperSession = serde_json::from_reader(fs::File::open(&session_path)?)
+            .context("Failed to parse session.json")?;
+        if session.spec_hash != spec_hash {
+            anyhow::bail!(
+                "Session in {:?} was started with a different spec; use a new --session directory",
+                options.session_dir
+            );
+        }
+        if session.provider != options.provider {
+            anyhow::bail!(
+                "Session in {:?} uses provider '{}', not '{}'",
+                options.session_dir,
+                session.provider,
+                options.provider
+            );
+        }
+        session
+    } else {
+        let start = options
+            .start
+            .ok_or_else(|| anyhow::anyhow!("--start is required to begin a new session"))?;
+        PaperSession {
+            spec: spec.clone(),
+            spec_hash: spec_hash.clone(),
+            provider: options.provider.clone(),
+            symbols: options
+                .symbols
+                .clone()
+                .unwrap_or_else(|| spec.strategy.symbols()),
+            timeframe: options.timeframe.clone(),
+            adjustment_policy: options.adjustment_policy.clone(),
+            start,
+            bars: Vec::new(),
+            last_trace: None,
+        }
+    };
+
+    let mut engine = new_engine(&spec)?;
+    for bar in &session.bars {
+        engine.on_bar(bar)?;
+    }
+    if !session.bars.is_empty() {
+        println!(
+            "Resumed session: {} bar(s), {} fill(s), equity ${:.2}",
+            session.bars.len(),
+            engine.num_trades(),
+            engine.portfolio().equity
+        );
+    }
+
+    let provider = registered_provider(&session.provider, &options.provider_options)?;
+    provider.adapter.supports_request(&AdapterRequest {
+        asset_class: MarketAssetClass::Equity,
+        event_type: MarketEventType::Bar,
+        fidelity_tier: FidelityTier::Tier1Bar,
+    })?;
+    let mut repo = options
+        .repo
+        .as_deref()
+        .map(Repository::open)
+        .transpose()
+        .context("Failed to open repository")?;
+    let metrics = Arc::new(Metrics::paper_trade());
+    if let Some(addr) = &options.metrics_addr {
+        spawn_metrics_server(addr, metrics.clone())?;
+    }
+
+    println!(
+        "Paper trading {} on {} via {} (session {:?})",
+        spec.strategy_name(),
+        session.symbols.join(","),
+        session.provider,
+        options.session_dir
+    );
+
+    let mut poll = 0;
+    loop {
+        poll += 1;
+        let request = FetchRequest {
+            symbols: session.symbols.clone(),
+            start: engine
+                .last_timestamp()
+                .map(|t| t + 1)
+                .unwrap_or(session.start),
+            end: chrono::Utc::now().timestamp(),
+            timeframe: session.timeframe.clone(),
+            adjustment_policy: session.adjustment_policy.clone(),
+        };
+
+        match fetch_new_bars(&provider, &request, engine.last_timestamp()) {
+            Ok(bars) => {
+                let started = Instant::now();
+                let orders_before = engine.orders_submitted();
+                let mut new_fills = Vec::new();
+                for bar in &bars {
+                    for fill in engine.on_bar(bar)? {
+                        print_fill(&fill);
+                        new_fills.push(fill);
+                    }
+                }
+                metrics.record_events(bars.len(), started.elapsed());
+                metrics.record_orders(engine.orders_submitted() - orders_before);
+                metrics.record_fills(new_fills.len());
+                session.bars.extend(bars.iter().cloned());
+
+                if let (Some(repo), false) = (repo.as_mut(), new_fills.is_empty()) {
+                    let started = Instant::now();
+                    let hash = commit_trace(repo, &session, &engine, &new_fills, options)?;
+                    metrics.record_commit(started.elapsed());
+                    session.last_trace = Some(hash);
+                }
+                save_session(&session, &engine, &options.session_dir)?;
+                println!(
+                    "Poll {}: {} new bar(s), {} new fill(s), equity ${:.2}",
+                    poll,
+                    bars.len(),
+                    new_fills.len(),
+                    engine.portfolio().equity
+                );
+            }
+            // Transient feed errors should not end a long-running session
+            Err(err) => eprintln!("Poll {}: fetch failed: {:#}", poll, err),
+        }
+
+        if options.max_polls.is_some_and(|max| poll >= max) {
+            break;
+        }
+        std::thread::sleep(options.poll_interval);
+    }
+
+    Ok(session)
+}
+
+fn new_engine(spec: &BacktestSpec) -> Result<PaperEngine> {
+    let engine = LiveEngine::new(
+        build_strategy(&spec.strategy)?,
+        build_broker(spec, &TradingHalts::new()),
+        spec.initial_cash,
+    );
+    Ok(match spec.warmup_bars {
+        Some(bars) => engine.with_warmup(bars),
+        None => engine,
+    })
+}
+
+/// Fetch, normalize and order bars newer than the last processed timestamp
+fn fetch_new_bars(
+    provider: &RegisteredProvider,
+    request: &FetchRequest,
+    last_timestamp: Option<i64>,
+) -> Result<Vec<Bar>> {
+    let records = provider.source.fetch(request)?;
+    let mut events = provider.adapter.normalize_batch(records, None)?.events;
+    sort_events_deterministically(&mut events);
+    Ok(bars_from_events(&events)
+        .into_iter()
+        .filter(|bar| last_timestamp.is_none_or(|last| bar.timestamp > last))
+        .collect())
+}
+
+fn print_fill(fill: &Fill) {
+    println!(
+        "  [fill] {} {:?} {:.4} {} @ {:.4} (costs {:.2})",
+        fill.timestamp,
+        fill.side,
+        fill.quantity,
+        fill.symbol,
+        fill.price,
+        fill.costs.charged()
+    );
+}
+
+/// Record a poll's simulated fills as a live-session Trace chained to the previous one
+fn commit_trace(
+    repo: &mut Repository,
+    session: &PaperSession,
+    engine: &PaperEngine,
+    new_fills: &[Fill],
+    options: &PaperTradeOptions,
+) -> Result<String> {
+    let portfolio = engine.portfolio();
+    let trace = Artifact::Trace(Trace {
+        operation: "paper_trade".to_string(),
+        inputs: vec![session.spec_hash.clone()],
+        output: options.session_dir.display().to_string(),
+        timestamp: engine.last_timestamp().unwrap_or(session.start),
+        metadata: serde_json::json!({
+            "session": "live",
+            "provider": session.provider,
+            "symbols": session.symbols,
+            "bars_processed": engine.bars_processed(),
+            "fills": new_fills,
+            "total_fills": engine.fills(),
+            "cash": portfolio.cash,
+            "equity": portfolio.equity,
+        }),
+    });
+    let parents = session.last_trace.clone().into_iter().collect();
+    let hash = repo
+        .commit(&trace, "Paper session fills", parents)
+        .context("Failed to commit paper session trace")?;
+    Ok(hash.as_hex().to_string())
+}
+
+fn save_session(session: &PaperSession, engine: &PaperEngine, session_dir: &Path) -> Result<()> {
+    // Write then rename so an interrupted save never corrupts the session
+    let tmp = session_dir.join("session.json.tmp");
+    serde_json::to_writer(fs::File::create(&tmp)?, session)?;
+    fs::rename(&tmp, session_dir.join("session.json"))?;
+
+    engine::output::write_trades_csv(engine.fills(), &session_dir.join("trades.csv"))?;
+    engine::output::write_order_events_jsonl(
+        engine.order_events(),
+        &session_dir.join("order_events.jsonl"),
+    )?;
+    engine::output::write_equity_points_csv(
+        engine.equity_points(),
+        &session_dir.join("equity_curve.csv"),
+    )?;
+    engine::output::write_exposure_csv(
+        engine.exposure_history(),
+        &session_dir.join("exposure.csv"),
+    )?;
+    engine::output::write_round_trips_csv(
+        engine.round_trips(),
+        &session_dir.join("trades-roundtrip.csv"),
+    )?;
+    let stats = engine::output::calculate_stats(
+        engine.equity_history(),
+        engine.fills(),
+        engine.costs(),
+        session.spec.metrics.annualization(),
+    );
+    engine::output::write_stats_json(&stats, &session_dir.join("stats.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::ProviderRecord;
+
+    const DAY: i64 = 86_400;
+
+    fn write_records(path: &Path, days: std::ops::Range<i64>) {
+        let lines: Vec<String> = days
+            .map(|i| {
+                let close = 100.0 + (i % 7) as f64;
+                serde_json::to_string(&ProviderRecord {
+                    symbol: "AAPL".to_string(),
+                    event_time: i * DAY,
+                    ingest_time: i * DAY,
+                    raw_payload: serde_json::json!({
+                        "o": close, "h": close + 1.0, "l": close - 1.0, "c": close, "v": 1000
+                    }),
+                    quality_flags: vec![],
+                })
+                .unwrap()
+            })
+            .collect();
+        fs::write(path, lines.join("\n")).unwrap();
+    }
+
+    #[test]
+    fn session_resumes_and_logs_fills_to_hipcortex() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec_path = dir.path().join("spec.json");
+        fs::write(
+            &spec_path,
+            serde_json::json!({
+                "initial_cash": 10_000.0,
+                "seed": 1,
+                "strategy": {"type": "sma_crossover", "symbol": "AAPL", "fast": 2, "slow": 4},
+                "cost_model": {"type": "zero"}
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let records = dir.path().join("records.jsonl");
+        write_records(&records, 1..20);
+
+        let options = PaperTradeOptions {
+            provider: "records".to_string(),
+            provider_options: ProviderOptions {
+                endpoint: Some(records.display().to_string()),
+            },
+            symbols: None,
+            timeframe: "1Day".to_string(),
+            adjustment_policy: "unadjusted".to_string(),
+            start: Some(0),
+            poll_interval: Duration::ZERO,
+            max_polls: Some(1),
+            session_dir: dir.path().join("session"),
+            repo: Some(dir.path().join("repo")),
+            metrics_addr: None,
+        };
+        let first = run_paper_trade(&spec_path, &[], &options).unwrap();
+        assert_eq!(first.bars.len(), 19);
+        assert!(first.last_trace.is_some());
+
+        // New bars arrive; the resumed session only processes what it has not seen
+        write_records(&records, 1..30);
+        let resumed = run_paper_trade(&spec_path, &[], &options).unwrap();
+        assert_eq!(resumed.bars.len(), 29);
+
+        let mut replay = new_engine(&resumed.spec).unwrap();
+        for bar in &resumed.bars {
+            replay.on_bar(bar).unwrap();
+        }
+        let trades =
+            engine::output::read_trades_csv(&options.session_dir.join("trades.csv")).unwrap();
+        assert_eq!(trades.len(), replay.num_trades());
+
+        let repo = Repository::open(dir.path().join("repo")).unwrap();
+        assert!(!repo.all_commits().unwrap().is_empty());
+
+        // A different spec cannot continue the session
+        let err = run_paper_trade(&spec_path, &["seed=2".to_string()], &options).unwrap_err();
+        assert!(err.to_string().contains("different spec"));
+    }
+}
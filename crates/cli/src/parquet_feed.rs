@@ -0,0 +1,264 @@
+//! Legacy bar parquet read lazily, one row group at a time.
+//!
+//! The footer is read once on open; each chunk then decodes a single row
+//! group, so only that group's bars are ever held in memory. Multi-year
+//! minute or tick files can be replayed through `ParquetDataFeed` without
+//! materializing the whole file; `ChunkedDataFeed` does the same for any
+//! file read a chunk of bars at a time.
+
+use anyhow::{Context, Result};
+use polars::io::parquet::metadata::FileMetadataRef;
+use polars::prelude::*;
+use schema::{Bar, DataFeed};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::columnar::bars_from_frame;
+
+/// A legacy bar parquet file, read row group by row group
+pub struct ParquetBars {
+    file: fs::File,
+    metadata: FileMetadataRef,
+    group: usize,
+    offset: usize,
+}
+
+impl ParquetBars {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open parquet file {:?}", path))?;
+        let metadata = ParquetReader::new(file.try_clone()?)
+            .get_metadata()
+            .with_context(|| format!("Failed to read parquet footer of {:?}", path))?
+            .clone();
+        Ok(Self {
+            file,
+            metadata,
+            group: 0,
+            offset: 0,
+        })
+    }
+
+    /// Bars of the next row group
+    pub fn next_chunk(&mut self) -> Result<Option<Vec<Bar>>> {
+        let Some(group) = self.metadata.row_groups.get(self.group) else {
+            return Ok(None);
+        };
+        let rows = group.num_rows();
+        let mut reader = ParquetReader::new(self.file.try_clone()?);
+        reader.set_metadata(self.metadata.clone());
+        let frame = reader.with_slice(Some((self.offset, rows))).finish()?;
+        self.group += 1;
+        self.offset += rows;
+        bars_from_frame(&frame).map(Some)
+    }
+}
+
+/// A bar file read a chunk of bars at a time
+pub trait BarChunks {
+    /// Bars of the next chunk, or `None` at the end of the file
+    fn next_chunk(&mut self) -> Result<Option<Vec<Bar>>>;
+
+    /// Start again from the first chunk
+    fn rewind(&mut self);
+
+    /// Bars in the whole file
+    fn total_rows(&self) -> usize;
+}
+
+impl BarChunks for ParquetBars {
+    fn next_chunk(&mut self) -> Result<Option<Vec<Bar>>> {
+        ParquetBars::next_chunk(self)
+    }
+
+    fn rewind(&mut self) {
+        self.group = 0;
+        self.offset = 0;
+    }
+
+    fn total_rows(&self) -> usize {
+        self.metadata.num_rows
+    }
+}
+
+impl Iterator for ParquetBars {
+    type Item = Result<Vec<Bar>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_chunk().transpose()
+    }
+}
+
+/// A `DataFeed` over a chunked bar file already in timestamp order, holding
+/// one chunk at a time.
+///
+/// Bars are not sorted, since that would need the whole file; a read error
+/// or a bar earlier than the one before it ends the feed, and `error` says
+/// why.
+pub struct ChunkedDataFeed<C: BarChunks> {
+    path: PathBuf,
+    bars: C,
+    chunk: Vec<Bar>,
+    /// Position of the next bar in `chunk`
+    index: usize,
+    last_timestamp: Option<i64>,
+    error: Option<anyhow::Error>,
+}
+
+/// A `DataFeed` over a legacy bar parquet file, a row group at a time
+pub type ParquetDataFeed = ChunkedDataFeed<ParquetBars>;
+
+impl ParquetDataFeed {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(ChunkedDataFeed::new(path, ParquetBars::open(path)?))
+    }
+}
+
+impl<C: BarChunks> ChunkedDataFeed<C> {
+    /// Feed the bars of `bars`, read from `path`
+    pub fn new(path: &Path, bars: C) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            bars,
+            chunk: Vec::new(),
+            index: 0,
+            last_timestamp: None,
+            error: None,
+        }
+    }
+
+    /// Why the feed stopped early, if it did
+    pub fn error(&self) -> Option<&anyhow::Error> {
+        self.error.as_ref()
+    }
+
+    /// Step to the next bar, checking its order; false at the end of the file
+    fn advance(&mut self) -> Result<bool> {
+        while self.index == self.chunk.len() {
+            match self.bars.next_chunk()? {
+                Some(chunk) => (self.chunk, self.index) = (chunk, 0),
+                None => return Ok(false),
+            }
+        }
+        let bar = &self.chunk[self.index];
+        if let Some(last) = self.last_timestamp.filter(|&last| bar.timestamp < last) {
+            anyhow::bail!(
+                "Bar at {} follows one at {} in {:?}; streamed parquet must be in timestamp order",
+                bar.timestamp,
+                last,
+                self.path
+            );
+        }
+        self.last_timestamp = Some(bar.timestamp);
+        self.index += 1;
+        Ok(true)
+    }
+}
+
+impl<C: BarChunks> DataFeed for ChunkedDataFeed<C> {
+    fn next_bar(&mut self) -> Option<&Bar> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.advance() {
+            Ok(true) => self.chunk.get(self.index - 1),
+            Ok(false) => None,
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.bars.rewind();
+        self.chunk = Vec::new();
+        self.index = 0;
+        self.last_timestamp = None;
+        self.error = None;
+    }
+
+    fn total_bars(&self) -> Option<usize> {
+        Some(self.bars.total_rows())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Write bars as legacy parquet in row groups of `row_group_size`
+    pub(crate) fn write_bars(path: &Path, bars: &[Bar], row_group_size: usize) {
+        let mut df = df!(
+            "timestamp" => bars.iter().map(|b| b.timestamp).collect::<Vec<_>>(),
+            "symbol" => bars.iter().map(|b| b.symbol.as_str()).collect::<Vec<_>>(),
+            "open" => bars.iter().map(|b| b.open).collect::<Vec<_>>(),
+            "high" => bars.iter().map(|b| b.high).collect::<Vec<_>>(),
+            "low" => bars.iter().map(|b| b.low).collect::<Vec<_>>(),
+            "close" => bars.iter().map(|b| b.close).collect::<Vec<_>>(),
+            "volume" => bars.iter().map(|b| b.volume).collect::<Vec<_>>(),
+        )
+        .unwrap();
+        ParquetWriter::new(fs::File::create(path).unwrap())
+            .with_row_group_size(Some(row_group_size))
+            .finish(&mut df)
+            .unwrap();
+    }
+
+    fn bar(timestamp: i64) -> Bar {
+        Bar {
+            timestamp,
+            symbol: if timestamp % 2 == 0 { "A" } else { "B" }.to_string(),
+            open: timestamp as f64,
+            high: timestamp as f64 + 1.0,
+            low: timestamp as f64 - 1.0,
+            close: timestamp as f64 + 0.5,
+            volume: 100.0,
+        }
+    }
+
+    #[test]
+    fn row_groups_stream_the_same_bars_as_a_full_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bars.parquet");
+        let bars: Vec<Bar> = (0..2_500).map(bar).collect();
+        write_bars(&path, &bars, 1_000);
+
+        let chunks: Vec<Vec<Bar>> = ParquetBars::open(&path)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), bars);
+
+        let mut feed = ParquetDataFeed::open(&path).unwrap();
+        assert_eq!(feed.total_bars(), Some(2_500));
+        let mut streamed = Vec::new();
+        while let Some(bar) = feed.next_bar() {
+            streamed.push(bar.clone());
+        }
+        assert_eq!(streamed, bars);
+        feed.reset();
+        assert_eq!(feed.next_bar(), Some(&bars[0]));
+        assert!(feed.error().is_none());
+    }
+
+    #[test]
+    fn out_of_order_bars_end_the_feed_with_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bars.parquet");
+        write_bars(&path, &[bar(1), bar(3), bar(2)], 2);
+
+        let mut feed = ParquetDataFeed::open(&path).unwrap();
+        let mut streamed = 0;
+        while feed.next_bar().is_some() {
+            streamed += 1;
+        }
+        assert_eq!(streamed, 2);
+        assert!(feed
+            .error()
+            .unwrap()
+            .to_string()
+            .contains("timestamp order"));
+    }
+}
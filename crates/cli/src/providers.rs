@@ -0,0 +1,384 @@
+use anyhow::{Context, Result};
+use schema::{LatencyClass, MarketDataAdapter, ProviderRecord, QualityFlag};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+use crate::adapters::OhlcvRecordAdapter;
+
+/// What to fetch from a provider; times are Unix seconds, `end` inclusive
+#[derive(Debug, Clone)]
+pub struct FetchRequest {
+    pub symbols: Vec<String>,
+    pub start: i64,
+    pub end: i64,
+    /// Provider-specific bar timeframe, e.g. `1Day`
+    pub timeframe: String,
+    /// Canonical adjustment policy name (`unadjusted`, `split_adjusted`, ...)
+    pub adjustment_policy: String,
+}
+
+/// Pulls provider-native records for a request
+pub trait RecordSource {
+    fn fetch(&self, request: &FetchRequest) -> Result<Vec<ProviderRecord>>;
+
+    /// Venue class recorded in dataset provenance
+    fn venue_class(&self) -> &str {
+        "unknown"
+    }
+
+    /// Timezone/calendar the provider's timestamps follow
+    fn timezone_calendar(&self) -> &str {
+        "UTC/24x7"
+    }
+
+    fn latency_class(&self) -> LatencyClass {
+        LatencyClass::Unknown
+    }
+}
+
+/// A record source paired with the adapter that normalizes its records
+pub struct RegisteredProvider {
+    pub source: Box<dyn RecordSource>,
+    pub adapter: Box<dyn MarketDataAdapter>,
+}
+
+/// Connection settings shared by providers
+#[derive(Debug, Clone, Default)]
+pub struct ProviderOptions {
+    /// Override the provider's API base URL (or the records file for `records`)
+    pub endpoint: Option<String>,
+}
+
+/// Names accepted by `--provider`
+pub fn provider_names() -> Vec<&'static str> {
+    let mut names = vec!["records"];
+    if cfg!(feature = "http") {
+        names.push("alpaca");
+    }
+    names
+}
+
+/// Look up a provider by name
+pub fn registered_provider(name: &str, options: &ProviderOptions) -> Result<RegisteredProvider> {
+    match name {
+        "records" => {
+            let path = options.endpoint.as_ref().ok_or_else(|| {
+                anyhow::anyhow!("the records provider needs --endpoint <records.jsonl>")
+            })?;
+            Ok(RegisteredProvider {
+                source: Box::new(RecordsFileSource {
+                    path: PathBuf::from(path),
+                }),
+                adapter: Box::new(OhlcvRecordAdapter::new("records")),
+            })
+        }
+        #[cfg(feature = "http")]
+        "alpaca" => Ok(RegisteredProvider {
+            source: Box::new(alpaca::AlpacaSource::from_env(options.endpoint.clone())?),
+            adapter: Box::new(OhlcvRecordAdapter::new("alpaca")),
+        }),
+        other => anyhow::bail!(
+            "Unknown provider '{}'; available: {}",
+            other,
+            provider_names().join(", ")
+        ),
+    }
+}
+
+/// Provider records stored locally as JSON lines, filtered to the request
+struct RecordsFileSource {
+    path: PathBuf,
+}
+
+impl RecordSource for RecordsFileSource {
+    fn fetch(&self, request: &FetchRequest) -> Result<Vec<ProviderRecord>> {
+        let file = std::fs::File::open(&self.path)
+            .with_context(|| format!("Failed to open records file {:?}", self.path))?;
+        let mut records = Vec::new();
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: ProviderRecord = serde_json::from_str(&line)
+                .with_context(|| format!("Invalid record on line {}", i + 1))?;
+            if request.symbols.contains(&record.symbol)
+                && (request.start..=request.end).contains(&record.event_time)
+            {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+}
+
+/// Parse `YYYY-MM-DD` (midnight UTC) or an RFC 3339 timestamp into Unix seconds
+pub fn parse_time(value: &str) -> Result<i64> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc()
+            .timestamp());
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.timestamp())
+        .with_context(|| format!("Invalid time '{}': expected YYYY-MM-DD or RFC 3339", value))
+}
+
+/// Build a record whose ingest time is unknown and therefore derived from its event time
+fn historical_record(
+    symbol: &str,
+    event_time: i64,
+    raw_payload: serde_json::Value,
+) -> ProviderRecord {
+    ProviderRecord {
+        symbol: symbol.to_string(),
+        event_time,
+        ingest_time: event_time,
+        raw_payload,
+        quality_flags: vec![QualityFlag::DerivedValue],
+    }
+}
+
+#[cfg(feature = "http")]
+mod alpaca {
+    use super::*;
+
+    const DEFAULT_ENDPOINT: &str = "https://data.alpaca.markets";
+    const PAGE_LIMIT: usize = 10_000;
+
+    /// Alpaca market data v2 historical bars
+    pub struct AlpacaSource {
+        endpoint: String,
+        key_id: String,
+        secret_key: String,
+    }
+
+    impl AlpacaSource {
+        /// Credentials come from `APCA_API_KEY_ID` and `APCA_API_SECRET_KEY`
+        pub fn from_env(endpoint: Option<String>) -> Result<Self> {
+            let var =
+                |name: &str| std::env::var(name).with_context(|| format!("{} is not set", name));
+            Ok(Self {
+                endpoint: endpoint.unwrap_or_else(|| DEFAULT_ENDPOINT.to_string()),
+                key_id: var("APCA_API_KEY_ID")?,
+                secret_key: var("APCA_API_SECRET_KEY")?,
+            })
+        }
+
+        #[cfg(test)]
+        pub fn new(endpoint: String) -> Self {
+            Self {
+                endpoint,
+                key_id: "key".to_string(),
+                secret_key: "secret".to_string(),
+            }
+        }
+    }
+
+    fn adjustment_param(policy: &str) -> Result<&'static str> {
+        Ok(match policy {
+            "unadjusted" => "raw",
+            "split_adjusted" => "split",
+            "dividend_adjusted" => "dividend",
+            "fully_adjusted" => "all",
+            other => anyhow::bail!(
+                "Alpaca does not support adjustment policy '{}' \
+                 (use unadjusted, split_adjusted, dividend_adjusted or fully_adjusted)",
+                other
+            ),
+        })
+    }
+
+    fn rfc3339(timestamp: i64) -> Result<String> {
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            .ok_or_else(|| anyhow::anyhow!("timestamp {} out of range", timestamp))
+    }
+
+    impl RecordSource for AlpacaSource {
+        fn fetch(&self, request: &FetchRequest) -> Result<Vec<ProviderRecord>> {
+            let url = format!("{}/v2/stocks/bars", self.endpoint.trim_end_matches('/'));
+            let symbols = request.symbols.join(",");
+            let start = rfc3339(request.start)?;
+            let end = rfc3339(request.end)?;
+            let adjustment = adjustment_param(&request.adjustment_policy)?;
+            let limit = PAGE_LIMIT.to_string();
+
+            let mut records = Vec::new();
+            let mut page_token: Option<String> = None;
+            loop {
+                let mut call = ureq::get(&url)
+                    .set("APCA-API-KEY-ID", &self.key_id)
+                    .set("APCA-API-SECRET-KEY", &self.secret_key)
+                    .query("symbols", &symbols)
+                    .query("timeframe", &request.timeframe)
+                    .query("start", &start)
+                    .query("end", &end)
+                    .query("adjustment", adjustment)
+                    .query("limit", &limit);
+                if let Some(token) = &page_token {
+                    call = call.query("page_token", token);
+                }
+                let body: serde_json::Value = call
+                    .call()
+                    .context("Alpaca bars request failed")?
+                    .into_json()
+                    .context("Alpaca returned invalid JSON")?;
+
+                if let Some(bars) = body.get("bars").and_then(|b| b.as_object()) {
+                    for (symbol, symbol_bars) in bars {
+                        for bar in symbol_bars.as_array().into_iter().flatten() {
+                            let t = bar.get("t").and_then(|t| t.as_str()).ok_or_else(|| {
+                                anyhow::anyhow!("Alpaca bar for {} has no timestamp", symbol)
+                            })?;
+                            records.push(historical_record(symbol, parse_time(t)?, bar.clone()));
+                        }
+                    }
+                }
+
+                page_token = body
+                    .get("next_page_token")
+                    .and_then(|t| t.as_str())
+                    .map(String::from);
+                if page_token.is_none() {
+                    break;
+                }
+            }
+            Ok(records)
+        }
+
+        fn venue_class(&self) -> &str {
+            "lit"
+        }
+
+        fn timezone_calendar(&self) -> &str {
+            "America/New_York/XNYS"
+        }
+
+        fn latency_class(&self) -> LatencyClass {
+            LatencyClass::Delayed
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        /// Serve canned JSON bodies, one per connection, returning the request lines seen
+        fn serve(bodies: Vec<&'static str>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let endpoint = format!("http://{}", listener.local_addr().unwrap());
+            let handle = std::thread::spawn(move || {
+                let mut requests = Vec::new();
+                for body in bodies {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut buf = [0u8; 4096];
+                    let n = stream.read(&mut buf).unwrap();
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    requests.push(request.lines().next().unwrap_or_default().to_string());
+                    write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                    .unwrap();
+                }
+                requests
+            });
+            (endpoint, handle)
+        }
+
+        #[test]
+        fn follows_pagination_and_maps_bars_to_records() {
+            let (endpoint, server) = serve(vec![
+                r#"{"bars":{"AAPL":[{"t":"2024-01-02T05:00:00Z","o":187.15,"h":188.44,"l":183.89,"c":185.64,"v":82488700}]},"next_page_token":"abc"}"#,
+                r#"{"bars":{"MSFT":[{"t":"2024-01-02T05:00:00Z","o":373.86,"h":375.9,"l":366.77,"c":370.87,"v":25258600}]},"next_page_token":null}"#,
+            ]);
+            let source = AlpacaSource::new(endpoint);
+            let request = FetchRequest {
+                symbols: vec!["AAPL".to_string(), "MSFT".to_string()],
+                start: parse_time("2024-01-01").unwrap(),
+                end: parse_time("2024-01-31").unwrap(),
+                timeframe: "1Day".to_string(),
+                adjustment_policy: "split_adjusted".to_string(),
+            };
+
+            let records = source.fetch(&request).unwrap();
+            let requests = server.join().unwrap();
+
+            assert_eq!(records.len(), 2);
+            assert_eq!(records[0].symbol, "AAPL");
+            assert_eq!(records[0].event_time, 1_704_171_600);
+            assert!(requests[0].contains("adjustment=split"));
+            assert!(requests[0].contains("symbols=AAPL%2CMSFT"));
+            assert!(requests[1].contains("page_token=abc"));
+
+            let event = OhlcvRecordAdapter::new("alpaca")
+                .normalize_record(records[1].clone())
+                .unwrap();
+            assert_eq!(event.symbol, "MSFT");
+        }
+
+        #[test]
+        fn rejects_unknown_adjustment_policy() {
+            assert!(adjustment_param("survivor_adjusted").is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dates_and_rfc3339() {
+        assert_eq!(parse_time("2024-01-02").unwrap(), 1_704_153_600);
+        assert_eq!(parse_time("2024-01-02T05:00:00Z").unwrap(), 1_704_171_600);
+        assert!(parse_time("01/02/2024").is_err());
+    }
+
+    #[test]
+    fn records_provider_filters_by_symbol_and_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("records.jsonl");
+        let lines: Vec<String> = [("AAPL", 100), ("AAPL", 200), ("MSFT", 150)]
+            .iter()
+            .map(|(symbol, t)| {
+                serde_json::to_string(&historical_record(
+                    symbol,
+                    *t,
+                    serde_json::json!({"o": 1, "h": 1, "l": 1, "c": 1}),
+                ))
+                .unwrap()
+            })
+            .collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let provider = registered_provider(
+            "records",
+            &ProviderOptions {
+                endpoint: Some(path.display().to_string()),
+            },
+        )
+        .unwrap();
+        let records = provider
+            .source
+            .fetch(&FetchRequest {
+                symbols: vec!["AAPL".to_string()],
+                start: 0,
+                end: 150,
+                timeframe: "1Day".to_string(),
+                adjustment_policy: "unadjusted".to_string(),
+            })
+            .unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].event_time, 100);
+        assert!(registered_provider("nope", &ProviderOptions::default()).is_err());
+    }
+}
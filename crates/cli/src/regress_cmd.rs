@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use broker_sim::TradingHalts;
+use engine::golden::{
+    bless, check_suite, CaseResult, CaseStatus, GoldenCase, GoldenSuite, RunHashes,
+};
+use schema::{assess_data_quality, Bar};
+use std::path::Path;
+
+use crate::backtest_cmd::{execute_backtest, load_spec};
+use crate::data::{bars_to_canonical_tier1_events, load_dataset, prepare_bars, LoadedDataset};
+
+/// Rerun a golden suite; with `bless_changes`, record the new hashes instead
+/// of failing. Returns whether every case matched (always true when blessing).
+pub fn run_regress(manifest: &Path, only: &[String], bless_changes: bool) -> Result<bool> {
+    let mut suite = GoldenSuite::load(manifest)?;
+    let root = manifest.parent().unwrap_or(Path::new("."));
+    let results = check_suite(&suite, only, |case| run_case(root, case))?;
+    print_results(&results);
+
+    if bless_changes {
+        let changed: Vec<CaseResult> = results.into_iter().filter(|r| !r.passed()).collect();
+        if changed.is_empty() {
+            println!("\nNothing to bless");
+        } else {
+            bless(&mut suite, &changed);
+            suite.save(manifest)?;
+            println!(
+                "\nBlessed {} case(s) into {:?}; review and commit the manifest",
+                changed.len(),
+                manifest
+            );
+        }
+        return Ok(true);
+    }
+
+    let failed = results.iter().filter(|r| !r.passed()).count();
+    if failed > 0 {
+        println!(
+            "\n{} of {} case(s) do not match their golden hashes; if the change is intended, rerun with --bless",
+            failed,
+            results.len()
+        );
+    }
+    Ok(failed == 0)
+}
+
+/// Backtest one case and hash its outputs
+pub fn run_case(root: &Path, case: &GoldenCase) -> Result<RunHashes> {
+    let spec = load_spec(&root.join(&case.spec), &[])?;
+    let dataset = load_golden_data(&root.join(&case.data), &spec.data_pipeline)?;
+    let outcome = execute_backtest(&spec, &dataset.bars, &dataset.halts, &dataset.quality)?;
+    RunHashes::of(&outcome.fills, &outcome.equity_history, &outcome.stats)
+}
+
+/// Bundled datasets are CSV bars so they stay reviewable; anything
+/// `--data` accepts works too
+fn load_golden_data(
+    path: &Path,
+    pipeline: &crate::spec::DataPipelineSpec,
+) -> Result<LoadedDataset> {
+    if path.extension().is_none_or(|ext| ext != "csv") {
+        return load_dataset(path, pipeline);
+    }
+    let bars = csv::Reader::from_path(path)
+        .with_context(|| format!("Failed to open golden dataset {:?}", path))?
+        .deserialize::<Bar>()
+        .enumerate()
+        .map(|(i, row)| row.with_context(|| format!("Invalid CSV row {} in {:?}", i + 1, path)))
+        .collect::<Result<Vec<_>>>()?;
+    let quality = assess_data_quality(&bars_to_canonical_tier1_events(&bars, "golden"), None);
+    let bars = prepare_bars(bars, pipeline)?;
+    let bar_report = schema::validate_bars(&bars);
+    Ok(LoadedDataset {
+        bars,
+        halts: TradingHalts::new(),
+        quality,
+        bar_report,
+    })
+}
+
+fn print_results(results: &[CaseResult]) {
+    println!("=== Golden Runs ===");
+    for result in results {
+        match &result.status {
+            CaseStatus::Passed => println!("✓ {}", result.name),
+            CaseStatus::Failed { mismatches } => {
+                println!("✗ {}: {} changed", result.name, mismatches.join(", "))
+            }
+            CaseStatus::Unblessed => println!("? {}: no golden hashes yet", result.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The suite bundled with the repository; run `make regress-bless` after
+    /// an intended change in results
+    #[test]
+    fn bundled_golden_suite_matches() {
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("regress/golden.json");
+        let suite = GoldenSuite::load(&manifest).unwrap();
+        let root = manifest.parent().unwrap();
+        let results = check_suite(&suite, &[], |case| run_case(root, case)).unwrap();
+        let failed: Vec<_> = results.iter().filter(|r| !r.passed()).collect();
+        assert!(failed.is_empty(), "{:#?}", failed);
+    }
+}
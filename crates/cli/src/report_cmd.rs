@@ -0,0 +1,606 @@
+use anyhow::{Context, Result};
+use chrono::Datelike;
+use crv_verifier::CRVReport;
+use plotters::prelude::*;
+use schema::{Fill, Side};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::benchmark::{load_benchmark, BenchmarkComparison, SavedBenchmark};
+use crate::run_data::{load_run_dir, RunData};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Return of one calendar month (UTC), as a fraction
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthlyReturn {
+    pub year: i32,
+    pub month: u32,
+    pub ret: f64,
+}
+
+/// Fill-level trade statistics with average-cost realized P&L
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradeSummary {
+    pub fills: usize,
+    pub buys: usize,
+    pub sells: usize,
+    pub symbols: usize,
+    pub notional: f64,
+    pub commission: f64,
+    /// Fills that reduced or closed a position
+    pub closing_trades: usize,
+    pub winning_trades: usize,
+    pub realized_pnl: f64,
+}
+
+impl TradeSummary {
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.closing_trades > 0).then(|| self.winning_trades as f64 / self.closing_trades as f64)
+    }
+}
+
+/// Render the files in an output directory into a self-contained HTML report
+pub fn run_report(out_dir: &Path, report_path: Option<&Path>) -> Result<PathBuf> {
+    let run = load_run_dir(out_dir)
+        .with_context(|| format!("Failed to load run outputs from {:?}", out_dir))?;
+
+    let crv_path = out_dir.join("crv_report.json");
+    let crv = if crv_path.exists() {
+        let file = fs::File::open(&crv_path)?;
+        Some(serde_json::from_reader(file).context("Failed to parse crv_report.json")?)
+    } else {
+        None
+    };
+
+    let benchmark = load_benchmark(out_dir)?;
+    let html = render_report(&run, crv.as_ref(), benchmark.as_ref())?;
+    let path = report_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| out_dir.join("report.html"));
+    fs::write(&path, html).with_context(|| format!("Failed to write report {:?}", path))?;
+    println!("Wrote report to {:?}", path);
+    Ok(path)
+}
+
+/// Build the report HTML; charts are inline SVG so the file has no external assets
+pub fn render_report(
+    run: &RunData,
+    crv: Option<&CRVReport>,
+    benchmark: Option<&SavedBenchmark>,
+) -> Result<String> {
+    let equity = last_per_timestamp(&run.equity_history);
+    let drawdown = drawdown_series(&equity);
+    let monthly = monthly_returns(&equity, run.stats.initial_equity);
+    let trades = trade_summary(&run.fills);
+    let stats = &run.stats;
+
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Backtest report - {label}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>Backtest report</h1>\n<p class=\"muted\">{label}</p>",
+        label = escape_html(&run.label),
+    )?;
+
+    html.push_str("<h2>Summary</h2>\n<table class=\"kv\">\n");
+    for (name, value) in [
+        ("Initial equity", format!("{:.2}", stats.initial_equity)),
+        ("Final equity", format!("{:.2}", stats.final_equity)),
+        (
+            "Total return",
+            format!("{:.2}%", stats.total_return * 100.0),
+        ),
+        ("Sharpe ratio", format!("{:.3}", stats.sharpe_ratio)),
+        (
+            "Sortino ratio",
+            format!("{:.3}", stats.performance.sortino_ratio),
+        ),
+        (
+            "Calmar ratio",
+            format!("{:.3}", stats.performance.calmar_ratio),
+        ),
+        (
+            "Annualized volatility",
+            format!("{:.2}%", stats.performance.annualized_volatility * 100.0),
+        ),
+        (
+            "Max drawdown",
+            format!("{:.2}%", stats.max_drawdown * 100.0),
+        ),
+        ("Trades", stats.num_trades.to_string()),
+        ("Closed trades", stats.performance.closed_trades.to_string()),
+        (
+            "Win rate",
+            format!("{:.2}%", stats.performance.win_rate * 100.0),
+        ),
+        (
+            "Profit factor",
+            format!("{:.3}", stats.performance.profit_factor),
+        ),
+        (
+            "Average trade PnL",
+            format!("{:.2}", stats.performance.average_trade_pnl),
+        ),
+        (
+            "Exposure time",
+            format!("{:.2}%", stats.performance.exposure_time * 100.0),
+        ),
+        ("Total commission", format!("{:.2}", stats.total_commission)),
+    ] {
+        writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, value)?;
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Equity curve</h2>\n");
+    let mut equity_series = vec![("Strategy", equity.clone(), RGBColor(31, 119, 180))];
+    if let Some((comparison, benchmark_equity)) = benchmark {
+        equity_series.push((
+            "Benchmark",
+            last_per_timestamp(benchmark_equity),
+            RGBColor(127, 127, 127),
+        ));
+        render_benchmark(&mut html, comparison)?;
+    }
+    html.push_str(&line_chart_svg(&equity_series, "Equity", false)?);
+    html.push_str("<h2>Drawdown</h2>\n");
+    let drawdown_pct: Vec<(i64, f64)> = drawdown.iter().map(|(t, d)| (*t, d * 100.0)).collect();
+    html.push_str(&line_chart_svg(
+        &[("Drawdown", drawdown_pct, RGBColor(214, 39, 40))],
+        "Drawdown %",
+        true,
+    )?);
+
+    html.push_str("<h2>Monthly returns</h2>\n");
+    render_heatmap(&mut html, &monthly)?;
+
+    html.push_str("<h2>Trade statistics</h2>\n<table class=\"kv\">\n");
+    let win_rate = trades
+        .win_rate()
+        .map(|w| format!("{:.1}%", w * 100.0))
+        .unwrap_or_else(|| "n/a".to_string());
+    for (name, value) in [
+        ("Fills", trades.fills.to_string()),
+        (
+            "Buys / sells",
+            format!("{} / {}", trades.buys, trades.sells),
+        ),
+        ("Symbols traded", trades.symbols.to_string()),
+        ("Notional traded", format!("{:.2}", trades.notional)),
+        ("Commission", format!("{:.2}", trades.commission)),
+        ("Closing trades", trades.closing_trades.to_string()),
+        ("Win rate", win_rate),
+        ("Realized P&amp;L", format!("{:.2}", trades.realized_pnl)),
+    ] {
+        writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, value)?;
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>CRV verification</h2>\n");
+    render_crv(&mut html, crv)?;
+
+    html.push_str("</body>\n</html>\n");
+    Ok(html)
+}
+
+/// Reduce the engine's equity history (a point per fill and per bar) to one value per timestamp.
+///
+/// The portfolio seeds its history with the initial cash at timestamp 0; that
+/// placeholder is dropped so charts start at the first bar.
+pub fn last_per_timestamp(history: &[(i64, f64)]) -> Vec<(i64, f64)> {
+    let mut by_time: BTreeMap<i64, f64> = history.iter().copied().collect();
+    if by_time.len() > 1 {
+        by_time.remove(&0);
+    }
+    by_time.into_iter().collect()
+}
+
+/// Drawdown from the running peak at each point, as a non-positive fraction
+pub fn drawdown_series(equity: &[(i64, f64)]) -> Vec<(i64, f64)> {
+    let mut peak = f64::NEG_INFINITY;
+    equity
+        .iter()
+        .map(|(t, e)| {
+            peak = peak.max(*e);
+            let drawdown = if peak > 0.0 { e / peak - 1.0 } else { 0.0 };
+            (*t, drawdown)
+        })
+        .collect()
+}
+
+/// Month-end to month-end returns; the first month is measured from `initial_equity`
+pub fn monthly_returns(equity: &[(i64, f64)], initial_equity: f64) -> Vec<MonthlyReturn> {
+    let mut month_ends: BTreeMap<(i32, u32), f64> = BTreeMap::new();
+    for (t, e) in equity {
+        if let Some(time) = chrono::DateTime::from_timestamp(*t, 0) {
+            month_ends.insert((time.year(), time.month()), *e);
+        }
+    }
+
+    let mut previous = initial_equity;
+    month_ends
+        .into_iter()
+        .map(|((year, month), end)| {
+            let ret = if previous != 0.0 {
+                end / previous - 1.0
+            } else {
+                0.0
+            };
+            previous = end;
+            MonthlyReturn { year, month, ret }
+        })
+        .collect()
+}
+
+/// Summarize fills, realizing P&L against each symbol's average entry price
+pub fn trade_summary(fills: &[Fill]) -> TradeSummary {
+    let mut summary = TradeSummary::default();
+    let mut symbols = BTreeSet::new();
+    // symbol -> (signed quantity, average price)
+    let mut positions: HashMap<&str, (f64, f64)> = HashMap::new();
+
+    for fill in fills {
+        summary.fills += 1;
+        summary.notional += fill.quantity * fill.price;
+        summary.commission += fill.costs.charged();
+        symbols.insert(fill.symbol.as_str());
+        let signed = match fill.side {
+            Side::Buy => {
+                summary.buys += 1;
+                fill.quantity
+            }
+            Side::Sell => {
+                summary.sells += 1;
+                -fill.quantity
+            }
+        };
+
+        let (quantity, avg_price) = positions.entry(fill.symbol.as_str()).or_default();
+        if *quantity != 0.0 && quantity.signum() != signed.signum() {
+            let closed = signed.abs().min(quantity.abs());
+            let pnl = closed * (fill.price - *avg_price) * quantity.signum() - fill.costs.charged();
+            summary.closing_trades += 1;
+            if pnl > 0.0 {
+                summary.winning_trades += 1;
+            }
+            summary.realized_pnl += pnl;
+
+            let remaining = *quantity + signed;
+            if remaining.signum() != quantity.signum() {
+                // Flipped (or flat): any excess opens a new position at this price
+                *avg_price = fill.price;
+            }
+            *quantity = remaining;
+        } else {
+            let total = *quantity + signed;
+            *avg_price = (*avg_price * quantity.abs() + fill.price * signed.abs()) / total.abs();
+            *quantity = total;
+        }
+    }
+
+    summary.symbols = symbols.len();
+    summary
+}
+
+/// Named points drawn in one color
+type ChartSeries<'a> = (&'a str, Vec<(i64, f64)>, RGBColor);
+
+/// Line chart of one or more series sharing axes; a legend is drawn for overlays
+fn line_chart_svg(series: &[ChartSeries], label: &str, area: bool) -> Result<String> {
+    let points: Vec<(i64, f64)> = series.iter().flat_map(|(_, p, _)| p).copied().collect();
+    if points.is_empty() {
+        return Ok("<p class=\"muted\">No data.</p>\n".to_string());
+    }
+
+    let x_min = points.iter().map(|(t, _)| *t).min().unwrap();
+    let x_max = points.iter().map(|(t, _)| *t).max().unwrap().max(x_min + 1);
+    let (mut y_min, mut y_max) = points
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (_, y)| {
+            (lo.min(*y), hi.max(*y))
+        });
+    if area {
+        y_max = y_max.max(0.0);
+    }
+    let pad = ((y_max - y_min) * 0.05).max(1e-9);
+    y_min -= pad;
+    y_max += pad;
+
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, (960, 320)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(&root)
+            .margin(12)
+            .x_label_area_size(32)
+            .y_label_area_size(80)
+            .build_cartesian_2d(x_min..x_max, y_min..y_max)?;
+        chart
+            .configure_mesh()
+            .x_labels(8)
+            .y_labels(6)
+            .x_label_formatter(&|t| format_date(*t))
+            .y_label_formatter(&|v| format!("{:.2}", v))
+            .y_desc(label)
+            .light_line_style(WHITE.mix(0.0))
+            .draw()?;
+        for (name, points, color) in series {
+            let color = *color;
+            let drawn = if area {
+                chart.draw_series(
+                    AreaSeries::new(points.iter().copied(), 0.0, color.mix(0.3))
+                        .border_style(color),
+                )?
+            } else {
+                chart.draw_series(LineSeries::new(
+                    points.iter().copied(),
+                    color.stroke_width(2),
+                ))?
+            };
+            drawn
+                .label(*name)
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 16, y)], color));
+        }
+        if series.len() > 1 {
+            chart
+                .configure_series_labels()
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK.mix(0.3))
+                .position(SeriesLabelPosition::UpperLeft)
+                .draw()?;
+        }
+        root.present()?;
+    }
+    svg.push('\n');
+    Ok(svg)
+}
+
+fn render_benchmark(html: &mut String, comparison: &BenchmarkComparison) -> Result<()> {
+    let relative = &comparison.relative;
+    writeln!(
+        html,
+        "<p class=\"muted\">Benchmark: buy-and-hold {} ({})</p>\n<table class=\"kv\">",
+        escape_html(&relative.symbol),
+        escape_html(&comparison.source)
+    )?;
+    for (name, value) in [
+        (
+            "Benchmark return",
+            format!("{:.2}%", comparison.stats.total_return * 100.0),
+        ),
+        (
+            "Excess return",
+            format!("{:.2}%", relative.excess_return * 100.0),
+        ),
+        ("Tracking error", format!("{:.4}", relative.tracking_error)),
+        (
+            "Information ratio",
+            format!("{:.3}", relative.information_ratio),
+        ),
+        ("Beta", format!("{:.3}", relative.beta)),
+        ("Alpha", format!("{:.4}", relative.alpha)),
+        ("Correlation", format!("{:.3}", relative.correlation)),
+        ("Up capture", format!("{:.2}%", relative.up_capture * 100.0)),
+        (
+            "Down capture",
+            format!("{:.2}%", relative.down_capture * 100.0),
+        ),
+    ] {
+        writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, value)?;
+    }
+    html.push_str("</table>\n");
+    Ok(())
+}
+
+fn render_heatmap(html: &mut String, monthly: &[MonthlyReturn]) -> Result<()> {
+    if monthly.is_empty() {
+        html.push_str("<p class=\"muted\">No data.</p>\n");
+        return Ok(());
+    }
+
+    let scale = monthly
+        .iter()
+        .map(|m| m.ret.abs())
+        .fold(0.0, f64::max)
+        .max(1e-12);
+    let mut by_year: BTreeMap<i32, [Option<f64>; 12]> = BTreeMap::new();
+    for m in monthly {
+        by_year.entry(m.year).or_default()[m.month as usize - 1] = Some(m.ret);
+    }
+
+    html.push_str("<table class=\"heatmap\">\n<tr><th>Year</th>");
+    for month in MONTHS {
+        write!(html, "<th>{}</th>", month)?;
+    }
+    html.push_str("<th>Year</th></tr>\n");
+    for (year, months) in &by_year {
+        write!(html, "<tr><th>{}</th>", year)?;
+        for ret in months {
+            match ret {
+                Some(ret) => write!(
+                    html,
+                    "<td style=\"background:{}\">{:.2}%</td>",
+                    heat_color(*ret / scale),
+                    ret * 100.0
+                )?,
+                None => html.push_str("<td></td>"),
+            }
+        }
+        let year_return = months.iter().flatten().fold(1.0, |acc, r| acc * (1.0 + r)) - 1.0;
+        writeln!(html, "<td><b>{:.2}%</b></td></tr>", year_return * 100.0)?;
+    }
+    html.push_str("</table>\n");
+    Ok(())
+}
+
+/// Green for gains, red for losses; `intensity` in [-1, 1]
+fn heat_color(intensity: f64) -> String {
+    let alpha = intensity.abs().clamp(0.0, 1.0) * 0.75 + 0.05;
+    if intensity >= 0.0 {
+        format!("rgba(44,160,44,{:.2})", alpha)
+    } else {
+        format!("rgba(214,39,40,{:.2})", alpha)
+    }
+}
+
+fn render_crv(html: &mut String, crv: Option<&CRVReport>) -> Result<()> {
+    let Some(crv) = crv else {
+        html.push_str("<p class=\"muted\">No crv_report.json in the output directory.</p>\n");
+        return Ok(());
+    };
+
+    if crv.passed {
+        html.push_str("<p class=\"pass\">PASSED - no violations</p>\n");
+        return Ok(());
+    }
+
+    writeln!(
+        html,
+        "<p class=\"fail\">FAILED - {} violation(s)</p>",
+        crv.violation_count()
+    )?;
+    html.push_str(
+        "<table>\n<tr><th>Rule</th><th>Severity</th><th>Message</th><th>Evidence</th></tr>\n",
+    );
+    for violation in &crv.violations {
+        writeln!(
+            html,
+            "<tr><td>{:?}</td><td>{:?}</td><td>{}</td><td>{}</td></tr>",
+            violation.rule_id,
+            violation.severity,
+            escape_html(&violation.message),
+            violation
+                .evidence
+                .iter()
+                .map(|e| escape_html(e))
+                .collect::<Vec<_>>()
+                .join("<br>")
+        )?;
+    }
+    html.push_str("</table>\n");
+    Ok(())
+}
+
+fn format_date(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const STYLE: &str = "body{font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;\
+margin:2em auto;max-width:1000px;color:#222}\
+h2{border-bottom:1px solid #ddd;padding-bottom:4px;margin-top:1.6em}\
+table{border-collapse:collapse;font-size:14px}\
+th,td{border:1px solid #ddd;padding:4px 8px;text-align:right}\
+table.kv th{text-align:left;background:#f6f6f6}\
+.heatmap td{min-width:52px}\
+.muted{color:#777}.pass{color:#2ca02c;font-weight:bold}.fail{color:#d62728;font-weight:bold}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::{BacktestStats, CostBreakdown};
+
+    const DAY: i64 = 86_400;
+
+    fn fill(side: Side, quantity: f64, price: f64) -> Fill {
+        Fill {
+            timestamp: 0,
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            price,
+            costs: CostBreakdown::default(),
+        }
+    }
+
+    #[test]
+    fn monthly_returns_chain_month_ends() {
+        // 2024-01-15, 2024-01-31, 2024-02-10
+        let equity = vec![
+            (1_705_276_800, 105.0),
+            (1_706_659_200, 110.0),
+            (1_707_523_200, 99.0),
+        ];
+        let monthly = monthly_returns(&equity, 100.0);
+        assert_eq!(monthly.len(), 2);
+        assert_eq!((monthly[0].year, monthly[0].month), (2024, 1));
+        assert!((monthly[0].ret - 0.10).abs() < 1e-12);
+        assert!((monthly[1].ret + 0.10).abs() < 1e-12);
+
+        let drawdown = drawdown_series(&equity);
+        assert_eq!(drawdown[1].1, 0.0);
+        assert!((drawdown[2].1 + 0.10).abs() < 1e-12);
+    }
+
+    #[test]
+    fn trade_summary_realizes_against_average_cost() {
+        let fills = vec![
+            fill(Side::Buy, 10.0, 100.0),
+            fill(Side::Buy, 10.0, 110.0),
+            fill(Side::Sell, 5.0, 120.0),
+            // Flip to short 5 at 90: closes 15 at a loss
+            fill(Side::Sell, 20.0, 90.0),
+            fill(Side::Buy, 5.0, 80.0),
+        ];
+        let summary = trade_summary(&fills);
+        assert_eq!((summary.buys, summary.sells), (3, 2));
+        assert_eq!(summary.closing_trades, 3);
+        assert_eq!(summary.winning_trades, 2);
+        // +75 - 225 + 50
+        assert!((summary.realized_pnl + 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn report_is_self_contained_html() {
+        let dir = tempfile::tempdir().unwrap();
+        let equity: Vec<(i64, f64)> = (0..90)
+            .map(|i| {
+                (
+                    1_704_067_200 + i * DAY,
+                    100_000.0 + (i as f64).sin() * 500.0,
+                )
+            })
+            .collect();
+        let fills = vec![fill(Side::Buy, 10.0, 100.0), fill(Side::Sell, 10.0, 105.0)];
+        let stats = BacktestStats {
+            initial_equity: 100_000.0,
+            final_equity: equity.last().unwrap().1,
+            total_return: 0.0,
+            num_trades: 2,
+            total_commission: 0.0,
+            costs: CostBreakdown::default(),
+            sharpe_ratio: 0.5,
+            max_drawdown: 0.01,
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
+        };
+        engine::output::write_trades_csv(&fills, &dir.path().join("trades.csv")).unwrap();
+        engine::output::write_equity_curve_csv(&equity, &dir.path().join("equity_curve.csv"))
+            .unwrap();
+        engine::output::write_stats_json(&stats, &dir.path().join("stats.json")).unwrap();
+
+        let path = run_report(dir.path(), None).unwrap();
+        let html = fs::read_to_string(path).unwrap();
+        assert!(html.matches("<svg").count() >= 2);
+        assert!(html.contains("<table class=\"heatmap\">"));
+        assert!(html.contains("Mar"));
+        assert!(html.contains("No crv_report.json"));
+        assert!(!html.contains("src=\"http"));
+    }
+}
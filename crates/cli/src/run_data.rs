@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use hipcortex::{Artifact, ContentHash, Repository};
+use schema::{BacktestStats, DataQualityReport, ExposurePoint, Fill};
+use std::path::Path;
+
+/// Stats, equity curve and trades of one run, from an output directory or HipCortex
+#[derive(Debug, Clone)]
+pub struct RunData {
+    pub label: String,
+    pub stats: BacktestStats,
+    pub equity_history: Vec<(i64, f64)>,
+    pub fills: Vec<Fill>,
+    /// Input data quality, when the run recorded it
+    pub quality: Option<DataQualityReport>,
+    /// Exposure at each equity mark, when the run recorded it
+    pub exposure_history: Option<Vec<ExposurePoint>>,
+}
+
+/// Load a run from an output directory, or from a BacktestResult/CRVReport hash
+pub fn load_run(source: &str, repo: Option<&Repository>) -> Result<RunData> {
+    let path = Path::new(source);
+    if path.is_dir() {
+        return load_run_dir(path);
+    }
+
+    let repo = repo.ok_or_else(|| {
+        anyhow::anyhow!(
+            "'{}' is not a directory; pass --repo to look it up as an artifact hash",
+            source
+        )
+    })?;
+    load_run_hash(source, repo)
+}
+
+/// Load a run from the files `write_outputs` produces
+pub fn load_run_dir(path: &Path) -> Result<RunData> {
+    let quality_path = path.join("data_quality.json");
+    let quality = if quality_path.exists() {
+        let file = std::fs::File::open(&quality_path)?;
+        Some(serde_json::from_reader(file).context("Failed to parse data_quality.json")?)
+    } else {
+        None
+    };
+    let exposure_path = path.join("exposure.csv");
+    let exposure_history = if exposure_path.exists() {
+        Some(engine::output::read_exposure_csv(&exposure_path)?)
+    } else {
+        None
+    };
+
+    Ok(RunData {
+        label: path.display().to_string(),
+        stats: engine::output::read_stats_json(&path.join("stats.json"))?,
+        equity_history: engine::output::read_equity_curve_csv(&path.join("equity_curve.csv"))?,
+        fills: engine::output::read_trades_csv(&path.join("trades.csv"))?,
+        quality,
+        exposure_history,
+    })
+}
+
+/// Load a run from a BacktestResult hash, or a CRVReport hash pointing at one
+pub fn load_run_hash(hash: &str, repo: &Repository) -> Result<RunData> {
+    let mut current = ContentHash::from_hex(hash.to_string());
+    loop {
+        match repo
+            .get(&current)
+            .with_context(|| format!("Failed to load artifact {}", current))?
+        {
+            Artifact::BacktestResult(result) => {
+                return Ok(RunData {
+                    label: hash.to_string(),
+                    stats: result.stats,
+                    equity_history: result
+                        .equity_curve
+                        .iter()
+                        .map(|p| (p.timestamp, p.equity))
+                        .collect(),
+                    fills: result.trades,
+                    quality: None,
+                    exposure_history: None,
+                });
+            }
+            Artifact::CRVReport(crv) => current = ContentHash::from_hex(crv.result_hash),
+            other => anyhow::bail!(
+                "Artifact {} is a {}, expected a backtest result or CRV report",
+                current,
+                other.artifact_type()
+            ),
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::backtest_cmd::{execute_backtest, load_spec, write_effective_spec, write_outputs};
+use crate::data::load_dataset;
+
+/// Sharpe coefficient of variation (std / |mean|) above which results are fragile
+pub const MAX_SHARPE_CV: f64 = 0.5;
+
+/// Spread of a stat across seeds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Distribution {
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    /// Sample statistics; `std` is zero for a single value
+    pub fn from_values(values: &[f64]) -> Self {
+        let n = values.len().max(1) as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let std = if values.len() > 1 {
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+        Self {
+            mean,
+            std,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+/// Result of one seed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedRunRow {
+    pub seed: u64,
+    pub total_return: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub num_trades: usize,
+    pub total_commission: f64,
+    pub crv_passed: bool,
+}
+
+/// Distribution of run statistics across seeds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedRobustnessReport {
+    pub runs: Vec<SeedRunRow>,
+    /// Keyed by stat name (`sharpe_ratio`, `max_drawdown`, ...)
+    pub distribution: BTreeMap<String, Distribution>,
+    pub fragile: bool,
+    pub reasons: Vec<String>,
+}
+
+impl SeedRobustnessReport {
+    pub fn from_runs(runs: Vec<SeedRunRow>) -> Self {
+        let stat = |f: fn(&SeedRunRow) -> f64| {
+            Distribution::from_values(&runs.iter().map(f).collect::<Vec<_>>())
+        };
+        let mut distribution = BTreeMap::new();
+        distribution.insert("total_return".to_string(), stat(|r| r.total_return));
+        distribution.insert("sharpe_ratio".to_string(), stat(|r| r.sharpe_ratio));
+        distribution.insert("max_drawdown".to_string(), stat(|r| r.max_drawdown));
+        distribution.insert("num_trades".to_string(), stat(|r| r.num_trades as f64));
+        distribution.insert("total_commission".to_string(), stat(|r| r.total_commission));
+
+        let mut reasons = Vec::new();
+        let sharpe = &distribution["sharpe_ratio"];
+        if sharpe.min < 0.0 && sharpe.max > 0.0 {
+            reasons.push(format!(
+                "Sharpe changes sign across seeds ({:.3} to {:.3})",
+                sharpe.min, sharpe.max
+            ));
+        }
+        if sharpe.mean.abs() > 1e-9 && sharpe.std / sharpe.mean.abs() > MAX_SHARPE_CV {
+            reasons.push(format!(
+                "Sharpe std {:.3} exceeds {:.0}% of |mean| {:.3}",
+                sharpe.std,
+                MAX_SHARPE_CV * 100.0,
+                sharpe.mean.abs()
+            ));
+        }
+        let passed = runs.iter().filter(|r| r.crv_passed).count();
+        if passed != 0 && passed != runs.len() {
+            reasons.push(format!(
+                "CRV verdict depends on the seed ({} of {} passed)",
+                passed,
+                runs.len()
+            ));
+        }
+
+        Self {
+            runs,
+            distribution,
+            fragile: !reasons.is_empty(),
+            reasons,
+        }
+    }
+}
+
+/// Parse `--seeds`: an inclusive range (`1..30`, `1..=30`) or a comma list (`1,5,9`)
+pub fn parse_seeds(value: &str) -> Result<Vec<u64>> {
+    let value = value.trim();
+    if let Some((start, end)) = value.split_once("..") {
+        let end = end.strip_prefix('=').unwrap_or(end);
+        let start: u64 = start
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid seed range start in '{}'", value))?;
+        let end: u64 = end
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid seed range end in '{}'", value))?;
+        if start > end {
+            anyhow::bail!("Seed range '{}' is empty", value);
+        }
+        return Ok((start..=end).collect());
+    }
+
+    let seeds = value
+        .split(',')
+        .map(|s| {
+            s.trim()
+                .parse()
+                .with_context(|| format!("Invalid seed '{}'", s.trim()))
+        })
+        .collect::<Result<Vec<u64>>>()?;
+    if seeds.is_empty() {
+        anyhow::bail!("No seeds given");
+    }
+    Ok(seeds)
+}
+
+/// Run one spec across many seeds and report how much the results move
+pub fn run_seed_robustness(
+    spec_path: &Path,
+    overrides: &[String],
+    data_path: &Path,
+    out_dir: &Path,
+    seeds: Option<Vec<u64>>,
+    num_seeds: Option<u64>,
+) -> Result<SeedRobustnessReport> {
+    let spec = load_spec(spec_path, overrides)?;
+    // --num-seeds counts up from the spec's own seed
+    let seeds = match (seeds, num_seeds) {
+        (Some(seeds), _) => seeds,
+        (None, Some(n)) if n > 0 => (spec.seed..spec.seed + n).collect(),
+        _ => anyhow::bail!("Seed robustness needs --seeds or a positive --num-seeds"),
+    };
+
+    let dataset = load_dataset(data_path, &spec.data_pipeline)?;
+    fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+    write_effective_spec(&spec, out_dir)?;
+    println!(
+        "Running {} seed(s) over {} bars",
+        seeds.len(),
+        dataset.bars.len()
+    );
+
+    let outcomes = seeds
+        .par_iter()
+        .map(|seed| {
+            let mut seeded = spec.clone();
+            seeded.seed = *seed;
+            execute_backtest(&seeded, &dataset.bars, &dataset.halts, &dataset.quality)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut runs = Vec::with_capacity(seeds.len());
+    for (seed, outcome) in seeds.iter().zip(&outcomes) {
+        let run_dir = out_dir.join("seeds").join(format!("seed_{}", seed));
+        fs::create_dir_all(&run_dir).context("Failed to create seed directory")?;
+        write_outputs(outcome, &run_dir)?;
+        runs.push(SeedRunRow {
+            seed: *seed,
+            total_return: outcome.stats.total_return,
+            sharpe_ratio: outcome.stats.sharpe_ratio,
+            max_drawdown: outcome.stats.max_drawdown,
+            num_trades: outcome.stats.num_trades,
+            total_commission: outcome.stats.total_commission,
+            crv_passed: outcome.crv_report.passed,
+        });
+    }
+
+    let report = SeedRobustnessReport::from_runs(runs);
+    let json_file = fs::File::create(out_dir.join("seed_robustness.json"))?;
+    serde_json::to_writer_pretty(json_file, &report)?;
+    let mut wtr = csv::Writer::from_path(out_dir.join("seed_runs.csv"))?;
+    for row in &report.runs {
+        wtr.serialize(row)?;
+    }
+    wtr.flush()?;
+
+    print_report(&report);
+    Ok(report)
+}
+
+fn print_report(report: &SeedRobustnessReport) {
+    println!("\n=== Seed Robustness ({} seeds) ===", report.runs.len());
+    println!(
+        "{:<18} {:>12} {:>12} {:>12} {:>12}",
+        "stat", "mean", "std", "min", "max"
+    );
+    for (name, d) in &report.distribution {
+        println!(
+            "{:<18} {:>12.4} {:>12.4} {:>12.4} {:>12.4}",
+            name, d.mean, d.std, d.min, d.max
+        );
+    }
+    if report.fragile {
+        println!("\n✗ Results are fragile to the seed:");
+        for reason in &report.reasons {
+            println!("  - {}", reason);
+        }
+    } else {
+        println!("\n✓ Results are stable across seeds");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(seed: u64, sharpe_ratio: f64, crv_passed: bool) -> SeedRunRow {
+        SeedRunRow {
+            seed,
+            total_return: 0.1,
+            sharpe_ratio,
+            max_drawdown: 0.05,
+            num_trades: 10,
+            total_commission: 5.0,
+            crv_passed,
+        }
+    }
+
+    #[test]
+    fn parses_ranges_and_lists() {
+        assert_eq!(parse_seeds("1..3").unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_seeds("1..=3").unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_seeds("4, 8,15").unwrap(), vec![4, 8, 15]);
+        assert!(parse_seeds("5..1").is_err());
+        assert!(parse_seeds("a,b").is_err());
+    }
+
+    #[test]
+    fn flags_fragile_results() {
+        let stable = SeedRobustnessReport::from_runs(vec![
+            row(1, 1.0, true),
+            row(2, 1.1, true),
+            row(3, 0.9, true),
+        ]);
+        assert!(!stable.fragile);
+        let sharpe = &stable.distribution["sharpe_ratio"];
+        assert!((sharpe.mean - 1.0).abs() < 1e-12);
+        assert!((sharpe.std - 0.1).abs() < 1e-12);
+        assert_eq!((sharpe.min, sharpe.max), (0.9, 1.1));
+
+        let fragile = SeedRobustnessReport::from_runs(vec![
+            row(1, 1.0, true),
+            row(2, -0.5, false),
+            row(3, 0.2, true),
+        ]);
+        assert!(fragile.fragile);
+        assert_eq!(fragile.reasons.len(), 3);
+    }
+}
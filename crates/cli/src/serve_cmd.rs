@@ -0,0 +1,640 @@
+use anyhow::{Context, Result};
+use schema::BacktestStats;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Instant;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::backtest_cmd::{
+    execute_and_write, write_analyses, write_effective_spec, BacktestOptions,
+};
+use crate::benchmark::BenchmarkSource;
+use crate::data::load_dataset;
+use crate::metrics::{self, Metrics};
+use crate::spec::{apply_overrides, parse_spec, BacktestSpec};
+
+/// Settings for `quant_engine serve`
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    pub addr: String,
+    /// Datasets, benchmarks, models and modules named in submitted jobs are resolved under this directory
+    pub data_root: PathBuf,
+    /// Each job writes into `<work_dir>/jobs/<id>/`
+    pub work_dir: PathBuf,
+    pub workers: usize,
+    /// Jobs waiting beyond this are rejected with 503
+    pub queue_capacity: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobState {
+    fn is_finished(self) -> bool {
+        matches!(self, JobState::Succeeded | JobState::Failed)
+    }
+}
+
+/// Externally visible state of a submitted job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub id: String,
+    pub state: JobState,
+    pub data: String,
+    pub spec_hash: String,
+    pub submitted_at: i64,
+    pub progress: Vec<String>,
+    pub error: Option<String>,
+    pub stats: Option<BacktestStats>,
+    pub crv_passed: Option<bool>,
+}
+
+/// Body of `POST /jobs`
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobRequest {
+    pub spec: serde_json::Value,
+    /// Dataset path relative to the server's data root
+    pub data: String,
+    /// `PATH=VALUE` overrides, as with `--set`
+    #[serde(default)]
+    pub overrides: Vec<String>,
+}
+
+struct Job {
+    id: String,
+    spec: BacktestSpec,
+    data_path: PathBuf,
+    dir: PathBuf,
+}
+
+struct JobTable {
+    jobs: BTreeMap<String, JobStatus>,
+    next_id: u64,
+}
+
+/// State shared between the HTTP handlers and the workers
+struct Shared {
+    table: Mutex<JobTable>,
+    /// Notified whenever any job's status changes
+    changed: Condvar,
+    queue: SyncSender<Job>,
+    options: ServeOptions,
+    metrics: Metrics,
+}
+
+impl Shared {
+    fn job(&self, id: &str) -> Option<JobStatus> {
+        self.table.lock().unwrap().jobs.get(id).cloned()
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut JobStatus)) {
+        if let Some(status) = self.table.lock().unwrap().jobs.get_mut(id) {
+            f(status);
+        }
+        self.changed.notify_all();
+    }
+
+    fn progress(&self, id: &str, message: String) {
+        self.update(id, |status| status.progress.push(message));
+    }
+
+    fn job_dir(&self, id: &str) -> PathBuf {
+        self.options.work_dir.join("jobs").join(id)
+    }
+}
+
+/// Serve the backtest job API until the process is stopped
+pub fn run_serve(options: ServeOptions) -> Result<()> {
+    let server = Server::http(&options.addr)
+        .map_err(|e| anyhow::anyhow!("Failed to bind {}: {}", options.addr, e))?;
+    println!(
+        "Serving backtest API on http://{} ({} worker(s), queue capacity {})",
+        server.server_addr(),
+        options.workers,
+        options.queue_capacity
+    );
+    serve(Arc::new(server), options)
+}
+
+/// Accept requests on an already-bound server; returns once the server is unblocked
+fn serve(server: Arc<Server>, options: ServeOptions) -> Result<()> {
+    if options.workers == 0 || options.queue_capacity == 0 {
+        anyhow::bail!("--workers and --queue-capacity must be positive");
+    }
+    let jobs_dir = options.work_dir.join("jobs");
+    fs::create_dir_all(&jobs_dir).context("Failed to create job directory")?;
+
+    let (queue, receiver) = mpsc::sync_channel(options.queue_capacity);
+    let shared = Arc::new(Shared {
+        table: Mutex::new(JobTable {
+            jobs: BTreeMap::new(),
+            next_id: next_job_number(&jobs_dir)?,
+        }),
+        changed: Condvar::new(),
+        queue,
+        options,
+        metrics: Metrics::serve(),
+    });
+
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..shared.options.workers {
+        let shared = shared.clone();
+        let receiver = receiver.clone();
+        thread::spawn(move || worker(&shared, &receiver));
+    }
+
+    for request in server.incoming_requests() {
+        let shared = shared.clone();
+        thread::spawn(move || handle(request, &shared));
+    }
+    Ok(())
+}
+
+/// Continue numbering after jobs left by a previous server in the same work dir
+fn next_job_number(jobs_dir: &Path) -> Result<u64> {
+    let mut next = 1;
+    for entry in fs::read_dir(jobs_dir)? {
+        let name = entry?.file_name();
+        if let Some(n) = name
+            .to_str()
+            .and_then(|n| n.strip_prefix("job-"))
+            .and_then(|n| n.parse::<u64>().ok())
+        {
+            next = next.max(n + 1);
+        }
+    }
+    Ok(next)
+}
+
+fn worker(shared: &Shared, receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // Hold the lock only while waiting so other workers can pick up jobs
+        let job = match receiver.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        shared.update(&job.id, |status| status.state = JobState::Running);
+
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run_job(shared, &job)))
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("Job panicked")));
+
+        shared.update(&job.id, |status| match result {
+            Ok((stats, crv_passed)) => {
+                status.progress.push("Completed".to_string());
+                status.state = JobState::Succeeded;
+                status.stats = Some(stats);
+                status.crv_passed = Some(crv_passed);
+            }
+            Err(err) => {
+                status.progress.push("Failed".to_string());
+                status.state = JobState::Failed;
+                status.error = Some(format!("{:#}", err));
+            }
+        });
+    }
+}
+
+fn run_job(shared: &Shared, job: &Job) -> Result<(BacktestStats, bool)> {
+    shared.progress(&job.id, format!("Loading data from {:?}", job.data_path));
+    let dataset = load_dataset(&job.data_path, &job.spec.data_pipeline)?;
+    let quality_file = fs::File::create(job.dir.join("data_quality.json"))?;
+    serde_json::to_writer_pretty(quality_file, &dataset.quality)?;
+
+    shared.progress(
+        &job.id,
+        format!(
+            "Running {} over {} bars",
+            job.spec.strategy_name(),
+            dataset.bars.len()
+        ),
+    );
+    // The same run and outputs as `quant_engine backtest`, benchmark included
+    let started = Instant::now();
+    let (outcome, _) =
+        execute_and_write(&job.spec, &dataset, &job.dir, &BacktestOptions::default())?;
+    shared
+        .metrics
+        .record_events(dataset.bars.len(), started.elapsed());
+    shared.metrics.record_fills(outcome.fills.len());
+
+    shared.progress(&job.id, "Writing results".to_string());
+    write_analyses(&job.spec, &outcome, &job.dir)?;
+    Ok((outcome.stats, outcome.crv_report.passed))
+}
+
+fn handle(request: Request, shared: &Arc<Shared>) {
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    let response = match (request.method(), segments.as_slice()) {
+        (Method::Get, ["health"]) => json(200, &serde_json::json!({"status": "ok"})),
+        (Method::Get, ["metrics"]) => {
+            let queued = {
+                let table = shared.table.lock().unwrap();
+                table
+                    .jobs
+                    .values()
+                    .filter(|status| status.state == JobState::Queued)
+                    .count()
+            };
+            shared.metrics.set_queue_depth(queued);
+            Response::from_string(shared.metrics.render()).with_header(metrics::content_type())
+        }
+        (Method::Post, ["jobs"]) => {
+            let mut request = request;
+            let response = submit(&mut request, shared);
+            let _ = request.respond(response);
+            return;
+        }
+        (Method::Get, ["jobs"]) => {
+            let table = shared.table.lock().unwrap();
+            json(200, &table.jobs.values().collect::<Vec<_>>())
+        }
+        (Method::Get, ["jobs", id]) => match shared.job(id) {
+            Some(status) => json(200, &status),
+            None => not_found(id),
+        },
+        (Method::Get, ["jobs", id, "events"]) => {
+            if shared.job(id).is_none() {
+                let _ = request.respond(not_found(id));
+            } else {
+                let id = id.to_string();
+                let _ = stream_events(request.into_writer(), shared, &id);
+            }
+            return;
+        }
+        (Method::Get, ["jobs", id, "results"]) => results(shared, id),
+        (Method::Get, ["jobs", id, "crv"]) => job_file(shared, id, "crv_report.json"),
+        (Method::Get, ["jobs", id, "files", name]) => job_file(shared, id, name),
+        _ => error(404, format!("No route for {} {}", request.method(), path)),
+    };
+    let _ = request.respond(response);
+}
+
+/// Validate a submitted spec and enqueue it
+fn submit(request: &mut Request, shared: &Shared) -> Response<Cursor<Vec<u8>>> {
+    let mut body = String::new();
+    if let Err(err) = request.as_reader().read_to_string(&mut body) {
+        return error(400, format!("Failed to read request body: {}", err));
+    }
+    let job_request: JobRequest = match serde_json::from_str(&body) {
+        Ok(r) => r,
+        Err(err) => return error(400, format!("Invalid job request: {}", err)),
+    };
+    let mut spec = {
+        let mut value = job_request.spec;
+        match apply_overrides(&mut value, &job_request.overrides).and_then(|_| parse_spec(value)) {
+            Ok(spec) => spec,
+            Err(err) => return error(400, format!("{:#}", err)),
+        }
+    };
+    // Models and modules load from the data root too, never elsewhere on the server
+    for path in spec.strategy.file_paths_mut() {
+        match resolve_under_root(&shared.options.data_root, "strategy file", path) {
+            Ok(resolved) => *path = resolved.to_string_lossy().into_owned(),
+            Err(err) => return error(400, format!("{:#}", err)),
+        }
+    }
+    if let Some(BenchmarkSource::Data(path)) = spec.benchmark.as_deref().map(BenchmarkSource::parse)
+    {
+        match resolve_under_root(&shared.options.data_root, "benchmark data", &path) {
+            Ok(resolved) => spec.benchmark = Some(resolved.to_string_lossy().into_owned()),
+            Err(err) => return error(400, format!("{:#}", err)),
+        }
+    }
+    let data_path = match resolve_under_root(&shared.options.data_root, "data", &job_request.data) {
+        Ok(path) => path,
+        Err(err) => return error(400, format!("{:#}", err)),
+    };
+
+    // Hold the table lock across enqueueing so a worker never sees an unknown job
+    let mut table = shared.table.lock().unwrap();
+    let id = format!("job-{:06}", table.next_id);
+    let dir = shared.job_dir(&id);
+    let spec_hash = match fs::create_dir_all(&dir)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| write_effective_spec(&spec, &dir))
+    {
+        Ok(hash) => hash,
+        Err(err) => return error(500, format!("Failed to create job directory: {:#}", err)),
+    };
+
+    let job = Job {
+        id: id.clone(),
+        spec,
+        data_path,
+        dir: dir.clone(),
+    };
+    match shared.queue.try_send(job) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => {
+            let _ = fs::remove_dir_all(&dir);
+            return error(503, "Job queue is full; retry later".to_string());
+        }
+        Err(TrySendError::Disconnected(_)) => {
+            let _ = fs::remove_dir_all(&dir);
+            return error(503, "Server is shutting down".to_string());
+        }
+    }
+    table.next_id += 1;
+
+    let status = JobStatus {
+        id: id.clone(),
+        state: JobState::Queued,
+        data: job_request.data,
+        spec_hash,
+        submitted_at: chrono::Utc::now().timestamp(),
+        progress: vec!["Queued".to_string()],
+        error: None,
+        stats: None,
+        crv_passed: None,
+    };
+    table.jobs.insert(id, status.clone());
+    json(202, &status)
+}
+
+/// Resolve a job's dataset or strategy file under the data root, refusing
+/// paths that escape it
+fn resolve_under_root(data_root: &Path, what: &str, path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        anyhow::bail!(
+            "{} must be a relative path inside the data root: '{}'",
+            what,
+            path
+        );
+    }
+    Ok(data_root.join(relative))
+}
+
+fn results(shared: &Shared, id: &str) -> Response<Cursor<Vec<u8>>> {
+    let Some(status) = shared.job(id) else {
+        return not_found(id);
+    };
+    if !status.state.is_finished() {
+        return error(409, format!("Job {} is still {:?}", id, status.state));
+    }
+    let mut files: Vec<String> = fs::read_dir(shared.job_dir(id))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok()?.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    json(
+        200,
+        &serde_json::json!({
+            "id": status.id,
+            "state": status.state,
+            "error": status.error,
+            "stats": status.stats,
+            "crv_passed": status.crv_passed,
+            "files": files,
+        }),
+    )
+}
+
+/// Return one output file of a finished job; only plain file names are served
+fn job_file(shared: &Shared, id: &str, name: &str) -> Response<Cursor<Vec<u8>>> {
+    let Some(status) = shared.job(id) else {
+        return not_found(id);
+    };
+    if !status.state.is_finished() {
+        return error(409, format!("Job {} is still {:?}", id, status.state));
+    }
+    if name.starts_with('.') || name.contains(['/', '\\']) {
+        return error(400, format!("Invalid file name '{}'", name));
+    }
+    match fs::read(shared.job_dir(id).join(name)) {
+        Ok(bytes) => {
+            let content_type = match Path::new(name).extension().and_then(|e| e.to_str()) {
+                Some("json") => "application/json",
+                Some("csv") => "text/csv",
+                Some("html") => "text/html; charset=utf-8",
+                _ => "application/octet-stream",
+            };
+            Response::from_data(bytes).with_header(header("Content-Type", content_type))
+        }
+        Err(_) => error(404, format!("Job {} has no file '{}'", id, name)),
+    }
+}
+
+/// Stream progress as server-sent events until the job finishes.
+///
+/// Written straight to the socket (rather than as a chunked response body) so
+/// each event is flushed as soon as it happens.
+fn stream_events(mut writer: Box<dyn Write + Send>, shared: &Shared, id: &str) -> Result<()> {
+    writer.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+    )?;
+    writer.flush()?;
+
+    let mut sent = 0;
+    loop {
+        let status = {
+            let mut table = shared.table.lock().unwrap();
+            loop {
+                let status = &table.jobs[id];
+                if status.progress.len() > sent || status.state.is_finished() {
+                    break status.clone();
+                }
+                table = shared.changed.wait(table).unwrap();
+            }
+        };
+        for message in &status.progress[sent..] {
+            write!(writer, "event: progress\ndata: {}\n\n", message)?;
+        }
+        sent = status.progress.len();
+        if status.state.is_finished() {
+            write!(
+                writer,
+                "event: done\ndata: {}\n\n",
+                serde_json::to_string(&status)?
+            )?;
+            writer.flush()?;
+            return Ok(());
+        }
+        writer.flush()?;
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+fn json(status: u16, body: &impl Serialize) -> Response<Cursor<Vec<u8>>> {
+    let body = serde_json::to_vec_pretty(body).unwrap_or_default();
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn error(status: u16, message: String) -> Response<Cursor<Vec<u8>>> {
+    json(status, &serde_json::json!({ "error": message }))
+}
+
+fn not_found(id: &str) -> Response<Cursor<Vec<u8>>> {
+    error(404, format!("Unknown job '{}'", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::*;
+    use std::io::Read;
+    use std::net::TcpStream;
+    use std::time::{Duration, Instant};
+
+    /// Minimal HTTP/1.0 client returning (status, body)
+    fn http(addr: &str, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            addr,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    fn write_bars(path: &Path) {
+        let n = 60;
+        let timestamps: Vec<i64> = (0..n).map(|i| 1_700_000_000 + i * 86_400).collect();
+        let close: Vec<f64> = (0..n).map(|i| 100.0 + (i % 9) as f64).collect();
+        let mut df = df!(
+            "timestamp" => timestamps,
+            "symbol" => vec!["AAPL"; n as usize],
+            "open" => close.clone(),
+            "high" => close.iter().map(|c| c + 1.0).collect::<Vec<_>>(),
+            "low" => close.iter().map(|c| c - 1.0).collect::<Vec<_>>(),
+            "close" => close,
+            "volume" => vec![1000.0; n as usize],
+        )
+        .unwrap();
+        ParquetWriter::new(fs::File::create(path).unwrap())
+            .finish(&mut df)
+            .unwrap();
+    }
+
+    #[test]
+    fn runs_submitted_jobs_and_serves_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_root = dir.path().join("data");
+        fs::create_dir_all(&data_root).unwrap();
+        write_bars(&data_root.join("bars.parquet"));
+
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let addr = server.server_addr().to_ip().unwrap().to_string();
+        let options = ServeOptions {
+            addr: addr.clone(),
+            data_root,
+            work_dir: dir.path().join("work"),
+            workers: 1,
+            queue_capacity: 4,
+        };
+        let handle = {
+            let server = server.clone();
+            thread::spawn(move || serve(server, options))
+        };
+
+        let spec = serde_json::json!({
+            "initial_cash": 10_000.0,
+            "seed": 1,
+            "strategy": {"type": "sma_crossover", "symbol": "AAPL", "fast": 2, "slow": 5},
+            "cost_model": {"type": "zero"},
+            "benchmark": "AAPL"
+        });
+        let body =
+            serde_json::json!({"spec": spec, "data": "bars.parquet", "overrides": ["seed=7"]});
+        let (code, submitted) = http(&addr, "POST", "/jobs", &body.to_string());
+        assert_eq!(code, 202, "{}", submitted);
+        let submitted: JobStatus = serde_json::from_str(&submitted).unwrap();
+        assert_eq!(submitted.id, "job-000001");
+
+        // The event stream ends once the job finishes
+        let (code, events) = http(&addr, "GET", "/jobs/job-000001/events", "");
+        assert_eq!(code, 200);
+        assert!(events.contains("event: progress"));
+        assert!(events.contains("event: done"));
+
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let status = loop {
+            let (_, body) = http(&addr, "GET", "/jobs/job-000001", "");
+            let status: JobStatus = serde_json::from_str(&body).unwrap();
+            if status.state.is_finished() || Instant::now() > deadline {
+                break status;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(status.state, JobState::Succeeded, "{:?}", status.error);
+        assert!(status.stats.unwrap().benchmark.is_some());
+
+        let (code, results) = http(&addr, "GET", "/jobs/job-000001/results", "");
+        assert_eq!(code, 200);
+        assert!(results.contains("trades.csv") && results.contains("benchmark.json"));
+        let (code, crv) = http(&addr, "GET", "/jobs/job-000001/crv", "");
+        assert_eq!(code, 200);
+        assert!(crv.contains("passed"));
+        let (_, effective) = http(
+            &addr,
+            "GET",
+            "/jobs/job-000001/files/effective_spec.json",
+            "",
+        );
+        assert!(effective.contains("\"seed\": 7"));
+
+        let (code, metrics) = http(&addr, "GET", "/metrics", "");
+        assert_eq!(code, 200);
+        assert!(metrics.contains("quant_engine_events_processed_total{mode=\"serve\"} 60\n"));
+        assert!(metrics.contains("quant_engine_job_queue_depth{mode=\"serve\"} 0\n"));
+
+        // Invalid specs and escaping data paths are rejected up front
+        let bad = serde_json::json!({"spec": {"initial_cash": -1.0}, "data": "bars.parquet"});
+        assert_eq!(http(&addr, "POST", "/jobs", &bad.to_string()).0, 400);
+        let escape = serde_json::json!({"spec": spec, "data": "../secret.parquet"});
+        assert_eq!(http(&addr, "POST", "/jobs", &escape.to_string()).0, 400);
+        let plugin = serde_json::json!({
+            "spec": spec,
+            "data": "bars.parquet",
+            "overrides": [r#"strategy={"type": "wasm", "module": "/etc/passwd", "symbols": ["AAPL"]}"#]
+        });
+        let (code, refused) = http(&addr, "POST", "/jobs", &plugin.to_string());
+        assert_eq!(code, 400);
+        assert!(refused.contains("inside the data root"), "{}", refused);
+        let benchmark = serde_json::json!({
+            "spec": spec,
+            "data": "bars.parquet",
+            "overrides": ["benchmark=/etc/spy.parquet"]
+        });
+        assert_eq!(http(&addr, "POST", "/jobs", &benchmark.to_string()).0, 400);
+        assert_eq!(http(&addr, "GET", "/jobs/job-999999", "").0, 404);
+
+        server.unblock();
+        handle.join().unwrap().unwrap();
+    }
+}
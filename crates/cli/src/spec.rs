@@ -1,4 +1,7 @@
+use clap::ValueEnum;
+use schema::MarketAssetClass;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BacktestSpec {
@@ -8,6 +11,50 @@ pub struct BacktestSpec {
     pub cost_model: CostModelSpec,
     #[serde(default)]
     pub data_pipeline: DataPipelineSpec,
+    /// Run the single-symbol strategy once per listed symbol, each with an
+    /// equal share of `initial_cash`, and merge the results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub universe: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub execution: ExecutionSpec,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub metrics: MetricsSpec,
+    /// Symbol in the data, or a separate one-symbol dataset, whose
+    /// buy-and-hold the run's relative stats are measured against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub benchmark: Option<String>,
+    /// Limits enforced on every order before it reaches the broker, and
+    /// verified by CRV in place of the defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PolicySpec>,
+    /// Bar timestamps that only seed the strategy's indicators, in place of
+    /// the warm-up the strategy declares
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warmup_bars: Option<usize>,
+    /// Resample the run's trades into `monte_carlo.json`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monte_carlo: Option<MonteCarloSpec>,
+    /// Flatten the account and stop trading past a drawdown; in a universe
+    /// run, each symbol's share of the account separately
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kill_switch: Option<KillSwitchSpec>,
+}
+
+impl BacktestSpec {
+    /// Limits CRV verifies the run against
+    pub fn policy_constraints(&self) -> crv_verifier::PolicyConstraints {
+        self.policy
+            .as_ref()
+            .map_or_else(Default::default, PolicySpec::constraints)
+    }
+
+    /// Symbols the run trades
+    pub fn symbols(&self) -> Vec<String> {
+        match &self.universe {
+            Some(universe) => universe.clone(),
+            None => self.strategy.symbols(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -16,6 +63,10 @@ pub enum DataPipelineSpec {
     #[default]
     Legacy,
     CanonicalTier1,
+    /// Canonical events released in the order they were ingested, so the
+    /// strategy never sees a bar before its source delivered it; bars
+    /// arriving after a newer bar of their symbol are dropped
+    PointInTime,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +79,692 @@ pub enum StrategySpec {
         vol_target: f64,
         vol_lookback: usize,
     },
+    /// Buy once and hold `allocation` of equity
+    #[serde(rename = "buy_and_hold")]
+    BuyAndHold {
+        symbol: String,
+        #[serde(default = "default_allocation")]
+        allocation: f64,
+    },
+    /// Long while the fast moving average is above the slow one; flat (or
+    /// short, in `long_short` mode) otherwise
+    #[serde(rename = "sma_crossover")]
+    SmaCrossover {
+        symbol: String,
+        fast: usize,
+        slow: usize,
+        #[serde(default = "default_allocation")]
+        allocation: f64,
+        #[serde(default, skip_serializing_if = "is_default")]
+        ma_type: MovingAverage,
+        #[serde(default, skip_serializing_if = "is_default")]
+        mode: Direction,
+    },
+    /// Fade z-score extremes of the close against its rolling mean
+    #[serde(rename = "mean_reversion")]
+    MeanReversion {
+        symbol: String,
+        lookback: usize,
+        entry_z: f64,
+        #[serde(default)]
+        exit_z: f64,
+        #[serde(default = "default_allocation")]
+        allocation: f64,
+    },
+    /// Enter on a close beyond the prior `entry_lookback`-bar high (or low),
+    /// exit on the opposite `exit_lookback`-bar channel or a trailing stop
+    #[serde(rename = "channel_breakout")]
+    ChannelBreakout {
+        symbol: String,
+        entry_lookback: usize,
+        exit_lookback: usize,
+        /// Exit once the close gives back this fraction from the best price
+        /// since entry
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        trailing_stop: Option<f64>,
+        #[serde(default, skip_serializing_if = "is_default")]
+        mode: Direction,
+        #[serde(default = "default_allocation")]
+        allocation: f64,
+    },
+    /// Hold the `top_n` symbols by trailing return, equally weighted
+    #[serde(rename = "cross_sectional_momentum")]
+    CrossSectionalMomentum {
+        symbols: Vec<String>,
+        lookback: usize,
+        top_n: usize,
+        /// Rebalance every N timestamps
+        #[serde(default = "default_rebalance_every")]
+        rebalance_every: usize,
+        #[serde(default = "default_allocation")]
+        allocation: f64,
+    },
+    /// Weight `symbols` inversely to rolling volatility, optionally scaled to
+    /// a target portfolio volatility
+    #[serde(rename = "risk_parity")]
+    RiskParity {
+        symbols: Vec<String>,
+        /// Per-bar returns used to estimate volatility and correlation
+        vol_lookback: usize,
+        /// Per-bar portfolio volatility to scale to, as in `ts_momentum`;
+        /// `allocation` still caps gross exposure
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        vol_target: Option<f64>,
+        /// Rebalance every N timestamps
+        #[serde(default = "default_rebalance_every")]
+        rebalance_every: usize,
+        #[serde(default = "default_allocation")]
+        allocation: f64,
+    },
+    /// Hold fixed weights of equity per symbol (e.g. 60/40), restored on
+    /// calendar boundaries and whenever a weight drifts outside its band
+    #[serde(rename = "fixed_weights")]
+    FixedWeights {
+        /// Target weight per symbol; the remainder stays in cash
+        weights: BTreeMap<String, f64>,
+        #[serde(default)]
+        rebalance: RebalanceFrequency,
+        /// Also rebalance when any weight is further than this from its target
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        drift_band: Option<f64>,
+    },
+    /// Trade z-score extremes of the spread `a - hedge_ratio * b`, with the
+    /// hedge ratio re-estimated by OLS over the lookback window
+    #[serde(rename = "pairs")]
+    Pairs {
+        symbol_a: String,
+        symbol_b: String,
+        lookback: usize,
+        entry_z: f64,
+        #[serde(default)]
+        exit_z: f64,
+        /// Gross exposure of both legs as a fraction of equity
+        #[serde(default = "default_allocation")]
+        allocation: f64,
+    },
+    /// Blend the positions of child strategies by weight
+    #[serde(rename = "ensemble")]
+    Ensemble {
+        members: Vec<EnsembleMember>,
+        #[serde(default)]
+        netting: Netting,
+    },
+    /// Trade child strategies side by side on one account, each on its own
+    /// share of capital and with its own stats (see `engine::multi_strategy`)
+    #[serde(rename = "multi_strategy")]
+    MultiStrategy { sleeves: Vec<SleeveSpec> },
+    /// Trade the signal of an ONNX model fed per-bar indicator features (see
+    /// `onnx_strategy`)
+    #[serde(rename = "onnx")]
+    Onnx {
+        /// Path to the `.onnx` file
+        model: String,
+        symbol: String,
+        /// Model inputs, in order
+        features: Vec<FeatureSpec>,
+        /// Refuse to run unless the model's SHA-256 matches
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model_sha256: Option<String>,
+        /// Long above `threshold`; short below `-threshold` in `long_short` mode
+        #[serde(default)]
+        threshold: f64,
+        #[serde(default, skip_serializing_if = "is_default")]
+        mode: Direction,
+        #[serde(default = "default_allocation")]
+        allocation: f64,
+    },
+    /// User strategy compiled to a sandboxed WASM module (see `wasm_strategy`)
+    #[serde(rename = "wasm")]
+    Wasm {
+        /// Path to the `.wasm` file
+        module: String,
+        symbols: Vec<String>,
+        /// Passed to the module's `init` export, if it has one
+        #[serde(default)]
+        params: serde_json::Value,
+        /// Refuse to run unless the module's SHA-256 matches
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        module_sha256: Option<String>,
+        /// Instruction budget for each `on_bar` call
+        #[serde(default = "default_fuel_per_bar")]
+        fuel_per_bar: u64,
+        #[serde(default = "default_max_memory_mb")]
+        max_memory_mb: u64,
+    },
+}
+
+impl StrategySpec {
+    /// Symbols the strategy trades
+    pub fn symbols(&self) -> Vec<String> {
+        match self {
+            StrategySpec::TsMomentum { symbol, .. }
+            | StrategySpec::BuyAndHold { symbol, .. }
+            | StrategySpec::SmaCrossover { symbol, .. }
+            | StrategySpec::MeanReversion { symbol, .. }
+            | StrategySpec::ChannelBreakout { symbol, .. }
+            | StrategySpec::Onnx { symbol, .. } => vec![symbol.clone()],
+            StrategySpec::Pairs {
+                symbol_a, symbol_b, ..
+            } => vec![symbol_a.clone(), symbol_b.clone()],
+            StrategySpec::CrossSectionalMomentum { symbols, .. }
+            | StrategySpec::RiskParity { symbols, .. }
+            | StrategySpec::Wasm { symbols, .. } => symbols.clone(),
+            StrategySpec::FixedWeights { weights, .. } => weights.keys().cloned().collect(),
+            StrategySpec::Ensemble { members, .. } => {
+                let mut symbols = Vec::new();
+                for symbol in members.iter().flat_map(|m| m.strategy.symbols()) {
+                    if !symbols.contains(&symbol) {
+                        symbols.push(symbol);
+                    }
+                }
+                symbols
+            }
+            StrategySpec::MultiStrategy { sleeves } => {
+                let mut symbols = Vec::new();
+                for symbol in sleeves.iter().flat_map(|s| s.strategy.symbols()) {
+                    if !symbols.contains(&symbol) {
+                        symbols.push(symbol);
+                    }
+                }
+                symbols
+            }
+        }
+    }
+
+    /// Paths of the model and module files the strategy loads, nested
+    /// strategies' included
+    pub fn file_paths_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            StrategySpec::Onnx { model, .. } => vec![model],
+            StrategySpec::Wasm { module, .. } => vec![module],
+            StrategySpec::Ensemble { members, .. } => members
+                .iter_mut()
+                .flat_map(|m| m.strategy.file_paths_mut())
+                .collect(),
+            StrategySpec::MultiStrategy { sleeves } => sleeves
+                .iter_mut()
+                .flat_map(|s| s.strategy.file_paths_mut())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Calendar period after which `fixed_weights` rebalances, in UTC
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebalanceFrequency {
+    #[default]
+    Monthly,
+    Quarterly,
+    Annually,
+}
+
+/// One child of an `ensemble` strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnsembleMember {
+    /// Multiplier on the child's positions; weights need not sum to 1
+    pub weight: f64,
+    pub strategy: StrategySpec,
+}
+
+/// One strategy of a `multi_strategy` and the capital it trades
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SleeveSpec {
+    /// Names the sleeve in `strategies.json`
+    pub name: String,
+    /// Share of `initial_cash`; allocations need not use all of it
+    pub allocation: f64,
+    pub strategy: StrategySpec,
+}
+
+/// One model input, computed each bar from the indicator library
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeatureSpec {
+    /// `close / close[lookback bars ago] - 1`
+    Return { lookback: usize },
+    /// `close / SMA - 1`
+    Sma { window: usize },
+    /// `close / EMA - 1`
+    Ema { window: usize },
+    /// Wilder's RSI, 0 to 100
+    Rsi { period: usize },
+    /// Average true range as a fraction of the close
+    Atr { period: usize },
+    /// MACD histogram as a fraction of the close
+    Macd {
+        fast: usize,
+        slow: usize,
+        signal: usize,
+    },
+    /// Standard deviation of one-bar returns
+    Volatility { window: usize },
+    /// Z-score of the close within its window
+    ZScore { window: usize },
+}
+
+/// How an ensemble turns its children's trades into orders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Netting {
+    /// Trade the weighted sum of the children's positions, so offsetting
+    /// children cancel out
+    #[default]
+    Net,
+    /// Pass on every child order scaled by its weight
+    Gross,
+}
+
+/// Moving average used by `sma_crossover`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovingAverage {
+    #[default]
+    Sma,
+    Ema,
+}
+
+/// Whether a strategy may go short when its signal turns down
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    #[default]
+    LongOnly,
+    LongShort,
+}
+
+/// Leave fields added after a strategy shipped out of its serialized spec
+/// while they hold their default, so existing specs keep their hashes
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+fn default_allocation() -> f64 {
+    1.0
+}
+
+fn default_rebalance_every() -> usize {
+    1
+}
+
+fn default_fuel_per_bar() -> u64 {
+    10_000_000
+}
+
+fn default_max_memory_mb() -> u64 {
+    64
+}
+
+/// How the simulated broker fills orders
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionSpec {
+    /// Largest fraction of a bar's volume filled per bar; larger orders fill
+    /// in parts across the following bars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_participation: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub fill_policy: FillPolicy,
+    /// Bars of the symbol an order waits before reaching the market
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_bars: Option<u32>,
+    /// Milliseconds after its bar an order reaches the market, by bar timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Seeded uniform jitter added to `latency_ms`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_jitter_ms: Option<u64>,
+    /// Reject fills taking gross exposure above this multiple of equity
+    /// (1.0 for a cash account, 2.0 for Reg T margin)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin_multiplier: Option<f64>,
+    /// Steps of a seeded Brownian-bridge path through each bar, along which
+    /// resting orders trigger and fill instead of on the bar's extremes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intrabar_steps: Option<u32>,
+    /// Initial and maintenance margin, liquidating positions on margin calls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin: Option<MarginSpec>,
+    /// Work market orders in slices over `algo_bars` bars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algo: Option<ExecutionAlgo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algo_bars: Option<u32>,
+    /// Symbols that can always be shorted. Setting this or `hard_to_borrow`
+    /// makes short sales of any other symbol fail their locate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub easy_to_borrow: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hard_to_borrow: BTreeMap<String, HardToBorrowSpec>,
+    /// Annual borrow fee of easy-to-borrow symbols
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub borrow_fee_rate: Option<f64>,
+    /// Funding exchanged on perpetual futures positions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding: Option<FundingSpec>,
+    /// Lot and tick sizes orders must trade in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instruments: Option<InstrumentsSpec>,
+    /// What happens to orders while the data marks their symbol halted or
+    /// at a price limit
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub halt_policy: HaltPolicy,
+    /// Interest on cash and fees on shorts, accrued each calendar day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub financing: Option<FinancingSpec>,
+    /// Conversion spreads charged on fills in foreign-currency instruments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fx: Option<FxSpec>,
+    /// Which tax lots a reducing fill closes, and so the PnL it realizes
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub lot_method: LotMethod,
+    /// Whether orders are kept within the cash and shares the account holds
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub cash_mode: CashMode,
+    /// Fraction of each buy's value a strict cash mode holds back for
+    /// commission, fees, slippage and the move to the fill price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cash_reserve: Option<f64>,
+}
+
+/// Currencies of foreign instruments and the spread converting each into
+/// the account's base currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FxSpec {
+    pub base: String,
+    /// Fraction of the converted amount lost to the spread, by currency
+    pub spreads: BTreeMap<String, f64>,
+    /// Currency of each symbol not traded in the base currency
+    pub symbols: BTreeMap<String, String>,
+    /// Which currency foreign symbols' prices are in
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub prices: FxPrices,
+}
+
+/// Currency the data prices foreign symbols in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FxPrices {
+    /// Already converted into the base currency
+    #[default]
+    Base,
+    /// Their own currency, converted at the rates of pair bars in the data:
+    /// `EURUSD` for euros into a dollar base, or its inverse `USDEUR`. The
+    /// broker's margin checks see local prices, so margin is not allowed.
+    Local,
+}
+
+/// Annual financing rates on an actual/360 day count; unset rates are zero
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FinancingSpec {
+    /// Charged on negative cash
+    #[serde(default)]
+    pub debit_rate: f64,
+    /// Credited on cash not held against shorts
+    #[serde(default)]
+    pub credit_rate: f64,
+    /// Charged on the market value of shorts; the broker's borrow fees
+    /// charge the same shorts, so only one of the two may be set
+    #[serde(default)]
+    pub short_borrow_rate: f64,
+}
+
+/// How returns are annualized in the run's stats; unset, daily bars on a
+/// 252-day trading year and a zero risk-free rate
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSpec {
+    /// Bars in a year, for data on a calendar other than 252 trading days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub periods_per_year: Option<f64>,
+    /// Seconds between bars of data traded around the clock, such as
+    /// crypto; 3600 annualizes over 8760 hourly bars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bar_interval: Option<i64>,
+    /// Annual rate subtracted from returns in the Sharpe and Sortino ratios
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub risk_free_rate: f64,
+    /// Bars in each window of `rolling_metrics.csv`, written when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rolling_window: Option<usize>,
+    /// Bootstrap confidence intervals into the stats, seeded by the spec's
+    /// seed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bootstrap: Option<BootstrapSpec>,
+}
+
+/// Resampling of the run's bar returns for confidence intervals
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BootstrapSpec {
+    #[serde(default = "default_samples")]
+    pub samples: usize,
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+    /// Consecutive bar returns drawn together, to keep autocorrelation
+    #[serde(default = "default_block_size")]
+    pub block_size: usize,
+}
+
+fn default_samples() -> usize {
+    1000
+}
+
+fn default_confidence() -> f64 {
+    0.95
+}
+
+impl MetricsSpec {
+    pub fn annualization(&self) -> engine::output::Annualization {
+        let annualization = match (self.periods_per_year, self.bar_interval) {
+            (Some(periods_per_year), _) => engine::output::Annualization {
+                periods_per_year,
+                ..Default::default()
+            },
+            (None, Some(seconds)) => engine::output::Annualization::from_bar_interval(seconds),
+            (None, None) => Default::default(),
+        };
+        annualization.with_risk_free_rate(self.risk_free_rate)
+    }
+}
+
+/// Drawdown past which the engine closes every position and refuses the
+/// strategy's orders for the rest of the run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KillSwitchSpec {
+    /// Fraction of peak equity
+    pub max_drawdown: f64,
+}
+
+/// Block bootstrap of the run's round-trip PnL, seeded by the spec's seed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonteCarloSpec {
+    #[serde(default = "default_simulations")]
+    pub simulations: usize,
+    /// Consecutive trades drawn together, to keep streaks
+    #[serde(default = "default_block_size")]
+    pub block_size: usize,
+    /// Share of initial cash whose loss counts as ruin
+    #[serde(default = "default_ruin_loss")]
+    pub ruin_loss: f64,
+}
+
+fn default_simulations() -> usize {
+    1000
+}
+
+fn default_block_size() -> usize {
+    1
+}
+
+fn default_ruin_loss() -> f64 {
+    0.5
+}
+
+/// Risk limits, each a multiple of equity except `max_drawdown`; unset
+/// limits keep CRV's defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicySpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_drawdown: Option<f64>,
+    /// Gross exposure over equity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_leverage: Option<f64>,
+    /// One position's absolute value over equity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_position_size: Option<f64>,
+    /// Notional traded over a trailing year over equity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turnover: Option<f64>,
+}
+
+impl PolicySpec {
+    pub fn constraints(&self) -> crv_verifier::PolicyConstraints {
+        let defaults = crv_verifier::PolicyConstraints::default();
+        crv_verifier::PolicyConstraints {
+            max_drawdown: self.max_drawdown.or(defaults.max_drawdown),
+            max_leverage: self.max_leverage.or(defaults.max_leverage),
+            max_turnover: self.max_turnover.or(defaults.max_turnover),
+            max_position_size: self.max_position_size.or(defaults.max_position_size),
+        }
+    }
+}
+
+/// Orders blocked by a halt or price limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HaltPolicy {
+    /// Keep working until trading resumes
+    #[default]
+    Queue,
+    Cancel,
+}
+
+/// Order tax lots are closed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LotMethod {
+    Fifo,
+    Lifo,
+    /// Every lot at the position's average price
+    #[default]
+    AverageCost,
+}
+
+/// How orders are held to the account's cash
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CashMode {
+    /// Orders go through as placed, and cash may go negative
+    #[default]
+    Unchecked,
+    /// Buys are downsized, or refused, to the cash on hand less
+    /// `cash_reserve` of their value; cash stays non-negative as long as
+    /// the reserve covers their costs
+    Strict,
+    /// As `Strict`, and sells to the shares held
+    StrictNoShorts,
+}
+
+/// Lot and tick sizes, with overrides by symbol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstrumentsSpec {
+    #[serde(flatten)]
+    pub default: InstrumentRulesSpec,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub rounding: Rounding,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub symbols: BTreeMap<String, InstrumentRulesSpec>,
+}
+
+/// Trading increments of an instrument; absent sizes are unconstrained
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InstrumentRulesSpec {
+    /// Quantities trade in multiples of this (1.0 for whole shares)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lot_size: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_size: Option<f64>,
+}
+
+/// What happens to orders off their lot or tick
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    /// Round quantities down to whole lots and prices to the tick away from
+    /// the market
+    #[default]
+    Snap,
+    Reject,
+}
+
+/// Funding schedule of perpetual futures, such as crypto perpetual swaps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FundingSpec {
+    /// Hours between funding times, counted from midnight UTC
+    pub interval_hours: u32,
+    pub perpetuals: BTreeMap<String, FundingRateSpec>,
+}
+
+/// A perpetual's funding rate per interval: one `rate`, or `rates` that each
+/// apply from a timestamp on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FundingRateSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rates: Vec<FundingRatePoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FundingRatePoint {
+    /// Unix timestamp in seconds the rate applies from
+    pub from: i64,
+    pub rate: f64,
+}
+
+/// Margin requirements, with overrides by symbol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarginSpec {
+    #[serde(flatten)]
+    pub default: MarginRequirementSpec,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub symbols: BTreeMap<String, MarginRequirementSpec>,
+}
+
+/// Margin as fractions of position value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarginRequirementSpec {
+    pub initial: f64,
+    pub maintenance: f64,
+}
+
+/// Terms of a hard-to-borrow symbol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardToBorrowSpec {
+    /// Annual borrow fee
+    pub fee_rate: f64,
+    /// Chance a short order finds shares to borrow
+    pub locate_probability: f64,
+}
+
+/// When market orders fill
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillPolicy {
+    /// At the close of the bar the strategy just saw
+    #[default]
+    Close,
+    /// At the open of the symbol's next bar, avoiding the close lookahead
+    NextOpen,
+}
+
+/// How a sliced order is spread over its bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionAlgo {
+    /// Equal slices per bar
+    Twap,
+    /// Slices in proportion to each bar's volume
+    Vwap,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +780,1997 @@ pub enum CostModelSpec {
         percentage: f64,
         minimum_commission: f64,
     },
+    /// Percentage commission and slippage growing with the order's share of
+    /// bar volume as `impact * participation^exponent` of price
+    #[serde(rename = "volume_participation")]
+    VolumeParticipation {
+        percentage: f64,
+        minimum_commission: f64,
+        impact: f64,
+        exponent: f64,
+    },
+    /// Percentage commission and random slippage drawn from `distribution`,
+    /// seeded by the spec's seed
+    #[serde(rename = "stochastic_slippage")]
+    StochasticSlippage {
+        percentage: f64,
+        minimum_commission: f64,
+        distribution: SlippageDistributionSpec,
+    },
     #[serde(rename = "zero")]
     Zero,
+    /// A cost model per symbol, else per asset class of the symbol as filed
+    /// in `symbol_classes`, else the default
+    #[serde(rename = "composite")]
+    Composite {
+        default: Box<CostModelSpec>,
+        #[serde(default)]
+        asset_classes: BTreeMap<MarketAssetClass, CostModelSpec>,
+        #[serde(default)]
+        symbols: BTreeMap<String, CostModelSpec>,
+        #[serde(default)]
+        symbol_classes: BTreeMap<String, MarketAssetClass>,
+    },
+    /// Another model's commission, fees and slippage times `multiplier`
+    #[serde(rename = "scaled")]
+    Scaled {
+        multiplier: f64,
+        model: Box<CostModelSpec>,
+    },
+}
+
+/// Slippage distribution as a fraction of price
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SlippageDistributionSpec {
+    Uniform { low: f64, high: f64 },
+    Normal { mean: f64, std_dev: f64 },
+    Exponential { mean: f64 },
+}
+
+/// Named broker cost presets for `--cost-preset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CostPreset {
+    /// Interactive Brokers Pro tiered, lowest volume tier: $0.0035/share, $0.35 minimum
+    #[value(name = "ibkr_tiered")]
+    IbkrTiered,
+    /// Alpaca commission-free US equities
+    #[value(name = "alpaca_zero")]
+    AlpacaZero,
+    /// Binance spot base tier: 0.1% of notional
+    #[value(name = "binance_spot")]
+    BinanceSpot,
+}
+
+impl CostPreset {
+    /// The fully-specified cost model the preset stands for
+    pub fn cost_model(self) -> CostModelSpec {
+        match self {
+            CostPreset::IbkrTiered => CostModelSpec::FixedPerShare {
+                cost_per_share: 0.0035,
+                minimum_commission: 0.35,
+            },
+            CostPreset::AlpacaZero => CostModelSpec::Zero,
+            CostPreset::BinanceSpot => CostModelSpec::Percentage {
+                percentage: 0.001,
+                minimum_commission: 0.0,
+            },
+        }
+    }
+}
+
+/// Expand a cost preset into a leading `cost_model` override.
+///
+/// Explicit `--set` overrides come after it, so they can still adjust
+/// individual preset fields (e.g. `cost_model.minimum_commission=1`).
+pub fn with_cost_preset(preset: Option<CostPreset>, overrides: Vec<String>) -> Vec<String> {
+    match preset {
+        Some(preset) => {
+            let cost_model =
+                serde_json::to_string(&preset.cost_model()).expect("cost model specs serialize");
+            std::iter::once(format!("cost_model={}", cost_model))
+                .chain(overrides)
+                .collect()
+        }
+        None => overrides,
+    }
+}
+
+/// Set a dotted field path (e.g. `strategy.lookback`) inside a JSON value.
+///
+/// Intermediate objects must already exist so that typos in a path surface as
+/// errors instead of silently adding unknown fields.
+pub fn set_json_path(
+    root: &mut serde_json::Value,
+    path: &str,
+    value: serde_json::Value,
+) -> anyhow::Result<()> {
+    let mut current = root;
+    let mut segments = path.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        let map = current
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("'{}' does not address an object field", path))?;
+        if segments.peek().is_none() {
+            map.insert(segment.to_string(), value);
+            return Ok(());
+        }
+        current = map
+            .get_mut(segment)
+            .ok_or_else(|| anyhow::anyhow!("unknown field '{}' in path '{}'", segment, path))?;
+    }
+    anyhow::bail!("empty field path")
+}
+
+/// Parse a `--set path=value` override.
+///
+/// The value is read as JSON when possible (`40`, `true`, `{"type": "zero"}`)
+/// and as a plain string otherwise (`symbol=MSFT`).
+pub fn parse_override(arg: &str) -> anyhow::Result<(String, serde_json::Value)> {
+    let (path, raw) = arg
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("override '{}' must have the form path=value", arg))?;
+    let path = path.trim();
+    if path.is_empty() {
+        anyhow::bail!("override '{}' has an empty field path", arg);
+    }
+    let value = serde_json::from_str(raw.trim())
+        .unwrap_or_else(|_| serde_json::Value::String(raw.to_string()));
+    Ok((path.to_string(), value))
+}
+
+/// Apply `--set` overrides to a raw spec document, in order
+pub fn apply_overrides(root: &mut serde_json::Value, overrides: &[String]) -> anyhow::Result<()> {
+    for arg in overrides {
+        let (path, value) = parse_override(arg)?;
+        set_json_path(root, &path, value)
+            .map_err(|e| anyhow::anyhow!("Invalid override '{}': {}", arg, e))?;
+    }
+    Ok(())
+}
+
+/// One problem found in a spec, addressed by its dotted field path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecIssue {
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SpecIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Constraint on a single spec field
+#[derive(Debug, Clone, Copy)]
+enum FieldRule {
+    /// Any finite number
+    Number,
+    Positive,
+    NonNegative,
+    /// In (0, 1]
+    UnitInterval,
+    PositiveInt,
+    NonNegativeInt,
+    NonEmptyString,
+    /// Non-empty array of non-empty strings
+    SymbolList,
+    /// Any JSON object
+    Object,
+    /// One of a fixed set of strings
+    OneOf(&'static [&'static str]),
+    /// A non-empty list of objects
+    ObjectList,
+    /// A non-empty object mapping symbols to weights in (0, 1]
+    WeightMap,
+}
+
+impl FieldRule {
+    fn check(self, value: &serde_json::Value) -> Option<String> {
+        let number = value.as_f64().filter(|v| v.is_finite());
+        let integer = value.as_u64();
+        match self {
+            FieldRule::Number => match number {
+                Some(_) => None,
+                None => Some(format!("must be a number (got {})", value)),
+            },
+            FieldRule::Positive => match number {
+                Some(v) if v > 0.0 => None,
+                _ => Some(format!("must be a number > 0 (got {})", value)),
+            },
+            FieldRule::NonNegative => match number {
+                Some(v) if v >= 0.0 => None,
+                _ => Some(format!("must be a number >= 0 (got {})", value)),
+            },
+            FieldRule::UnitInterval => match number {
+                Some(v) if v > 0.0 && v <= 1.0 => None,
+                _ => Some(format!("must be in (0, 1] (got {})", value)),
+            },
+            FieldRule::PositiveInt => match integer {
+                Some(v) if v >= 1 => None,
+                _ => Some(format!("must be an integer >= 1 (got {})", value)),
+            },
+            FieldRule::NonNegativeInt => match integer {
+                Some(_) => None,
+                None => Some(format!("must be a non-negative integer (got {})", value)),
+            },
+            FieldRule::NonEmptyString => match value.as_str() {
+                Some(s) if !s.trim().is_empty() => None,
+                _ => Some(format!("must be a non-empty string (got {})", value)),
+            },
+            FieldRule::SymbolList => match value.as_array() {
+                Some(items)
+                    if !items.is_empty()
+                        && items
+                            .iter()
+                            .all(|s| s.as_str().is_some_and(|s| !s.trim().is_empty())) =>
+                {
+                    None
+                }
+                _ => Some(format!(
+                    "must be a non-empty list of symbols (got {})",
+                    value
+                )),
+            },
+            FieldRule::Object => match value {
+                serde_json::Value::Object(_) => None,
+                _ => Some(format!("must be an object (got {})", value)),
+            },
+            FieldRule::ObjectList => match value.as_array() {
+                Some(items) if !items.is_empty() && items.iter().all(|i| i.is_object()) => None,
+                _ => Some(format!(
+                    "must be a non-empty list of objects (got {})",
+                    value
+                )),
+            },
+            FieldRule::WeightMap => match value.as_object() {
+                Some(map)
+                    if !map.is_empty()
+                        && map.iter().all(|(symbol, weight)| {
+                            !symbol.trim().is_empty()
+                                && weight.as_f64().is_some_and(|w| w > 0.0 && w <= 1.0)
+                        }) =>
+                {
+                    None
+                }
+                _ => Some(format!(
+                    "must map symbols to weights in (0, 1] (got {})",
+                    value
+                )),
+            },
+            FieldRule::OneOf(options) => match value.as_str() {
+                Some(s) if options.contains(&s) => None,
+                _ => Some(format!(
+                    "must be one of {} (got {})",
+                    options.join(", "),
+                    value
+                )),
+            },
+        }
+    }
+}
+
+/// `(field, rule, required)`
+type FieldTable = &'static [(&'static str, FieldRule, bool)];
+
+const SPEC_FIELDS: FieldTable = &[
+    ("initial_cash", FieldRule::Positive, true),
+    ("seed", FieldRule::NonNegativeInt, true),
+    ("universe", FieldRule::SymbolList, false),
+    ("benchmark", FieldRule::NonEmptyString, false),
+    ("warmup_bars", FieldRule::PositiveInt, false),
+];
+
+const MONTE_CARLO_FIELDS: FieldTable = &[
+    ("simulations", FieldRule::PositiveInt, false),
+    ("block_size", FieldRule::PositiveInt, false),
+    ("ruin_loss", FieldRule::UnitInterval, false),
+];
+
+const KILL_SWITCH_FIELDS: FieldTable = &[("max_drawdown", FieldRule::UnitInterval, true)];
+
+const STRATEGY_TYPES: &[(&str, FieldTable)] = &[
+    (
+        "ts_momentum",
+        &[
+            ("symbol", FieldRule::NonEmptyString, true),
+            ("lookback", FieldRule::PositiveInt, true),
+            ("vol_target", FieldRule::UnitInterval, true),
+            ("vol_lookback", FieldRule::PositiveInt, true),
+        ],
+    ),
+    (
+        "buy_and_hold",
+        &[
+            ("symbol", FieldRule::NonEmptyString, true),
+            ("allocation", FieldRule::UnitInterval, false),
+        ],
+    ),
+    (
+        "sma_crossover",
+        &[
+            ("symbol", FieldRule::NonEmptyString, true),
+            ("fast", FieldRule::PositiveInt, true),
+            ("slow", FieldRule::PositiveInt, true),
+            ("allocation", FieldRule::UnitInterval, false),
+            ("ma_type", FieldRule::OneOf(&["sma", "ema"]), false),
+            (
+                "mode",
+                FieldRule::OneOf(&["long_only", "long_short"]),
+                false,
+            ),
+        ],
+    ),
+    (
+        "mean_reversion",
+        &[
+            ("symbol", FieldRule::NonEmptyString, true),
+            ("lookback", FieldRule::PositiveInt, true),
+            ("entry_z", FieldRule::Positive, true),
+            ("exit_z", FieldRule::NonNegative, false),
+            ("allocation", FieldRule::UnitInterval, false),
+        ],
+    ),
+    (
+        "channel_breakout",
+        &[
+            ("symbol", FieldRule::NonEmptyString, true),
+            ("entry_lookback", FieldRule::PositiveInt, true),
+            ("exit_lookback", FieldRule::PositiveInt, true),
+            ("trailing_stop", FieldRule::UnitInterval, false),
+            (
+                "mode",
+                FieldRule::OneOf(&["long_only", "long_short"]),
+                false,
+            ),
+            ("allocation", FieldRule::UnitInterval, false),
+        ],
+    ),
+    (
+        "cross_sectional_momentum",
+        &[
+            ("symbols", FieldRule::SymbolList, true),
+            ("lookback", FieldRule::PositiveInt, true),
+            ("top_n", FieldRule::PositiveInt, true),
+            ("rebalance_every", FieldRule::PositiveInt, false),
+            ("allocation", FieldRule::UnitInterval, false),
+        ],
+    ),
+    (
+        "risk_parity",
+        &[
+            ("symbols", FieldRule::SymbolList, true),
+            ("vol_lookback", FieldRule::PositiveInt, true),
+            ("vol_target", FieldRule::Positive, false),
+            ("rebalance_every", FieldRule::PositiveInt, false),
+            ("allocation", FieldRule::UnitInterval, false),
+        ],
+    ),
+    (
+        "fixed_weights",
+        &[
+            ("weights", FieldRule::WeightMap, true),
+            (
+                "rebalance",
+                FieldRule::OneOf(&["monthly", "quarterly", "annually"]),
+                false,
+            ),
+            ("drift_band", FieldRule::UnitInterval, false),
+        ],
+    ),
+    (
+        "pairs",
+        &[
+            ("symbol_a", FieldRule::NonEmptyString, true),
+            ("symbol_b", FieldRule::NonEmptyString, true),
+            ("lookback", FieldRule::PositiveInt, true),
+            ("entry_z", FieldRule::Positive, true),
+            ("exit_z", FieldRule::NonNegative, false),
+            ("allocation", FieldRule::UnitInterval, false),
+        ],
+    ),
+    (
+        "ensemble",
+        &[
+            ("members", FieldRule::ObjectList, true),
+            ("netting", FieldRule::OneOf(&["net", "gross"]), false),
+        ],
+    ),
+    (
+        "multi_strategy",
+        &[("sleeves", FieldRule::ObjectList, true)],
+    ),
+    (
+        "onnx",
+        &[
+            ("model", FieldRule::NonEmptyString, true),
+            ("symbol", FieldRule::NonEmptyString, true),
+            ("features", FieldRule::ObjectList, true),
+            ("model_sha256", FieldRule::NonEmptyString, false),
+            ("threshold", FieldRule::NonNegative, false),
+            (
+                "mode",
+                FieldRule::OneOf(&["long_only", "long_short"]),
+                false,
+            ),
+            ("allocation", FieldRule::UnitInterval, false),
+        ],
+    ),
+    (
+        "wasm",
+        &[
+            ("module", FieldRule::NonEmptyString, true),
+            ("symbols", FieldRule::SymbolList, true),
+            ("params", FieldRule::Object, false),
+            ("module_sha256", FieldRule::NonEmptyString, false),
+            ("fuel_per_bar", FieldRule::PositiveInt, false),
+            ("max_memory_mb", FieldRule::PositiveInt, false),
+        ],
+    ),
+];
+
+const FEATURE_TYPES: &[(&str, FieldTable)] = &[
+    ("return", &[("lookback", FieldRule::PositiveInt, true)]),
+    ("sma", &[("window", FieldRule::PositiveInt, true)]),
+    ("ema", &[("window", FieldRule::PositiveInt, true)]),
+    ("rsi", &[("period", FieldRule::PositiveInt, true)]),
+    ("atr", &[("period", FieldRule::PositiveInt, true)]),
+    (
+        "macd",
+        &[
+            ("fast", FieldRule::PositiveInt, true),
+            ("slow", FieldRule::PositiveInt, true),
+            ("signal", FieldRule::PositiveInt, true),
+        ],
+    ),
+    ("volatility", &[("window", FieldRule::PositiveInt, true)]),
+    ("z_score", &[("window", FieldRule::PositiveInt, true)]),
+];
+
+const COST_MODEL_TYPES: &[(&str, FieldTable)] = &[
+    (
+        "fixed_per_share",
+        &[
+            ("cost_per_share", FieldRule::NonNegative, true),
+            ("minimum_commission", FieldRule::NonNegative, true),
+        ],
+    ),
+    (
+        "percentage",
+        &[
+            ("percentage", FieldRule::NonNegative, true),
+            ("minimum_commission", FieldRule::NonNegative, true),
+        ],
+    ),
+    (
+        "volume_participation",
+        &[
+            ("percentage", FieldRule::NonNegative, true),
+            ("minimum_commission", FieldRule::NonNegative, true),
+            ("impact", FieldRule::NonNegative, true),
+            ("exponent", FieldRule::Positive, true),
+        ],
+    ),
+    (
+        "stochastic_slippage",
+        &[
+            ("percentage", FieldRule::NonNegative, true),
+            ("minimum_commission", FieldRule::NonNegative, true),
+            ("distribution", FieldRule::Object, true),
+        ],
+    ),
+    ("zero", &[]),
+    (
+        "composite",
+        &[
+            ("default", FieldRule::Object, true),
+            ("asset_classes", FieldRule::Object, false),
+            ("symbols", FieldRule::Object, false),
+            ("symbol_classes", FieldRule::Object, false),
+        ],
+    ),
+    (
+        "scaled",
+        &[
+            ("multiplier", FieldRule::NonNegative, true),
+            ("model", FieldRule::Object, true),
+        ],
+    ),
+];
+
+const SLIPPAGE_DISTRIBUTIONS: &[(&str, FieldTable)] = &[
+    (
+        "uniform",
+        &[
+            ("low", FieldRule::Number, true),
+            ("high", FieldRule::Number, true),
+        ],
+    ),
+    (
+        "normal",
+        &[
+            ("mean", FieldRule::Number, true),
+            ("std_dev", FieldRule::NonNegative, true),
+        ],
+    ),
+    ("exponential", &[("mean", FieldRule::Positive, true)]),
+];
+
+const ASSET_CLASSES: &[&str] = &["equity", "future", "option", "crypto", "fx", "commodity"];
+
+const EXECUTION_FIELDS: FieldTable = &[
+    ("max_participation", FieldRule::UnitInterval, false),
+    (
+        "fill_policy",
+        FieldRule::OneOf(&["close", "next_open"]),
+        false,
+    ),
+    ("latency_bars", FieldRule::NonNegativeInt, false),
+    ("latency_ms", FieldRule::NonNegativeInt, false),
+    ("latency_jitter_ms", FieldRule::NonNegativeInt, false),
+    ("margin_multiplier", FieldRule::Positive, false),
+    ("algo", FieldRule::OneOf(&["twap", "vwap"]), false),
+    ("algo_bars", FieldRule::PositiveInt, false),
+    ("easy_to_borrow", FieldRule::SymbolList, false),
+    ("borrow_fee_rate", FieldRule::NonNegative, false),
+    ("intrabar_steps", FieldRule::PositiveInt, false),
+    ("halt_policy", FieldRule::OneOf(&["queue", "cancel"]), false),
+    (
+        "lot_method",
+        FieldRule::OneOf(&["fifo", "lifo", "average_cost"]),
+        false,
+    ),
+    (
+        "cash_mode",
+        FieldRule::OneOf(&["unchecked", "strict", "strict_no_shorts"]),
+        false,
+    ),
+    ("cash_reserve", FieldRule::UnitInterval, false),
+];
+
+const MARGIN_FIELDS: FieldTable = &[
+    ("initial", FieldRule::UnitInterval, true),
+    ("maintenance", FieldRule::UnitInterval, true),
+];
+
+const FUNDING_FIELDS: FieldTable = &[("interval_hours", FieldRule::PositiveInt, true)];
+
+const FUNDING_RATE_FIELDS: FieldTable = &[
+    ("rate", FieldRule::Number, false),
+    ("rates", FieldRule::ObjectList, false),
+];
+
+const FUNDING_POINT_FIELDS: FieldTable = &[
+    ("from", FieldRule::NonNegativeInt, true),
+    ("rate", FieldRule::Number, true),
+];
+
+const INSTRUMENTS_FIELDS: FieldTable = &[
+    ("lot_size", FieldRule::Positive, false),
+    ("tick_size", FieldRule::Positive, false),
+    ("rounding", FieldRule::OneOf(&["snap", "reject"]), false),
+];
+
+const INSTRUMENT_FIELDS: FieldTable = &[
+    ("lot_size", FieldRule::Positive, false),
+    ("tick_size", FieldRule::Positive, false),
+];
+
+const FX_FIELDS: FieldTable = &[
+    ("base", FieldRule::NonEmptyString, true),
+    ("prices", FieldRule::OneOf(&["base", "local"]), false),
+];
+
+const FINANCING_FIELDS: FieldTable = &[
+    ("debit_rate", FieldRule::NonNegative, false),
+    ("credit_rate", FieldRule::NonNegative, false),
+    ("short_borrow_rate", FieldRule::NonNegative, false),
+];
+
+const HARD_TO_BORROW_FIELDS: FieldTable = &[
+    ("fee_rate", FieldRule::NonNegative, true),
+    ("locate_probability", FieldRule::UnitInterval, true),
+];
+
+const METRICS_FIELDS: FieldTable = &[
+    ("periods_per_year", FieldRule::Positive, false),
+    ("bar_interval", FieldRule::PositiveInt, false),
+    ("risk_free_rate", FieldRule::Number, false),
+    ("rolling_window", FieldRule::PositiveInt, false),
+];
+
+const BOOTSTRAP_FIELDS: FieldTable = &[
+    ("samples", FieldRule::PositiveInt, false),
+    ("confidence", FieldRule::UnitInterval, false),
+    ("block_size", FieldRule::PositiveInt, false),
+];
+
+const POLICY_FIELDS: FieldTable = &[
+    ("max_drawdown", FieldRule::Positive, false),
+    ("max_leverage", FieldRule::Positive, false),
+    ("max_position_size", FieldRule::Positive, false),
+    ("max_turnover", FieldRule::Positive, false),
+];
+
+const DATA_PIPELINES: &[&str] = &["legacy", "canonical_tier1", "point_in_time"];
+
+/// Check a raw spec document and report every problem at once.
+///
+/// Covers missing and unknown fields, types and value ranges, so a bad spec
+/// fails with field paths rather than the first serde error or a garbage run.
+pub fn validate_spec_value(value: &serde_json::Value) -> Vec<SpecIssue> {
+    let mut issues = Vec::new();
+    let Some(object) = value.as_object() else {
+        issues.push(issue("<root>", "spec must be a JSON object"));
+        return issues;
+    };
+
+    check_fields(
+        object,
+        "",
+        SPEC_FIELDS,
+        &[
+            "strategy",
+            "cost_model",
+            "data_pipeline",
+            "execution",
+            "metrics",
+            "policy",
+            "monte_carlo",
+            "kill_switch",
+        ],
+        &mut issues,
+    );
+    check_tagged(
+        object.get("strategy"),
+        "strategy",
+        STRATEGY_TYPES,
+        &mut issues,
+    );
+    if let Some(strategy) = object.get("strategy") {
+        check_strategy_relations(strategy, "strategy", &mut issues);
+        if object.contains_key("universe") && !has_symbol_field(strategy) {
+            issues.push(issue(
+                "universe",
+                "needs a single-symbol strategy (one with a \"symbol\" field)",
+            ));
+        }
+    }
+    check_cost_model(object.get("cost_model"), "cost_model", &mut issues);
+    if let Some(execution) = object.get("execution") {
+        match execution.as_object() {
+            Some(fields) => {
+                check_fields(
+                    fields,
+                    "execution",
+                    EXECUTION_FIELDS,
+                    &[
+                        "hard_to_borrow",
+                        "margin",
+                        "funding",
+                        "instruments",
+                        "financing",
+                        "fx",
+                    ],
+                    &mut issues,
+                );
+                if let Some(fx) = fields.get("fx") {
+                    check_fx(fx, &mut issues);
+                }
+                if let Some(financing) = fields.get("financing") {
+                    match financing.as_object() {
+                        Some(financing) => check_fields(
+                            financing,
+                            "execution.financing",
+                            FINANCING_FIELDS,
+                            &[],
+                            &mut issues,
+                        ),
+                        None => issues.push(issue(
+                            "execution.financing",
+                            &format!("must be an object (got {})", financing),
+                        )),
+                    }
+                }
+                if let Some(instruments) = fields.get("instruments") {
+                    check_instruments(instruments, &mut issues);
+                }
+                if let Some(funding) = fields.get("funding") {
+                    check_funding(funding, &mut issues);
+                }
+                if let Some(margin) = fields.get("margin") {
+                    check_margin(margin, &mut issues);
+                    if fields.contains_key("margin_multiplier") {
+                        issues.push(issue(
+                            "execution.margin",
+                            "cannot be combined with execution.margin_multiplier",
+                        ));
+                    }
+                }
+                let short_borrow = fields
+                    .get("financing")
+                    .is_some_and(|financing| financing.get("short_borrow_rate").is_some());
+                if short_borrow
+                    && (fields.contains_key("borrow_fee_rate")
+                        || fields.contains_key("hard_to_borrow"))
+                {
+                    issues.push(issue(
+                        "execution.financing.short_borrow_rate",
+                        "cannot be combined with execution.borrow_fee_rate or execution.hard_to_borrow, which already charge borrow fees on shorts",
+                    ));
+                }
+                let local_prices = fields
+                    .get("fx")
+                    .and_then(|fx| fx.get("prices"))
+                    .is_some_and(|prices| prices == "local");
+                if local_prices
+                    && (fields.contains_key("margin") || fields.contains_key("margin_multiplier"))
+                {
+                    issues.push(issue(
+                        "execution.fx.prices",
+                        "local prices cannot be combined with execution.margin or execution.margin_multiplier",
+                    ));
+                }
+                if let Some(hard_to_borrow) = fields.get("hard_to_borrow") {
+                    check_hard_to_borrow(hard_to_borrow, &mut issues);
+                }
+                if fields.contains_key("borrow_fee_rate")
+                    && !fields.contains_key("easy_to_borrow")
+                    && !fields.contains_key("hard_to_borrow")
+                {
+                    issues.push(issue(
+                        "execution.borrow_fee_rate",
+                        "needs execution.easy_to_borrow or execution.hard_to_borrow",
+                    ));
+                }
+                if fields.contains_key("latency_bars") && fields.contains_key("latency_ms") {
+                    issues.push(issue(
+                        "execution.latency_ms",
+                        "cannot be combined with execution.latency_bars",
+                    ));
+                }
+                if fields.contains_key("latency_jitter_ms") && !fields.contains_key("latency_ms") {
+                    issues.push(issue(
+                        "execution.latency_jitter_ms",
+                        "needs execution.latency_ms",
+                    ));
+                }
+                let strict = fields
+                    .get("cash_mode")
+                    .is_some_and(|mode| mode != "unchecked");
+                if fields.contains_key("cash_reserve") && !strict {
+                    issues.push(issue(
+                        "execution.cash_reserve",
+                        "needs execution.cash_mode strict or strict_no_shorts",
+                    ));
+                }
+                if fields.contains_key("algo") != fields.contains_key("algo_bars") {
+                    issues.push(issue(
+                        "execution.algo",
+                        "execution.algo and execution.algo_bars must be set together",
+                    ));
+                }
+            }
+            None => issues.push(issue(
+                "execution",
+                &format!("must be an object (got {})", execution),
+            )),
+        }
+    }
+    if let Some(metrics) = object.get("metrics") {
+        match metrics.as_object() {
+            Some(fields) => {
+                check_fields(
+                    fields,
+                    "metrics",
+                    METRICS_FIELDS,
+                    &["bootstrap"],
+                    &mut issues,
+                );
+                if let Some(bootstrap) = fields.get("bootstrap") {
+                    match bootstrap.as_object() {
+                        Some(bootstrap) => check_fields(
+                            bootstrap,
+                            "metrics.bootstrap",
+                            BOOTSTRAP_FIELDS,
+                            &[],
+                            &mut issues,
+                        ),
+                        None => issues.push(issue(
+                            "metrics.bootstrap",
+                            &format!("must be an object (got {})", bootstrap),
+                        )),
+                    }
+                }
+                if fields.contains_key("periods_per_year") && fields.contains_key("bar_interval") {
+                    issues.push(issue(
+                        "metrics.bar_interval",
+                        "cannot be combined with metrics.periods_per_year",
+                    ));
+                }
+            }
+            None => issues.push(issue(
+                "metrics",
+                &format!("must be an object (got {})", metrics),
+            )),
+        }
+    }
+    if let Some(policy) = object.get("policy") {
+        match policy.as_object() {
+            Some(fields) => check_fields(fields, "policy", POLICY_FIELDS, &[], &mut issues),
+            None => issues.push(issue(
+                "policy",
+                &format!("must be an object (got {})", policy),
+            )),
+        }
+    }
+    if let Some(monte_carlo) = object.get("monte_carlo") {
+        match monte_carlo.as_object() {
+            Some(fields) => {
+                check_fields(fields, "monte_carlo", MONTE_CARLO_FIELDS, &[], &mut issues)
+            }
+            None => issues.push(issue(
+                "monte_carlo",
+                &format!("must be an object (got {})", monte_carlo),
+            )),
+        }
+    }
+    if let Some(kill_switch) = object.get("kill_switch") {
+        match kill_switch.as_object() {
+            Some(fields) => {
+                check_fields(fields, "kill_switch", KILL_SWITCH_FIELDS, &[], &mut issues)
+            }
+            None => issues.push(issue(
+                "kill_switch",
+                &format!("must be an object (got {})", kill_switch),
+            )),
+        }
+    }
+    if let Some(pipeline) = object.get("data_pipeline") {
+        if !pipeline
+            .as_str()
+            .is_some_and(|p| DATA_PIPELINES.contains(&p))
+        {
+            issues.push(issue(
+                "data_pipeline",
+                &format!(
+                    "must be one of {} (got {})",
+                    DATA_PIPELINES.join(", "),
+                    pipeline
+                ),
+            ));
+        }
+    }
+    issues
+}
+
+/// Validate and deserialize a spec document
+pub fn parse_spec(value: serde_json::Value) -> anyhow::Result<BacktestSpec> {
+    let issues = validate_spec_value(&value);
+    if !issues.is_empty() {
+        let list: Vec<String> = issues.iter().map(|i| format!("  - {}", i)).collect();
+        anyhow::bail!(
+            "Invalid spec ({} problem(s)):\n{}",
+            issues.len(),
+            list.join("\n")
+        );
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Whether the strategy type takes one `symbol`, so it can be run per
+/// symbol of a universe
+fn has_symbol_field(strategy: &serde_json::Value) -> bool {
+    let kind = strategy.get("type").and_then(|t| t.as_str());
+    STRATEGY_TYPES
+        .iter()
+        .find(|(name, _)| Some(*name) == kind)
+        .is_some_and(|(_, fields)| fields.iter().any(|(field, ..)| *field == "symbol"))
+}
+
+const ENSEMBLE_MEMBER_FIELDS: FieldTable = &[("weight", FieldRule::Positive, true)];
+
+const SLEEVE_FIELDS: FieldTable = &[
+    ("name", FieldRule::NonEmptyString, true),
+    ("allocation", FieldRule::UnitInterval, true),
+];
+
+/// Constraints between strategy parameters that single-field rules cannot
+/// express, for the strategy at `path`
+fn check_strategy_relations(strategy: &serde_json::Value, path: &str, issues: &mut Vec<SpecIssue>) {
+    let int = |field: &str| strategy.get(field).and_then(|v| v.as_u64());
+    let at = |field: &str| join_path(path, field);
+    match strategy.get("type").and_then(|t| t.as_str()) {
+        Some("sma_crossover") => {
+            if let (Some(fast), Some(slow)) = (int("fast"), int("slow")) {
+                if fast >= slow {
+                    issues.push(issue(
+                        &at("fast"),
+                        &format!("must be less than {} ({} >= {})", at("slow"), fast, slow),
+                    ));
+                }
+            }
+        }
+        Some("mean_reversion") | Some("pairs") => {
+            let entry = strategy.get("entry_z").and_then(|v| v.as_f64());
+            let exit = strategy.get("exit_z").and_then(|v| v.as_f64());
+            if let (Some(entry), Some(exit)) = (entry, exit) {
+                if exit >= entry {
+                    issues.push(issue(
+                        &at("exit_z"),
+                        &format!(
+                            "must be less than {} ({} >= {})",
+                            at("entry_z"),
+                            exit,
+                            entry
+                        ),
+                    ));
+                }
+            }
+            if int("lookback") == Some(1) {
+                issues.push(issue(
+                    &at("lookback"),
+                    "must be at least 2 to estimate a standard deviation",
+                ));
+            }
+            let text = |field: &str| strategy.get(field).and_then(|v| v.as_str());
+            if text("symbol_a").is_some() && text("symbol_a") == text("symbol_b") {
+                issues.push(issue(
+                    &at("symbol_b"),
+                    &format!("must differ from {}", at("symbol_a")),
+                ));
+            }
+        }
+        Some("risk_parity") if int("vol_lookback") == Some(1) => {
+            issues.push(issue(
+                &at("vol_lookback"),
+                "must be at least 2 to estimate a standard deviation",
+            ));
+        }
+        Some("cross_sectional_momentum") => {
+            let count = strategy
+                .get("symbols")
+                .and_then(|v| v.as_array())
+                .map(|s| s.len());
+            if let (Some(top_n), Some(count)) = (int("top_n"), count) {
+                if top_n as usize > count {
+                    issues.push(issue(
+                        &at("top_n"),
+                        &format!(
+                            "must not exceed the number of symbols ({} > {})",
+                            top_n, count
+                        ),
+                    ));
+                }
+            }
+        }
+        Some("fixed_weights") => {
+            let total: f64 = strategy
+                .get("weights")
+                .and_then(|v| v.as_object())
+                .into_iter()
+                .flatten()
+                .filter_map(|(_, w)| w.as_f64())
+                .sum();
+            if total > 1.0 + 1e-9 {
+                issues.push(issue(
+                    &at("weights"),
+                    &format!("must sum to at most 1 (got {})", total),
+                ));
+            }
+        }
+        Some("onnx") => {
+            let features = strategy.get("features").and_then(|v| v.as_array());
+            for (i, feature) in features.into_iter().flatten().enumerate() {
+                let feature_path = format!("{}[{}]", at("features"), i);
+                check_tagged(Some(feature), &feature_path, FEATURE_TYPES, issues);
+                let int = |field: &str| feature.get(field).and_then(|v| v.as_u64());
+                match feature.get("type").and_then(|t| t.as_str()) {
+                    Some("macd") => {
+                        if let (Some(fast), Some(slow)) = (int("fast"), int("slow")) {
+                            if fast >= slow {
+                                issues.push(issue(
+                                    &join_path(&feature_path, "fast"),
+                                    &format!("must be less than slow ({} >= {})", fast, slow),
+                                ));
+                            }
+                        }
+                    }
+                    Some("volatility") | Some("z_score") if int("window") == Some(1) => {
+                        issues.push(issue(
+                            &join_path(&feature_path, "window"),
+                            "must be at least 2 to estimate a standard deviation",
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Some("ensemble") => {
+            let members = strategy.get("members").and_then(|v| v.as_array());
+            for (i, member) in members.into_iter().flatten().enumerate() {
+                let Some(object) = member.as_object() else {
+                    continue;
+                };
+                let member_path = format!("{}[{}]", at("members"), i);
+                check_fields(
+                    object,
+                    &member_path,
+                    ENSEMBLE_MEMBER_FIELDS,
+                    &["strategy"],
+                    issues,
+                );
+                let child_path = join_path(&member_path, "strategy");
+                check_tagged(object.get("strategy"), &child_path, STRATEGY_TYPES, issues);
+                if let Some(child) = object.get("strategy") {
+                    check_strategy_relations(child, &child_path, issues);
+                }
+            }
+        }
+        Some("multi_strategy") => {
+            let sleeves = strategy.get("sleeves").and_then(|v| v.as_array());
+            let mut names = Vec::new();
+            let mut total = 0.0;
+            for (i, sleeve) in sleeves.into_iter().flatten().enumerate() {
+                let Some(object) = sleeve.as_object() else {
+                    continue;
+                };
+                let sleeve_path = format!("{}[{}]", at("sleeves"), i);
+                check_fields(object, &sleeve_path, SLEEVE_FIELDS, &["strategy"], issues);
+                if let Some(name) = object.get("name").and_then(|v| v.as_str()) {
+                    if names.contains(&name) {
+                        issues.push(issue(
+                            &join_path(&sleeve_path, "name"),
+                            &format!("duplicates another sleeve's name {:?}", name),
+                        ));
+                    }
+                    names.push(name);
+                }
+                total += object
+                    .get("allocation")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or(0.0);
+                let child_path = join_path(&sleeve_path, "strategy");
+                check_tagged(object.get("strategy"), &child_path, STRATEGY_TYPES, issues);
+                if let Some(child) = object.get("strategy") {
+                    check_strategy_relations(child, &child_path, issues);
+                }
+            }
+            if total > 1.0 + 1e-9 {
+                issues.push(issue(
+                    &at("sleeves"),
+                    &format!("allocations must sum to at most 1 (got {})", total),
+                ));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn issue(path: &str, message: &str) -> SpecIssue {
+    SpecIssue {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn join_path(prefix: &str, field: &str) -> String {
+    if prefix.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", prefix, field)
+    }
+}
+
+fn check_fields(
+    object: &serde_json::Map<String, serde_json::Value>,
+    prefix: &str,
+    fields: FieldTable,
+    nested: &[&str],
+    issues: &mut Vec<SpecIssue>,
+) {
+    for (name, rule, required) in fields {
+        match object.get(*name) {
+            Some(value) => {
+                if let Some(message) = rule.check(value) {
+                    issues.push(issue(&join_path(prefix, name), &message));
+                }
+            }
+            None if *required => issues.push(issue(&join_path(prefix, name), "missing field")),
+            None => {}
+        }
+    }
+    for key in object.keys() {
+        let known = fields.iter().any(|(name, _, _)| name == key) || nested.contains(&key.as_str());
+        if !known {
+            issues.push(issue(&join_path(prefix, key), "unknown field"));
+        }
+    }
+}
+
+/// Check one margin requirement; maintenance may not exceed initial margin
+fn check_margin_requirement(
+    object: &serde_json::Map<String, serde_json::Value>,
+    path: &str,
+    nested: &[&str],
+    issues: &mut Vec<SpecIssue>,
+) {
+    check_fields(object, path, MARGIN_FIELDS, nested, issues);
+    let rate = |name| object.get(name).and_then(|v| v.as_f64());
+    if let (Some(initial), Some(maintenance)) = (rate("initial"), rate("maintenance")) {
+        if maintenance > initial {
+            issues.push(issue(
+                &join_path(path, "maintenance"),
+                "must not exceed the initial margin",
+            ));
+        }
+    }
+}
+
+/// Check margin requirements and their per-symbol overrides
+fn check_margin(value: &serde_json::Value, issues: &mut Vec<SpecIssue>) {
+    let path = "execution.margin";
+    let Some(margin) = value.as_object() else {
+        issues.push(issue(path, &format!("must be an object (got {})", value)));
+        return;
+    };
+    check_margin_requirement(margin, path, &["symbols"], issues);
+    let Some(symbols) = margin.get("symbols") else {
+        return;
+    };
+    let symbols_path = join_path(path, "symbols");
+    let Some(symbols) = symbols.as_object() else {
+        issues.push(issue(
+            &symbols_path,
+            &format!("must map symbols to margin requirements (got {})", symbols),
+        ));
+        return;
+    };
+    for (symbol, requirement) in symbols {
+        let prefix = join_path(&symbols_path, symbol);
+        match requirement.as_object() {
+            Some(requirement) => check_margin_requirement(requirement, &prefix, &[], issues),
+            None => issues.push(issue(
+                &prefix,
+                &format!("must be an object (got {})", requirement),
+            )),
+        }
+    }
+}
+
+/// Check the hard-to-borrow terms, keyed by symbol
+fn check_hard_to_borrow(value: &serde_json::Value, issues: &mut Vec<SpecIssue>) {
+    let path = "execution.hard_to_borrow";
+    let Some(symbols) = value.as_object() else {
+        issues.push(issue(
+            path,
+            &format!("must map symbols to borrow terms (got {})", value),
+        ));
+        return;
+    };
+    for (symbol, terms) in symbols {
+        let prefix = join_path(path, symbol);
+        match terms.as_object() {
+            Some(terms) => check_fields(terms, &prefix, HARD_TO_BORROW_FIELDS, &[], issues),
+            None => issues.push(issue(
+                &prefix,
+                &format!("must be an object (got {})", terms),
+            )),
+        }
+    }
+}
+
+/// Check the default lot and tick sizes and their per-symbol overrides
+fn check_instruments(value: &serde_json::Value, issues: &mut Vec<SpecIssue>) {
+    let path = "execution.instruments";
+    let Some(instruments) = value.as_object() else {
+        issues.push(issue(path, &format!("must be an object (got {})", value)));
+        return;
+    };
+    check_fields(instruments, path, INSTRUMENTS_FIELDS, &["symbols"], issues);
+    let Some(symbols) = instruments.get("symbols") else {
+        return;
+    };
+    let symbols_path = join_path(path, "symbols");
+    let Some(symbols) = symbols.as_object() else {
+        issues.push(issue(
+            &symbols_path,
+            &format!("must map symbols to lot and tick sizes (got {})", symbols),
+        ));
+        return;
+    };
+    for (symbol, rules) in symbols {
+        let prefix = join_path(&symbols_path, symbol);
+        match rules.as_object() {
+            Some(rules) => check_fields(rules, &prefix, INSTRUMENT_FIELDS, &[], issues),
+            None => issues.push(issue(
+                &prefix,
+                &format!("must be an object (got {})", rules),
+            )),
+        }
+    }
+}
+
+/// Check a cost model and, for a composite or scaled one, each model it
+/// wraps
+fn check_cost_model(value: Option<&serde_json::Value>, path: &str, issues: &mut Vec<SpecIssue>) {
+    check_tagged(value, path, COST_MODEL_TYPES, issues);
+    let object = value.and_then(|v| v.as_object());
+    if let Some(model) = object
+        .filter(|object| object.get("type").and_then(|t| t.as_str()) == Some("scaled"))
+        .and_then(|object| object.get("model"))
+    {
+        check_cost_model(Some(model), &join_path(path, "model"), issues);
+    }
+    if let Some(distribution) = object
+        .filter(|object| object.get("type").and_then(|t| t.as_str()) == Some("stochastic_slippage"))
+        .and_then(|object| object.get("distribution"))
+    {
+        let distribution_path = join_path(path, "distribution");
+        check_tagged(
+            Some(distribution),
+            &distribution_path,
+            SLIPPAGE_DISTRIBUTIONS,
+            issues,
+        );
+        let bound = |name: &str| distribution.get(name).and_then(|v| v.as_f64());
+        if let (Some(low), Some(high)) = (bound("low"), bound("high")) {
+            if low > high {
+                issues.push(issue(
+                    &join_path(&distribution_path, "high"),
+                    &format!("must be >= low ({})", low),
+                ));
+            }
+        }
+    }
+    let Some(composite) =
+        object.filter(|object| object.get("type").and_then(|t| t.as_str()) == Some("composite"))
+    else {
+        return;
+    };
+    if let Some(default) = composite.get("default") {
+        check_cost_model(Some(default), &join_path(path, "default"), issues);
+    }
+    let entries = |name: &str| {
+        composite
+            .get(name)
+            .and_then(|v| v.as_object())
+            .into_iter()
+            .flatten()
+    };
+    for (asset_class, model) in entries("asset_classes") {
+        let prefix = join_path(&join_path(path, "asset_classes"), asset_class);
+        let name = serde_json::Value::from(asset_class.as_str());
+        if let Some(message) = FieldRule::OneOf(ASSET_CLASSES).check(&name) {
+            issues.push(issue(&prefix, &message));
+        }
+        check_cost_model(Some(model), &prefix, issues);
+    }
+    for (symbol, model) in entries("symbols") {
+        check_cost_model(
+            Some(model),
+            &join_path(&join_path(path, "symbols"), symbol),
+            issues,
+        );
+    }
+    for (symbol, asset_class) in entries("symbol_classes") {
+        if let Some(message) = FieldRule::OneOf(ASSET_CLASSES).check(asset_class) {
+            let prefix = join_path(&join_path(path, "symbol_classes"), symbol);
+            issues.push(issue(&prefix, &message));
+        }
+    }
+}
+
+/// Check the FX spreads and that every foreign symbol's currency has one
+fn check_fx(value: &serde_json::Value, issues: &mut Vec<SpecIssue>) {
+    let path = "execution.fx";
+    let Some(fx) = value.as_object() else {
+        issues.push(issue(path, &format!("must be an object (got {})", value)));
+        return;
+    };
+    check_fields(fx, path, FX_FIELDS, &["spreads", "symbols"], issues);
+    let map = |name: &str, issues: &mut Vec<SpecIssue>| match fx.get(name) {
+        Some(serde_json::Value::Object(map)) => Some(map.clone()),
+        Some(other) => {
+            let message = format!("must be an object (got {})", other);
+            issues.push(issue(&join_path(path, name), &message));
+            None
+        }
+        None => {
+            issues.push(issue(&join_path(path, name), "missing field"));
+            None
+        }
+    };
+    let spreads = map("spreads", issues).unwrap_or_default();
+    for (currency, spread) in &spreads {
+        if let Some(message) = FieldRule::NonNegative.check(spread) {
+            issues.push(issue(
+                &join_path(&join_path(path, "spreads"), currency),
+                &message,
+            ));
+        }
+    }
+    let base = fx.get("base").and_then(|v| v.as_str());
+    for (symbol, currency) in map("symbols", issues).unwrap_or_default() {
+        let prefix = join_path(&join_path(path, "symbols"), &symbol);
+        match currency.as_str() {
+            Some(currency) if Some(currency) == base || spreads.contains_key(currency) => {}
+            Some(currency) => issues.push(issue(
+                &prefix,
+                &format!(
+                    "currency '{}' has no spread in execution.fx.spreads",
+                    currency
+                ),
+            )),
+            None => issues.push(issue(
+                &prefix,
+                &format!("must be a currency code (got {})", currency),
+            )),
+        }
+    }
+}
+
+/// Check the funding interval and each perpetual's rate source
+fn check_funding(value: &serde_json::Value, issues: &mut Vec<SpecIssue>) {
+    let path = "execution.funding";
+    let Some(funding) = value.as_object() else {
+        issues.push(issue(path, &format!("must be an object (got {})", value)));
+        return;
+    };
+    check_fields(funding, path, FUNDING_FIELDS, &["perpetuals"], issues);
+    let perpetuals_path = join_path(path, "perpetuals");
+    let Some(perpetuals) = funding.get("perpetuals") else {
+        issues.push(issue(&perpetuals_path, "missing field"));
+        return;
+    };
+    let Some(perpetuals) = perpetuals.as_object() else {
+        issues.push(issue(
+            &perpetuals_path,
+            &format!("must map symbols to funding rates (got {})", perpetuals),
+        ));
+        return;
+    };
+    for (symbol, source) in perpetuals {
+        let prefix = join_path(&perpetuals_path, symbol);
+        let Some(source) = source.as_object() else {
+            issues.push(issue(
+                &prefix,
+                &format!("must be an object (got {})", source),
+            ));
+            continue;
+        };
+        check_fields(source, &prefix, FUNDING_RATE_FIELDS, &[], issues);
+        if source.contains_key("rate") == source.contains_key("rates") {
+            issues.push(issue(&prefix, "needs exactly one of rate and rates"));
+        }
+        let points = source.get("rates").and_then(|v| v.as_array());
+        for (i, point) in points.into_iter().flatten().enumerate() {
+            if let Some(point) = point.as_object() {
+                let point_path = format!("{}[{}]", join_path(&prefix, "rates"), i);
+                check_fields(point, &point_path, FUNDING_POINT_FIELDS, &[], issues);
+            }
+        }
+    }
+}
+
+/// Check an internally tagged (`"type": ...`) section against its variant table
+fn check_tagged(
+    value: Option<&serde_json::Value>,
+    path: &str,
+    variants: &[(&str, FieldTable)],
+    issues: &mut Vec<SpecIssue>,
+) {
+    let names: Vec<&str> = variants.iter().map(|(name, _)| *name).collect();
+    let Some(value) = value else {
+        issues.push(issue(path, "missing field"));
+        return;
+    };
+    let Some(object) = value.as_object() else {
+        issues.push(issue(path, "must be an object with a \"type\" field"));
+        return;
+    };
+    let type_path = join_path(path, "type");
+    let Some(type_name) = object.get("type").and_then(|t| t.as_str()) else {
+        issues.push(issue(
+            &type_path,
+            &format!("missing; expected one of {}", names.join(", ")),
+        ));
+        return;
+    };
+    match variants.iter().find(|(name, _)| *name == type_name) {
+        Some((_, fields)) => check_fields(object, path, fields, &["type"], issues),
+        None => issues.push(issue(
+            &type_path,
+            &format!(
+                "unknown type '{}'; expected one of {}",
+                type_name,
+                names.join(", ")
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_presets_expand_to_valid_specs() {
+        let base = serde_json::json!({
+            "initial_cash": 10000.0,
+            "seed": 1,
+            "strategy": {"type": "buy_and_hold", "symbol": "AAPL"},
+            "cost_model": {"type": "zero"}
+        });
+        for preset in CostPreset::value_variants() {
+            let mut value = base.clone();
+            let overrides = with_cost_preset(Some(*preset), vec![]);
+            apply_overrides(&mut value, &overrides).unwrap();
+            let spec = parse_spec(value).unwrap();
+            assert_eq!(
+                serde_json::to_value(&spec.cost_model).unwrap(),
+                serde_json::to_value(preset.cost_model()).unwrap()
+            );
+        }
+
+        // Later overrides refine the preset
+        let mut value = base;
+        let overrides = with_cost_preset(
+            Some(CostPreset::IbkrTiered),
+            vec!["cost_model.minimum_commission=1.0".to_string()],
+        );
+        apply_overrides(&mut value, &overrides).unwrap();
+        assert!(matches!(
+            parse_spec(value).unwrap().cost_model,
+            CostModelSpec::FixedPerShare { minimum_commission, .. } if minimum_commission == 1.0
+        ));
+    }
+
+    #[test]
+    fn set_json_path_updates_nested_fields() {
+        let mut value = serde_json::json!({"seed": 1, "strategy": {"lookback": 20}});
+        set_json_path(&mut value, "strategy.lookback", serde_json::json!(40)).unwrap();
+        set_json_path(&mut value, "seed", serde_json::json!(7)).unwrap();
+        assert_eq!(value["strategy"]["lookback"], 40);
+        assert_eq!(value["seed"], 7);
+
+        assert!(set_json_path(&mut value, "strategy.missing.x", serde_json::json!(1)).is_err());
+        assert!(set_json_path(&mut value, "seed.x", serde_json::json!(1)).is_err());
+    }
+
+    #[test]
+    fn overrides_parse_json_or_fall_back_to_strings() {
+        let mut value = serde_json::json!({
+            "seed": 1,
+            "strategy": {"symbol": "AAPL", "lookback": 20},
+            "cost_model": {"type": "zero"}
+        });
+        apply_overrides(
+            &mut value,
+            &[
+                "strategy.lookback=40".to_string(),
+                "seed=7".to_string(),
+                "strategy.symbol=MSFT".to_string(),
+                "cost_model={\"type\": \"percentage\"}".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(value["strategy"]["lookback"], 40);
+        assert_eq!(value["seed"], 7);
+        assert_eq!(value["strategy"]["symbol"], "MSFT");
+        assert_eq!(value["cost_model"]["type"], "percentage");
+
+        assert!(parse_override("seed").is_err());
+        assert!(apply_overrides(&mut value, &["stratgy.lookback=1".to_string()]).is_err());
+    }
+
+    #[test]
+    fn validation_reports_every_problem_with_paths() {
+        let value = serde_json::json!({
+            "initial_cash": -5.0,
+            "seed": 1,
+            "strategy": {
+                "type": "ts_momentum",
+                "symbol": "AAPL",
+                "lookback": 0,
+                "vol_target": 1.5
+            },
+            "cost_model": {"type": "percentage", "percentage": 0.001, "minimum_comission": 1.0},
+            "execution": {"max_participation": 0.0, "partial": true},
+            "data_pipeline": "fast"
+        });
+        let paths: Vec<String> = validate_spec_value(&value)
+            .into_iter()
+            .map(|i| i.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "initial_cash",
+                "strategy.lookback",
+                "strategy.vol_target",
+                "strategy.vol_lookback",
+                "cost_model.minimum_commission",
+                "cost_model.minimum_comission",
+                "execution.max_participation",
+                "execution.partial",
+                "data_pipeline",
+            ]
+        );
+
+        let err = parse_spec(value).unwrap_err().to_string();
+        assert!(err.contains("9 problem(s)"));
+        assert!(err.contains("strategy.vol_target: must be in (0, 1]"));
+    }
+
+    #[test]
+    fn volume_participation_costs_need_a_positive_exponent() {
+        let value = |exponent: f64| {
+            serde_json::json!({
+                "initial_cash": 10000.0,
+                "seed": 1,
+                "strategy": {"type": "buy_and_hold", "symbol": "AAPL"},
+                "cost_model": {
+                    "type": "volume_participation",
+                    "percentage": 0.0,
+                    "minimum_commission": 0.0,
+                    "impact": 0.1,
+                    "exponent": exponent
+                }
+            })
+        };
+        let paths: Vec<String> = validate_spec_value(&value(0.0))
+            .into_iter()
+            .map(|i| i.path)
+            .collect();
+        assert_eq!(paths, ["cost_model.exponent"]);
+        let spec = parse_spec(value(0.5)).unwrap();
+        assert!(matches!(
+            spec.cost_model,
+            CostModelSpec::VolumeParticipation { exponent, .. } if exponent == 0.5
+        ));
+    }
+
+    #[test]
+    fn stochastic_slippage_needs_a_valid_distribution() {
+        let value = |distribution: serde_json::Value| {
+            serde_json::json!({
+                "initial_cash": 10000.0,
+                "seed": 1,
+                "strategy": {"type": "buy_and_hold", "symbol": "AAPL"},
+                "cost_model": {
+                    "type": "stochastic_slippage",
+                    "percentage": 0.0,
+                    "minimum_commission": 0.0,
+                    "distribution": distribution
+                }
+            })
+        };
+        let paths = |distribution| -> Vec<String> {
+            validate_spec_value(&value(distribution))
+                .into_iter()
+                .map(|i| i.path)
+                .collect()
+        };
+        assert_eq!(
+            paths(serde_json::json!({"type": "uniform", "low": 0.002, "high": 0.001})),
+            ["cost_model.distribution.high"]
+        );
+        assert_eq!(
+            paths(serde_json::json!({"type": "normal", "mean": 0.0, "std_dev": -1.0})),
+            ["cost_model.distribution.std_dev"]
+        );
+        assert_eq!(
+            paths(serde_json::json!({"type": "cauchy"})),
+            ["cost_model.distribution.type"]
+        );
+        let spec = parse_spec(value(
+            serde_json::json!({"type": "exponential", "mean": 0.001}),
+        ))
+        .unwrap();
+        assert!(matches!(
+            spec.cost_model,
+            CostModelSpec::StochasticSlippage {
+                distribution: SlippageDistributionSpec::Exponential { mean },
+                ..
+            } if mean == 0.001
+        ));
+    }
+
+    #[test]
+    fn composite_cost_models_check_every_route() {
+        let value = |composite: serde_json::Value| {
+            serde_json::json!({
+                "initial_cash": 10000.0,
+                "seed": 1,
+                "strategy": {"type": "buy_and_hold", "symbol": "ES"},
+                "cost_model": composite
+            })
+        };
+        let composite = serde_json::json!({
+            "type": "composite",
+            "default": {"type": "percentage", "percentage": 0.001, "minimum_commission": 0.0},
+            "asset_classes": {
+                "future": {"type": "fixed_per_share", "cost_per_share": 2.25, "minimum_commission": 0.0}
+            },
+            "symbols": {"GME": {"type": "zero"}},
+            "symbol_classes": {"ES": "future"}
+        });
+        let spec = parse_spec(value(composite)).unwrap();
+        let CostModelSpec::Composite {
+            asset_classes,
+            symbol_classes,
+            ..
+        } = spec.cost_model
+        else {
+            panic!("expected a composite cost model");
+        };
+        assert!(asset_classes.contains_key(&MarketAssetClass::Future));
+        assert_eq!(symbol_classes["ES"], MarketAssetClass::Future);
+
+        let bad = serde_json::json!({
+            "type": "composite",
+            "default": {"type": "percentage", "percentage": -1.0, "minimum_commission": 0.0},
+            "asset_classes": {"bond": {"type": "zero"}},
+            "symbols": {"GME": {"type": "free"}},
+            "symbol_classes": {"ES": "futures"}
+        });
+        let paths: Vec<String> = validate_spec_value(&value(bad))
+            .into_iter()
+            .map(|i| i.path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "cost_model.default.percentage",
+                "cost_model.asset_classes.bond",
+                "cost_model.symbols.GME.type",
+                "cost_model.symbol_classes.ES",
+            ]
+        );
+
+        let scaled = serde_json::json!({
+            "type": "scaled", "multiplier": -2.0,
+            "model": {"type": "fixed_per_share", "cost_per_share": -0.01, "minimum_commission": 0.0}
+        });
+        let paths: Vec<String> = validate_spec_value(&value(scaled))
+            .into_iter()
+            .map(|i| i.path)
+            .collect();
+        assert_eq!(
+            paths,
+            ["cost_model.multiplier", "cost_model.model.cost_per_share"]
+        );
+    }
+
+    #[test]
+    fn metrics_take_a_bar_interval_or_periods_per_year() {
+        let spec = |metrics: serde_json::Value| {
+            serde_json::json!({
+                "initial_cash": 10000.0,
+                "seed": 1,
+                "strategy": {"type": "buy_and_hold", "symbol": "BTC"},
+                "cost_model": {"type": "zero"},
+                "metrics": metrics
+            })
+        };
+        let issues = validate_spec_value(&spec(
+            serde_json::json!({"periods_per_year": 365, "bar_interval": 3600}),
+        ));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "metrics.bar_interval");
+
+        let parsed = parse_spec(spec(
+            serde_json::json!({"bar_interval": 3600, "risk_free_rate": 0.04}),
+        ))
+        .unwrap();
+        let annualization = parsed.metrics.annualization();
+        assert_eq!(annualization.periods_per_year, 8760.0);
+        assert_eq!(annualization.risk_free_rate, 0.04);
+        let default = parse_spec(spec(serde_json::json!({}))).unwrap();
+        assert_eq!(default.metrics.annualization(), Default::default());
+        assert!(serde_json::to_value(&default)
+            .unwrap()
+            .get("metrics")
+            .is_none());
+    }
+
+    #[test]
+    fn execution_latency_is_bars_or_milliseconds() {
+        let spec = |execution: serde_json::Value| {
+            serde_json::json!({
+                "initial_cash": 10000.0,
+                "seed": 1,
+                "strategy": {"type": "buy_and_hold", "symbol": "AAPL"},
+                "cost_model": {"type": "zero"},
+                "execution": execution
+            })
+        };
+        let paths = |execution| -> Vec<String> {
+            validate_spec_value(&spec(execution))
+                .into_iter()
+                .map(|i| i.path)
+                .collect()
+        };
+        assert_eq!(
+            paths(serde_json::json!({"latency_bars": 1, "latency_ms": 50})),
+            ["execution.latency_ms"]
+        );
+        assert_eq!(
+            paths(serde_json::json!({"latency_jitter_ms": 10})),
+            ["execution.latency_jitter_ms"]
+        );
+        let parsed = parse_spec(spec(
+            serde_json::json!({"latency_ms": 50, "latency_jitter_ms": 10}),
+        ))
+        .unwrap();
+        assert_eq!(parsed.execution.latency_ms, Some(50));
+
+        assert_eq!(
+            paths(serde_json::json!({"algo": "twap"})),
+            ["execution.algo"]
+        );
+        let parsed = parse_spec(spec(serde_json::json!({"algo": "vwap", "algo_bars": 5}))).unwrap();
+        assert_eq!(parsed.execution.algo, Some(ExecutionAlgo::Vwap));
+
+        assert_eq!(
+            paths(serde_json::json!({
+                "borrow_fee_rate": 0.01,
+                "hard_to_borrow": {"GME": {"fee_rate": 0.5, "locate_probability": 1.5}}
+            })),
+            ["execution.hard_to_borrow.GME.locate_probability"]
+        );
+        assert_eq!(
+            paths(serde_json::json!({"borrow_fee_rate": 0.01})),
+            ["execution.borrow_fee_rate"]
+        );
+        assert_eq!(
+            paths(serde_json::json!({"cash_reserve": 0.01})),
+            ["execution.cash_reserve"]
+        );
+        assert!(paths(serde_json::json!({"cash_mode": "strict", "cash_reserve": 0.01})).is_empty());
+
+        assert_eq!(
+            paths(serde_json::json!({
+                "funding": {
+                    "interval_hours": 8,
+                    "perpetuals": {
+                        "BTC-PERP": {"rate": 0.0001, "rates": [{"from": 0, "rate": 0.0002}]},
+                        "ETH-PERP": {"rates": [{"from": -1, "rate": "high"}]}
+                    }
+                }
+            })),
+            [
+                "execution.funding.perpetuals.BTC-PERP",
+                "execution.funding.perpetuals.ETH-PERP.rates[0].from",
+                "execution.funding.perpetuals.ETH-PERP.rates[0].rate"
+            ]
+        );
+        let parsed = parse_spec(spec(serde_json::json!({
+            "funding": {
+                "interval_hours": 8,
+                "perpetuals": {"BTC-PERP": {"rates": [{"from": 0, "rate": -0.0001}]}}
+            }
+        })))
+        .unwrap();
+        assert_eq!(
+            parsed.execution.funding.unwrap().perpetuals["BTC-PERP"].rates[0].rate,
+            -0.0001
+        );
+
+        assert_eq!(
+            paths(serde_json::json!({
+                "instruments": {
+                    "lot_size": 1,
+                    "rounding": "round",
+                    "symbols": {"BTC": {"lot_size": 0.001, "tick_size": 0}}
+                }
+            })),
+            [
+                "execution.instruments.rounding",
+                "execution.instruments.symbols.BTC.tick_size"
+            ]
+        );
+        assert_eq!(
+            paths(serde_json::json!({"halt_policy": "skip"})),
+            ["execution.halt_policy"]
+        );
+        assert_eq!(
+            paths(serde_json::json!({"financing": {"debit_rate": -0.05, "rate": 0.01}})),
+            ["execution.financing.debit_rate", "execution.financing.rate"]
+        );
+        assert_eq!(
+            paths(serde_json::json!({
+                "easy_to_borrow": ["AAPL"],
+                "borrow_fee_rate": 0.01,
+                "financing": {"short_borrow_rate": 0.01}
+            })),
+            ["execution.financing.short_borrow_rate"]
+        );
+        assert_eq!(
+            paths(serde_json::json!({
+                "fx": {
+                    "base": "USD",
+                    "spreads": {"EUR": -0.001},
+                    "symbols": {"SAP": "EUR", "7203.T": "JPY"}
+                }
+            })),
+            ["execution.fx.spreads.EUR", "execution.fx.symbols.7203.T"]
+        );
+        assert_eq!(
+            paths(serde_json::json!({
+                "margin_multiplier": 2.0,
+                "fx": {
+                    "base": "USD",
+                    "prices": "local",
+                    "spreads": {"JPY": 0.001},
+                    "symbols": {"7203.T": "JPY"}
+                }
+            })),
+            ["execution.fx.prices"]
+        );
+        let parsed = parse_spec(spec(
+            serde_json::json!({"financing": {"credit_rate": 0.04}}),
+        ))
+        .unwrap();
+        let financing = parsed.execution.financing.unwrap();
+        assert_eq!((financing.debit_rate, financing.credit_rate), (0.0, 0.04));
+        let parsed = parse_spec(spec(serde_json::json!({
+            "instruments": {"lot_size": 1, "tick_size": 0.01, "rounding": "reject"}
+        })))
+        .unwrap();
+        let instruments = parsed.execution.instruments.unwrap();
+        assert_eq!(instruments.default.tick_size, Some(0.01));
+        assert_eq!(instruments.rounding, Rounding::Reject);
+
+        assert_eq!(
+            paths(serde_json::json!({
+                "margin_multiplier": 2.0,
+                "margin": {
+                    "initial": 0.5,
+                    "maintenance": 0.25,
+                    "symbols": {"GME": {"initial": 0.5, "maintenance": 0.75}}
+                }
+            })),
+            [
+                "execution.margin.symbols.GME.maintenance",
+                "execution.margin"
+            ]
+        );
+        let parsed = parse_spec(spec(serde_json::json!({
+            "margin": {
+                "initial": 0.5,
+                "maintenance": 0.25,
+                "symbols": {"GME": {"initial": 1.0, "maintenance": 0.5}}
+            }
+        })))
+        .unwrap();
+        let margin = parsed.execution.margin.unwrap();
+        assert_eq!(
+            (margin.default.initial, margin.symbols["GME"].maintenance),
+            (0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn example_specs_validate_and_parse() {
+        for file in ["spec.json", "spec_alpaca_canonical.json"] {
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../examples")
+                .join(file);
+            let value: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            assert_eq!(validate_spec_value(&value), vec![]);
+            parse_spec(value).unwrap();
+        }
+
+        let unknown = serde_json::json!({
+            "initial_cash": 1.0,
+            "seed": 0,
+            "strategy": {"type": "magic"},
+            "cost_model": {"type": "zero"}
+        });
+        let issues = validate_spec_value(&unknown);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("expected one of ts_momentum"));
+    }
+
+    #[test]
+    fn strategy_relations_are_checked() {
+        let spec = |strategy: serde_json::Value| {
+            serde_json::json!({
+                "initial_cash": 1000.0,
+                "seed": 0,
+                "strategy": strategy,
+                "cost_model": {"type": "zero"}
+            })
+        };
+
+        let crossover = spec(serde_json::json!({
+            "type": "sma_crossover", "symbol": "AAPL", "fast": 50, "slow": 20
+        }));
+        let issues = validate_spec_value(&crossover);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "strategy.fast");
+
+        let ema = spec(serde_json::json!({
+            "type": "sma_crossover", "symbol": "AAPL", "fast": 5, "slow": 20,
+            "ma_type": "ema", "mode": "both"
+        }));
+        let issues = validate_spec_value(&ema);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "strategy.mode");
+
+        let ensemble = spec(serde_json::json!({
+            "type": "ensemble", "netting": "net", "members": [
+                {"weight": 0.5, "strategy": {"type": "buy_and_hold", "symbol": "AAPL"}},
+                {"weight": 0.5, "strategy": {"type": "sma_crossover", "symbol": "AAPL", "fast": 9, "slow": 5}}
+            ]
+        }));
+        let issues = validate_spec_value(&ensemble);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "strategy.members[1].strategy.fast");
+        assert!(issues[0]
+            .message
+            .contains("strategy.members[1].strategy.slow"));
+
+        let sleeves = spec(serde_json::json!({
+            "type": "multi_strategy", "sleeves": [
+                {"name": "a", "allocation": 0.6, "strategy": {"type": "buy_and_hold", "symbol": "AAPL"}},
+                {"name": "a", "allocation": 0.6, "strategy": {"type": "buy_and_hold", "symbol": "MSFT"}}
+            ]
+        }));
+        let paths: Vec<_> = validate_spec_value(&sleeves)
+            .into_iter()
+            .map(|issue| issue.path)
+            .collect();
+        assert_eq!(paths, ["strategy.sleeves[1].name", "strategy.sleeves"]);
+
+        let overweight = spec(serde_json::json!({
+            "type": "fixed_weights", "weights": {"SPY": 0.7, "TLT": 0.4}
+        }));
+        let issues = validate_spec_value(&overweight);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "strategy.weights");
+
+        let onnx = spec(serde_json::json!({
+            "type": "onnx", "model": "m.onnx", "symbol": "AAPL",
+            "features": [{"type": "rsi", "period": 14}, {"type": "macd", "fast": 26, "slow": 12, "signal": 9}]
+        }));
+        let issues = validate_spec_value(&onnx);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "strategy.features[1].fast");
+
+        let breakout = spec(serde_json::json!({
+            "type": "channel_breakout", "symbol": "AAPL", "entry_lookback": 20,
+            "exit_lookback": 10, "trailing_stop": 1.5
+        }));
+        let issues = validate_spec_value(&breakout);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "strategy.trailing_stop");
+
+        let cross_sectional = spec(serde_json::json!({
+            "type": "cross_sectional_momentum", "symbols": ["A", "B"], "lookback": 5, "top_n": 3
+        }));
+        assert_eq!(
+            validate_spec_value(&cross_sectional)[0].path,
+            "strategy.top_n"
+        );
+
+        let reversion = spec(serde_json::json!({
+            "type": "mean_reversion", "symbol": "AAPL", "lookback": 20, "entry_z": 2.0
+        }));
+        let same_leg = spec(serde_json::json!({
+            "type": "pairs", "symbol_a": "KO", "symbol_b": "KO", "lookback": 20, "entry_z": 2.0
+        }));
+        assert_eq!(validate_spec_value(&same_leg)[0].path, "strategy.symbol_b");
+
+        match parse_spec(reversion).unwrap().strategy {
+            StrategySpec::MeanReversion {
+                exit_z, allocation, ..
+            } => assert_eq!((exit_z, allocation), (0.0, 1.0)),
+            other => panic!("unexpected strategy {:?}", other),
+        }
+    }
 }
@@ -1,14 +1,226 @@
-use schema::{Bar, Order, OrderType, Portfolio, Side, Strategy};
-use std::collections::VecDeque;
+use anyhow::Result;
+use engine::multi_strategy::{MultiStrategy, Sleeve};
+use engine::{ExecutionRules, TargetExecutor};
+use indicators::sizing::{equity_fraction_shares, VolTargetSizer};
+use indicators::{Ema, Sma, ZScore};
+use schema::{
+    Bar, Order, OrderType, Portfolio, Side, Strategy, Target, TargetPosition, TargetStrategy,
+    TimerEvent,
+};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+
+use crate::spec::{
+    Direction, MovingAverage, Netting, RebalanceFrequency, SleeveSpec, StrategySpec,
+};
+use crate::wasm_strategy::{WasmLimits, WasmStrategy};
+
+/// Build the strategy a spec selects.
+///
+/// This is the registry of built-in strategies: adding a `StrategySpec`
+/// variant and an arm here makes it selectable from spec files. Only plugin
+/// strategies can fail to build (missing or invalid module).
+pub fn build_strategy(spec: &StrategySpec) -> Result<Box<dyn Strategy>> {
+    Ok(match spec {
+        StrategySpec::TsMomentum {
+            symbol,
+            lookback,
+            vol_target,
+            vol_lookback,
+        } => Box::new(TsMomentumStrategy::new(
+            symbol.clone(),
+            *lookback,
+            *vol_target,
+            *vol_lookback,
+        )),
+        StrategySpec::BuyAndHold { symbol, allocation } => Box::new(TargetExecutor::new(
+            BuyAndHoldStrategy::new(symbol.clone(), *allocation),
+            ExecutionRules::default(),
+        )),
+        StrategySpec::SmaCrossover {
+            symbol,
+            fast,
+            slow,
+            allocation,
+            ma_type,
+            mode,
+        } => Box::new(SmaCrossoverStrategy::new(
+            symbol.clone(),
+            *fast,
+            *slow,
+            *allocation,
+            *ma_type,
+            *mode,
+        )),
+        StrategySpec::MeanReversion {
+            symbol,
+            lookback,
+            entry_z,
+            exit_z,
+            allocation,
+        } => Box::new(MeanReversionStrategy::new(
+            symbol.clone(),
+            *lookback,
+            *entry_z,
+            *exit_z,
+            *allocation,
+        )),
+        StrategySpec::ChannelBreakout {
+            symbol,
+            entry_lookback,
+            exit_lookback,
+            trailing_stop,
+            mode,
+            allocation,
+        } => Box::new(ChannelBreakoutStrategy::new(
+            symbol.clone(),
+            *entry_lookback,
+            *exit_lookback,
+            *trailing_stop,
+            *mode,
+            *allocation,
+        )),
+        StrategySpec::CrossSectionalMomentum {
+            symbols,
+            lookback,
+            top_n,
+            rebalance_every,
+            allocation,
+        } => Box::new(CrossSectionalMomentumStrategy::new(
+            symbols.clone(),
+            *lookback,
+            *top_n,
+            *rebalance_every,
+            *allocation,
+        )),
+        StrategySpec::RiskParity {
+            symbols,
+            vol_lookback,
+            vol_target,
+            rebalance_every,
+            allocation,
+        } => Box::new(RiskParityStrategy::new(
+            symbols.clone(),
+            *vol_lookback,
+            *vol_target,
+            *rebalance_every,
+            *allocation,
+        )),
+        StrategySpec::FixedWeights {
+            weights,
+            rebalance,
+            drift_band,
+        } => Box::new(TargetExecutor::new(
+            FixedWeightsStrategy::new(weights.clone(), *rebalance, *drift_band),
+            ExecutionRules::default(),
+        )),
+        StrategySpec::Pairs {
+            symbol_a,
+            symbol_b,
+            lookback,
+            entry_z,
+            exit_z,
+            allocation,
+        } => Box::new(PairsStrategy::new(
+            symbol_a.clone(),
+            symbol_b.clone(),
+            *lookback,
+            *entry_z,
+            *exit_z,
+            *allocation,
+        )),
+        StrategySpec::Ensemble { members, netting } => Box::new(EnsembleStrategy::new(
+            members
+                .iter()
+                .map(|member| Ok((member.weight, build_strategy(&member.strategy)?)))
+                .collect::<Result<_>>()?,
+            *netting,
+        )),
+        StrategySpec::MultiStrategy { sleeves } => Box::new(build_multi_strategy(sleeves)?),
+        #[cfg(feature = "onnx")]
+        StrategySpec::Onnx {
+            model,
+            symbol,
+            features,
+            model_sha256,
+            threshold,
+            mode,
+            allocation,
+        } => Box::new(crate::onnx_strategy::OnnxStrategy::load(
+            Path::new(model),
+            model_sha256.as_deref(),
+            symbol.clone(),
+            features,
+            *threshold,
+            *mode,
+            *allocation,
+        )?),
+        #[cfg(not(feature = "onnx"))]
+        StrategySpec::Onnx { .. } => {
+            anyhow::bail!("strategy type 'onnx' needs quant_engine built with the `onnx` feature")
+        }
+        StrategySpec::Wasm {
+            module,
+            params,
+            module_sha256,
+            fuel_per_bar,
+            max_memory_mb,
+            ..
+        } => Box::new(WasmStrategy::load(
+            Path::new(module),
+            params,
+            module_sha256.as_deref(),
+            WasmLimits {
+                fuel_per_bar: *fuel_per_bar,
+                max_memory_bytes: (*max_memory_mb as usize).saturating_mul(1 << 20),
+            },
+        )?),
+    })
+}
+
+/// Market order moving `symbol` from `current` to `target` shares, ignoring dust
+pub(crate) fn order_to_target(symbol: &str, current: f64, target: f64) -> Vec<Order> {
+    let delta = target - current;
+    if delta.abs() <= 0.1 {
+        return vec![];
+    }
+    let (side, quantity) = if delta > 0.0 {
+        (Side::Buy, delta)
+    } else {
+        (Side::Sell, -delta)
+    };
+    vec![Order {
+        symbol: symbol.to_string(),
+        side,
+        quantity,
+        order_type: OrderType::Market,
+        limit_price: None,
+        stop_price: None,
+        order_id: None,
+        bracket: None,
+    }]
+}
+
+pub(crate) fn position_of(portfolio: &Portfolio, symbol: &str) -> f64 {
+    portfolio
+        .get_position(symbol)
+        .map(|p| p.quantity)
+        .unwrap_or(0.0)
+}
+
+fn push_bounded<T>(history: &mut VecDeque<T>, value: T, capacity: usize) {
+    history.push_back(value);
+    while history.len() > capacity {
+        history.pop_front();
+    }
+}
 
 /// Time-series momentum strategy with volatility targeting
 pub struct TsMomentumStrategy {
     symbol: String,
     lookback: usize,
-    vol_target: f64,
-    vol_lookback: usize,
     price_history: VecDeque<f64>,
-    return_history: VecDeque<f64>,
+    sizer: VolTargetSizer,
 }
 
 impl TsMomentumStrategy {
@@ -16,10 +228,8 @@ impl TsMomentumStrategy {
         Self {
             symbol,
             lookback,
-            vol_target,
-            vol_lookback,
             price_history: VecDeque::new(),
-            return_history: VecDeque::new(),
+            sizer: VolTargetSizer::new(vol_lookback, vol_target),
         }
     }
 
@@ -32,40 +242,9 @@ impl TsMomentumStrategy {
         Some((end_price - start_price) / start_price)
     }
 
-    fn calculate_volatility(&self) -> Option<f64> {
-        if self.return_history.len() < self.vol_lookback {
-            return None;
-        }
-        let recent_returns: Vec<f64> = self
-            .return_history
-            .iter()
-            .rev()
-            .take(self.vol_lookback)
-            .copied()
-            .collect();
-
-        let mean = recent_returns.iter().sum::<f64>() / recent_returns.len() as f64;
-        let variance = recent_returns
-            .iter()
-            .map(|r| (r - mean).powi(2))
-            .sum::<f64>()
-            / recent_returns.len() as f64;
-        Some(variance.sqrt())
-    }
-
     fn calculate_target_position(&self, current_price: f64, portfolio: &Portfolio) -> Option<f64> {
         let momentum = self.calculate_momentum()?;
-        let volatility = self.calculate_volatility()?;
-
-        if volatility < 1e-8 {
-            return Some(0.0);
-        }
-
-        // Calculate position size based on volatility targeting
-        // target_notional = equity * vol_target / volatility
-        // position_size = target_notional / price
-        let target_notional = portfolio.equity * self.vol_target / volatility;
-        let target_shares = target_notional / current_price;
+        let target_shares = self.sizer.shares(portfolio.equity, current_price)?;
 
         // Apply momentum signal: positive momentum = long, negative = short
         let signal = if momentum > 0.01 {
@@ -86,59 +265,951 @@ impl Strategy for TsMomentumStrategy {
             return vec![];
         }
 
-        // Update price history
-        self.price_history.push_back(bar.close);
-        if self.price_history.len() > self.lookback + self.vol_lookback {
-            self.price_history.pop_front();
+        push_bounded(&mut self.price_history, bar.close, self.lookback);
+        self.sizer.update(bar.close);
+
+        match self.calculate_target_position(bar.close, portfolio) {
+            Some(target) => {
+                order_to_target(&self.symbol, position_of(portfolio, &self.symbol), target)
+            }
+            None => vec![], // Not enough data yet
+        }
+    }
+
+    fn name(&self) -> &str {
+        "TsMomentum"
+    }
+}
+
+/// Buy `allocation` of equity on the first bar and hold it
+pub struct BuyAndHoldStrategy {
+    symbol: String,
+    allocation: f64,
+    invested: bool,
+}
+
+impl BuyAndHoldStrategy {
+    pub fn new(symbol: String, allocation: f64) -> Self {
+        Self {
+            symbol,
+            allocation,
+            invested: false,
+        }
+    }
+}
+
+impl TargetStrategy for BuyAndHoldStrategy {
+    fn targets(&mut self, bar: &Bar, _portfolio: &Portfolio) -> Vec<TargetPosition> {
+        if bar.symbol != self.symbol || self.invested || bar.close <= 0.0 {
+            return vec![];
+        }
+        self.invested = true;
+        vec![TargetPosition {
+            symbol: self.symbol.clone(),
+            target: Target::Weight(self.allocation),
+        }]
+    }
+
+    fn name(&self) -> &str {
+        "BuyAndHold"
+    }
+}
+
+/// Long `allocation` of equity while the fast moving average is above the
+/// slow one; flat otherwise, or short the same amount in `LongShort` mode.
+///
+/// Trades only when the signal flips, so position size is fixed at entry.
+/// EMAs are seeded with the first close and, like SMAs, give no signal until
+/// `slow` bars have been seen.
+pub struct SmaCrossoverStrategy {
+    symbol: String,
+    allocation: f64,
+    ma_type: MovingAverage,
+    mode: Direction,
+    fast_sma: Sma,
+    slow_sma: Sma,
+    fast_ema: Ema,
+    slow_ema: Ema,
+    /// -1 short, 0 flat, 1 long
+    position: i8,
+}
+
+impl SmaCrossoverStrategy {
+    pub fn new(
+        symbol: String,
+        fast: usize,
+        slow: usize,
+        allocation: f64,
+        ma_type: MovingAverage,
+        mode: Direction,
+    ) -> Self {
+        Self {
+            symbol,
+            allocation,
+            ma_type,
+            mode,
+            fast_sma: Sma::new(fast),
+            slow_sma: Sma::new(slow),
+            fast_ema: Ema::new(fast),
+            slow_ema: Ema::new(slow),
+            position: 0,
+        }
+    }
+}
+
+impl Strategy for SmaCrossoverStrategy {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        if bar.symbol != self.symbol {
+            return vec![];
+        }
+        let averages = match self.ma_type {
+            MovingAverage::Sma => (
+                self.fast_sma.update(bar.close),
+                self.slow_sma.update(bar.close),
+            ),
+            MovingAverage::Ema => (
+                self.fast_ema.update(bar.close),
+                self.slow_ema.update(bar.close),
+            ),
+        };
+        let (Some(fast_ma), Some(slow_ma)) = averages else {
+            return vec![];
+        };
+
+        let position = if fast_ma > slow_ma {
+            1
+        } else if self.mode == Direction::LongShort {
+            -1
+        } else {
+            0
+        };
+        if position == self.position {
+            return vec![];
         }
+        self.position = position;
+
+        let target = f64::from(position)
+            * equity_fraction_shares(portfolio.equity, self.allocation, bar.close);
+        order_to_target(&self.symbol, position_of(portfolio, &self.symbol), target)
+    }
+
+    fn name(&self) -> &str {
+        "SmaCrossover"
+    }
+}
 
-        // Calculate return
-        if self.price_history.len() >= 2 {
-            let prev_price = self.price_history[self.price_history.len() - 2];
-            let curr_price = bar.close;
-            let ret = (curr_price - prev_price) / prev_price;
-            self.return_history.push_back(ret);
-            if self.return_history.len() > self.vol_lookback {
-                self.return_history.pop_front();
+/// Enter against a z-score beyond `entry_z` and exit once it reverts inside `exit_z`
+pub struct MeanReversionStrategy {
+    symbol: String,
+    entry_z: f64,
+    exit_z: f64,
+    allocation: f64,
+    z_score: ZScore,
+    /// -1 short, 0 flat, 1 long
+    state: i8,
+}
+
+impl MeanReversionStrategy {
+    pub fn new(
+        symbol: String,
+        lookback: usize,
+        entry_z: f64,
+        exit_z: f64,
+        allocation: f64,
+    ) -> Self {
+        Self {
+            symbol,
+            entry_z,
+            exit_z,
+            allocation,
+            z_score: ZScore::new(lookback.max(2)),
+            state: 0,
+        }
+    }
+}
+
+impl Strategy for MeanReversionStrategy {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        if bar.symbol != self.symbol {
+            return vec![];
+        }
+        let Some(z) = self.z_score.update(bar.close) else {
+            return vec![];
+        };
+
+        let state = match self.state {
+            0 if z <= -self.entry_z => 1,
+            0 if z >= self.entry_z => -1,
+            1 if z >= -self.exit_z => 0,
+            -1 if z <= self.exit_z => 0,
+            current => current,
+        };
+        if state == self.state {
+            return vec![];
+        }
+        self.state = state;
+
+        let target =
+            f64::from(state) * equity_fraction_shares(portfolio.equity, self.allocation, bar.close);
+        order_to_target(&self.symbol, position_of(portfolio, &self.symbol), target)
+    }
+
+    fn name(&self) -> &str {
+        "MeanReversion"
+    }
+}
+
+/// Donchian channel breakout on a single symbol.
+///
+/// Goes long `allocation` of equity when the close exceeds the highest high of
+/// the prior `entry_lookback` bars (short below the lowest low in `LongShort`
+/// mode). A long exits when the close falls below the lowest low of the prior
+/// `exit_lookback` bars, or `trailing_stop` below the highest high since
+/// entry; shorts mirror this. Stops are checked against closes and exited with
+/// market orders, so they fill at the close that breaches them.
+pub struct ChannelBreakoutStrategy {
+    symbol: String,
+    entry_lookback: usize,
+    exit_lookback: usize,
+    trailing_stop: Option<f64>,
+    mode: Direction,
+    allocation: f64,
+    /// `(high, low)` of prior bars
+    ranges: VecDeque<(f64, f64)>,
+    /// -1 short, 0 flat, 1 long
+    state: i8,
+    /// Best high (long) or low (short) since entry
+    extreme: f64,
+}
+
+impl ChannelBreakoutStrategy {
+    pub fn new(
+        symbol: String,
+        entry_lookback: usize,
+        exit_lookback: usize,
+        trailing_stop: Option<f64>,
+        mode: Direction,
+        allocation: f64,
+    ) -> Self {
+        Self {
+            symbol,
+            entry_lookback,
+            exit_lookback,
+            trailing_stop,
+            mode,
+            allocation,
+            ranges: VecDeque::new(),
+            state: 0,
+            extreme: 0.0,
+        }
+    }
+
+    /// Highest high and lowest low of the last `bars` prior bars
+    fn channel(&self, bars: usize) -> Option<(f64, f64)> {
+        if self.ranges.len() < bars {
+            return None;
+        }
+        Some(self.ranges.iter().rev().take(bars).fold(
+            (f64::NEG_INFINITY, f64::INFINITY),
+            |(high, low), &(h, l)| (high.max(h), low.min(l)),
+        ))
+    }
+
+    fn exit_signal(&self, bar: &Bar) -> bool {
+        let channel = self.channel(self.exit_lookback);
+        match self.state {
+            1 => {
+                channel.is_some_and(|(_, low)| bar.close < low)
+                    || self
+                        .trailing_stop
+                        .is_some_and(|stop| bar.close <= self.extreme * (1.0 - stop))
+            }
+            -1 => {
+                channel.is_some_and(|(high, _)| bar.close > high)
+                    || self
+                        .trailing_stop
+                        .is_some_and(|stop| bar.close >= self.extreme * (1.0 + stop))
             }
+            _ => false,
         }
+    }
 
-        // Get current position
-        let current_position = portfolio
-            .get_position(&self.symbol)
-            .map(|p| p.quantity)
-            .unwrap_or(0.0);
+    fn entry_signal(&self, bar: &Bar) -> i8 {
+        match self.channel(self.entry_lookback) {
+            Some((high, _)) if bar.close > high => 1,
+            Some((_, low)) if bar.close < low && self.mode == Direction::LongShort => -1,
+            _ => 0,
+        }
+    }
+}
+
+impl Strategy for ChannelBreakoutStrategy {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        if bar.symbol != self.symbol {
+            return vec![];
+        }
+        match self.state {
+            1 => self.extreme = self.extreme.max(bar.high),
+            -1 => self.extreme = self.extreme.min(bar.low),
+            _ => {}
+        }
+
+        let mut state = self.state;
+        if self.exit_signal(bar) {
+            state = 0;
+        }
+        if state == 0 {
+            state = self.entry_signal(bar);
+            self.extreme = if state == 1 { bar.high } else { bar.low };
+        }
+        push_bounded(
+            &mut self.ranges,
+            (bar.high, bar.low),
+            self.entry_lookback.max(self.exit_lookback),
+        );
+        if state == self.state {
+            return vec![];
+        }
+        self.state = state;
+
+        let target =
+            f64::from(state) * equity_fraction_shares(portfolio.equity, self.allocation, bar.close);
+        order_to_target(&self.symbol, position_of(portfolio, &self.symbol), target)
+    }
+
+    fn name(&self) -> &str {
+        "ChannelBreakout"
+    }
+}
+
+/// Long the `top_n` symbols by trailing `lookback` return, equally weighted.
+///
+/// Rankings are taken when the first bar of a rebalance timestamp arrives,
+/// from closes of earlier timestamps only. Each symbol is then traded to its
+/// target on its own bar, since fills happen at that bar's close.
+pub struct CrossSectionalMomentumStrategy {
+    symbols: Vec<String>,
+    lookback: usize,
+    top_n: usize,
+    rebalance_every: usize,
+    allocation: f64,
+    closes: BTreeMap<String, VecDeque<f64>>,
+    current_timestamp: Option<i64>,
+    timestamps_seen: usize,
+    rebalance_timestamp: Option<i64>,
+    weights: BTreeMap<String, f64>,
+}
+
+impl CrossSectionalMomentumStrategy {
+    pub fn new(
+        symbols: Vec<String>,
+        lookback: usize,
+        top_n: usize,
+        rebalance_every: usize,
+        allocation: f64,
+    ) -> Self {
+        Self {
+            symbols,
+            lookback,
+            top_n,
+            rebalance_every: rebalance_every.max(1),
+            allocation,
+            closes: BTreeMap::new(),
+            current_timestamp: None,
+            timestamps_seen: 0,
+            rebalance_timestamp: None,
+            weights: BTreeMap::new(),
+        }
+    }
+
+    /// Trailing returns for symbols with a full lookback window
+    fn trailing_returns(&self) -> Vec<(&str, f64)> {
+        self.symbols
+            .iter()
+            .filter_map(|symbol| {
+                let closes = self.closes.get(symbol)?;
+                if closes.len() <= self.lookback {
+                    return None;
+                }
+                let start = closes[closes.len() - 1 - self.lookback];
+                let end = closes[closes.len() - 1];
+                (start > 0.0).then(|| (symbol.as_str(), end / start - 1.0))
+            })
+            .collect()
+    }
 
-        // Calculate target position
-        let target_position = match self.calculate_target_position(bar.close, portfolio) {
-            Some(pos) => pos,
-            None => return vec![], // Not enough data yet
+    fn rebalance(&mut self, timestamp: i64) {
+        let mut ranked = self.trailing_returns();
+        // Only rank once every symbol can be compared
+        if ranked.len() < self.symbols.len() {
+            return;
+        }
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let weight = self.allocation / self.top_n as f64;
+        let winners: Vec<String> = ranked
+            .iter()
+            .take(self.top_n)
+            .map(|(symbol, _)| symbol.to_string())
+            .collect();
+        self.weights = self
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let w = if winners.contains(symbol) {
+                    weight
+                } else {
+                    0.0
+                };
+                (symbol.clone(), w)
+            })
+            .collect();
+        self.rebalance_timestamp = Some(timestamp);
+    }
+}
+
+impl Strategy for CrossSectionalMomentumStrategy {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        if !self.symbols.contains(&bar.symbol) {
+            return vec![];
+        }
+
+        if self.current_timestamp != Some(bar.timestamp) {
+            self.current_timestamp = Some(bar.timestamp);
+            self.timestamps_seen += 1;
+            if (self.timestamps_seen - 1).is_multiple_of(self.rebalance_every) {
+                self.rebalance(bar.timestamp);
+            }
+        }
+
+        let capacity = self.lookback + 1;
+        push_bounded(
+            self.closes.entry(bar.symbol.clone()).or_default(),
+            bar.close,
+            capacity,
+        );
+
+        if self.rebalance_timestamp != Some(bar.timestamp) || bar.close <= 0.0 {
+            return vec![];
+        }
+        let weight = self.weights.get(&bar.symbol).copied().unwrap_or(0.0);
+        let target = equity_fraction_shares(portfolio.equity, weight, bar.close);
+        order_to_target(&bar.symbol, position_of(portfolio, &bar.symbol), target)
+    }
+
+    fn name(&self) -> &str {
+        "CrossSectionalMomentum"
+    }
+}
+
+/// Long every symbol with weights inverse to its rolling volatility.
+///
+/// Weights sum to `allocation`; with a `vol_target` they are instead scaled so
+/// the portfolio volatility implied by the sample covariance of returns hits
+/// the target, never exceeding `allocation` gross. Rebalancing follows
+/// `CrossSectionalMomentum`: weights are set when the first bar of a
+/// rebalance timestamp arrives, from earlier closes, and each symbol is
+/// traded on its own bar.
+pub struct RiskParityStrategy {
+    symbols: Vec<String>,
+    vol_lookback: usize,
+    vol_target: Option<f64>,
+    rebalance_every: usize,
+    allocation: f64,
+    closes: BTreeMap<String, VecDeque<f64>>,
+    current_timestamp: Option<i64>,
+    timestamps_seen: usize,
+    rebalance_timestamp: Option<i64>,
+    weights: BTreeMap<String, f64>,
+}
+
+impl RiskParityStrategy {
+    pub fn new(
+        symbols: Vec<String>,
+        vol_lookback: usize,
+        vol_target: Option<f64>,
+        rebalance_every: usize,
+        allocation: f64,
+    ) -> Self {
+        Self {
+            symbols,
+            vol_lookback: vol_lookback.max(2),
+            vol_target,
+            rebalance_every: rebalance_every.max(1),
+            allocation,
+            closes: BTreeMap::new(),
+            current_timestamp: None,
+            timestamps_seen: 0,
+            rebalance_timestamp: None,
+            weights: BTreeMap::new(),
+        }
+    }
+
+    /// Demeaned returns of every symbol over the window, or `None` until all
+    /// symbols have a full window
+    fn demeaned_returns(&self) -> Option<Vec<Vec<f64>>> {
+        self.symbols
+            .iter()
+            .map(|symbol| {
+                let closes = self.closes.get(symbol)?;
+                if closes.len() <= self.vol_lookback || closes.iter().any(|c| *c <= 0.0) {
+                    return None;
+                }
+                let returns: Vec<f64> = closes
+                    .iter()
+                    .zip(closes.iter().skip(1))
+                    .map(|(prev, next)| next / prev - 1.0)
+                    .collect();
+                let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+                Some(returns.iter().map(|r| r - mean).collect())
+            })
+            .collect()
+    }
+
+    fn rebalance(&mut self, timestamp: i64) {
+        let Some(returns) = self.demeaned_returns() else {
+            return;
         };
+        let n = self.vol_lookback as f64;
+        let cov = |i: usize, j: usize| {
+            returns[i]
+                .iter()
+                .zip(&returns[j])
+                .map(|(a, b)| a * b)
+                .sum::<f64>()
+                / n
+        };
+        let vols: Vec<f64> = (0..returns.len()).map(|i| cov(i, i).sqrt()).collect();
+        // A constant price has no risk to balance against; wait for movement
+        if vols.iter().any(|v| *v < 1e-12) {
+            return;
+        }
 
-        // Generate order if position needs adjustment
-        let position_delta = target_position - current_position;
-        if position_delta.abs() > 0.1 {
-            // Only trade if delta is significant
-            let (side, quantity) = if position_delta > 0.0 {
-                (Side::Buy, position_delta)
-            } else {
-                (Side::Sell, -position_delta)
-            };
+        let inverse_sum: f64 = vols.iter().map(|v| 1.0 / v).sum();
+        let mut weights: Vec<f64> = vols.iter().map(|v| 1.0 / v / inverse_sum).collect();
+        let scale = match self.vol_target {
+            Some(target) => {
+                let variance: f64 = (0..weights.len())
+                    .flat_map(|i| (0..weights.len()).map(move |j| (i, j)))
+                    .map(|(i, j)| weights[i] * weights[j] * cov(i, j))
+                    .sum();
+                (target / variance.sqrt()).min(self.allocation)
+            }
+            None => self.allocation,
+        };
+        for weight in &mut weights {
+            *weight *= scale;
+        }
+
+        self.weights = self.symbols.iter().cloned().zip(weights).collect();
+        self.rebalance_timestamp = Some(timestamp);
+    }
+}
 
-            vec![Order {
-                symbol: self.symbol.clone(),
-                side,
-                quantity,
-                order_type: OrderType::Market,
-                limit_price: None,
-            }]
+impl Strategy for RiskParityStrategy {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        if !self.symbols.contains(&bar.symbol) {
+            return vec![];
+        }
+
+        if self.current_timestamp != Some(bar.timestamp) {
+            self.current_timestamp = Some(bar.timestamp);
+            self.timestamps_seen += 1;
+            if (self.timestamps_seen - 1).is_multiple_of(self.rebalance_every) {
+                self.rebalance(bar.timestamp);
+            }
+        }
+
+        push_bounded(
+            self.closes.entry(bar.symbol.clone()).or_default(),
+            bar.close,
+            self.vol_lookback + 1,
+        );
+
+        if self.rebalance_timestamp != Some(bar.timestamp) || bar.close <= 0.0 {
+            return vec![];
+        }
+        let weight = self.weights.get(&bar.symbol).copied().unwrap_or(0.0);
+        let target = equity_fraction_shares(portfolio.equity, weight, bar.close);
+        order_to_target(&bar.symbol, position_of(portfolio, &bar.symbol), target)
+    }
+
+    fn name(&self) -> &str {
+        "RiskParity"
+    }
+}
+
+/// Hold fixed weights of equity, rebalanced on the first timestamp of each
+/// calendar period and whenever a weight drifts more than `drift_band` from
+/// its target.
+///
+/// The decision is taken when the first bar of a timestamp arrives, with
+/// drift measured at the previous timestamp's closes; run it through a
+/// `TargetExecutor`, which trades each symbol to its weight on its own bar.
+pub struct FixedWeightsStrategy {
+    weights: BTreeMap<String, f64>,
+    rebalance: RebalanceFrequency,
+    drift_band: Option<f64>,
+    last_closes: BTreeMap<String, f64>,
+    current_timestamp: Option<i64>,
+    /// Calendar period of the last rebalance
+    rebalanced_period: Option<(i32, u32)>,
+}
+
+impl FixedWeightsStrategy {
+    pub fn new(
+        weights: BTreeMap<String, f64>,
+        rebalance: RebalanceFrequency,
+        drift_band: Option<f64>,
+    ) -> Self {
+        Self {
+            weights,
+            rebalance,
+            drift_band,
+            last_closes: BTreeMap::new(),
+            current_timestamp: None,
+            rebalanced_period: None,
+        }
+    }
+
+    /// `(year, period within year)` of a timestamp in seconds
+    fn period(&self, timestamp: i64) -> (i32, u32) {
+        use chrono::Datelike;
+        let date = chrono::DateTime::from_timestamp(timestamp, 0)
+            .unwrap_or_default()
+            .date_naive();
+        match self.rebalance {
+            RebalanceFrequency::Monthly => (date.year(), date.month0()),
+            RebalanceFrequency::Quarterly => (date.year(), date.month0() / 3),
+            RebalanceFrequency::Annually => (date.year(), 0),
+        }
+    }
+
+    fn drifted(&self, portfolio: &Portfolio) -> bool {
+        let Some(band) = self.drift_band else {
+            return false;
+        };
+        if portfolio.equity <= 0.0 {
+            return false;
+        }
+        self.weights.iter().any(|(symbol, target)| {
+            let price = self.last_closes.get(symbol).copied().unwrap_or(0.0);
+            let weight = position_of(portfolio, symbol) * price / portfolio.equity;
+            (weight - target).abs() > band
+        })
+    }
+}
+
+impl TargetStrategy for FixedWeightsStrategy {
+    fn targets(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<TargetPosition> {
+        if !self.weights.contains_key(&bar.symbol) {
+            return vec![];
+        }
+
+        let mut targets = vec![];
+        if self.current_timestamp != Some(bar.timestamp) {
+            self.current_timestamp = Some(bar.timestamp);
+            let period = self.period(bar.timestamp);
+            if self.rebalanced_period != Some(period) || self.drifted(portfolio) {
+                self.rebalanced_period = Some(period);
+                targets = self
+                    .weights
+                    .iter()
+                    .map(|(symbol, weight)| TargetPosition {
+                        symbol: symbol.clone(),
+                        target: Target::Weight(*weight),
+                    })
+                    .collect();
+            }
+        }
+        self.last_closes.insert(bar.symbol.clone(), bar.close);
+        targets
+    }
+
+    fn name(&self) -> &str {
+        "FixedWeights"
+    }
+}
+
+/// Trade the spread between two symbols when it strays `entry_z` standard
+/// deviations from its rolling mean, and close both legs once it reverts
+/// inside `exit_z`.
+///
+/// The hedge ratio is the OLS slope of `a` on `b` over the lookback window.
+/// Like `CrossSectionalMomentum`, signals are taken when the first bar of a
+/// timestamp arrives, from closes of earlier timestamps only, and each leg is
+/// traded on its own bar so both fill at the same timestamp.
+pub struct PairsStrategy {
+    symbol_a: String,
+    symbol_b: String,
+    lookback: usize,
+    entry_z: f64,
+    exit_z: f64,
+    allocation: f64,
+    /// Closes of completed timestamps, `(a, b)`
+    closes: VecDeque<(f64, f64)>,
+    /// Closes of the timestamp being received
+    pending: (Option<f64>, Option<f64>),
+    current_timestamp: Option<i64>,
+    /// -1 short the spread, 0 flat, 1 long the spread
+    state: i8,
+    signal_timestamp: Option<i64>,
+    /// Target shares of `(a, b)` set at the last signal
+    targets: (f64, f64),
+}
+
+impl PairsStrategy {
+    pub fn new(
+        symbol_a: String,
+        symbol_b: String,
+        lookback: usize,
+        entry_z: f64,
+        exit_z: f64,
+        allocation: f64,
+    ) -> Self {
+        Self {
+            symbol_a,
+            symbol_b,
+            lookback: lookback.max(2),
+            entry_z,
+            exit_z,
+            allocation,
+            closes: VecDeque::new(),
+            pending: (None, None),
+            current_timestamp: None,
+            state: 0,
+            signal_timestamp: None,
+            targets: (0.0, 0.0),
+        }
+    }
+
+    /// OLS slope of `a` on `b` over the window
+    fn hedge_ratio(&self) -> Option<f64> {
+        let n = self.closes.len() as f64;
+        let mean_a = self.closes.iter().map(|(a, _)| a).sum::<f64>() / n;
+        let mean_b = self.closes.iter().map(|(_, b)| b).sum::<f64>() / n;
+        let (cov, var_b) = self.closes.iter().fold((0.0, 0.0), |(cov, var), (a, b)| {
+            (
+                cov + (a - mean_a) * (b - mean_b),
+                var + (b - mean_b).powi(2),
+            )
+        });
+        (var_b > 1e-12).then(|| cov / var_b)
+    }
+
+    /// Hedge ratio and z-score of the latest spread within the window
+    fn spread_z(&self) -> Option<(f64, f64)> {
+        if self.closes.len() < self.lookback {
+            return None;
+        }
+        let beta = self.hedge_ratio()?;
+        let spreads: Vec<f64> = self.closes.iter().map(|(a, b)| a - beta * b).collect();
+        let mean = spreads.iter().sum::<f64>() / spreads.len() as f64;
+        let variance =
+            spreads.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / spreads.len() as f64;
+        let std = variance.sqrt();
+        let latest = *spreads.last()?;
+        (std > 1e-12).then(|| (beta, (latest - mean) / std))
+    }
+
+    /// Close out the previous timestamp and re-evaluate the signal
+    fn on_new_timestamp(&mut self, timestamp: i64, equity: f64) {
+        if let (Some(a), Some(b)) = self.pending {
+            push_bounded(&mut self.closes, (a, b), self.lookback);
+        }
+        self.pending = (None, None);
+
+        let Some((beta, z)) = self.spread_z() else {
+            return;
+        };
+        let state = match self.state {
+            0 if z <= -self.entry_z => 1,
+            0 if z >= self.entry_z => -1,
+            1 if z >= -self.exit_z => 0,
+            -1 if z <= self.exit_z => 0,
+            current => current,
+        };
+        if state == self.state {
+            return;
+        }
+        self.state = state;
+        self.signal_timestamp = Some(timestamp);
+
+        // Split gross exposure so |shares_a| * a + |shares_b| * b = allocation * equity
+        let (price_a, price_b) = *self.closes.back().expect("spread_z needs closes");
+        let gross_per_unit = price_a + beta.abs() * price_b;
+        let units = if gross_per_unit > 0.0 {
+            f64::from(state) * equity * self.allocation / gross_per_unit
         } else {
-            vec![]
+            0.0
+        };
+        self.targets = (units, -beta * units);
+    }
+}
+
+impl Strategy for PairsStrategy {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        let is_a = bar.symbol == self.symbol_a;
+        if !is_a && bar.symbol != self.symbol_b {
+            return vec![];
+        }
+
+        if self.current_timestamp != Some(bar.timestamp) {
+            self.current_timestamp = Some(bar.timestamp);
+            self.on_new_timestamp(bar.timestamp, portfolio.equity);
+        }
+        if is_a {
+            self.pending.0 = Some(bar.close);
+        } else {
+            self.pending.1 = Some(bar.close);
         }
+
+        if self.signal_timestamp != Some(bar.timestamp) {
+            return vec![];
+        }
+        let target = if is_a { self.targets.0 } else { self.targets.1 };
+        order_to_target(&bar.symbol, position_of(portfolio, &bar.symbol), target)
     }
 
     fn name(&self) -> &str {
-        "TsMomentum"
+        "Pairs"
+    }
+}
+
+/// The sleeves of a `multi_strategy` spec, each with its strategy built
+pub fn build_multi_strategy(sleeves: &[SleeveSpec]) -> Result<MultiStrategy> {
+    Ok(MultiStrategy::new(
+        sleeves
+            .iter()
+            .map(|sleeve| {
+                Ok(Sleeve {
+                    name: sleeve.name.clone(),
+                    allocation: sleeve.allocation,
+                    strategy: build_strategy(&sleeve.strategy)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+    ))
+}
+
+/// Child of an `EnsembleStrategy` and the book it trades on paper
+struct EnsembleChild {
+    weight: f64,
+    strategy: Box<dyn Strategy>,
+    /// Shares the child holds, assuming each of its orders fills
+    positions: BTreeMap<String, f64>,
+}
+
+/// Runs child strategies side by side and combines their trades.
+///
+/// Every child sees the whole portfolio's equity but only its own positions,
+/// tracked as if its orders always fill. With `Netting::Net` the ensemble
+/// trades each symbol, on that symbol's bar, to the weighted sum of the
+/// children's positions, so offsetting signals never reach the broker; with
+/// `Netting::Gross` each child order is passed on scaled by its weight.
+pub struct EnsembleStrategy {
+    children: Vec<EnsembleChild>,
+    netting: Netting,
+    last_closes: BTreeMap<String, f64>,
+}
+
+impl EnsembleStrategy {
+    pub fn new(members: Vec<(f64, Box<dyn Strategy>)>, netting: Netting) -> Self {
+        Self {
+            children: members
+                .into_iter()
+                .map(|(weight, strategy)| EnsembleChild {
+                    weight,
+                    strategy,
+                    positions: BTreeMap::new(),
+                })
+                .collect(),
+            netting,
+            last_closes: BTreeMap::new(),
+        }
+    }
+
+    /// The child's paper book, valued at the latest closes seen
+    fn child_portfolio(&self, child: &EnsembleChild, portfolio: &Portfolio) -> Portfolio {
+        let mut book = Portfolio::new(portfolio.equity);
+        book.timestamp = portfolio.timestamp;
+        for (symbol, quantity) in &child.positions {
+            let price = self.last_closes.get(symbol).copied().unwrap_or(0.0);
+            book.cash -= quantity * price;
+            book.get_position_mut(symbol).quantity = *quantity;
+        }
+        book
+    }
+
+    /// Orders the children place through `place`, scaled by their weights,
+    /// with each child's positions updated as if its orders fill
+    fn child_orders(
+        &mut self,
+        portfolio: &Portfolio,
+        mut place: impl FnMut(&mut dyn Strategy, &Portfolio) -> Vec<Order>,
+    ) -> Vec<Order> {
+        let mut gross_orders = Vec::new();
+        for i in 0..self.children.len() {
+            let book = self.child_portfolio(&self.children[i], portfolio);
+            let child = &mut self.children[i];
+            for order in place(child.strategy.as_mut(), &book) {
+                let signed = match order.side {
+                    Side::Buy => order.quantity,
+                    Side::Sell => -order.quantity,
+                };
+                *child.positions.entry(order.symbol.clone()).or_default() += signed;
+                gross_orders.push(Order {
+                    quantity: order.quantity * child.weight,
+                    ..order
+                });
+            }
+        }
+        gross_orders
+    }
+
+    /// Weighted sum of the children's positions in `symbol`
+    fn net_target(&self, symbol: &str) -> f64 {
+        self.children
+            .iter()
+            .map(|c| c.weight * c.positions.get(symbol).copied().unwrap_or(0.0))
+            .sum()
+    }
+}
+
+impl Strategy for EnsembleStrategy {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        self.last_closes.insert(bar.symbol.clone(), bar.close);
+
+        let gross_orders = self.child_orders(portfolio, |child, book| child.on_bar(bar, book));
+        match self.netting {
+            Netting::Gross => gross_orders,
+            Netting::Net => order_to_target(
+                &bar.symbol,
+                position_of(portfolio, &bar.symbol),
+                self.net_target(&bar.symbol),
+            ),
+        }
+    }
+
+    fn on_timer(&mut self, timer: &TimerEvent, portfolio: &Portfolio) -> Vec<Order> {
+        let gross_orders = self.child_orders(portfolio, |child, book| child.on_timer(timer, book));
+        match self.netting {
+            Netting::Gross => gross_orders,
+            // Netted positions are traded to on each symbol's next bar
+            Netting::Net => vec![],
+        }
+    }
+
+    /// The longest warm-up of any child
+    fn warmup_bars(&self) -> usize {
+        self.children
+            .iter()
+            .map(|c| c.strategy.warmup_bars())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn name(&self) -> &str {
+        "Ensemble"
     }
 }
 
@@ -210,4 +1281,413 @@ mod tests {
         assert_eq!(hashes[0], hashes[1]);
         assert_eq!(hashes[1], hashes[2]);
     }
+
+    fn bar(timestamp: i64, symbol: &str, close: f64) -> Bar {
+        Bar {
+            timestamp,
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000.0,
+        }
+    }
+
+    #[test]
+    fn sma_crossover_enters_and_exits_on_signal_flips() {
+        let mut strategy = SmaCrossoverStrategy::new(
+            "AAPL".to_string(),
+            2,
+            4,
+            1.0,
+            MovingAverage::Sma,
+            Direction::LongOnly,
+        );
+        let portfolio = Portfolio::new(10_000.0);
+        let closes = [10.0, 10.0, 10.0, 10.0, 12.0, 13.0, 9.0, 8.0];
+        let sides: Vec<Option<Side>> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                strategy
+                    .on_bar(&bar(i as i64, "AAPL", *c), &portfolio)
+                    .first()
+                    .map(|o| o.side)
+            })
+            .collect();
+        assert_eq!(sides[4], Some(Side::Buy));
+        assert_eq!(sides[5], None);
+        // The test portfolio never fills, so the exit is a no-op order
+        assert!(sides[6..].iter().all(|s| s.is_none()));
+        assert_eq!(strategy.position, 0);
+    }
+
+    #[test]
+    fn ema_crossover_goes_short_in_long_short_mode() {
+        let mut strategy = SmaCrossoverStrategy::new(
+            "AAPL".to_string(),
+            2,
+            4,
+            1.0,
+            MovingAverage::Ema,
+            Direction::LongShort,
+        );
+        let portfolio = Portfolio::new(10_000.0);
+        let closes = [10.0, 11.0, 12.0, 13.0, 14.0, 9.0, 8.0];
+        let orders: Vec<Vec<Order>> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| strategy.on_bar(&bar(i as i64, "AAPL", *c), &portfolio))
+            .collect();
+        assert!(orders[..3].iter().all(|o| o.is_empty()));
+        // Warmed up in an uptrend: long
+        assert_eq!(orders[3][0].side, Side::Buy);
+        assert!((orders[3][0].quantity - 10_000.0 / 13.0).abs() < 1e-9);
+        // The drop to 9 pulls the fast EMA under the slow one: short
+        assert_eq!(orders[5][0].side, Side::Sell);
+        assert!((orders[5][0].quantity - 10_000.0 / 9.0).abs() < 1e-9);
+        assert_eq!(strategy.position, -1);
+    }
+
+    #[test]
+    fn mean_reversion_fades_extremes() {
+        let mut strategy = MeanReversionStrategy::new("AAPL".to_string(), 5, 1.5, 0.0, 1.0);
+        let portfolio = Portfolio::new(10_000.0);
+        for (i, close) in [10.0, 10.1, 9.9, 10.0, 10.0].iter().enumerate() {
+            assert!(strategy
+                .on_bar(&bar(i as i64, "AAPL", *close), &portfolio)
+                .is_empty());
+        }
+        let orders = strategy.on_bar(&bar(5, "AAPL", 8.0), &portfolio);
+        assert_eq!(orders[0].side, Side::Buy);
+        assert_eq!(strategy.state, 1);
+    }
+
+    #[test]
+    fn mean_reversion_shorts_spikes_and_exits_on_reversion() {
+        let mut strategy = MeanReversionStrategy::new("AAPL".to_string(), 5, 1.5, 0.5, 1.0);
+        let portfolio = Portfolio::new(10_000.0);
+        for (i, close) in [10.0, 10.1, 9.9, 10.0, 10.0].iter().enumerate() {
+            strategy.on_bar(&bar(i as i64, "AAPL", *close), &portfolio);
+        }
+        let orders = strategy.on_bar(&bar(5, "AAPL", 12.0), &portfolio);
+        assert_eq!(orders[0].side, Side::Sell);
+        assert_eq!(strategy.state, -1);
+
+        // Still stretched: hold the short
+        strategy.on_bar(&bar(6, "AAPL", 12.5), &portfolio);
+        assert_eq!(strategy.state, -1);
+        // Back near the mean: flat
+        for (i, close) in [10.0, 10.0, 10.0].iter().enumerate() {
+            strategy.on_bar(&bar(7 + i as i64, "AAPL", *close), &portfolio);
+        }
+        assert_eq!(strategy.state, 0);
+    }
+
+    #[test]
+    fn test_mean_reversion_determinism() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        // Oscillating closes so the strategy enters and exits repeatedly
+        let bars: Vec<Bar> = (0..60)
+            .map(|i| {
+                let close = 100.0 + 5.0 * (i as f64 * 0.7).sin() + (i % 7) as f64 * 0.3;
+                bar(i * 1000, "AAPL", close)
+            })
+            .collect();
+
+        let mut hashes = Vec::new();
+        let mut order_counts = Vec::new();
+
+        for _ in 0..3 {
+            let mut strategy = MeanReversionStrategy::new("AAPL".to_string(), 10, 1.0, 0.2, 0.5);
+            let portfolio = Portfolio::new(10000.0);
+
+            let mut hasher = DefaultHasher::new();
+            let mut count = 0;
+            for bar in &bars {
+                let orders = strategy.on_bar(bar, &portfolio);
+                orders.len().hash(&mut hasher);
+                count += orders.len();
+                for order in orders {
+                    order.quantity.to_bits().hash(&mut hasher);
+                    (order.side == Side::Buy).hash(&mut hasher);
+                }
+            }
+
+            hashes.push(hasher.finish());
+            order_counts.push(count);
+        }
+
+        // All runs should produce the same hash
+        assert!(order_counts[0] > 0);
+        assert_eq!(hashes[0], hashes[1]);
+        assert_eq!(hashes[1], hashes[2]);
+    }
+
+    #[test]
+    fn cross_sectional_momentum_holds_the_leaders() {
+        let symbols = vec!["A".to_string(), "B".to_string(), "C".to_string()];
+        let mut strategy = CrossSectionalMomentumStrategy::new(symbols, 1, 1, 1, 1.0);
+        let portfolio = Portfolio::new(9_000.0);
+        let mut orders = Vec::new();
+        for (t, closes) in [[10.0, 10.0, 10.0], [11.0, 12.0, 9.0], [11.0, 12.0, 9.0]]
+            .iter()
+            .enumerate()
+        {
+            for (symbol, close) in ["A", "B", "C"].iter().zip(closes) {
+                orders.extend(strategy.on_bar(&bar(t as i64, symbol, *close), &portfolio));
+            }
+        }
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].symbol, "B");
+        assert_eq!(orders[0].side, Side::Buy);
+        assert!((orders[0].quantity - 750.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pairs_trades_both_legs_against_the_spread() {
+        let mut strategy = PairsStrategy::new("A".to_string(), "B".to_string(), 6, 1.5, 0.5, 1.0);
+        let portfolio = Portfolio::new(10_000.0);
+        // A tracks 2 * B until A jumps at t = 6
+        let b_closes = [10.0, 10.5, 10.2, 10.8, 10.4, 10.6, 10.5, 10.5];
+        let mut orders_by_t = Vec::new();
+        for (t, b) in b_closes.iter().enumerate() {
+            let a = if t == 6 {
+                2.0 * b + 3.0
+            } else {
+                2.0 * b + 0.01 * t as f64
+            };
+            let mut orders = strategy.on_bar(&bar(t as i64, "A", a), &portfolio);
+            orders.extend(strategy.on_bar(&bar(t as i64, "B", *b), &portfolio));
+            orders_by_t.push(orders);
+        }
+
+        // The spike at t = 6 is acted on at t = 7, selling A and buying the hedge
+        assert!(orders_by_t[..7].iter().all(|o| o.is_empty()));
+        let entry = &orders_by_t[7];
+        assert_eq!(entry.len(), 2);
+        assert_eq!((entry[0].symbol.as_str(), entry[0].side), ("A", Side::Sell));
+        assert_eq!((entry[1].symbol.as_str(), entry[1].side), ("B", Side::Buy));
+        // Hedge ratio near 2: the B leg holds about twice the A leg's shares
+        assert!((entry[1].quantity / entry[0].quantity - 2.0).abs() < 0.2);
+        let gross = entry[0].quantity * (2.0 * 10.5 + 3.0) + entry[1].quantity * 10.5;
+        assert!((gross - 10_000.0).abs() < 1e-6);
+        assert_eq!(strategy.state, -1);
+    }
+
+    #[test]
+    fn risk_parity_weights_inverse_to_volatility_at_target() {
+        let symbols = vec!["A".to_string(), "B".to_string()];
+        let mut strategy = RiskParityStrategy::new(symbols, 4, Some(0.004), 1, 1.0);
+        let portfolio = Portfolio::new(10_000.0);
+        // A swings +/-2% and B +/-1% in step: vols 0.02 and 0.01
+        let (mut a, mut b) = (100.0, 50.0);
+        let mut orders = Vec::new();
+        for t in 0..6 {
+            if t > 0 {
+                let sign = if t % 2 == 1 { 1.0 } else { -1.0 };
+                a *= 1.0 + 0.02 * sign;
+                b *= 1.0 + 0.01 * sign;
+            }
+            orders = strategy.on_bar(&bar(t, "A", a), &portfolio);
+            orders.extend(strategy.on_bar(&bar(t, "B", b), &portfolio));
+        }
+
+        // Inverse-vol weights 1/3 and 2/3 give a perfectly correlated
+        // portfolio vol of 0.04/3; scaling to 0.004 leaves 0.1 and 0.2
+        assert!((strategy.weights["A"] - 0.1).abs() < 1e-9);
+        assert!((strategy.weights["B"] - 0.2).abs() < 1e-9);
+        assert_eq!(orders.len(), 2);
+        assert!((orders[0].quantity - 10_000.0 * 0.1 / a).abs() < 1e-6);
+        assert!((orders[1].quantity - 10_000.0 * 0.2 / b).abs() < 1e-6);
+
+        // Without a target the weights fill the allocation
+        let mut unscaled = RiskParityStrategy::new(vec!["A".into(), "B".into()], 4, None, 1, 0.9);
+        unscaled.closes = strategy.closes.clone();
+        unscaled.rebalance(6);
+        assert!((unscaled.weights["A"] - 0.3).abs() < 1e-9);
+        assert!((unscaled.weights["B"] - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fixed_weights_rebalance_monthly_and_on_drift() {
+        const DAY: i64 = 86_400;
+        // 2024-01-30
+        let jan_30 = 1_706_572_800;
+        let weights = BTreeMap::from([("SPY".to_string(), 0.6), ("TLT".to_string(), 0.4)]);
+        let fixed = |drift_band| {
+            TargetExecutor::new(
+                FixedWeightsStrategy::new(weights.clone(), RebalanceFrequency::Monthly, drift_band),
+                ExecutionRules::default(),
+            )
+        };
+        let mut strategy = fixed(None);
+        let portfolio = Portfolio::new(10_000.0);
+        let day =
+            |strategy: &mut TargetExecutor<FixedWeightsStrategy>, t: i64, portfolio: &Portfolio| {
+                let mut orders = strategy.on_bar(&bar(t, "SPY", 100.0), portfolio);
+                orders.extend(strategy.on_bar(&bar(t, "TLT", 50.0), portfolio));
+                orders
+            };
+
+        let first = day(&mut strategy, jan_30, &portfolio);
+        let quantities: Vec<f64> = first.iter().map(|o| o.quantity).collect();
+        assert_eq!(quantities, vec![60.0, 80.0]);
+        assert!(day(&mut strategy, jan_30 + DAY, &portfolio).is_empty());
+        // February 1st starts a new month
+        assert_eq!(day(&mut strategy, jan_30 + 2 * DAY, &portfolio).len(), 2);
+
+        // Holding 60 SPY and 80 TLT is on target at equity 10,000; at 12,000
+        // SPY is 50%, outside a 5% band
+        let mut banded = fixed(Some(0.05));
+        let mut held = Portfolio::new(0.0);
+        held.get_position_mut("SPY").quantity = 60.0;
+        held.get_position_mut("TLT").quantity = 80.0;
+        held.equity = 10_000.0;
+        assert!(day(&mut banded, jan_30, &held).is_empty());
+        assert!(day(&mut banded, jan_30 + DAY, &held).is_empty());
+        held.equity = 12_000.0;
+        let orders = day(&mut banded, jan_30 + DAY + 3600, &held);
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].side, Side::Buy);
+    }
+
+    /// Holds a fixed number of shares of whatever it sees
+    struct FixedShares(f64);
+
+    impl Strategy for FixedShares {
+        fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+            order_to_target(&bar.symbol, position_of(portfolio, &bar.symbol), self.0)
+        }
+
+        fn name(&self) -> &str {
+            "FixedShares"
+        }
+    }
+
+    fn ensemble(weights: [f64; 2], netting: Netting) -> EnsembleStrategy {
+        EnsembleStrategy::new(
+            vec![
+                (weights[0], Box::new(FixedShares(100.0))),
+                (weights[1], Box::new(FixedShares(-100.0))),
+            ],
+            netting,
+        )
+    }
+
+    #[test]
+    fn ensemble_nets_weighted_child_positions() {
+        let portfolio = Portfolio::new(10_000.0);
+
+        // Equal and opposite children cancel out
+        let mut netted = ensemble([0.5, 0.5], Netting::Net);
+        assert!(netted.on_bar(&bar(0, "A", 10.0), &portfolio).is_empty());
+
+        let mut tilted = ensemble([0.75, 0.25], Netting::Net);
+        let orders = tilted.on_bar(&bar(0, "A", 10.0), &portfolio);
+        assert_eq!(orders.len(), 1);
+        assert_eq!((orders[0].side, orders[0].quantity), (Side::Buy, 50.0));
+        // Children see their own books, so they do not trade again
+        tilted.on_bar(&bar(1, "A", 11.0), &portfolio);
+        let held: Vec<f64> = tilted.children.iter().map(|c| c.positions["A"]).collect();
+        assert_eq!(held, vec![100.0, -100.0]);
+
+        let mut gross = ensemble([0.5, 0.5], Netting::Gross);
+        let orders = gross.on_bar(&bar(0, "A", 10.0), &portfolio);
+        let trades: Vec<(Side, f64)> = orders.iter().map(|o| (o.side, o.quantity)).collect();
+        assert_eq!(trades, vec![(Side::Buy, 50.0), (Side::Sell, 50.0)]);
+    }
+
+    /// Breakout state after each close; the test portfolio never fills
+    fn breakout_states(strategy: &mut ChannelBreakoutStrategy, closes: &[f64]) -> Vec<i8> {
+        let portfolio = Portfolio::new(10_000.0);
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                strategy.on_bar(&bar(i as i64, "AAPL", *c), &portfolio);
+                strategy.state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn breakout_enters_on_new_high_and_exits_on_trailing_stop() {
+        let mut strategy = ChannelBreakoutStrategy::new(
+            "AAPL".to_string(),
+            3,
+            3,
+            Some(0.1),
+            Direction::LongOnly,
+            1.0,
+        );
+        let portfolio = Portfolio::new(10_000.0);
+        for (i, close) in [10.0, 10.0, 10.0].iter().enumerate() {
+            assert!(strategy
+                .on_bar(&bar(i as i64, "AAPL", *close), &portfolio)
+                .is_empty());
+        }
+        let orders = strategy.on_bar(&bar(3, "AAPL", 11.0), &portfolio);
+        assert_eq!(orders[0].side, Side::Buy);
+        assert!((orders[0].quantity - 10_000.0 / 11.0).abs() < 1e-9);
+
+        // 10.7 is above the exit channel but more than 10% under the 12 peak;
+        // long-only, the later downside breakout does not open a short
+        let states = breakout_states(&mut strategy, &[12.0, 10.7, 9.0]);
+        assert_eq!(states, vec![1, 0, 0]);
+    }
+
+    #[test]
+    fn breakout_short_exits_on_opposite_channel_and_reverses() {
+        let mut strategy =
+            ChannelBreakoutStrategy::new("AAPL".to_string(), 3, 2, None, Direction::LongShort, 1.0);
+        // Short below the 3-bar low, hold inside the 2-bar exit channel, then
+        // a close above both channels exits and flips long
+        let states = breakout_states(&mut strategy, &[10.0, 10.0, 10.0, 9.0, 9.5, 10.5, 10.4]);
+        assert_eq!(states, vec![0, 0, 0, -1, -1, 1, 1]);
+    }
+
+    #[test]
+    fn registry_builds_every_spec_variant() {
+        let specs = [
+            serde_json::json!({"type": "ts_momentum", "symbol": "A", "lookback": 5, "vol_target": 0.1, "vol_lookback": 5}),
+            serde_json::json!({"type": "buy_and_hold", "symbol": "A"}),
+            serde_json::json!({"type": "sma_crossover", "symbol": "A", "fast": 2, "slow": 5}),
+            serde_json::json!({"type": "mean_reversion", "symbol": "A", "lookback": 5, "entry_z": 2.0}),
+            serde_json::json!({"type": "cross_sectional_momentum", "symbols": ["A", "B"], "lookback": 5, "top_n": 1}),
+            serde_json::json!({"type": "pairs", "symbol_a": "A", "symbol_b": "B", "lookback": 20, "entry_z": 2.0}),
+            serde_json::json!({"type": "channel_breakout", "symbol": "A", "entry_lookback": 20, "exit_lookback": 10}),
+            serde_json::json!({"type": "risk_parity", "symbols": ["A", "B"], "vol_lookback": 20}),
+            serde_json::json!({"type": "fixed_weights", "weights": {"A": 0.6, "B": 0.4}, "rebalance": "quarterly"}),
+            serde_json::json!({"type": "ensemble", "members": [
+                {"weight": 0.5, "strategy": {"type": "buy_and_hold", "symbol": "A"}},
+                {"weight": 0.5, "strategy": {"type": "sma_crossover", "symbol": "A", "fast": 2, "slow": 5}}
+            ]}),
+        ];
+        let names: Vec<String> = specs
+            .into_iter()
+            .map(|value| {
+                let spec: StrategySpec = serde_json::from_value(value).unwrap();
+                build_strategy(&spec).unwrap().name().to_string()
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "TsMomentum",
+                "BuyAndHold",
+                "SmaCrossover",
+                "MeanReversion",
+                "CrossSectionalMomentum",
+                "Pairs",
+                "ChannelBreakout",
+                "RiskParity",
+                "FixedWeights",
+                "Ensemble"
+            ]
+        );
+    }
 }
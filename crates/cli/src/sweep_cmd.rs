@@ -0,0 +1,455 @@
+use anyhow::{Context, Result};
+use crv_verifier::CRVReport;
+use hipcortex::{Artifact, Repository, Trace};
+use rayon::prelude::*;
+use schema::BacktestStats;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backtest_cmd::{execute_backtest, write_effective_spec, write_outputs, BacktestOutcome};
+use crate::checkpoint::{load_completed, write_checkpoint};
+use crate::data::{load_dataset, LoadedDataset};
+use crate::lineage::{commit_run, RunHashes};
+use crate::spec::{apply_overrides, parse_spec, set_json_path, BacktestSpec};
+
+/// Parameter sweep definition.
+///
+/// `grid` maps dotted spec paths to candidate values; every combination is run.
+/// Seeds and cost models are swept like any other field, e.g.
+/// `"seed": [1, 2, 3]` or `"cost_model": [{"type": "zero"}, ...]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepSpec {
+    pub base: serde_json::Value,
+    #[serde(default)]
+    pub grid: BTreeMap<String, Vec<serde_json::Value>>,
+    /// BacktestStats field used for ranking; nested fields are dotted, e.g.
+    /// `costs.slippage`
+    #[serde(default = "default_objective")]
+    pub objective: String,
+    /// Rank ascending instead of descending (e.g. for max_drawdown)
+    #[serde(default)]
+    pub minimize: bool,
+}
+
+fn default_objective() -> String {
+    "sharpe_ratio".to_string()
+}
+
+/// One expanded grid point
+#[derive(Debug, Clone)]
+pub struct SweepRun {
+    pub run_id: String,
+    pub params: BTreeMap<String, serde_json::Value>,
+    pub spec: BacktestSpec,
+}
+
+/// Ranked summary row for one run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepResultRow {
+    pub rank: usize,
+    pub run_id: String,
+    pub params: BTreeMap<String, serde_json::Value>,
+    pub objective: f64,
+    pub total_return: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub num_trades: usize,
+    pub total_commission: f64,
+    pub crv_passed: bool,
+    pub crv_violations: usize,
+    pub hashes: Option<RunHashes>,
+    /// Every statistic of the run, for objectives beyond the columns above
+    pub stats: BacktestStats,
+}
+
+impl SweepSpec {
+    /// Expand the grid into concrete specs, in deterministic (sorted key) order
+    pub fn expand(&self) -> Result<Vec<SweepRun>> {
+        let mut combinations: Vec<BTreeMap<String, serde_json::Value>> = vec![BTreeMap::new()];
+        for (path, values) in &self.grid {
+            if values.is_empty() {
+                anyhow::bail!("sweep grid entry '{}' has no values", path);
+            }
+            // The data is loaded once for every combination
+            if path == "data_pipeline" || path.starts_with("data_pipeline.") {
+                anyhow::bail!(
+                    "sweep grid entry '{}' cannot vary the data pipeline; set it in the base spec",
+                    path
+                );
+            }
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combo| {
+                    values.iter().map(move |value| {
+                        let mut next = combo.clone();
+                        next.insert(path.clone(), value.clone());
+                        next
+                    })
+                })
+                .collect();
+        }
+
+        combinations
+            .into_iter()
+            .enumerate()
+            .map(|(i, params)| {
+                let mut value = self.base.clone();
+                for (path, v) in &params {
+                    set_json_path(&mut value, path, v.clone())
+                        .with_context(|| format!("Invalid sweep grid path '{}'", path))?;
+                }
+                let spec = parse_spec(value)
+                    .with_context(|| format!("Sweep combination {} is not a valid spec", i + 1))?;
+                Ok(SweepRun {
+                    run_id: format!("run_{:04}", i + 1),
+                    params,
+                    spec,
+                })
+            })
+            .collect()
+    }
+}
+
+pub fn run_sweep(
+    sweep_path: &Path,
+    overrides: &[String],
+    data_path: &Path,
+    out_dir: &Path,
+    jobs: Option<usize>,
+    repo_path: Option<&PathBuf>,
+    resume: bool,
+) -> Result<Vec<(String, CRVReport)>> {
+    let sweep_str = fs::read_to_string(sweep_path).context("Failed to read sweep spec file")?;
+    let mut sweep: SweepSpec =
+        serde_json::from_str(&sweep_str).context("Failed to parse sweep spec JSON")?;
+    apply_overrides(&mut sweep.base, overrides)?;
+    let runs = sweep.expand()?;
+
+    // All combinations share the data pipeline of the base spec, which the
+    // grid cannot vary
+    let dataset = load_dataset(data_path, &runs[0].spec.data_pipeline)?;
+    println!(
+        "Sweeping {} combination(s) over {} bars",
+        runs.len(),
+        dataset.bars.len()
+    );
+
+    fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+    let dataset_hash = engine::canonical_json_hash(&dataset.bars)?;
+
+    let execute = || {
+        runs.par_iter()
+            .map(|run| run_point(run, &dataset, &dataset_hash, out_dir, resume))
+            .collect::<Result<Vec<_>>>()
+    };
+    let results = match jobs {
+        Some(n) => rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .context("Failed to build sweep thread pool")?
+            .install(execute)?,
+        None => execute()?,
+    };
+    let resumed = results.iter().filter(|(_, resumed)| *resumed).count();
+    if resume {
+        println!(
+            "Resumed {} of {} run(s) from verified checkpoints",
+            resumed,
+            runs.len()
+        );
+    }
+    let outcomes: Vec<BacktestOutcome> = results.into_iter().map(|(outcome, _)| outcome).collect();
+
+    let mut hashes: Vec<Option<RunHashes>> = vec![None; runs.len()];
+    if let Some(repo_path) = repo_path {
+        let mut repo = Repository::open(repo_path).context("Failed to open repository")?;
+        let sweep_trace = Artifact::Trace(Trace {
+            operation: "sweep".to_string(),
+            inputs: vec![dataset_hash.clone()],
+            output: out_dir.display().to_string(),
+            timestamp: dataset.bars.last().map(|b| b.timestamp).unwrap_or(0),
+            metadata: serde_json::to_value(&sweep)?,
+        });
+        let sweep_hash = repo
+            .commit(&sweep_trace, "Sweep spec", vec![])
+            .context("Failed to commit sweep spec")?;
+        println!("Committed sweep spec: {}", sweep_hash);
+
+        for (i, (run, outcome)) in runs.iter().zip(&outcomes).enumerate() {
+            hashes[i] = Some(commit_run(
+                &mut repo,
+                &run.spec,
+                &dataset_hash,
+                outcome,
+                &[sweep_hash.as_hex().to_string()],
+                &format!("Sweep {}", run.run_id),
+            )?);
+        }
+    }
+
+    let mut rows = Vec::with_capacity(runs.len());
+    for ((run, outcome), hashes) in runs.iter().zip(&outcomes).zip(hashes) {
+        let objective = objective_value(&outcome.stats, &sweep.objective)?;
+
+        rows.push(SweepResultRow {
+            rank: 0,
+            run_id: run.run_id.clone(),
+            params: run.params.clone(),
+            objective,
+            total_return: outcome.stats.total_return,
+            sharpe_ratio: outcome.stats.sharpe_ratio,
+            max_drawdown: outcome.stats.max_drawdown,
+            num_trades: outcome.stats.num_trades,
+            total_commission: outcome.stats.total_commission,
+            crv_passed: outcome.crv_report.passed,
+            crv_violations: outcome.crv_report.violation_count(),
+            hashes,
+            stats: outcome.stats.clone(),
+        });
+    }
+
+    rank_rows(&mut rows, sweep.minimize);
+    write_results(&rows, out_dir)?;
+
+    println!("\n=== Sweep Results ({}) ===", sweep.objective);
+    for row in rows.iter().take(10) {
+        println!(
+            "#{:<3} {}  {}={:.4}  return={:.2}%  crv={}",
+            row.rank,
+            row.run_id,
+            sweep.objective,
+            row.objective,
+            row.total_return * 100.0,
+            if row.crv_passed { "pass" } else { "fail" }
+        );
+    }
+    println!(
+        "Wrote sweep results to {:?}",
+        out_dir.join("sweep_results.csv")
+    );
+
+    Ok(runs
+        .iter()
+        .zip(outcomes)
+        .map(|(run, outcome)| (run.run_id.clone(), outcome.crv_report))
+        .collect())
+}
+
+/// Execute one grid point into its run directory.
+///
+/// When resuming, a run whose checkpoint matches its spec, the data and its
+/// output files is loaded instead of re-run.
+fn run_point(
+    run: &SweepRun,
+    dataset: &LoadedDataset,
+    dataset_hash: &str,
+    out_dir: &Path,
+    resume: bool,
+) -> Result<(BacktestOutcome, bool)> {
+    let run_dir = out_dir.join("runs").join(&run.run_id);
+    let spec_hash = engine::canonical_json_hash(&run.spec)?;
+    if resume {
+        if let Some(outcome) = load_completed(&run_dir, &spec_hash, dataset_hash)? {
+            return Ok((outcome, true));
+        }
+    }
+
+    let outcome = execute_backtest(&run.spec, &dataset.bars, &dataset.halts, &dataset.quality)?;
+    fs::create_dir_all(&run_dir).context("Failed to create run directory")?;
+    write_outputs(&outcome, &run_dir)?;
+    write_effective_spec(&run.spec, &run_dir)?;
+    write_checkpoint(&run_dir, &spec_hash, dataset_hash)?;
+    Ok((outcome, false))
+}
+
+/// The statistic at the dotted `path` of `stats`
+fn objective_value(stats: &BacktestStats, path: &str) -> Result<f64> {
+    let stats_json = serde_json::to_value(stats)?;
+    path.split('.')
+        .try_fold(&stats_json, |value, key| value.get(key))
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| anyhow::anyhow!("Unknown or non-numeric sweep objective '{}'", path))
+}
+
+/// Rank rows by objective; ties keep grid order so ranking is deterministic
+fn rank_rows(rows: &mut [SweepResultRow], minimize: bool) {
+    rows.sort_by(|a, b| {
+        let ord = if minimize {
+            a.objective.total_cmp(&b.objective)
+        } else {
+            b.objective.total_cmp(&a.objective)
+        };
+        ord.then_with(|| a.run_id.cmp(&b.run_id))
+    });
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.rank = i + 1;
+    }
+}
+
+fn write_results(rows: &[SweepResultRow], out_dir: &Path) -> Result<()> {
+    let json_file = fs::File::create(out_dir.join("sweep_results.json"))?;
+    serde_json::to_writer_pretty(json_file, rows)?;
+
+    let param_keys: Vec<String> = rows
+        .first()
+        .map(|r| r.params.keys().cloned().collect())
+        .unwrap_or_default();
+    let mut wtr = csv::Writer::from_path(out_dir.join("sweep_results.csv"))?;
+    let mut header = vec!["rank".to_string(), "run_id".to_string()];
+    header.extend(param_keys.iter().cloned());
+    header.extend(
+        [
+            "objective",
+            "total_return",
+            "sharpe_ratio",
+            "max_drawdown",
+            "num_trades",
+            "total_commission",
+            "crv_passed",
+            "crv_violations",
+            "result_hash",
+        ]
+        .map(String::from),
+    );
+    wtr.write_record(&header)?;
+
+    for row in rows {
+        let mut record = vec![row.rank.to_string(), row.run_id.clone()];
+        record.extend(param_keys.iter().map(|k| match &row.params[k] {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }));
+        record.extend([
+            row.objective.to_string(),
+            row.total_return.to_string(),
+            row.sharpe_ratio.to_string(),
+            row.max_drawdown.to_string(),
+            row.num_trades.to_string(),
+            row.total_commission.to_string(),
+            row.crv_passed.to_string(),
+            row.crv_violations.to_string(),
+            row.hashes
+                .as_ref()
+                .map(|h| h.result.clone())
+                .unwrap_or_default(),
+        ]);
+        wtr.write_record(&record)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_sweep() -> SweepSpec {
+        serde_json::from_value(serde_json::json!({
+            "base": {
+                "initial_cash": 100000.0,
+                "seed": 42,
+                "strategy": {
+                    "type": "ts_momentum",
+                    "symbol": "AAPL",
+                    "lookback": 20,
+                    "vol_target": 0.15,
+                    "vol_lookback": 20
+                },
+                "cost_model": {"type": "zero"}
+            },
+            "grid": {
+                "strategy.lookback": [10, 20, 40],
+                "seed": [1, 2],
+                "cost_model": [{"type": "zero"}, {"type": "percentage", "percentage": 0.001, "minimum_commission": 1.0}]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn expands_full_cartesian_grid_deterministically() {
+        let runs = sample_sweep().expand().unwrap();
+        assert_eq!(runs.len(), 12);
+        assert_eq!(runs[0].run_id, "run_0001");
+        assert_eq!(runs[0].spec.seed, 1);
+
+        let again = sample_sweep().expand().unwrap();
+        for (a, b) in runs.iter().zip(&again) {
+            assert_eq!(a.params, b.params);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_grid_paths() {
+        let mut sweep = sample_sweep();
+        sweep
+            .grid
+            .insert("strategy.nope.deep".to_string(), vec![serde_json::json!(1)]);
+        assert!(sweep.expand().is_err());
+
+        let mut sweep = sample_sweep();
+        sweep.grid.insert(
+            "data_pipeline".to_string(),
+            vec![
+                serde_json::json!("legacy"),
+                serde_json::json!("canonical_tier1"),
+            ],
+        );
+        let err = sweep.expand().unwrap_err().to_string();
+        assert!(err.contains("cannot vary the data pipeline"), "{}", err);
+    }
+
+    #[test]
+    fn ranking_respects_direction_and_ties() {
+        let row = |id: &str, objective: f64| SweepResultRow {
+            rank: 0,
+            run_id: id.to_string(),
+            params: BTreeMap::new(),
+            objective,
+            total_return: 0.0,
+            sharpe_ratio: 0.0,
+            max_drawdown: 0.0,
+            num_trades: 0,
+            total_commission: 0.0,
+            crv_passed: true,
+            crv_violations: 0,
+            hashes: None,
+            stats: engine::output::calculate_stats(
+                &[],
+                &[],
+                Default::default(),
+                Default::default(),
+            ),
+        };
+        let mut rows = vec![
+            row("run_0002", 1.0),
+            row("run_0001", 1.0),
+            row("run_0003", 2.0),
+        ];
+        rank_rows(&mut rows, false);
+        let order: Vec<&str> = rows.iter().map(|r| r.run_id.as_str()).collect();
+        assert_eq!(order, vec!["run_0003", "run_0001", "run_0002"]);
+
+        rank_rows(&mut rows, true);
+        assert_eq!(rows[0].run_id, "run_0001");
+        assert_eq!(rows[2].rank, 3);
+    }
+
+    #[test]
+    fn objectives_reach_nested_stats() {
+        let mut stats = engine::output::calculate_stats(
+            &[(0, 100.0), (1, 110.0)],
+            &[],
+            Default::default(),
+            Default::default(),
+        );
+        stats.costs.slippage = 2.5;
+        assert_eq!(objective_value(&stats, "total_return").unwrap(), 0.1);
+        assert_eq!(objective_value(&stats, "costs.slippage").unwrap(), 2.5);
+        // Unfinanced runs have no financing stats to rank by
+        assert!(objective_value(&stats, "financing.debit_interest").is_err());
+        assert!(objective_value(&stats, "costs").is_err());
+    }
+}
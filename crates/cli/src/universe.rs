@@ -0,0 +1,329 @@
+//! Universe backtests: one single-symbol strategy run per symbol.
+//!
+//! Each symbol gets an isolated sub-backtest over its own bars with an equal
+//! share of `initial_cash`; sub-backtests share no state, so they run in
+//! parallel on the rayon pool. Their fills and order events are merged by
+//! timestamp (ties in universe order) and their equity curves summed, forward-filling each one,
+//! so the combined result does not depend on thread scheduling.
+
+use anyhow::{Context, Result};
+use broker_sim::TradingHalts;
+use crv_verifier::CRVVerifier;
+use rayon::prelude::*;
+use schema::{
+    Bar, ConstrainedOrders, CostBreakdown, DataQualityReport, EquityPoint, ExposurePoint,
+    FinancingStats,
+};
+use std::collections::BTreeSet;
+
+use crate::backtest_cmd::{bootstrap_intervals, execute_backtest, BacktestOutcome};
+use crate::spec::{BacktestSpec, StrategySpec};
+
+/// Run `spec`'s strategy once per symbol of `universe` and merge the results
+pub fn execute_universe(
+    spec: &BacktestSpec,
+    universe: &[String],
+    bars: &[Bar],
+    halts: &TradingHalts,
+    quality: &DataQualityReport,
+) -> Result<BacktestOutcome> {
+    if universe.is_empty() {
+        anyhow::bail!("Universe lists no symbols");
+    }
+    let cash = spec.initial_cash / universe.len() as f64;
+    let outcomes: Vec<BacktestOutcome> = universe
+        .par_iter()
+        .map(|symbol| {
+            let mut sub = spec.clone();
+            sub.universe = None;
+            // Only the merged run is bootstrapped
+            sub.metrics.bootstrap = None;
+            sub.initial_cash = cash;
+            sub.strategy = with_symbol(&spec.strategy, symbol)?;
+            let bars: Vec<Bar> = bars
+                .iter()
+                .filter(|b| &b.symbol == symbol)
+                .cloned()
+                .collect();
+            execute_backtest(&sub, &bars, halts, quality)
+                .with_context(|| format!("Universe backtest for {} failed", symbol))
+        })
+        .collect::<Result<_>>()?;
+
+    let mut fills: Vec<_> = outcomes.iter().flat_map(|o| o.fills.clone()).collect();
+    // Stable, so fills of one timestamp stay in universe order
+    fills.sort_by_key(|f| f.timestamp);
+    let mut order_events: Vec<_> = outcomes
+        .iter()
+        .flat_map(|o| o.order_events.clone())
+        .collect();
+    order_events.sort_by_key(|e| e.timestamp);
+    let equity_points = merge_equity(&outcomes, cash);
+    let equity_history: Vec<(i64, f64)> = equity_points
+        .iter()
+        .map(|p| (p.timestamp, p.equity))
+        .collect();
+    let costs = outcomes
+        .iter()
+        .fold(CostBreakdown::default(), |mut total, o| {
+            total += o.stats.costs;
+            total
+        });
+
+    let mut stats = engine::output::calculate_stats(
+        &equity_history,
+        &fills,
+        costs,
+        spec.metrics.annualization(),
+    );
+    // Sub-backtests share one spec, so all of them are financed or none
+    stats.financing = outcomes
+        .iter()
+        .filter_map(|o| o.stats.financing.as_ref())
+        .cloned()
+        .reduce(|total, sub| FinancingStats {
+            debit_interest: total.debit_interest + sub.debit_interest,
+            short_borrow_fees: total.short_borrow_fees + sub.short_borrow_fees,
+            credit_interest: total.credit_interest + sub.credit_interest,
+        });
+    stats.constrained_orders = outcomes
+        .iter()
+        .filter_map(|o| o.stats.constrained_orders)
+        .reduce(|total, sub| ConstrainedOrders {
+            downsized: total.downsized + sub.downsized,
+            rejected: total.rejected + sub.rejected,
+        });
+    stats.bootstrap = bootstrap_intervals(spec, &equity_history);
+    let exposure_history = merge_exposure(&outcomes, &equity_history);
+    let mut round_trips: Vec<_> = outcomes
+        .iter()
+        .flat_map(|o| o.round_trips.clone())
+        .collect();
+    round_trips.sort_by_key(|trip| trip.exit_time);
+    let mut lot_realizations: Vec<_> = outcomes
+        .iter()
+        .flat_map(|o| o.lot_realizations.clone())
+        .collect();
+    lot_realizations.sort_by_key(|lot| lot.closed_at);
+    let mut risk_rejections: Vec<_> = outcomes
+        .iter()
+        .flat_map(|o| o.risk_rejections.clone())
+        .collect();
+    risk_rejections.sort_by_key(|r| r.timestamp);
+    let verifier = CRVVerifier::new(spec.policy_constraints());
+    let mut crv_report =
+        verifier.verify_with_data_quality(&stats, &fills, &equity_history, quality)?;
+    verifier.verify_exposure(&exposure_history, &mut crv_report);
+    verifier.verify_risk_rejections(&risk_rejections, &mut crv_report);
+    let mut kill_switch_trips: Vec<_> = outcomes
+        .iter()
+        .flat_map(|o| o.kill_switch_trips.clone())
+        .collect();
+    kill_switch_trips.sort_by_key(|trip| trip.timestamp);
+    verifier.verify_kill_switch(&kill_switch_trips, &mut crv_report);
+    Ok(BacktestOutcome {
+        stats,
+        fills,
+        equity_history,
+        equity_points,
+        exposure_history,
+        round_trips,
+        lot_realizations,
+        crv_report,
+        order_events,
+        risk_rejections,
+        trace: outcomes
+            .iter()
+            .filter_map(|o| o.trace.clone())
+            .reduce(|total, sub| total.merge(&sub)),
+        sleeves: Vec::new(),
+        kill_switch_trips,
+    })
+}
+
+/// The strategy with its `symbol` replaced
+fn with_symbol(strategy: &StrategySpec, symbol: &str) -> Result<StrategySpec> {
+    let mut value = serde_json::to_value(strategy)?;
+    match value.get_mut("symbol") {
+        Some(field) => *field = serde_json::Value::String(symbol.to_string()),
+        None => anyhow::bail!("A universe needs a single-symbol strategy"),
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Total equity, cash and positions at every timestamp any sub-backtest
+/// marked; a sub-backtest counts as `initial` cash before its first mark and
+/// at its last mark after it
+fn merge_equity(outcomes: &[BacktestOutcome], initial: f64) -> Vec<EquityPoint> {
+    let timestamps: BTreeSet<i64> = outcomes
+        .iter()
+        .flat_map(|o| o.equity_points.iter().map(|p| p.timestamp))
+        .collect();
+    let mut cursors = vec![0; outcomes.len()];
+    let start = EquityPoint {
+        timestamp: 0,
+        equity: initial,
+        cash: initial,
+        positions_value: 0.0,
+    };
+    let mut latest = vec![start; outcomes.len()];
+    timestamps
+        .into_iter()
+        .map(|timestamp| {
+            for (i, outcome) in outcomes.iter().enumerate() {
+                let history = &outcome.equity_points;
+                while cursors[i] < history.len() && history[cursors[i]].timestamp <= timestamp {
+                    latest[i] = history[cursors[i]];
+                    cursors[i] += 1;
+                }
+            }
+            EquityPoint {
+                timestamp,
+                equity: latest.iter().map(|p| p.equity).sum(),
+                cash: latest.iter().map(|p| p.cash).sum(),
+                positions_value: latest.iter().map(|p| p.positions_value).sum(),
+            }
+        })
+        .collect()
+}
+
+/// Summed exposures and traded notional at each mark of the merged
+/// `equity_history`, forward-filling each sub-backtest like `merge_equity`
+fn merge_exposure(
+    outcomes: &[BacktestOutcome],
+    equity_history: &[(i64, f64)],
+) -> Vec<ExposurePoint> {
+    let mut cursors = vec![0; outcomes.len()];
+    let mut latest = vec![ExposurePoint::default(); outcomes.len()];
+    equity_history
+        .iter()
+        .map(|&(timestamp, equity)| {
+            for (i, outcome) in outcomes.iter().enumerate() {
+                let history = &outcome.exposure_history;
+                while cursors[i] < history.len() && history[cursors[i]].timestamp <= timestamp {
+                    latest[i] = history[cursors[i]];
+                    cursors[i] += 1;
+                }
+            }
+            ExposurePoint::new(
+                timestamp,
+                latest.iter().map(|p| p.gross_exposure).sum(),
+                latest.iter().map(|p| p.net_exposure).sum(),
+                latest.iter().map(|p| p.traded_notional).sum(),
+                equity,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spec::parse_spec;
+
+    fn bars() -> Vec<Bar> {
+        // B starts a day late; both rise 1% a day
+        let mut bars = Vec::new();
+        for day in 0..20i64 {
+            for (symbol, start) in [("A", 0), ("B", 1)] {
+                if day >= start {
+                    let close = 100.0 * 1.01f64.powi((day - start) as i32);
+                    bars.push(Bar {
+                        timestamp: day * 86_400,
+                        symbol: symbol.to_string(),
+                        open: close,
+                        high: close,
+                        low: close,
+                        close,
+                        volume: 1_000.0,
+                    });
+                }
+            }
+        }
+        bars
+    }
+
+    fn spec() -> BacktestSpec {
+        parse_spec(serde_json::json!({
+            "initial_cash": 10_000.0,
+            "seed": 1,
+            "strategy": {"type": "buy_and_hold", "symbol": "A"},
+            "cost_model": {"type": "zero"},
+            "universe": ["A", "B"]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn universe_runs_split_capital_and_merge_deterministically() {
+        let bars = bars();
+        let quality = DataQualityReport::default();
+        let spec = spec();
+        let merged = execute_universe(
+            &spec,
+            &["A".into(), "B".into()],
+            &bars,
+            &TradingHalts::new(),
+            &quality,
+        )
+        .unwrap();
+
+        let symbols: Vec<&str> = merged.fills.iter().map(|f| f.symbol.as_str()).collect();
+        assert_eq!(symbols, ["A", "B"]);
+        assert_eq!(merged.fills[0].quantity, 50.0);
+        // Before B's first bar its half of the cash is still idle
+        assert_eq!(merged.equity_history[0], (0, 10_000.0));
+        assert_eq!(merged.equity_history.len(), 20);
+        // Both halves end fully invested, long only
+        assert_eq!(merged.exposure_history.len(), 20);
+        let last = merged.exposure_history.last().unwrap();
+        assert_eq!(last.gross_exposure, last.net_exposure);
+        assert!((last.leverage - 1.0).abs() < 1e-9);
+
+        // The total matches standalone runs with half the cash each
+        let mut alone = spec.clone();
+        alone.universe = None;
+        alone.initial_cash = 5_000.0;
+        let mut final_equity = |symbol: &str| {
+            alone.strategy = with_symbol(&spec.strategy, symbol).unwrap();
+            let own: Vec<Bar> = bars
+                .iter()
+                .filter(|b| b.symbol == symbol)
+                .cloned()
+                .collect();
+            execute_backtest(&alone, &own, &TradingHalts::new(), &quality)
+                .unwrap()
+                .stats
+                .final_equity
+        };
+        let total = final_equity("A") + final_equity("B");
+        assert!((merged.stats.final_equity - total).abs() < 1e-6);
+
+        let again = execute_universe(
+            &spec,
+            &["A".into(), "B".into()],
+            &bars,
+            &TradingHalts::new(),
+            &quality,
+        )
+        .unwrap();
+        assert_eq!(
+            engine::canonical_json_hash(&merged.equity_history).unwrap(),
+            engine::canonical_json_hash(&again.equity_history).unwrap()
+        );
+    }
+
+    #[test]
+    fn universe_needs_a_single_symbol_strategy() {
+        let err = parse_spec(serde_json::json!({
+            "initial_cash": 10_000.0,
+            "seed": 1,
+            "strategy": {"type": "pairs", "symbol_a": "A", "symbol_b": "B",
+                         "lookback": 5, "entry_z": 2.0},
+            "cost_model": {"type": "zero"},
+            "universe": ["A", "B"]
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("universe"));
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::{Context, Result};
+use crv_verifier::{CRVReport, CRVVerifier, GateOutcome, Severity, VerificationPolicy};
+use hipcortex::Repository;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::backtest_cmd::print_crv_report;
+use crate::gate::GateSummary;
+use crate::run_data::{load_run_dir, load_run_hash, RunData};
+
+/// Where the run being verified comes from
+pub enum VerifySource<'a> {
+    OutputDir(&'a Path),
+    Hash { hash: &'a str, repo: &'a Path },
+}
+
+/// Report written by `verify`: the re-run CRV report plus the gate decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub source: String,
+    pub gate: GateOutcome,
+    pub crv_report: CRVReport,
+}
+
+/// Re-run CRV against an existing run and return whether it passes the gate
+pub fn run_verify(
+    source: VerifySource,
+    policy_path: Option<&Path>,
+    report_path: Option<&Path>,
+    gate: Option<Severity>,
+) -> Result<bool> {
+    let run = match source {
+        VerifySource::OutputDir(dir) => load_run_dir(dir)?,
+        VerifySource::Hash { hash, repo } => {
+            let repo = Repository::open(repo).context("Failed to open repository")?;
+            load_run_hash(hash, &repo)?
+        }
+    };
+    let mut policy = match policy_path {
+        Some(path) => VerificationPolicy::load(path)?,
+        None => VerificationPolicy::default(),
+    };
+    if let Some(fail_on) = gate {
+        policy.fail_on = fail_on;
+    }
+
+    let report = verify_run(&run, &policy)?;
+
+    println!("=== CRV Verification: {} ===", report.source);
+    if run.quality.is_none() {
+        println!("No data quality report found; data quality rules skipped");
+    }
+    print_crv_report(&report.crv_report);
+    println!(
+        "\nGate (fail on {:?}+): {} ({} blocking of {} violation(s))",
+        report.gate.fail_on,
+        if report.gate.passed { "PASS" } else { "FAIL" },
+        report.gate.blocking_violations,
+        report.gate.total_violations
+    );
+    println!(
+        "{}",
+        GateSummary::new(
+            policy.fail_on,
+            [(report.source.clone(), &report.crv_report)]
+        )
+        .line()
+    );
+
+    if let Some(path) = report_path {
+        let file = fs::File::create(path)
+            .with_context(|| format!("Failed to create report file {:?}", path))?;
+        serde_json::to_writer_pretty(file, &report)?;
+        println!("Wrote verification report to {:?}", path);
+    }
+
+    Ok(report.gate.passed)
+}
+
+/// Run every applicable CRV check on a loaded run under `policy`
+pub fn verify_run(run: &RunData, policy: &VerificationPolicy) -> Result<VerifyReport> {
+    let verifier = CRVVerifier::new(policy.constraints.clone());
+    let mut crv_report = match &run.quality {
+        Some(quality) => verifier.verify_with_data_quality(
+            &run.stats,
+            &run.fills,
+            &run.equity_history,
+            quality,
+        )?,
+        None => verifier.verify(&run.stats, &run.fills, &run.equity_history)?,
+    };
+    if let Some(exposure_history) = &run.exposure_history {
+        verifier.verify_exposure(exposure_history, &mut crv_report);
+    }
+    let crv_report = policy.apply(crv_report);
+
+    Ok(VerifyReport {
+        source: run.label.clone(),
+        gate: policy.gate(&crv_report),
+        crv_report,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crv_verifier::{PolicyConstraints, RuleId, Severity};
+    use schema::CostBreakdown;
+
+    fn drawdown_run() -> RunData {
+        let equity_history = vec![(1, 100_000.0), (2, 70_000.0), (3, 80_000.0)];
+        RunData {
+            label: "test".to_string(),
+            stats: engine::output::calculate_stats(
+                &equity_history,
+                &[],
+                CostBreakdown::default(),
+                Default::default(),
+            ),
+            equity_history,
+            fills: vec![],
+            quality: None,
+            exposure_history: None,
+        }
+    }
+
+    #[test]
+    fn policy_constraints_drive_verdict() {
+        let run = drawdown_run();
+
+        let default = verify_run(&run, &VerificationPolicy::default()).unwrap();
+        assert!(!default.gate.passed);
+        assert_eq!(
+            default.crv_report.violations[0].rule_id,
+            RuleId::MaxDrawdownConstraint
+        );
+
+        let relaxed = VerificationPolicy {
+            constraints: PolicyConstraints {
+                max_drawdown: Some(0.5),
+                ..PolicyConstraints::default()
+            },
+            ..VerificationPolicy::default()
+        };
+        let report = verify_run(&run, &relaxed).unwrap();
+        assert!(report.crv_report.passed);
+        assert!(report.gate.passed);
+    }
+
+    #[test]
+    fn gate_threshold_controls_exit_decision() {
+        let run = drawdown_run();
+        let critical_only = VerificationPolicy {
+            fail_on: Severity::Critical,
+            ..VerificationPolicy::default()
+        };
+        let report = verify_run(&run, &critical_only).unwrap();
+
+        // The drawdown breach is High, so it is reported but does not block
+        assert!(!report.crv_report.passed);
+        assert!(report.gate.passed);
+    }
+}
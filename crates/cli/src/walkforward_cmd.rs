@@ -0,0 +1,346 @@
+use anyhow::{Context, Result};
+use crv_verifier::{walk_forward_efficiency, CRVReport, CRVVerifier, WalkForwardWindow};
+use rayon::prelude::*;
+use schema::{BacktestStats, Bar};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::backtest_cmd::{
+    execute_backtest, load_spec, print_crv_report, write_effective_spec, write_outputs,
+};
+use crate::data::load_dataset;
+
+/// Bar index ranges of one walk-forward window, in distinct timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowBounds {
+    pub train_start: usize,
+    pub test_start: usize,
+    pub test_end: usize,
+}
+
+/// Headline statistics of one side of a window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowSummary {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub total_return: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub num_trades: usize,
+}
+
+/// In-sample vs out-of-sample comparison for one window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowRow {
+    pub window_id: String,
+    pub in_sample: WindowSummary,
+    pub out_of_sample: WindowSummary,
+    pub oos_crv_passed: bool,
+}
+
+/// Aggregate IS/OOS comparison across all windows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkForwardAggregate {
+    pub windows: usize,
+    pub mean_is_return: f64,
+    pub mean_oos_return: f64,
+    pub mean_is_sharpe: f64,
+    pub mean_oos_sharpe: f64,
+    /// OOS returns chained across windows
+    pub compounded_oos_return: f64,
+    pub profitable_oos_windows: usize,
+    /// Mean OOS Sharpe / mean IS Sharpe, when the in-sample Sharpe is positive
+    pub sharpe_efficiency: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkForwardReport {
+    pub train_bars: usize,
+    pub test_bars: usize,
+    pub step_bars: usize,
+    pub windows: Vec<WindowRow>,
+    pub aggregate: WalkForwardAggregate,
+}
+
+/// Split `num_timestamps` distinct timestamps into rolling train/test windows.
+///
+/// Windows advance by `step` and stop once a full test window no longer fits.
+pub fn walk_forward_windows(
+    num_timestamps: usize,
+    train: usize,
+    test: usize,
+    step: usize,
+) -> Result<Vec<WindowBounds>> {
+    if train == 0 || test == 0 || step == 0 {
+        anyhow::bail!("Walk-forward train, test and step lengths must be positive");
+    }
+
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start + train + test <= num_timestamps {
+        windows.push(WindowBounds {
+            train_start: start,
+            test_start: start + train,
+            test_end: start + train + test,
+        });
+        start += step;
+    }
+
+    if windows.is_empty() {
+        anyhow::bail!(
+            "Not enough data for one walk-forward window: {} timestamps, need {}",
+            num_timestamps,
+            train + test
+        );
+    }
+    Ok(windows)
+}
+
+pub fn run_walkforward(
+    spec_path: &Path,
+    overrides: &[String],
+    data_path: &Path,
+    out_dir: &Path,
+    train: usize,
+    test: usize,
+    step: Option<usize>,
+) -> Result<CRVReport> {
+    let spec = load_spec(spec_path, overrides)?;
+    let dataset = load_dataset(data_path, &spec.data_pipeline)?;
+    let step = step.unwrap_or(test);
+
+    // Windows are counted in distinct timestamps so multi-symbol bars stay together
+    let mut timestamps: Vec<i64> = dataset.bars.iter().map(|b| b.timestamp).collect();
+    timestamps.dedup();
+    let bounds = walk_forward_windows(timestamps.len(), train, test, step)?;
+    println!(
+        "Walk-forward over {} bars: {} window(s), train={} test={} step={}",
+        dataset.bars.len(),
+        bounds.len(),
+        train,
+        test,
+        step
+    );
+
+    fs::create_dir_all(out_dir).context("Failed to create output directory")?;
+    write_effective_spec(&spec, out_dir)?;
+
+    let slice = |from: usize, to: usize| -> &[Bar] {
+        let bars = &dataset.bars;
+        let lo = bars.partition_point(|b| b.timestamp < timestamps[from]);
+        let hi = if to < timestamps.len() {
+            bars.partition_point(|b| b.timestamp < timestamps[to])
+        } else {
+            bars.len()
+        };
+        &bars[lo..hi]
+    };
+
+    let outcomes = bounds
+        .par_iter()
+        .map(|w| {
+            let in_sample = execute_backtest(
+                &spec,
+                slice(w.train_start, w.test_start),
+                &dataset.halts,
+                &dataset.quality,
+            )?;
+            let out_of_sample = execute_backtest(
+                &spec,
+                slice(w.test_start, w.test_end),
+                &dataset.halts,
+                &dataset.quality,
+            )?;
+            Ok((in_sample, out_of_sample))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut rows = Vec::with_capacity(bounds.len());
+    let mut windows = Vec::with_capacity(bounds.len());
+    for (i, (w, (in_sample, out_of_sample))) in bounds.iter().zip(outcomes).enumerate() {
+        let window_id = format!("window_{:03}", i + 1);
+        let window_dir = out_dir.join("windows").join(&window_id);
+        for (side, outcome) in [("in_sample", &in_sample), ("out_of_sample", &out_of_sample)] {
+            let dir = window_dir.join(side);
+            fs::create_dir_all(&dir).context("Failed to create window directory")?;
+            write_outputs(outcome, &dir)?;
+        }
+
+        rows.push(WindowRow {
+            window_id: window_id.clone(),
+            in_sample: summarize(
+                &in_sample.stats,
+                timestamps[w.train_start],
+                timestamps[w.test_start - 1],
+            ),
+            out_of_sample: summarize(
+                &out_of_sample.stats,
+                timestamps[w.test_start],
+                timestamps[w.test_end - 1],
+            ),
+            oos_crv_passed: out_of_sample.crv_report.passed,
+        });
+        windows.push(WalkForwardWindow {
+            label: window_id,
+            in_sample: in_sample.stats,
+            out_of_sample: out_of_sample.stats,
+            report: out_of_sample.crv_report,
+        });
+    }
+
+    let report = WalkForwardReport {
+        train_bars: train,
+        test_bars: test,
+        step_bars: step,
+        aggregate: aggregate(&windows),
+        windows: rows,
+    };
+    write_report(&report, out_dir)?;
+
+    let crv_report = CRVVerifier::with_defaults().verify_walk_forward(&windows)?;
+    let crv_file = fs::File::create(out_dir.join("crv_report.json"))?;
+    serde_json::to_writer_pretty(crv_file, &crv_report)?;
+
+    println!("\n=== Walk-Forward Windows ===");
+    for row in &report.windows {
+        println!(
+            "{}  IS return={:.2}% sharpe={:.3}  OOS return={:.2}% sharpe={:.3}",
+            row.window_id,
+            row.in_sample.total_return * 100.0,
+            row.in_sample.sharpe_ratio,
+            row.out_of_sample.total_return * 100.0,
+            row.out_of_sample.sharpe_ratio
+        );
+    }
+    let agg = &report.aggregate;
+    println!("\n=== Walk-Forward Summary ===");
+    println!("Mean IS Sharpe: {:.4}", agg.mean_is_sharpe);
+    println!("Mean OOS Sharpe: {:.4}", agg.mean_oos_sharpe);
+    match agg.sharpe_efficiency {
+        Some(e) => println!("Sharpe efficiency: {:.1}%", e * 100.0),
+        None => println!("Sharpe efficiency: n/a"),
+    }
+    println!(
+        "Compounded OOS return: {:.2}%",
+        agg.compounded_oos_return * 100.0
+    );
+    println!(
+        "Profitable OOS windows: {}/{}",
+        agg.profitable_oos_windows, agg.windows
+    );
+
+    println!("\n=== Running CRV Verification ===");
+    print_crv_report(&crv_report);
+
+    println!("Walk-forward completed. Results written to {:?}", out_dir);
+    Ok(crv_report)
+}
+
+fn summarize(stats: &BacktestStats, start_time: i64, end_time: i64) -> WindowSummary {
+    WindowSummary {
+        start_time,
+        end_time,
+        total_return: stats.total_return,
+        sharpe_ratio: stats.sharpe_ratio,
+        max_drawdown: stats.max_drawdown,
+        num_trades: stats.num_trades,
+    }
+}
+
+fn aggregate(windows: &[WalkForwardWindow]) -> WalkForwardAggregate {
+    let n = windows.len().max(1) as f64;
+    let mean = |f: fn(&WalkForwardWindow) -> f64| windows.iter().map(f).sum::<f64>() / n;
+    WalkForwardAggregate {
+        windows: windows.len(),
+        mean_is_return: mean(|w| w.in_sample.total_return),
+        mean_oos_return: mean(|w| w.out_of_sample.total_return),
+        mean_is_sharpe: mean(|w| w.in_sample.sharpe_ratio),
+        mean_oos_sharpe: mean(|w| w.out_of_sample.sharpe_ratio),
+        compounded_oos_return: windows
+            .iter()
+            .fold(1.0, |acc, w| acc * (1.0 + w.out_of_sample.total_return))
+            - 1.0,
+        profitable_oos_windows: windows
+            .iter()
+            .filter(|w| w.out_of_sample.total_return > 0.0)
+            .count(),
+        sharpe_efficiency: walk_forward_efficiency(windows),
+    }
+}
+
+fn write_report(report: &WalkForwardReport, out_dir: &Path) -> Result<()> {
+    let json_file = fs::File::create(out_dir.join("walkforward_report.json"))?;
+    serde_json::to_writer_pretty(json_file, report)?;
+
+    let mut wtr = csv::Writer::from_path(out_dir.join("walkforward_report.csv"))?;
+    wtr.write_record([
+        "window_id",
+        "is_start",
+        "is_end",
+        "oos_start",
+        "oos_end",
+        "is_return",
+        "oos_return",
+        "is_sharpe",
+        "oos_sharpe",
+        "is_max_drawdown",
+        "oos_max_drawdown",
+        "oos_crv_passed",
+    ])?;
+    for row in &report.windows {
+        wtr.write_record([
+            row.window_id.clone(),
+            row.in_sample.start_time.to_string(),
+            row.in_sample.end_time.to_string(),
+            row.out_of_sample.start_time.to_string(),
+            row.out_of_sample.end_time.to_string(),
+            row.in_sample.total_return.to_string(),
+            row.out_of_sample.total_return.to_string(),
+            row.in_sample.sharpe_ratio.to_string(),
+            row.out_of_sample.sharpe_ratio.to_string(),
+            row.in_sample.max_drawdown.to_string(),
+            row.out_of_sample.max_drawdown.to_string(),
+            row.oos_crv_passed.to_string(),
+        ])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_roll_by_step_and_stop_at_end() {
+        let windows = walk_forward_windows(10, 4, 2, 2).unwrap();
+        assert_eq!(
+            windows,
+            vec![
+                WindowBounds {
+                    train_start: 0,
+                    test_start: 4,
+                    test_end: 6
+                },
+                WindowBounds {
+                    train_start: 2,
+                    test_start: 6,
+                    test_end: 8
+                },
+                WindowBounds {
+                    train_start: 4,
+                    test_start: 8,
+                    test_end: 10
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_degenerate_windows() {
+        assert!(walk_forward_windows(10, 0, 2, 2).is_err());
+        assert!(walk_forward_windows(10, 4, 2, 0).is_err());
+        assert!(walk_forward_windows(5, 4, 2, 2).is_err());
+    }
+}
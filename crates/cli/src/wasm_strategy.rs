@@ -0,0 +1,304 @@
+//! Strategies loaded from user-supplied WASM modules.
+//!
+//! A plugin module must export:
+//!
+//! - `memory`: its linear memory
+//! - `alloc(len: i32) -> i32`: reserve `len` bytes for the host to write into
+//! - `on_bar(ptr: i32, len: i32) -> i64`: receives `{"bar": Bar, "portfolio":
+//!   Portfolio}` as UTF-8 JSON and returns a JSON array of `Order`s, packed as
+//!   `(ptr << 32) | len`
+//!
+//! and may export `init(ptr: i32, len: i32)`, which is called once with the
+//! spec's `params` as JSON. Modules run in an interpreter with no imports, so
+//! they cannot touch the filesystem, network or clock; each `on_bar` call is
+//! limited by an instruction (fuel) budget and memory is capped.
+
+use anyhow::{Context, Result};
+use schema::{Bar, Order, Portfolio, Strategy};
+use std::fs;
+use std::path::Path;
+use wasmi::{
+    Config, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+
+/// Sandbox resource limits for a plugin
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    pub fuel_per_bar: u64,
+    pub max_memory_bytes: usize,
+}
+
+pub struct WasmStrategy {
+    name: String,
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    on_bar: TypedFunc<(i32, i32), i64>,
+    fuel_per_bar: u64,
+}
+
+impl WasmStrategy {
+    /// Load and instantiate a plugin, verifying its hash when one is pinned
+    pub fn load(
+        path: &Path,
+        params: &serde_json::Value,
+        expected_sha256: Option<&str>,
+        limits: WasmLimits,
+    ) -> Result<Self> {
+        let bytes =
+            fs::read(path).with_context(|| format!("Failed to read WASM module {:?}", path))?;
+        if let Some(expected) = expected_sha256 {
+            let actual = engine::stable_hash_bytes(&bytes);
+            if !actual.eq_ignore_ascii_case(expected) {
+                anyhow::bail!(
+                    "WASM module {:?} has SHA-256 {}, spec pins {}",
+                    path,
+                    actual,
+                    expected
+                );
+            }
+        }
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("wasm")
+            .to_string();
+        Self::from_bytes(name, &bytes, params, limits)
+            .with_context(|| format!("Failed to load WASM strategy {:?}", path))
+    }
+
+    fn from_bytes(
+        name: String,
+        bytes: &[u8],
+        params: &serde_json::Value,
+        limits: WasmLimits,
+    ) -> Result<Self> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes).map_err(wasm_error)?;
+
+        // Nothing is linked in, so any import means the module expects host access
+        if let Some(import) = module.imports().next() {
+            anyhow::bail!(
+                "module imports {}::{}; plugins may not import host functions",
+                import.module(),
+                import.name()
+            );
+        }
+
+        let mut store = Store::new(
+            &engine,
+            StoreLimitsBuilder::new()
+                .memory_size(limits.max_memory_bytes)
+                .instances(1)
+                .build(),
+        );
+        store.limiter(|limits| limits);
+        // Start functions and `init` run under the same budget as a bar
+        store.set_fuel(limits.fuel_per_bar).map_err(wasm_error)?;
+        let instance = Linker::<StoreLimits>::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(wasm_error)?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("module does not export `memory`"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| anyhow::anyhow!("module must export `alloc(i32) -> i32`: {}", e))?;
+        let on_bar = instance
+            .get_typed_func::<(i32, i32), i64>(&store, "on_bar")
+            .map_err(|e| anyhow::anyhow!("module must export `on_bar(i32, i32) -> i64`: {}", e))?;
+
+        let mut strategy = Self {
+            name,
+            store,
+            memory,
+            alloc,
+            on_bar,
+            fuel_per_bar: limits.fuel_per_bar,
+        };
+        if let Ok(init) = instance.get_typed_func::<(i32, i32), ()>(&strategy.store, "init") {
+            let (ptr, len) = strategy.write_input(&serde_json::to_vec(params)?)?;
+            init.call(&mut strategy.store, (ptr, len))
+                .map_err(wasm_error)
+                .context("`init` failed")?;
+        }
+        Ok(strategy)
+    }
+
+    /// Run the plugin for one bar; traps, fuel exhaustion and bad output are errors
+    pub fn try_on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Result<Vec<Order>> {
+        self.store.set_fuel(self.fuel_per_bar).map_err(wasm_error)?;
+        let input = serde_json::to_vec(&serde_json::json!({
+            "bar": bar,
+            "portfolio": portfolio,
+        }))?;
+        let (ptr, len) = self.write_input(&input)?;
+        let packed = self
+            .on_bar
+            .call(&mut self.store, (ptr, len))
+            .map_err(wasm_error)?;
+
+        let (ptr, len) = (
+            (packed as u64 >> 32) as usize,
+            (packed as u64 & 0xffff_ffff) as usize,
+        );
+        let mut output = vec![0u8; len];
+        self.memory
+            .read(&self.store, ptr, &mut output)
+            .map_err(|e| anyhow::anyhow!("`on_bar` returned an out-of-bounds result: {}", e))?;
+        serde_json::from_slice(&output).context("`on_bar` did not return a JSON array of orders")
+    }
+
+    /// Copy `data` into plugin memory through its `alloc` export
+    fn write_input(&mut self, data: &[u8]) -> Result<(i32, i32)> {
+        let len = i32::try_from(data.len()).context("input too large for a WASM module")?;
+        let ptr = self
+            .alloc
+            .call(&mut self.store, len)
+            .map_err(wasm_error)
+            .context("`alloc` failed")?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, data)
+            .map_err(|e| anyhow::anyhow!("`alloc` returned an out-of-bounds pointer: {}", e))?;
+        Ok((ptr, len))
+    }
+}
+
+impl Strategy for WasmStrategy {
+    /// A failing plugin aborts the run rather than silently stop trading
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        match self.try_on_bar(bar, portfolio) {
+            Ok(orders) => orders,
+            Err(err) => panic!(
+                "WASM strategy '{}' failed at {}: {:#}",
+                self.name, bar.timestamp, err
+            ),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn wasm_error(err: wasmi::Error) -> anyhow::Error {
+    anyhow::anyhow!("{}", err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: WasmLimits = WasmLimits {
+        fuel_per_bar: 1_000_000,
+        max_memory_bytes: 1 << 20,
+    };
+
+    /// Buys `params` shares on every bar: the module echoes a fixed
+    /// order whose quantity byte is patched in by `init`
+    const BUY_EVERY_BAR: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (data (i32.const 0) "[{\"symbol\":\"AAPL\",\"side\":\"Buy\",\"quantity\":1,\"order_type\":\"Market\",\"limit_price\":null}]")
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "init") (param $ptr i32) (param $len i32)
+            ;; params is a single digit, e.g. `3`
+            (i32.store8 (i32.const 42) (i32.load8_u (local.get $ptr))))
+          (func (export "on_bar") (param i32 i32) (result i64)
+            (i64.const 86)))
+    "#;
+
+    fn bar() -> Bar {
+        Bar {
+            timestamp: 1000,
+            symbol: "AAPL".to_string(),
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.0,
+            volume: 1000.0,
+        }
+    }
+
+    #[test]
+    fn runs_plugin_orders_and_params() {
+        let wasm = wat::parse_str(BUY_EVERY_BAR).unwrap();
+        let mut strategy =
+            WasmStrategy::from_bytes("buy".to_string(), &wasm, &serde_json::json!(3), LIMITS)
+                .unwrap();
+        let orders = strategy
+            .try_on_bar(&bar(), &Portfolio::new(10_000.0))
+            .unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].symbol, "AAPL");
+        assert_eq!(orders[0].quantity, 3.0);
+    }
+
+    #[test]
+    fn enforces_sandbox_limits() {
+        let spin = r#"
+            (module
+              (memory (export "memory") 1)
+              (func (export "alloc") (param i32) (result i32) (i32.const 0))
+              (func (export "on_bar") (param i32 i32) (result i64)
+                (loop $forever (br $forever))
+                (i64.const 0)))
+        "#;
+        let mut strategy = WasmStrategy::from_bytes(
+            "spin".to_string(),
+            &wat::parse_str(spin).unwrap(),
+            &serde_json::Value::Null,
+            LIMITS,
+        )
+        .unwrap();
+        assert!(strategy
+            .try_on_bar(&bar(), &Portfolio::new(10_000.0))
+            .is_err());
+
+        let host_access = r#"
+            (module
+              (import "env" "read_file" (func (param i32)))
+              (memory (export "memory") 1))
+        "#;
+        let err = WasmStrategy::from_bytes(
+            "io".to_string(),
+            &wat::parse_str(host_access).unwrap(),
+            &serde_json::Value::Null,
+            LIMITS,
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("may not import"));
+
+        let greedy = r#"(module (memory (export "memory") 64))"#;
+        assert!(WasmStrategy::from_bytes(
+            "greedy".to_string(),
+            &wat::parse_str(greedy).unwrap(),
+            &serde_json::Value::Null,
+            LIMITS,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_module_with_wrong_pinned_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("buy.wasm");
+        let wasm = wat::parse_str(BUY_EVERY_BAR).unwrap();
+        fs::write(&path, &wasm).unwrap();
+
+        let pinned = engine::stable_hash_bytes(&wasm);
+        let strategy = WasmStrategy::load(&path, &serde_json::json!(1), Some(&pinned), LIMITS);
+        assert_eq!(strategy.unwrap().name(), "buy");
+        assert!(WasmStrategy::load(&path, &serde_json::json!(1), Some("00"), LIMITS).is_err());
+    }
+}
@@ -1,7 +1,11 @@
 #![forbid(unsafe_code)]
 
-use schema::{CostModel, Side};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use schema::{CostModel, MarketAssetClass, MarketContext, Side};
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 
 /// Fixed commission per share with optional minimum
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,12 +33,18 @@ impl Default for FixedPerShareCost {
 }
 
 impl CostModel for FixedPerShareCost {
-    fn calculate_commission(&self, quantity: f64, _price: f64) -> f64 {
+    fn calculate_commission(&self, quantity: f64, _price: f64, _market: &MarketContext) -> f64 {
         let commission = quantity.abs() * self.cost_per_share;
         commission.max(self.minimum_commission)
     }
 
-    fn calculate_slippage(&self, _quantity: f64, _price: f64, _side: Side) -> f64 {
+    fn calculate_slippage(
+        &self,
+        _quantity: f64,
+        _price: f64,
+        _side: Side,
+        _market: &MarketContext,
+    ) -> f64 {
         // No slippage in this simple model
         0.0
     }
@@ -66,32 +76,303 @@ impl Default for PercentageCost {
 }
 
 impl CostModel for PercentageCost {
-    fn calculate_commission(&self, quantity: f64, price: f64) -> f64 {
+    fn calculate_commission(&self, quantity: f64, price: f64, _market: &MarketContext) -> f64 {
         let notional = quantity.abs() * price;
         let commission = notional * self.percentage;
         commission.max(self.minimum_commission)
     }
 
-    fn calculate_slippage(&self, _quantity: f64, _price: f64, _side: Side) -> f64 {
+    fn calculate_slippage(
+        &self,
+        _quantity: f64,
+        _price: f64,
+        _side: Side,
+        _market: &MarketContext,
+    ) -> f64 {
         // No slippage in this simple model
         0.0
     }
 }
 
+/// Percentage commission plus price impact that grows with the order's share
+/// of bar volume.
+///
+/// Slippage per share is `price * impact * participation^exponent`, where
+/// participation is quantity over bar volume: an exponent of 1.0 is linear
+/// and 0.5 the square-root law. A bar without volume counts as full
+/// participation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeParticipationCost {
+    pub percentage: f64,
+    pub minimum_commission: f64,
+    /// Slippage as a fraction of price when trading the whole bar's volume
+    pub impact: f64,
+    pub exponent: f64,
+}
+
+impl VolumeParticipationCost {
+    pub fn new(percentage: f64, minimum_commission: f64, impact: f64, exponent: f64) -> Self {
+        Self {
+            percentage,
+            minimum_commission,
+            impact,
+            exponent,
+        }
+    }
+}
+
+impl CostModel for VolumeParticipationCost {
+    fn calculate_commission(&self, quantity: f64, price: f64, _market: &MarketContext) -> f64 {
+        let notional = quantity.abs() * price;
+        (notional * self.percentage).max(self.minimum_commission)
+    }
+
+    fn calculate_slippage(
+        &self,
+        quantity: f64,
+        price: f64,
+        _side: Side,
+        market: &MarketContext,
+    ) -> f64 {
+        let participation = if market.volume > 0.0 {
+            quantity.abs() / market.volume
+        } else {
+            1.0
+        };
+        price * self.impact * participation.powf(self.exponent)
+    }
+}
+
+/// Distribution of slippage as a fraction of price; negative draws improve
+/// on the price
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SlippageDistribution {
+    Uniform {
+        low: f64,
+        high: f64,
+    },
+    Normal {
+        mean: f64,
+        std_dev: f64,
+    },
+    /// Always adverse, with a long tail of bad fills
+    Exponential {
+        mean: f64,
+    },
+}
+
+/// Standard normal draw via the Box-Muller transform
+pub fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+impl SlippageDistribution {
+    fn sample(&self, rng: &mut ChaCha8Rng) -> f64 {
+        match *self {
+            SlippageDistribution::Uniform { low, high } => low + (high - low) * rng.gen::<f64>(),
+            SlippageDistribution::Normal { mean, std_dev } => mean + std_dev * standard_normal(rng),
+            SlippageDistribution::Exponential { mean } => {
+                -mean * rng.gen_range(f64::EPSILON..1.0).ln()
+            }
+        }
+    }
+}
+
+/// Percentage commission plus random slippage for stress-testing a
+/// strategy's sensitivity to execution noise.
+///
+/// Draws come from a generator seeded with the backtest seed, so a run
+/// replays exactly.
+#[derive(Debug)]
+pub struct StochasticSlippageCost {
+    pub percentage: f64,
+    pub minimum_commission: f64,
+    pub distribution: SlippageDistribution,
+    rng: RefCell<ChaCha8Rng>,
+}
+
+impl StochasticSlippageCost {
+    pub fn new(
+        percentage: f64,
+        minimum_commission: f64,
+        distribution: SlippageDistribution,
+        seed: u64,
+    ) -> Self {
+        // Its own stream, apart from the broker's draws off the same seed
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(2);
+        Self {
+            percentage,
+            minimum_commission,
+            distribution,
+            rng: RefCell::new(rng),
+        }
+    }
+}
+
+impl CostModel for StochasticSlippageCost {
+    fn calculate_commission(&self, quantity: f64, price: f64, _market: &MarketContext) -> f64 {
+        let notional = quantity.abs() * price;
+        (notional * self.percentage).max(self.minimum_commission)
+    }
+
+    fn calculate_slippage(
+        &self,
+        _quantity: f64,
+        price: f64,
+        _side: Side,
+        _market: &MarketContext,
+    ) -> f64 {
+        price * self.distribution.sample(&mut self.rng.borrow_mut())
+    }
+}
+
 /// Zero cost model (for testing)
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ZeroCost;
 
 impl CostModel for ZeroCost {
-    fn calculate_commission(&self, _quantity: f64, _price: f64) -> f64 {
+    fn calculate_commission(&self, _quantity: f64, _price: f64, _market: &MarketContext) -> f64 {
         0.0
     }
 
-    fn calculate_slippage(&self, _quantity: f64, _price: f64, _side: Side) -> f64 {
+    fn calculate_slippage(
+        &self,
+        _quantity: f64,
+        _price: f64,
+        _side: Side,
+        _market: &MarketContext,
+    ) -> f64 {
         0.0
     }
 }
 
+/// Routes each trade to the cost model of its symbol, else of the symbol's
+/// asset class, else a default
+pub struct CompositeCostModel {
+    default: Box<dyn CostModel>,
+    asset_classes: BTreeMap<MarketAssetClass, Box<dyn CostModel>>,
+    symbols: BTreeMap<String, Box<dyn CostModel>>,
+    symbol_classes: BTreeMap<String, MarketAssetClass>,
+}
+
+impl CompositeCostModel {
+    pub fn new(default: Box<dyn CostModel>) -> Self {
+        Self {
+            default,
+            asset_classes: BTreeMap::new(),
+            symbols: BTreeMap::new(),
+            symbol_classes: BTreeMap::new(),
+        }
+    }
+
+    /// Price trades in symbols of `asset_class` with `model`
+    pub fn with_asset_class(
+        mut self,
+        asset_class: MarketAssetClass,
+        model: Box<dyn CostModel>,
+    ) -> Self {
+        self.asset_classes.insert(asset_class, model);
+        self
+    }
+
+    /// Price trades in `symbol` with `model`, whatever its asset class
+    pub fn with_symbol(mut self, symbol: impl Into<String>, model: Box<dyn CostModel>) -> Self {
+        self.symbols.insert(symbol.into(), model);
+        self
+    }
+
+    /// File `symbol` under `asset_class`
+    pub fn with_symbol_class(
+        mut self,
+        symbol: impl Into<String>,
+        asset_class: MarketAssetClass,
+    ) -> Self {
+        self.symbol_classes.insert(symbol.into(), asset_class);
+        self
+    }
+
+    /// The model that prices trades in `symbol`
+    pub fn model(&self, symbol: &str) -> &dyn CostModel {
+        if let Some(model) = self.symbols.get(symbol) {
+            return model.as_ref();
+        }
+        self.symbol_classes
+            .get(symbol)
+            .and_then(|asset_class| self.asset_classes.get(asset_class))
+            .unwrap_or(&self.default)
+            .as_ref()
+    }
+}
+
+impl CostModel for CompositeCostModel {
+    fn calculate_commission(&self, quantity: f64, price: f64, market: &MarketContext) -> f64 {
+        self.model(&market.symbol)
+            .calculate_commission(quantity, price, market)
+    }
+
+    fn calculate_slippage(
+        &self,
+        quantity: f64,
+        price: f64,
+        side: Side,
+        market: &MarketContext,
+    ) -> f64 {
+        self.model(&market.symbol)
+            .calculate_slippage(quantity, price, side, market)
+    }
+
+    fn calculate_exchange_fees(&self, quantity: f64, price: f64, market: &MarketContext) -> f64 {
+        self.model(&market.symbol)
+            .calculate_exchange_fees(quantity, price, market)
+    }
+
+    fn calculate_borrow_fee(&self, notional: f64, fee_rate: f64, days: f64) -> f64 {
+        self.default.calculate_borrow_fee(notional, fee_rate, days)
+    }
+}
+
+/// Another model's commission, exchange fees and slippage times
+/// `multiplier`, for testing how results hold up as trading gets dearer.
+/// Borrow fees are a cost of holding and stay as they are.
+pub struct ScaledCost<C> {
+    model: C,
+    multiplier: f64,
+}
+
+impl<C: CostModel> ScaledCost<C> {
+    pub fn new(model: C, multiplier: f64) -> Self {
+        Self { model, multiplier }
+    }
+}
+
+impl<C: CostModel> CostModel for ScaledCost<C> {
+    fn calculate_commission(&self, quantity: f64, price: f64, market: &MarketContext) -> f64 {
+        self.model.calculate_commission(quantity, price, market) * self.multiplier
+    }
+
+    fn calculate_slippage(
+        &self,
+        quantity: f64,
+        price: f64,
+        side: Side,
+        market: &MarketContext,
+    ) -> f64 {
+        self.model.calculate_slippage(quantity, price, side, market) * self.multiplier
+    }
+
+    fn calculate_exchange_fees(&self, quantity: f64, price: f64, market: &MarketContext) -> f64 {
+        self.model.calculate_exchange_fees(quantity, price, market) * self.multiplier
+    }
+
+    fn calculate_borrow_fee(&self, notional: f64, fee_rate: f64, days: f64) -> f64 {
+        self.model.calculate_borrow_fee(notional, fee_rate, days)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,10 +382,11 @@ mod tests {
         let cost = FixedPerShareCost::new(0.01, 5.0);
 
         // Small trade - should use minimum
-        assert_eq!(cost.calculate_commission(100.0, 50.0), 5.0);
+        let market = MarketContext::default();
+        assert_eq!(cost.calculate_commission(100.0, 50.0, &market), 5.0);
 
         // Large trade - should exceed minimum
-        assert_eq!(cost.calculate_commission(1000.0, 50.0), 10.0);
+        assert_eq!(cost.calculate_commission(1000.0, 50.0, &market), 10.0);
     }
 
     #[test]
@@ -112,17 +394,112 @@ mod tests {
         let cost = PercentageCost::new(0.001, 1.0);
 
         // $5000 notional at 0.1% = $5
-        assert_eq!(cost.calculate_commission(100.0, 50.0), 5.0);
+        let market = MarketContext::default();
+        assert_eq!(cost.calculate_commission(100.0, 50.0, &market), 5.0);
 
         // Small trade - should use minimum
-        assert_eq!(cost.calculate_commission(10.0, 5.0), 1.0);
+        assert_eq!(cost.calculate_commission(10.0, 5.0, &market), 1.0);
     }
 
     #[test]
     fn test_zero_cost() {
         let cost = ZeroCost;
-        assert_eq!(cost.calculate_commission(100.0, 50.0), 0.0);
-        assert_eq!(cost.calculate_slippage(100.0, 50.0, Side::Buy), 0.0);
+        let market = MarketContext {
+            volume: 1000.0,
+            ..MarketContext::default()
+        };
+        assert_eq!(cost.calculate_commission(100.0, 50.0, &market), 0.0);
+        assert_eq!(
+            cost.calculate_slippage(100.0, 50.0, Side::Buy, &market),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_scaled_cost_multiplies_trading_costs_only() {
+        let market = MarketContext {
+            volume: 10_000.0,
+            ..MarketContext::default()
+        };
+        let base = || VolumeParticipationCost::new(0.001, 1.0, 0.1, 1.0);
+        let doubled = ScaledCost::new(base(), 2.0);
+        assert_eq!(doubled.calculate_commission(100.0, 50.0, &market), 10.0);
+        assert!(
+            (doubled.calculate_slippage(1_000.0, 50.0, Side::Buy, &market) - 1.0).abs() < 1e-12
+        );
+        assert_eq!(
+            doubled.calculate_borrow_fee(36_000.0, 0.1, 1.0),
+            base().calculate_borrow_fee(36_000.0, 0.1, 1.0)
+        );
+
+        let free = ScaledCost::new(base(), 0.0);
+        assert_eq!(free.calculate_commission(100.0, 50.0, &market), 0.0);
+    }
+
+    #[test]
+    fn test_volume_participation_slippage() {
+        let market = MarketContext {
+            volume: 10_000.0,
+            ..MarketContext::default()
+        };
+        let linear = VolumeParticipationCost::new(0.0, 0.0, 0.1, 1.0);
+        // 10% of the bar at 10% impact moves a $50 price by 1%
+        assert!((linear.calculate_slippage(1_000.0, 50.0, Side::Buy, &market) - 0.5).abs() < 1e-12);
+
+        // The square-root law charges small orders relatively more
+        let sqrt = VolumeParticipationCost::new(0.0, 0.0, 0.1, 0.5);
+        let small = sqrt.calculate_slippage(100.0, 50.0, Side::Sell, &market);
+        let large = sqrt.calculate_slippage(10_000.0, 50.0, Side::Sell, &market);
+        assert!((small - 0.5).abs() < 1e-12);
+        assert!((large - 5.0).abs() < 1e-12);
+        let no_volume = MarketContext::default();
+        assert_eq!(
+            sqrt.calculate_slippage(1.0, 50.0, Side::Buy, &no_volume),
+            5.0
+        );
+    }
+
+    #[test]
+    fn test_stochastic_slippage_replays_from_its_seed() {
+        let normal = SlippageDistribution::Normal {
+            mean: 0.001,
+            std_dev: 0.0005,
+        };
+        let draws = |seed: u64| {
+            let cost = StochasticSlippageCost::new(0.0, 0.0, normal, seed);
+            let market = MarketContext::default();
+            (0..1_000)
+                .map(|_| cost.calculate_slippage(100.0, 50.0, Side::Buy, &market))
+                .collect::<Vec<f64>>()
+        };
+        let run = draws(7);
+        assert_eq!(run, draws(7));
+        assert_ne!(run, draws(8));
+        // Centred on 0.1% of a $50 price
+        let mean = run.iter().sum::<f64>() / run.len() as f64;
+        assert!((mean - 0.05).abs() < 0.005);
+
+        let uniform = StochasticSlippageCost::new(
+            0.0,
+            0.0,
+            SlippageDistribution::Uniform {
+                low: 0.0,
+                high: 0.002,
+            },
+            7,
+        );
+        let exponential = StochasticSlippageCost::new(
+            0.0,
+            0.0,
+            SlippageDistribution::Exponential { mean: 0.001 },
+            7,
+        );
+        let market = MarketContext::default();
+        for _ in 0..100 {
+            let u = uniform.calculate_slippage(1.0, 50.0, Side::Sell, &market);
+            assert!((0.0..=0.1).contains(&u));
+            assert!(exponential.calculate_slippage(1.0, 50.0, Side::Sell, &market) > 0.0);
+        }
     }
 
     #[test]
@@ -133,21 +510,55 @@ mod tests {
             Box::new(ZeroCost),
         ];
 
+        let market = MarketContext {
+            volume: 1_000_000.0,
+            ..MarketContext::default()
+        };
         for cost_model in costs {
             // Commission should always be non-negative
-            let comm1 = cost_model.calculate_commission(100.0, 50.0);
+            let comm1 = cost_model.calculate_commission(100.0, 50.0, &market);
             assert!(comm1 >= 0.0, "Commission should be non-negative");
 
             // Commission should scale with quantity (or stay at minimum)
-            let comm2 = cost_model.calculate_commission(1000.0, 50.0);
+            let comm2 = cost_model.calculate_commission(1000.0, 50.0, &market);
             assert!(
                 comm2 >= comm1,
                 "Commission should not decrease with quantity"
             );
 
             // Slippage should be zero or small
-            let slippage = cost_model.calculate_slippage(100.0, 50.0, Side::Buy);
+            let slippage = cost_model.calculate_slippage(100.0, 50.0, Side::Buy, &market);
             assert!(slippage.abs() < 10.0, "Slippage should be reasonable");
         }
     }
+
+    #[test]
+    fn test_composite_routes_by_symbol_then_asset_class() {
+        let composite = CompositeCostModel::new(Box::new(PercentageCost::new(0.001, 0.0)))
+            .with_asset_class(
+                MarketAssetClass::Equity,
+                Box::new(FixedPerShareCost::new(0.005, 1.0)),
+            )
+            .with_asset_class(
+                MarketAssetClass::Future,
+                Box::new(FixedPerShareCost::new(2.25, 0.0)),
+            )
+            .with_symbol_class("AAPL", MarketAssetClass::Equity)
+            .with_symbol_class("ES", MarketAssetClass::Future)
+            .with_symbol_class("GME", MarketAssetClass::Equity)
+            .with_symbol("GME", Box::new(ZeroCost));
+        let commission = |symbol: &str, quantity: f64, price: f64| {
+            let market = MarketContext {
+                symbol: symbol.to_string(),
+                volume: 1_000.0,
+            };
+            composite.calculate_commission(quantity, price, &market)
+        };
+        // Per share, per contract, and crypto on the percentage default
+        assert_eq!(commission("AAPL", 1_000.0, 150.0), 5.0);
+        assert_eq!(commission("ES", 4.0, 5_000.0), 9.0);
+        assert!((commission("BTC-USD", 0.5, 60_000.0) - 30.0).abs() < 1e-9);
+        // A symbol's own model wins over its asset class
+        assert_eq!(commission("GME", 1_000.0, 20.0), 0.0);
+    }
 }
@@ -1,7 +1,11 @@
 #![forbid(unsafe_code)]
 
+pub mod policy;
 pub mod types;
 pub mod verifier;
 
+pub use policy::{GateOutcome, VerificationPolicy};
 pub use types::{CRVReport, CRVViolation, RuleId, Severity};
-pub use verifier::{CRVVerifier, PolicyConstraints, UniverseMetadata};
+pub use verifier::{
+    walk_forward_efficiency, CRVVerifier, PolicyConstraints, UniverseMetadata, WalkForwardWindow,
+};
@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::types::{CRVReport, RuleId, Severity};
+use crate::verifier::PolicyConstraints;
+
+/// Rule configuration and gating policy for re-running CRV outside a backtest.
+///
+/// Loaded from JSON, e.g.
+/// `{"constraints": {"max_drawdown": 0.3}, "disabled_rules": ["turnover_constraint"], "fail_on": "high"}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationPolicy {
+    #[serde(default)]
+    pub constraints: PolicyConstraints,
+    /// Rules whose violations are dropped from the report
+    #[serde(default)]
+    pub disabled_rules: Vec<RuleId>,
+    /// Lowest severity that fails the gate
+    #[serde(default = "default_fail_on")]
+    pub fail_on: Severity,
+}
+
+fn default_fail_on() -> Severity {
+    Severity::High
+}
+
+impl Default for VerificationPolicy {
+    fn default() -> Self {
+        Self {
+            constraints: PolicyConstraints::default(),
+            disabled_rules: Vec::new(),
+            fail_on: default_fail_on(),
+        }
+    }
+}
+
+/// Outcome of applying a gating policy to a CRV report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateOutcome {
+    pub passed: bool,
+    pub fail_on: Severity,
+    pub blocking_violations: usize,
+    pub total_violations: usize,
+}
+
+impl VerificationPolicy {
+    pub fn load(path: &Path) -> Result<Self> {
+        let policy_str = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read policy file {:?}", path))?;
+        serde_json::from_str(&policy_str).context("Failed to parse policy JSON")
+    }
+
+    /// Drop violations of disabled rules, recomputing `passed`
+    pub fn apply(&self, report: CRVReport) -> CRVReport {
+        let mut filtered = CRVReport::new(report.timestamp);
+        for violation in report.violations {
+            if !self.disabled_rules.contains(&violation.rule_id) {
+                filtered.add_violation(violation);
+            }
+        }
+        filtered
+    }
+
+    /// Decide whether the report passes the gate
+    pub fn gate(&self, report: &CRVReport) -> GateOutcome {
+        let blocking_violations = report.count_at_least(self.fail_on);
+        GateOutcome {
+            passed: blocking_violations == 0,
+            fail_on: self.fail_on,
+            blocking_violations,
+            total_violations: report.violation_count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CRVViolation;
+
+    fn report_with(violations: &[(RuleId, Severity)]) -> CRVReport {
+        let mut report = CRVReport::new(1);
+        for (rule_id, severity) in violations {
+            report.add_violation(CRVViolation {
+                rule_id: *rule_id,
+                severity: *severity,
+                message: String::new(),
+                evidence: vec![],
+            });
+        }
+        report
+    }
+
+    #[test]
+    fn parses_partial_policy_with_defaults() {
+        let policy: VerificationPolicy = serde_json::from_str(
+            r#"{"constraints": {"max_drawdown": 0.3}, "disabled_rules": ["data_quality"]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.constraints.max_drawdown, Some(0.3));
+        assert_eq!(policy.constraints.max_leverage, Some(2.0));
+        assert_eq!(policy.disabled_rules, vec![RuleId::DataQuality]);
+        assert_eq!(policy.fail_on, Severity::High);
+    }
+
+    #[test]
+    fn disabled_rules_and_threshold_decide_gate() {
+        let report = report_with(&[
+            (RuleId::DataQuality, Severity::High),
+            (RuleId::SharpeRatioValidation, Severity::Medium),
+        ]);
+
+        let strict = VerificationPolicy {
+            fail_on: Severity::Medium,
+            ..VerificationPolicy::default()
+        };
+        assert_eq!(strict.gate(&report).blocking_violations, 2);
+
+        let lenient = VerificationPolicy {
+            disabled_rules: vec![RuleId::DataQuality],
+            ..VerificationPolicy::default()
+        };
+        let filtered = lenient.apply(report);
+        assert_eq!(filtered.violation_count(), 1);
+        assert!(!filtered.passed);
+        assert!(lenient.gate(&filtered).passed);
+        assert!(lenient.gate(&lenient.apply(CRVReport::new(1))).passed);
+    }
+}
@@ -11,6 +11,23 @@ pub enum Severity {
     Info,
 }
 
+impl Severity {
+    /// Whether this severity is at least as serious as `threshold`
+    pub fn at_least(self, threshold: Severity) -> bool {
+        self.rank() >= threshold.rank()
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            Severity::Info => 0,
+            Severity::Low => 1,
+            Severity::Medium => 2,
+            Severity::High => 3,
+            Severity::Critical => 4,
+        }
+    }
+}
+
 /// Rule identifier for different types of checks
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -29,6 +46,14 @@ pub enum RuleId {
     MaxLeverageConstraint,
     /// Turnover policy constraint
     TurnoverConstraint,
+    /// Max position size policy constraint
+    MaxPositionSizeConstraint,
+    /// Input data quality (gaps, duplicates, ordering)
+    DataQuality,
+    /// Out-of-sample performance degradation across walk-forward windows
+    WalkForwardDegradation,
+    /// The drawdown kill switch flattened the account and stopped trading
+    KillSwitch,
 }
 
 /// A single violation found during CRV verification
@@ -71,6 +96,14 @@ impl CRVReport {
     pub fn violation_count(&self) -> usize {
         self.violations.len()
     }
+
+    /// Number of violations at or above `threshold`
+    pub fn count_at_least(&self, threshold: Severity) -> usize {
+        self.violations
+            .iter()
+            .filter(|v| v.severity.at_least(threshold))
+            .count()
+    }
 }
 
 #[cfg(test)]
@@ -104,6 +137,24 @@ mod tests {
         assert!(report.has_critical_violations());
     }
 
+    #[test]
+    fn test_severity_threshold_counts() {
+        let mut report = CRVReport::new(0);
+        for severity in [Severity::Low, Severity::High, Severity::Critical] {
+            report.add_violation(CRVViolation {
+                rule_id: RuleId::DataQuality,
+                severity,
+                message: String::new(),
+                evidence: vec![],
+            });
+        }
+
+        assert!(Severity::Critical.at_least(Severity::High));
+        assert!(!Severity::Medium.at_least(Severity::High));
+        assert_eq!(report.count_at_least(Severity::High), 2);
+        assert_eq!(report.count_at_least(Severity::Info), 3);
+    }
+
     #[test]
     fn test_crv_report_serialization() {
         let mut report = CRVReport::new(12345);
@@ -1,6 +1,10 @@
 use crate::types::{CRVReport, CRVViolation, RuleId, Severity};
 use anyhow::Result;
-use schema::{BacktestStats, Fill};
+use schema::{
+    BacktestStats, DataQualityReport, ExposurePoint, Fill, KillSwitchTrip, OrderRejection,
+    RejectionReason,
+};
+use serde::{Deserialize, Serialize};
 
 /// Threshold for unrealistic Sharpe ratio (annualized)
 const SHARPE_RATIO_UNREALISTIC_THRESHOLD: f64 = 10.0;
@@ -17,12 +21,62 @@ const MIN_UNIVERSE_SIZE_FOR_CHERRY_PICKING: usize = 10;
 /// Tolerance for max drawdown calculation validation
 const MAX_DRAWDOWN_TOLERANCE: f64 = 0.01;
 
+/// Maximum share of expected sessions that may be missing from the input data
+const DATA_QUALITY_MAX_MISSING_PCT: f64 = 5.0;
+
+/// Data quality score below which results are considered unreliable
+const DATA_QUALITY_MIN_SCORE: f64 = 80.0;
+
+/// Out-of-sample Sharpe below this fraction of in-sample Sharpe counts as degradation
+const WALK_FORWARD_MIN_EFFICIENCY: f64 = 0.5;
+
+/// Maximum share of windows where a profitable in-sample run loses out of sample
+const WALK_FORWARD_MAX_FLIP_PCT: f64 = 50.0;
+
+/// Report the first point whose `measure` exceeds `limit` under `rule`
+fn check_exposure_limit(
+    exposure_history: &[ExposurePoint],
+    limit: f64,
+    (rule_id, severity, name): (RuleId, Severity, &str),
+    measure: fn(&ExposurePoint) -> f64,
+    report: &mut CRVReport,
+) {
+    let breach = exposure_history
+        .iter()
+        .enumerate()
+        .find(|(_, point)| measure(point) > limit);
+    if let Some((i, point)) = breach {
+        report.add_violation(CRVViolation {
+            rule_id,
+            severity,
+            message: format!(
+                "{} {:.2}x exceeds limit {:.2}x",
+                name,
+                measure(point),
+                limit
+            ),
+            evidence: vec![
+                format!(
+                    "Point #{}: timestamp={}, gross exposure={:.2}, traded notional={:.2}",
+                    i, point.timestamp, point.gross_exposure, point.traded_notional
+                ),
+                format!("Limit: {:.4}", limit),
+            ],
+        });
+    }
+}
+
 /// Policy constraints for verification
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PolicyConstraints {
     pub max_drawdown: Option<f64>,
     pub max_leverage: Option<f64>,
     pub max_turnover: Option<f64>,
+    /// One position's absolute value over equity; enforced before each
+    /// trade rather than verified afterwards
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_position_size: Option<f64>,
 }
 
 impl Default for PolicyConstraints {
@@ -31,6 +85,7 @@ impl Default for PolicyConstraints {
             max_drawdown: Some(0.25), // 25% default max drawdown
             max_leverage: Some(2.0),  // 2x default max leverage
             max_turnover: None,       // No default turnover limit
+            max_position_size: None,
         }
     }
 }
@@ -51,6 +106,42 @@ pub struct UniverseMetadata {
     pub traded_symbols: Vec<String>,
 }
 
+/// In-sample and out-of-sample results for one walk-forward window
+#[derive(Debug, Clone)]
+pub struct WalkForwardWindow {
+    pub label: String,
+    pub in_sample: BacktestStats,
+    pub out_of_sample: BacktestStats,
+    /// CRV report of the out-of-sample run
+    pub report: CRVReport,
+}
+
+/// Mean out-of-sample Sharpe divided by mean in-sample Sharpe.
+///
+/// `None` when there are no windows or the in-sample Sharpe is not positive,
+/// since the ratio is meaningless without an in-sample edge.
+pub fn walk_forward_efficiency(windows: &[WalkForwardWindow]) -> Option<f64> {
+    if windows.is_empty() {
+        return None;
+    }
+    let n = windows.len() as f64;
+    let is_sharpe = windows
+        .iter()
+        .map(|w| w.in_sample.sharpe_ratio)
+        .sum::<f64>()
+        / n;
+    let oos_sharpe = windows
+        .iter()
+        .map(|w| w.out_of_sample.sharpe_ratio)
+        .sum::<f64>()
+        / n;
+    if is_sharpe > 0.0 && is_sharpe.is_finite() && oos_sharpe.is_finite() {
+        Some(oos_sharpe / is_sharpe)
+    } else {
+        None
+    }
+}
+
 impl CRVVerifier {
     pub fn new(constraints: PolicyConstraints) -> Self {
         Self { constraints }
@@ -98,6 +189,271 @@ impl CRVVerifier {
         Ok(report)
     }
 
+    /// Verify backtest together with a quality report for its input data
+    pub fn verify_with_data_quality(
+        &self,
+        stats: &BacktestStats,
+        fills: &[Fill],
+        equity_history: &[(i64, f64)],
+        quality: &DataQualityReport,
+    ) -> Result<CRVReport> {
+        let mut report = self.verify(stats, fills, equity_history)?;
+
+        self.check_data_quality(quality, &mut report)?;
+
+        Ok(report)
+    }
+
+    /// Combine out-of-sample window reports and check for walk-forward degradation.
+    ///
+    /// Window violations are merged once per rule and message, with the window
+    /// labels they occurred in added as evidence.
+    pub fn verify_walk_forward(&self, windows: &[WalkForwardWindow]) -> Result<CRVReport> {
+        if windows.is_empty() {
+            anyhow::bail!("Walk-forward verification requires at least one window");
+        }
+
+        let timestamp = windows
+            .iter()
+            .map(|w| w.report.timestamp)
+            .max()
+            .unwrap_or(0);
+        let mut report = CRVReport::new(timestamp);
+
+        let mut merged: Vec<(CRVViolation, Vec<String>)> = Vec::new();
+        for window in windows {
+            for violation in &window.report.violations {
+                match merged
+                    .iter_mut()
+                    .find(|(v, _)| v.rule_id == violation.rule_id && v.message == violation.message)
+                {
+                    Some((_, labels)) => labels.push(window.label.clone()),
+                    None => merged.push((violation.clone(), vec![window.label.clone()])),
+                }
+            }
+        }
+        for (mut violation, labels) in merged {
+            violation
+                .evidence
+                .insert(0, format!("Windows: {}", labels.join(", ")));
+            report.add_violation(violation);
+        }
+
+        self.check_walk_forward_degradation(windows, &mut report)?;
+
+        Ok(report)
+    }
+
+    /// Check that out-of-sample results hold up against in-sample results
+    fn check_walk_forward_degradation(
+        &self,
+        windows: &[WalkForwardWindow],
+        report: &mut CRVReport,
+    ) -> Result<()> {
+        if let Some(efficiency) = walk_forward_efficiency(windows) {
+            if efficiency < WALK_FORWARD_MIN_EFFICIENCY {
+                report.add_violation(CRVViolation {
+                    rule_id: RuleId::WalkForwardDegradation,
+                    severity: Severity::Medium,
+                    message: format!(
+                        "Out-of-sample Sharpe is {:.1}% of in-sample Sharpe",
+                        efficiency * 100.0
+                    ),
+                    evidence: vec![
+                        format!("Windows: {}", windows.len()),
+                        format!(
+                            "Minimum efficiency: {:.1}%",
+                            WALK_FORWARD_MIN_EFFICIENCY * 100.0
+                        ),
+                        "Large degradation suggests the strategy is overfit".to_string(),
+                    ],
+                });
+            }
+        }
+
+        let profitable: Vec<&WalkForwardWindow> = windows
+            .iter()
+            .filter(|w| w.in_sample.total_return > 0.0)
+            .collect();
+        if !profitable.is_empty() {
+            let flipped: Vec<&str> = profitable
+                .iter()
+                .filter(|w| w.out_of_sample.total_return <= 0.0)
+                .map(|w| w.label.as_str())
+                .collect();
+            let flip_pct = flipped.len() as f64 / profitable.len() as f64 * 100.0;
+            if flip_pct > WALK_FORWARD_MAX_FLIP_PCT {
+                report.add_violation(CRVViolation {
+                    rule_id: RuleId::WalkForwardDegradation,
+                    severity: Severity::High,
+                    message: format!(
+                        "{} of {} profitable in-sample window(s) lost money out of sample",
+                        flipped.len(),
+                        profitable.len()
+                    ),
+                    evidence: vec![
+                        format!("Windows: {}", flipped.join(", ")),
+                        format!("Limit: {:.1}%", WALK_FORWARD_MAX_FLIP_PCT),
+                    ],
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check input data quality: duplicates, gaps, ordering and overall score
+    fn check_data_quality(
+        &self,
+        quality: &DataQualityReport,
+        report: &mut CRVReport,
+    ) -> Result<()> {
+        if quality.duplicate_events > 0 {
+            report.add_violation(CRVViolation {
+                rule_id: RuleId::DataQuality,
+                severity: Severity::High,
+                message: format!(
+                    "Input data contains {} duplicate event(s)",
+                    quality.duplicate_events
+                ),
+                evidence: vec![
+                    format!("Total events: {}", quality.total_events),
+                    "Duplicated bars are double-counted by strategies and the engine".to_string(),
+                ],
+            });
+        }
+
+        let missing_pct = (1.0 - quality.completeness()) * 100.0;
+        if missing_pct > DATA_QUALITY_MAX_MISSING_PCT {
+            report.add_violation(CRVViolation {
+                rule_id: RuleId::DataQuality,
+                severity: Severity::Medium,
+                message: format!(
+                    "Input data is missing {:.1}% of expected sessions ({} missing)",
+                    missing_pct, quality.missing_sessions
+                ),
+                evidence: vec![
+                    format!(
+                        "Gaps: {}, max gap: {}s, expected interval: {}s",
+                        quality.gaps.gap_count,
+                        quality.gaps.max_gap,
+                        quality.gaps.expected_interval
+                    ),
+                    format!("Limit: {:.1}%", DATA_QUALITY_MAX_MISSING_PCT),
+                ],
+            });
+        }
+
+        if quality.out_of_order_events > 0 {
+            report.add_violation(CRVViolation {
+                rule_id: RuleId::DataQuality,
+                severity: Severity::Low,
+                message: format!(
+                    "Input data had {} out-of-order event(s) ({:.2}%)",
+                    quality.out_of_order_events,
+                    quality.out_of_order_ratio * 100.0
+                ),
+                evidence: vec![
+                    "Events were reordered deterministically before replay".to_string(),
+                    "Verify the source does not deliver late or restated records".to_string(),
+                ],
+            });
+        }
+
+        if quality.score < DATA_QUALITY_MIN_SCORE {
+            report.add_violation(CRVViolation {
+                rule_id: RuleId::DataQuality,
+                severity: Severity::Medium,
+                message: format!("Data quality score {:.1} is below minimum", quality.score),
+                evidence: vec![format!("Minimum score: {:.1}", DATA_QUALITY_MIN_SCORE)],
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check the leverage and turnover at each equity mark against the
+    /// policy limits, adding the first breach of each to `report`
+    pub fn verify_exposure(&self, exposure_history: &[ExposurePoint], report: &mut CRVReport) {
+        if let Some(limit) = self.constraints.max_leverage {
+            check_exposure_limit(
+                exposure_history,
+                limit,
+                (RuleId::MaxLeverageConstraint, Severity::High, "Leverage"),
+                |point| point.leverage,
+                report,
+            );
+        }
+        if let Some(limit) = self.constraints.max_turnover {
+            check_exposure_limit(
+                exposure_history,
+                limit,
+                (RuleId::TurnoverConstraint, Severity::Medium, "Turnover"),
+                |point| point.turnover,
+                report,
+            );
+        }
+    }
+
+    /// Log the orders pre-trade risk checks refused, one violation per
+    /// limit with the first refusal as evidence
+    pub fn verify_risk_rejections(&self, rejections: &[OrderRejection], report: &mut CRVReport) {
+        let mut by_limit: Vec<(&str, f64, Vec<&OrderRejection>)> = Vec::new();
+        for rejection in rejections {
+            let RejectionReason::RiskLimit { limit, maximum } = &rejection.reason else {
+                continue;
+            };
+            match by_limit.iter_mut().find(|(name, _, _)| name == limit) {
+                Some((_, _, refused)) => refused.push(rejection),
+                None => by_limit.push((limit, *maximum, vec![rejection])),
+            }
+        }
+        for (limit, maximum, refused) in by_limit {
+            let rule_id = match limit {
+                "max_leverage" => RuleId::MaxLeverageConstraint,
+                "max_turnover" => RuleId::TurnoverConstraint,
+                _ => RuleId::MaxPositionSizeConstraint,
+            };
+            let first = refused[0];
+            report.add_violation(CRVViolation {
+                rule_id,
+                severity: Severity::Info,
+                message: format!(
+                    "Pre-trade checks refused {} order(s) at the {} limit of {:.2}x",
+                    refused.len(),
+                    limit,
+                    maximum
+                ),
+                evidence: vec![
+                    format!(
+                        "First: timestamp={}, {:?} {} {}",
+                        first.timestamp, first.order.side, first.order.quantity, first.order.symbol
+                    ),
+                    "The limit held; the strategy asked for more than it allows".to_string(),
+                ],
+            });
+        }
+    }
+
+    /// Log each trip of the drawdown kill switch
+    pub fn verify_kill_switch(&self, trips: &[KillSwitchTrip], report: &mut CRVReport) {
+        for trip in trips {
+            report.add_violation(CRVViolation {
+                rule_id: RuleId::KillSwitch,
+                severity: Severity::Medium,
+                message: format!(
+                    "Kill switch tripped at a {:.2}% drawdown, past its {:.2}% threshold; positions were closed and trading stopped",
+                    trip.drawdown * 100.0,
+                    trip.max_drawdown * 100.0
+                ),
+                evidence: vec![format!(
+                    "timestamp={}, equity={:.2}, peak equity={:.2}",
+                    trip.timestamp, trip.equity, trip.peak_equity
+                )],
+            });
+        }
+    }
+
     /// Check for survivorship bias in universe composition
     fn check_survivorship_bias(
         &self,
@@ -351,6 +707,7 @@ impl CRVVerifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use schema::CostBreakdown;
 
     fn create_test_stats() -> BacktestStats {
         BacktestStats {
@@ -359,8 +716,14 @@ mod tests {
             total_return: 0.1,
             num_trades: 10,
             total_commission: 50.0,
+            costs: CostBreakdown::from_commission(50.0),
             sharpe_ratio: 1.5,
             max_drawdown: 0.15,
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
         }
     }
 
@@ -375,8 +738,14 @@ mod tests {
             total_return: 0.1,
             num_trades: 10,
             total_commission: 50.0,
+            costs: CostBreakdown::from_commission(50.0),
             sharpe_ratio: 1.5,
             max_drawdown: 0.05, // 5% max drawdown
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
         };
 
         let fills = vec![];
@@ -407,8 +776,10 @@ mod tests {
 
     #[test]
     fn test_verifier_detects_max_drawdown_violation() {
-        let mut constraints = PolicyConstraints::default();
-        constraints.max_drawdown = Some(0.10); // 10% limit
+        let constraints = PolicyConstraints {
+            max_drawdown: Some(0.10), // 10% limit
+            ..PolicyConstraints::default()
+        };
 
         let verifier = CRVVerifier::new(constraints);
 
@@ -483,7 +854,7 @@ mod tests {
                 side: schema::Side::Buy,
                 quantity: 10.0,
                 price: 100.0,
-                commission: 5.0,
+                costs: CostBreakdown::from_commission(5.0),
             },
             Fill {
                 timestamp: 1000, // Out of order!
@@ -491,7 +862,7 @@ mod tests {
                 side: schema::Side::Sell,
                 quantity: 10.0,
                 price: 105.0,
-                commission: 5.0,
+                costs: CostBreakdown::from_commission(5.0),
             },
         ];
 
@@ -524,6 +895,72 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn exposure_over_the_leverage_and_turnover_limits_is_reported() {
+        let exposure = [
+            ExposurePoint::new(1000, 150_000.0, 150_000.0, 150_000.0, 100_000.0),
+            ExposurePoint::new(2000, 250_000.0, -50_000.0, 400_000.0, 100_000.0),
+            ExposurePoint::new(3000, 300_000.0, 0.0, 600_000.0, 100_000.0),
+        ];
+        let mut report = CRVReport::new(3000);
+        CRVVerifier::with_defaults().verify_exposure(&exposure, &mut report);
+        // No default turnover limit; 2.5x is the first mark over 2x leverage
+        assert_eq!(report.violation_count(), 1);
+        assert_eq!(report.violations[0].rule_id, RuleId::MaxLeverageConstraint);
+        assert!(report.violations[0].evidence[0].starts_with("Point #1"));
+
+        let verifier = CRVVerifier::new(PolicyConstraints {
+            max_leverage: None,
+            max_turnover: Some(5.0),
+            ..PolicyConstraints::default()
+        });
+        let mut report = CRVReport::new(3000);
+        verifier.verify_exposure(&exposure, &mut report);
+        assert_eq!(report.violations[0].rule_id, RuleId::TurnoverConstraint);
+        assert!(report.violations[0].message.starts_with("Turnover 6.00x"));
+    }
+
+    #[test]
+    fn risk_check_refusals_are_logged_per_limit() {
+        let refusal = |timestamp, limit: &str| OrderRejection {
+            timestamp,
+            order: schema::Order {
+                symbol: "AAPL".to_string(),
+                side: schema::Side::Buy,
+                quantity: 10.0,
+                order_type: schema::OrderType::Market,
+                limit_price: None,
+                stop_price: None,
+                order_id: None,
+                bracket: None,
+            },
+            reason: RejectionReason::RiskLimit {
+                limit: limit.to_string(),
+                maximum: 2.0,
+            },
+        };
+        let rejections = [
+            refusal(1000, "max_leverage"),
+            refusal(2000, "max_position_size"),
+            refusal(3000, "max_leverage"),
+        ];
+        let mut report = CRVReport::new(3000);
+        CRVVerifier::with_defaults().verify_risk_rejections(&rejections, &mut report);
+
+        assert_eq!(report.violation_count(), 2);
+        assert_eq!(report.count_at_least(Severity::Low), 0);
+        let leverage = &report.violations[0];
+        assert_eq!(leverage.rule_id, RuleId::MaxLeverageConstraint);
+        assert!(leverage
+            .message
+            .starts_with("Pre-trade checks refused 2 order(s)"));
+        assert!(leverage.evidence[0].starts_with("First: timestamp=1000"));
+        assert_eq!(
+            report.violations[1].rule_id,
+            RuleId::MaxPositionSizeConstraint
+        );
+    }
+
     #[test]
     fn test_verifier_detects_survivorship_bias_delisted() {
         let verifier = CRVVerifier::with_defaults();
@@ -577,6 +1014,90 @@ mod tests {
             .any(|v| v.rule_id == RuleId::SurvivorshipBias && v.severity == Severity::Medium));
     }
 
+    #[test]
+    fn test_verifier_detects_poor_data_quality() {
+        let verifier = CRVVerifier::with_defaults();
+        let stats = BacktestStats {
+            max_drawdown: 0.0,
+            ..create_test_stats()
+        };
+        let fills = vec![];
+        let equity_history = vec![(1000, 100000.0), (2000, 110000.0)];
+
+        let clean = DataQualityReport {
+            total_events: 100,
+            score: 100.0,
+            ..Default::default()
+        };
+        let report = verifier
+            .verify_with_data_quality(&stats, &fills, &equity_history, &clean)
+            .unwrap();
+        assert!(report.passed);
+
+        let broken = DataQualityReport {
+            total_events: 100,
+            missing_sessions: 20,
+            duplicate_events: 3,
+            score: 60.0,
+            ..Default::default()
+        };
+        let report = verifier
+            .verify_with_data_quality(&stats, &fills, &equity_history, &broken)
+            .unwrap();
+        assert!(!report.passed);
+        assert!(report
+            .violations
+            .iter()
+            .any(|v| v.rule_id == RuleId::DataQuality && v.severity == Severity::High));
+    }
+
+    #[test]
+    fn test_verifier_detects_walk_forward_degradation() {
+        let verifier = CRVVerifier::with_defaults();
+        let window = |label: &str, is_sharpe: f64, oos_sharpe: f64, oos_return: f64| {
+            let mut report = CRVReport::new(1000);
+            report.add_violation(CRVViolation {
+                rule_id: RuleId::DataQuality,
+                severity: Severity::Low,
+                message: "shared issue".to_string(),
+                evidence: vec![],
+            });
+            WalkForwardWindow {
+                label: label.to_string(),
+                in_sample: BacktestStats {
+                    sharpe_ratio: is_sharpe,
+                    ..create_test_stats()
+                },
+                out_of_sample: BacktestStats {
+                    sharpe_ratio: oos_sharpe,
+                    total_return: oos_return,
+                    ..create_test_stats()
+                },
+                report,
+            }
+        };
+
+        let robust = vec![window("w1", 1.5, 1.2, 0.05), window("w2", 1.5, 1.0, 0.02)];
+        let report = verifier.verify_walk_forward(&robust).unwrap();
+        assert_eq!(report.violation_count(), 1);
+        assert_eq!(report.violations[0].evidence[0], "Windows: w1, w2");
+
+        let overfit = vec![
+            window("w1", 2.0, -0.5, -0.03),
+            window("w2", 2.0, 0.2, -0.01),
+        ];
+        let report = verifier.verify_walk_forward(&overfit).unwrap();
+        let degradation: Vec<Severity> = report
+            .violations
+            .iter()
+            .filter(|v| v.rule_id == RuleId::WalkForwardDegradation)
+            .map(|v| v.severity)
+            .collect();
+        assert_eq!(degradation, vec![Severity::Medium, Severity::High]);
+
+        assert!(verifier.verify_walk_forward(&[]).is_err());
+    }
+
     #[test]
     fn test_verifier_rejects_empty_equity_history() {
         let verifier = CRVVerifier::with_defaults();
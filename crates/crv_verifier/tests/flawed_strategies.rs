@@ -1,6 +1,6 @@
 /// Integration tests for CRV verifier with intentionally flawed strategies
 use crv_verifier::{CRVVerifier, PolicyConstraints, RuleId, Severity};
-use schema::{BacktestStats, Fill, Side};
+use schema::{BacktestStats, CostBreakdown, Fill, Side};
 
 #[test]
 fn test_flawed_strategy_with_lookahead_bias() {
@@ -14,8 +14,14 @@ fn test_flawed_strategy_with_lookahead_bias() {
         total_return: 0.5,
         num_trades: 3,
         total_commission: 15.0,
+        costs: CostBreakdown::from_commission(15.0),
         sharpe_ratio: 2.5,
         max_drawdown: 0.08,
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     // Fills are intentionally out of order - evidence of lookahead bias
@@ -26,7 +32,7 @@ fn test_flawed_strategy_with_lookahead_bias() {
             side: Side::Buy,
             quantity: 100.0,
             price: 150.0,
-            commission: 5.0,
+            costs: CostBreakdown::from_commission(5.0),
         },
         Fill {
             timestamp: 1000, // This is earlier! Lookahead bias detected
@@ -34,7 +40,7 @@ fn test_flawed_strategy_with_lookahead_bias() {
             side: Side::Sell,
             quantity: 100.0,
             price: 145.0,
-            commission: 5.0,
+            costs: CostBreakdown::from_commission(5.0),
         },
     ];
 
@@ -52,8 +58,10 @@ fn test_flawed_strategy_with_lookahead_bias() {
 #[test]
 fn test_flawed_strategy_with_excessive_drawdown() {
     // Strategy exceeds max drawdown policy
-    let mut constraints = PolicyConstraints::default();
-    constraints.max_drawdown = Some(0.20); // 20% limit
+    let constraints = PolicyConstraints {
+        max_drawdown: Some(0.20), // 20% limit
+        ..PolicyConstraints::default()
+    };
 
     let verifier = CRVVerifier::new(constraints);
 
@@ -63,8 +71,14 @@ fn test_flawed_strategy_with_excessive_drawdown() {
         total_return: -0.1,
         num_trades: 50,
         total_commission: 250.0,
+        costs: CostBreakdown::from_commission(250.0),
         sharpe_ratio: -0.5,
         max_drawdown: 0.35, // 35% drawdown - exceeds policy!
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     let fills = vec![];
@@ -95,8 +109,14 @@ fn test_flawed_strategy_with_bankruptcy() {
         total_return: -1.5,
         num_trades: 20,
         total_commission: 100.0,
+        costs: CostBreakdown::from_commission(100.0),
         sharpe_ratio: -5.0,
         max_drawdown: 1.5,
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     let fills = vec![];
@@ -126,8 +146,14 @@ fn test_flawed_strategy_with_bad_sharpe_calculation() {
         total_return: 0.2,
         num_trades: 10,
         total_commission: 50.0,
+        costs: CostBreakdown::from_commission(50.0),
         sharpe_ratio: 25.0, // Impossibly high!
         max_drawdown: 0.05,
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     let fills = vec![];
@@ -153,8 +179,14 @@ fn test_flawed_strategy_with_invalid_drawdown_calculation() {
         total_return: -0.5,
         num_trades: 5,
         total_commission: 25.0,
+        costs: CostBreakdown::from_commission(25.0),
         sharpe_ratio: -1.0,
         max_drawdown: 2.5, // > 1.0 is invalid!
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     let fills = vec![];
@@ -180,8 +212,14 @@ fn test_multiple_violations_detected() {
         total_return: -0.2,
         num_trades: 10,
         total_commission: 50.0,
+        costs: CostBreakdown::from_commission(50.0),
         sharpe_ratio: 15.0, // Unrealistic
         max_drawdown: 0.30, // Exceeds default 25% limit
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     let fills = vec![];
@@ -219,8 +257,14 @@ fn test_flawed_strategy_with_survivorship_bias() {
         total_return: 0.5,
         num_trades: 30,
         total_commission: 150.0,
+        costs: CostBreakdown::from_commission(150.0),
         sharpe_ratio: 2.0,
         max_drawdown: 0.10,
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     let fills = vec![];
@@ -1,6 +1,6 @@
 /// Golden file tests for CRV report JSON structure
-use crv_verifier::{CRVVerifier, PolicyConstraints};
-use schema::{BacktestStats, Fill};
+use crv_verifier::CRVVerifier;
+use schema::{BacktestStats, CostBreakdown, Fill};
 use std::fs;
 use std::path::PathBuf;
 
@@ -21,8 +21,14 @@ fn test_golden_passing_backtest() {
         total_return: 0.1,
         num_trades: 10,
         total_commission: 50.0,
+        costs: CostBreakdown::from_commission(50.0),
         sharpe_ratio: 1.5,
         max_drawdown: 0.05,
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     let fills: Vec<Fill> = vec![];
@@ -65,8 +71,14 @@ fn test_golden_excessive_drawdown() {
         total_return: -0.1,
         num_trades: 50,
         total_commission: 250.0,
+        costs: CostBreakdown::from_commission(250.0),
         sharpe_ratio: -0.5,
         max_drawdown: 0.35, // 35% drawdown - exceeds 25% limit
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     let fills: Vec<Fill> = vec![];
@@ -119,8 +131,14 @@ fn test_report_json_schema_structure() {
         total_return: 0.1,
         num_trades: 10,
         total_commission: 50.0,
+        costs: CostBreakdown::from_commission(50.0),
         sharpe_ratio: 1.5,
         max_drawdown: 0.05,
+        financing: None,
+        performance: Default::default(),
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
     };
 
     let fills: Vec<Fill> = vec![];
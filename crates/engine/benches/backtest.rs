@@ -0,0 +1,101 @@
+//! Bars per second through `BacktestEngine` on a synthetic random walk.
+//!
+//! Run with `cargo bench -p engine`.
+
+use broker_sim::SimpleBroker;
+use cost::ZeroCost;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use engine::{BacktestEngine, VecDataFeed};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use schema::{Bar, Order, OrderType, Portfolio, Side, Strategy};
+
+/// Seeded daily random walk over `symbols` symbols, interleaved by timestamp
+fn synthetic_bars(days: usize, symbols: usize) -> Vec<Bar> {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let mut closes = vec![100.0; symbols];
+    let mut bars = Vec::with_capacity(days * symbols);
+    for day in 0..days {
+        for (i, close) in closes.iter_mut().enumerate() {
+            let open = *close;
+            *close *= 1.0 + rng.gen_range(-0.02..0.02);
+            bars.push(Bar {
+                timestamp: day as i64 * 86_400,
+                symbol: format!("S{:03}", i),
+                open,
+                high: open.max(*close) * 1.005,
+                low: open.min(*close) * 0.995,
+                close: *close,
+                volume: 1_000_000.0,
+            });
+        }
+    }
+    bars
+}
+
+/// Flips between long and flat every `every` bars of each symbol, so the
+/// broker and portfolio see a steady stream of fills
+struct Churn {
+    every: usize,
+    seen: usize,
+}
+
+impl Strategy for Churn {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        self.seen += 1;
+        if !self.seen.is_multiple_of(self.every) {
+            return vec![];
+        }
+        let held = portfolio
+            .get_position(&bar.symbol)
+            .map(|p| p.quantity)
+            .unwrap_or(0.0);
+        let (side, quantity) = if held > 0.0 {
+            (Side::Sell, held)
+        } else {
+            (Side::Buy, 10.0)
+        };
+        vec![Order {
+            symbol: bar.symbol.clone(),
+            side,
+            quantity,
+            order_type: OrderType::Market,
+            limit_price: None,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        }]
+    }
+
+    fn name(&self) -> &str {
+        "Churn"
+    }
+}
+
+fn backtest_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("backtest");
+    for (days, symbols) in [(10_000, 1), (2_500, 20)] {
+        let bars = synthetic_bars(days, symbols);
+        group.throughput(Throughput::Elements(bars.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("bars", format!("{}x{}", days, symbols)),
+            &bars,
+            |b, bars| {
+                b.iter(|| {
+                    let mut engine = BacktestEngine::new(
+                        VecDataFeed::new(bars.clone()),
+                        Churn { every: 5, seen: 0 },
+                        SimpleBroker::new(ZeroCost, 42),
+                        1_000_000.0,
+                    );
+                    engine.run().unwrap();
+                    black_box(engine.equity_history().len())
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, backtest_throughput);
+criterion_main!(benches);
@@ -1,77 +1,472 @@
+use crate::currency::Currencies;
+use crate::financing::FinancingRates;
+use crate::kill_switch::KillSwitch;
+use crate::lots::{LotMethod, LotRealization};
+use crate::observer::EngineObserver;
 use crate::portfolio::PortfolioManager;
+use crate::progress::Progress;
+use crate::risk::{CashCheck, RiskCheck, RiskScreen};
+use crate::round_trips::RoundTrip;
+use crate::scheduler::{Scheduler, WarmUp};
+use crate::trace::{RunTrace, Stage};
 use anyhow::Result;
-use schema::{BrokerSim, DataFeed, Fill, Strategy};
+use schema::{
+    Bar, BorrowCharge, BrokerSim, ConstrainedOrders, CostBreakdown, DataFeed, EquityPoint,
+    ExposurePoint, Fill, FinancingStats, FundingPayment, KillSwitchTrip, MarginCall,
+    OptionExpiration, Order, OrderEvent, OrderRejection, RejectionReason, Strategy,
+};
 use std::collections::HashMap;
+use std::time::Instant;
 
 /// Event-driven backtest engine
 pub struct BacktestEngine<D: DataFeed, S: Strategy, B: BrokerSim> {
-    data_feed: D,
+    /// Taken out while the engine runs, since each bar is lent by the feed
+    data_feed: Option<D>,
     strategy: S,
     broker: B,
     portfolio_manager: PortfolioManager,
     fills: Vec<Fill>,
+    rejections: Vec<OrderRejection>,
+    borrow_charges: Vec<BorrowCharge>,
+    funding_payments: Vec<FundingPayment>,
+    option_expirations: Vec<OptionExpiration>,
+    margin_calls: Vec<MarginCall>,
+    order_events: Vec<OrderEvent>,
     current_prices: HashMap<String, f64>,
+    financing: Option<FinancingRates>,
+    /// Day of the latest bar, from which financing accrues
+    financing_day: Option<i64>,
+    risk: RiskScreen,
+    kill_switch: Option<KillSwitch>,
+    scheduler: Scheduler,
+    warm_up: WarmUp,
+    observers: Vec<Box<dyn EngineObserver + Send>>,
+    /// Bars between progress reports, and where they go
+    progress: Option<(usize, ProgressCallback)>,
+    trace: Option<RunTrace>,
 }
 
+type ProgressCallback = Box<dyn FnMut(&Progress) + Send>;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
 impl<D: DataFeed, S: Strategy, B: BrokerSim> BacktestEngine<D, S, B> {
     pub fn new(data_feed: D, strategy: S, broker: B, initial_cash: f64) -> Self {
+        let warm_up = WarmUp::new(strategy.warmup_bars());
         Self {
-            data_feed,
+            data_feed: Some(data_feed),
             strategy,
             broker,
             portfolio_manager: PortfolioManager::new(initial_cash),
             fills: Vec::new(),
+            rejections: Vec::new(),
+            borrow_charges: Vec::new(),
+            funding_payments: Vec::new(),
+            option_expirations: Vec::new(),
+            margin_calls: Vec::new(),
+            order_events: Vec::new(),
             current_prices: HashMap::new(),
+            financing: None,
+            financing_day: None,
+            risk: RiskScreen::default(),
+            kill_switch: None,
+            scheduler: Scheduler::default(),
+            warm_up,
+            observers: Vec::new(),
+            progress: None,
+            trace: None,
         }
     }
 
+    /// Accrue interest on cash and fees on shorts on each new calendar day
+    pub fn with_financing(mut self, rates: FinancingRates) -> Self {
+        self.financing = Some(rates);
+        self
+    }
+
+    /// Account for positions lot by lot, closing lots in `method`'s order
+    pub fn with_lot_method(mut self, method: LotMethod) -> Self {
+        self.portfolio_manager = self.portfolio_manager.with_lot_method(method);
+        self
+    }
+
+    /// Keep the books in the base currency, converting foreign symbols at
+    /// the rates of currency pair bars in the data
+    pub fn with_currencies(mut self, currencies: Currencies) -> Self {
+        self.portfolio_manager = self.portfolio_manager.with_currencies(currencies);
+        self
+    }
+
+    /// Pass every order through `check`, after any added before, before
+    /// the broker sees it; orders it refuses are recorded as rejections
+    pub fn with_risk_check(mut self, check: impl RiskCheck + Send + 'static) -> Self {
+        self.risk.push(check);
+        self
+    }
+
+    /// Downsize or refuse buys costing more than the cash on hand once
+    /// `cost_reserve` of their value is held back for costs, and without
+    /// `allow_shorts` sells of more than is held
+    pub fn with_strict_cash(self, allow_shorts: bool, cost_reserve: f64) -> Self {
+        self.with_risk_check(CashCheck {
+            allow_shorts,
+            cost_reserve,
+        })
+    }
+
+    /// Flatten the account and stop trading once equity closes more than
+    /// `max_drawdown` below its peak
+    pub fn with_kill_switch(mut self, max_drawdown: f64) -> Self {
+        let equity = self.portfolio_manager.portfolio().equity;
+        self.kill_switch = Some(KillSwitch::new(max_drawdown, equity));
+        self
+    }
+
+    /// Warm the strategy up on the first `bars` bar timestamps, in place of
+    /// the warm-up it declares
+    pub fn with_warmup(mut self, bars: usize) -> Self {
+        self.warm_up = WarmUp::new(bars);
+        self
+    }
+
+    /// Call `observer` from the loop, after any registered before
+    pub fn with_observer(mut self, observer: impl EngineObserver + Send + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Report progress to `callback` every `every` bars read, and once the
+    /// feed is exhausted
+    pub fn with_progress(
+        mut self,
+        every: usize,
+        callback: impl FnMut(&Progress) + Send + 'static,
+    ) -> Self {
+        self.progress = Some((every.max(1), Box::new(callback)));
+        self
+    }
+
+    /// Count the run's events and time each stage of the loop
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(RunTrace::new(self.strategy.name()));
+        self
+    }
+
     /// Run the backtest bar-by-bar
     pub fn run(&mut self) -> Result<()> {
-        loop {
-            // Get next bar
-            let bar = match self.data_feed.next_bar() {
-                Some(b) => b,
-                None => break, // No more data
-            };
+        let mut feed = self
+            .data_feed
+            .take()
+            .expect("the data feed is returned after every run");
+        let result = self.run_feed(&mut feed);
+        self.data_feed = Some(feed);
+        result
+    }
 
-            // Update current prices
-            self.current_prices.insert(bar.symbol.clone(), bar.close);
+    fn run_feed(&mut self, feed: &mut D) -> Result<()> {
+        let started = Instant::now();
+        let total_bars = feed.total_bars();
+        let mut bars_processed = 0;
+        while let Some(bar) = feed.next_bar() {
+            if let Some((every, callback)) = &mut self.progress {
+                if bars_processed > 0 && bars_processed % *every == 0 {
+                    callback(&Progress {
+                        bars_processed,
+                        total_bars,
+                        elapsed: started.elapsed(),
+                    });
+                }
+            }
+            bars_processed += 1;
+            let mut clock = Instant::now();
+            if self.warm_up.is_warming(bar.timestamp) {
+                // Warm-up bars only seed the strategy: nothing trades,
+                // accrues or is marked on the equity curve
+                self.mark_price(bar);
+                self.portfolio_manager.advance_to(bar.timestamp);
+                let portfolio = self.portfolio_manager.portfolio();
+                self.scheduler.warm_up(&mut self.strategy, bar, portfolio);
+                self.strategy.on_bar(bar, portfolio);
+                if let Some(trace) = &mut self.trace {
+                    trace.counts.warmup_bars += 1;
+                    trace.lap(Stage::Strategy, &mut clock);
+                }
+                continue;
+            }
+            self.accrue_financing(bar.timestamp);
 
-            // Let strategy generate orders based on current bar and portfolio state
-            let orders = self
-                .strategy
-                .on_bar(&bar, self.portfolio_manager.portfolio());
+            // Update current prices
+            self.mark_price(bar);
+            self.portfolio_manager.advance_to(bar.timestamp);
+            self.lap(Stage::Accounting, &mut clock);
+
+            // Timers for periods the bar closes fire first, then the strategy
+            // generates orders based on current bar and portfolio state
+            let mut orders = self.scheduler.timer_orders(
+                &mut self.strategy,
+                bar,
+                self.portfolio_manager.portfolio(),
+            );
+            orders.extend(
+                self.strategy
+                    .on_bar(bar, self.portfolio_manager.portfolio()),
+            );
+            self.lap(Stage::Strategy, &mut clock);
+            let orders = if self.kill_switch().is_some() {
+                self.refuse_and_flatten(orders, &bar.symbol, bar.timestamp)
+            } else {
+                self.screen_orders(orders, bar.timestamp)
+            };
+            if let Some(trace) = &mut self.trace {
+                trace.counts.orders += orders.len();
+                trace.lap(Stage::Risk, &mut clock);
+            }
+            for observer in &mut self.observers {
+                for order in &orders {
+                    observer.on_order(order);
+                }
+            }
 
-            // Process orders through broker
-            if !orders.is_empty() {
-                let new_fills = self.broker.process_orders(orders, &bar)?;
+            // Process orders through broker; every bar goes through so resting
+            // orders can fill even when the strategy submits nothing
+            let result =
+                self.broker
+                    .process_orders_for(orders, bar, self.portfolio_manager.portfolio())?;
+            self.lap(Stage::Broker, &mut clock);
 
-                // Apply fills to portfolio
-                for fill in &new_fills {
-                    self.portfolio_manager
-                        .apply_fill(fill, &self.current_prices)?;
+            // Borrow fees and funding accrued before the bar's fills, then the fills
+            for charge in &result.borrow_charges {
+                self.portfolio_manager.apply_borrow_charge(charge);
+            }
+            for payment in &result.funding_payments {
+                self.portfolio_manager.apply_funding_payment(payment);
+            }
+            for fill in &result.fills {
+                self.portfolio_manager
+                    .apply_fill(fill, &self.current_prices)?;
+                for observer in &mut self.observers {
+                    observer.on_fill(fill, self.portfolio_manager.portfolio());
                 }
+            }
 
-                self.fills.extend(new_fills);
+            self.fills.extend(result.fills);
+            self.rejections.extend(result.rejections);
+            self.borrow_charges.extend(result.borrow_charges);
+            self.funding_payments.extend(result.funding_payments);
+            self.option_expirations.extend(result.option_expirations);
+            self.margin_calls.extend(result.margin_calls);
+            self.order_events.extend(result.order_events);
+            self.lap(Stage::Accounting, &mut clock);
+
+            // Cancels and amendments apply from the next bar, so none can
+            // reach back into the bar the strategy has just seen
+            let open_orders = self.broker.open_orders();
+            if self.kill_switch().is_none() {
+                for instruction in self.strategy.manage_orders(bar, &open_orders) {
+                    self.broker.apply_instruction(&instruction)?;
+                }
             }
+            self.lap(Stage::Strategy, &mut clock);
 
             // Update equity at end of bar
             self.portfolio_manager.update_equity(&self.current_prices);
+            self.check_kill_switch(bar.timestamp)?;
+            for observer in &mut self.observers {
+                observer.on_bar(bar, self.portfolio_manager.portfolio());
+            }
+            self.lap(Stage::Accounting, &mut clock);
         }
 
+        for observer in &mut self.observers {
+            observer.on_end(self.portfolio_manager.portfolio());
+        }
+        if let Some((_, callback)) = &mut self.progress {
+            callback(&Progress {
+                bars_processed,
+                total_bars,
+                elapsed: started.elapsed(),
+            });
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.counts.bars = bars_processed;
+            trace.counts.fills = self.fills.len();
+            trace.counts.rejections = self.rejections.len();
+        }
         Ok(())
     }
 
+    /// Record the bar's close as its symbol's price, cloning the symbol
+    /// only the first time it is seen
+    fn mark_price(&mut self, bar: &Bar) {
+        match self.current_prices.get_mut(&bar.symbol) {
+            Some(price) => *price = bar.close,
+            None => {
+                self.current_prices.insert(bar.symbol.clone(), bar.close);
+            }
+        }
+    }
+
+    /// Charge the time since `clock` to `stage`, when tracing
+    fn lap(&mut self, stage: Stage, clock: &mut Instant) {
+        if let Some(trace) = &mut self.trace {
+            trace.lap(stage, clock);
+        }
+    }
+
+    /// Orders that pass the risk checks, recording those they refuse
+    fn screen_orders(&mut self, orders: Vec<Order>, timestamp: i64) -> Vec<Order> {
+        let (orders, rejections) = self.risk.screen(
+            orders,
+            self.portfolio_manager.portfolio(),
+            &self.portfolio_manager.base_prices(&self.current_prices),
+            self.portfolio_manager.traded_notional(),
+            timestamp,
+        );
+        self.rejections.extend(rejections);
+        orders
+    }
+
+    /// Refuse the strategy's `orders` once the kill switch has tripped, in
+    /// favour of an order closing the bar's `symbol`
+    fn refuse_and_flatten(
+        &mut self,
+        orders: Vec<Order>,
+        symbol: &str,
+        timestamp: i64,
+    ) -> Vec<Order> {
+        self.rejections
+            .extend(orders.into_iter().map(|order| OrderRejection {
+                timestamp,
+                order,
+                reason: RejectionReason::KillSwitch,
+            }));
+        let portfolio = self.portfolio_manager.portfolio();
+        self.kill_switch
+            .as_mut()
+            .and_then(|switch| switch.flatten(symbol, portfolio))
+            .into_iter()
+            .collect()
+    }
+
+    /// Mark the latest equity against the kill switch, cancelling every
+    /// resting and in-flight order when it trips
+    fn check_kill_switch(&mut self, timestamp: i64) -> Result<()> {
+        let equity = self.portfolio_manager.portfolio().equity;
+        let Some(switch) = &mut self.kill_switch else {
+            return Ok(());
+        };
+        if switch.mark(timestamp, equity).is_some() {
+            self.broker.cancel_all()?;
+        }
+        Ok(())
+    }
+
+    /// Financing for the days since the previous bar, on the account as it
+    /// stood at the previous close
+    fn accrue_financing(&mut self, timestamp: i64) {
+        let Some(rates) = self.financing else {
+            return;
+        };
+        let day = timestamp.div_euclid(SECONDS_PER_DAY);
+        let previous = self.financing_day.replace(day);
+        if let Some(days) = previous.map(|previous| day - previous).filter(|&d| d > 0) {
+            self.portfolio_manager
+                .apply_financing(&rates, days as f64, &self.current_prices);
+        }
+    }
+
+    /// Interest and borrow fees accrued, when the account is financed
+    pub fn financing(&self) -> Option<FinancingStats> {
+        self.financing.map(|_| self.portfolio_manager.financing())
+    }
+
+    /// The feed the engine reads bars from
+    pub fn data_feed(&self) -> &D {
+        self.data_feed
+            .as_ref()
+            .expect("the data feed is returned after every run")
+    }
+
+    /// Events and stage timings of the run, when traced
+    pub fn trace(&self) -> Option<&RunTrace> {
+        self.trace.as_ref()
+    }
+
     /// Get the fills (trades) from the backtest
     pub fn fills(&self) -> &[Fill] {
         &self.fills
     }
 
+    /// Orders the broker refused, in the order they were refused
+    pub fn rejections(&self) -> &[OrderRejection] {
+        &self.rejections
+    }
+
+    /// Borrow fees charged on shorts, in the order they accrued
+    pub fn borrow_charges(&self) -> &[BorrowCharge] {
+        &self.borrow_charges
+    }
+
+    /// Funding exchanged on perpetuals, in the order it settled
+    pub fn funding_payments(&self) -> &[FundingPayment] {
+        &self.funding_payments
+    }
+
+    /// Net funding paid on perpetuals; negative when more was received
+    pub fn total_funding(&self) -> f64 {
+        self.portfolio_manager.total_funding()
+    }
+
+    /// When the kill switch tripped, if it has
+    pub fn kill_switch(&self) -> Option<&KillSwitchTrip> {
+        self.kill_switch.as_ref().and_then(KillSwitch::trip)
+    }
+
+    /// Orders the risk checks downsized and refused, when there are any
+    pub fn constrained_orders(&self) -> Option<ConstrainedOrders> {
+        self.risk.constrained()
+    }
+
+    /// Option positions exercised, assigned or expired worthless
+    pub fn option_expirations(&self) -> &[OptionExpiration] {
+        &self.option_expirations
+    }
+
+    /// Margin calls the broker met with forced liquidations
+    pub fn margin_calls(&self) -> &[MarginCall] {
+        &self.margin_calls
+    }
+
+    /// Every step of every order at the broker, in the order it happened
+    pub fn order_events(&self) -> &[OrderEvent] {
+        &self.order_events
+    }
+
     /// Get the equity history
     pub fn equity_history(&self) -> &[(i64, f64)] {
         self.portfolio_manager.equity_history()
     }
 
+    /// Cash and position value behind every equity mark
+    pub fn equity_points(&self) -> &[EquityPoint] {
+        self.portfolio_manager.equity_points()
+    }
+
+    /// Gross and net exposure, leverage and turnover at every equity mark
+    pub fn exposure_history(&self) -> &[ExposurePoint] {
+        self.portfolio_manager.exposure_history()
+    }
+
+    /// Every tax lot closed, with its realized PnL and holding period
+    pub fn lot_realizations(&self) -> &[LotRealization] {
+        self.portfolio_manager.lot_realizations()
+    }
+
+    /// Round trips closed so far, with excursions marked at every bar
+    pub fn round_trips(&self) -> &[RoundTrip] {
+        self.portfolio_manager.round_trips()
+    }
+
     /// Get realized PnL
     pub fn realized_pnl(&self) -> f64 {
         self.portfolio_manager.realized_pnl()
@@ -87,6 +482,11 @@ impl<D: DataFeed, S: Strategy, B: BrokerSim> BacktestEngine<D, S, B> {
         self.portfolio_manager.total_commission()
     }
 
+    /// Get fill costs by kind
+    pub fn costs(&self) -> CostBreakdown {
+        self.portfolio_manager.costs()
+    }
+
     /// Get number of trades
     pub fn num_trades(&self) -> usize {
         self.fills.len()
@@ -98,8 +498,8 @@ mod tests {
     use super::*;
     use crate::data_feed::VecDataFeed;
     use broker_sim::SimpleBroker;
-    use cost::ZeroCost;
-    use schema::{Bar, Order, OrderType, Portfolio, Side};
+    use cost::{PercentageCost, ZeroCost};
+    use schema::{Bar, OrderInstruction, OrderType, Portfolio, Side, TimerEvent, TimerKind};
 
     // Simple buy-and-hold strategy for testing
     struct BuyAndHoldStrategy {
@@ -126,6 +526,9 @@ mod tests {
                     quantity: 10.0,
                     order_type: OrderType::Market,
                     limit_price: None,
+                    stop_price: None,
+                    order_id: None,
+                    bracket: None,
                 }]
             } else {
                 vec![]
@@ -137,6 +540,353 @@ mod tests {
         }
     }
 
+    /// Rests a limit buy below the market, cancelling it after `patience` bars
+    struct PatientBid {
+        bars: usize,
+        patience: usize,
+    }
+
+    impl Strategy for PatientBid {
+        fn on_bar(&mut self, bar: &Bar, _portfolio: &Portfolio) -> Vec<Order> {
+            self.bars += 1;
+            if self.bars > 1 {
+                return vec![];
+            }
+            vec![Order {
+                symbol: bar.symbol.clone(),
+                side: Side::Buy,
+                quantity: 10.0,
+                order_type: OrderType::Limit,
+                limit_price: Some(90.0),
+                stop_price: None,
+                order_id: None,
+                bracket: None,
+            }]
+        }
+
+        fn manage_orders(&mut self, _bar: &Bar, open_orders: &[Order]) -> Vec<OrderInstruction> {
+            if self.bars < self.patience {
+                return vec![];
+            }
+            open_orders
+                .iter()
+                .filter_map(|o| o.order_id)
+                .map(|order_id| OrderInstruction::Cancel { order_id })
+                .collect()
+        }
+
+        fn name(&self) -> &str {
+            "PatientBid"
+        }
+    }
+
+    #[test]
+    fn test_strategies_cancel_resting_orders() {
+        let bars: Vec<Bar> = [(100.0, 99.0), (98.0, 95.0), (85.0, 80.0)]
+            .into_iter()
+            .zip(1..)
+            .map(|((close, low), timestamp)| Bar {
+                timestamp,
+                symbol: "AAPL".to_string(),
+                open: close,
+                high: close + 1.0,
+                low,
+                close,
+                volume: 10000.0,
+            })
+            .collect();
+        let run = |patience| {
+            let strategy = PatientBid { bars: 0, patience };
+            let broker = SimpleBroker::new(ZeroCost, 42);
+            let mut engine =
+                BacktestEngine::new(VecDataFeed::new(bars.clone()), strategy, broker, 10000.0);
+            engine.run().unwrap();
+            engine.fills().to_vec()
+        };
+
+        assert_eq!(run(3)[0].price, 85.0);
+        assert!(run(2).is_empty());
+    }
+
+    #[test]
+    fn test_expiring_calls_are_exercised_into_stock() {
+        use broker_sim::{OptionContract, OptionContracts, OptionKind};
+
+        let bar = |timestamp, symbol: &str, close| Bar {
+            timestamp,
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 10000.0,
+        };
+        let options = OptionContracts::new().with_contract(
+            "AAPL_C100",
+            OptionContract {
+                underlying: "AAPL".to_string(),
+                kind: OptionKind::Call,
+                strike: 100.0,
+                expiry: 2,
+                multiplier: 100.0,
+            },
+        );
+        // Ten contracts at $500 each, then the underlying closes at $110 on expiry
+        let bars = vec![bar(1, "AAPL_C100", 500.0), bar(2, "AAPL", 110.0)];
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars),
+            BuyAndHoldStrategy::new("AAPL_C100".to_string()),
+            SimpleBroker::new(ZeroCost, 42).with_options(options),
+            200_000.0,
+        );
+        engine.run().unwrap();
+
+        assert_eq!(
+            engine.option_expirations()[0].outcome,
+            schema::ExpiryOutcome::Exercised
+        );
+        let portfolio = engine.portfolio_manager.portfolio();
+        assert_eq!(portfolio.get_position("AAPL").unwrap().quantity, 1_000.0);
+        assert_eq!(portfolio.get_position("AAPL_C100").unwrap().quantity, 0.0);
+        assert_eq!(portfolio.cash, 95_000.0);
+        assert_eq!(engine.equity_history().last().unwrap().1, 205_000.0);
+    }
+
+    /// Buys a share of B at each month end, recording every timer
+    struct MonthlyBuyer {
+        timers: Vec<TimerEvent>,
+    }
+
+    impl Strategy for MonthlyBuyer {
+        fn on_bar(&mut self, _bar: &Bar, _portfolio: &Portfolio) -> Vec<Order> {
+            vec![]
+        }
+
+        fn on_timer(&mut self, timer: &TimerEvent, _portfolio: &Portfolio) -> Vec<Order> {
+            self.timers.push(*timer);
+            if timer.kind != TimerKind::MonthEnd {
+                return vec![];
+            }
+            vec![Order {
+                symbol: "B".to_string(),
+                side: Side::Buy,
+                quantity: 1.0,
+                order_type: OrderType::Market,
+                limit_price: None,
+                stop_price: None,
+                order_id: None,
+                bracket: None,
+            }]
+        }
+
+        fn name(&self) -> &str {
+            "MonthlyBuyer"
+        }
+    }
+
+    #[test]
+    fn test_timer_orders_wait_for_a_bar_of_their_symbol() {
+        // Noon from Tuesday 2024-01-30 to Friday 2024-02-02, A then B each day
+        let noon = 1_706_616_000;
+        let bars: Vec<Bar> = (0..4)
+            .flat_map(|day| {
+                ["A", "B"].map(|symbol| Bar {
+                    timestamp: noon + day * SECONDS_PER_DAY,
+                    symbol: symbol.to_string(),
+                    open: 100.0,
+                    high: 100.0,
+                    low: 100.0,
+                    close: if symbol == "A" { 10.0 } else { 20.0 },
+                    volume: 10000.0,
+                })
+            })
+            .collect();
+        let strategy = MonthlyBuyer { timers: vec![] };
+        let broker = SimpleBroker::new(ZeroCost, 42);
+        let mut engine = BacktestEngine::new(VecDataFeed::new(bars), strategy, broker, 1000.0);
+        engine.run().unwrap();
+
+        let kinds: Vec<TimerKind> = engine.strategy.timers.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TimerKind::DailyClose,
+                TimerKind::DailyClose,
+                TimerKind::MonthEnd,
+                TimerKind::DailyClose
+            ]
+        );
+        // Fired on February's first A bar, filled on its B bar
+        let fills = engine.fills();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].symbol, "B");
+        assert_eq!(fills[0].timestamp, noon + 2 * SECONDS_PER_DAY);
+        assert_eq!(fills[0].price, 20.0);
+    }
+
+    /// Buys a share on every bar after seeding on the first two
+    struct SeededBuyer {
+        bars_seen: usize,
+    }
+
+    impl Strategy for SeededBuyer {
+        fn on_bar(&mut self, bar: &Bar, _portfolio: &Portfolio) -> Vec<Order> {
+            self.bars_seen += 1;
+            vec![Order {
+                symbol: bar.symbol.clone(),
+                side: Side::Buy,
+                quantity: 1.0,
+                order_type: OrderType::Market,
+                limit_price: None,
+                stop_price: None,
+                order_id: None,
+                bracket: None,
+            }]
+        }
+
+        fn warmup_bars(&self) -> usize {
+            2
+        }
+
+        fn name(&self) -> &str {
+            "SeededBuyer"
+        }
+    }
+
+    #[test]
+    fn test_warm_up_bars_seed_the_strategy_without_trading() {
+        let bars: Vec<Bar> = (1..=5)
+            .map(|timestamp| Bar {
+                timestamp,
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 100.0,
+                low: 100.0,
+                close: 100.0,
+                volume: 10000.0,
+            })
+            .collect();
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars.clone()),
+            SeededBuyer { bars_seen: 0 },
+            SimpleBroker::new(ZeroCost, 42),
+            1000.0,
+        );
+        engine.run().unwrap();
+
+        assert_eq!(engine.strategy.bars_seen, 5);
+        let filled: Vec<i64> = engine.fills().iter().map(|f| f.timestamp).collect();
+        assert_eq!(filled, [3, 4, 5]);
+        let mut marked: Vec<i64> = engine.equity_history().iter().map(|p| p.0).collect();
+        marked.dedup();
+        assert_eq!(marked, [0, 3, 4, 5]);
+
+        // The engine's setting replaces the strategy's own
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars),
+            SeededBuyer { bars_seen: 0 },
+            SimpleBroker::new(ZeroCost, 42),
+            1000.0,
+        )
+        .with_warmup(0);
+        engine.run().unwrap();
+        assert_eq!(engine.fills().len(), 5);
+    }
+
+    #[test]
+    fn test_financing_accrues_on_each_new_day() {
+        let day = SECONDS_PER_DAY;
+        // Ten shares at $100 overdraw $500 of cash from day 1 to day 11
+        let bars: Vec<Bar> = [day, day + 3_600, 11 * day]
+            .into_iter()
+            .map(|timestamp| Bar {
+                timestamp,
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 100.0,
+                low: 100.0,
+                close: 100.0,
+                volume: 10000.0,
+            })
+            .collect();
+        let rates = FinancingRates {
+            debit_rate: 0.072,
+            credit_rate: 0.036,
+            short_borrow_rate: 0.0,
+        };
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars),
+            BuyAndHoldStrategy::new("AAPL".to_string()),
+            SimpleBroker::new(ZeroCost, 42),
+            500.0,
+        )
+        .with_financing(rates);
+        engine.run().unwrap();
+
+        let financing = engine.financing().unwrap();
+        assert!((financing.debit_interest - 1.0).abs() < 1e-9);
+        assert_eq!(financing.credit_interest, 0.0);
+        assert!((engine.portfolio_manager.portfolio().cash + 501.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unaffordable_orders_are_rejected() {
+        let bar = Bar {
+            timestamp: 1000,
+            symbol: "AAPL".to_string(),
+            open: 100.0,
+            high: 102.0,
+            low: 99.0,
+            close: 101.0,
+            volume: 10000.0,
+        };
+        let run = |multiplier| {
+            let broker = SimpleBroker::new(ZeroCost, 42).with_margin_multiplier(multiplier);
+            let mut engine = BacktestEngine::new(
+                VecDataFeed::new(vec![bar.clone()]),
+                BuyAndHoldStrategy::new("AAPL".to_string()),
+                broker,
+                1000.0,
+            );
+            engine.run().unwrap();
+            (engine.num_trades(), engine.rejections().to_vec())
+        };
+
+        // $1,010 of stock on $1,000 of cash
+        let (trades, rejections) = run(1.0);
+        assert_eq!((trades, rejections.len()), (0, 1));
+        assert_eq!(rejections[0].order.quantity, 10.0);
+        assert_eq!(run(2.0), (1, vec![]));
+    }
+
+    #[test]
+    fn test_strict_cash_reserves_room_for_costs() {
+        let bar = Bar {
+            timestamp: 1000,
+            symbol: "AAPL".to_string(),
+            open: 100.0,
+            high: 100.0,
+            low: 100.0,
+            close: 100.0,
+            volume: 10000.0,
+        };
+        let cash_left = |reserve| {
+            let mut engine = BacktestEngine::new(
+                VecDataFeed::new(vec![bar.clone()]),
+                BuyAndHoldStrategy::new("AAPL".to_string()),
+                SimpleBroker::new(PercentageCost::new(0.01, 0.0), 42),
+                500.0,
+            )
+            .with_strict_cash(true, reserve);
+            engine.run().unwrap();
+            engine.portfolio_manager.portfolio().cash
+        };
+
+        // Five shares use up the cash and the 1% commission overdraws it
+        assert!((cash_left(0.0) + 5.0).abs() < 1e-9);
+        assert!(cash_left(0.01).abs() < 1e-9);
+    }
+
     #[test]
     fn test_simple_backtest() {
         let bars = vec![
@@ -173,6 +923,9 @@ mod tests {
         // Equity should be initial cash - purchase + current value
         let equity_history = engine.equity_history();
         assert!(equity_history.len() >= 2);
+
+        // Marks are stamped with their bar even when nothing fills on it
+        assert_eq!(equity_history.last().unwrap().0, 2000);
     }
 
     #[test]
@@ -0,0 +1,160 @@
+//! Bootstrap confidence intervals for a run's headline stats.
+//!
+//! The run's bar returns are resampled in blocks, as `monte_carlo` resamples
+//! trades, and each resampled series is scored like the run itself. The
+//! intervals are percentiles of those scores, so an interval that excludes
+//! zero marks an edge the returns distinguish from none. Draws come from a
+//! ChaCha stream of the seed, so the intervals repeat exactly.
+
+use crate::monte_carlo::{block_sample, percentile};
+use crate::output::{bar_returns, mean_and_std_dev, Annualization};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use schema::{BootstrapStats, ConfidenceInterval};
+
+/// How a run's returns are resampled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BootstrapConfig {
+    pub samples: usize,
+    /// Share of resamples each interval covers, such as 0.95
+    pub confidence: f64,
+    /// Consecutive bar returns drawn together, to keep autocorrelation
+    pub block_size: usize,
+    pub seed: u64,
+}
+
+/// Intervals around the Sharpe ratio, total return and max drawdown of
+/// `equity_history`; none with fewer than two bar returns to resample
+pub fn bootstrap_stats(
+    equity_history: &[(i64, f64)],
+    annualization: Annualization,
+    config: &BootstrapConfig,
+) -> Option<BootstrapStats> {
+    let returns = bar_returns(equity_history);
+    if returns.len() < 2 {
+        return None;
+    }
+    let samples = config.samples.max(1);
+    let block_size = config.block_size.clamp(1, returns.len());
+    let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+
+    let mut sharpe_ratios = Vec::with_capacity(samples);
+    let mut total_returns = Vec::with_capacity(samples);
+    let mut max_drawdowns = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let resampled: Vec<f64> = block_sample(&mut rng, returns.len(), block_size)
+            .into_iter()
+            .map(|i| returns[i])
+            .collect();
+        let (mut growth, mut peak, mut drawdown) = (1.0f64, 1.0f64, 0.0f64);
+        for r in &resampled {
+            growth *= 1.0 + r;
+            peak = peak.max(growth);
+            if peak > 0.0 {
+                drawdown = drawdown.max((peak - growth) / peak);
+            }
+        }
+        sharpe_ratios.push(sharpe_ratio(&resampled, annualization));
+        total_returns.push(growth - 1.0);
+        max_drawdowns.push(drawdown);
+    }
+
+    let interval = |mut values: Vec<f64>| {
+        values.sort_by(f64::total_cmp);
+        let tail = (1.0 - config.confidence) / 2.0;
+        ConfidenceInterval {
+            lower: percentile(&values, tail),
+            upper: percentile(&values, 1.0 - tail),
+        }
+    };
+    Some(BootstrapStats {
+        samples,
+        confidence: config.confidence,
+        block_size,
+        sharpe_ratio: interval(sharpe_ratios),
+        total_return: interval(total_returns),
+        max_drawdown: interval(max_drawdowns),
+    })
+}
+
+/// Annualized Sharpe ratio of `returns`, as `calculate_stats` reports it
+fn sharpe_ratio(returns: &[f64], annualization: Annualization) -> f64 {
+    let (mean, std_dev) = mean_and_std_dev(returns);
+    if std_dev > 0.0 {
+        (mean - annualization.risk_free_per_period()) / std_dev
+            * annualization.periods_per_year.sqrt()
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::calculate_stats;
+
+    fn config(seed: u64) -> BootstrapConfig {
+        BootstrapConfig {
+            samples: 500,
+            confidence: 0.9,
+            block_size: 2,
+            seed,
+        }
+    }
+
+    /// Equity compounding `returns` from 100
+    fn curve(returns: &[f64]) -> Vec<(i64, f64)> {
+        let mut equity = vec![(0, 100.0)];
+        for (t, r) in returns.iter().enumerate() {
+            let last = equity[t].1;
+            equity.push((t as i64 + 1, last * (1.0 + r)));
+        }
+        equity
+    }
+
+    #[test]
+    fn intervals_bracket_the_runs_own_stats_and_repeat_for_a_seed() {
+        let returns: Vec<f64> = (0..120)
+            .map(|i| [0.012, -0.004, 0.007, -0.009, 0.015, 0.002][i % 6])
+            .collect();
+        let equity = curve(&returns);
+        let annualization = Annualization::default();
+        let stats = calculate_stats(&equity, &[], Default::default(), annualization);
+        let bootstrap = bootstrap_stats(&equity, annualization, &config(1)).unwrap();
+        assert_eq!(
+            bootstrap,
+            bootstrap_stats(&equity, annualization, &config(1)).unwrap()
+        );
+        assert_ne!(
+            bootstrap,
+            bootstrap_stats(&equity, annualization, &config(2)).unwrap()
+        );
+
+        for (interval, value) in [
+            (bootstrap.sharpe_ratio, stats.sharpe_ratio),
+            (bootstrap.total_return, stats.total_return),
+        ] {
+            assert!(interval.lower <= value && value <= interval.upper);
+        }
+        // Resampling breaks the run's regular alternation into streaks, so
+        // its own drawdown is about as shallow as any
+        assert!(stats.max_drawdown <= bootstrap.max_drawdown.upper);
+        assert!(bootstrap.max_drawdown.lower < bootstrap.max_drawdown.upper);
+        // A steady positive drift is an edge the resamples agree on
+        assert!(bootstrap.sharpe_ratio.excludes_zero());
+    }
+
+    #[test]
+    fn noise_around_zero_is_not_an_edge() {
+        let returns: Vec<f64> = (0..200)
+            .map(|i| if i % 2 == 0 { 0.01 } else { -0.0099 })
+            .collect();
+        let iid = BootstrapConfig {
+            block_size: 1,
+            ..config(3)
+        };
+        let bootstrap = bootstrap_stats(&curve(&returns), Annualization::default(), &iid).unwrap();
+        assert!(!bootstrap.sharpe_ratio.excludes_zero());
+        assert!(bootstrap_stats(&curve(&[0.01]), Annualization::default(), &config(3)).is_none());
+    }
+}
@@ -0,0 +1,131 @@
+//! Base-currency accounting for instruments priced in other currencies.
+//!
+//! FX rates come through the data feed as bars of currency pairs: a bar of
+//! `EURUSD` prices one euro in dollars, and `USDEUR` is read as its inverse.
+//! Fills, marks and charges of a foreign symbol are converted into the base
+//! currency at the latest rate before they reach the books.
+
+use schema::{CostBreakdown, Fill};
+use std::collections::{BTreeMap, HashMap};
+
+/// The account's base currency, each foreign symbol's currency and the
+/// latest rate of each
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currencies {
+    base: String,
+    symbols: BTreeMap<String, String>,
+    /// Base currency per unit of each foreign currency
+    rates: BTreeMap<String, f64>,
+}
+
+impl Currencies {
+    pub fn new(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into(),
+            symbols: BTreeMap::new(),
+            rates: BTreeMap::new(),
+        }
+    }
+
+    /// Price `symbol` in `currency`; unlisted symbols are priced in the base
+    pub fn with_symbol(mut self, symbol: impl Into<String>, currency: impl Into<String>) -> Self {
+        let currency = currency.into();
+        if currency != self.base {
+            self.symbols.insert(symbol.into(), currency);
+        }
+        self
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn currency(&self, symbol: &str) -> &str {
+        self.symbols.get(symbol).unwrap_or(&self.base)
+    }
+
+    /// Take the latest rate of each foreign currency from its pair's price
+    pub fn observe(&mut self, prices: &HashMap<String, f64>) {
+        for currency in self.symbols.values() {
+            let direct = prices.get(&format!("{}{}", currency, self.base)).copied();
+            let inverse = || {
+                prices
+                    .get(&format!("{}{}", self.base, currency))
+                    .map(|rate| 1.0 / rate)
+            };
+            if let Some(rate) = direct
+                .or_else(inverse)
+                .filter(|r| r.is_finite() && *r > 0.0)
+            {
+                self.rates.insert(currency.clone(), rate);
+            }
+        }
+    }
+
+    /// Base currency per unit of `symbol`'s price, once a rate has been seen
+    pub fn rate(&self, symbol: &str) -> Option<f64> {
+        match self.symbols.get(symbol) {
+            Some(currency) => self.rates.get(currency).copied(),
+            None => Some(1.0),
+        }
+    }
+
+    /// `fill` with its price and costs in the base currency
+    pub fn fill_to_base(&self, fill: &Fill) -> Option<Fill> {
+        let rate = self.rate(&fill.symbol)?;
+        let costs = fill.costs;
+        Some(Fill {
+            price: fill.price * rate,
+            costs: CostBreakdown {
+                commission: costs.commission * rate,
+                exchange_fees: costs.exchange_fees * rate,
+                slippage: costs.slippage * rate,
+                borrow: costs.borrow * rate,
+                other: costs.other * rate,
+            },
+            ..fill.clone()
+        })
+    }
+
+    /// Prices in the base currency; foreign symbols with no rate yet are
+    /// left out, as unpriced
+    pub fn prices_to_base(&self, prices: &HashMap<String, f64>) -> HashMap<String, f64> {
+        prices
+            .iter()
+            .filter_map(|(symbol, price)| Some((symbol.clone(), price * self.rate(symbol)?)))
+            .collect()
+    }
+
+    /// An amount of `symbol`'s currency in the base, at its latest rate
+    pub fn amount_to_base(&self, symbol: &str, amount: f64) -> f64 {
+        amount * self.rate(symbol).unwrap_or(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_quote_either_way_round() {
+        let mut currencies = Currencies::new("USD")
+            .with_symbol("SAP", "EUR")
+            .with_symbol("7203.T", "JPY")
+            .with_symbol("AAPL", "USD");
+        assert_eq!(currencies.rate("SAP"), None);
+        assert_eq!(currencies.rate("AAPL"), Some(1.0));
+
+        let prices = HashMap::from([
+            ("EURUSD".to_string(), 1.25),
+            ("USDJPY".to_string(), 160.0),
+            ("SAP".to_string(), 200.0),
+            ("7203.T".to_string(), 3200.0),
+        ]);
+        currencies.observe(&prices);
+        let base = currencies.prices_to_base(&prices);
+        assert_eq!(base["SAP"], 250.0);
+        assert_eq!(base["7203.T"], 20.0);
+        assert_eq!(base["EURUSD"], 1.25);
+        assert_eq!(currencies.amount_to_base("7203.T", 1600.0), 10.0);
+    }
+}
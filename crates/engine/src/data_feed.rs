@@ -28,30 +28,26 @@ impl VecDataFeed {
 }
 
 impl DataFeed for VecDataFeed {
-    fn next_bar(&mut self) -> Option<Bar> {
-        if self.index < self.bars.len() {
-            let bar = self.bars[self.index].clone();
-            self.index += 1;
-            Some(bar)
-        } else {
-            None
-        }
+    fn next_bar(&mut self) -> Option<&Bar> {
+        let bar = self.bars.get(self.index)?;
+        self.index += 1;
+        Some(bar)
     }
 
     fn reset(&mut self) {
         self.index = 0;
     }
+
+    fn total_bars(&self) -> Option<usize> {
+        Some(self.bars.len())
+    }
 }
 
 impl CanonicalEventFeed for VecCanonicalEventFeed {
-    fn next_event(&mut self) -> Option<EventEnvelope> {
-        if self.index < self.events.len() {
-            let event = self.events[self.index].clone();
-            self.index += 1;
-            Some(event)
-        } else {
-            None
-        }
+    fn next_event(&mut self) -> Option<&EventEnvelope> {
+        let event = self.events.get(self.index)?;
+        self.index += 1;
+        Some(event)
     }
 
     fn reset_events(&mut self) {
@@ -0,0 +1,68 @@
+//! Daily interest and borrow fees on an account's cash and shorts.
+
+/// Calendar days in a year of interest, on an actual/360 day count
+const DAYS_PER_YEAR: f64 = 360.0;
+
+/// Annual rates an account is financed at
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FinancingRates {
+    /// Charged on negative cash
+    pub debit_rate: f64,
+    /// Credited on cash not held against shorts
+    pub credit_rate: f64,
+    /// Charged on the market value of shorts
+    pub short_borrow_rate: f64,
+}
+
+/// Financing for one stretch of days
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FinancingAccrual {
+    pub debit_interest: f64,
+    pub short_borrow_fees: f64,
+    pub credit_interest: f64,
+}
+
+impl FinancingAccrual {
+    /// Net change to cash
+    pub fn net(&self) -> f64 {
+        self.credit_interest - self.debit_interest - self.short_borrow_fees
+    }
+}
+
+impl FinancingRates {
+    /// Financing over `days` on `cash` and shorts worth `short_value`.
+    /// Short sale proceeds back the shorts, so they earn no credit interest.
+    pub fn accrue(&self, cash: f64, short_value: f64, days: f64) -> FinancingAccrual {
+        let year_fraction = days / DAYS_PER_YEAR;
+        let idle_cash = cash - short_value;
+        FinancingAccrual {
+            debit_interest: (-cash).max(0.0) * self.debit_rate * year_fraction,
+            short_borrow_fees: short_value * self.short_borrow_rate * year_fraction,
+            credit_interest: idle_cash.max(0.0) * self.credit_rate * year_fraction,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cash_and_shorts_accrue_on_actual_360() {
+        let rates = FinancingRates {
+            debit_rate: 0.072,
+            credit_rate: 0.036,
+            short_borrow_rate: 0.018,
+        };
+        // $10,000 overdrawn for five days at 7.2% a year
+        let accrual = rates.accrue(-10_000.0, 0.0, 5.0);
+        assert!((accrual.debit_interest - 10.0).abs() < 1e-9);
+        assert_eq!(accrual.credit_interest, 0.0);
+
+        // $30,000 of cash, $20,000 of it proceeds of a short
+        let accrual = rates.accrue(30_000.0, 20_000.0, 10.0);
+        assert!((accrual.credit_interest - 10.0).abs() < 1e-9);
+        assert!((accrual.short_borrow_fees - 10.0).abs() < 1e-9);
+        assert!(accrual.net().abs() < 1e-9);
+    }
+}
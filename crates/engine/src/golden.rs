@@ -0,0 +1,219 @@
+//! Golden-run regression checks.
+//!
+//! A golden suite is a JSON manifest of reference runs, each a spec and a
+//! dataset with the canonical hashes its fills, equity curve and stats are
+//! expected to produce. `check_suite` reruns every case through a caller's
+//! runner and compares; `bless` records the hashes a run actually produced,
+//! for when a change in results is intended.
+
+use anyhow::{Context, Result};
+use schema::{BacktestStats, Fill};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::determinism::canonical_json_hash;
+
+/// Canonical hashes of one run's outputs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunHashes {
+    pub fills: String,
+    pub equity: String,
+    pub stats: String,
+}
+
+impl RunHashes {
+    pub fn of(fills: &[Fill], equity: &[(i64, f64)], stats: &BacktestStats) -> Result<Self> {
+        Ok(Self {
+            fills: canonical_json_hash(&fills)?,
+            equity: canonical_json_hash(&equity)?,
+            stats: canonical_json_hash(stats)?,
+        })
+    }
+
+    /// Names of the outputs whose hashes differ from `other`
+    pub fn mismatches(&self, other: &RunHashes) -> Vec<&'static str> {
+        [
+            ("fills", self.fills != other.fills),
+            ("equity", self.equity != other.equity),
+            ("stats", self.stats != other.stats),
+        ]
+        .into_iter()
+        .filter_map(|(name, differs)| differs.then_some(name))
+        .collect()
+    }
+}
+
+/// One reference run; paths are relative to the manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenCase {
+    pub name: String,
+    pub spec: PathBuf,
+    pub data: PathBuf,
+    /// `None` until the case is first blessed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<RunHashes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenSuite {
+    pub cases: Vec<GoldenCase>,
+}
+
+impl GoldenSuite {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read golden manifest {:?}", path))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse golden manifest {:?}", path))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        fs::write(path, json).with_context(|| format!("Failed to write golden manifest {:?}", path))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CaseStatus {
+    Passed,
+    /// Outputs whose hashes changed
+    Failed {
+        mismatches: Vec<String>,
+    },
+    /// The case has no expected hashes yet
+    Unblessed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseResult {
+    pub name: String,
+    #[serde(flatten)]
+    pub status: CaseStatus,
+    pub actual: RunHashes,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.status == CaseStatus::Passed
+    }
+}
+
+/// Run the named cases (all when `only` is empty) and compare their hashes
+pub fn check_suite(
+    suite: &GoldenSuite,
+    only: &[String],
+    mut run: impl FnMut(&GoldenCase) -> Result<RunHashes>,
+) -> Result<Vec<CaseResult>> {
+    if let Some(unknown) = only
+        .iter()
+        .find(|name| !suite.cases.iter().any(|c| &c.name == *name))
+    {
+        anyhow::bail!("No golden case named '{}'", unknown);
+    }
+    suite
+        .cases
+        .iter()
+        .filter(|case| only.is_empty() || only.contains(&case.name))
+        .map(|case| {
+            let actual =
+                run(case).with_context(|| format!("Golden case '{}' failed to run", case.name))?;
+            let status = match &case.expected {
+                None => CaseStatus::Unblessed,
+                Some(expected) => match expected.mismatches(&actual) {
+                    mismatches if mismatches.is_empty() => CaseStatus::Passed,
+                    mismatches => CaseStatus::Failed {
+                        mismatches: mismatches.into_iter().map(String::from).collect(),
+                    },
+                },
+            };
+            Ok(CaseResult {
+                name: case.name.clone(),
+                status,
+                actual,
+            })
+        })
+        .collect()
+}
+
+/// Record each result's actual hashes as its case's expected hashes
+pub fn bless(suite: &mut GoldenSuite, results: &[CaseResult]) {
+    for result in results {
+        if let Some(case) = suite.cases.iter_mut().find(|c| c.name == result.name) {
+            case.expected = Some(result.actual.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::CostBreakdown;
+
+    fn hashes(tag: &str) -> RunHashes {
+        RunHashes {
+            fills: format!("fills-{}", tag),
+            equity: "equity".to_string(),
+            stats: format!("stats-{}", tag),
+        }
+    }
+
+    fn case(name: &str, expected: Option<RunHashes>) -> GoldenCase {
+        GoldenCase {
+            name: name.to_string(),
+            spec: PathBuf::from(format!("specs/{}.json", name)),
+            data: PathBuf::from("data/bars.csv"),
+            expected,
+        }
+    }
+
+    #[test]
+    fn check_reports_changed_outputs_until_blessed() {
+        let mut suite = GoldenSuite {
+            cases: vec![case("a", Some(hashes("old"))), case("b", None)],
+        };
+        let results = check_suite(&suite, &[], |_| Ok(hashes("new"))).unwrap();
+        assert_eq!(
+            results[0].status,
+            CaseStatus::Failed {
+                mismatches: vec!["fills".to_string(), "stats".to_string()]
+            }
+        );
+        assert_eq!(results[1].status, CaseStatus::Unblessed);
+
+        bless(&mut suite, &results[..1]);
+        let results = check_suite(&suite, &["a".to_string()], |_| Ok(hashes("new"))).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].passed());
+        assert!(check_suite(&suite, &["c".to_string()], |_| Ok(hashes("new"))).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("golden.json");
+        suite.save(&path).unwrap();
+        assert_eq!(GoldenSuite::load(&path).unwrap(), suite);
+    }
+
+    #[test]
+    fn hashes_cover_every_output() {
+        let stats = BacktestStats {
+            initial_equity: 100.0,
+            final_equity: 110.0,
+            total_return: 0.1,
+            num_trades: 0,
+            total_commission: 0.0,
+            costs: CostBreakdown::default(),
+            sharpe_ratio: 1.0,
+            max_drawdown: 0.0,
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
+        };
+        let base = RunHashes::of(&[], &[(0, 100.0), (1, 110.0)], &stats).unwrap();
+        let moved = RunHashes::of(&[], &[(0, 100.0), (1, 110.000001)], &stats).unwrap();
+        assert_eq!(base.mismatches(&moved), ["equity"]);
+    }
+}
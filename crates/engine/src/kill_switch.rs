@@ -0,0 +1,213 @@
+//! Drawdown kill switch, a circuit breaker on the whole account.
+//!
+//! Equity is marked against its running peak at each bar's close. Once the
+//! drawdown exceeds the threshold the switch trips for good: orders resting
+//! at the broker or still on their way to it are cancelled, each position is closed at market on its
+//! symbol's next bar, and every order the strategy places from then on is
+//! refused.
+
+use schema::{KillSwitchTrip, Order, OrderType, Portfolio, Side};
+use std::collections::BTreeSet;
+
+/// Trips once equity falls more than `max_drawdown` below its peak
+#[derive(Debug, Clone, PartialEq)]
+pub struct KillSwitch {
+    max_drawdown: f64,
+    peak: f64,
+    trip: Option<KillSwitchTrip>,
+    /// Symbols already sent an order to close them
+    flattened: BTreeSet<String>,
+}
+
+impl KillSwitch {
+    /// A switch on an account starting at `initial_equity`
+    pub fn new(max_drawdown: f64, initial_equity: f64) -> Self {
+        Self {
+            max_drawdown,
+            peak: initial_equity,
+            trip: None,
+            flattened: BTreeSet::new(),
+        }
+    }
+
+    /// Mark `equity` at `timestamp`, returning the trip when this mark is
+    /// the one that trips the switch
+    pub fn mark(&mut self, timestamp: i64, equity: f64) -> Option<KillSwitchTrip> {
+        if self.trip.is_some() {
+            return None;
+        }
+        self.peak = self.peak.max(equity);
+        if self.peak <= 0.0 {
+            return None;
+        }
+        let drawdown = (self.peak - equity) / self.peak;
+        if drawdown <= self.max_drawdown {
+            return None;
+        }
+        let trip = KillSwitchTrip {
+            timestamp,
+            equity,
+            peak_equity: self.peak,
+            drawdown,
+            max_drawdown: self.max_drawdown,
+        };
+        self.trip = Some(trip);
+        Some(trip)
+    }
+
+    /// When the switch tripped, if it has
+    pub fn trip(&self) -> Option<&KillSwitchTrip> {
+        self.trip.as_ref()
+    }
+
+    /// Market order closing the position in `symbol`, once per symbol and
+    /// only after the switch has tripped
+    pub fn flatten(&mut self, symbol: &str, portfolio: &Portfolio) -> Option<Order> {
+        self.trip?;
+        let held = portfolio.get_position(symbol).map_or(0.0, |p| p.quantity);
+        if held == 0.0 || !self.flattened.insert(symbol.to_string()) {
+            return None;
+        }
+        Some(Order {
+            symbol: symbol.to_string(),
+            side: if held > 0.0 { Side::Sell } else { Side::Buy },
+            quantity: held.abs(),
+            order_type: OrderType::Market,
+            limit_price: None,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::BacktestEngine;
+    use crate::data_feed::VecDataFeed;
+    use broker_sim::{Latency, SimpleBroker};
+    use cost::ZeroCost;
+    use schema::{Bar, RejectionReason, Strategy};
+
+    /// Buys ten shares on every bar
+    struct Accumulate;
+
+    impl Strategy for Accumulate {
+        fn on_bar(&mut self, bar: &Bar, _portfolio: &Portfolio) -> Vec<Order> {
+            vec![Order {
+                symbol: bar.symbol.clone(),
+                side: Side::Buy,
+                quantity: 10.0,
+                order_type: OrderType::Market,
+                limit_price: None,
+                stop_price: None,
+                order_id: None,
+                bracket: None,
+            }]
+        }
+
+        fn name(&self) -> &str {
+            "Accumulate"
+        }
+    }
+
+    #[test]
+    fn the_switch_trips_once_past_the_threshold() {
+        let mut switch = KillSwitch::new(0.1, 1000.0);
+        assert_eq!(switch.mark(1, 1100.0), None);
+        // 10% below the 1100 peak is not more than 10%
+        assert_eq!(switch.mark(2, 990.0), None);
+        let trip = switch.mark(3, 980.0).unwrap();
+        assert_eq!((trip.timestamp, trip.peak_equity), (3, 1100.0));
+        assert!((trip.drawdown - 120.0 / 1100.0).abs() < 1e-12);
+        assert_eq!(switch.mark(4, 500.0), None);
+        assert_eq!(switch.trip(), Some(&trip));
+    }
+
+    fn bars(closes: &[f64]) -> Vec<Bar> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Bar {
+                timestamp: i as i64 + 1,
+                symbol: "AAPL".to_string(),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 10000.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_tripped_engine_flattens_and_refuses_the_strategy() {
+        let bars = bars(&[100.0, 100.0, 80.0, 70.0, 90.0]);
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars),
+            Accumulate,
+            SimpleBroker::new(ZeroCost, 42),
+            1000.0,
+        )
+        .with_kill_switch(0.2);
+        engine.run().unwrap();
+
+        // Twenty shares bought at 100 and ten at 80 lose 400 of the 1000
+        // peak at the third close, past the 20% threshold
+        let trip = engine.kill_switch().unwrap();
+        assert_eq!(trip.timestamp, 3);
+        assert_eq!(trip.equity, 600.0);
+
+        // The thirty shares are sold on the next bar, and nothing after
+        let fills = engine.fills();
+        assert_eq!(fills.len(), 4);
+        assert_eq!((fills[3].side, fills[3].quantity), (Side::Sell, 30.0));
+        assert_eq!(fills[3].timestamp, 4);
+        let refused: Vec<_> = engine
+            .rejections()
+            .iter()
+            .map(|r| (r.timestamp, r.reason.clone()))
+            .collect();
+        assert_eq!(
+            refused,
+            [
+                (4, RejectionReason::KillSwitch),
+                (5, RejectionReason::KillSwitch)
+            ]
+        );
+        assert_eq!(
+            engine.equity_history().last().unwrap().1,
+            1000.0 - 400.0 - 300.0
+        );
+    }
+
+    #[test]
+    fn orders_in_flight_when_the_switch_trips_never_fill() {
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars(&[100.0, 100.0, 70.0, 70.0, 70.0, 70.0])),
+            Accumulate,
+            SimpleBroker::new(ZeroCost, 42).with_latency(Latency::Bars(1)),
+            1000.0,
+        )
+        .with_kill_switch(0.2);
+        engine.run().unwrap();
+
+        // Ten shares each at 100 and 70 trip the switch at the third close,
+        // with the third bar's order still on its way
+        assert_eq!(engine.kill_switch().unwrap().timestamp, 3);
+        let fills: Vec<_> = engine
+            .fills()
+            .iter()
+            .map(|f| (f.timestamp, f.side, f.quantity))
+            .collect();
+        assert_eq!(
+            fills,
+            [
+                (2, Side::Buy, 10.0),
+                (3, Side::Buy, 10.0),
+                (5, Side::Sell, 20.0)
+            ]
+        );
+    }
+}
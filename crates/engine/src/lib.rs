@@ -1,12 +1,36 @@
 #![forbid(unsafe_code)]
 
 pub mod backtest;
+pub mod bootstrap;
+pub mod currency;
 pub mod data_feed;
 pub mod determinism;
+pub mod financing;
+pub mod golden;
+pub mod kill_switch;
+pub mod live;
+pub mod lots;
+pub mod monte_carlo;
+pub mod multi_strategy;
+pub mod observer;
 pub mod output;
 pub mod portfolio;
+pub mod progress;
+pub mod risk;
+pub mod round_trips;
+pub mod scheduler;
+pub mod summation;
+pub mod targets;
+pub mod trace;
 
 pub use backtest::BacktestEngine;
 pub use data_feed::{VecCanonicalEventFeed, VecDataFeed};
 pub use determinism::{canonical_json_hash, stable_hash_bytes};
+pub use financing::FinancingRates;
+pub use live::LiveEngine;
+pub use observer::EngineObserver;
 pub use portfolio::PortfolioManager;
+pub use progress::Progress;
+pub use summation::{neumaier_sum, NeumaierSum};
+pub use targets::{ExecutionRules, TargetExecutor};
+pub use trace::RunTrace;
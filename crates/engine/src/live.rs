@@ -0,0 +1,419 @@
+use crate::portfolio::PortfolioManager;
+use crate::risk::{RiskCheck, RiskScreen};
+use crate::round_trips::RoundTrip;
+use crate::scheduler::{Scheduler, WarmUp};
+use anyhow::Result;
+use schema::{
+    Bar, BorrowCharge, BrokerResult, BrokerSim, CostBreakdown, EquityPoint, ExposurePoint, Fill,
+    FundingPayment, LiveBroker, MarginCall, OptionExpiration, Order, OrderEvent, OrderRejection,
+    Portfolio, Strategy,
+};
+use std::collections::HashMap;
+
+/// Incremental engine for live and paper sessions.
+///
+/// Bars are pushed one at a time as a feed delivers them instead of being
+/// pulled from a finite `DataFeed`. With a `BrokerSim`, each step matches
+/// `BacktestEngine::run`, so replaying a session's bars reproduces its state
+/// exactly. With a `LiveBroker`, `on_bar_async` runs the same step against a
+/// real account.
+pub struct LiveEngine<S: Strategy, B> {
+    strategy: S,
+    broker: B,
+    portfolio_manager: PortfolioManager,
+    fills: Vec<Fill>,
+    rejections: Vec<OrderRejection>,
+    borrow_charges: Vec<BorrowCharge>,
+    funding_payments: Vec<FundingPayment>,
+    option_expirations: Vec<OptionExpiration>,
+    margin_calls: Vec<MarginCall>,
+    order_events: Vec<OrderEvent>,
+    current_prices: HashMap<String, f64>,
+    last_timestamp: Option<i64>,
+    bars_processed: usize,
+    orders_submitted: usize,
+    risk: RiskScreen,
+    scheduler: Scheduler,
+    warm_up: WarmUp,
+}
+
+impl<S: Strategy, B> LiveEngine<S, B> {
+    pub fn new(strategy: S, broker: B, initial_cash: f64) -> Self {
+        let warm_up = WarmUp::new(strategy.warmup_bars());
+        Self {
+            strategy,
+            broker,
+            portfolio_manager: PortfolioManager::new(initial_cash),
+            fills: Vec::new(),
+            rejections: Vec::new(),
+            borrow_charges: Vec::new(),
+            funding_payments: Vec::new(),
+            option_expirations: Vec::new(),
+            margin_calls: Vec::new(),
+            order_events: Vec::new(),
+            current_prices: HashMap::new(),
+            last_timestamp: None,
+            bars_processed: 0,
+            orders_submitted: 0,
+            risk: RiskScreen::default(),
+            scheduler: Scheduler::default(),
+            warm_up,
+        }
+    }
+
+    /// Pass every order through `check` before the broker sees it, as
+    /// `BacktestEngine::with_risk_check` does
+    pub fn with_risk_check(mut self, check: impl RiskCheck + Send + 'static) -> Self {
+        self.risk.push(check);
+        self
+    }
+
+    /// Warm the strategy up on the first `bars` bar timestamps, as
+    /// `BacktestEngine::with_warmup` does
+    pub fn with_warmup(mut self, bars: usize) -> Self {
+        self.warm_up = WarmUp::new(bars);
+        self
+    }
+
+    /// Advance to `bar` and return the orders the strategy places on it, or
+    /// `None` while the strategy warms up
+    fn start_bar(&mut self, bar: &Bar) -> Result<Option<Vec<Order>>> {
+        if let Some(last) = self.last_timestamp {
+            if bar.timestamp < last {
+                anyhow::bail!(
+                    "Out-of-order bar for {} at {} (last processed {})",
+                    bar.symbol,
+                    bar.timestamp,
+                    last
+                );
+            }
+        }
+        self.last_timestamp = Some(bar.timestamp);
+        self.bars_processed += 1;
+        self.current_prices.insert(bar.symbol.clone(), bar.close);
+        self.portfolio_manager.advance_to(bar.timestamp);
+        if self.warm_up.is_warming(bar.timestamp) {
+            let portfolio = self.portfolio_manager.portfolio();
+            self.scheduler.warm_up(&mut self.strategy, bar, portfolio);
+            self.strategy.on_bar(bar, portfolio);
+            return Ok(None);
+        }
+
+        let mut orders = self.scheduler.timer_orders(
+            &mut self.strategy,
+            bar,
+            self.portfolio_manager.portfolio(),
+        );
+        orders.extend(
+            self.strategy
+                .on_bar(bar, self.portfolio_manager.portfolio()),
+        );
+        let (orders, rejections) = self.risk.screen(
+            orders,
+            self.portfolio_manager.portfolio(),
+            &self.portfolio_manager.base_prices(&self.current_prices),
+            self.portfolio_manager.traded_notional(),
+            bar.timestamp,
+        );
+        self.rejections.extend(rejections);
+
+        self.orders_submitted += orders.len();
+        Ok(Some(orders))
+    }
+
+    /// Apply what the broker did on a bar to the portfolio
+    fn record(&mut self, result: BrokerResult) -> Result<Vec<Fill>> {
+        for charge in &result.borrow_charges {
+            self.portfolio_manager.apply_borrow_charge(charge);
+        }
+        for payment in &result.funding_payments {
+            self.portfolio_manager.apply_funding_payment(payment);
+        }
+        for fill in &result.fills {
+            self.portfolio_manager
+                .apply_fill(fill, &self.current_prices)?;
+        }
+        self.fills.extend(result.fills.iter().cloned());
+        self.rejections.extend(result.rejections);
+        self.borrow_charges.extend(result.borrow_charges);
+        self.funding_payments.extend(result.funding_payments);
+        self.option_expirations.extend(result.option_expirations);
+        self.margin_calls.extend(result.margin_calls);
+        self.order_events.extend(result.order_events);
+        Ok(result.fills)
+    }
+
+    pub fn portfolio(&self) -> &Portfolio {
+        self.portfolio_manager.portfolio()
+    }
+
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    /// Orders the broker refused, in the order they were refused
+    pub fn rejections(&self) -> &[OrderRejection] {
+        &self.rejections
+    }
+
+    /// Borrow fees charged on shorts, in the order they accrued
+    pub fn borrow_charges(&self) -> &[BorrowCharge] {
+        &self.borrow_charges
+    }
+
+    /// Funding exchanged on perpetuals, in the order it settled
+    pub fn funding_payments(&self) -> &[FundingPayment] {
+        &self.funding_payments
+    }
+
+    /// Net funding paid on perpetuals; negative when more was received
+    pub fn total_funding(&self) -> f64 {
+        self.portfolio_manager.total_funding()
+    }
+
+    /// Option positions exercised, assigned or expired worthless
+    pub fn option_expirations(&self) -> &[OptionExpiration] {
+        &self.option_expirations
+    }
+
+    /// Margin calls the broker met with forced liquidations
+    pub fn margin_calls(&self) -> &[MarginCall] {
+        &self.margin_calls
+    }
+
+    /// Every step of every order at the broker, in the order it happened
+    pub fn order_events(&self) -> &[OrderEvent] {
+        &self.order_events
+    }
+
+    pub fn equity_history(&self) -> &[(i64, f64)] {
+        self.portfolio_manager.equity_history()
+    }
+
+    /// Cash and position value behind every equity mark
+    pub fn equity_points(&self) -> &[EquityPoint] {
+        self.portfolio_manager.equity_points()
+    }
+
+    /// Gross and net exposure, leverage and turnover at every equity mark
+    pub fn exposure_history(&self) -> &[ExposurePoint] {
+        self.portfolio_manager.exposure_history()
+    }
+
+    /// Round trips closed so far, with excursions marked at every bar
+    pub fn round_trips(&self) -> &[RoundTrip] {
+        self.portfolio_manager.round_trips()
+    }
+
+    pub fn total_commission(&self) -> f64 {
+        self.portfolio_manager.total_commission()
+    }
+
+    pub fn costs(&self) -> CostBreakdown {
+        self.portfolio_manager.costs()
+    }
+
+    pub fn num_trades(&self) -> usize {
+        self.fills.len()
+    }
+
+    /// Timestamp of the most recent bar processed
+    pub fn last_timestamp(&self) -> Option<i64> {
+        self.last_timestamp
+    }
+
+    pub fn bars_processed(&self) -> usize {
+        self.bars_processed
+    }
+
+    /// Orders the strategy has sent to the broker, filled or not
+    pub fn orders_submitted(&self) -> usize {
+        self.orders_submitted
+    }
+}
+
+impl<S: Strategy, B: BrokerSim> LiveEngine<S, B> {
+    /// Process one bar and return the fills it produced.
+    ///
+    /// Bars must arrive in non-decreasing timestamp order.
+    pub fn on_bar(&mut self, bar: &Bar) -> Result<Vec<Fill>> {
+        let Some(orders) = self.start_bar(bar)? else {
+            return Ok(Vec::new());
+        };
+        let result =
+            self.broker
+                .process_orders_for(orders, bar, self.portfolio_manager.portfolio())?;
+        let fills = self.record(result)?;
+        let open_orders = self.broker.open_orders();
+        for instruction in self.strategy.manage_orders(bar, &open_orders) {
+            self.broker.apply_instruction(&instruction)?;
+        }
+        self.portfolio_manager.update_equity(&self.current_prices);
+        Ok(fills)
+    }
+}
+
+impl<S: Strategy, B: LiveBroker> LiveEngine<S, B> {
+    /// Process one bar through a live broker and return the fills it
+    /// reported
+    pub async fn on_bar_async(&mut self, bar: &Bar) -> Result<Vec<Fill>> {
+        let Some(orders) = self.start_bar(bar)? else {
+            return Ok(Vec::new());
+        };
+        let result = self
+            .broker
+            .process_orders_for(orders, bar, self.portfolio_manager.portfolio())
+            .await?;
+        let fills = self.record(result)?;
+        let open_orders = self.broker.open_orders().await?;
+        for instruction in self.strategy.manage_orders(bar, &open_orders) {
+            self.broker.apply_instruction(&instruction).await?;
+        }
+        self.portfolio_manager.update_equity(&self.current_prices);
+        Ok(fills)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BacktestEngine, VecDataFeed};
+    use broker_sim::SimpleBroker;
+    use cost::ZeroCost;
+    use schema::{OrderAmendment, OrderType, Side};
+
+    /// Alternates buying and selling one share every bar
+    struct FlipFlop {
+        buy: bool,
+    }
+
+    impl Strategy for FlipFlop {
+        fn on_bar(&mut self, bar: &Bar, _portfolio: &Portfolio) -> Vec<Order> {
+            self.buy = !self.buy;
+            vec![Order {
+                symbol: bar.symbol.clone(),
+                side: if self.buy { Side::Buy } else { Side::Sell },
+                quantity: 1.0,
+                order_type: OrderType::Market,
+                limit_price: None,
+                stop_price: None,
+                order_id: None,
+                bracket: None,
+            }]
+        }
+
+        fn name(&self) -> &str {
+            "FlipFlop"
+        }
+    }
+
+    fn bars() -> Vec<Bar> {
+        (0..6)
+            .map(|i| Bar {
+                timestamp: 1000 * (i + 1),
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 102.0,
+                low: 99.0,
+                close: 100.0 + i as f64,
+                volume: 1000.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn incremental_bars_match_batch_backtest() {
+        let mut live = LiveEngine::new(
+            FlipFlop { buy: false },
+            SimpleBroker::new(ZeroCost, 42),
+            10_000.0,
+        );
+        for bar in bars() {
+            assert_eq!(live.on_bar(&bar).unwrap().len(), 1);
+        }
+
+        let mut batch = BacktestEngine::new(
+            VecDataFeed::new(bars()),
+            FlipFlop { buy: false },
+            SimpleBroker::new(ZeroCost, 42),
+            10_000.0,
+        );
+        batch.run().unwrap();
+
+        assert_eq!(live.fills(), batch.fills());
+        assert_eq!(live.equity_history(), batch.equity_history());
+        assert_eq!(live.last_timestamp(), Some(6000));
+        assert_eq!(live.orders_submitted(), 6);
+    }
+
+    #[test]
+    fn rejects_out_of_order_bars() {
+        let mut live = LiveEngine::new(
+            FlipFlop { buy: false },
+            SimpleBroker::new(ZeroCost, 42),
+            10_000.0,
+        );
+        let bars = bars();
+        live.on_bar(&bars[2]).unwrap();
+        assert!(live.on_bar(&bars[0]).is_err());
+    }
+
+    /// A simulator behind the async interface, as a stand-in for a network broker
+    struct Remote(SimpleBroker<ZeroCost>);
+
+    impl LiveBroker for Remote {
+        async fn process_orders_for(
+            &mut self,
+            orders: Vec<Order>,
+            bar: &Bar,
+            portfolio: &Portfolio,
+        ) -> Result<BrokerResult> {
+            self.0.process_orders_for(orders, bar, portfolio)
+        }
+
+        async fn open_orders(&mut self) -> Result<Vec<Order>> {
+            Ok(BrokerSim::open_orders(&self.0))
+        }
+
+        async fn cancel(&mut self, order_id: u64) -> Result<()> {
+            self.0.cancel(order_id).map(|_| ())
+        }
+
+        async fn amend(&mut self, order_id: u64, amendment: &OrderAmendment) -> Result<()> {
+            self.0.amend(order_id, amendment)
+        }
+
+        fn name(&self) -> &str {
+            "Remote"
+        }
+    }
+
+    /// Poll a future that never waits to completion
+    fn ready<T>(future: impl std::future::Future<Output = T>) -> T {
+        let mut future = std::pin::pin!(future);
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        match future.as_mut().poll(&mut context) {
+            std::task::Poll::Ready(value) => value,
+            std::task::Poll::Pending => panic!("future was not ready"),
+        }
+    }
+
+    #[test]
+    fn live_brokers_run_the_same_step() {
+        let mut remote = LiveEngine::new(
+            FlipFlop { buy: false },
+            Remote(SimpleBroker::new(ZeroCost, 42)),
+            10_000.0,
+        );
+        let mut simulated = LiveEngine::new(
+            FlipFlop { buy: false },
+            SimpleBroker::new(ZeroCost, 42),
+            10_000.0,
+        );
+        for bar in bars() {
+            let fills = ready(remote.on_bar_async(&bar)).unwrap();
+            assert_eq!(fills, simulated.on_bar(&bar).unwrap());
+        }
+        assert_eq!(remote.equity_history(), simulated.equity_history());
+    }
+}
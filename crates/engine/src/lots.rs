@@ -0,0 +1,267 @@
+//! Tax-lot accounting: which lots each closing fill realizes.
+//!
+//! Every fill that opens or adds to a position opens a lot; fills that
+//! reduce it close lots in the order the `LotMethod` picks, realizing PnL
+//! and a holding period per lot. A loss realized within `WASH_SALE_WINDOW`
+//! of another lot of the same symbol and side being opened is flagged as a
+//! wash sale.
+
+use schema::{Fill, Side};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Seconds either side of a loss in which reopening makes it a wash sale
+pub const WASH_SALE_WINDOW: i64 = 30 * 86_400;
+
+/// Lots smaller than this are fully closed
+const FLAT: f64 = 1e-9;
+
+/// Which lots a closing fill closes, and at what cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LotMethod {
+    /// Oldest lot first
+    Fifo,
+    /// Newest lot first
+    Lifo,
+    /// Every lot at the position's average price, oldest first
+    #[default]
+    AverageCost,
+}
+
+/// Part of a position opened by one fill
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaxLot {
+    pub id: u64,
+    pub symbol: String,
+    /// `Buy` for a long lot, `Sell` for a short one
+    pub side: Side,
+    /// Quantity still open
+    pub quantity: f64,
+    /// Cost per unit; the position's average under `AverageCost`
+    pub price: f64,
+    pub opened_at: i64,
+}
+
+/// A lot, or part of one, closed by a fill
+#[derive(Debug, Clone, PartialEq)]
+pub struct LotRealization {
+    pub lot_id: u64,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub opened_at: i64,
+    pub closed_at: i64,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    /// PnL before costs
+    pub pnl: f64,
+    /// A loss with another lot of the symbol and side opened within
+    /// `WASH_SALE_WINDOW` of it
+    pub wash_sale: bool,
+}
+
+impl LotRealization {
+    /// Seconds the lot was held
+    pub fn holding_period(&self) -> i64 {
+        self.closed_at - self.opened_at
+    }
+}
+
+/// Open lots by symbol and every lot closed so far
+#[derive(Debug, Clone, Default)]
+pub struct LotBook {
+    method: LotMethod,
+    open: BTreeMap<String, VecDeque<TaxLot>>,
+    realizations: Vec<LotRealization>,
+    /// `(symbol, side, lot id, opened at)` of every lot opened
+    openings: Vec<(String, Side, u64, i64)>,
+    next_id: u64,
+}
+
+impl LotBook {
+    pub fn new(method: LotMethod) -> Self {
+        Self {
+            method,
+            ..Self::default()
+        }
+    }
+
+    pub fn method(&self) -> LotMethod {
+        self.method
+    }
+
+    /// Close lots against `fill` and open one with what is left of it,
+    /// returning the PnL realized
+    pub fn apply_fill(&mut self, fill: &Fill) -> f64 {
+        let mut remaining = fill.quantity;
+        let mut realized = 0.0;
+        let lots = self.open.entry(fill.symbol.clone()).or_default();
+        while remaining > FLAT {
+            let lot = match self.method {
+                LotMethod::Lifo => lots.back_mut(),
+                LotMethod::Fifo | LotMethod::AverageCost => lots.front_mut(),
+            };
+            let Some(lot) = lot.filter(|lot| lot.side != fill.side) else {
+                break;
+            };
+            let quantity = remaining.min(lot.quantity);
+            let pnl = match lot.side {
+                Side::Buy => quantity * (fill.price - lot.price),
+                Side::Sell => quantity * (lot.price - fill.price),
+            };
+            let wash_sale = pnl < 0.0
+                && self.openings.iter().any(|(symbol, side, id, opened_at)| {
+                    *symbol == fill.symbol
+                        && *side == lot.side
+                        && *id != lot.id
+                        && (fill.timestamp - opened_at).abs() <= WASH_SALE_WINDOW
+                });
+            self.realizations.push(LotRealization {
+                lot_id: lot.id,
+                symbol: fill.symbol.clone(),
+                side: lot.side,
+                quantity,
+                opened_at: lot.opened_at,
+                closed_at: fill.timestamp,
+                entry_price: lot.price,
+                exit_price: fill.price,
+                pnl,
+                wash_sale,
+            });
+            realized += pnl;
+            remaining -= quantity;
+            lot.quantity -= quantity;
+            if lot.quantity <= FLAT {
+                match self.method {
+                    LotMethod::Lifo => lots.pop_back(),
+                    LotMethod::Fifo | LotMethod::AverageCost => lots.pop_front(),
+                };
+            }
+        }
+
+        if remaining > FLAT {
+            let id = self.next_id;
+            self.next_id += 1;
+            // Losses realized shortly before this lot opened become wash sales
+            for realization in self.realizations.iter_mut().rev() {
+                if realization.closed_at < fill.timestamp - WASH_SALE_WINDOW {
+                    break;
+                }
+                if realization.symbol == fill.symbol
+                    && realization.side == fill.side
+                    && realization.pnl < 0.0
+                {
+                    realization.wash_sale = true;
+                }
+            }
+            self.openings
+                .push((fill.symbol.clone(), fill.side, id, fill.timestamp));
+            lots.push_back(TaxLot {
+                id,
+                symbol: fill.symbol.clone(),
+                side: fill.side,
+                quantity: remaining,
+                price: fill.price,
+                opened_at: fill.timestamp,
+            });
+            if self.method == LotMethod::AverageCost {
+                let average = cost_basis(lots);
+                for lot in lots.iter_mut() {
+                    lot.price = average;
+                }
+            }
+        }
+        if lots.is_empty() {
+            self.open.remove(&fill.symbol);
+        }
+        realized
+    }
+
+    /// Open lots of `symbol`, oldest first
+    pub fn lots(&self, symbol: &str) -> impl Iterator<Item = &TaxLot> {
+        self.open.get(symbol).into_iter().flatten()
+    }
+
+    /// Quantity-weighted cost of the open lots of `symbol`
+    pub fn cost_basis(&self, symbol: &str) -> Option<f64> {
+        self.open.get(symbol).map(cost_basis)
+    }
+
+    /// Every lot closed so far, in closing order
+    pub fn realizations(&self) -> &[LotRealization] {
+        &self.realizations
+    }
+}
+
+fn cost_basis(lots: &VecDeque<TaxLot>) -> f64 {
+    let quantity: f64 = lots.iter().map(|lot| lot.quantity).sum();
+    lots.iter().map(|lot| lot.quantity * lot.price).sum::<f64>() / quantity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::CostBreakdown;
+
+    const DAY: i64 = 86_400;
+
+    fn fill(day: i64, side: Side, quantity: f64, price: f64) -> Fill {
+        Fill {
+            timestamp: day * DAY,
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            price,
+            costs: CostBreakdown::default(),
+        }
+    }
+
+    fn book(method: LotMethod) -> LotBook {
+        let mut book = LotBook::new(method);
+        book.apply_fill(&fill(0, Side::Buy, 10.0, 100.0));
+        book.apply_fill(&fill(100, Side::Buy, 10.0, 120.0));
+        book
+    }
+
+    #[test]
+    fn methods_pick_which_lots_a_sale_closes() {
+        let sale = fill(200, Side::Sell, 15.0, 110.0);
+
+        let mut fifo = book(LotMethod::Fifo);
+        assert_eq!(fifo.apply_fill(&sale), 100.0 - 50.0);
+        let holding: Vec<i64> = fifo
+            .realizations()
+            .iter()
+            .map(|r| r.holding_period() / DAY)
+            .collect();
+        assert_eq!(holding, [200, 100]);
+        assert_eq!(fifo.cost_basis("AAPL"), Some(120.0));
+
+        let mut lifo = book(LotMethod::Lifo);
+        assert_eq!(lifo.apply_fill(&sale), -100.0 + 50.0);
+        assert_eq!(lifo.cost_basis("AAPL"), Some(100.0));
+
+        let mut average = book(LotMethod::AverageCost);
+        assert_eq!(average.apply_fill(&sale), 0.0);
+        assert_eq!(average.lots("AAPL").count(), 1);
+        assert_eq!(average.cost_basis("AAPL"), Some(110.0));
+
+        // Selling through flat closes everything and opens a short lot
+        assert_eq!(average.apply_fill(&fill(201, Side::Sell, 10.0, 110.0)), 0.0);
+        let short: Vec<&TaxLot> = average.lots("AAPL").collect();
+        assert_eq!((short[0].side, short[0].quantity), (Side::Sell, 5.0));
+    }
+
+    #[test]
+    fn losses_near_a_reopening_are_wash_sales() {
+        let mut book = LotBook::new(LotMethod::Fifo);
+        book.apply_fill(&fill(0, Side::Buy, 10.0, 100.0));
+        book.apply_fill(&fill(40, Side::Sell, 10.0, 90.0));
+        book.apply_fill(&fill(50, Side::Buy, 10.0, 95.0));
+        book.apply_fill(&fill(60, Side::Sell, 10.0, 105.0));
+        book.apply_fill(&fill(100, Side::Buy, 5.0, 80.0));
+        book.apply_fill(&fill(110, Side::Sell, 5.0, 70.0));
+        let flags: Vec<bool> = book.realizations().iter().map(|r| r.wash_sale).collect();
+        // A loss rebought 10 days later, a gain, then a loss with no other lot
+        assert_eq!(flags, [true, false, false]);
+    }
+}
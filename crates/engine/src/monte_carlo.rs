@@ -0,0 +1,183 @@
+//! Monte Carlo resampling of a run's realized trade PnL.
+//!
+//! Each simulation rebuilds a trade sequence as long as the run's from
+//! blocks of consecutive round trips, drawn with replacement and wrapping
+//! past the last trade, so streaks inside a block survive. The sequence is
+//! replayed from the initial equity. Draws come from a ChaCha stream of the
+//! seed, so a report repeats exactly.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+
+/// How a run's trades are resampled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonteCarloConfig {
+    pub simulations: usize,
+    /// Consecutive trades drawn together
+    pub block_size: usize,
+    /// Share of initial equity whose loss at any point counts as ruin
+    pub ruin_loss: f64,
+    pub seed: u64,
+}
+
+/// Summary of one simulated quantity across all simulations
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Distribution {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub p5: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    fn of(mut values: Vec<f64>) -> Self {
+        values.sort_by(f64::total_cmp);
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        Self {
+            mean,
+            std_dev: variance.sqrt(),
+            min: values[0],
+            p5: percentile(&values, 0.05),
+            p25: percentile(&values, 0.25),
+            median: percentile(&values, 0.5),
+            p75: percentile(&values, 0.75),
+            p95: percentile(&values, 0.95),
+            max: values[values.len() - 1],
+        }
+    }
+}
+
+/// Indices of `len` items resampled in blocks of `block_size` consecutive
+/// items, each starting anywhere and wrapping past the last item
+pub(crate) fn block_sample(rng: &mut ChaCha8Rng, len: usize, block_size: usize) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(len);
+    while indices.len() < len {
+        let start = rng.gen_range(0..len);
+        let block = block_size.min(len - indices.len());
+        indices.extend((0..block).map(|offset| (start + offset) % len));
+    }
+    indices
+}
+
+/// Linearly interpolated percentile of non-empty sorted `values`
+pub(crate) fn percentile(values: &[f64], p: f64) -> f64 {
+    let rank = p * (values.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    values[lower] + (values[upper] - values[lower]) * (rank - lower as f64)
+}
+
+/// Distributions of final equity and max drawdown over resampled trade
+/// sequences, and how often they end in ruin
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonteCarloReport {
+    pub simulations: usize,
+    pub block_size: usize,
+    pub seed: u64,
+    /// Round trips in the run, and in each simulated sequence
+    pub trades: usize,
+    pub initial_equity: f64,
+    pub ruin_loss: f64,
+    pub final_equity: Distribution,
+    /// As a fraction of the running peak, like the run's own max drawdown
+    pub max_drawdown: Distribution,
+    /// Share of simulations that lost `ruin_loss` of initial equity
+    pub ruin_probability: f64,
+}
+
+/// Block-bootstrap the realized `pnls` of a run's trades, in the order they
+/// closed
+pub fn resample_trades(
+    pnls: &[f64],
+    initial_equity: f64,
+    config: &MonteCarloConfig,
+) -> MonteCarloReport {
+    let simulations = config.simulations.max(1);
+    let block_size = config.block_size.clamp(1, pnls.len().max(1));
+    let ruin_level = initial_equity * (1.0 - config.ruin_loss);
+    let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+
+    let mut final_equity = Vec::with_capacity(simulations);
+    let mut max_drawdown = Vec::with_capacity(simulations);
+    let mut ruined = 0;
+    for _ in 0..simulations {
+        let (mut equity, mut peak, mut drawdown) = (initial_equity, initial_equity, 0.0f64);
+        let mut ruin = false;
+        for i in block_sample(&mut rng, pnls.len(), block_size) {
+            equity += pnls[i];
+            peak = peak.max(equity);
+            if peak > 0.0 {
+                drawdown = drawdown.max((peak - equity) / peak);
+            }
+            ruin |= equity <= ruin_level;
+        }
+        final_equity.push(equity);
+        max_drawdown.push(drawdown);
+        ruined += usize::from(ruin);
+    }
+
+    MonteCarloReport {
+        simulations,
+        block_size,
+        seed: config.seed,
+        trades: pnls.len(),
+        initial_equity,
+        ruin_loss: config.ruin_loss,
+        final_equity: Distribution::of(final_equity),
+        max_drawdown: Distribution::of(max_drawdown),
+        ruin_probability: ruined as f64 / simulations as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(block_size: usize, seed: u64) -> MonteCarloConfig {
+        MonteCarloConfig {
+            simulations: 500,
+            block_size,
+            ruin_loss: 0.5,
+            seed,
+        }
+    }
+
+    #[test]
+    fn resampling_keeps_the_trade_count_and_repeats_for_a_seed() {
+        let pnls = [100.0, -50.0, 30.0, -400.0, 80.0, 20.0, -10.0];
+        let report = resample_trades(&pnls, 1000.0, &config(3, 7));
+        assert_eq!(report, resample_trades(&pnls, 1000.0, &config(3, 7)));
+        assert_ne!(report, resample_trades(&pnls, 1000.0, &config(3, 8)));
+        assert_eq!(report.trades, 7);
+
+        // Every sequence draws seven trades, so final equity stays between
+        // seven of the worst and seven of the best
+        let final_equity = report.final_equity;
+        assert!(final_equity.min >= 1000.0 - 7.0 * 400.0);
+        assert!(final_equity.max <= 1000.0 + 7.0 * 100.0);
+        assert!(final_equity.min <= final_equity.median && final_equity.median <= final_equity.max);
+        assert!(report.ruin_probability > 0.0 && report.ruin_probability < 1.0);
+    }
+
+    #[test]
+    fn a_block_of_every_trade_replays_the_run() {
+        // Drawing all trades as one block only rotates the sequence, which
+        // leaves the final equity unchanged
+        let pnls = [10.0, -5.0, 20.0];
+        let report = resample_trades(&pnls, 100.0, &config(3, 42));
+        assert_eq!(report.final_equity.min, 125.0);
+        assert_eq!(report.final_equity.max, 125.0);
+        assert_eq!(report.final_equity.std_dev, 0.0);
+        assert_eq!(report.ruin_probability, 0.0);
+
+        let idle = resample_trades(&[], 100.0, &config(3, 42));
+        assert_eq!((idle.trades, idle.final_equity.mean), (0, 100.0));
+    }
+}
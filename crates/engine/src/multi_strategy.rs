@@ -0,0 +1,444 @@
+//! Several strategies trading one account, each on its own slice of capital.
+//!
+//! A `MultiStrategy` gives every sleeve a sub-portfolio of `allocation`
+//! times the account's starting equity and shows each sleeve only its own
+//! book. Each bar the sleeves' orders are netted into one market order per
+//! symbol; sleeves trading against each other cross internally at the last
+//! close, so only the net reaches the broker.
+//!
+//! The engine's fills are split among the sleeves by `SleeveBooks`, an
+//! observer registered alongside the strategy: a fill goes to the sleeves
+//! with orders still outstanding in its symbol, in proportion to what each
+//! asked for, with its costs shared by quantity. Financing, borrow and
+//! funding charges stay with the account, as do fills no sleeve is waiting
+//! on, so the sleeves' equity need not sum to the account's.
+
+use crate::observer::EngineObserver;
+use crate::output::{calculate_stats, Annualization};
+use crate::portfolio::PortfolioManager;
+use schema::{
+    BacktestStats, Bar, CostBreakdown, Fill, Order, OrderType, Portfolio, Side, Strategy,
+    TimerEvent,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Quantities smaller than this count as zero
+const FLAT: f64 = 1e-9;
+
+/// A strategy and the share of capital it trades
+pub struct Sleeve {
+    pub name: String,
+    /// Share of the account's starting equity
+    pub allocation: f64,
+    pub strategy: Box<dyn Strategy>,
+}
+
+/// A sleeve's sub-portfolio and what it is still waiting to have filled
+struct SleeveBook {
+    name: String,
+    allocation: f64,
+    /// Opened on the first bar, once the account's equity is known
+    manager: Option<PortfolioManager>,
+    fills: Vec<Fill>,
+    costs: CostBreakdown,
+    /// Signed quantity asked for in each symbol and not yet filled, with the
+    /// timestamp it was asked at
+    outstanding: BTreeMap<String, (i64, f64)>,
+}
+
+impl SleeveBook {
+    fn manager(&mut self) -> &mut PortfolioManager {
+        self.manager
+            .as_mut()
+            .expect("sleeve books open on the first bar")
+    }
+
+    /// Take `quantity` (signed) of `fill` into the sub-portfolio
+    fn apply(&mut self, fill: &Fill, quantity: f64, costs: CostBreakdown, prices: &Prices) {
+        let fill = Fill {
+            side: if quantity > 0.0 {
+                Side::Buy
+            } else {
+                Side::Sell
+            },
+            quantity: quantity.abs(),
+            costs,
+            ..fill.clone()
+        };
+        self.manager()
+            .apply_fill(&fill, prices)
+            .expect("sleeve books keep no currencies");
+        self.costs += costs;
+        self.fills.push(fill);
+    }
+}
+
+type Prices = HashMap<String, f64>;
+
+#[derive(Default)]
+struct Books {
+    sleeves: Vec<SleeveBook>,
+    /// Latest close of every symbol seen
+    prices: Prices,
+}
+
+/// The sleeves' sub-portfolios, shared by a `MultiStrategy` and the engine
+/// observer that books fills into them
+#[derive(Clone)]
+pub struct SleeveBooks(Arc<Mutex<Books>>);
+
+impl SleeveBooks {
+    fn lock(&self) -> MutexGuard<'_, Books> {
+        self.0.lock().expect("sleeve books poisoned")
+    }
+
+    /// Each sleeve's stats over its own equity curve and fills
+    pub fn stats(&self, annualization: Annualization) -> Vec<SleeveStats> {
+        self.lock()
+            .sleeves
+            .iter()
+            .map(|sleeve| SleeveStats {
+                name: sleeve.name.clone(),
+                allocation: sleeve.allocation,
+                stats: calculate_stats(
+                    sleeve
+                        .manager
+                        .as_ref()
+                        .map_or(&[], |manager| manager.equity_history()),
+                    &sleeve.fills,
+                    sleeve.costs,
+                    annualization,
+                ),
+            })
+            .collect()
+    }
+}
+
+impl EngineObserver for SleeveBooks {
+    fn on_fill(&mut self, fill: &Fill, _portfolio: &Portfolio) {
+        let mut books = self.lock();
+        let Books { sleeves, prices } = &mut *books;
+        let filled = match fill.side {
+            Side::Buy => fill.quantity,
+            Side::Sell => -fill.quantity,
+        };
+        let asked: f64 = sleeves
+            .iter()
+            .filter_map(|s| s.outstanding.get(&fill.symbol))
+            .map(|(_, quantity)| quantity)
+            .sum();
+        // Nobody is waiting on a fill this way
+        if asked.abs() <= FLAT || asked.signum() != filled.signum() {
+            return;
+        }
+        let share = (filled / asked).min(1.0);
+        let shares: Vec<f64> = sleeves
+            .iter()
+            .map(|s| {
+                s.outstanding
+                    .get(&fill.symbol)
+                    .map_or(0.0, |(_, q)| q * share)
+            })
+            .collect();
+        let gross: f64 = shares.iter().map(|q| q.abs()).sum();
+        // Costs of the part of the fill the sleeves asked for, shared by quantity
+        let charged = fill.costs.scaled(share * asked / filled);
+        for (sleeve, quantity) in sleeves.iter_mut().zip(shares) {
+            if quantity.abs() <= FLAT {
+                continue;
+            }
+            let (_, outstanding) = sleeve.outstanding.get_mut(&fill.symbol).unwrap();
+            *outstanding -= quantity;
+            if outstanding.abs() <= FLAT {
+                sleeve.outstanding.remove(&fill.symbol);
+            }
+            sleeve.apply(
+                fill,
+                quantity,
+                charged.scaled(quantity.abs() / gross),
+                prices,
+            );
+        }
+    }
+
+    fn on_bar(&mut self, bar: &Bar, _portfolio: &Portfolio) {
+        let mut books = self.lock();
+        let Books { sleeves, prices } = &mut *books;
+        for sleeve in sleeves {
+            let manager = sleeve.manager();
+            manager.advance_to(bar.timestamp);
+            manager.update_equity(prices);
+        }
+    }
+}
+
+/// Each sleeve's share of capital and stats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SleeveStats {
+    pub name: String,
+    pub allocation: f64,
+    pub stats: BacktestStats,
+}
+
+/// Runs sleeves side by side on one account; register `books()` with the
+/// engine as an observer so fills reach the sleeves
+pub struct MultiStrategy {
+    strategies: Vec<Box<dyn Strategy>>,
+    books: SleeveBooks,
+}
+
+impl MultiStrategy {
+    pub fn new(sleeves: Vec<Sleeve>) -> Self {
+        let mut strategies = Vec::with_capacity(sleeves.len());
+        let mut books = Books::default();
+        for sleeve in sleeves {
+            strategies.push(sleeve.strategy);
+            books.sleeves.push(SleeveBook {
+                name: sleeve.name,
+                allocation: sleeve.allocation,
+                manager: None,
+                fills: Vec::new(),
+                costs: CostBreakdown::default(),
+                outstanding: BTreeMap::new(),
+            });
+        }
+        Self {
+            strategies,
+            books: SleeveBooks(Arc::new(Mutex::new(books))),
+        }
+    }
+
+    /// The observer that books the engine's fills into the sleeves
+    pub fn books(&self) -> SleeveBooks {
+        self.books.clone()
+    }
+
+    /// Orders the sleeves place through `place`, each shown its own book,
+    /// netted into one market order per symbol
+    fn net_orders(
+        &mut self,
+        timestamp: i64,
+        portfolio: &Portfolio,
+        mut place: impl FnMut(&mut dyn Strategy, &Portfolio) -> Vec<Order>,
+    ) -> Vec<Order> {
+        let mut books = self.books.lock();
+        let Books { sleeves, prices } = &mut *books;
+        let mut asked: BTreeMap<String, Vec<(usize, f64)>> = BTreeMap::new();
+        for (i, (strategy, sleeve)) in self
+            .strategies
+            .iter_mut()
+            .zip(sleeves.iter_mut())
+            .enumerate()
+        {
+            let manager = sleeve
+                .manager
+                .get_or_insert_with(|| PortfolioManager::new(sleeve.allocation * portfolio.equity));
+            manager.advance_to(timestamp);
+            for order in place(strategy.as_mut(), manager.portfolio()) {
+                let signed = match order.side {
+                    Side::Buy => order.quantity,
+                    Side::Sell => -order.quantity,
+                };
+                asked.entry(order.symbol).or_default().push((i, signed));
+            }
+        }
+
+        let mut orders = Vec::new();
+        for (symbol, requests) in asked {
+            // A sleeve's new orders replace what it asked for on earlier
+            // bars, since it sized them against a book without those fills
+            for &(i, quantity) in &requests {
+                let entry = sleeves[i]
+                    .outstanding
+                    .entry(symbol.clone())
+                    .or_insert((timestamp, 0.0));
+                if entry.0 != timestamp {
+                    *entry = (timestamp, 0.0);
+                }
+                entry.1 += quantity;
+            }
+            let net: f64 = requests.iter().map(|(_, quantity)| quantity).sum();
+            if net.abs() > FLAT {
+                orders.push(Order {
+                    symbol,
+                    side: if net > 0.0 { Side::Buy } else { Side::Sell },
+                    quantity: net.abs(),
+                    order_type: OrderType::Market,
+                    limit_price: None,
+                    stop_price: None,
+                    order_id: None,
+                    bracket: None,
+                });
+                continue;
+            }
+            // Sleeves that offset exactly cross at the last close, at no cost
+            let Some(&price) = prices.get(&symbol) else {
+                continue;
+            };
+            let cross = Fill {
+                timestamp,
+                symbol: symbol.clone(),
+                side: Side::Buy,
+                quantity: 0.0,
+                price,
+                costs: CostBreakdown::default(),
+            };
+            for sleeve in sleeves.iter_mut() {
+                if let Some((_, quantity)) = sleeve.outstanding.remove(&symbol) {
+                    if quantity.abs() > FLAT {
+                        sleeve.apply(&cross, quantity, CostBreakdown::default(), prices);
+                    }
+                }
+            }
+        }
+        orders
+    }
+}
+
+impl Strategy for MultiStrategy {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        self.books
+            .lock()
+            .prices
+            .insert(bar.symbol.clone(), bar.close);
+        self.net_orders(bar.timestamp, portfolio, |strategy, book| {
+            strategy.on_bar(bar, book)
+        })
+    }
+
+    fn on_timer(&mut self, timer: &TimerEvent, portfolio: &Portfolio) -> Vec<Order> {
+        self.net_orders(portfolio.timestamp, portfolio, |strategy, book| {
+            strategy.on_timer(timer, book)
+        })
+    }
+
+    /// The longest warm-up of any sleeve
+    fn warmup_bars(&self) -> usize {
+        self.strategies
+            .iter()
+            .map(|s| s.warmup_bars())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn name(&self) -> &str {
+        "MultiStrategy"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::BacktestEngine;
+    use crate::data_feed::VecDataFeed;
+    use broker_sim::SimpleBroker;
+    use cost::FixedPerShareCost;
+
+    /// Trades `quantity` shares of its sleeve's book on the first bar
+    struct Once {
+        quantity: f64,
+        traded: bool,
+    }
+
+    impl Strategy for Once {
+        fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+            assert!(portfolio.equity <= 6000.0, "sleeves see only their book");
+            if std::mem::replace(&mut self.traded, true) {
+                return vec![];
+            }
+            vec![Order {
+                symbol: bar.symbol.clone(),
+                side: if self.quantity > 0.0 {
+                    Side::Buy
+                } else {
+                    Side::Sell
+                },
+                quantity: self.quantity.abs(),
+                order_type: OrderType::Market,
+                limit_price: None,
+                stop_price: None,
+                order_id: None,
+                bracket: None,
+            }]
+        }
+
+        fn name(&self) -> &str {
+            "Once"
+        }
+    }
+
+    fn sleeve(name: &str, allocation: f64, quantity: f64) -> Sleeve {
+        Sleeve {
+            name: name.to_string(),
+            allocation,
+            strategy: Box::new(Once {
+                quantity,
+                traded: false,
+            }),
+        }
+    }
+
+    fn run(sleeves: Vec<Sleeve>) -> (Vec<Fill>, Vec<SleeveStats>) {
+        let bars: Vec<Bar> = [100.0, 110.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Bar {
+                timestamp: i as i64 + 1,
+                symbol: "AAPL".to_string(),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 10000.0,
+            })
+            .collect();
+        let strategy = MultiStrategy::new(sleeves);
+        let books = strategy.books();
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars),
+            strategy,
+            SimpleBroker::new(FixedPerShareCost::new(0.1, 0.0), 42),
+            10000.0,
+        )
+        .with_observer(books.clone());
+        engine.run().unwrap();
+        (
+            engine.fills().to_vec(),
+            books.stats(Annualization::default()),
+        )
+    }
+
+    #[test]
+    fn sleeves_net_their_orders_and_split_the_fill() {
+        let (fills, sleeves) = run(vec![sleeve("long", 0.6, 10.0), sleeve("short", 0.4, -4.0)]);
+
+        // Only the net six shares reach the broker
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].side, fills[0].quantity), (Side::Buy, 6.0));
+
+        // Each sleeve holds what it asked for, marked up ten a share, and
+        // pays the six shares' commission in proportion to its quantity
+        let long = &sleeves[0].stats;
+        assert_eq!(
+            (sleeves[0].name.as_str(), long.initial_equity),
+            ("long", 6000.0)
+        );
+        assert!((long.costs.commission - 0.6 * 10.0 / 14.0).abs() < 1e-12);
+        assert!((long.final_equity - (6000.0 + 100.0 - long.costs.commission)).abs() < 1e-9);
+        let short = &sleeves[1].stats;
+        assert_eq!(short.initial_equity, 4000.0);
+        assert!((short.final_equity - (4000.0 - 40.0 - short.costs.commission)).abs() < 1e-9);
+        assert_eq!((long.num_trades, short.num_trades), (1, 1));
+    }
+
+    #[test]
+    fn offsetting_sleeves_cross_without_the_broker() {
+        let (fills, sleeves) = run(vec![sleeve("long", 0.5, 5.0), sleeve("short", 0.5, -5.0)]);
+        assert!(fills.is_empty());
+        assert_eq!(sleeves[0].stats.final_equity, 5050.0);
+        assert_eq!(sleeves[1].stats.final_equity, 4950.0);
+        assert_eq!(sleeves[0].stats.costs, CostBreakdown::default());
+    }
+}
@@ -0,0 +1,130 @@
+//! Hooks into the engine loop for loggers, plots and instrumentation.
+//!
+//! Observers registered on a `BacktestEngine` are called in registration
+//! order at fixed points of each bar and once the feed is exhausted. They
+//! see the run but cannot change it, so adding one never moves a result.
+
+use schema::{Bar, Fill, Order, Portfolio};
+
+/// Callbacks from the engine loop; each defaults to doing nothing
+pub trait EngineObserver {
+    /// Called once a bar after the warm-up is done with, the portfolio
+    /// marked at its close
+    fn on_bar(&mut self, _bar: &Bar, _portfolio: &Portfolio) {}
+
+    /// Called for each order that passes the risk checks, as it goes to the
+    /// broker
+    fn on_order(&mut self, _order: &Order) {}
+
+    /// Called for each fill once it is applied to the portfolio
+    fn on_fill(&mut self, _fill: &Fill, _portfolio: &Portfolio) {}
+
+    /// Called once the feed is exhausted
+    fn on_end(&mut self, _portfolio: &Portfolio) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::BacktestEngine;
+    use crate::data_feed::VecDataFeed;
+    use broker_sim::SimpleBroker;
+    use cost::ZeroCost;
+    use schema::{OrderType, Side, Strategy};
+    use std::sync::{Arc, Mutex};
+
+    /// Buys a share on every bar
+    struct Accumulate;
+
+    impl Strategy for Accumulate {
+        fn on_bar(&mut self, bar: &Bar, _portfolio: &Portfolio) -> Vec<Order> {
+            vec![Order {
+                symbol: bar.symbol.clone(),
+                side: Side::Buy,
+                quantity: 1.0,
+                order_type: OrderType::Market,
+                limit_price: None,
+                stop_price: None,
+                order_id: None,
+                bracket: None,
+            }]
+        }
+
+        fn name(&self) -> &str {
+            "Accumulate"
+        }
+    }
+
+    /// Writes each callback to a shared log
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl EngineObserver for Recorder {
+        fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) {
+            let held = portfolio
+                .get_position(&bar.symbol)
+                .map_or(0.0, |p| p.quantity);
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("bar {} holding {}", bar.timestamp, held));
+        }
+
+        fn on_order(&mut self, order: &Order) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("order {}", order.quantity));
+        }
+
+        fn on_fill(&mut self, fill: &Fill, _portfolio: &Portfolio) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("fill {}", fill.timestamp));
+        }
+
+        fn on_end(&mut self, portfolio: &Portfolio) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("end {}", portfolio.cash));
+        }
+    }
+
+    #[test]
+    fn observers_see_each_step_of_the_loop_in_order() {
+        let bars: Vec<Bar> = (1..=2)
+            .map(|timestamp| Bar {
+                timestamp,
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 100.0,
+                low: 100.0,
+                close: 100.0,
+                volume: 10000.0,
+            })
+            .collect();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars),
+            Accumulate,
+            SimpleBroker::new(ZeroCost, 42),
+            1000.0,
+        )
+        .with_observer(Recorder(log.clone()));
+        engine.run().unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "order 1",
+                "fill 1",
+                "bar 1 holding 1",
+                "order 1",
+                "fill 2",
+                "bar 2 holding 2",
+                "end 800",
+            ]
+        );
+    }
+}
@@ -1,19 +1,82 @@
-use anyhow::Result;
-use schema::{BacktestStats, Fill};
+use crate::lots::LotRealization;
+use crate::monte_carlo::MonteCarloReport;
+use crate::multi_strategy::SleeveStats;
+use crate::round_trips::{pair_fills, RoundTrip};
+use crate::summation::neumaier_sum;
+use anyhow::{Context, Result};
+use schema::{
+    BacktestStats, CostBreakdown, EquityPoint, ExposurePoint, Fill, KillSwitchTrip, OrderEvent,
+    PerformanceStats, Side,
+};
+use std::collections::HashMap;
 use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 
+/// Bars in a year of daily data, for annualizing
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// Seconds in a calendar year, for data that trades around the clock
+const SECONDS_PER_YEAR: f64 = 365.0 * 86_400.0;
+
+/// How per-bar returns scale to a year, and the rate they are measured in
+/// excess of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Annualization {
+    /// Bars in a year
+    pub periods_per_year: f64,
+    /// Annual risk-free rate, as a fraction
+    pub risk_free_rate: f64,
+}
+
+impl Default for Annualization {
+    /// Daily equity bars against a zero rate
+    fn default() -> Self {
+        Self {
+            periods_per_year: TRADING_DAYS_PER_YEAR,
+            risk_free_rate: 0.0,
+        }
+    }
+}
+
+impl Annualization {
+    /// Bars `seconds` apart around the clock, as crypto trades
+    pub fn from_bar_interval(seconds: i64) -> Self {
+        Self {
+            periods_per_year: SECONDS_PER_YEAR / seconds as f64,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_risk_free_rate(mut self, risk_free_rate: f64) -> Self {
+        self.risk_free_rate = risk_free_rate;
+        self
+    }
+
+    /// The risk-free rate compounded down to one bar
+    pub fn risk_free_per_period(&self) -> f64 {
+        (1.0 + self.risk_free_rate).powf(1.0 / self.periods_per_year) - 1.0
+    }
+}
+
+/// Positions smaller than this count as flat
+const FLAT: f64 = 1e-9;
+
 /// Write trades to CSV
 pub fn write_trades_csv(fills: &[Fill], output_path: &Path) -> Result<()> {
     let mut wtr = csv::Writer::from_writer(File::create(output_path)?);
 
-    wtr.write_record(&[
+    wtr.write_record([
         "timestamp",
         "symbol",
         "side",
         "quantity",
         "price",
         "commission",
+        "exchange_fees",
+        "slippage",
+        "borrow",
+        "other",
     ])?;
 
     for fill in fills {
@@ -23,7 +86,11 @@ pub fn write_trades_csv(fills: &[Fill], output_path: &Path) -> Result<()> {
             format!("{:?}", fill.side),
             fill.quantity.to_string(),
             fill.price.to_string(),
-            fill.commission.to_string(),
+            fill.costs.commission.to_string(),
+            fill.costs.exchange_fees.to_string(),
+            fill.costs.slippage.to_string(),
+            fill.costs.borrow.to_string(),
+            fill.costs.other.to_string(),
         ])?;
     }
 
@@ -35,7 +102,7 @@ pub fn write_trades_csv(fills: &[Fill], output_path: &Path) -> Result<()> {
 pub fn write_equity_curve_csv(equity_history: &[(i64, f64)], output_path: &Path) -> Result<()> {
     let mut wtr = csv::Writer::from_writer(File::create(output_path)?);
 
-    wtr.write_record(&["timestamp", "equity"])?;
+    wtr.write_record(["timestamp", "equity"])?;
 
     for (timestamp, equity) in equity_history {
         wtr.write_record(&[timestamp.to_string(), equity.to_string()])?;
@@ -45,6 +112,200 @@ pub fn write_equity_curve_csv(equity_history: &[(i64, f64)], output_path: &Path)
     Ok(())
 }
 
+/// Write an equity curve with each mark's cash and position value to CSV
+pub fn write_equity_points_csv(equity_points: &[EquityPoint], output_path: &Path) -> Result<()> {
+    let mut wtr = csv::Writer::from_writer(File::create(output_path)?);
+
+    wtr.write_record(["timestamp", "equity", "cash", "positions_value"])?;
+
+    for point in equity_points {
+        wtr.write_record(&[
+            point.timestamp.to_string(),
+            point.equity.to_string(),
+            point.cash.to_string(),
+            point.positions_value.to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Write the exposure, leverage and turnover at each equity mark to CSV
+pub fn write_exposure_csv(exposure_history: &[ExposurePoint], output_path: &Path) -> Result<()> {
+    let mut wtr = csv::Writer::from_writer(File::create(output_path)?);
+
+    wtr.write_record([
+        "timestamp",
+        "gross_exposure",
+        "net_exposure",
+        "leverage",
+        "traded_notional",
+        "turnover",
+    ])?;
+
+    for point in exposure_history {
+        wtr.write_record(&[
+            point.timestamp.to_string(),
+            point.gross_exposure.to_string(),
+            point.net_exposure.to_string(),
+            point.leverage.to_string(),
+            point.traded_notional.to_string(),
+            point.turnover.to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Write round-trip trades to CSV, with their holding periods in seconds
+pub fn write_round_trips_csv(round_trips: &[RoundTrip], output_path: &Path) -> Result<()> {
+    let mut wtr = csv::Writer::from_writer(File::create(output_path)?);
+
+    wtr.write_record([
+        "symbol",
+        "side",
+        "entry_time",
+        "exit_time",
+        "holding_period",
+        "quantity",
+        "entry_price",
+        "exit_price",
+        "pnl",
+        "return",
+        "mae",
+        "mfe",
+    ])?;
+
+    for trip in round_trips {
+        wtr.write_record(&[
+            trip.symbol.clone(),
+            format!("{:?}", trip.side),
+            trip.entry_time.to_string(),
+            trip.exit_time.to_string(),
+            trip.holding_period().to_string(),
+            trip.quantity.to_string(),
+            trip.entry_price.to_string(),
+            trip.exit_price.to_string(),
+            trip.pnl.to_string(),
+            trip.trade_return.to_string(),
+            trip.mae.to_string(),
+            trip.mfe.to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Read round trips written by `write_round_trips_csv`
+pub fn read_round_trips_csv(input_path: &Path) -> Result<Vec<RoundTrip>> {
+    let mut rdr = csv::Reader::from_path(input_path)
+        .with_context(|| format!("Failed to open round trips file {:?}", input_path))?;
+
+    let mut round_trips = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        if record.len() != 12 {
+            anyhow::bail!(
+                "Expected 12 columns in round trips file, found {}",
+                record.len()
+            );
+        }
+        round_trips.push(RoundTrip {
+            symbol: record[0].to_string(),
+            side: parse_side(&record[1])?,
+            entry_time: record[2].parse()?,
+            exit_time: record[3].parse()?,
+            quantity: record[5].parse()?,
+            entry_price: record[6].parse()?,
+            exit_price: record[7].parse()?,
+            pnl: record[8].parse()?,
+            trade_return: record[9].parse()?,
+            mae: record[10].parse()?,
+            mfe: record[11].parse()?,
+        });
+    }
+    Ok(round_trips)
+}
+
+/// Write closed tax lots to CSV, with their holding periods in seconds
+pub fn write_tax_lots_csv(realizations: &[LotRealization], output_path: &Path) -> Result<()> {
+    let mut wtr = csv::Writer::from_writer(File::create(output_path)?);
+
+    wtr.write_record([
+        "lot_id",
+        "symbol",
+        "side",
+        "quantity",
+        "opened_at",
+        "closed_at",
+        "holding_period",
+        "entry_price",
+        "exit_price",
+        "pnl",
+        "wash_sale",
+    ])?;
+
+    for lot in realizations {
+        wtr.write_record(&[
+            lot.lot_id.to_string(),
+            lot.symbol.clone(),
+            format!("{:?}", lot.side),
+            lot.quantity.to_string(),
+            lot.opened_at.to_string(),
+            lot.closed_at.to_string(),
+            lot.holding_period().to_string(),
+            lot.entry_price.to_string(),
+            lot.exit_price.to_string(),
+            lot.pnl.to_string(),
+            lot.wash_sale.to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Read closed tax lots written by `write_tax_lots_csv`
+pub fn read_tax_lots_csv(input_path: &Path) -> Result<Vec<LotRealization>> {
+    let mut rdr = csv::Reader::from_path(input_path)
+        .with_context(|| format!("Failed to open tax lots file {:?}", input_path))?;
+
+    let mut realizations = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        if record.len() != 11 {
+            anyhow::bail!(
+                "Expected 11 columns in tax lots file, found {}",
+                record.len()
+            );
+        }
+        realizations.push(LotRealization {
+            lot_id: record[0].parse()?,
+            symbol: record[1].to_string(),
+            side: parse_side(&record[2])?,
+            quantity: record[3].parse()?,
+            opened_at: record[4].parse()?,
+            closed_at: record[5].parse()?,
+            entry_price: record[7].parse()?,
+            exit_price: record[8].parse()?,
+            pnl: record[9].parse()?,
+            wash_sale: record[10].parse()?,
+        });
+    }
+    Ok(realizations)
+}
+
+fn parse_side(side: &str) -> Result<Side> {
+    match side {
+        "Buy" => Ok(Side::Buy),
+        "Sell" => Ok(Side::Sell),
+        other => anyhow::bail!("Unknown trade side '{}'", other),
+    }
+}
+
 /// Write backtest statistics to JSON
 pub fn write_stats_json(stats: &BacktestStats, output_path: &Path) -> Result<()> {
     let file = File::create(output_path)?;
@@ -52,21 +313,204 @@ pub fn write_stats_json(stats: &BacktestStats, output_path: &Path) -> Result<()>
     Ok(())
 }
 
-/// Calculate backtest statistics from equity history
+/// Write each sleeve's stats of a multi-strategy run as JSON
+pub fn write_sleeve_stats_json(sleeves: &[SleeveStats], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    serde_json::to_writer_pretty(file, sleeves)?;
+    Ok(())
+}
+
+/// Read sleeve stats written by `write_sleeve_stats_json`
+pub fn read_sleeve_stats_json(input_path: &Path) -> Result<Vec<SleeveStats>> {
+    let file = File::open(input_path)
+        .with_context(|| format!("Failed to open sleeve stats file {:?}", input_path))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// Write the trips of a run's kill switch as JSON
+pub fn write_kill_switch_json(trips: &[KillSwitchTrip], output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    serde_json::to_writer_pretty(file, trips)?;
+    Ok(())
+}
+
+/// Read kill switch trips written by `write_kill_switch_json`
+pub fn read_kill_switch_json(input_path: &Path) -> Result<Vec<KillSwitchTrip>> {
+    let file = File::open(input_path)
+        .with_context(|| format!("Failed to open kill switch file {:?}", input_path))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// Write a Monte Carlo resampling report as JSON
+pub fn write_monte_carlo_json(report: &MonteCarloReport, output_path: &Path) -> Result<()> {
+    let file = File::create(output_path)?;
+    serde_json::to_writer_pretty(file, report)?;
+    Ok(())
+}
+
+/// Write the broker's order events as JSON lines, one event per line
+pub fn write_order_events_jsonl(events: &[OrderEvent], output_path: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(output_path)?);
+    for event in events {
+        serde_json::to_writer(&mut writer, event)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read order events written by `write_order_events_jsonl`
+pub fn read_order_events_jsonl(input_path: &Path) -> Result<Vec<OrderEvent>> {
+    let file = File::open(input_path)
+        .with_context(|| format!("Failed to open order events file {:?}", input_path))?;
+    let mut events = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        events.push(
+            serde_json::from_str(&line)
+                .with_context(|| format!("Invalid order event on line {}", i + 1))?,
+        );
+    }
+    Ok(events)
+}
+
+/// Read trades written by `write_trades_csv`, or by versions of it that
+/// wrote commission as the only cost
+pub fn read_trades_csv(input_path: &Path) -> Result<Vec<Fill>> {
+    let mut rdr = csv::Reader::from_path(input_path)
+        .with_context(|| format!("Failed to open trades file {:?}", input_path))?;
+
+    let mut fills = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        if record.len() != 6 && record.len() != 10 {
+            anyhow::bail!(
+                "Expected 6 or 10 columns in trades file, found {}",
+                record.len()
+            );
+        }
+        fills.push(Fill {
+            timestamp: record[0].parse()?,
+            symbol: record[1].to_string(),
+            side: parse_side(&record[2])?,
+            quantity: record[3].parse()?,
+            price: record[4].parse()?,
+            costs: CostBreakdown {
+                commission: record[5].parse()?,
+                exchange_fees: record.get(6).map_or(Ok(0.0), str::parse)?,
+                slippage: record.get(7).map_or(Ok(0.0), str::parse)?,
+                borrow: record.get(8).map_or(Ok(0.0), str::parse)?,
+                other: record.get(9).map_or(Ok(0.0), str::parse)?,
+            },
+        });
+    }
+    Ok(fills)
+}
+
+/// Read the equity of a curve written by `write_equity_curve_csv` or
+/// `write_equity_points_csv`
+pub fn read_equity_curve_csv(input_path: &Path) -> Result<Vec<(i64, f64)>> {
+    let mut rdr = csv::Reader::from_path(input_path)
+        .with_context(|| format!("Failed to open equity curve file {:?}", input_path))?;
+
+    let mut equity_history = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        if record.len() != 2 && record.len() != 4 {
+            anyhow::bail!(
+                "Expected 2 or 4 columns in equity curve file, found {}",
+                record.len()
+            );
+        }
+        equity_history.push((record[0].parse()?, record[1].parse()?));
+    }
+    Ok(equity_history)
+}
+
+/// Read an equity curve written by `write_equity_points_csv`
+pub fn read_equity_points_csv(input_path: &Path) -> Result<Vec<EquityPoint>> {
+    let mut rdr = csv::Reader::from_path(input_path)
+        .with_context(|| format!("Failed to open equity curve file {:?}", input_path))?;
+
+    let mut equity_points = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        if record.len() != 4 {
+            anyhow::bail!(
+                "Expected 4 columns in equity curve file, found {}",
+                record.len()
+            );
+        }
+        equity_points.push(EquityPoint {
+            timestamp: record[0].parse()?,
+            equity: record[1].parse()?,
+            cash: record[2].parse()?,
+            positions_value: record[3].parse()?,
+        });
+    }
+    Ok(equity_points)
+}
+
+/// Read an exposure series written by `write_exposure_csv`
+pub fn read_exposure_csv(input_path: &Path) -> Result<Vec<ExposurePoint>> {
+    let mut rdr = csv::Reader::from_path(input_path)
+        .with_context(|| format!("Failed to open exposure file {:?}", input_path))?;
+
+    let mut exposure_history = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        if record.len() != 6 {
+            anyhow::bail!(
+                "Expected 6 columns in exposure file, found {}",
+                record.len()
+            );
+        }
+        exposure_history.push(ExposurePoint {
+            timestamp: record[0].parse()?,
+            gross_exposure: record[1].parse()?,
+            net_exposure: record[2].parse()?,
+            leverage: record[3].parse()?,
+            traded_notional: record[4].parse()?,
+            turnover: record[5].parse()?,
+        });
+    }
+    Ok(exposure_history)
+}
+
+/// Read backtest statistics written by `write_stats_json`
+pub fn read_stats_json(input_path: &Path) -> Result<BacktestStats> {
+    let file = File::open(input_path)
+        .with_context(|| format!("Failed to open stats file {:?}", input_path))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// Calculate backtest statistics from equity history and fills, annualizing
+/// ratios over bars of `annualization`'s frequency
 pub fn calculate_stats(
     equity_history: &[(i64, f64)],
-    num_trades: usize,
-    total_commission: f64,
+    fills: &[Fill],
+    costs: CostBreakdown,
+    annualization: Annualization,
 ) -> BacktestStats {
+    let num_trades = fills.len();
     if equity_history.is_empty() {
         return BacktestStats {
             initial_equity: 0.0,
             final_equity: 0.0,
             total_return: 0.0,
             num_trades,
-            total_commission,
+            total_commission: costs.commission,
+            costs,
             sharpe_ratio: 0.0,
             max_drawdown: 0.0,
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
         };
     }
 
@@ -80,31 +524,37 @@ pub fn calculate_stats(
             final_equity,
             total_return: 0.0,
             num_trades,
-            total_commission,
+            total_commission: costs.commission,
+            costs,
             sharpe_ratio: 0.0,
             max_drawdown: 0.0,
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
         };
     }
 
     let total_return = (final_equity - initial_equity) / initial_equity;
+    let returns = bar_returns(equity_history);
 
-    // Calculate returns for Sharpe ratio
-    let mut returns = Vec::new();
-    for i in 1..equity_history.len() {
-        let prev_equity = equity_history[i - 1].1;
-        let curr_equity = equity_history[i].1;
-        if prev_equity > 0.0 {
-            returns.push((curr_equity - prev_equity) / prev_equity);
-        }
-    }
-
+    let mut performance = PerformanceStats::default();
+    let periods_per_year = annualization.periods_per_year;
     let sharpe_ratio = if returns.len() > 1 {
-        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
-        let variance =
-            returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64;
-        let std_dev = variance.sqrt();
+        // Ratios are of returns in excess of the risk-free rate
+        let risk_free = annualization.risk_free_per_period();
+        let n = returns.len() as f64;
+        let (mean, std_dev) = mean_and_std_dev(&returns);
+        let excess = mean - risk_free;
+        let downside_deviation =
+            (neumaier_sum(returns.iter().map(|r| (r - risk_free).min(0.0).powi(2))) / n).sqrt();
+        performance.annualized_volatility = std_dev * periods_per_year.sqrt();
+        if downside_deviation > 0.0 {
+            performance.sortino_ratio = excess / downside_deviation * periods_per_year.sqrt();
+        }
         if std_dev > 0.0 {
-            mean / std_dev * (252.0_f64).sqrt() // Annualized Sharpe
+            excess / std_dev * periods_per_year.sqrt() // Annualized Sharpe
         } else {
             0.0
         }
@@ -112,8 +562,70 @@ pub fn calculate_stats(
         0.0
     };
 
-    // Calculate max drawdown
-    let mut max_equity = initial_equity;
+    let max_drawdown = max_drawdown(equity_history);
+
+    // Marks share a timestamp across symbols and fills, so years count
+    // distinct timestamps rather than marks
+    let periods = equity_history
+        .windows(2)
+        .filter(|w| w[1].0 != w[0].0)
+        .count();
+    if max_drawdown > 0.0 && periods > 0 {
+        let years = periods as f64 / periods_per_year;
+        let growth = (final_equity / initial_equity).max(0.0);
+        performance.calmar_ratio = (growth.powf(1.0 / years) - 1.0) / max_drawdown;
+    }
+    let pnls: Vec<f64> = pair_fills(fills).iter().map(|trip| trip.pnl).collect();
+    if !pnls.is_empty() {
+        let gross_profit = neumaier_sum(pnls.iter().map(|pnl| pnl.max(0.0)));
+        let gross_loss = -neumaier_sum(pnls.iter().map(|pnl| pnl.min(0.0)));
+        performance.closed_trades = pnls.len();
+        performance.win_rate =
+            pnls.iter().filter(|&&pnl| pnl > 0.0).count() as f64 / pnls.len() as f64;
+        if gross_loss > 0.0 {
+            performance.profit_factor = gross_profit / gross_loss;
+        }
+        performance.average_trade_pnl = neumaier_sum(pnls.iter().copied()) / pnls.len() as f64;
+    }
+    performance.exposure_time = exposure_time(equity_history, fills);
+
+    BacktestStats {
+        initial_equity,
+        final_equity,
+        total_return,
+        num_trades,
+        total_commission: costs.commission,
+        costs,
+        sharpe_ratio,
+        max_drawdown,
+        performance,
+        financing: None,
+        benchmark: None,
+        constrained_orders: None,
+        bootstrap: None,
+    }
+}
+
+/// Return of each bar over the one before it, skipping bars after no equity
+pub(crate) fn bar_returns(equity_history: &[(i64, f64)]) -> Vec<f64> {
+    equity_history
+        .windows(2)
+        .filter(|w| w[0].1 > 0.0)
+        .map(|w| (w[1].1 - w[0].1) / w[0].1)
+        .collect()
+}
+
+/// Population mean and standard deviation
+pub(crate) fn mean_and_std_dev(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = neumaier_sum(values.iter().copied()) / n;
+    let variance = neumaier_sum(values.iter().map(|v| (v - mean).powi(2))) / n;
+    (mean, variance.sqrt())
+}
+
+/// Largest fall from a running peak, as a fraction of that peak
+fn max_drawdown(equity_history: &[(i64, f64)]) -> f64 {
+    let mut max_equity = equity_history.first().map_or(0.0, |(_, e)| *e);
     let mut max_drawdown = 0.0;
     for (_, equity) in equity_history {
         if *equity > max_equity {
@@ -127,27 +639,122 @@ pub fn calculate_stats(
             }
         }
     }
+    max_drawdown
+}
 
-    BacktestStats {
-        initial_equity,
-        final_equity,
-        total_return,
-        num_trades,
-        total_commission,
-        sharpe_ratio,
-        max_drawdown,
+/// Risk measures over the `window` bars ending at one equity mark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RollingMetrics {
+    pub timestamp: i64,
+    /// Annualized Sharpe ratio of the window's returns
+    pub sharpe_ratio: f64,
+    /// Max drawdown within the window
+    pub max_drawdown: f64,
+    /// Annualized standard deviation of the window's returns
+    pub volatility: f64,
+}
+
+/// Rolling Sharpe, drawdown and volatility at each mark with `window` bar
+/// returns behind it, annualized as `calculate_stats` does
+pub fn rolling_metrics(
+    equity_history: &[(i64, f64)],
+    window: usize,
+    annualization: Annualization,
+) -> Vec<RollingMetrics> {
+    let risk_free = annualization.risk_free_per_period();
+    let scale = annualization.periods_per_year.sqrt();
+    equity_history
+        .windows(window + 1)
+        .map(|marks| {
+            let returns = bar_returns(marks);
+            let (mean, std_dev) = if returns.is_empty() {
+                (0.0, 0.0)
+            } else {
+                mean_and_std_dev(&returns)
+            };
+            RollingMetrics {
+                timestamp: marks[window].0,
+                sharpe_ratio: if std_dev > 0.0 {
+                    (mean - risk_free) / std_dev * scale
+                } else {
+                    0.0
+                },
+                max_drawdown: max_drawdown(marks),
+                volatility: std_dev * scale,
+            }
+        })
+        .collect()
+}
+
+/// Write rolling metrics to CSV
+pub fn write_rolling_metrics_csv(metrics: &[RollingMetrics], output_path: &Path) -> Result<()> {
+    let mut wtr = csv::Writer::from_writer(File::create(output_path)?);
+
+    wtr.write_record(["timestamp", "sharpe_ratio", "max_drawdown", "volatility"])?;
+
+    for point in metrics {
+        wtr.write_record(&[
+            point.timestamp.to_string(),
+            point.sharpe_ratio.to_string(),
+            point.max_drawdown.to_string(),
+            point.volatility.to_string(),
+        ])?;
     }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Share of `equity_history`'s bars ending with any position open
+fn exposure_time(equity_history: &[(i64, f64)], fills: &[Fill]) -> f64 {
+    let mut positions: HashMap<&str, f64> = HashMap::new();
+    let mut pending = fills.iter().peekable();
+    let exposed = equity_history
+        .iter()
+        .filter(|(timestamp, _)| {
+            while let Some(fill) = pending.next_if(|fill| fill.timestamp <= *timestamp) {
+                let position = positions.entry(fill.symbol.as_str()).or_default();
+                match fill.side {
+                    Side::Buy => *position += fill.quantity,
+                    Side::Sell => *position -= fill.quantity,
+                }
+            }
+            positions.values().any(|position| position.abs() >= FLAT)
+        })
+        .count();
+    exposed as f64 / equity_history.len() as f64
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use schema::{OrderEventKind, RejectionReason};
+
+    fn fill(timestamp: i64, side: Side, quantity: f64, price: f64, commission: f64) -> Fill {
+        Fill {
+            timestamp,
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            price,
+            costs: CostBreakdown::from_commission(commission),
+        }
+    }
 
     #[test]
     fn test_calculate_stats_simple() {
         let equity_history = vec![(0, 10000.0), (1, 10500.0), (2, 11000.0)];
+        let fills = [
+            fill(0, Side::Buy, 10.0, 100.0, 5.0),
+            fill(2, Side::Sell, 10.0, 200.0, 5.0),
+        ];
 
-        let stats = calculate_stats(&equity_history, 2, 10.0);
+        let stats = calculate_stats(
+            &equity_history,
+            &fills,
+            CostBreakdown::from_commission(10.0),
+            Annualization::default(),
+        );
 
         assert_eq!(stats.initial_equity, 10000.0);
         assert_eq!(stats.final_equity, 11000.0);
@@ -156,6 +763,136 @@ mod tests {
         assert_eq!(stats.total_commission, 10.0);
     }
 
+    #[test]
+    fn test_outputs_round_trip() {
+        let dir = std::env::temp_dir().join(format!("engine_output_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let fills = vec![Fill {
+            timestamp: 1,
+            symbol: "AAPL".to_string(),
+            side: Side::Sell,
+            quantity: 10.0,
+            price: 101.5,
+            costs: CostBreakdown {
+                commission: 0.25,
+                slippage: 0.5,
+                other: 0.1,
+                ..CostBreakdown::default()
+            },
+        }];
+        let equity_history = vec![(0, 10000.0), (1, 10010.5)];
+        let stats = calculate_stats(&equity_history, &fills, fills[0].costs, Default::default());
+        let event = |kind| OrderEvent {
+            timestamp: 1,
+            order_id: 3,
+            symbol: "AAPL".to_string(),
+            kind,
+        };
+        let events = vec![
+            event(OrderEventKind::Accepted),
+            event(OrderEventKind::Filled {
+                quantity: 10.0,
+                price: 101.5,
+            }),
+            event(OrderEventKind::Rejected(RejectionReason::LocateFailed {
+                hard_to_borrow: true,
+            })),
+        ];
+
+        write_trades_csv(&fills, &dir.join("trades.csv")).unwrap();
+        write_equity_curve_csv(&equity_history, &dir.join("equity_curve.csv")).unwrap();
+        write_stats_json(&stats, &dir.join("stats.json")).unwrap();
+        write_order_events_jsonl(&events, &dir.join("order_events.jsonl")).unwrap();
+        // Positions with no equity left have unbounded leverage
+        let exposure = vec![
+            ExposurePoint::new(0, 0.0, 0.0, 0.0, 10000.0),
+            ExposurePoint::new(1, 1015.0, -1015.0, 1015.0, 0.0),
+        ];
+        write_exposure_csv(&exposure, &dir.join("exposure.csv")).unwrap();
+        let covered = Fill {
+            timestamp: 2,
+            side: Side::Buy,
+            ..fills[0].clone()
+        };
+        let round_trips = pair_fills(&[fills[0].clone(), covered]);
+        write_round_trips_csv(&round_trips, &dir.join("trades-roundtrip.csv")).unwrap();
+
+        assert_eq!(read_trades_csv(&dir.join("trades.csv")).unwrap(), fills);
+        assert_eq!(
+            read_equity_curve_csv(&dir.join("equity_curve.csv")).unwrap(),
+            equity_history
+        );
+        let points = vec![
+            EquityPoint {
+                timestamp: 0,
+                equity: 10000.0,
+                cash: 10000.0,
+                positions_value: 0.0,
+            },
+            EquityPoint {
+                timestamp: 1,
+                equity: 10010.5,
+                cash: 11025.5,
+                positions_value: -1015.0,
+            },
+        ];
+        write_equity_points_csv(&points, &dir.join("equity_points.csv")).unwrap();
+        assert_eq!(
+            read_equity_points_csv(&dir.join("equity_points.csv")).unwrap(),
+            points
+        );
+        // The equity column alone reads back like a plain equity curve
+        assert_eq!(
+            read_equity_curve_csv(&dir.join("equity_points.csv")).unwrap(),
+            equity_history
+        );
+        assert!(read_equity_points_csv(&dir.join("equity_curve.csv")).is_err());
+        let read_exposure = read_exposure_csv(&dir.join("exposure.csv")).unwrap();
+        assert_eq!(read_exposure, exposure);
+        assert_eq!(read_exposure[1].leverage, f64::INFINITY);
+        assert_eq!(
+            read_round_trips_csv(&dir.join("trades-roundtrip.csv")).unwrap(),
+            round_trips
+        );
+        let read_stats = read_stats_json(&dir.join("stats.json")).unwrap();
+        assert_eq!(read_stats.final_equity, stats.final_equity);
+        assert_eq!(read_stats.costs, fills[0].costs);
+        let written = std::fs::read_to_string(dir.join("order_events.jsonl")).unwrap();
+        assert!(written
+            .lines()
+            .nth(2)
+            .unwrap()
+            .contains(r#""event":"rejected","reason":"locate_failed""#));
+        assert_eq!(
+            read_order_events_jsonl(&dir.join("order_events.jsonl")).unwrap(),
+            events
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn drawdown_matches_brute_force(
+            equity in proptest::collection::vec(1.0f64..1e9, 1..200)
+        ) {
+            let history: Vec<(i64, f64)> =
+                equity.iter().enumerate().map(|(t, e)| (t as i64, *e)).collect();
+            let stats = calculate_stats(&history, &[], CostBreakdown::default(), Default::default());
+
+            let mut worst = 0.0f64;
+            for (i, peak) in equity.iter().enumerate() {
+                for later in &equity[i..] {
+                    worst = worst.max((peak - later) / peak);
+                }
+            }
+            proptest::prop_assert!((0.0..1.0).contains(&stats.max_drawdown));
+            proptest::prop_assert!((stats.max_drawdown - worst).abs() < 1e-12);
+            proptest::prop_assert!(stats.sharpe_ratio.is_finite());
+        }
+    }
+
     #[test]
     fn test_calculate_stats_with_drawdown() {
         let equity_history = vec![
@@ -165,8 +902,127 @@ mod tests {
             (3, 11000.0),
         ];
 
-        let stats = calculate_stats(&equity_history, 3, 10.0);
+        let stats = calculate_stats(
+            &equity_history,
+            &[],
+            CostBreakdown::from_commission(10.0),
+            Default::default(),
+        );
 
         assert!((stats.max_drawdown - 0.25).abs() < 1e-6); // 25% drawdown
     }
+
+    #[test]
+    fn round_trips_and_returns_give_the_extended_stats() {
+        let equity_history = vec![
+            (0, 10000.0),
+            (1, 11000.0),
+            (2, 9900.0),
+            (3, 10890.0),
+            (4, 10890.0),
+        ];
+        // A winning long, then a losing short entered by flipping the long
+        let fills = [
+            fill(1, Side::Buy, 10.0, 100.0, 1.0),
+            fill(2, Side::Sell, 20.0, 110.0, 2.0),
+            fill(3, Side::Buy, 10.0, 115.0, 1.0),
+        ];
+        let stats = calculate_stats(
+            &equity_history,
+            &fills,
+            CostBreakdown::default(),
+            Default::default(),
+        );
+        let performance = &stats.performance;
+
+        // +100 - 1 - 1 on the long; -50 - 1 - 1 on the short
+        assert_eq!(performance.closed_trades, 2);
+        assert_eq!(performance.win_rate, 0.5);
+        assert!((performance.profit_factor - 98.0 / 52.0).abs() < 1e-12);
+        assert!((performance.average_trade_pnl - 23.0).abs() < 1e-12);
+        // Open over bars 1 and 2 of five
+        assert!((performance.exposure_time - 0.4).abs() < 1e-12);
+
+        // Returns +10%, -10%, +10% and 0
+        let downside = (0.01_f64 / 4.0).sqrt();
+        let std_dev =
+            (neumaier_sum([0.1, -0.1, 0.1, 0.0].map(|r| (r - 0.025_f64).powi(2))) / 4.0).sqrt();
+        assert!((performance.sortino_ratio - 0.025 / downside * 252f64.sqrt()).abs() < 1e-9);
+        assert!((performance.annualized_volatility - std_dev * 252f64.sqrt()).abs() < 1e-9);
+        let annual_return = 1.089_f64.powf(252.0 / 4.0) - 1.0;
+        assert!((performance.calmar_ratio - annual_return / 0.1).abs() < 1e-6 * annual_return);
+    }
+
+    #[test]
+    fn calmar_counts_years_in_bars_not_marks() {
+        // Two symbols mark equity at each timestamp
+        let equity_history = [
+            (1, 10000.0),
+            (1, 10000.0),
+            (2, 11000.0),
+            (2, 11000.0),
+            (3, 9900.0),
+            (3, 9900.0),
+            (4, 10890.0),
+            (4, 10890.0),
+        ];
+        let stats = calculate_stats(
+            &equity_history,
+            &[],
+            CostBreakdown::default(),
+            Default::default(),
+        );
+        let annual_return = 1.089_f64.powf(252.0 / 3.0) - 1.0;
+        let calmar = stats.performance.calmar_ratio;
+        assert!((calmar - annual_return / 0.1).abs() < 1e-6 * annual_return);
+    }
+
+    #[test]
+    fn hourly_bars_annualize_over_a_calendar_year_against_the_risk_free_rate() {
+        let equity_history = vec![(0, 100.0), (3600, 101.0), (7200, 100.5), (10800, 102.0)];
+        let hourly = Annualization::from_bar_interval(3600);
+        assert_eq!(hourly.periods_per_year, 8760.0);
+
+        let daily = calculate_stats(
+            &equity_history,
+            &[],
+            CostBreakdown::default(),
+            Default::default(),
+        );
+        let stats = calculate_stats(&equity_history, &[], CostBreakdown::default(), hourly);
+        let scale = (8760.0_f64 / 252.0).sqrt();
+        assert!((stats.sharpe_ratio - daily.sharpe_ratio * scale).abs() < 1e-9);
+        assert!(
+            (stats.performance.annualized_volatility
+                - daily.performance.annualized_volatility * scale)
+                .abs()
+                < 1e-9
+        );
+
+        let with_rate = hourly.with_risk_free_rate(0.05);
+        assert!(((with_rate.risk_free_per_period() + 1.0).powf(8760.0) - 1.05).abs() < 1e-9);
+        let excess = calculate_stats(&equity_history, &[], CostBreakdown::default(), with_rate);
+        assert!(excess.sharpe_ratio < stats.sharpe_ratio);
+        assert_eq!(excess.total_return, stats.total_return);
+    }
+
+    #[test]
+    fn rolling_metrics_match_stats_over_each_window() {
+        let equity_history: Vec<(i64, f64)> = [100.0, 104.0, 98.0, 101.0, 110.0, 99.0]
+            .iter()
+            .enumerate()
+            .map(|(t, e)| (t as i64, *e))
+            .collect();
+        let annualization = Annualization::default().with_risk_free_rate(0.02);
+        let rolling = rolling_metrics(&equity_history, 3, annualization);
+        assert_eq!(rolling.len(), 3);
+        for (point, marks) in rolling.iter().zip(equity_history.windows(4)) {
+            let stats = calculate_stats(marks, &[], CostBreakdown::default(), annualization);
+            assert_eq!(point.timestamp, marks[3].0);
+            assert!((point.sharpe_ratio - stats.sharpe_ratio).abs() < 1e-12);
+            assert_eq!(point.max_drawdown, stats.max_drawdown);
+            assert_eq!(point.volatility, stats.performance.annualized_volatility);
+        }
+        assert!(rolling_metrics(&equity_history, 6, annualization).is_empty());
+    }
 }
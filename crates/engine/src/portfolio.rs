@@ -1,29 +1,126 @@
+use crate::currency::Currencies;
+use crate::financing::FinancingRates;
+use crate::lots::{LotBook, LotMethod, LotRealization, TaxLot};
+use crate::round_trips::{RoundTrip, RoundTripLedger};
+use crate::summation::{neumaier_sum, NeumaierSum};
 use anyhow::Result;
-use schema::{Fill, Portfolio, Side};
-use std::collections::HashMap;
-
-/// Manages portfolio state and accounting
+use broker_sim::{MarginSchedule, MarginStatus};
+use schema::{
+    BorrowCharge, CostBreakdown, EquityPoint, ExposurePoint, Fill, FinancingStats, FundingPayment,
+    Portfolio, Position, Side,
+};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+
+/// Manages portfolio state and accounting.
+///
+/// Cash, realized PnL and commission are kept as compensated sums, and
+/// position values are summed in symbol order, so totals neither drift over
+/// long runs nor depend on hash map iteration order.
 pub struct PortfolioManager {
     portfolio: Portfolio,
-    realized_pnl: f64,
-    total_commission: f64,
+    cash: NeumaierSum,
+    realized_pnl: NeumaierSum,
+    total_commission: NeumaierSum,
+    total_exchange_fees: NeumaierSum,
+    total_slippage: NeumaierSum,
+    fill_borrow_fees: NeumaierSum,
+    other_costs: NeumaierSum,
+    total_borrow_fees: NeumaierSum,
+    total_funding: NeumaierSum,
+    debit_interest: NeumaierSum,
+    short_borrow_fees: NeumaierSum,
+    credit_interest: NeumaierSum,
     equity_history: Vec<(i64, f64)>,
+    /// `equity_history` with equity split into cash and positions
+    equity_points: Vec<EquityPoint>,
+    exposure_history: Vec<ExposurePoint>,
+    /// Timestamp and notional of the fills within the turnover window
+    recent_fills: VecDeque<(i64, f64)>,
+    round_trips: RoundTripLedger,
+    lots: LotBook,
+    currencies: Option<Currencies>,
 }
 
+/// Seconds of trading turnover is measured over, a trailing year so
+/// turnover is annual
+const TURNOVER_WINDOW: i64 = 365 * 86_400;
+
 impl PortfolioManager {
     pub fn new(initial_cash: f64) -> Self {
         Self {
             portfolio: Portfolio::new(initial_cash),
-            realized_pnl: 0.0,
-            total_commission: 0.0,
+            cash: NeumaierSum::new(initial_cash),
+            realized_pnl: NeumaierSum::default(),
+            total_commission: NeumaierSum::default(),
+            total_exchange_fees: NeumaierSum::default(),
+            total_slippage: NeumaierSum::default(),
+            fill_borrow_fees: NeumaierSum::default(),
+            other_costs: NeumaierSum::default(),
+            total_borrow_fees: NeumaierSum::default(),
+            total_funding: NeumaierSum::default(),
+            debit_interest: NeumaierSum::default(),
+            short_borrow_fees: NeumaierSum::default(),
+            credit_interest: NeumaierSum::default(),
             equity_history: vec![(0, initial_cash)],
+            equity_points: vec![EquityPoint {
+                timestamp: 0,
+                equity: initial_cash,
+                cash: initial_cash,
+                positions_value: 0.0,
+            }],
+            exposure_history: vec![ExposurePoint::new(0, 0.0, 0.0, 0.0, initial_cash)],
+            recent_fills: VecDeque::new(),
+            round_trips: RoundTripLedger::new(),
+            lots: LotBook::default(),
+            currencies: None,
         }
     }
 
-    /// Apply a fill to the portfolio
+    /// Keep the books in `currencies`' base, converting fills, prices and
+    /// charges of foreign symbols at the rates in the prices passed in
+    pub fn with_currencies(mut self, currencies: Currencies) -> Self {
+        self.currencies = Some(currencies);
+        self
+    }
+
+    /// Realize PnL lot by lot in the order `method` closes them. Under
+    /// `Fifo` and `Lifo` a position's average price is that of its open lots.
+    pub fn with_lot_method(mut self, method: LotMethod) -> Self {
+        self.lots = LotBook::new(method);
+        self
+    }
+
+    /// Advance the portfolio clock to the current bar so equity marks carry its timestamp
+    pub fn advance_to(&mut self, timestamp: i64) {
+        self.portfolio.timestamp = self.portfolio.timestamp.max(timestamp);
+    }
+
+    /// Apply a fill to the portfolio. A fill that takes a position through
+    /// flat closes it and opens the rest on the other side at the fill price.
     pub fn apply_fill(&mut self, fill: &Fill, current_prices: &HashMap<String, f64>) -> Result<()> {
+        self.observe_rates(current_prices);
+        let converted;
+        let fill = match &self.currencies {
+            Some(currencies) => {
+                converted = currencies.fill_to_base(fill).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "No {}{} rate in the data before the {} fill at {}",
+                        currencies.currency(&fill.symbol),
+                        currencies.base(),
+                        fill.symbol,
+                        fill.timestamp
+                    )
+                })?;
+                &converted
+            }
+            None => fill,
+        };
+
         // Update timestamp
         self.portfolio.timestamp = fill.timestamp;
+        let lot_pnl = self.lots.apply_fill(fill);
+        let by_lot = self.lots.method() != LotMethod::AverageCost;
 
         // Get or create position
         let position = self.portfolio.get_position_mut(&fill.symbol);
@@ -60,7 +157,7 @@ impl PortfolioManager {
                     closed_quantity * (entry_price - exit_price)
                 };
 
-                self.realized_pnl += pnl;
+                self.realized_pnl.add(if by_lot { lot_pnl } else { pnl });
             }
         }
 
@@ -70,26 +167,43 @@ impl PortfolioManager {
             position.quantity = 0.0;
             position.avg_price = 0.0;
         } else {
-            // Update average price for the new quantity
-            if (old_quantity >= 0.0 && new_quantity > old_quantity)
+            if old_quantity * new_quantity < 0.0 {
+                // Flipped through flat: the new position was opened at this fill
+                position.avg_price = fill.price;
+            } else if (old_quantity >= 0.0 && new_quantity > old_quantity)
                 || (old_quantity <= 0.0 && new_quantity < old_quantity)
             {
-                // Adding to position - update average price
+                // Adding to position - update average price for the new quantity
                 let old_value = old_quantity * old_avg_price;
                 let new_value = quantity_delta * fill.price;
                 position.avg_price = (old_value + new_value) / new_quantity;
             }
             // If reducing position but not flipping, keep the same avg price
             position.quantity = new_quantity;
+            if by_lot {
+                if let Some(basis) = self.lots.cost_basis(&fill.symbol) {
+                    position.avg_price = basis;
+                }
+            }
         }
 
-        // Update cash: pay for buys, receive for sells, always pay commission
-        let cash_flow = match fill.side {
-            Side::Buy => -(fill.quantity * fill.price + fill.commission),
-            Side::Sell => fill.quantity * fill.price - fill.commission,
-        };
-        self.portfolio.cash += cash_flow;
-        self.total_commission += fill.commission;
+        // Update cash: pay for buys, receive for sells, always pay the costs
+        // charged on top of the price
+        let notional = fill.quantity * fill.price;
+        self.recent_fills
+            .push_back((fill.timestamp, notional.abs()));
+        self.cash.add(match fill.side {
+            Side::Buy => -notional,
+            Side::Sell => notional,
+        });
+        self.cash.add(-fill.costs.charged());
+        self.portfolio.cash = self.cash.value();
+        self.total_commission.add(fill.costs.commission);
+        self.total_exchange_fees.add(fill.costs.exchange_fees);
+        self.total_slippage.add(fill.costs.slippage);
+        self.fill_borrow_fees.add(fill.costs.borrow);
+        self.other_costs.add(fill.costs.other);
+        self.round_trips.apply_fill(fill);
 
         // Update equity
         self.update_equity(current_prices);
@@ -97,17 +211,87 @@ impl PortfolioManager {
         Ok(())
     }
 
-    /// Update equity based on current market prices
+    /// Pay a borrow fee on a short out of cash
+    pub fn apply_borrow_charge(&mut self, charge: &BorrowCharge) {
+        let fee = self.amount_to_base(&charge.symbol, charge.fee);
+        self.cash.add(-fee);
+        self.portfolio.cash = self.cash.value();
+        self.total_borrow_fees.add(fee);
+    }
+
+    /// Settle a perpetual's funding payment in cash
+    pub fn apply_funding_payment(&mut self, payment: &FundingPayment) {
+        let amount = self.amount_to_base(&payment.symbol, payment.amount);
+        self.cash.add(-amount);
+        self.portfolio.cash = self.cash.value();
+        self.total_funding.add(amount);
+    }
+
+    /// Accrue `days` of financing at `rates` on cash and on shorts valued
+    /// at `current_prices`
+    pub fn apply_financing(
+        &mut self,
+        rates: &FinancingRates,
+        days: f64,
+        current_prices: &HashMap<String, f64>,
+    ) {
+        self.observe_rates(current_prices);
+        let current_prices = &*self.base_prices(current_prices);
+        let short_value = -self.sum_positions(current_prices, |position, price| {
+            position.market_value(price).min(0.0)
+        });
+        let accrual = rates.accrue(self.cash.value(), short_value, days);
+        self.cash.add(accrual.net());
+        self.portfolio.cash = self.cash.value();
+        self.debit_interest.add(accrual.debit_interest);
+        self.short_borrow_fees.add(accrual.short_borrow_fees);
+        self.credit_interest.add(accrual.credit_interest);
+    }
+
+    /// Equity against the margin `schedule` requires of the positions at
+    /// `current_prices`
+    pub fn margin_status(
+        &self,
+        schedule: &MarginSchedule,
+        current_prices: &HashMap<String, f64>,
+    ) -> MarginStatus {
+        schedule.status(&self.portfolio, &self.base_prices(current_prices))
+    }
+
+    /// Update equity based on current market prices, marking exposure,
+    /// turnover and open round trips alongside it
     pub fn update_equity(&mut self, current_prices: &HashMap<String, f64>) {
-        let mut positions_value = 0.0;
-        for position in self.portfolio.positions.values() {
-            if let Some(&price) = current_prices.get(&position.symbol) {
-                positions_value += position.market_value(price);
-            }
+        self.observe_rates(current_prices);
+        let current_prices = &*self.base_prices(current_prices);
+        let timestamp = self.portfolio.timestamp;
+        self.round_trips.mark(current_prices);
+        let positions_value = self.sum_positions(current_prices, Position::market_value);
+        self.portfolio.equity = neumaier_sum([self.portfolio.cash, positions_value]);
+        self.equity_history.push((timestamp, self.portfolio.equity));
+        self.equity_points.push(EquityPoint {
+            timestamp,
+            equity: self.portfolio.equity,
+            cash: self.portfolio.cash,
+            positions_value,
+        });
+
+        while self
+            .recent_fills
+            .front()
+            .is_some_and(|&(filled, _)| filled <= timestamp - TURNOVER_WINDOW)
+        {
+            self.recent_fills.pop_front();
         }
-        self.portfolio.equity = self.portfolio.cash + positions_value;
-        self.equity_history
-            .push((self.portfolio.timestamp, self.portfolio.equity));
+        let gross_exposure = self.sum_positions(current_prices, |position, price| {
+            position.market_value(price).abs()
+        });
+        self.exposure_history.push(ExposurePoint::new(
+            timestamp,
+            gross_exposure,
+            positions_value,
+            self.traded_notional(),
+            self.portfolio.equity,
+        ));
     }
 
     pub fn portfolio(&self) -> &Portfolio {
@@ -115,25 +299,121 @@ impl PortfolioManager {
     }
 
     pub fn realized_pnl(&self) -> f64 {
-        self.realized_pnl
+        self.realized_pnl.value()
     }
 
     pub fn total_commission(&self) -> f64 {
-        self.total_commission
+        self.total_commission.value()
+    }
+
+    /// Costs of every fill so far, by kind
+    pub fn costs(&self) -> CostBreakdown {
+        CostBreakdown {
+            commission: self.total_commission.value(),
+            exchange_fees: self.total_exchange_fees.value(),
+            slippage: self.total_slippage.value(),
+            borrow: self.fill_borrow_fees.value(),
+            other: self.other_costs.value(),
+        }
+    }
+
+    pub fn total_borrow_fees(&self) -> f64 {
+        self.total_borrow_fees.value()
+    }
+
+    /// Net funding paid on perpetuals; negative when more was received
+    pub fn total_funding(&self) -> f64 {
+        self.total_funding.value()
+    }
+
+    /// Interest and borrow fees accrued by `apply_financing`
+    pub fn financing(&self) -> FinancingStats {
+        FinancingStats {
+            debit_interest: self.debit_interest.value(),
+            short_borrow_fees: self.short_borrow_fees.value(),
+            credit_interest: self.credit_interest.value(),
+        }
     }
 
     pub fn equity_history(&self) -> &[(i64, f64)] {
         &self.equity_history
     }
 
+    /// Cash and position value behind every equity mark
+    pub fn equity_points(&self) -> &[EquityPoint] {
+        &self.equity_points
+    }
+
+    /// Exposure at every equity mark
+    pub fn exposure_history(&self) -> &[ExposurePoint] {
+        &self.exposure_history
+    }
+
+    /// Notional of the fills within the turnover window
+    pub fn traded_notional(&self) -> f64 {
+        neumaier_sum(self.recent_fills.iter().map(|&(_, notional)| notional))
+    }
+
+    /// Open lots of `symbol`, oldest first
+    pub fn lots(&self, symbol: &str) -> impl Iterator<Item = &TaxLot> {
+        self.lots.lots(symbol)
+    }
+
+    /// Every lot closed so far, in closing order
+    pub fn lot_realizations(&self) -> &[LotRealization] {
+        self.lots.realizations()
+    }
+
+    /// Round trips closed so far
+    pub fn round_trips(&self) -> &[RoundTrip] {
+        self.round_trips.round_trips()
+    }
+
     pub fn unrealized_pnl(&self, current_prices: &HashMap<String, f64>) -> f64 {
-        let mut unrealized = 0.0;
-        for position in self.portfolio.positions.values() {
-            if let Some(&price) = current_prices.get(&position.symbol) {
-                unrealized += position.unrealized_pnl(price);
-            }
+        self.sum_positions(&self.base_prices(current_prices), Position::unrealized_pnl)
+    }
+
+    fn observe_rates(&mut self, prices: &HashMap<String, f64>) {
+        if let Some(currencies) = &mut self.currencies {
+            currencies.observe(prices);
+        }
+    }
+
+    /// `prices` in the base currency, at the latest rates observed
+    pub(crate) fn base_prices<'a>(
+        &self,
+        prices: &'a HashMap<String, f64>,
+    ) -> Cow<'a, HashMap<String, f64>> {
+        match &self.currencies {
+            Some(currencies) => Cow::Owned(currencies.prices_to_base(prices)),
+            None => Cow::Borrowed(prices),
         }
-        unrealized
+    }
+
+    fn amount_to_base(&self, symbol: &str, amount: f64) -> f64 {
+        match &self.currencies {
+            Some(currencies) => currencies.amount_to_base(symbol, amount),
+            None => amount,
+        }
+    }
+
+    /// Compensated sum of `value` over priced positions, in symbol order
+    fn sum_positions(
+        &self,
+        current_prices: &HashMap<String, f64>,
+        value: fn(&Position, f64) -> f64,
+    ) -> f64 {
+        let mut values: Vec<(&str, f64)> = self
+            .portfolio
+            .positions
+            .values()
+            .filter_map(|position| {
+                let price = *current_prices.get(&position.symbol)?;
+                Some((position.symbol.as_str(), value(position, price)))
+            })
+            .collect();
+        values.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        neumaier_sum(values.into_iter().map(|(_, value)| value))
     }
 }
 
@@ -154,7 +434,7 @@ mod tests {
             side: Side::Buy,
             quantity: 10.0,
             price: 100.0,
-            commission: 5.0,
+            costs: CostBreakdown::from_commission(5.0),
         };
 
         pm.apply_fill(&fill, &prices).unwrap();
@@ -183,7 +463,7 @@ mod tests {
             side: Side::Buy,
             quantity: 10.0,
             price: 100.0,
-            commission: 5.0,
+            costs: CostBreakdown::from_commission(5.0),
         };
         pm.apply_fill(&buy_fill, &prices).unwrap();
 
@@ -195,7 +475,7 @@ mod tests {
             side: Side::Sell,
             quantity: 10.0,
             price: 110.0,
-            commission: 5.0,
+            costs: CostBreakdown::from_commission(5.0),
         };
         pm.apply_fill(&sell_fill, &prices).unwrap();
 
@@ -230,7 +510,7 @@ mod tests {
             side: Side::Buy,
             quantity: 10.0,
             price: 100.0,
-            commission: 5.0,
+            costs: CostBreakdown::from_commission(5.0),
         };
         pm.apply_fill(&buy_fill, &prices).unwrap();
 
@@ -246,6 +526,63 @@ mod tests {
         // Equity should reflect unrealized gain
         let expected_equity = cash + 10.0 * 110.0;
         assert!((pm.portfolio().equity - expected_equity).abs() < 0.01);
+
+        // Every mark splits into the cash and position value behind it
+        let last = *pm.equity_points().last().unwrap();
+        assert_eq!((last.cash, last.positions_value), (cash, 1100.0));
+        assert_eq!(pm.equity_points().len(), pm.equity_history().len());
+        for (point, &(timestamp, equity)) in pm.equity_points().iter().zip(pm.equity_history()) {
+            assert_eq!((point.timestamp, point.equity), (timestamp, equity));
+            assert!((point.cash + point.positions_value - equity).abs() < 1e-9);
+        }
+    }
+
+    proptest::proptest! {
+        /// Random fills in whole cents: cash matches exact integer accounting,
+        /// and equity change equals realized plus unrealized PnL less commission
+        #[test]
+        fn accounting_identities_hold_for_random_fills(
+            trades in proptest::collection::vec(
+                (0usize..3, proptest::bool::ANY, 1u32..500, 1u32..100_000, 0u32..500),
+                1..200,
+            )
+        ) {
+            let symbols = ["AAA", "BBB", "CCC"];
+            let initial_cents: i128 = 100_000_000;
+            let mut pm = PortfolioManager::new(initial_cents as f64 / 100.0);
+            let mut prices = HashMap::new();
+            let mut cash_cents = initial_cents;
+            let mut traded = 0.0;
+            for (t, (symbol, buy, quantity, price_cents, commission_cents)) in
+                trades.into_iter().enumerate()
+            {
+                let price = price_cents as f64 / 100.0;
+                prices.insert(symbols[symbol].to_string(), price);
+                let notional = i128::from(quantity) * i128::from(price_cents);
+                cash_cents += if buy { -notional } else { notional };
+                cash_cents -= i128::from(commission_cents);
+                traded += notional as f64 / 100.0;
+                pm.apply_fill(
+                    &Fill {
+                        timestamp: t as i64,
+                        symbol: symbols[symbol].to_string(),
+                        side: if buy { Side::Buy } else { Side::Sell },
+                        quantity: f64::from(quantity),
+                        price,
+                        costs: CostBreakdown::from_commission(commission_cents as f64 / 100.0),
+                    },
+                    &prices,
+                )
+                .unwrap();
+            }
+
+            let tolerance = 1e-9 * traded + 1e-6;
+            let portfolio = pm.portfolio();
+            proptest::prop_assert!((portfolio.cash - cash_cents as f64 / 100.0).abs() < tolerance);
+            let pnl = pm.realized_pnl() + pm.unrealized_pnl(&prices) - pm.total_commission();
+            let change = portfolio.equity - initial_cents as f64 / 100.0;
+            proptest::prop_assert!((pnl - change).abs() < tolerance);
+        }
     }
 
     #[test]
@@ -261,7 +598,7 @@ mod tests {
             side: Side::Buy,
             quantity: 10.0,
             price: 100.0,
-            commission: 5.0,
+            costs: CostBreakdown::from_commission(5.0),
         };
         pm.apply_fill(&buy_fill, &prices).unwrap();
 
@@ -273,7 +610,7 @@ mod tests {
             side: Side::Sell,
             quantity: 5.0,
             price: 110.0,
-            commission: 5.0,
+            costs: CostBreakdown::from_commission(5.0),
         };
         pm.apply_fill(&sell_fill, &prices).unwrap();
 
@@ -285,4 +622,122 @@ mod tests {
         assert_eq!(position.quantity, 5.0);
         assert_eq!(position.avg_price, 100.0); // Average price unchanged
     }
+
+    #[test]
+    fn test_flip_through_flat_opens_at_the_fill_price() {
+        let mut pm = PortfolioManager::new(10000.0);
+        let mut prices = HashMap::from([("AAPL".to_string(), 100.0)]);
+        let fill = |timestamp, side, quantity, price| Fill {
+            timestamp,
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            price,
+            costs: CostBreakdown::default(),
+        };
+        pm.apply_fill(&fill(1000, Side::Buy, 10.0, 100.0), &prices)
+            .unwrap();
+
+        // Selling 15 at $110 closes the long for $100 and opens a short of 5
+        prices.insert("AAPL".to_string(), 110.0);
+        pm.apply_fill(&fill(2000, Side::Sell, 15.0, 110.0), &prices)
+            .unwrap();
+        assert_eq!(pm.realized_pnl(), 100.0);
+        let position = pm.portfolio().get_position("AAPL").unwrap();
+        assert_eq!((position.quantity, position.avg_price), (-5.0, 110.0));
+
+        // The short gains from $110, not from the long's $100
+        prices.insert("AAPL".to_string(), 105.0);
+        assert_eq!(pm.unrealized_pnl(&prices), 25.0);
+    }
+
+    #[test]
+    fn exposure_nets_shorts_and_turnover_rolls_off() {
+        let mut pm = PortfolioManager::new(10000.0);
+        let prices = HashMap::from([("AAPL".to_string(), 100.0), ("MSFT".to_string(), 50.0)]);
+        for (symbol, side, quantity) in [("AAPL", Side::Buy, 10.0), ("MSFT", Side::Sell, 20.0)] {
+            let fill = Fill {
+                timestamp: 1000,
+                symbol: symbol.to_string(),
+                side,
+                quantity,
+                price: prices[symbol],
+                costs: CostBreakdown::default(),
+            };
+            pm.apply_fill(&fill, &prices).unwrap();
+        }
+
+        let point = *pm.exposure_history().last().unwrap();
+        assert_eq!(pm.exposure_history().len(), pm.equity_history().len());
+        assert_eq!(
+            point,
+            ExposurePoint::new(1000, 2000.0, 0.0, 2000.0, 10000.0)
+        );
+        assert!((point.leverage - 0.2).abs() < 1e-12);
+
+        // A year on the fills have left the turnover window
+        pm.advance_to(1000 + TURNOVER_WINDOW);
+        pm.update_equity(&prices);
+        let point = pm.exposure_history().last().unwrap();
+        assert_eq!((point.gross_exposure, point.turnover), (2000.0, 0.0));
+    }
+
+    #[test]
+    fn fifo_lots_realize_the_oldest_cost_and_keep_equity_whole() {
+        let mut pm = PortfolioManager::new(10000.0).with_lot_method(LotMethod::Fifo);
+        let mut prices = HashMap::new();
+        for (side, quantity, price) in [
+            (Side::Buy, 10.0, 100.0),
+            (Side::Buy, 10.0, 120.0),
+            (Side::Sell, 15.0, 110.0),
+        ] {
+            prices.insert("AAPL".to_string(), price);
+            let fill = Fill {
+                timestamp: 1000,
+                symbol: "AAPL".to_string(),
+                side,
+                quantity,
+                price,
+                costs: CostBreakdown::from_commission(1.0),
+            };
+            pm.apply_fill(&fill, &prices).unwrap();
+        }
+
+        // +$100 on the $100 lot, -$50 on half the $120 one
+        assert_eq!(pm.realized_pnl(), 50.0);
+        assert_eq!(pm.lot_realizations().len(), 2);
+        let position = pm.portfolio().get_position("AAPL").unwrap();
+        assert_eq!((position.quantity, position.avg_price), (5.0, 120.0));
+        assert_eq!(pm.lots("AAPL").count(), 1);
+        let pnl = pm.realized_pnl() + pm.unrealized_pnl(&prices) - pm.total_commission();
+        assert!((pm.portfolio().equity - 10000.0 - pnl).abs() < 1e-9);
+    }
+
+    #[test]
+    fn foreign_positions_are_valued_at_the_latest_rate() {
+        let currencies = Currencies::new("USD").with_symbol("SAP", "EUR");
+        let mut pm = PortfolioManager::new(10000.0).with_currencies(currencies);
+        let fill = Fill {
+            timestamp: 1000,
+            symbol: "SAP".to_string(),
+            side: Side::Buy,
+            quantity: 10.0,
+            price: 100.0,
+            costs: CostBreakdown::from_commission(1.0),
+        };
+        let mut prices = HashMap::from([("SAP".to_string(), 100.0)]);
+        let err = pm.apply_fill(&fill, &prices).unwrap_err();
+        assert!(err.to_string().contains("No EURUSD rate"));
+
+        prices.insert("EURUSD".to_string(), 1.2);
+        pm.apply_fill(&fill, &prices).unwrap();
+        assert!((pm.portfolio().cash - (10000.0 - 1200.0 - 1.2)).abs() < 1e-9);
+        assert!((pm.total_commission() - 1.2).abs() < 1e-12);
+
+        // The euro rallies with SAP unchanged in euros
+        prices.insert("EURUSD".to_string(), 1.5);
+        pm.update_equity(&prices);
+        assert!((pm.portfolio().equity - (10000.0 - 1.2 + 300.0)).abs() < 1e-9);
+        assert!((pm.unrealized_pnl(&prices) - 300.0).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,108 @@
+//! Progress of a run through its feed, for long backtests.
+
+use std::time::Duration;
+
+/// How far a run has read through its feed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    /// Bars read from the feed so far, warm-up included
+    pub bars_processed: usize,
+    /// Bars in the whole feed, when it knows
+    pub total_bars: Option<usize>,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Share of the feed read so far, when its length is known
+    pub fn fraction(&self) -> Option<f64> {
+        self.total_bars
+            .filter(|&total| total > 0)
+            .map(|total| (self.bars_processed as f64 / total as f64).min(1.0))
+    }
+
+    /// Time left at the rate so far, once any bars have been read
+    pub fn eta(&self) -> Option<Duration> {
+        let total = self.total_bars?;
+        if self.bars_processed == 0 {
+            return None;
+        }
+        let remaining = total.saturating_sub(self.bars_processed);
+        Some(
+            self.elapsed
+                .mul_f64(remaining as f64 / self.bars_processed as f64),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::BacktestEngine;
+    use crate::data_feed::VecDataFeed;
+    use broker_sim::SimpleBroker;
+    use cost::ZeroCost;
+    use schema::{Bar, Order, Portfolio, Strategy};
+    use std::sync::{Arc, Mutex};
+
+    /// Trades nothing
+    struct Idle;
+
+    impl Strategy for Idle {
+        fn on_bar(&mut self, _bar: &Bar, _portfolio: &Portfolio) -> Vec<Order> {
+            vec![]
+        }
+
+        fn name(&self) -> &str {
+            "Idle"
+        }
+    }
+
+    #[test]
+    fn eta_extrapolates_the_rate_so_far() {
+        let progress = Progress {
+            bars_processed: 250,
+            total_bars: Some(1_000),
+            elapsed: Duration::from_secs(10),
+        };
+        assert_eq!(progress.fraction(), Some(0.25));
+        assert_eq!(progress.eta(), Some(Duration::from_secs(30)));
+
+        let unknown = Progress {
+            total_bars: None,
+            ..progress
+        };
+        assert_eq!((unknown.fraction(), unknown.eta()), (None, None));
+    }
+
+    #[test]
+    fn engine_reports_every_few_bars_and_at_the_end() {
+        let bars: Vec<Bar> = (1..=5)
+            .map(|timestamp| Bar {
+                timestamp,
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 100.0,
+                low: 100.0,
+                close: 100.0,
+                volume: 10000.0,
+            })
+            .collect();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars),
+            Idle,
+            SimpleBroker::new(ZeroCost, 42),
+            1000.0,
+        )
+        .with_progress(2, move |p: &Progress| {
+            sink.lock().unwrap().push((p.bars_processed, p.total_bars))
+        });
+        engine.run().unwrap();
+
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [(2, Some(5)), (4, Some(5)), (5, Some(5))]
+        );
+    }
+}
@@ -0,0 +1,367 @@
+//! Pre-trade risk checks between a strategy's orders and the broker.
+//!
+//! Each order a strategy places on a bar goes through the engine's
+//! `RiskCheck`s in turn before the broker sees it, and is passed on whole,
+//! resized or refused. Orders earlier in the same bar count as if filled at
+//! the latest price, so a batch cannot slip past a limit that each of its
+//! orders respects alone.
+
+use schema::{ConstrainedOrders, Order, OrderRejection, Portfolio, RejectionReason, Side};
+use std::collections::HashMap;
+
+/// Quantities smaller than this are nothing left to trade
+const FLAT: f64 = 1e-9;
+
+/// The account as a risk check sees it when an order comes through
+#[derive(Debug, Clone, Copy)]
+pub struct RiskContext<'a> {
+    /// Positions with the bar's earlier orders applied
+    pub portfolio: &'a Portfolio,
+    /// Latest price of each symbol, in the account's currency
+    pub prices: &'a HashMap<String, f64>,
+    /// Notional traded over the turnover window, with the bar's earlier
+    /// orders added
+    pub traded_notional: f64,
+}
+
+/// What a risk check does with an order
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskDecision {
+    Accept,
+    /// Send it with this smaller quantity
+    Resize(f64),
+    Reject(RejectionReason),
+}
+
+/// A check every order passes through on its way to the broker
+pub trait RiskCheck {
+    fn check(&mut self, order: &Order, context: &RiskContext) -> RiskDecision;
+}
+
+/// Limits on exposure, each a multiple of equity.
+///
+/// Orders that only reduce a position always pass, so no limit can trap the
+/// account in the position that breached it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RiskLimits {
+    /// Gross exposure over equity
+    pub max_leverage: Option<f64>,
+    /// One position's absolute value over equity
+    pub max_position_size: Option<f64>,
+    /// Notional traded over the turnover window over equity
+    pub max_turnover: Option<f64>,
+}
+
+impl RiskCheck for RiskLimits {
+    fn check(&mut self, order: &Order, context: &RiskContext) -> RiskDecision {
+        let Some(&price) = context.prices.get(&order.symbol).filter(|p| **p > 0.0) else {
+            return RiskDecision::Accept;
+        };
+        let equity = context.portfolio.equity;
+        let held = context
+            .portfolio
+            .get_position(&order.symbol)
+            .map_or(0.0, |p| p.quantity);
+        let reducing = match order.side {
+            Side::Buy => held < 0.0,
+            Side::Sell => held > 0.0,
+        };
+        // Quantity up to which the order only takes the position towards flat
+        let reducible = if reducing { held.abs() } else { 0.0 };
+        // Most an order can trade when its position may reach `cap` shares
+        let within = |cap: f64| {
+            if reducing {
+                held.abs() + cap.max(0.0)
+            } else {
+                cap - held.abs()
+            }
+        };
+
+        let mut allowed = Vec::new();
+        if let Some(limit) = self.max_leverage {
+            let gross: f64 = context
+                .portfolio
+                .positions
+                .values()
+                .filter_map(|p| Some(p.quantity.abs() * context.prices.get(&p.symbol)?))
+                .sum();
+            let cap = (limit * equity - gross) / price + held.abs();
+            allowed.push(("max_leverage", limit, within(cap)));
+        }
+        if let Some(limit) = self.max_position_size {
+            allowed.push(("max_position_size", limit, within(limit * equity / price)));
+        }
+        if let Some(limit) = self.max_turnover {
+            let headroom = (limit * equity - context.traded_notional) / price;
+            allowed.push(("max_turnover", limit, headroom.max(reducible)));
+        }
+
+        match allowed
+            .into_iter()
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .filter(|&(_, _, quantity)| quantity < order.quantity)
+        {
+            None => RiskDecision::Accept,
+            Some((_, _, quantity)) if quantity > FLAT => RiskDecision::Resize(quantity),
+            Some((limit, maximum, _)) => RiskDecision::Reject(RejectionReason::RiskLimit {
+                limit: limit.to_string(),
+                maximum,
+            }),
+        }
+    }
+}
+
+/// Keeps buys within the cash on hand at the latest price, less the
+/// reserve, and with `allow_shorts` off sells within the shares held
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CashCheck {
+    pub allow_shorts: bool,
+    /// Fraction of each buy's value held back for its commission, fees,
+    /// slippage and any move to its fill price
+    pub cost_reserve: f64,
+}
+
+impl RiskCheck for CashCheck {
+    fn check(&mut self, order: &Order, context: &RiskContext) -> RiskDecision {
+        let (allowed, reason) = match order.side {
+            Side::Buy => {
+                let Some(&price) = context.prices.get(&order.symbol).filter(|p| **p > 0.0) else {
+                    return RiskDecision::Accept;
+                };
+                let available = context.portfolio.cash.max(0.0);
+                let price = price * (1.0 + self.cost_reserve.max(0.0));
+                let reason = RejectionReason::InsufficientCash {
+                    required: order.quantity * price,
+                    available,
+                };
+                (available / price, reason)
+            }
+            Side::Sell if !self.allow_shorts => {
+                let held = context
+                    .portfolio
+                    .get_position(&order.symbol)
+                    .map_or(0.0, |p| p.quantity.max(0.0));
+                (held, RejectionReason::ExceedsHoldings { held })
+            }
+            Side::Sell => return RiskDecision::Accept,
+        };
+        if allowed >= order.quantity {
+            RiskDecision::Accept
+        } else if allowed > FLAT {
+            RiskDecision::Resize(allowed)
+        } else {
+            RiskDecision::Reject(reason)
+        }
+    }
+}
+
+/// The checks orders pass through, and how many they have constrained
+#[derive(Default)]
+pub struct RiskScreen {
+    checks: Vec<Box<dyn RiskCheck + Send>>,
+    constrained: ConstrainedOrders,
+}
+
+impl RiskScreen {
+    /// Run `check` after those already added
+    pub fn push(&mut self, check: impl RiskCheck + Send + 'static) {
+        self.checks.push(Box::new(check));
+    }
+
+    /// Orders downsized and refused so far, once there is a check
+    pub fn constrained(&self) -> Option<ConstrainedOrders> {
+        (!self.checks.is_empty()).then_some(self.constrained)
+    }
+
+    /// Pass `orders` through every check in turn, returning those to send
+    /// on and those refused at `timestamp`
+    pub fn screen(
+        &mut self,
+        orders: Vec<Order>,
+        portfolio: &Portfolio,
+        prices: &HashMap<String, f64>,
+        traded_notional: f64,
+        timestamp: i64,
+    ) -> (Vec<Order>, Vec<OrderRejection>) {
+        if self.checks.is_empty() {
+            return (orders, Vec::new());
+        }
+        let mut book = portfolio.clone();
+        let mut traded_notional = traded_notional;
+        let mut passed = Vec::new();
+        let mut rejections = Vec::new();
+        'orders: for mut order in orders {
+            let requested = order.quantity;
+            for check in &mut self.checks {
+                let context = RiskContext {
+                    portfolio: &book,
+                    prices,
+                    traded_notional,
+                };
+                match check.check(&order, &context) {
+                    RiskDecision::Accept => {}
+                    RiskDecision::Resize(quantity) => order.quantity = quantity,
+                    RiskDecision::Reject(reason) => {
+                        self.constrained.rejected += 1;
+                        rejections.push(OrderRejection {
+                            timestamp,
+                            order,
+                            reason,
+                        });
+                        continue 'orders;
+                    }
+                }
+            }
+            if order.quantity < requested {
+                self.constrained.downsized += 1;
+            }
+            let price = prices.get(&order.symbol).copied().unwrap_or(0.0);
+            let signed = match order.side {
+                Side::Buy => order.quantity,
+                Side::Sell => -order.quantity,
+            };
+            book.get_position_mut(&order.symbol).quantity += signed;
+            book.cash -= signed * price;
+            traded_notional += order.quantity * price;
+            passed.push(order);
+        }
+        (passed, rejections)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::OrderType;
+
+    fn order(side: Side, quantity: f64) -> Order {
+        Order {
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            order_type: OrderType::Market,
+            limit_price: None,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        }
+    }
+
+    #[test]
+    fn orders_are_cut_to_the_tightest_limit() {
+        let prices = HashMap::from([("AAPL".to_string(), 100.0)]);
+        let mut portfolio = Portfolio::new(10_000.0);
+        portfolio.get_position_mut("AAPL").quantity = 50.0;
+        let mut screen = RiskScreen::default();
+        screen.push(RiskLimits {
+            max_leverage: Some(2.0),
+            max_position_size: Some(1.5),
+            max_turnover: None,
+        });
+
+        // 150 shares reach the position limit before 200 reach leverage
+        let (passed, rejections) = screen.screen(
+            vec![order(Side::Buy, 80.0), order(Side::Buy, 80.0)],
+            &portfolio,
+            &prices,
+            0.0,
+            7,
+        );
+        let quantities: Vec<f64> = passed.iter().map(|o| o.quantity).collect();
+        assert_eq!(quantities, [80.0, 20.0]);
+        assert!(rejections.is_empty());
+
+        portfolio.get_position_mut("AAPL").quantity = 150.0;
+        let (passed, rejections) = screen.screen(
+            vec![order(Side::Buy, 10.0), order(Side::Sell, 400.0)],
+            &portfolio,
+            &prices,
+            0.0,
+            7,
+        );
+        // Selling out and going 150 short is within both limits
+        assert_eq!(passed.len(), 1);
+        assert_eq!(passed[0].quantity, 300.0);
+        assert_eq!(
+            rejections[0].reason,
+            RejectionReason::RiskLimit {
+                limit: "max_position_size".to_string(),
+                maximum: 1.5
+            }
+        );
+        assert_eq!(
+            screen.constrained(),
+            Some(ConstrainedOrders {
+                downsized: 2,
+                rejected: 1
+            })
+        );
+    }
+
+    #[test]
+    fn turnover_limits_never_block_a_reduction() {
+        let prices = HashMap::from([("AAPL".to_string(), 100.0)]);
+        let mut portfolio = Portfolio::new(10_000.0);
+        portfolio.get_position_mut("AAPL").quantity = 50.0;
+        let mut limits = RiskLimits {
+            max_turnover: Some(1.0),
+            ..RiskLimits::default()
+        };
+        let context = RiskContext {
+            portfolio: &portfolio,
+            prices: &prices,
+            traded_notional: 10_000.0,
+        };
+
+        assert_eq!(
+            limits.check(&order(Side::Sell, 50.0), &context),
+            RiskDecision::Accept
+        );
+        assert_eq!(
+            limits.check(&order(Side::Sell, 60.0), &context),
+            RiskDecision::Resize(50.0)
+        );
+        assert!(matches!(
+            limits.check(&order(Side::Buy, 1.0), &context),
+            RiskDecision::Reject(_)
+        ));
+    }
+
+    #[test]
+    fn strict_cash_keeps_a_bar_of_buys_within_cash() {
+        let prices = HashMap::from([("AAPL".to_string(), 100.0)]);
+        let mut portfolio = Portfolio::new(10_000.0);
+        portfolio.get_position_mut("AAPL").quantity = 20.0;
+        let mut screen = RiskScreen::default();
+        screen.push(CashCheck {
+            allow_shorts: false,
+            cost_reserve: 0.0,
+        });
+
+        let (passed, rejections) = screen.screen(
+            vec![
+                order(Side::Buy, 60.0),
+                order(Side::Buy, 60.0),
+                order(Side::Buy, 1.0),
+                order(Side::Sell, 200.0),
+            ],
+            &portfolio,
+            &prices,
+            0.0,
+            7,
+        );
+        let quantities: Vec<(Side, f64)> = passed.iter().map(|o| (o.side, o.quantity)).collect();
+        // The sale is cut to the 120 shares held by then
+        assert_eq!(
+            quantities,
+            [(Side::Buy, 60.0), (Side::Buy, 40.0), (Side::Sell, 120.0)]
+        );
+        assert_eq!(
+            rejections[0].reason,
+            RejectionReason::InsufficientCash {
+                required: 100.0,
+                available: 0.0
+            }
+        );
+    }
+}
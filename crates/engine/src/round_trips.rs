@@ -0,0 +1,220 @@
+//! Round-trip trades paired from the fill stream.
+//!
+//! A round trip runs from a fill that opens a position in a symbol to the
+//! fill that takes it flat again. A fill that flips a position closes the
+//! trade with the part that flattens it and opens the next with the rest;
+//! its charged costs split pro rata between the two.
+
+use schema::{Fill, Side};
+use std::collections::{BTreeMap, HashMap};
+
+/// Positions smaller than this count as flat
+const FLAT: f64 = 1e-9;
+
+/// One position from open to flat
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundTrip {
+    pub symbol: String,
+    /// `Buy` for a long, `Sell` for a short
+    pub side: Side,
+    pub entry_time: i64,
+    pub exit_time: i64,
+    /// Quantity opened, summed over every fill that added to the position
+    pub quantity: f64,
+    /// Average price of the fills that added to the position
+    pub entry_price: f64,
+    /// Average price of the fills that reduced it
+    pub exit_price: f64,
+    /// PnL net of charged costs
+    pub pnl: f64,
+    /// PnL over the notional opened
+    pub trade_return: f64,
+    /// Worst unrealized PnL while open, before costs; zero or negative
+    pub mae: f64,
+    /// Best unrealized PnL while open, before costs; zero or positive
+    pub mfe: f64,
+}
+
+impl RoundTrip {
+    /// Seconds from entry to exit
+    pub fn holding_period(&self) -> i64 {
+        self.exit_time - self.entry_time
+    }
+}
+
+/// A round trip still open
+#[derive(Debug, Clone)]
+struct OpenTrade {
+    side: Side,
+    entry_time: i64,
+    /// Signed quantity held
+    position: f64,
+    /// Cash flows of the fills net of costs; the PnL once flat
+    pnl: f64,
+    /// Cash flows before costs, for the excursions
+    gross: f64,
+    entry_quantity: f64,
+    entry_notional: f64,
+    exit_quantity: f64,
+    exit_notional: f64,
+    mae: f64,
+    mfe: f64,
+}
+
+impl OpenTrade {
+    fn mark(&mut self, price: f64) {
+        let unrealized = self.gross + self.position * price;
+        self.mae = self.mae.min(unrealized);
+        self.mfe = self.mfe.max(unrealized);
+    }
+}
+
+/// Pairs fills into round trips as they happen, tracking each open trade's
+/// excursions at every price it is marked at
+#[derive(Debug, Clone, Default)]
+pub struct RoundTripLedger {
+    open: BTreeMap<String, OpenTrade>,
+    closed: Vec<RoundTrip>,
+}
+
+impl RoundTripLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply_fill(&mut self, fill: &Fill) {
+        if fill.quantity <= 0.0 {
+            return;
+        }
+        let signed = match fill.side {
+            Side::Buy => fill.quantity,
+            Side::Sell => -fill.quantity,
+        };
+        let position = self.open.get(&fill.symbol).map_or(0.0, |t| t.position);
+        let closing = if position * signed < 0.0 {
+            signed.signum() * signed.abs().min(position.abs())
+        } else {
+            0.0
+        };
+        for part in [closing, signed - closing] {
+            if part == 0.0 {
+                continue;
+            }
+            let trade = self
+                .open
+                .entry(fill.symbol.clone())
+                .or_insert_with(|| OpenTrade {
+                    side: fill.side,
+                    entry_time: fill.timestamp,
+                    position: 0.0,
+                    pnl: 0.0,
+                    gross: 0.0,
+                    entry_quantity: 0.0,
+                    entry_notional: 0.0,
+                    exit_quantity: 0.0,
+                    exit_notional: 0.0,
+                    mae: 0.0,
+                    mfe: 0.0,
+                });
+            trade.pnl -= part * fill.price + fill.costs.charged() * part.abs() / fill.quantity;
+            trade.gross -= part * fill.price;
+            trade.position += part;
+            if part == closing {
+                trade.exit_quantity += part.abs();
+                trade.exit_notional += part.abs() * fill.price;
+            } else {
+                trade.entry_quantity += part.abs();
+                trade.entry_notional += part.abs() * fill.price;
+            }
+            trade.mark(fill.price);
+            if part == closing && trade.position.abs() < FLAT {
+                let trade = self.open.remove(&fill.symbol).unwrap();
+                self.closed.push(RoundTrip {
+                    symbol: fill.symbol.clone(),
+                    side: trade.side,
+                    entry_time: trade.entry_time,
+                    exit_time: fill.timestamp,
+                    quantity: trade.entry_quantity,
+                    entry_price: trade.entry_notional / trade.entry_quantity,
+                    exit_price: trade.exit_notional / trade.exit_quantity,
+                    pnl: trade.pnl,
+                    trade_return: if trade.entry_notional > 0.0 {
+                        trade.pnl / trade.entry_notional
+                    } else {
+                        0.0
+                    },
+                    mae: trade.mae,
+                    mfe: trade.mfe,
+                });
+            }
+        }
+    }
+
+    /// Mark every open trade with a price to its symbol's price
+    pub fn mark(&mut self, prices: &HashMap<String, f64>) {
+        for (symbol, trade) in &mut self.open {
+            if let Some(&price) = prices.get(symbol) {
+                trade.mark(price);
+            }
+        }
+    }
+
+    /// Closed round trips, in closing order
+    pub fn round_trips(&self) -> &[RoundTrip] {
+        &self.closed
+    }
+}
+
+/// Round trips of a fill stream, with excursions marked at fill prices only
+pub fn pair_fills(fills: &[Fill]) -> Vec<RoundTrip> {
+    let mut ledger = RoundTripLedger::new();
+    for fill in fills {
+        ledger.apply_fill(fill);
+    }
+    ledger.closed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::CostBreakdown;
+
+    fn fill(timestamp: i64, side: Side, quantity: f64, price: f64) -> Fill {
+        Fill {
+            timestamp,
+            symbol: "AAPL".to_string(),
+            side,
+            quantity,
+            price,
+            costs: CostBreakdown::from_commission(quantity / 10.0),
+        }
+    }
+
+    #[test]
+    fn flips_close_one_trade_and_open_the_next() {
+        let mut ledger = RoundTripLedger::new();
+        ledger.apply_fill(&fill(0, Side::Buy, 10.0, 100.0));
+        ledger.mark(&HashMap::from([("AAPL".to_string(), 95.0)]));
+        ledger.apply_fill(&fill(60, Side::Buy, 10.0, 104.0));
+        ledger.mark(&HashMap::from([("AAPL".to_string(), 110.0)]));
+        // Sells the long's 20 and goes short 10
+        ledger.apply_fill(&fill(120, Side::Sell, 30.0, 108.0));
+        ledger.apply_fill(&fill(180, Side::Buy, 10.0, 111.0));
+
+        let trips = ledger.round_trips();
+        assert_eq!(trips.len(), 2);
+        let long = &trips[0];
+        assert_eq!((long.side, long.holding_period()), (Side::Buy, 120));
+        assert_eq!((long.quantity, long.entry_price), (20.0, 102.0));
+        // +120 gross less 1 + 1 + 2 of commission
+        assert!((long.pnl - 116.0).abs() < 1e-9);
+        assert!((long.trade_return - 116.0 / 2040.0).abs() < 1e-12);
+        assert_eq!((long.mae, long.mfe), (-50.0, 160.0));
+
+        let short = &trips[1];
+        assert_eq!((short.side, short.entry_time), (Side::Sell, 120));
+        assert_eq!((short.entry_price, short.exit_price), (108.0, 111.0));
+        assert!((short.pnl + 32.0).abs() < 1e-9);
+        assert_eq!((short.mae, short.mfe), (-30.0, 0.0));
+    }
+}
@@ -0,0 +1,175 @@
+//! Calendar timers fired into the engine loop.
+//!
+//! A period is over once a bar from a later one arrives, so each timer fires
+//! just before that bar reaches the strategy and never looks ahead. Gaps in
+//! the data fire a kind's timer once, not once per empty period. Periods are
+//! UTC calendar days, Monday-to-Sunday weeks and months.
+//!
+//! `WarmUp` counts off the bar timestamps at the start of a run that only
+//! seed a strategy's indicators.
+
+use chrono::Datelike;
+use schema::{Bar, Order, Portfolio, Strategy, TimerEvent, TimerKind};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Fires `TimerKind`s at period ends and holds the orders strategies place
+/// on them until a bar of their symbol arrives
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    kinds: Vec<TimerKind>,
+    last_timestamp: Option<i64>,
+    held: Vec<Order>,
+}
+
+impl Default for Scheduler {
+    /// Every kind of timer
+    fn default() -> Self {
+        Self::new([
+            TimerKind::DailyClose,
+            TimerKind::WeekEnd,
+            TimerKind::MonthEnd,
+        ])
+    }
+}
+
+impl Scheduler {
+    /// Fire `kinds`, in the order given when several fall due at once
+    pub fn new(kinds: impl IntoIterator<Item = TimerKind>) -> Self {
+        Self {
+            kinds: kinds.into_iter().collect(),
+            last_timestamp: None,
+            held: Vec::new(),
+        }
+    }
+
+    /// Timers due as a bar at `timestamp` arrives
+    pub fn advance(&mut self, timestamp: i64) -> Vec<TimerEvent> {
+        let Some(last) = self.last_timestamp.replace(timestamp) else {
+            return Vec::new();
+        };
+        self.kinds
+            .iter()
+            .filter(|&&kind| period(kind, timestamp) > period(kind, last))
+            .map(|&kind| TimerEvent { timestamp, kind })
+            .collect()
+    }
+
+    /// Fire the timers due at `bar` into `strategy`, then return the held
+    /// orders for `bar`'s symbol
+    pub fn timer_orders<S: Strategy>(
+        &mut self,
+        strategy: &mut S,
+        bar: &Bar,
+        portfolio: &Portfolio,
+    ) -> Vec<Order> {
+        for timer in self.advance(bar.timestamp) {
+            self.held.extend(strategy.on_timer(&timer, portfolio));
+        }
+        if self.held.is_empty() {
+            return Vec::new();
+        }
+        let (due, held) = std::mem::take(&mut self.held)
+            .into_iter()
+            .partition(|order| order.symbol == bar.symbol);
+        self.held = held;
+        due
+    }
+
+    /// Fire the timers due at `bar` into `strategy` while it warms up,
+    /// dropping any orders it places on them
+    pub fn warm_up<S: Strategy>(&mut self, strategy: &mut S, bar: &Bar, portfolio: &Portfolio) {
+        for timer in self.advance(bar.timestamp) {
+            strategy.on_timer(&timer, portfolio);
+        }
+    }
+}
+
+/// Counts off a strategy's warm-up, in distinct bar timestamps so every
+/// symbol of a multi-symbol feed gets the same bars
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WarmUp {
+    bars: usize,
+    seen: usize,
+    last_timestamp: Option<i64>,
+}
+
+impl WarmUp {
+    pub fn new(bars: usize) -> Self {
+        Self {
+            bars,
+            ..Self::default()
+        }
+    }
+
+    /// Whether a bar at `timestamp` is still part of the warm-up
+    pub fn is_warming(&mut self, timestamp: i64) -> bool {
+        if self.seen > self.bars {
+            return false;
+        }
+        if self.last_timestamp != Some(timestamp) {
+            self.last_timestamp = Some(timestamp);
+            self.seen += 1;
+        }
+        self.seen <= self.bars
+    }
+}
+
+/// Index of the period of `kind` holding `timestamp`, increasing with time
+fn period(kind: TimerKind, timestamp: i64) -> i64 {
+    let day = timestamp.div_euclid(SECONDS_PER_DAY);
+    match kind {
+        TimerKind::DailyClose => day,
+        // 1970-01-01 was a Thursday, so weeks start three days later
+        TimerKind::WeekEnd => (day + 3).div_euclid(7),
+        TimerKind::MonthEnd => {
+            let date = chrono::DateTime::from_timestamp(timestamp, 0)
+                .unwrap_or_default()
+                .date_naive();
+            i64::from(date.year()) * 12 + i64::from(date.month0())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timers_fire_once_their_period_is_over() {
+        let day = SECONDS_PER_DAY;
+        // Friday 2024-01-26, Monday 2024-01-29, Wednesday 2024-01-31 and
+        // Thursday 2024-02-01
+        let friday = 1_706_227_200;
+        let mut scheduler = Scheduler::default();
+        let kinds = |timers: Vec<TimerEvent>| -> Vec<TimerKind> {
+            timers.into_iter().map(|t| t.kind).collect()
+        };
+
+        assert!(scheduler.advance(friday).is_empty());
+        assert!(scheduler.advance(friday + 3_600).is_empty());
+        assert_eq!(
+            kinds(scheduler.advance(friday + 3 * day)),
+            [TimerKind::DailyClose, TimerKind::WeekEnd]
+        );
+        assert_eq!(
+            kinds(scheduler.advance(friday + 5 * day)),
+            [TimerKind::DailyClose]
+        );
+        assert_eq!(
+            kinds(scheduler.advance(friday + 6 * day)),
+            [TimerKind::DailyClose, TimerKind::MonthEnd]
+        );
+    }
+
+    #[test]
+    fn warm_up_counts_timestamps_not_bars() {
+        let mut warm_up = WarmUp::new(2);
+        let warming: Vec<bool> = [0, 0, 60, 60, 120, 120, 180]
+            .into_iter()
+            .map(|t| warm_up.is_warming(t))
+            .collect();
+        assert_eq!(warming, [true, true, true, true, false, false, false]);
+        assert!(!WarmUp::new(0).is_warming(0));
+    }
+}
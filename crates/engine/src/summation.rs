@@ -0,0 +1,63 @@
+//! Compensated floating-point summation for accounting totals.
+//!
+//! Cash, PnL and commission are running sums over every fill of a run. Naive
+//! `+=` loses the low bits of small terms added to large totals, and the loss
+//! grows with run length; Neumaier's variant of Kahan summation carries the
+//! lost bits separately, keeping the error at about one rounding of the
+//! result however many terms were added.
+
+/// A running sum with Neumaier compensation
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NeumaierSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl NeumaierSum {
+    pub fn new(initial: f64) -> Self {
+        Self {
+            sum: initial,
+            compensation: 0.0,
+        }
+    }
+
+    pub fn add(&mut self, value: f64) {
+        let sum = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - sum) + value;
+        } else {
+            self.compensation += (value - sum) + self.sum;
+        }
+        self.sum = sum;
+    }
+
+    pub fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+/// Compensated sum of `values`
+pub fn neumaier_sum(values: impl IntoIterator<Item = f64>) -> f64 {
+    let mut sum = NeumaierSum::default();
+    for value in values {
+        sum.add(value);
+    }
+    sum.value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compensation_keeps_small_terms() {
+        // Naive summation returns 0 here: the 1s vanish next to 1e100
+        assert_eq!(neumaier_sum([1.0, 1e100, 1.0, -1e100]), 2.0);
+
+        let mut cash = NeumaierSum::new(1e9);
+        for _ in 0..1_000_000 {
+            cash.add(0.01);
+        }
+        assert_eq!(cash.value(), 1e9 + 10_000.0);
+    }
+}
@@ -0,0 +1,222 @@
+use schema::{
+    Bar, Order, OrderType, Portfolio, Side, Strategy, Target, TargetStrategy, TimerEvent,
+};
+use std::collections::BTreeMap;
+
+/// Fractional trades at or below this many shares are skipped when no lot
+/// size is set, as the built-in strategies do
+const DUST: f64 = 0.1;
+
+/// How a `TargetExecutor` turns targets into orders
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExecutionRules {
+    /// Order quantities are rounded down to a multiple of this
+    pub lot_size: Option<f64>,
+    /// Largest order as a fraction of the bar's volume
+    pub max_participation: Option<f64>,
+}
+
+/// Runs a `TargetStrategy` as a `Strategy`.
+///
+/// A target is traded on the next bar of its own symbol, so a strategy may
+/// set targets for every symbol at once and each still fills at its own
+/// close. Weights are converted with the equity and close of that bar. A
+/// target is dropped once an order covering the whole remaining difference
+/// has been sent; an order clipped by `max_participation` leaves the target
+/// pending, and the rest is traded on the symbol's following bars.
+pub struct TargetExecutor<T: TargetStrategy> {
+    strategy: T,
+    rules: ExecutionRules,
+    pending: BTreeMap<String, Target>,
+}
+
+impl<T: TargetStrategy> TargetExecutor<T> {
+    pub fn new(strategy: T, rules: ExecutionRules) -> Self {
+        Self {
+            strategy,
+            rules,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Targets not yet fully traded, by symbol
+    pub fn pending(&self) -> &BTreeMap<String, Target> {
+        &self.pending
+    }
+
+    /// Shares of the order that moves the position in `bar.symbol` toward
+    /// `target`, signed, and whether it gets all the way there
+    fn order_quantity(&self, target: Target, bar: &Bar, portfolio: &Portfolio) -> (f64, bool) {
+        let desired = match target {
+            Target::Shares(shares) => shares,
+            Target::Weight(weight) => weight * portfolio.equity / bar.close,
+        };
+        let current = portfolio
+            .get_position(&bar.symbol)
+            .map(|p| p.quantity)
+            .unwrap_or(0.0);
+        let delta = desired - current;
+
+        let mut quantity = delta.abs();
+        let mut complete = true;
+        if let Some(participation) = self.rules.max_participation {
+            let cap = participation * bar.volume.max(0.0);
+            if quantity > cap {
+                quantity = cap;
+                complete = false;
+            }
+        }
+        quantity = match self.rules.lot_size {
+            Some(lot) if lot > 0.0 => (quantity / lot + 1e-9).floor() * lot,
+            _ if quantity <= DUST => 0.0,
+            _ => quantity,
+        };
+        (quantity.copysign(delta), complete)
+    }
+}
+
+impl<T: TargetStrategy> Strategy for TargetExecutor<T> {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        for target in self.strategy.targets(bar, portfolio) {
+            self.pending.insert(target.symbol, target.target);
+        }
+
+        let Some(&target) = self.pending.get(&bar.symbol) else {
+            return vec![];
+        };
+        if bar.close <= 0.0 {
+            return vec![];
+        }
+        let (quantity, complete) = self.order_quantity(target, bar, portfolio);
+        if complete {
+            self.pending.remove(&bar.symbol);
+        }
+        if quantity == 0.0 {
+            return vec![];
+        }
+        vec![Order {
+            symbol: bar.symbol.clone(),
+            side: if quantity > 0.0 {
+                Side::Buy
+            } else {
+                Side::Sell
+            },
+            quantity: quantity.abs(),
+            order_type: OrderType::Market,
+            limit_price: None,
+            stop_price: None,
+            order_id: None,
+            bracket: None,
+        }]
+    }
+
+    /// Targets set on a timer are traded on each symbol's next bar
+    fn on_timer(&mut self, timer: &TimerEvent, portfolio: &Portfolio) -> Vec<Order> {
+        for target in self.strategy.on_timer(timer, portfolio) {
+            self.pending.insert(target.symbol, target.target);
+        }
+        vec![]
+    }
+
+    fn warmup_bars(&self) -> usize {
+        self.strategy.warmup_bars()
+    }
+
+    fn name(&self) -> &str {
+        self.strategy.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::BacktestEngine;
+    use crate::data_feed::VecDataFeed;
+    use broker_sim::SimpleBroker;
+    use cost::ZeroCost;
+    use schema::TargetPosition;
+
+    /// Sets the given targets on the first bar only
+    struct Once(Vec<TargetPosition>);
+
+    impl TargetStrategy for Once {
+        fn targets(&mut self, _bar: &Bar, _portfolio: &Portfolio) -> Vec<TargetPosition> {
+            std::mem::take(&mut self.0)
+        }
+
+        fn name(&self) -> &str {
+            "Once"
+        }
+    }
+
+    fn target(symbol: &str, target: Target) -> TargetPosition {
+        TargetPosition {
+            symbol: symbol.to_string(),
+            target,
+        }
+    }
+
+    fn bar(timestamp: i64, symbol: &str, close: f64, volume: f64) -> Bar {
+        Bar {
+            timestamp,
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume,
+        }
+    }
+
+    #[test]
+    fn weights_are_rounded_to_lots_and_traded_on_each_symbols_bar() {
+        let strategy = Once(vec![
+            target("AAA", Target::Weight(0.5)),
+            target("BBB", Target::Shares(-25.0)),
+        ]);
+        let mut executor = TargetExecutor::new(
+            strategy,
+            ExecutionRules {
+                lot_size: Some(10.0),
+                max_participation: None,
+            },
+        );
+        let portfolio = Portfolio::new(10_000.0);
+
+        // 5000 / 30 = 166.7 shares, rounded down to 160
+        let orders = executor.on_bar(&bar(0, "AAA", 30.0, 1e6), &portfolio);
+        assert_eq!(orders.len(), 1);
+        assert_eq!((orders[0].side, orders[0].quantity), (Side::Buy, 160.0));
+        assert_eq!(executor.pending().len(), 1);
+
+        let orders = executor.on_bar(&bar(0, "BBB", 10.0, 1e6), &portfolio);
+        assert_eq!((orders[0].side, orders[0].quantity), (Side::Sell, 20.0));
+        assert!(executor.pending().is_empty());
+        assert!(executor
+            .on_bar(&bar(1, "AAA", 30.0, 1e6), &portfolio)
+            .is_empty());
+    }
+
+    #[test]
+    fn participation_limit_spreads_an_order_over_bars() {
+        let bars: Vec<Bar> = (0..4).map(|t| bar(t, "AAA", 10.0, 400.0)).collect();
+        let strategy = Once(vec![target("AAA", Target::Shares(250.0))]);
+        let executor = TargetExecutor::new(
+            strategy,
+            ExecutionRules {
+                lot_size: None,
+                max_participation: Some(0.25),
+            },
+        );
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars),
+            executor,
+            SimpleBroker::new(ZeroCost, 0),
+            10_000.0,
+        );
+        engine.run().unwrap();
+
+        let quantities: Vec<f64> = engine.fills().iter().map(|f| f.quantity).collect();
+        assert_eq!(quantities, vec![100.0, 100.0, 50.0]);
+    }
+}
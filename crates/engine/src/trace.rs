@@ -0,0 +1,167 @@
+//! What a run did and where its time went, for lineage traces.
+//!
+//! Counts come from the run itself and repeat exactly; timings are wall
+//! clock and do not, so nothing that is hashed for determinism includes a
+//! `RunTrace`.
+
+use std::time::{Duration, Instant};
+
+/// Events a run went through
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventCounts {
+    /// Bars read from the feed, warm-up included
+    pub bars: usize,
+    pub warmup_bars: usize,
+    /// Orders that reached the broker
+    pub orders: usize,
+    pub fills: usize,
+    /// Orders refused by the risk checks or the broker
+    pub rejections: usize,
+}
+
+/// A stage of the engine loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Timers, `on_bar` and `manage_orders`
+    Strategy,
+    Risk,
+    /// Order processing and instructions
+    Broker,
+    /// Applying charges and fills, marking equity and observers
+    Accounting,
+}
+
+/// Wall-clock time spent in each stage of the loop
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimings {
+    pub strategy: Duration,
+    pub risk: Duration,
+    pub broker: Duration,
+    pub accounting: Duration,
+}
+
+impl StageTimings {
+    pub fn total(&self) -> Duration {
+        self.strategy + self.risk + self.broker + self.accounting
+    }
+
+    fn stage_mut(&mut self, stage: Stage) -> &mut Duration {
+        match stage {
+            Stage::Strategy => &mut self.strategy,
+            Stage::Risk => &mut self.risk,
+            Stage::Broker => &mut self.broker,
+            Stage::Accounting => &mut self.accounting,
+        }
+    }
+}
+
+/// A run's strategy, event counts and stage timings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunTrace {
+    pub strategy: String,
+    pub counts: EventCounts,
+    pub timings: StageTimings,
+}
+
+impl RunTrace {
+    pub fn new(strategy: impl Into<String>) -> Self {
+        Self {
+            strategy: strategy.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Charge the time since `clock` to `stage` and restart the clock
+    pub fn lap(&mut self, stage: Stage, clock: &mut Instant) {
+        let now = Instant::now();
+        *self.timings.stage_mut(stage) += now - *clock;
+        *clock = now;
+    }
+
+    /// Both runs' counts and timings together, as for runs merged into one
+    pub fn merge(mut self, other: &RunTrace) -> Self {
+        let (counts, more) = (&mut self.counts, other.counts);
+        counts.bars += more.bars;
+        counts.warmup_bars += more.warmup_bars;
+        counts.orders += more.orders;
+        counts.fills += more.fills;
+        counts.rejections += more.rejections;
+        let (timings, more) = (&mut self.timings, other.timings);
+        timings.strategy += more.strategy;
+        timings.risk += more.risk;
+        timings.broker += more.broker;
+        timings.accounting += more.accounting;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::BacktestEngine;
+    use crate::data_feed::VecDataFeed;
+    use broker_sim::SimpleBroker;
+    use cost::ZeroCost;
+    use schema::{Bar, Order, OrderType, Portfolio, Side, Strategy};
+
+    /// Buys a share on every bar
+    struct Accumulate;
+
+    impl Strategy for Accumulate {
+        fn on_bar(&mut self, bar: &Bar, _portfolio: &Portfolio) -> Vec<Order> {
+            vec![Order {
+                symbol: bar.symbol.clone(),
+                side: Side::Buy,
+                quantity: 1.0,
+                order_type: OrderType::Market,
+                limit_price: None,
+                stop_price: None,
+                order_id: None,
+                bracket: None,
+            }]
+        }
+
+        fn name(&self) -> &str {
+            "Accumulate"
+        }
+    }
+
+    #[test]
+    fn traced_runs_count_their_events() {
+        let bars: Vec<Bar> = (1..=5)
+            .map(|timestamp| Bar {
+                timestamp,
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 100.0,
+                low: 100.0,
+                close: 100.0,
+                volume: 10000.0,
+            })
+            .collect();
+        let mut engine = BacktestEngine::new(
+            VecDataFeed::new(bars),
+            Accumulate,
+            SimpleBroker::new(ZeroCost, 42),
+            1000.0,
+        )
+        .with_warmup(1)
+        .with_trace();
+        engine.run().unwrap();
+
+        let trace = engine.trace().unwrap();
+        assert_eq!(trace.strategy, "Accumulate");
+        let counts = EventCounts {
+            bars: 5,
+            warmup_bars: 1,
+            orders: 4,
+            fills: 4,
+            rejections: 0,
+        };
+        assert_eq!(trace.counts, counts);
+
+        let merged = trace.clone().merge(trace);
+        assert_eq!(merged.counts.fills, 8);
+        assert_eq!(merged.timings.total(), trace.timings.total() * 2);
+    }
+}
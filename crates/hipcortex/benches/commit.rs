@@ -0,0 +1,37 @@
+//! Commits per second into a HipCortex repository on local disk.
+//!
+//! Run with `cargo bench -p hipcortex`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use hipcortex::{Artifact, Repository, StrategySpec};
+
+fn spec(n: u64) -> Artifact {
+    // Distinct parameters so every commit stores a new object
+    Artifact::StrategySpec(StrategySpec {
+        name: format!("bench-{}", n),
+        description: "Benchmark strategy".to_string(),
+        strategy_type: "ts_momentum".to_string(),
+        parameters: serde_json::json!({"lookback": n % 250 + 2, "run": n}),
+        goal: "throughput".to_string(),
+        regime_tags: vec!["bench".to_string()],
+    })
+}
+
+fn commit_throughput(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut repo = Repository::open(dir.path().join(".hipcortex")).unwrap();
+    let mut n = 0;
+
+    let mut group = c.benchmark_group("hipcortex");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("commit", |b| {
+        b.iter(|| {
+            n += 1;
+            repo.commit(&spec(n), "bench commit", vec![]).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, commit_throughput);
+criterion_main!(benches);
@@ -1,7 +1,7 @@
 use crv_verifier::CRVReport;
 use schema::{
-    BacktestStats, Bar, EquityPoint, FidelityTier, Fill, LatencyClass, QualityFlag,
-    TransformationStep,
+    BacktestStats, Bar, DataQualityReport, EquityPoint, FidelityTier, Fill, LatencyClass,
+    QualityFlag, TransformationStep,
 };
 use serde::{Deserialize, Serialize};
 
@@ -15,6 +15,7 @@ pub enum Artifact {
     BacktestResult(BacktestResult),
     CRVReport(CRVReportArtifact),
     Trace(Trace),
+    DataQualityReport(DataQualityReportArtifact),
 }
 
 impl Artifact {
@@ -27,6 +28,7 @@ impl Artifact {
             Artifact::BacktestResult(_) => "backtest_result",
             Artifact::CRVReport(_) => "crv_report",
             Artifact::Trace(_) => "trace",
+            Artifact::DataQualityReport(_) => "data_quality_report",
         }
     }
 }
@@ -184,6 +186,13 @@ pub struct CRVReportArtifact {
     pub report: CRVReport,
 }
 
+/// Data quality report artifact for a dataset
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DataQualityReportArtifact {
+    pub dataset_hash: String,
+    pub report: DataQualityReport,
+}
+
 /// Trace artifact for debugging and audit
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Trace {
@@ -7,8 +7,8 @@ pub mod repository;
 pub mod storage;
 
 pub use artifact::{
-    Artifact, BacktestConfig, BacktestResult, CRVReportArtifact, CostModelConfig, Dataset,
-    DatasetMetadata, PolicyConstraints, StrategySpec, Trace,
+    Artifact, BacktestConfig, BacktestResult, CRVReportArtifact, CostModelConfig,
+    DataQualityReportArtifact, Dataset, DatasetMetadata, PolicyConstraints, StrategySpec, Trace,
 };
 pub use audit::{AuditLog, CommitEntry};
 pub use index::{ArtifactMetadata, MetadataIndex, SearchQuery};
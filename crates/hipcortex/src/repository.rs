@@ -171,6 +171,18 @@ impl Repository {
                 policy: None,
                 description: Some(trace.operation.clone()),
             },
+            Artifact::DataQualityReport(quality) => ArtifactMetadata {
+                hash: hash.as_hex().to_string(),
+                artifact_type: "data_quality_report".to_string(),
+                timestamp,
+                goal: None,
+                regime_tags: vec![],
+                policy: None,
+                description: Some(format!(
+                    "score {:.1} for dataset {}",
+                    quality.report.score, quality.dataset_hash
+                )),
+            },
         }
     }
 }
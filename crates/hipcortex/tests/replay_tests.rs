@@ -2,7 +2,7 @@ use hipcortex::{
     Artifact, BacktestConfig, BacktestResult, ContentHash, CostModelConfig, PolicyConstraints,
     Repository, StrategySpec,
 };
-use schema::{BacktestStats, EquityPoint};
+use schema::{BacktestStats, CostBreakdown, EquityPoint};
 use tempfile::TempDir;
 
 #[test]
@@ -56,8 +56,14 @@ fn test_replay_reproducibility() {
             total_return: 0.25,
             num_trades: 10,
             total_commission: 50.0,
+            costs: CostBreakdown::from_commission(50.0),
             sharpe_ratio: 1.5,
             max_drawdown: 0.15,
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
         },
         trades: vec![],
         equity_curve: vec![
@@ -253,8 +259,14 @@ fn test_full_replay_simulation() {
             total_return: 0.10,
             num_trades: 5,
             total_commission: 0.0,
+            costs: CostBreakdown::default(),
             sharpe_ratio: 1.2,
             max_drawdown: 0.08,
+            financing: None,
+            performance: Default::default(),
+            benchmark: None,
+            constrained_orders: None,
+            bootstrap: None,
         },
         trades: vec![],
         equity_curve: vec![],
@@ -0,0 +1,381 @@
+//! Incremental technical indicators shared by the built-in strategies.
+//!
+//! Each indicator takes one value per bar through `update`, costs O(1) per
+//! update and returns `None` until its window is full. Standard deviations
+//! are population (divide by N), as in the strategies that predate this crate.
+
+#![forbid(unsafe_code)]
+
+use schema::Bar;
+use std::collections::VecDeque;
+
+pub mod sizing;
+
+/// Simple moving average over the last `window` values
+#[derive(Debug, Clone)]
+pub struct Sma {
+    window: usize,
+    values: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Sma {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            values: VecDeque::new(),
+            sum: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        self.values.push_back(value);
+        self.sum += value;
+        if self.values.len() > self.window {
+            self.sum -= self.values.pop_front().unwrap_or_default();
+        }
+        self.value()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        (self.values.len() == self.window).then(|| self.sum / self.window as f64)
+    }
+}
+
+/// Exponential moving average with `alpha = 2 / (window + 1)`, seeded with
+/// the first value and reported once `window` values have been seen
+#[derive(Debug, Clone)]
+pub struct Ema {
+    window: usize,
+    alpha: f64,
+    ema: Option<f64>,
+    seen: usize,
+}
+
+impl Ema {
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            alpha: 2.0 / (window as f64 + 1.0),
+            ema: None,
+            seen: 0,
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        self.ema = Some(match self.ema {
+            Some(ema) => ema + self.alpha * (value - ema),
+            None => value,
+        });
+        self.seen += 1;
+        self.value()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        self.ema.filter(|_| self.seen >= self.window)
+    }
+}
+
+/// Mean and standard deviation of the last `window` values.
+///
+/// Uses Welford's update in both directions, which keeps the variance
+/// accurate for prices far from zero where sum-of-squares formulas cancel.
+#[derive(Debug, Clone)]
+pub struct RollingStd {
+    window: usize,
+    values: VecDeque<f64>,
+    mean: f64,
+    m2: f64,
+}
+
+impl RollingStd {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            values: VecDeque::new(),
+            mean: 0.0,
+            m2: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        self.values.push_back(value);
+        let n = self.values.len() as f64;
+        let delta = value - self.mean;
+        self.mean += delta / n;
+        self.m2 += delta * (value - self.mean);
+
+        if self.values.len() > self.window {
+            let old = self.values.pop_front().unwrap_or_default();
+            let n = self.values.len() as f64;
+            let delta = old - self.mean;
+            self.mean -= delta / n;
+            self.m2 -= delta * (old - self.mean);
+        }
+        self.m2 = self.m2.max(0.0);
+        self.std()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        self.is_full().then_some(self.mean)
+    }
+
+    pub fn std(&self) -> Option<f64> {
+        self.is_full()
+            .then(|| (self.m2 / self.window as f64).sqrt())
+    }
+
+    fn is_full(&self) -> bool {
+        self.values.len() == self.window
+    }
+}
+
+/// Z-score of the latest value against the window it belongs to; `None` while
+/// the window is filling or flat
+#[derive(Debug, Clone)]
+pub struct ZScore {
+    stats: RollingStd,
+}
+
+impl ZScore {
+    pub fn new(window: usize) -> Self {
+        Self {
+            stats: RollingStd::new(window),
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        let std = self.stats.update(value)?;
+        let mean = self.stats.mean()?;
+        (std > 1e-12).then(|| (value - mean) / std)
+    }
+}
+
+/// Wilder's relative strength index over `period` changes, 0 to 100
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    period: usize,
+    previous: Option<f64>,
+    changes: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            previous: None,
+            changes: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        let previous = self.previous.replace(value)?;
+        let change = value - previous;
+        let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+        self.changes += 1;
+        let period = self.period as f64;
+        if self.changes <= self.period {
+            // Seed with the plain average of the first `period` changes
+            self.avg_gain += gain / period;
+            self.avg_loss += loss / period;
+        } else {
+            self.avg_gain = (self.avg_gain * (period - 1.0) + gain) / period;
+            self.avg_loss = (self.avg_loss * (period - 1.0) + loss) / period;
+        }
+        if self.changes < self.period {
+            return None;
+        }
+        Some(if self.avg_loss == 0.0 {
+            if self.avg_gain == 0.0 {
+                50.0
+            } else {
+                100.0
+            }
+        } else {
+            100.0 - 100.0 / (1.0 + self.avg_gain / self.avg_loss)
+        })
+    }
+}
+
+/// Wilder's average true range over `period` bars
+#[derive(Debug, Clone)]
+pub struct Atr {
+    period: usize,
+    previous_close: Option<f64>,
+    seen: usize,
+    atr: f64,
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            previous_close: None,
+            seen: 0,
+            atr: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, bar: &Bar) -> Option<f64> {
+        let range = bar.high - bar.low;
+        let true_range = match self.previous_close.replace(bar.close) {
+            Some(close) => range
+                .max((bar.high - close).abs())
+                .max((bar.low - close).abs()),
+            None => range,
+        };
+        self.seen += 1;
+        let period = self.period as f64;
+        if self.seen <= self.period {
+            self.atr += true_range / period;
+        } else {
+            self.atr = (self.atr * (period - 1.0) + true_range) / period;
+        }
+        self.value()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        (self.seen >= self.period).then_some(self.atr)
+    }
+}
+
+/// MACD line, its signal line and their difference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdValue {
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+/// Moving average convergence/divergence: `EMA(fast) - EMA(slow)` with an
+/// EMA signal line over the MACD values once both averages are warm
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        Self {
+            fast: Ema::new(fast),
+            slow: Ema::new(slow),
+            signal: Ema::new(signal),
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<MacdValue> {
+        let fast = self.fast.update(value);
+        let slow = self.slow.update(value);
+        let macd = fast? - slow?;
+        let signal = self.signal.update(macd)?;
+        Some(MacdValue {
+            macd,
+            signal,
+            histogram: macd - signal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOSES: [f64; 12] = [
+        44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03,
+    ];
+
+    fn close_bar(close: f64, high: f64, low: f64) -> Bar {
+        Bar {
+            timestamp: 0,
+            symbol: "A".to_string(),
+            open: close,
+            high,
+            low,
+            close,
+            volume: 0.0,
+        }
+    }
+
+    #[test]
+    fn rolling_indicators_match_full_window_recomputation() {
+        let mut sma = Sma::new(5);
+        let mut std = RollingStd::new(5);
+        for (i, close) in CLOSES.iter().enumerate() {
+            let sma_value = sma.update(*close);
+            let std_value = std.update(*close);
+            if i < 4 {
+                assert!(sma_value.is_none() && std_value.is_none());
+                continue;
+            }
+            let window = &CLOSES[i - 4..=i];
+            let mean = window.iter().sum::<f64>() / 5.0;
+            let variance = window.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / 5.0;
+            assert!((sma_value.unwrap() - mean).abs() < 1e-9);
+            assert!((std_value.unwrap() - variance.sqrt()).abs() < 1e-9);
+        }
+
+        let mut z = ZScore::new(3);
+        assert_eq!(z.update(1.0), None);
+        assert_eq!(z.update(1.0), None);
+        assert_eq!(z.update(1.0), None, "a flat window has no z-score");
+        let value = z.update(4.0).unwrap();
+        // Window [1, 1, 4]: mean 2, std sqrt(2)
+        assert!((value - 2.0 / 2f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ema_rsi_atr_and_macd_follow_their_definitions() {
+        let mut ema = Ema::new(3);
+        assert_eq!(ema.update(10.0), None);
+        assert_eq!(ema.update(12.0), None);
+        // Seeded at 10, then alpha 0.5: 11, then 12.5
+        assert_eq!(ema.update(14.0), Some(12.5));
+
+        let mut rsi = Rsi::new(2);
+        assert_eq!(rsi.update(10.0), None);
+        assert_eq!(rsi.update(11.0), None);
+        // Gains 1, 0 and losses 0, 0.5 over the seed period
+        let value = rsi.update(10.5).unwrap();
+        assert!((value - 100.0 * 2.0 / 3.0).abs() < 1e-9);
+
+        let mut atr = Atr::new(2);
+        assert_eq!(atr.update(&close_bar(10.0, 11.0, 9.0)), None);
+        // Gap up: the true range runs from the prior close to the high
+        assert_eq!(atr.update(&close_bar(13.0, 14.0, 12.5)), Some(3.0));
+        assert_eq!(atr.update(&close_bar(13.0, 13.5, 12.5)), Some(2.0));
+
+        let mut macd = Macd::new(2, 4, 2);
+        let values: Vec<Option<MacdValue>> = CLOSES.iter().map(|c| macd.update(*c)).collect();
+        assert!(values[..4].iter().all(|v| v.is_none()));
+        let last = values.last().unwrap().unwrap();
+        assert!((last.histogram - (last.macd - last.signal)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn indicators_are_deterministic() {
+        let run = || {
+            let mut macd = Macd::new(3, 6, 3);
+            let mut rsi = Rsi::new(4);
+            let mut z = ZScore::new(5);
+            CLOSES
+                .iter()
+                .cycle()
+                .take(60)
+                .map(|c| {
+                    let m = macd.update(*c).map(|v| v.histogram.to_bits());
+                    let r = rsi.update(*c).map(f64::to_bits);
+                    let s = z.update(*c).map(f64::to_bits);
+                    (m, r, s)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+}
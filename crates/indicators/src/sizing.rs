@@ -0,0 +1,284 @@
+//! Position sizing helpers.
+//!
+//! Sizes are returned as fractions of equity or share counts; turning them
+//! into orders is left to the strategy (or a `TargetExecutor`).
+
+use schema::Bar;
+use std::collections::VecDeque;
+
+use crate::{Atr, RollingStd};
+
+/// Kelly fraction `p - (1 - p) / b` for win rate `p` and payoff ratio `b`
+/// (average win over average loss). Negative when the edge is negative.
+pub fn kelly_fraction(win_rate: f64, payoff_ratio: f64) -> f64 {
+    if payoff_ratio <= 0.0 {
+        return if win_rate >= 1.0 { 1.0 } else { -1.0 };
+    }
+    win_rate - (1.0 - win_rate) / payoff_ratio
+}
+
+/// Shares to buy or sell so that being stopped out at `stop` loses
+/// `risk_fraction` of `equity`. Zero when entry and stop coincide.
+pub fn fixed_fractional_shares(equity: f64, risk_fraction: f64, entry: f64, stop: f64) -> f64 {
+    let risk_per_share = (entry - stop).abs();
+    if risk_per_share <= 0.0 || equity <= 0.0 {
+        return 0.0;
+    }
+    equity * risk_fraction / risk_per_share
+}
+
+/// Shares worth `fraction` of `equity` at `price`. Zero for a price that is
+/// not positive.
+pub fn equity_fraction_shares(equity: f64, fraction: f64, price: f64) -> f64 {
+    if price <= 0.0 {
+        return 0.0;
+    }
+    equity * fraction / price
+}
+
+/// Notional that runs at `vol_target` given per-bar return `volatility`.
+/// Zero when the volatility is too small to scale against.
+pub fn vol_target_notional(equity: f64, vol_target: f64, volatility: f64) -> f64 {
+    if volatility < 1e-8 {
+        return 0.0;
+    }
+    equity * vol_target / volatility
+}
+
+/// `shares` scaled down, keeping their sign, so the position at `price` is
+/// worth at most `max_notional`
+pub fn cap_notional(shares: f64, price: f64, max_notional: f64) -> f64 {
+    let notional = (shares * price).abs();
+    if notional <= max_notional || notional == 0.0 {
+        return shares;
+    }
+    shares * (max_notional.max(0.0) / notional)
+}
+
+/// Fractional Kelly sizing from the last `window` trade returns.
+///
+/// Record each closed trade's return with `record`; `fraction` is the Kelly
+/// fraction estimated from them, scaled by `scale` (0.5 is "half Kelly") and
+/// clamped to `[0, cap]`.
+#[derive(Debug, Clone)]
+pub struct KellySizer {
+    window: usize,
+    scale: f64,
+    cap: f64,
+    returns: VecDeque<f64>,
+}
+
+impl KellySizer {
+    pub fn new(window: usize, scale: f64, cap: f64) -> Self {
+        Self {
+            window: window.max(1),
+            scale,
+            cap,
+            returns: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, trade_return: f64) {
+        self.returns.push_back(trade_return);
+        if self.returns.len() > self.window {
+            self.returns.pop_front();
+        }
+    }
+
+    /// Share of recorded trades with a positive return
+    pub fn win_rate(&self) -> Option<f64> {
+        if self.returns.is_empty() {
+            return None;
+        }
+        let wins = self.returns.iter().filter(|r| **r > 0.0).count();
+        Some(wins as f64 / self.returns.len() as f64)
+    }
+
+    /// Average win over average loss; `None` until there is one of each
+    pub fn payoff_ratio(&self) -> Option<f64> {
+        let average = |trades: Vec<f64>| {
+            (!trades.is_empty()).then(|| trades.iter().sum::<f64>() / trades.len() as f64)
+        };
+        let wins = average(self.returns.iter().copied().filter(|r| *r > 0.0).collect())?;
+        let losses = average(
+            self.returns
+                .iter()
+                .map(|r| -r)
+                .filter(|r| *r > 0.0)
+                .collect(),
+        )?;
+        Some(wins / losses)
+    }
+
+    /// Fraction of equity to commit; `None` until the window is full and
+    /// holds both a win and a loss
+    pub fn fraction(&self) -> Option<f64> {
+        if self.returns.len() < self.window {
+            return None;
+        }
+        let kelly = kelly_fraction(self.win_rate()?, self.payoff_ratio()?);
+        Some((kelly * self.scale).clamp(0.0, self.cap.max(0.0)))
+    }
+}
+
+/// Fixed-fractional sizing with the stop `multiple` ATRs from entry.
+///
+/// `shares` sizes a position so that hitting the stop loses `risk_fraction`
+/// of equity; wider markets get smaller positions.
+#[derive(Debug, Clone)]
+pub struct AtrRiskSizer {
+    atr: Atr,
+    multiple: f64,
+    risk_fraction: f64,
+}
+
+impl AtrRiskSizer {
+    pub fn new(period: usize, multiple: f64, risk_fraction: f64) -> Self {
+        Self {
+            atr: Atr::new(period),
+            multiple,
+            risk_fraction,
+        }
+    }
+
+    /// Update the ATR and return the stop distance in price
+    pub fn update(&mut self, bar: &Bar) -> Option<f64> {
+        self.atr.update(bar).map(|atr| atr * self.multiple)
+    }
+
+    /// Shares to trade at `entry` for `equity`; `None` during warm-up
+    pub fn shares(&self, equity: f64, entry: f64) -> Option<f64> {
+        let distance = self.atr.value()? * self.multiple;
+        Some(fixed_fractional_shares(
+            equity,
+            self.risk_fraction,
+            entry,
+            entry - distance,
+        ))
+    }
+}
+
+/// Volatility-targeted sizing from close-to-close returns.
+///
+/// Feed each close to `update`; `shares` sizes a position whose notional is
+/// `vol_target` over the rolling standard deviation of the last `window`
+/// returns, optionally capped at `max_leverage` times equity.
+#[derive(Debug, Clone)]
+pub struct VolTargetSizer {
+    returns: RollingStd,
+    last_close: Option<f64>,
+    vol_target: f64,
+    max_leverage: Option<f64>,
+}
+
+impl VolTargetSizer {
+    pub fn new(window: usize, vol_target: f64) -> Self {
+        Self {
+            returns: RollingStd::new(window),
+            last_close: None,
+            vol_target,
+            max_leverage: None,
+        }
+    }
+
+    pub fn with_max_leverage(mut self, max_leverage: f64) -> Self {
+        self.max_leverage = Some(max_leverage);
+        self
+    }
+
+    /// Record a close and return the volatility once the window is full
+    pub fn update(&mut self, close: f64) -> Option<f64> {
+        if let Some(prev) = self.last_close.replace(close) {
+            self.returns.update((close - prev) / prev);
+        }
+        self.volatility()
+    }
+
+    pub fn volatility(&self) -> Option<f64> {
+        self.returns.std()
+    }
+
+    /// Shares to hold at `price` for `equity`; `None` during warm-up
+    pub fn shares(&self, equity: f64, price: f64) -> Option<f64> {
+        let notional = vol_target_notional(equity, self.vol_target, self.volatility()?);
+        let shares = notional / price;
+        Some(match self.max_leverage {
+            Some(leverage) => cap_notional(shares, price, leverage * equity),
+            None => shares,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kelly_estimates_follow_recorded_trades() {
+        assert!((kelly_fraction(0.6, 1.0) - 0.2).abs() < 1e-12);
+        assert!(kelly_fraction(0.4, 1.0) < 0.0);
+
+        let mut sizer = KellySizer::new(4, 0.5, 0.25);
+        for r in [0.02, -0.01, 0.02] {
+            sizer.record(r);
+        }
+        assert_eq!(sizer.fraction(), None);
+        sizer.record(-0.01);
+        // p = 0.5, b = 2: full Kelly 0.25, half Kelly 0.125
+        assert_eq!(sizer.win_rate(), Some(0.5));
+        assert!((sizer.fraction().unwrap() - 0.125).abs() < 1e-12);
+
+        // Only the last four trades count: with no wins there is no payoff
+        // estimate, and a losing edge sizes to zero
+        for _ in 0..4 {
+            sizer.record(-0.01);
+        }
+        assert_eq!(sizer.payoff_ratio(), None);
+        assert_eq!(sizer.fraction(), None);
+        sizer.record(0.001);
+        assert_eq!(sizer.fraction(), Some(0.0));
+    }
+
+    #[test]
+    fn atr_stops_risk_a_fixed_fraction_of_equity() {
+        assert_eq!(fixed_fractional_shares(10_000.0, 0.01, 50.0, 48.0), 50.0);
+        assert_eq!(fixed_fractional_shares(10_000.0, 0.01, 50.0, 50.0), 0.0);
+
+        let mut sizer = AtrRiskSizer::new(2, 2.0, 0.01);
+        let bar = |high: f64, low: f64| Bar {
+            timestamp: 0,
+            symbol: "A".to_string(),
+            open: low,
+            high,
+            low,
+            close: (high + low) / 2.0,
+            volume: 0.0,
+        };
+        assert_eq!(sizer.update(&bar(101.0, 99.0)), None);
+        assert_eq!(sizer.shares(10_000.0, 100.0), None);
+        // True ranges of 2 and 2: ATR 2, stop 4 away
+        assert_eq!(sizer.update(&bar(101.0, 99.0)), Some(4.0));
+        assert_eq!(sizer.shares(10_000.0, 100.0), Some(25.0));
+    }
+
+    #[test]
+    fn volatility_targets_scale_inversely_and_respect_caps() {
+        assert_eq!(equity_fraction_shares(10_000.0, 0.5, 50.0), 100.0);
+        assert_eq!(equity_fraction_shares(10_000.0, 0.5, 0.0), 0.0);
+        assert_eq!(vol_target_notional(10_000.0, 0.01, 0.02), 5_000.0);
+        assert_eq!(vol_target_notional(10_000.0, 0.01, 0.0), 0.0);
+        assert_eq!(cap_notional(-300.0, 10.0, 1_500.0), -150.0);
+        assert_eq!(cap_notional(100.0, 10.0, 1_500.0), 100.0);
+
+        // Returns alternate +10% and -10%: population std 0.1
+        let mut sizer = VolTargetSizer::new(2, 0.05);
+        assert_eq!(sizer.update(100.0), None);
+        assert_eq!(sizer.update(110.0), None);
+        assert_eq!(sizer.shares(10_000.0, 99.0), None);
+        assert!((sizer.update(99.0).unwrap() - 0.1).abs() < 1e-12);
+        assert!((sizer.shares(10_000.0, 50.0).unwrap() - 100.0).abs() < 1e-9);
+
+        let capped = sizer.clone().with_max_leverage(0.25);
+        assert!((capped.shares(10_000.0, 50.0).unwrap() - 50.0).abs() < 1e-9);
+    }
+}
@@ -1,9 +1,13 @@
 #![forbid(unsafe_code)]
 
 pub mod market_data;
+pub mod quality;
 pub mod traits;
 pub mod types;
+pub mod validation;
 
 pub use market_data::*;
+pub use quality::*;
 pub use traits::*;
 pub use types::*;
+pub use validation::*;
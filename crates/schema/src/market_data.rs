@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::types::Bar;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum MarketEventType {
     Bar,
@@ -12,6 +12,7 @@ pub enum MarketEventType {
     OrderBookUpdate,
     OptionsChainSnapshot,
     FundamentalsSnapshot,
+    TradingStatus,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -31,7 +32,7 @@ pub enum LatencyClass {
     Unknown,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum MarketAssetClass {
     Equity,
@@ -42,7 +43,7 @@ pub enum MarketAssetClass {
     Commodity,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum QualityFlag {
     MissingSourceField,
@@ -95,11 +96,124 @@ pub struct OptionsChainPayload {
     pub contracts: Vec<OptionContractSnapshot>,
 }
 
+/// Whether a symbol can trade, from a status event until the next one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingState {
+    #[default]
+    Trading,
+    /// Nothing trades
+    Halted,
+    /// Pinned at the upper price band, so buys cannot fill
+    LimitUp,
+    /// Pinned at the lower price band, so sells cannot fill
+    LimitDown,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct FundamentalsPayload {
-    pub metric_name: String,
+pub struct TradingStatusPayload {
+    pub state: TradingState,
+    /// Venue's reason code, such as a news or volatility halt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinancialStatementType {
+    IncomeStatement,
+    BalanceSheet,
+    CashFlow,
+    KeyMetrics,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FundamentalMetric {
+    pub name: String,
     pub value: f64,
-    pub period: Option<String>,
+    pub unit: Option<String>,
+}
+
+/// Point-in-time snapshot of one financial statement for one fiscal period.
+///
+/// `as_of` is when this version of the statement became publicly known. A
+/// restatement is a later snapshot for the same period with `restated` set, so
+/// strategies only see restated figures once `as_of` has passed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FundamentalsPayload {
+    pub statement: FinancialStatementType,
+    pub period: String,
+    pub fiscal_period_start: i64,
+    pub fiscal_period_end: i64,
+    pub report_date: i64,
+    pub as_of: i64,
+    #[serde(default)]
+    pub restated: bool,
+    pub metrics: Vec<FundamentalMetric>,
+}
+
+impl FundamentalsPayload {
+    /// Look up a metric value by name
+    pub fn metric(&self, name: &str) -> Option<f64> {
+        self.metrics
+            .iter()
+            .find(|m| m.name == name)
+            .map(|m| m.value)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.period.trim().is_empty() {
+            anyhow::bail!("fundamentals snapshot missing period");
+        }
+        if self.metrics.is_empty() {
+            anyhow::bail!("fundamentals snapshot for {} has no metrics", self.period);
+        }
+        if self.fiscal_period_end < self.fiscal_period_start {
+            anyhow::bail!(
+                "fundamentals snapshot for {} ends before it starts",
+                self.period
+            );
+        }
+        if self.report_date < self.fiscal_period_end {
+            anyhow::bail!(
+                "fundamentals snapshot for {} reported before fiscal period end",
+                self.period
+            );
+        }
+        if self.as_of < self.report_date {
+            anyhow::bail!(
+                "fundamentals snapshot for {} known (as_of={}) before it was reported ({})",
+                self.period,
+                self.as_of,
+                self.report_date
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Select the latest version of each (statement, period) known at `as_of`.
+///
+/// Snapshots published after `as_of` are ignored, which keeps restated numbers
+/// from leaking into decisions made before the restatement. The result is
+/// ordered by fiscal period end, then statement type.
+pub fn point_in_time_fundamentals(
+    snapshots: &[FundamentalsPayload],
+    as_of: i64,
+) -> Vec<&FundamentalsPayload> {
+    let mut latest: Vec<&FundamentalsPayload> = Vec::new();
+    for snapshot in snapshots.iter().filter(|s| s.as_of <= as_of) {
+        match latest
+            .iter_mut()
+            .find(|l| l.statement == snapshot.statement && l.period == snapshot.period)
+        {
+            Some(existing) if snapshot.as_of >= existing.as_of => *existing = snapshot,
+            Some(_) => {}
+            None => latest.push(snapshot),
+        }
+    }
+    latest.sort_by_key(|snapshot| (snapshot.fiscal_period_end, snapshot.statement));
+    latest
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -111,6 +225,7 @@ pub enum MarketEventPayload {
     OrderBookUpdate(OrderBookPayload),
     OptionsChainSnapshot(OptionsChainPayload),
     FundamentalsSnapshot(FundamentalsPayload),
+    TradingStatus(TradingStatusPayload),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -148,6 +263,17 @@ impl EventEnvelope {
             );
         }
 
+        if let MarketEventPayload::FundamentalsSnapshot(fundamentals) = &self.payload {
+            fundamentals.validate()?;
+            if fundamentals.as_of > self.event_time {
+                anyhow::bail!(
+                    "fundamentals snapshot delivered at {} before it was known ({})",
+                    self.event_time,
+                    fundamentals.as_of
+                );
+            }
+        }
+
         Ok(())
     }
 }
@@ -161,6 +287,7 @@ impl MarketEventPayload {
             Self::OrderBookUpdate(_) => MarketEventType::OrderBookUpdate,
             Self::OptionsChainSnapshot(_) => MarketEventType::OptionsChainSnapshot,
             Self::FundamentalsSnapshot(_) => MarketEventType::FundamentalsSnapshot,
+            Self::TradingStatus(_) => MarketEventType::TradingStatus,
         }
     }
 }
@@ -365,6 +492,67 @@ mod tests {
         assert!(validate_events_for_tier(&[trade_event], FidelityTier::Tier2TickQuote).is_ok());
     }
 
+    fn sample_fundamentals(as_of: i64, revenue: f64, restated: bool) -> FundamentalsPayload {
+        FundamentalsPayload {
+            statement: FinancialStatementType::IncomeStatement,
+            period: "FY2023".to_string(),
+            fiscal_period_start: 1_672_531_200,
+            fiscal_period_end: 1_703_980_800,
+            report_date: 1_706_745_600,
+            as_of,
+            restated,
+            metrics: vec![
+                FundamentalMetric {
+                    name: "revenue".to_string(),
+                    value: revenue,
+                    unit: Some("USD".to_string()),
+                },
+                FundamentalMetric {
+                    name: "eps".to_string(),
+                    value: 6.1,
+                    unit: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn fundamentals_validation_rejects_early_delivery() {
+        let snapshot = sample_fundamentals(1_706_745_600, 383.3e9, false);
+        assert!(snapshot.validate().is_ok());
+        assert_eq!(snapshot.metric("eps"), Some(6.1));
+
+        let event = EventEnvelope {
+            event_type: MarketEventType::FundamentalsSnapshot,
+            symbol: "AAPL".to_string(),
+            event_time: 1_706_745_599,
+            ingest_time: 1_706_745_600,
+            source_id: "filings".to_string(),
+            quality_flags: vec![],
+            payload: MarketEventPayload::FundamentalsSnapshot(snapshot),
+        };
+        assert!(event.validate_required_fields().is_err());
+    }
+
+    #[test]
+    fn point_in_time_fundamentals_hides_future_restatements() {
+        let snapshots = vec![
+            sample_fundamentals(1_706_745_600, 383.3e9, false),
+            sample_fundamentals(1_720_000_000, 380.0e9, true),
+        ];
+
+        let before = point_in_time_fundamentals(&snapshots, 1_710_000_000);
+        assert_eq!(before.len(), 1);
+        assert_eq!(before[0].metric("revenue"), Some(383.3e9));
+
+        let after = point_in_time_fundamentals(&snapshots, 1_720_000_000);
+        assert_eq!(after.len(), 1);
+        assert!(after[0].restated);
+        assert_eq!(after[0].metric("revenue"), Some(380.0e9));
+
+        assert!(point_in_time_fundamentals(&snapshots, 1_700_000_000).is_empty());
+    }
+
     #[test]
     fn provider_capability_check_reports_unsupported() {
         let capabilities = ProviderCapabilityDeclaration {
@@ -0,0 +1,364 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::market_data::{EventEnvelope, MarketEventType, QualityFlag};
+
+/// Gaps longer than this multiple of the expected interval count as missing sessions
+const GAP_TOLERANCE_MULTIPLE: f64 = 1.5;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Gap statistics computed over consecutive events of the same symbol and type
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GapStatistics {
+    /// Interval used to decide what counts as a gap (explicit or inferred median)
+    pub expected_interval: i64,
+    pub gap_count: usize,
+    pub max_gap: i64,
+    pub mean_interval: f64,
+}
+
+/// Data quality report for a canonical event batch
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataQualityReport {
+    pub total_events: usize,
+    pub symbols: Vec<String>,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub missing_sessions: usize,
+    pub gaps: GapStatistics,
+    pub flag_counts: BTreeMap<QualityFlag, usize>,
+    pub flagged_events: usize,
+    pub duplicate_events: usize,
+    pub out_of_order_events: usize,
+    pub out_of_order_ratio: f64,
+    /// Overall quality score in [0, 100]
+    pub score: f64,
+}
+
+impl DataQualityReport {
+    /// Fraction of expected sessions that are present
+    pub fn completeness(&self) -> f64 {
+        let expected = self.total_events + self.missing_sessions;
+        if expected == 0 {
+            return 1.0;
+        }
+        1.0 - self.missing_sessions as f64 / expected as f64
+    }
+
+    pub fn duplicate_ratio(&self) -> f64 {
+        if self.total_events == 0 {
+            return 0.0;
+        }
+        self.duplicate_events as f64 / self.total_events as f64
+    }
+
+    pub fn flagged_ratio(&self) -> f64 {
+        if self.total_events == 0 {
+            return 0.0;
+        }
+        self.flagged_events as f64 / self.total_events as f64
+    }
+}
+
+/// Scan a canonical event batch and score its quality.
+///
+/// Events are examined in the order given, so out-of-order counts reflect the
+/// source ordering before any deterministic sort. When `expected_interval` is
+/// `None`, the median spacing between consecutive events of a symbol is used.
+///
+/// Daily or slower data is held to the NYSE session calendar: weekends and
+/// exchange holidays inside a gap are not missing sessions.
+pub fn assess_data_quality(
+    events: &[EventEnvelope],
+    expected_interval: Option<i64>,
+) -> DataQualityReport {
+    if events.is_empty() {
+        return DataQualityReport {
+            score: 100.0,
+            ..Default::default()
+        };
+    }
+
+    let mut flag_counts = BTreeMap::new();
+    let mut flagged_events = 0;
+    let mut seen = HashSet::new();
+    let mut duplicate_events = 0;
+    let mut out_of_order_events = 0;
+    let mut last_time: HashMap<(&str, MarketEventType), i64> = HashMap::new();
+    let mut streams: BTreeMap<(&str, MarketEventType), Vec<i64>> = BTreeMap::new();
+
+    for event in events {
+        if !event.quality_flags.is_empty() {
+            flagged_events += 1;
+        }
+        for flag in &event.quality_flags {
+            *flag_counts.entry(*flag).or_insert(0) += 1;
+        }
+
+        if !seen.insert((event.symbol.as_str(), event.event_type, event.event_time)) {
+            duplicate_events += 1;
+        }
+
+        let key = (event.symbol.as_str(), event.event_type);
+        if let Some(prev) = last_time.get(&key) {
+            if event.event_time < *prev {
+                out_of_order_events += 1;
+            }
+        }
+        last_time.insert(key, event.event_time);
+
+        streams
+            .entry((event.symbol.as_str(), event.event_type))
+            .or_default()
+            .push(event.event_time);
+    }
+
+    // Interval statistics over each sorted, de-duplicated stream
+    let mut intervals = Vec::new();
+    for times in streams.values_mut() {
+        times.sort_unstable();
+        times.dedup();
+        intervals.extend(times.windows(2).map(|w| (w[0], w[1] - w[0])));
+    }
+
+    let spacings: Vec<i64> = intervals.iter().map(|&(_, interval)| interval).collect();
+    let expected = expected_interval.unwrap_or_else(|| median(&spacings));
+    let mut gaps = GapStatistics {
+        expected_interval: expected,
+        ..Default::default()
+    };
+    let mut missing_sessions = 0;
+    if !spacings.is_empty() {
+        gaps.mean_interval = spacings.iter().sum::<i64>() as f64 / spacings.len() as f64;
+        gaps.max_gap = spacings.iter().copied().max().unwrap_or(0);
+    }
+    if expected > 0 {
+        for &(start, interval) in &intervals {
+            if interval as f64 <= expected as f64 * GAP_TOLERANCE_MULTIPLE {
+                continue;
+            }
+            let slots = (interval / expected - 1).max(1);
+            let missing = if expected >= SECONDS_PER_DAY {
+                (1..=slots)
+                    .filter(|k| is_session_day(start + k * expected))
+                    .count()
+            } else {
+                slots as usize
+            };
+            if missing > 0 {
+                gaps.gap_count += 1;
+                missing_sessions += missing;
+            }
+        }
+    }
+
+    let pairs = events.len().saturating_sub(1).max(1);
+    let mut symbols: Vec<String> = events.iter().map(|e| e.symbol.clone()).collect();
+    symbols.sort();
+    symbols.dedup();
+
+    let mut report = DataQualityReport {
+        total_events: events.len(),
+        symbols,
+        start_time: events.iter().map(|e| e.event_time).min().unwrap_or(0),
+        end_time: events.iter().map(|e| e.event_time).max().unwrap_or(0),
+        missing_sessions,
+        gaps,
+        flag_counts,
+        flagged_events,
+        duplicate_events,
+        out_of_order_events,
+        out_of_order_ratio: out_of_order_events as f64 / pairs as f64,
+        score: 0.0,
+    };
+
+    // Completeness, uniqueness and ordering dominate; quality flags only shave the score
+    let flag_factor = 1.0 - 0.1 * report.flagged_ratio();
+    report.score = 100.0
+        * report.completeness()
+        * (1.0 - report.duplicate_ratio())
+        * (1.0 - report.out_of_order_ratio)
+        * flag_factor;
+
+    report
+}
+
+/// Whether the UTC day of `timestamp` is a regular NYSE session: a weekday
+/// other than an exchange holiday. One-off closures are not modelled.
+fn is_session_day(timestamp: i64) -> bool {
+    let Some(date) = DateTime::from_timestamp(timestamp, 0).map(|t| t.date_naive()) else {
+        return true;
+    };
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !is_nyse_holiday(date)
+}
+
+fn is_nyse_holiday(date: NaiveDate) -> bool {
+    let year = date.year();
+    // Weekend holidays close the nearest weekday; New Year's Day on a
+    // Saturday closes nothing, since its Friday is in the year before
+    let observed = |month, day| {
+        NaiveDate::from_ymd_opt(year, month, day).map(|d| match d.weekday() {
+            Weekday::Sat => d - Duration::days(1),
+            Weekday::Sun => d + Duration::days(1),
+            _ => d,
+        })
+    };
+    let nth = |month, weekday, n| NaiveDate::from_weekday_of_month_opt(year, month, weekday, n);
+    let holidays = [
+        observed(1, 1),
+        nth(1, Weekday::Mon, 3),
+        nth(2, Weekday::Mon, 3),
+        easter_sunday(year).map(|d| d - Duration::days(2)),
+        nth(5, Weekday::Mon, 5).or_else(|| nth(5, Weekday::Mon, 4)),
+        observed(6, 19).filter(|_| year >= 2022),
+        observed(7, 4),
+        nth(9, Weekday::Mon, 1),
+        nth(11, Weekday::Thu, 4),
+        observed(12, 25),
+    ];
+    holidays.contains(&Some(date))
+}
+
+/// Western Easter, by the anonymous Gregorian algorithm
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let (a, b, c) = (year % 19, year / 100, year % 100);
+    let g = (b - (b + 8) / 25 + 1) / 3;
+    let h = (19 * a + b - b / 4 - g + 15) % 30;
+    let l = (32 + 2 * (b % 4) + 2 * (c / 4) - h - c % 4) % 7;
+    let n = h + l - 7 * ((a + 11 * h + 22 * l) / 451) + 114;
+    NaiveDate::from_ymd_opt(year, (n / 31) as u32, (n % 31 + 1) as u32)
+}
+
+fn median(values: &[i64]) -> i64 {
+    if values.is_empty() {
+        return 0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    sorted[sorted.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market_data::MarketEventPayload;
+    use crate::types::Bar;
+
+    fn bar_event(time: i64, flags: Vec<QualityFlag>) -> EventEnvelope {
+        EventEnvelope {
+            event_type: MarketEventType::Bar,
+            symbol: "AAPL".to_string(),
+            event_time: time,
+            ingest_time: time + 1,
+            source_id: "test".to_string(),
+            quality_flags: flags,
+            payload: MarketEventPayload::Bar(Bar {
+                timestamp: time,
+                symbol: "AAPL".to_string(),
+                open: 100.0,
+                high: 102.0,
+                low: 99.0,
+                close: 101.0,
+                volume: 1_000.0,
+            }),
+        }
+    }
+
+    #[test]
+    fn clean_batch_scores_perfectly() {
+        let events: Vec<_> = (1..=5).map(|i| bar_event(i * 60, vec![])).collect();
+        let report = assess_data_quality(&events, None);
+
+        assert_eq!(report.total_events, 5);
+        assert_eq!(report.gaps.expected_interval, 60);
+        assert_eq!(report.missing_sessions, 0);
+        assert_eq!(report.duplicate_events, 0);
+        assert_eq!(report.out_of_order_events, 0);
+        assert!((report.score - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn detects_gaps_duplicates_and_disorder() {
+        let events = vec![
+            bar_event(60, vec![]),
+            bar_event(120, vec![QualityFlag::LateSourceData]),
+            bar_event(120, vec![]),
+            bar_event(360, vec![]),
+            bar_event(300, vec![]),
+            bar_event(420, vec![]),
+        ];
+        let report = assess_data_quality(&events, Some(60));
+
+        // 120 -> 300 skips 180 and 240
+        assert_eq!(report.missing_sessions, 2);
+        assert_eq!(report.gaps.gap_count, 1);
+        assert_eq!(report.gaps.max_gap, 180);
+        assert_eq!(report.duplicate_events, 1);
+        assert_eq!(report.out_of_order_events, 1);
+        assert_eq!(
+            report.flag_counts.get(&QualityFlag::LateSourceData),
+            Some(&1)
+        );
+        assert!(report.score < 100.0);
+    }
+
+    #[test]
+    fn daily_bars_skip_weekends_and_exchange_holidays() {
+        let start = NaiveDate::from_ymd_opt(2023, 1, 2).unwrap();
+        let days: Vec<NaiveDate> = (0..365)
+            .map(|i| start + Duration::days(i))
+            .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+            .collect();
+        let daily = |days: &[NaiveDate]| -> Vec<EventEnvelope> {
+            days.iter()
+                .map(|d| {
+                    bar_event(
+                        d.and_hms_opt(21, 0, 0).unwrap().and_utc().timestamp(),
+                        vec![],
+                    )
+                })
+                .collect()
+        };
+
+        let weekdays = assess_data_quality(&daily(&days), None);
+        assert_eq!(weekdays.gaps.expected_interval, SECONDS_PER_DAY);
+        assert_eq!(weekdays.missing_sessions, 0);
+        assert_eq!(weekdays.gaps.gap_count, 0);
+
+        // Without Good Friday, Juneteenth, Thanksgiving and Christmas
+        let holidays = [(4, 7), (6, 19), (11, 23), (12, 25)]
+            .map(|(m, d)| NaiveDate::from_ymd_opt(2023, m, d).unwrap());
+        let sessions: Vec<NaiveDate> = days
+            .iter()
+            .copied()
+            .filter(|d| !holidays.contains(d))
+            .collect();
+        assert_eq!(
+            assess_data_quality(&daily(&sessions), None).missing_sessions,
+            0
+        );
+
+        // A missing Wednesday is still a missing session
+        let wednesday = NaiveDate::from_ymd_opt(2023, 3, 15).unwrap();
+        let missing: Vec<NaiveDate> = sessions
+            .iter()
+            .copied()
+            .filter(|d| *d != wednesday)
+            .collect();
+        let report = assess_data_quality(&daily(&missing), None);
+        assert_eq!((report.missing_sessions, report.gaps.gap_count), (1, 1));
+    }
+
+    #[test]
+    fn report_round_trips_through_json() {
+        let events = vec![bar_event(60, vec![QualityFlag::DerivedValue])];
+        let report = assess_data_quality(&events, None);
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("derived_value"));
+        let parsed: DataQualityReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, report);
+    }
+}
@@ -1,17 +1,29 @@
-use crate::types::{Bar, Fill, Order, Portfolio};
+use crate::types::{
+    Bar, BrokerResult, Fill, MarketContext, Order, OrderAmendment, OrderInstruction, Portfolio,
+    TargetPosition, TimerEvent,
+};
 use crate::{
     AdapterRequest, EventEnvelope, NormalizedEventBatch, ProviderCapabilityDeclaration,
     ProviderRecord,
 };
 use anyhow::Result;
+use std::future::Future;
 
 /// Trait for providing market data
 pub trait DataFeed {
     /// Get the next bar. Returns None when data is exhausted.
-    fn next_bar(&mut self) -> Option<Bar>;
+    ///
+    /// The bar is lent rather than cloned, so a long run doesn't allocate
+    /// a copy of every bar it reads.
+    fn next_bar(&mut self) -> Option<&Bar>;
 
     /// Reset the data feed to the beginning
     fn reset(&mut self);
+
+    /// Bars in the whole feed, when known without reading it
+    fn total_bars(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Trait for trading strategies
@@ -19,6 +31,50 @@ pub trait Strategy {
     /// Called when a new bar arrives. Strategy can return orders to submit.
     fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order>;
 
+    /// Called when a day, week or month of bars has ended, before the next
+    /// bar reaches `on_bar`. Orders returned wait for the next bar of their
+    /// own symbol.
+    fn on_timer(&mut self, _timer: &TimerEvent, _portfolio: &Portfolio) -> Vec<Order> {
+        Vec::new()
+    }
+
+    /// Called once the bar's orders have reached the broker, with the orders
+    /// still resting there. Instructions take effect from the next bar.
+    fn manage_orders(&mut self, _bar: &Bar, _open_orders: &[Order]) -> Vec<OrderInstruction> {
+        Vec::new()
+    }
+
+    /// Bar timestamps the strategy needs to seed its indicators. The engine
+    /// feeds them through `on_bar` and `on_timer` but drops their orders and
+    /// starts the equity curve after them.
+    fn warmup_bars(&self) -> usize {
+        0
+    }
+
+    /// Get strategy name
+    fn name(&self) -> &str;
+}
+
+/// Trait for strategies that say what to hold rather than how to trade.
+///
+/// The engine's `TargetExecutor` turns the targets into orders, so a
+/// `TargetStrategy` runs anywhere a `Strategy` does.
+pub trait TargetStrategy {
+    /// Called when a new bar arrives. Returned targets replace any earlier
+    /// target for the same symbol; symbols not mentioned are left alone.
+    fn targets(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<TargetPosition>;
+
+    /// Called when a day, week or month of bars has ended, before the next
+    /// bar reaches `targets`
+    fn on_timer(&mut self, _timer: &TimerEvent, _portfolio: &Portfolio) -> Vec<TargetPosition> {
+        Vec::new()
+    }
+
+    /// Bar timestamps of warm-up, as `Strategy::warmup_bars`
+    fn warmup_bars(&self) -> usize {
+        0
+    }
+
     /// Get strategy name
     fn name(&self) -> &str;
 }
@@ -28,23 +84,138 @@ pub trait BrokerSim {
     /// Process orders and return fills
     fn process_orders(&mut self, orders: Vec<Order>, bar: &Bar) -> Result<Vec<Fill>>;
 
+    /// Process orders for `portfolio`. Brokers that check buying power
+    /// reject the orders it cannot cover instead of filling them, and
+    /// brokers that lend shares charge borrow fees on its shorts.
+    fn process_orders_for(
+        &mut self,
+        orders: Vec<Order>,
+        bar: &Bar,
+        _portfolio: &Portfolio,
+    ) -> Result<BrokerResult> {
+        Ok(BrokerResult {
+            fills: self.process_orders(orders, bar)?,
+            ..BrokerResult::default()
+        })
+    }
+
+    /// Orders resting at the broker, each with its `order_id`
+    fn open_orders(&self) -> Vec<Order> {
+        Vec::new()
+    }
+
+    /// Cancel a resting order and return it
+    fn cancel(&mut self, order_id: u64) -> Result<Order> {
+        anyhow::bail!("{} cannot cancel order {}", self.name(), order_id)
+    }
+
+    /// Cancel every order the broker holds, including any still on their
+    /// way to the market, and return them
+    fn cancel_all(&mut self) -> Result<Vec<Order>> {
+        let mut cancelled = Vec::new();
+        for order in self.open_orders() {
+            if let Some(order_id) = order.order_id {
+                cancelled.push(self.cancel(order_id)?);
+            }
+        }
+        Ok(cancelled)
+    }
+
+    /// Change a resting order's quantity or prices
+    fn amend(&mut self, order_id: u64, _amendment: &OrderAmendment) -> Result<()> {
+        anyhow::bail!("{} cannot amend order {}", self.name(), order_id)
+    }
+
+    /// Carry out a strategy's instruction
+    fn apply_instruction(&mut self, instruction: &OrderInstruction) -> Result<()> {
+        match instruction {
+            OrderInstruction::Cancel { order_id } => self.cancel(*order_id).map(|_| ()),
+            OrderInstruction::Amend {
+                order_id,
+                amendment,
+            } => self.amend(*order_id, amendment),
+        }
+    }
+
+    /// Get broker name
+    fn name(&self) -> &str;
+}
+
+/// Asynchronous counterpart of `BrokerSim` for brokers reached over a
+/// network, so a strategy the engine backtests can trade a real account
+pub trait LiveBroker: Send {
+    /// Submit the orders placed on `bar` and return the fills and
+    /// rejections the broker has reported since the previous call
+    fn process_orders_for(
+        &mut self,
+        orders: Vec<Order>,
+        bar: &Bar,
+        portfolio: &Portfolio,
+    ) -> impl Future<Output = Result<BrokerResult>> + Send;
+
+    /// Orders resting at the broker, each with its `order_id`
+    fn open_orders(&mut self) -> impl Future<Output = Result<Vec<Order>>> + Send;
+
+    /// Cancel a resting order
+    fn cancel(&mut self, order_id: u64) -> impl Future<Output = Result<()>> + Send;
+
+    /// Change a resting order's quantity or prices
+    fn amend(
+        &mut self,
+        order_id: u64,
+        amendment: &OrderAmendment,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Carry out a strategy's instruction
+    fn apply_instruction(
+        &mut self,
+        instruction: &OrderInstruction,
+    ) -> impl Future<Output = Result<()>> + Send {
+        async move {
+            match instruction {
+                OrderInstruction::Cancel { order_id } => self.cancel(*order_id).await,
+                OrderInstruction::Amend {
+                    order_id,
+                    amendment,
+                } => self.amend(*order_id, amendment).await,
+            }
+        }
+    }
+
     /// Get broker name
     fn name(&self) -> &str;
 }
 
 /// Trait for calculating trading costs
 pub trait CostModel {
-    /// Calculate commission for a trade
-    fn calculate_commission(&self, quantity: f64, price: f64) -> f64;
+    /// Calculate commission for trading `quantity` at `price` in `market`
+    fn calculate_commission(&self, quantity: f64, price: f64, market: &MarketContext) -> f64;
+
+    /// Calculate slippage (price impact) of trading `quantity` in `market`
+    fn calculate_slippage(
+        &self,
+        quantity: f64,
+        price: f64,
+        side: crate::types::Side,
+        market: &MarketContext,
+    ) -> f64;
+
+    /// Exchange and regulatory fees for trading `quantity` at `price`
+    fn calculate_exchange_fees(&self, _quantity: f64, _price: f64, _market: &MarketContext) -> f64 {
+        0.0
+    }
 
-    /// Calculate slippage (price impact)
-    fn calculate_slippage(&self, quantity: f64, price: f64, side: crate::types::Side) -> f64;
+    /// Borrow fee for holding `notional` short for `days` calendar days at
+    /// an annual `fee_rate`, on an actual/360 day count
+    fn calculate_borrow_fee(&self, notional: f64, fee_rate: f64, days: f64) -> f64 {
+        notional.abs() * fee_rate * days / 360.0
+    }
 }
 
 /// Trait for canonical event feeds
 pub trait CanonicalEventFeed {
     /// Get the next market event. Returns None when data is exhausted.
-    fn next_event(&mut self) -> Option<EventEnvelope>;
+    fn next_event(&mut self) -> Option<&EventEnvelope>;
 
     /// Reset the event feed to the beginning.
     fn reset_events(&mut self);
@@ -95,11 +266,48 @@ pub trait MarketDataAdapter {
 
 // Implement CostModel for Box<dyn CostModel> to allow dynamic dispatch
 impl CostModel for Box<dyn CostModel> {
-    fn calculate_commission(&self, quantity: f64, price: f64) -> f64 {
-        (**self).calculate_commission(quantity, price)
+    fn calculate_commission(&self, quantity: f64, price: f64, market: &MarketContext) -> f64 {
+        (**self).calculate_commission(quantity, price, market)
+    }
+
+    fn calculate_slippage(
+        &self,
+        quantity: f64,
+        price: f64,
+        side: crate::types::Side,
+        market: &MarketContext,
+    ) -> f64 {
+        (**self).calculate_slippage(quantity, price, side, market)
+    }
+
+    fn calculate_exchange_fees(&self, quantity: f64, price: f64, market: &MarketContext) -> f64 {
+        (**self).calculate_exchange_fees(quantity, price, market)
+    }
+
+    fn calculate_borrow_fee(&self, notional: f64, fee_rate: f64, days: f64) -> f64 {
+        (**self).calculate_borrow_fee(notional, fee_rate, days)
+    }
+}
+
+// Implement Strategy for Box<dyn Strategy> so spec-selected strategies can drive the engine
+impl Strategy for Box<dyn Strategy> {
+    fn on_bar(&mut self, bar: &Bar, portfolio: &Portfolio) -> Vec<Order> {
+        (**self).on_bar(bar, portfolio)
+    }
+
+    fn on_timer(&mut self, timer: &TimerEvent, portfolio: &Portfolio) -> Vec<Order> {
+        (**self).on_timer(timer, portfolio)
+    }
+
+    fn manage_orders(&mut self, bar: &Bar, open_orders: &[Order]) -> Vec<OrderInstruction> {
+        (**self).manage_orders(bar, open_orders)
+    }
+
+    fn warmup_bars(&self) -> usize {
+        (**self).warmup_bars()
     }
 
-    fn calculate_slippage(&self, quantity: f64, price: f64, side: crate::types::Side) -> f64 {
-        (**self).calculate_slippage(quantity, price, side)
+    fn name(&self) -> &str {
+        (**self).name()
     }
 }
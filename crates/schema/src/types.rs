@@ -25,6 +25,10 @@ pub enum Side {
 pub enum OrderType {
     Market,
     Limit,
+    /// Becomes a market order once the stop price trades
+    Stop,
+    /// Becomes a limit order once the stop price trades
+    StopLimit,
 }
 
 /// An order to be submitted
@@ -35,6 +39,259 @@ pub struct Order {
     pub quantity: f64,
     pub order_type: OrderType,
     pub limit_price: Option<f64>,
+    /// Trigger price of `Stop` and `StopLimit` orders
+    #[serde(default)]
+    pub stop_price: Option<f64>,
+    /// Identifier to cancel or amend the order by; assigned by the broker
+    /// in submission order when left empty
+    #[serde(default)]
+    pub order_id: Option<u64>,
+    /// Exits to place once the order fills
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bracket: Option<Bracket>,
+}
+
+/// Exits attached to an entry order.
+///
+/// Each fill of the entry places a take-profit limit and a stop-loss stop for
+/// the filled quantity on the opposite side. The two legs are one-cancels-
+/// other: a fill on either cancels the same quantity of the other.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bracket {
+    pub take_profit: f64,
+    pub stop_loss: f64,
+}
+
+/// Changes to a resting order; fields left `None` keep their value
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderAmendment {
+    /// New unfilled quantity
+    pub quantity: Option<f64>,
+    pub limit_price: Option<f64>,
+    pub stop_price: Option<f64>,
+}
+
+/// What a strategy wants done with one of its resting orders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderInstruction {
+    Cancel {
+        order_id: u64,
+    },
+    Amend {
+        order_id: u64,
+        amendment: OrderAmendment,
+    },
+}
+
+/// Why a broker refused an order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum RejectionReason {
+    /// Filling would take the initial margin required past the account's
+    /// equity
+    InsufficientBuyingPower { required: f64, available: f64 },
+    /// A short sale found no shares to borrow; `hard_to_borrow` is false
+    /// when the symbol cannot be shorted at all
+    LocateFailed { hard_to_borrow: bool },
+    /// A live broker refused the order
+    Refused { message: String },
+    /// The quantity is not a whole number of the instrument's lots
+    OffLot { lot_size: f64 },
+    /// A price is not on the instrument's tick
+    OffTick { tick_size: f64 },
+    /// A pre-trade risk check refused it for breaching `limit`, a multiple
+    /// of equity
+    RiskLimit { limit: String, maximum: f64 },
+    /// A buy would cost more than the cash on hand
+    InsufficientCash { required: f64, available: f64 },
+    /// A sell would go short with shorting disabled
+    ExceedsHoldings { held: f64 },
+    /// The drawdown kill switch has stopped trading
+    KillSwitch,
+}
+
+/// An order a broker refused instead of filling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderRejection {
+    pub timestamp: i64,
+    /// The order as it stood when refused, so a partially filled order
+    /// carries only its unfilled quantity
+    pub order: Order,
+    pub reason: RejectionReason,
+}
+
+/// Stage of an order's life at a broker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum OrderEventKind {
+    /// Handed to the broker, as submitted
+    Submitted {
+        order: Order,
+    },
+    /// Reached the market, after any latency
+    Accepted,
+    PartiallyFilled {
+        quantity: f64,
+        price: f64,
+        /// Quantity still working
+        remaining: f64,
+    },
+    Filled {
+        quantity: f64,
+        price: f64,
+    },
+    /// Withdrawn with `remaining` unfilled, by the strategy or because an
+    /// OCO sibling traded
+    Cancelled {
+        remaining: f64,
+    },
+    Rejected(RejectionReason),
+}
+
+/// One step in an order's life at a broker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderEvent {
+    pub timestamp: i64,
+    pub order_id: u64,
+    pub symbol: String,
+    #[serde(flatten)]
+    pub kind: OrderEventKind,
+}
+
+/// Borrow fee accrued on a short position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BorrowCharge {
+    pub timestamp: i64,
+    pub symbol: String,
+    /// Shares short over the period
+    pub quantity: f64,
+    /// Price the short was valued at
+    pub price: f64,
+    /// Calendar days accrued
+    pub days: i64,
+    pub fee: f64,
+}
+
+/// Funding exchanged on a perpetual futures position at one funding time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FundingPayment {
+    pub timestamp: i64,
+    pub symbol: String,
+    /// Signed position (negative for short)
+    pub quantity: f64,
+    /// Price the position was valued at
+    pub price: f64,
+    /// Funding rate for the interval
+    pub rate: f64,
+    /// Amount the account paid; negative when it received funding
+    pub amount: f64,
+}
+
+/// What became of an option position at expiry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryOutcome {
+    /// A long position in the money was exercised
+    Exercised,
+    /// A short position in the money was assigned
+    Assigned,
+    /// Out of the money, so it lapsed worthless
+    Expired,
+}
+
+/// An option position settled at expiry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptionExpiration {
+    pub timestamp: i64,
+    pub symbol: String,
+    pub underlying: String,
+    /// Signed contracts (negative for short)
+    pub quantity: f64,
+    pub strike: f64,
+    /// Underlying price the option settled against
+    pub underlying_price: f64,
+    pub outcome: ExpiryOutcome,
+}
+
+/// An account found with less equity than its maintenance margin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarginCall {
+    pub timestamp: i64,
+    pub equity: f64,
+    pub maintenance_required: f64,
+}
+
+/// The drawdown kill switch tripping, after which the account is flattened
+/// and the strategy's orders are refused
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KillSwitchTrip {
+    /// Close of the bar whose equity mark breached the threshold
+    pub timestamp: i64,
+    pub equity: f64,
+    pub peak_equity: f64,
+    /// As a fraction of `peak_equity`
+    pub drawdown: f64,
+    /// Threshold the drawdown exceeded
+    pub max_drawdown: f64,
+}
+
+/// What a broker did with the orders of one bar
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BrokerResult {
+    pub fills: Vec<Fill>,
+    pub rejections: Vec<OrderRejection>,
+    pub borrow_charges: Vec<BorrowCharge>,
+    pub funding_payments: Vec<FundingPayment>,
+    /// Options settled at expiry, whose closing and exercise fills are in
+    /// `fills`
+    pub option_expirations: Vec<OptionExpiration>,
+    /// Margin calls met by forced liquidations, whose fills are in `fills`
+    pub margin_calls: Vec<MarginCall>,
+    /// Lifecycle of every order the broker handled, in the order it happened
+    pub order_events: Vec<OrderEvent>,
+}
+
+/// Holding a target-based strategy wants in one symbol
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Target {
+    /// Signed share count (negative for short)
+    Shares(f64),
+    /// Signed fraction of portfolio equity
+    Weight(f64),
+}
+
+/// A target for one symbol, returned by a `TargetStrategy`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetPosition {
+    pub symbol: String,
+    pub target: Target,
+}
+
+/// Calendar period whose end a timer marks, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerKind {
+    DailyClose,
+    /// Weeks run Monday to Sunday
+    WeekEnd,
+    MonthEnd,
+}
+
+/// A period ended, seen when the first bar after it arrives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerEvent {
+    /// Timestamp of the bar the timer fires on
+    pub timestamp: i64,
+    pub kind: TimerKind,
+}
+
+/// Market conditions an order trades in, for costs that depend on them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketContext {
+    /// Symbol the order trades
+    pub symbol: String,
+    /// Volume of the bar the order trades on
+    pub volume: f64,
 }
 
 /// A filled order (trade)
@@ -45,7 +302,56 @@ pub struct Fill {
     pub side: Side,
     pub quantity: f64,
     pub price: f64,
+    pub costs: CostBreakdown,
+}
+
+/// What a fill cost, by kind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostBreakdown {
     pub commission: f64,
+    /// Exchange and regulatory fees
+    pub exchange_fees: f64,
+    /// Price impact over the whole quantity; already in the fill price
+    pub slippage: f64,
+    /// Borrow or locate fees for a short sale
+    pub borrow: f64,
+    /// Anything else, such as currency conversion
+    pub other: f64,
+}
+
+impl CostBreakdown {
+    pub fn from_commission(commission: f64) -> Self {
+        Self {
+            commission,
+            ..Self::default()
+        }
+    }
+
+    /// Cash paid on top of the fill price, which is everything but slippage
+    pub fn charged(&self) -> f64 {
+        self.commission + self.exchange_fees + self.borrow + self.other
+    }
+
+    /// Every cost times `factor`, as for a share of a fill
+    pub fn scaled(&self, factor: f64) -> Self {
+        Self {
+            commission: self.commission * factor,
+            exchange_fees: self.exchange_fees * factor,
+            slippage: self.slippage * factor,
+            borrow: self.borrow * factor,
+            other: self.other * factor,
+        }
+    }
+}
+
+impl std::ops::AddAssign for CostBreakdown {
+    fn add_assign(&mut self, other: Self) {
+        self.commission += other.commission;
+        self.exchange_fees += other.exchange_fees;
+        self.slippage += other.slippage;
+        self.borrow += other.borrow;
+        self.other += other.other;
+    }
 }
 
 /// Current position for a symbol
@@ -78,6 +384,47 @@ impl Position {
     }
 }
 
+/// How exposed an account is at one equity mark
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExposurePoint {
+    pub timestamp: i64,
+    /// Sum of absolute position values
+    pub gross_exposure: f64,
+    /// Long position value less short
+    pub net_exposure: f64,
+    /// Gross exposure over equity; infinite with positions and no equity
+    pub leverage: f64,
+    /// Notional traded over the rolling turnover window
+    pub traded_notional: f64,
+    /// Traded notional over equity
+    pub turnover: f64,
+}
+
+impl ExposurePoint {
+    /// Ratios of the exposures and traded notional to `equity`
+    pub fn new(
+        timestamp: i64,
+        gross_exposure: f64,
+        net_exposure: f64,
+        traded_notional: f64,
+        equity: f64,
+    ) -> Self {
+        let ratio = |value: f64| match value {
+            _ if equity > 0.0 => value / equity,
+            0.0 => 0.0,
+            _ => f64::INFINITY,
+        };
+        Self {
+            timestamp,
+            gross_exposure,
+            net_exposure,
+            leverage: ratio(gross_exposure),
+            traded_notional,
+            turnover: ratio(traded_notional),
+        }
+    }
+}
+
 /// Portfolio state at a point in time
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Portfolio {
@@ -109,7 +456,7 @@ impl Portfolio {
 }
 
 /// Equity curve point
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub struct EquityPoint {
     pub timestamp: i64,
     pub equity: f64,
@@ -125,6 +472,122 @@ pub struct BacktestStats {
     pub total_return: f64,
     pub num_trades: usize,
     pub total_commission: f64,
+    /// Fill costs of the run by kind
+    #[serde(default)]
+    pub costs: CostBreakdown,
     pub sharpe_ratio: f64,
     pub max_drawdown: f64,
+    /// Downside, drawdown and per-trade measures of the run
+    #[serde(default)]
+    pub performance: PerformanceStats,
+    /// Interest and borrow fees accrued on the account, when financed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub financing: Option<FinancingStats>,
+    /// Performance against the spec's benchmark, when it names one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub benchmark: Option<RelativeStats>,
+    /// Orders cut down or refused before reaching the broker, when the run
+    /// checks them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constrained_orders: Option<ConstrainedOrders>,
+    /// Confidence intervals resampled from the bar returns, when the run
+    /// asks for them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bootstrap: Option<BootstrapStats>,
+}
+
+/// Bounds a statistic falls within at some confidence
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfidenceInterval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl ConfidenceInterval {
+    /// Whether zero lies outside the interval, so the statistic is
+    /// distinguishable from zero at its confidence
+    pub fn excludes_zero(&self) -> bool {
+        self.lower > 0.0 || self.upper < 0.0
+    }
+}
+
+/// Percentile-bootstrap confidence intervals of a run's headline stats
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BootstrapStats {
+    /// Resampled return series
+    pub samples: usize,
+    /// Share of resamples each interval covers, such as 0.95
+    pub confidence: f64,
+    /// Consecutive bar returns drawn together
+    pub block_size: usize,
+    pub sharpe_ratio: ConfidenceInterval,
+    pub total_return: ConfidenceInterval,
+    pub max_drawdown: ConfidenceInterval,
+}
+
+/// Risk-adjusted and per-trade performance of a backtest. Ratios whose
+/// denominator is zero are 0, as the Sharpe ratio is without variance.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerformanceStats {
+    /// Mean bar return over downside deviation, annualized
+    pub sortino_ratio: f64,
+    /// Annualized return over max drawdown
+    pub calmar_ratio: f64,
+    /// Standard deviation of bar returns, annualized
+    pub annualized_volatility: f64,
+    /// Round trips closed, flat to flat in one symbol
+    pub closed_trades: usize,
+    /// Share of closed trades that made money
+    pub win_rate: f64,
+    /// Gross profit over gross loss of closed trades
+    pub profit_factor: f64,
+    /// Mean PnL of closed trades, net of charged costs
+    pub average_trade_pnl: f64,
+    /// Share of bars with a position open
+    pub exposure_time: f64,
+}
+
+/// Performance of a backtest against buy-and-hold of a benchmark, over the
+/// bars both equity curves share
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RelativeStats {
+    /// The benchmark symbol
+    pub symbol: String,
+    /// Strategy total return minus benchmark total return
+    pub excess_return: f64,
+    /// Annualized standard deviation of per-bar active returns
+    pub tracking_error: f64,
+    pub information_ratio: f64,
+    pub beta: f64,
+    /// Annualized intercept of strategy returns regressed on benchmark returns
+    pub alpha: f64,
+    pub correlation: f64,
+    /// Mean strategy return over mean benchmark return, on bars the
+    /// benchmark rose
+    #[serde(default)]
+    pub up_capture: f64,
+    /// Mean strategy return over mean benchmark return, on bars the
+    /// benchmark fell
+    #[serde(default)]
+    pub down_capture: f64,
+}
+
+/// Orders pre-trade checks cut down or refused over a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConstrainedOrders {
+    /// Sent on with a smaller quantity
+    pub downsized: usize,
+    /// Never sent
+    pub rejected: usize,
+}
+
+/// Financing accrued on an account over a backtest
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FinancingStats {
+    /// Interest charged on negative cash
+    pub debit_interest: f64,
+    /// Fees charged on the market value of shorts
+    pub short_borrow_fees: f64,
+    /// Interest credited on idle cash
+    pub credit_interest: f64,
 }
@@ -0,0 +1,315 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::market_data::{EventEnvelope, MarketEventPayload};
+use crate::types::Bar;
+
+/// Kind of OHLCV consistency problem found on a bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BarViolationKind {
+    EmptySymbol,
+    NonFiniteValue,
+    NonPositivePrice,
+    HighBelowOpenClose,
+    LowAboveOpenClose,
+    NegativeVolume,
+}
+
+/// A single consistency violation on a bar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BarViolation {
+    pub kind: BarViolationKind,
+    pub message: String,
+}
+
+/// A violation located within a batch of bars
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BarIssue {
+    pub index: usize,
+    pub timestamp: i64,
+    pub symbol: String,
+    pub kind: BarViolationKind,
+    pub message: String,
+}
+
+/// Structured result of validating a batch of bars
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BarValidationReport {
+    pub bars_checked: usize,
+    pub invalid_bars: usize,
+    pub issues: Vec<BarIssue>,
+}
+
+impl Bar {
+    /// Collect every OHLCV consistency violation on this bar
+    pub fn violations(&self) -> Vec<BarViolation> {
+        let mut violations = Vec::new();
+
+        if self.symbol.trim().is_empty() {
+            violations.push(BarViolation {
+                kind: BarViolationKind::EmptySymbol,
+                message: "symbol is empty".to_string(),
+            });
+        }
+
+        let values = [
+            ("open", self.open),
+            ("high", self.high),
+            ("low", self.low),
+            ("close", self.close),
+            ("volume", self.volume),
+        ];
+        let non_finite: Vec<&str> = values
+            .iter()
+            .filter(|(_, v)| !v.is_finite())
+            .map(|(name, _)| *name)
+            .collect();
+        if !non_finite.is_empty() {
+            violations.push(BarViolation {
+                kind: BarViolationKind::NonFiniteValue,
+                message: format!("non-finite value in {}", non_finite.join(", ")),
+            });
+            // Ordering checks are meaningless once a value is NaN/inf
+            return violations;
+        }
+
+        let non_positive: Vec<&str> = values[..4]
+            .iter()
+            .filter(|(_, v)| *v <= 0.0)
+            .map(|(name, _)| *name)
+            .collect();
+        if !non_positive.is_empty() {
+            violations.push(BarViolation {
+                kind: BarViolationKind::NonPositivePrice,
+                message: format!("non-positive price in {}", non_positive.join(", ")),
+            });
+        }
+
+        if self.high < self.open.max(self.close) {
+            violations.push(BarViolation {
+                kind: BarViolationKind::HighBelowOpenClose,
+                message: format!(
+                    "high {} is below max(open, close) {}",
+                    self.high,
+                    self.open.max(self.close)
+                ),
+            });
+        }
+
+        if self.low > self.open.min(self.close) {
+            violations.push(BarViolation {
+                kind: BarViolationKind::LowAboveOpenClose,
+                message: format!(
+                    "low {} is above min(open, close) {}",
+                    self.low,
+                    self.open.min(self.close)
+                ),
+            });
+        }
+
+        if self.volume < 0.0 {
+            violations.push(BarViolation {
+                kind: BarViolationKind::NegativeVolume,
+                message: format!("volume {} is negative", self.volume),
+            });
+        }
+
+        violations
+    }
+
+    /// Validate OHLCV consistency, failing with all violations on this bar
+    pub fn validate(&self) -> Result<()> {
+        let violations = self.violations();
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        let messages: Vec<String> = violations.into_iter().map(|v| v.message).collect();
+        anyhow::bail!(
+            "invalid bar {}@{}: {}",
+            self.symbol,
+            self.timestamp,
+            messages.join("; ")
+        );
+    }
+}
+
+impl BarValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn issue_count(&self) -> usize {
+        self.issues.len()
+    }
+
+    /// Number of issues per violation kind, in stable order
+    pub fn counts_by_kind(&self) -> BTreeMap<BarViolationKind, usize> {
+        let mut counts = BTreeMap::new();
+        for issue in &self.issues {
+            *counts.entry(issue.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Fail with a summary of the first few issues if the batch is invalid
+    pub fn ensure_valid(&self) -> Result<()> {
+        if self.is_valid() {
+            return Ok(());
+        }
+
+        const MAX_LISTED: usize = 5;
+        let listed: Vec<String> = self
+            .issues
+            .iter()
+            .take(MAX_LISTED)
+            .map(|i| format!("#{} {}@{}: {}", i.index, i.symbol, i.timestamp, i.message))
+            .collect();
+        let remainder = self.issue_count().saturating_sub(MAX_LISTED);
+        let suffix = if remainder > 0 {
+            format!(" (+{} more)", remainder)
+        } else {
+            String::new()
+        };
+
+        anyhow::bail!(
+            "{} of {} bars failed validation: {}{}",
+            self.invalid_bars,
+            self.bars_checked,
+            listed.join(", "),
+            suffix
+        );
+    }
+
+    fn record(&mut self, index: usize, bar: &Bar) {
+        self.bars_checked += 1;
+        let violations = bar.violations();
+        if violations.is_empty() {
+            return;
+        }
+
+        self.invalid_bars += 1;
+        for violation in violations {
+            self.issues.push(BarIssue {
+                index,
+                timestamp: bar.timestamp,
+                symbol: bar.symbol.clone(),
+                kind: violation.kind,
+                message: violation.message,
+            });
+        }
+    }
+}
+
+/// Validate a batch of bars, collecting every violation with its position
+pub fn validate_bars(bars: &[Bar]) -> BarValidationReport {
+    let mut report = BarValidationReport::default();
+    for (index, bar) in bars.iter().enumerate() {
+        report.record(index, bar);
+    }
+    report
+}
+
+/// Validate the bar payloads of a canonical event batch; non-bar events are skipped
+/// and issue indices refer to positions in `events`
+pub fn validate_bar_events(events: &[EventEnvelope]) -> BarValidationReport {
+    let mut report = BarValidationReport::default();
+    for (index, event) in events.iter().enumerate() {
+        if let MarketEventPayload::Bar(bar) = &event.payload {
+            report.record(index, bar);
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_bar() -> Bar {
+        Bar {
+            timestamp: 1_700_000_000,
+            symbol: "AAPL".to_string(),
+            open: 100.0,
+            high: 102.0,
+            low: 99.0,
+            close: 101.0,
+            volume: 1_000.0,
+        }
+    }
+
+    #[test]
+    fn valid_bar_passes() {
+        let bar = sample_bar();
+        assert!(bar.violations().is_empty());
+        assert!(bar.validate().is_ok());
+    }
+
+    #[test]
+    fn detects_inconsistent_ohlc() {
+        let bar = Bar {
+            high: 100.5,
+            low: 100.8,
+            volume: -1.0,
+            ..sample_bar()
+        };
+
+        let kinds: Vec<BarViolationKind> = bar.violations().iter().map(|v| v.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                BarViolationKind::HighBelowOpenClose,
+                BarViolationKind::LowAboveOpenClose,
+                BarViolationKind::NegativeVolume,
+            ]
+        );
+        assert!(bar.validate().is_err());
+    }
+
+    #[test]
+    fn detects_non_positive_and_non_finite_prices() {
+        let zero = Bar {
+            low: 0.0,
+            ..sample_bar()
+        };
+        assert_eq!(
+            zero.violations()[0].kind,
+            BarViolationKind::NonPositivePrice
+        );
+
+        let nan = Bar {
+            close: f64::NAN,
+            ..sample_bar()
+        };
+        let violations = nan.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].kind, BarViolationKind::NonFiniteValue);
+    }
+
+    #[test]
+    fn batch_report_locates_issues() {
+        let bars = vec![
+            sample_bar(),
+            Bar {
+                high: 90.0,
+                ..sample_bar()
+            },
+            sample_bar(),
+        ];
+
+        let report = validate_bars(&bars);
+        assert_eq!(report.bars_checked, 3);
+        assert_eq!(report.invalid_bars, 1);
+        assert_eq!(report.issues[0].index, 1);
+        assert_eq!(
+            report
+                .counts_by_kind()
+                .get(&BarViolationKind::HighBelowOpenClose),
+            Some(&1)
+        );
+        assert!(report.ensure_valid().is_err());
+        assert!(validate_bars(&[sample_bar()]).ensure_valid().is_ok());
+    }
+}
@@ -29,6 +29,9 @@ for i in range(num_days):
     low = price * (1 - abs(np.random.normal(0, 0.01)))
     open_price = price * (1 + np.random.normal(0, 0.005))
     close = price
+    # Keep OHLC consistent: high/low must envelop open and close
+    high = max(high, open_price, close)
+    low = min(low, open_price, close)
     volume = np.random.uniform(1000000, 5000000)
     
     timestamps.append(timestamp)